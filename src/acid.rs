@@ -0,0 +1,113 @@
+//! `acid_io` support for [`RefTake`].
+
+#[cfg(not(feature = "std"))]
+use core::cmp;
+
+#[cfg(not(feature = "std"))]
+use acid_io::BufRead;
+use acid_io::Read;
+
+use crate::RefTake;
+
+/// Implements `acid_io::Read` with a byte limit.
+///
+/// This covers `acid_io`'s own `no_std` traits. With its `std` feature,
+/// which the `std` feature of this crate turns on, they are those of
+/// `std::io`, and the `std::io` implementations of `RefTake` serve instead.
+/// Reads are clamped to the remaining limit, and at limit zero the inner
+/// reader is not called at all.
+#[cfg(not(feature = "std"))]
+impl<R: Read> Read for RefTake<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> acid_io::Result<usize> {
+        if self.limit == 0 {
+            return Ok(0);
+        }
+
+        let max = cmp::min(buf.len() as u64, self.limit) as usize;
+        let n = self.inner.read(&mut buf[..max])?;
+        assert!(n as u64 <= self.limit, "number of read bytes exceeds limit");
+        self.limit -= n as u64;
+        Ok(n)
+    }
+}
+
+/// Implements `acid_io::BufRead` with a byte limit.
+///
+/// `fill_buf()` returns the inner buffer capped at the remaining limit, and
+/// `consume()` is clamped to the limit as well.
+#[cfg(not(feature = "std"))]
+impl<R: BufRead> BufRead for RefTake<'_, R> {
+    fn fill_buf(&mut self) -> acid_io::Result<&[u8]> {
+        if self.limit == 0 {
+            return Ok(&[]);
+        }
+
+        let buf = self.inner.fill_buf()?;
+        let cap = cmp::min(buf.len() as u64, self.limit) as usize;
+        Ok(&buf[..cap])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        let amt = cmp::min(amt as u64, self.limit) as usize;
+        self.limit -= amt as u64;
+        self.inner.consume(amt);
+    }
+}
+
+/// Extension trait to provide a `take_ref_acid` method on all `acid_io::Read` types.
+pub trait AcidRefTakeExt {
+    /// Wraps the reader in a `RefTake`, allowing limited reading via a mutable reference.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use acid_io::Read;
+    /// use reftake::AcidRefTakeExt;
+    ///
+    /// let mut reader: &[u8] = b"hello world";
+    /// let mut buf = [0u8; 16];
+    /// let n = reader.take_ref_acid(5).read(&mut buf).unwrap();
+    /// assert_eq!(&buf[..n], b"hello");
+    /// assert_eq!(reader, b" world");
+    /// ```
+    fn take_ref_acid(&mut self, limit: u64) -> RefTake<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: Read> AcidRefTakeExt for T {
+    fn take_ref_acid(&mut self, limit: u64) -> RefTake<'_, Self> {
+        RefTake::wrap(self, limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use acid_io::BufRead;
+
+    #[test]
+    fn test_reads_are_clamped() {
+        let mut reader: &[u8] = b"abcdefgh";
+        let mut take = reader.take_ref_acid(5);
+
+        let mut buf = [0u8; 3];
+        assert_eq!(Read::read(&mut take, &mut buf).unwrap(), 3);
+        assert_eq!(Read::read(&mut take, &mut buf).unwrap(), 2);
+        assert_eq!(&buf[..2], b"de");
+        assert_eq!(Read::read(&mut take, &mut buf).unwrap(), 0);
+        assert_eq!(reader, b"fgh");
+    }
+
+    #[test]
+    fn test_fill_buf_and_consume_are_clamped() {
+        let mut reader: &[u8] = b"0123456789";
+        let mut take = reader.take_ref_acid(4);
+
+        assert_eq!(BufRead::fill_buf(&mut take).unwrap(), b"0123");
+        BufRead::consume(&mut take, 10);
+        assert_eq!(take.current_limit(), 0);
+        assert_eq!(BufRead::fill_buf(&mut take).unwrap(), b"");
+        assert_eq!(reader, b"456789");
+    }
+}
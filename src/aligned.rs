@@ -0,0 +1,214 @@
+//! A limited view over a borrowed reader that only issues block-aligned reads.
+
+use std::{
+    cmp,
+    io::{self, BufRead, Read},
+};
+
+/// Smallest buffer a [`RefTakeAligned`] reads into.
+const MIN_CAPACITY: usize = 64 * 1024;
+
+/// A non-owning adapter limiting a reader that must be read in whole blocks.
+///
+/// Files opened with `O_DIRECT` and raw block devices reject reads whose
+/// length, or buffer address, is not a multiple of the block size, so a
+/// [`RefTake`](crate::RefTake) cutting the last read down to the odd
+/// remaining limit breaks them. This adapter reads into an internal buffer
+/// aligned to `block_size` in memory, always asking the inner reader for
+/// a whole number of blocks, and serves the caller the part of it that is
+/// within the window.
+///
+/// Reads are cut down to the blocks covering the rest of the window, so
+/// the inner reader is left less than a block past the end of the window,
+/// and those bytes are dropped; see
+/// [`RefTakeAligned::excess`]. The window should start at a block boundary
+/// of the inner reader for every read to be aligned.
+pub struct RefTakeAligned<'a, R> {
+    inner: &'a mut R,
+    /// Bytes of the window not pulled from the inner reader yet.
+    limit: u64,
+    /// Backing storage, one block larger than the aligned buffer within it.
+    storage: Box<[u8]>,
+    /// Offset of the aligned buffer in `storage`.
+    start: usize,
+    capacity: usize,
+    block_size: usize,
+    pos: usize,
+    filled: usize,
+    excess: u64,
+}
+
+impl<'a, R> RefTakeAligned<'a, R> {
+    /// Creates a new `RefTakeAligned` reading at most `limit` bytes of the
+    /// window in multiples of `block_size`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `block_size` is not a power of two.
+    pub fn wrap(inner: &'a mut R, limit: u64, block_size: usize) -> Self {
+        assert!(
+            block_size.is_power_of_two(),
+            "block size must be a power of two"
+        );
+        let capacity = block_size.max(MIN_CAPACITY);
+        let storage = vec![0u8; capacity + block_size].into_boxed_slice();
+        let start = storage.as_ptr().align_offset(block_size);
+        Self {
+            inner,
+            limit,
+            storage,
+            start,
+            capacity,
+            block_size,
+            pos: 0,
+            filled: 0,
+            excess: 0,
+        }
+    }
+
+    /// Returns the number of bytes that may still be read, including buffered ones.
+    pub fn current_limit(&self) -> u64 {
+        self.limit + (self.filled - self.pos) as u64
+    }
+
+    /// Returns the bytes of the window that have been read but not consumed yet.
+    pub fn buffer(&self) -> &[u8] {
+        &self.storage[self.start + self.pos..self.start + self.filled]
+    }
+
+    /// Returns the number of bytes read from the inner reader past the end of the window.
+    pub fn excess(&self) -> u64 {
+        self.excess
+    }
+}
+
+impl<R: Read> Read for RefTakeAligned<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        let available = self.fill_buf()?;
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<R: Read> BufRead for RefTakeAligned<'_, R> {
+    fn fill_buf(&mut self) -> Result<&[u8], io::Error> {
+        // Don't call into inner reader at all at EOF because it may still block
+        if self.pos == self.filled && self.limit > 0 {
+            // The blocks covering the rest of the window, not a whole buffer
+            let blocks = self
+                .limit
+                .checked_next_multiple_of(self.block_size as u64)
+                .unwrap_or(u64::MAX);
+            let len = cmp::min(blocks, self.capacity as u64) as usize;
+            let buf = &mut self.storage[self.start..self.start + len];
+            let n = self.inner.read(buf)?;
+            assert!(n <= buf.len(), "number of read bytes exceeds buffer");
+            let keep = cmp::min(n as u64, self.limit) as usize;
+            self.limit -= keep as u64;
+            self.excess += (n - keep) as u64;
+            self.pos = 0;
+            self.filled = keep;
+        }
+        Ok(self.buffer())
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = (self.pos + amt).min(self.filled);
+    }
+}
+
+/// Extension trait to provide a `take_ref_aligned` method on all `Read` types.
+pub trait RefTakeAlignedExt {
+    /// Wraps the reader in a `RefTakeAligned` with the given limit and block size.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Cursor, Read};
+    /// use reftake::RefTakeAlignedExt;
+    ///
+    /// let mut device = Cursor::new(vec![7u8; 8192]);
+    /// let mut take = device.take_ref_aligned(1000, 512);
+    /// let mut buf = Vec::new();
+    /// take.read_to_end(&mut buf).unwrap();
+    /// assert_eq!(buf.len(), 1000);
+    /// assert_eq!(take.excess(), 24);
+    /// ```
+    fn take_ref_aligned(&mut self, limit: u64, block_size: usize) -> RefTakeAligned<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: Read> RefTakeAlignedExt for T {
+    fn take_ref_aligned(&mut self, limit: u64, block_size: usize) -> RefTakeAligned<'_, Self> {
+        RefTakeAligned::wrap(self, limit, block_size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fails reads that are not whole, aligned blocks, like an `O_DIRECT` file.
+    struct Direct<'a> {
+        data: &'a [u8],
+        block: usize,
+    }
+
+    impl Read for Direct<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            assert_eq!(buf.len() % self.block, 0, "unaligned length");
+            assert_eq!(buf.as_ptr() as usize % self.block, 0, "unaligned buffer");
+            self.data.read(buf)
+        }
+    }
+
+    #[test]
+    fn test_reads_are_aligned_and_clamped() {
+        let data: Vec<u8> = (0..=255).cycle().take(200_000).collect();
+        let mut device = Direct {
+            data: &data,
+            block: 4096,
+        };
+        let mut take = device.take_ref_aligned(70_000, 4096);
+        let mut buf = [0u8; 1000];
+        take.read_exact(&mut buf).unwrap();
+        assert_eq!(take.current_limit(), 69_000);
+        let mut rest = Vec::new();
+        take.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest, data[1000..70_000]);
+        assert_eq!(take.excess(), 18 * 4096 - 70_000);
+        assert_eq!(take.fill_buf().unwrap(), b"");
+    }
+
+    #[test]
+    fn test_small_window_reads_one_block() {
+        let data = vec![1u8; 200_000];
+        let mut device = Direct {
+            data: &data,
+            block: 512,
+        };
+        let mut buf = Vec::new();
+        device
+            .take_ref_aligned(10, 512)
+            .read_to_end(&mut buf)
+            .unwrap();
+        assert_eq!(buf.len(), 10);
+        assert_eq!(device.data.len(), 200_000 - 512);
+    }
+
+    #[test]
+    fn test_short_source() {
+        let mut device = Direct {
+            data: b"tiny",
+            block: 512,
+        };
+        let mut take = device.take_ref_aligned(100, 512);
+        let mut buf = Vec::new();
+        take.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, b"tiny");
+        assert_eq!((take.current_limit(), take.excess()), (96, 0));
+    }
+}
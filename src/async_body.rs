@@ -0,0 +1,222 @@
+//! `http_body::Body` support: bounded readers as bodies, and bodies with a byte limit.
+
+use std::{
+    error::Error,
+    io,
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+use bytes::{Buf, Bytes};
+use futures_core::Stream;
+use http_body::{Body, Frame, SizeHint};
+use tokio::io::AsyncRead;
+
+use crate::{ByteStream, LimitExceeded};
+
+/// Implements `http_body::Body` for a stream of chunks read from a tokio reader.
+///
+/// Every chunk becomes a data frame. Over a [`RefTake`](crate::RefTake) the
+/// body is bounded to the window, so a response can be served straight from
+/// a section of a file or a connection.
+impl<R: AsyncRead + Unpin> Body for ByteStream<'_, R> {
+    type Data = Bytes;
+    type Error = io::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, io::Error>>> {
+        let chunk = ready!(self.poll_next(cx));
+        Poll::Ready(chunk.map(|chunk| chunk.map(Frame::data)))
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.done
+    }
+}
+
+/// A body that fails once more than `limit` bytes of data have come through.
+///
+/// The `http_body::Body` counterpart of [`RefGuard`](crate::RefGuard), for
+/// enforcing a maximum request size in hyper or axum services. A data frame
+/// that would take the total past the limit is not passed on; the body
+/// fails with an `io::Error` carrying a [`LimitExceeded`] payload instead,
+/// as it does before reading anything when the inner body's size hint
+/// already announces too much, such as an oversized `Content-Length`.
+/// Errors of the inner body are passed on wrapped in an `io::Error`, and
+/// trailers are passed on unchanged.
+#[derive(Debug)]
+pub struct LimitedBody<B> {
+    inner: B,
+    limit: u64,
+    remaining: u64,
+}
+
+impl<B> LimitedBody<B> {
+    /// Creates a new `LimitedBody` letting at most `limit` bytes of `inner` through.
+    pub const fn new(inner: B, limit: u64) -> Self {
+        Self {
+            inner,
+            limit,
+            remaining: limit,
+        }
+    }
+
+    /// Returns the number of bytes that may still come through.
+    pub fn current_limit(&self) -> u64 {
+        self.remaining
+    }
+
+    /// Returns a reference to the inner body.
+    pub fn get_ref(&self) -> &B {
+        &self.inner
+    }
+
+    /// Consumes the adapter, returning the inner body.
+    pub fn into_inner(self) -> B {
+        self.inner
+    }
+}
+
+impl<B> Body for LimitedBody<B>
+where
+    B: Body + Unpin,
+    B::Error: Into<Box<dyn Error + Send + Sync>>,
+{
+    type Data = B::Data;
+    type Error = io::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<B::Data>, io::Error>>> {
+        let this = &mut *self;
+        if this.inner.size_hint().lower() > this.remaining {
+            return Poll::Ready(Some(Err(LimitExceeded::new(this.limit).into())));
+        }
+        let frame = match ready!(Pin::new(&mut this.inner).poll_frame(cx)) {
+            Some(Ok(frame)) => frame,
+            Some(Err(e)) => return Poll::Ready(Some(Err(io::Error::other(e)))),
+            None => return Poll::Ready(None),
+        };
+        if let Some(data) = frame.data_ref() {
+            let len = data.remaining() as u64;
+            if len > this.remaining {
+                return Poll::Ready(Some(Err(LimitExceeded::new(this.limit).into())));
+            }
+            this.remaining -= len;
+        }
+        Poll::Ready(Some(Ok(frame)))
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        let inner = self.inner.size_hint();
+        let mut hint = SizeHint::new();
+        hint.set_upper(inner.upper().unwrap_or(u64::MAX).min(self.remaining));
+        hint.set_lower(inner.lower().min(self.remaining));
+        hint
+    }
+}
+
+/// Extension trait to provide a `limit_bytes` method on all `http_body::Body` types.
+pub trait LimitedBodyExt: Body {
+    /// Wraps the body in a `LimitedBody` letting at most `limit` bytes through.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::future::poll_fn;
+    /// use std::pin::Pin;
+    /// use futures::executor::block_on;
+    /// use http_body::Body;
+    /// use reftake::{LimitExceeded, LimitedBodyExt};
+    ///
+    /// let mut body = String::from("a request body that is too long").limit_bytes(16);
+    /// let err = block_on(poll_fn(|cx| Pin::new(&mut body).poll_frame(cx)))
+    ///     .unwrap()
+    ///     .unwrap_err();
+    /// assert_eq!(LimitExceeded::from_io(&err), Some(&LimitExceeded::new(16)));
+    /// ```
+    fn limit_bytes(self, limit: u64) -> LimitedBody<Self>
+    where
+        Self: Sized,
+    {
+        LimitedBody::new(self, limit)
+    }
+}
+
+impl<B: Body> LimitedBodyExt for B {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ByteStreamExt, RefTake};
+    use std::{convert::Infallible, future::poll_fn, io::Cursor};
+
+    async fn frames<B: Body + Unpin>(mut body: B) -> Vec<Result<Frame<B::Data>, B::Error>> {
+        let mut out = Vec::new();
+        while let Some(frame) = poll_fn(|cx| Pin::new(&mut body).poll_frame(cx)).await {
+            out.push(frame);
+        }
+        out
+    }
+
+    /// A body of the given chunks that does not announce its size.
+    struct Chunks(Vec<&'static str>);
+
+    impl Body for Chunks {
+        type Data = Bytes;
+        type Error = Infallible;
+
+        fn poll_frame(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+        ) -> Poll<Option<Result<Frame<Bytes>, Infallible>>> {
+            let next = (!self.0.is_empty()).then(|| self.0.remove(0));
+            Poll::Ready(next.map(|chunk| Ok(Frame::data(Bytes::from_static(chunk.as_bytes())))))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reader_window_as_body() {
+        let mut reader = Cursor::new(b"0123456789".to_vec());
+        let mut take = RefTake::wrap(&mut reader, 7);
+        let mut body = take.byte_stream_ref(4);
+        assert!(!body.is_end_stream());
+
+        let data: Vec<_> = frames(&mut body)
+            .await
+            .into_iter()
+            .map(|frame| frame.unwrap().into_data().unwrap())
+            .collect();
+        assert_eq!(data, ["0123", "456"]);
+        assert!(body.is_end_stream());
+    }
+
+    #[tokio::test]
+    async fn test_limit_is_checked_per_frame() {
+        let body = Chunks(vec!["abc", "def", "g"]).limit_bytes(6);
+        assert_eq!(body.size_hint().upper(), Some(6));
+        let results = frames(body).await;
+        assert_eq!(results.len(), 3);
+        assert!(results[1].is_ok());
+        let err = results[2].as_ref().unwrap_err();
+        assert_eq!(LimitExceeded::from_io(err), Some(&LimitExceeded::new(6)));
+
+        let results = frames(Chunks(vec!["abc", "def"]).limit_bytes(6)).await;
+        assert!(results.iter().all(Result::is_ok));
+    }
+
+    #[tokio::test]
+    async fn test_announced_size_fails_early() {
+        let mut body = String::from("0123456789").limit_bytes(4);
+        let frame = poll_fn(|cx| Pin::new(&mut body).poll_frame(cx)).await;
+        assert!(LimitExceeded::from_io(&frame.unwrap().unwrap_err()).is_some());
+        assert_eq!(body.into_inner(), "0123456789");
+    }
+}
@@ -0,0 +1,229 @@
+//! A bounded blocking reader exposed as a tokio `AsyncRead`.
+
+use std::{
+    cmp,
+    future::Future,
+    io::{self, ErrorKind, Read},
+    mem,
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+use tokio::{
+    io::{AsyncRead, ReadBuf},
+    task::JoinHandle,
+};
+
+/// Largest read handed to the blocking pool at once.
+const CHUNK_SIZE: usize = 8 * 1024;
+
+type BlockingRead<R> = JoinHandle<(R, Vec<u8>, io::Result<()>)>;
+
+/// An `AsyncRead` over the next `limit` bytes of a blocking reader, running
+/// each read on tokio's blocking thread pool.
+///
+/// Reads behave like those of a [`RefTake`](crate::RefTake): they stop at
+/// the limit, the inner reader is not called at all once it is reached,
+/// and an inner EOF ends the stream early. Since the reads run on another
+/// thread, the reader is owned rather than borrowed; [`SyncReadBridge::into_inner`]
+/// hands it back, positioned after the bytes read.
+///
+/// Must be used from within a tokio runtime.
+pub struct SyncReadBridge<R> {
+    /// The reader, unless a blocking read owns it.
+    inner: Option<R>,
+    limit: u64,
+    pending: Option<BlockingRead<R>>,
+    /// Bytes of the last blocking read not returned yet.
+    buf: Vec<u8>,
+    pos: usize,
+}
+
+/// The reader is never pinned; it is only moved in and out of blocking tasks.
+impl<R> Unpin for SyncReadBridge<R> {}
+
+impl<R> SyncReadBridge<R> {
+    /// Creates a new `SyncReadBridge` that reads at most `limit` bytes from `inner`.
+    pub const fn new(inner: R, limit: u64) -> Self {
+        Self {
+            inner: Some(inner),
+            limit,
+            pending: None,
+            buf: Vec::new(),
+            pos: 0,
+        }
+    }
+
+    /// Returns the number of bytes that can still be read, including bytes
+    /// already read from the inner reader but not returned yet.
+    pub fn current_limit(&self) -> u64 {
+        self.limit + (self.buf.len() - self.pos) as u64
+    }
+}
+
+impl<R: Send + 'static> SyncReadBridge<R> {
+    /// Waits for a blocking read in progress, if any, and returns the reader.
+    ///
+    /// Bytes read from it but not returned yet are discarded.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a blocking read panicked, taking the reader with it.
+    pub async fn into_inner(mut self) -> io::Result<R> {
+        if let Some(pending) = self.pending.take() {
+            let (inner, _, _) = pending.await.map_err(io::Error::other)?;
+            return Ok(inner);
+        }
+        self.inner.take().ok_or_else(lost)
+    }
+}
+
+fn lost() -> io::Error {
+    io::Error::other("blocking reader lost in a panicked read")
+}
+
+impl<R: Read + Send + 'static> AsyncRead for SyncReadBridge<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        loop {
+            if this.pos < this.buf.len() {
+                let n = cmp::min(buf.remaining(), this.buf.len() - this.pos);
+                buf.put_slice(&this.buf[this.pos..this.pos + n]);
+                this.pos += n;
+                return Poll::Ready(Ok(()));
+            }
+
+            if let Some(pending) = &mut this.pending {
+                let joined = ready!(Pin::new(pending).poll(cx));
+                this.pending = None;
+                let (inner, data, result) = joined.map_err(io::Error::other)?;
+                this.inner = Some(inner);
+                this.buf = data;
+                this.pos = 0;
+                result?;
+                this.limit -= this.buf.len() as u64;
+                if this.buf.is_empty() {
+                    return Poll::Ready(Ok(()));
+                }
+                continue;
+            }
+
+            // Don't call the inner reader at all at EOF because it may block
+            if this.limit == 0 || buf.remaining() == 0 {
+                return Poll::Ready(Ok(()));
+            }
+            let mut inner = this.inner.take().ok_or_else(lost)?;
+            let mut data = mem::take(&mut this.buf);
+            let max = cmp::min(this.limit, CHUNK_SIZE as u64) as usize;
+            this.pending = Some(tokio::task::spawn_blocking(move || {
+                data.resize(max, 0);
+                let result = loop {
+                    match inner.read(&mut data) {
+                        Ok(n) => {
+                            data.truncate(n);
+                            break Ok(());
+                        }
+                        Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                        Err(e) => {
+                            data.clear();
+                            break Err(e);
+                        }
+                    }
+                };
+                (inner, data, result)
+            }));
+        }
+    }
+}
+
+/// Extension trait to provide an `into_async_take` method on all owned `Read` types.
+pub trait SyncReadBridgeExt {
+    /// Moves the reader into a `SyncReadBridge` over its next `limit` bytes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use reftake::SyncReadBridgeExt;
+    /// use tokio::io::AsyncReadExt;
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let file = Cursor::new(b"header|payload".to_vec());
+    /// let mut header = file.into_async_take(6);
+    /// let mut text = String::new();
+    /// header.read_to_string(&mut text).await.unwrap();
+    /// assert_eq!(text, "header");
+    ///
+    /// let file = header.into_inner().await.unwrap();
+    /// assert_eq!(file.position(), 6);
+    /// # });
+    /// ```
+    fn into_async_take(self, limit: u64) -> SyncReadBridge<Self>
+    where
+        Self: Sized;
+}
+
+impl<T: Read + Send + 'static> SyncReadBridgeExt for T {
+    fn into_async_take(self, limit: u64) -> SyncReadBridge<Self> {
+        SyncReadBridge::new(self, limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use tokio::io::AsyncReadExt;
+
+    /// Panics when read, to check that the limit keeps it from being called.
+    struct Unreadable;
+
+    impl Read for Unreadable {
+        fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+            panic!("reader called past the limit");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reads_stop_at_limit() {
+        let data: Vec<u8> = (0..20_000u32).map(|i| i as u8).collect();
+        let mut bridge = Cursor::new(data.clone()).into_async_take(12_345);
+        let mut out = Vec::new();
+        bridge.read_to_end(&mut out).await.unwrap();
+        assert_eq!(out, data[..12_345]);
+        assert_eq!(bridge.current_limit(), 0);
+        assert_eq!(bridge.into_inner().await.unwrap().position(), 12_345);
+    }
+
+    #[tokio::test]
+    async fn test_inner_eof_ends_early() {
+        let mut bridge = SyncReadBridge::new(Cursor::new(b"abc".to_vec()), 10);
+        let mut out = Vec::new();
+        bridge.read_to_end(&mut out).await.unwrap();
+        assert_eq!(out, b"abc");
+        assert_eq!(bridge.current_limit(), 7);
+    }
+
+    #[tokio::test]
+    async fn test_exhausted_window_does_not_read() {
+        let mut bridge = Unreadable.into_async_take(0);
+        let mut out = Vec::new();
+        assert_eq!(bridge.read_to_end(&mut out).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_small_buffers_get_leftovers() {
+        let mut bridge = Cursor::new(b"abcdef".to_vec()).into_async_take(5);
+        let mut two = [0u8; 2];
+        bridge.read_exact(&mut two).await.unwrap();
+        assert_eq!(bridge.current_limit(), 3);
+        bridge.read_exact(&mut two).await.unwrap();
+        assert_eq!(&two, b"cd");
+        assert_eq!(bridge.read(&mut two).await.unwrap(), 1);
+        assert_eq!(bridge.read(&mut two).await.unwrap(), 0);
+    }
+}
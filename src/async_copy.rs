@@ -0,0 +1,96 @@
+//! Bounded async copying out of a [`RefTake`] window.
+
+use std::io::{self, ErrorKind};
+
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::{CopyStatus, RefTake};
+
+impl<R: AsyncBufRead + Unpin> RefTake<'_, R> {
+    /// Copies the rest of the window into `writer`, then flushes it.
+    ///
+    /// Like `tokio::io::copy_buf`, the bytes are written straight from the
+    /// inner reader's buffer, without an intermediate copy. Unlike it, the
+    /// result tells a window copied in full apart from an inner reader that
+    /// ended early; a window ending exactly at EOF counts as copied in full.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use reftake::{AsyncRefTakeExt, CopyStatus};
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let mut reader: &[u8] = b"body of the upload";
+    /// let mut out = Vec::new();
+    /// let status = reader.take_ref_async(4).copy_to(&mut out).await.unwrap();
+    /// assert_eq!(status, CopyStatus::Limit(4));
+    /// assert_eq!(out, b"body");
+    ///
+    /// let status = reader.take_ref_async(100).copy_to(&mut out).await.unwrap();
+    /// assert_eq!(status, CopyStatus::Eof(14));
+    /// # });
+    /// ```
+    pub async fn copy_to<W: AsyncWrite + Unpin + ?Sized>(
+        &mut self,
+        writer: &mut W,
+    ) -> io::Result<CopyStatus> {
+        let mut copied = 0;
+        let status = loop {
+            if self.limit == 0 {
+                break CopyStatus::Limit(copied);
+            }
+            let buf = self.fill_buf().await?;
+            if buf.is_empty() {
+                break CopyStatus::Eof(copied);
+            }
+            let n = writer.write(buf).await?;
+            if n == 0 {
+                return Err(io::Error::new(
+                    ErrorKind::WriteZero,
+                    "write zero byte into writer",
+                ));
+            }
+            self.consume(n);
+            copied += n as u64;
+        };
+        writer.flush().await?;
+        Ok(status)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AsyncRefTakeExt, RefTakeWrite};
+    use tokio::io::BufReader;
+
+    #[tokio::test]
+    async fn test_copy_stops_at_limit_or_eof() {
+        let mut reader = BufReader::with_capacity(3, &b"0123456789"[..]);
+        let mut out = Vec::new();
+        let status = reader.take_ref_async(7).copy_to(&mut out).await.unwrap();
+        assert_eq!(status, CopyStatus::Limit(7));
+        assert!(status.limit_reached());
+        assert_eq!(out, b"0123456");
+
+        let status = reader.take_ref_async(10).copy_to(&mut out).await.unwrap();
+        assert_eq!(status, CopyStatus::Eof(3));
+        assert_eq!(status.bytes_copied(), 3);
+        assert_eq!(out, b"0123456789");
+    }
+
+    #[tokio::test]
+    async fn test_copy_into_full_writer() {
+        let mut reader: &[u8] = b"abcdef";
+        let mut out = Vec::new();
+        let mut writer = RefTakeWrite::wrap(&mut out, 2);
+        let err = reader
+            .take_ref_async(6)
+            .copy_to(&mut writer)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::WriteZero);
+        assert_eq!(out, b"ab");
+        assert_eq!(reader, b"cdef");
+    }
+}
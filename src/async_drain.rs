@@ -0,0 +1,195 @@
+//! Explicit draining of async [`RefTake`] windows.
+
+use std::{
+    future::poll_fn,
+    io,
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+use tokio::io::{AsyncRead, ReadBuf};
+
+use crate::{RefTake, async_read::poll_read_limited};
+
+impl<R: AsyncRead + Unpin> RefTake<'_, R> {
+    /// Reads and discards the rest of the window, returning the number of
+    /// bytes discarded.
+    ///
+    /// A destructor can't await, so async code has to skip the unread rest
+    /// of a frame or body explicitly before reading what follows it. The
+    /// drain stops early if the inner reader ends first. It is cancel safe:
+    /// the bytes discarded so far are accounted for in the limit.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use reftake::AsyncRefTakeExt;
+    /// use tokio::io::AsyncReadExt;
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let mut conn: &[u8] = b"frame bodynext";
+    /// let mut frame = conn.take_ref_async(10);
+    /// assert_eq!(frame.read_u8().await.unwrap(), b'f');
+    /// assert_eq!(frame.drain().await.unwrap(), 9);
+    /// assert_eq!(conn, b"next");
+    /// # });
+    /// ```
+    pub async fn drain(&mut self) -> io::Result<u64> {
+        let mut drained = 0;
+        poll_fn(|cx| {
+            let mut scratch = [0u8; 8 * 1024];
+            loop {
+                let mut buf = ReadBuf::new(&mut scratch);
+                ready!(poll_read_limited(self.inner, &mut self.limit, cx, &mut buf))?;
+                match buf.filled().len() {
+                    0 => {
+                        trace_event!(debug, drained, "window drained");
+                        return Poll::Ready(Ok(drained));
+                    }
+                    n => drained += n as u64,
+                }
+            }
+        })
+        .await
+    }
+}
+
+/// An async window that has to be drained before it goes away.
+///
+/// Reads behave like those of an async [`RefTake`]. The guard exists to
+/// make forgetting [`RefTake::drain`] visible: it is `#[must_use]`, and
+/// dropping it with unread bytes left trips a debug assertion, so a handler
+/// that returns early without skipping the rest of its frame is caught in
+/// tests rather than desynchronizing the connection in production.
+///
+/// # Panics
+///
+/// In debug builds, dropping the guard before [`MustDrain::drain`] has
+/// reached the end of the window panics, unless the thread is already
+/// panicking or the inner reader ended early.
+#[must_use = "the unread rest of the window must be skipped with `drain().await`"]
+pub struct MustDrain<'a, R> {
+    take: RefTake<'a, R>,
+    /// Whether the inner reader ended before the window did.
+    eof: bool,
+}
+
+impl<'a, R> MustDrain<'a, R> {
+    /// Creates a new `MustDrain` over the next `limit` bytes of the given reader reference.
+    pub const fn wrap(inner: &'a mut R, limit: u64) -> Self {
+        Self {
+            take: RefTake::wrap(inner, limit),
+            eof: false,
+        }
+    }
+
+    /// Returns the number of bytes left to read or drain.
+    pub fn current_limit(&self) -> u64 {
+        self.take.current_limit()
+    }
+}
+
+impl<R: AsyncRead + Unpin> MustDrain<'_, R> {
+    /// Reads and discards the rest of the window, returning the number of
+    /// bytes discarded, and releases the guard.
+    pub async fn drain(mut self) -> io::Result<u64> {
+        let drained = self.take.drain().await?;
+        self.eof = true;
+        Ok(drained)
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for MustDrain<'_, R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let before = buf.filled().len();
+        ready!(Pin::new(&mut self.take).poll_read(cx, buf))?;
+        if buf.filled().len() == before && buf.remaining() > 0 {
+            self.eof = true;
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Checks that the window was drained, in debug builds.
+impl<R> Drop for MustDrain<'_, R> {
+    fn drop(&mut self) {
+        debug_assert!(
+            self.eof || self.take.current_limit() == 0 || std::thread::panicking(),
+            "MustDrain dropped with {} unread bytes; call `drain().await` first",
+            self.take.current_limit()
+        );
+    }
+}
+
+/// Extension trait to provide a `take_ref_must_drain` method on all `tokio::io::AsyncRead` types.
+pub trait MustDrainExt {
+    /// Wraps the async reader in a `MustDrain` over its next `limit` bytes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use reftake::MustDrainExt;
+    /// use tokio::io::AsyncReadExt;
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let mut conn: &[u8] = b"\x03abcnext";
+    /// let len = conn.read_u8().await.unwrap();
+    /// let mut frame = conn.take_ref_must_drain(len.into());
+    /// assert_eq!(frame.read_u8().await.unwrap(), b'a');
+    /// frame.drain().await.unwrap();
+    /// assert_eq!(conn, b"next");
+    /// # });
+    /// ```
+    fn take_ref_must_drain(&mut self, limit: u64) -> MustDrain<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: AsyncRead + Unpin> MustDrainExt for T {
+    fn take_ref_must_drain(&mut self, limit: u64) -> MustDrain<'_, Self> {
+        MustDrain::wrap(self, limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AsyncRefTakeExt;
+    use tokio::io::AsyncReadExt;
+
+    #[tokio::test]
+    async fn test_drain_stops_at_limit_or_eof() {
+        let data = vec![1u8; 20_000];
+        let mut reader = data.as_slice();
+        assert_eq!(reader.take_ref_async(17_000).drain().await.unwrap(), 17_000);
+        assert_eq!(reader.len(), 3_000);
+        assert_eq!(reader.take_ref_async(5_000).drain().await.unwrap(), 3_000);
+    }
+
+    #[tokio::test]
+    async fn test_guard_released_by_drain_or_read_to_end() {
+        let mut reader: &[u8] = b"abcdefgh";
+        let guard = reader.take_ref_must_drain(3);
+        assert_eq!(guard.current_limit(), 3);
+        assert_eq!(guard.drain().await.unwrap(), 3);
+
+        let mut guard = reader.take_ref_must_drain(10);
+        let mut rest = Vec::new();
+        guard.read_to_end(&mut rest).await.unwrap();
+        drop(guard);
+        assert_eq!(rest, b"defgh");
+    }
+
+    #[tokio::test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "MustDrain dropped with 2 unread bytes")]
+    async fn test_undrained_guard_panics() {
+        let mut reader: &[u8] = b"abc";
+        let mut guard = reader.take_ref_must_drain(3);
+        guard.read_u8().await.unwrap();
+    }
+}
@@ -0,0 +1,148 @@
+//! An async window whose limit can change while it is being read.
+
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use tokio::{
+    io::{AsyncRead, ReadBuf},
+    sync::watch,
+};
+
+use crate::async_read::poll_read_limited;
+
+/// A non-owning async adapter that reads at most as many bytes as a
+/// changing quota allows.
+///
+/// The quota is the total number of bytes the window may yield, given by a
+/// closure or a `tokio::sync::watch` channel, and is looked up again on
+/// every read, so a new value takes effect on the next poll. Raising the
+/// quota lets reading resume after the window reported EOF; lowering it
+/// below what has already been read ends the window at once.
+pub struct DynamicTake<'a, R, F> {
+    inner: &'a mut R,
+    quota: F,
+    read: u64,
+}
+
+impl<'a, R, F: Fn() -> u64> DynamicTake<'a, R, F> {
+    /// Creates a new `DynamicTake` whose quota is the current value of `quota()`.
+    pub const fn wrap(inner: &'a mut R, quota: F) -> Self {
+        Self {
+            inner,
+            quota,
+            read: 0,
+        }
+    }
+
+    /// Returns the number of bytes read so far.
+    pub fn bytes_read(&self) -> u64 {
+        self.read
+    }
+
+    /// Returns the number of bytes the current quota still allows.
+    pub fn current_limit(&self) -> u64 {
+        (self.quota)().saturating_sub(self.read)
+    }
+}
+
+impl<R: AsyncRead + Unpin, F: Fn() -> u64 + Unpin> AsyncRead for DynamicTake<'_, R, F> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        let mut limit = this.current_limit();
+        let before = limit;
+        let result = poll_read_limited(this.inner, &mut limit, cx, buf);
+        this.read += before - limit;
+        result
+    }
+}
+
+/// Extension trait to provide a `take_ref_watch` method on all `tokio::io::AsyncRead` types.
+pub trait DynamicTakeExt {
+    /// Wraps the async reader in a `DynamicTake` following the quota sent on `quota`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use reftake::DynamicTakeExt;
+    /// use tokio::io::AsyncReadExt;
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let (quota, rx) = tokio::sync::watch::channel(4);
+    /// let mut upload: &[u8] = b"0123456789";
+    /// let mut limited = upload.take_ref_watch(rx);
+    ///
+    /// let mut buf = Vec::new();
+    /// limited.read_to_end(&mut buf).await.unwrap();
+    /// assert_eq!(buf, b"0123");
+    ///
+    /// quota.send(6).unwrap();
+    /// limited.read_to_end(&mut buf).await.unwrap();
+    /// assert_eq!(buf, b"012345");
+    /// # });
+    /// ```
+    fn take_ref_watch(
+        &mut self,
+        quota: watch::Receiver<u64>,
+    ) -> DynamicTake<'_, Self, impl Fn() -> u64 + Unpin>
+    where
+        Self: Sized;
+}
+
+impl<T: AsyncRead + Unpin> DynamicTakeExt for T {
+    fn take_ref_watch(
+        &mut self,
+        quota: watch::Receiver<u64>,
+    ) -> DynamicTake<'_, Self, impl Fn() -> u64 + Unpin> {
+        DynamicTake::wrap(self, move || *quota.borrow())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use tokio::io::AsyncReadExt;
+
+    #[tokio::test]
+    async fn test_closure_quota_is_read_on_every_poll() {
+        let quota = Cell::new(3);
+        let mut reader: &[u8] = b"abcdefgh";
+        let mut limited = DynamicTake::wrap(&mut reader, || quota.get());
+
+        let mut buf = [0u8; 8];
+        assert_eq!(limited.read(&mut buf).await.unwrap(), 3);
+        assert_eq!(limited.read(&mut buf).await.unwrap(), 0);
+        quota.set(5);
+        assert_eq!(limited.current_limit(), 2);
+        assert_eq!(limited.read(&mut buf).await.unwrap(), 2);
+        assert_eq!(&buf[..2], b"de");
+        quota.set(1);
+        assert_eq!(limited.current_limit(), 0);
+        assert_eq!(limited.read(&mut buf).await.unwrap(), 0);
+        assert_eq!(limited.bytes_read(), 5);
+        assert_eq!(reader, b"fgh");
+    }
+
+    #[tokio::test]
+    async fn test_watch_quota() {
+        let (quota, rx) = watch::channel(2);
+        let mut reader: &[u8] = b"abcdef";
+        let mut limited = reader.take_ref_watch(rx);
+
+        assert_eq!(
+            limited.read_u16().await.unwrap(),
+            u16::from_be_bytes(*b"ab")
+        );
+        quota.send(10).unwrap();
+        let mut rest = String::new();
+        limited.read_to_string(&mut rest).await.unwrap();
+        assert_eq!(rest, "cdef");
+    }
+}
@@ -0,0 +1,113 @@
+//! `embedded_io_async` support for [`RefTake`].
+
+use core::cmp;
+
+use embedded_io_async::{BufRead, Read};
+
+use crate::RefTake;
+
+/// Implements `embedded_io_async::Read` with a byte limit.
+///
+/// Reads are clamped to the remaining limit, and at limit zero the inner
+/// reader is not called at all, so a bounded read never waits on a UART or
+/// socket for bytes beyond the window.
+impl<R: Read> Read for RefTake<'_, R> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        if self.limit == 0 {
+            return Ok(0);
+        }
+
+        let max = cmp::min(buf.len() as u64, self.limit) as usize;
+        let n = self.inner.read(&mut buf[..max]).await?;
+        assert!(n as u64 <= self.limit, "number of read bytes exceeds limit");
+        self.limit -= n as u64;
+        Ok(n)
+    }
+}
+
+/// Implements `embedded_io_async::BufRead` with a byte limit.
+///
+/// `fill_buf()` returns the inner buffer capped at the remaining limit, and
+/// `consume()` is clamped to the limit as well.
+impl<R: BufRead> BufRead for RefTake<'_, R> {
+    async fn fill_buf(&mut self) -> Result<&[u8], Self::Error> {
+        if self.limit == 0 {
+            return Ok(&[]);
+        }
+
+        let buf = self.inner.fill_buf().await?;
+        let cap = cmp::min(buf.len() as u64, self.limit) as usize;
+        Ok(&buf[..cap])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        let amt = cmp::min(amt as u64, self.limit) as usize;
+        self.limit -= amt as u64;
+        self.inner.consume(amt);
+    }
+}
+
+/// Extension trait to provide a `take_ref_embedded` method on all `embedded_io_async::Read` types.
+pub trait EmbeddedRefTakeExt {
+    /// Wraps the reader in a `RefTake`, allowing limited reading via a mutable reference.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_io_async::Read;
+    /// use futures::executor::block_on;
+    /// use reftake::EmbeddedRefTakeExt;
+    ///
+    /// block_on(async {
+    ///     let mut uart: &[u8] = b"AT+OK\r\nnext";
+    ///     let mut reply = [0u8; 16];
+    ///     let n = uart.take_ref_embedded(7).read(&mut reply).await.unwrap();
+    ///     assert_eq!(&reply[..n], b"AT+OK\r\n");
+    ///     assert_eq!(uart, b"next");
+    /// });
+    /// ```
+    fn take_ref_embedded(&mut self, limit: u64) -> RefTake<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: Read> EmbeddedRefTakeExt for T {
+    fn take_ref_embedded(&mut self, limit: u64) -> RefTake<'_, Self> {
+        RefTake::wrap(self, limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+
+    #[test]
+    fn test_reads_are_clamped() {
+        block_on(async {
+            let mut reader: &[u8] = b"abcdefgh";
+            let mut take = reader.take_ref_embedded(5);
+
+            let mut buf = [0u8; 3];
+            assert_eq!(take.read(&mut buf).await.unwrap(), 3);
+            assert_eq!(take.read(&mut buf).await.unwrap(), 2);
+            assert_eq!(&buf[..2], b"de");
+            assert_eq!(take.read(&mut buf).await.unwrap(), 0);
+            assert_eq!(reader, b"fgh");
+        });
+    }
+
+    #[test]
+    fn test_fill_buf_and_consume_are_clamped() {
+        block_on(async {
+            let mut reader: &[u8] = b"0123456789";
+            let mut take = reader.take_ref_embedded(4);
+
+            assert_eq!(take.fill_buf().await.unwrap(), b"0123");
+            take.consume(10);
+            assert_eq!(take.current_limit(), 0);
+            assert_eq!(take.fill_buf().await.unwrap(), b"");
+            assert_eq!(reader, b"456789");
+        });
+    }
+}
@@ -0,0 +1,191 @@
+//! A cancel-safe `read_exact` over a borrowed tokio reader.
+
+use std::{
+    cmp,
+    future::poll_fn,
+    io::{self, ErrorKind},
+    mem,
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+use tokio::io::{AsyncRead, ReadBuf};
+
+use crate::RefTake;
+
+/// An async window whose exact reads survive cancellation.
+///
+/// `AsyncReadExt::read_exact` keeps the bytes it has read so far in its
+/// future, so when it loses a `select!` race they are gone and the stream
+/// is out of step. [`ResumableTake::read_exact_buffered`] keeps them in the
+/// adapter instead: calling it again after a cancellation carries on where
+/// the previous call stopped. Plain reads return any such buffered bytes
+/// first, so nothing is lost either way.
+pub struct ResumableTake<'a, R> {
+    take: RefTake<'a, R>,
+    /// Bytes read by an exact read that hasn't completed yet.
+    pending: Vec<u8>,
+}
+
+impl<'a, R> ResumableTake<'a, R> {
+    /// Creates a new `ResumableTake` over the next `limit` bytes of the given reader reference.
+    pub const fn wrap(inner: &'a mut R, limit: u64) -> Self {
+        Self {
+            take: RefTake::wrap(inner, limit),
+            pending: Vec::new(),
+        }
+    }
+
+    /// Returns the number of bytes of the window not returned yet, including buffered ones.
+    pub fn current_limit(&self) -> u64 {
+        self.take.current_limit() + self.pending.len() as u64
+    }
+
+    /// Returns the bytes read by an exact read that hasn't completed yet.
+    pub fn buffered(&self) -> &[u8] {
+        &self.pending
+    }
+}
+
+impl<R: AsyncRead + Unpin> ResumableTake<'_, R> {
+    /// Reads exactly `len` bytes of the window, keeping partial progress
+    /// across cancellation.
+    ///
+    /// If the future is dropped before it completes, the bytes read so far
+    /// stay buffered in the adapter, and the next call picks them up. A
+    /// window or reader that ends first fails with
+    /// `ErrorKind::UnexpectedEof`, leaving the bytes read in
+    /// [`ResumableTake::buffered`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use reftake::ResumableTakeExt;
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let mut conn: &[u8] = b"HEADbody";
+    /// let mut frame = conn.take_ref_resumable(8);
+    /// let header = frame.read_exact_buffered(4).await.unwrap();
+    /// assert_eq!(header, b"HEAD");
+    /// assert_eq!(frame.current_limit(), 4);
+    /// # });
+    /// ```
+    pub async fn read_exact_buffered(&mut self, len: usize) -> io::Result<Vec<u8>> {
+        poll_fn(|cx| self.poll_read_exact(cx, len)).await
+    }
+
+    fn poll_read_exact(&mut self, cx: &mut Context<'_>, len: usize) -> Poll<io::Result<Vec<u8>>> {
+        while self.pending.len() < len {
+            let start = self.pending.len();
+            self.pending.resize(len, 0);
+            let mut buf = ReadBuf::new(&mut self.pending[start..]);
+            let result = Pin::new(&mut self.take).poll_read(cx, &mut buf);
+            let n = buf.filled().len();
+            self.pending.truncate(start + n);
+            ready!(result)?;
+            if n == 0 {
+                return Poll::Ready(Err(io::Error::new(
+                    ErrorKind::UnexpectedEof,
+                    format!("window ended after {start} of {len} bytes"),
+                )));
+            }
+        }
+        let rest = self.pending.split_off(len);
+        Poll::Ready(Ok(mem::replace(&mut self.pending, rest)))
+    }
+}
+
+/// Returns buffered bytes first, then reads on within the window.
+impl<R: AsyncRead + Unpin> AsyncRead for ResumableTake<'_, R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        if !self.pending.is_empty() {
+            let n = cmp::min(buf.remaining(), self.pending.len());
+            buf.put_slice(&self.pending[..n]);
+            self.pending.drain(..n);
+            return Poll::Ready(Ok(()));
+        }
+        Pin::new(&mut self.take).poll_read(cx, buf)
+    }
+}
+
+/// Extension trait to provide a `take_ref_resumable` method on all `tokio::io::AsyncRead` types.
+pub trait ResumableTakeExt {
+    /// Wraps the async reader in a `ResumableTake` over its next `limit` bytes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use reftake::ResumableTakeExt;
+    /// use tokio::io::AsyncWriteExt;
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap().block_on(async {
+    /// let (mut client, mut server) = tokio::io::duplex(64);
+    /// client.write_all(b"01234").await.unwrap();
+    ///
+    /// let mut frame = server.take_ref_resumable(8);
+    /// tokio::select! {
+    ///     _ = frame.read_exact_buffered(8) => unreachable!(),
+    ///     _ = tokio::time::sleep(Duration::from_millis(10)) => {}
+    /// }
+    /// assert_eq!(frame.buffered(), b"01234");
+    ///
+    /// client.write_all(b"567").await.unwrap();
+    /// assert_eq!(frame.read_exact_buffered(8).await.unwrap(), b"01234567");
+    /// # });
+    /// ```
+    fn take_ref_resumable(&mut self, limit: u64) -> ResumableTake<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: AsyncRead + Unpin> ResumableTakeExt for T {
+    fn take_ref_resumable(&mut self, limit: u64) -> ResumableTake<'_, Self> {
+        ResumableTake::wrap(self, limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::future::Future;
+    use std::task::Waker;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[tokio::test]
+    async fn test_cancelled_read_resumes() {
+        let (mut client, mut server) = tokio::io::duplex(64);
+        let mut frame = server.take_ref_resumable(6);
+
+        client.write_all(b"abc").await.unwrap();
+        {
+            let mut read = Box::pin(frame.read_exact_buffered(4));
+            let mut cx = Context::from_waker(Waker::noop());
+            assert!(read.as_mut().poll(&mut cx).is_pending());
+        }
+        assert_eq!(frame.buffered(), b"abc");
+        assert_eq!(frame.current_limit(), 6);
+
+        client.write_all(b"defgh").await.unwrap();
+        assert_eq!(frame.read_exact_buffered(2).await.unwrap(), b"ab");
+        assert_eq!(frame.read_exact_buffered(2).await.unwrap(), b"cd");
+        let mut rest = Vec::new();
+        frame.read_to_end(&mut rest).await.unwrap();
+        assert_eq!(rest, b"ef");
+    }
+
+    #[tokio::test]
+    async fn test_window_end_keeps_bytes() {
+        let mut reader: &[u8] = b"xyz";
+        let mut frame = reader.take_ref_resumable(2);
+        let err = frame.read_exact_buffered(3).await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+        assert_eq!(frame.buffered(), b"xy");
+        assert_eq!(frame.read_u8().await.unwrap(), b'x');
+        assert_eq!(reader, b"z");
+    }
+}
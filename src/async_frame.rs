@@ -0,0 +1,440 @@
+//! Framed messages over a borrowed tokio reader, as futures or a `Stream`.
+
+use std::{
+    future::poll_fn,
+    io::{self, ErrorKind, Read},
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+use bytes::{Bytes, BytesMut};
+use futures_core::Stream;
+use tokio::io::{AsyncRead, ReadBuf};
+
+use crate::{
+    FrameBody, FrameConfig, FramingStrategy, LimitExceeded, RefTake, async_read::poll_read_limited,
+};
+
+/// Implements `tokio::io::AsyncRead` for the body of a frame of an [`AsyncFrameReader`].
+impl<R: AsyncRead + Unpin> AsyncRead for FrameBody<'_, R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        poll_read_limited(this.inner, &mut this.remaining, cx, buf)
+    }
+}
+
+/// The async counterpart of [`FrameReader`](crate::FrameReader), over a
+/// borrowed tokio reader.
+///
+/// Frames can be consumed in two ways:
+///
+/// - [`AsyncFrameReader::next_frame`] returns a [`RefTake`] limited to the
+///   body of the next frame, to be read with `tokio::io::AsyncReadExt`.
+///   Whatever is left unread of it is skipped before the next header.
+/// - As a [`Stream`], each item is the whole body of the next frame as
+///   owned [`Bytes`], for pipelines that want messages rather than readers.
+///
+/// Headers and trailers are parsed by the same [`FramingStrategy`] as the
+/// blocking reader, fed with the bytes received so far, so a header split
+/// across several reads is picked up where it stopped. Header bytes are
+/// requested from the inner reader one at a time to avoid reading into the
+/// body, so wrap unbuffered sources in a `tokio::io::BufReader`.
+///
+/// Both `next_frame` and the stream are cancel safe: progress is kept in the
+/// reader, not in the future.
+pub struct AsyncFrameReader<'a, R, S = FrameConfig> {
+    body: FrameBody<'a, R>,
+    strategy: S,
+    /// Whether a body has been opened whose trailer wasn't checked yet.
+    open: bool,
+    /// Bytes of the header or trailer being parsed, received so far.
+    partial: Vec<u8>,
+    /// Whether the inner reader hit EOF while parsing a header or trailer.
+    eof: bool,
+    /// Body of the frame being collected by the `Stream` implementation.
+    payload: Option<BytesMut>,
+    /// Whether the stream has ended, cleanly or with an error.
+    done: bool,
+}
+
+/// Feeds the bytes received so far to a header or trailer parser, failing
+/// with `ErrorKind::WouldBlock` when it needs more.
+struct Received<'p> {
+    partial: &'p [u8],
+    pos: usize,
+    eof: bool,
+}
+
+impl Read for Received<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos < self.partial.len() {
+            let n = (&self.partial[self.pos..]).read(buf)?;
+            self.pos += n;
+            return Ok(n);
+        }
+        if self.eof || buf.is_empty() {
+            return Ok(0);
+        }
+        Err(ErrorKind::WouldBlock.into())
+    }
+}
+
+impl<'a, R> AsyncFrameReader<'a, R> {
+    /// Creates a new `AsyncFrameReader` with the default layout that rejects
+    /// frames longer than `max_frame_len` bytes.
+    pub fn wrap(inner: &'a mut R, max_frame_len: u64) -> Self {
+        Self::with_config(
+            inner,
+            FrameConfig {
+                max_frame_len,
+                ..FrameConfig::default()
+            },
+        )
+    }
+
+    /// Creates a new `AsyncFrameReader` with the given framing parameters.
+    pub const fn with_config(inner: &'a mut R, config: FrameConfig) -> Self {
+        Self::with_strategy(inner, config)
+    }
+
+    /// Returns the framing parameters.
+    pub fn config(&self) -> &FrameConfig {
+        &self.strategy
+    }
+}
+
+impl<'a, R, S: FramingStrategy> AsyncFrameReader<'a, R, S> {
+    /// Creates a new `AsyncFrameReader` reading frames in the format of `strategy`.
+    pub const fn with_strategy(inner: &'a mut R, strategy: S) -> Self {
+        Self {
+            body: FrameBody {
+                inner,
+                remaining: 0,
+            },
+            strategy,
+            open: false,
+            partial: Vec::new(),
+            eof: false,
+            payload: None,
+            done: false,
+        }
+    }
+
+    /// Returns the framing strategy.
+    pub fn strategy(&self) -> &S {
+        &self.strategy
+    }
+
+    /// Returns the maximum accepted frame length.
+    pub fn max_frame_len(&self) -> u64 {
+        self.strategy.max_frame_len()
+    }
+}
+
+impl<'a, R: AsyncRead + Unpin, S: FramingStrategy> AsyncFrameReader<'a, R, S> {
+    /// Skips the rest of the current frame, if any, then reads the next frame header.
+    ///
+    /// Returns `Ok(None)` when the stream ends cleanly between frames. A
+    /// stream that ends inside a header or a skipped body fails with
+    /// `ErrorKind::UnexpectedEof`, and a frame longer than the maximum fails
+    /// with a [`LimitExceeded`] error. Errors from the strategy are passed on.
+    pub async fn next_frame(&mut self) -> io::Result<Option<RefTake<'_, FrameBody<'a, R>>>> {
+        let Some(len) = poll_fn(|cx| self.poll_advance(cx)).await? else {
+            return Ok(None);
+        };
+        Ok(Some(RefTake::wrap(&mut self.body, len)))
+    }
+
+    /// Returns the unread rest of the frame last returned by
+    /// [`AsyncFrameReader::next_frame`], or `None` if there is none.
+    pub fn current_frame(&mut self) -> Option<RefTake<'_, FrameBody<'a, R>>> {
+        let remaining = self.body.remaining;
+        self.open.then(|| RefTake::wrap(&mut self.body, remaining))
+    }
+
+    /// Skips the rest of the current frame and its trailer, then reads the
+    /// next header and returns the length of its body.
+    fn poll_advance(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<Option<u64>>> {
+        self.payload = None;
+        if self.open {
+            let mut scratch = [0u8; 8 * 1024];
+            while self.body.remaining > 0 {
+                let mut buf = ReadBuf::new(&mut scratch);
+                ready!(Pin::new(&mut self.body).poll_read(cx, &mut buf))?;
+                if buf.filled().is_empty() {
+                    return Poll::Ready(Err(io::Error::new(
+                        ErrorKind::UnexpectedEof,
+                        "stream ended inside a frame body",
+                    )));
+                }
+            }
+            ready!(self.poll_parse(cx, |strategy, reader| strategy.validate_trailer(reader)))?;
+            self.open = false;
+        }
+
+        let Some(len) =
+            ready!(self.poll_parse(cx, |strategy, reader| strategy.decode_header(reader)))?
+        else {
+            return Poll::Ready(Ok(None));
+        };
+        let max_frame_len = self.strategy.max_frame_len();
+        if len > max_frame_len {
+            return Poll::Ready(Err(LimitExceeded::new(max_frame_len).into()));
+        }
+
+        self.body.remaining = len;
+        self.open = true;
+        Poll::Ready(Ok(Some(len)))
+    }
+
+    /// Runs a header or trailer parser of the strategy over the bytes
+    /// received so far, receiving one more byte each time it runs short.
+    fn poll_parse<T>(
+        &mut self,
+        cx: &mut Context<'_>,
+        mut parse: impl FnMut(&mut S, &mut Received<'_>) -> io::Result<T>,
+    ) -> Poll<io::Result<T>> {
+        loop {
+            let mut reader = Received {
+                partial: &self.partial,
+                pos: 0,
+                eof: self.eof,
+            };
+            match parse(&mut self.strategy, &mut reader) {
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+                result => {
+                    self.partial.clear();
+                    self.eof = false;
+                    return Poll::Ready(result);
+                }
+            }
+
+            let mut byte = [0u8; 1];
+            let mut buf = ReadBuf::new(&mut byte);
+            ready!(Pin::new(&mut *self.body.inner).poll_read(cx, &mut buf))?;
+            match buf.filled() {
+                [] => self.eof = true,
+                &[b] => self.partial.push(b),
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    /// Reads the rest of the body being collected into `payload`.
+    fn poll_payload(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<Bytes>> {
+        let payload = self.payload.get_or_insert_with(BytesMut::new);
+        while self.body.remaining > 0 {
+            let len = payload.len();
+            let chunk = self.body.remaining.min(8 * 1024) as usize;
+            payload.resize(len + chunk, 0);
+            let mut buf = ReadBuf::new(&mut payload[len..]);
+            let result = Pin::new(&mut self.body).poll_read(cx, &mut buf);
+            let n = buf.filled().len();
+            payload.truncate(len + n);
+            ready!(result)?;
+            if n == 0 {
+                return Poll::Ready(Err(io::Error::new(
+                    ErrorKind::UnexpectedEof,
+                    "stream ended inside a frame body",
+                )));
+            }
+        }
+        Poll::Ready(Ok(self.payload.take().unwrap_or_default().freeze()))
+    }
+}
+
+/// Yields the body of each frame as owned [`Bytes`].
+///
+/// The stream ends after the last complete frame, or after the first error.
+impl<R: AsyncRead + Unpin, S: FramingStrategy + Unpin> Stream for AsyncFrameReader<'_, R, S> {
+    type Item = io::Result<Bytes>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.done {
+            return Poll::Ready(None);
+        }
+
+        if this.payload.is_none() {
+            match ready!(this.poll_advance(cx)) {
+                Ok(Some(len)) => {
+                    this.payload = Some(BytesMut::with_capacity(len.min(8 * 1024) as usize))
+                }
+                Ok(None) => {
+                    this.done = true;
+                    return Poll::Ready(None);
+                }
+                Err(e) => {
+                    this.done = true;
+                    return Poll::Ready(Some(Err(e)));
+                }
+            }
+        }
+
+        let result = ready!(this.poll_payload(cx));
+        this.done = result.is_err();
+        Poll::Ready(Some(result))
+    }
+}
+
+/// Extension trait to provide a `frames_ref_async` method on all `tokio::io::AsyncRead` types.
+pub trait AsyncFrameReaderExt {
+    /// Wraps the async reader in an `AsyncFrameReader` accepting frames of at
+    /// most `max_frame_len` bytes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use futures::StreamExt;
+    /// use reftake::AsyncFrameReaderExt;
+    /// use tokio::io::{AsyncReadExt, BufReader};
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let data: &[u8] = b"\0\0\0\x05hello\0\0\0\x05world\0\0\0\x01!";
+    /// let mut reader = BufReader::new(data);
+    /// let mut frames = reader.frames_ref_async(1024);
+    ///
+    /// let mut first = [0u8; 2];
+    /// frames.next_frame().await.unwrap().unwrap().read_exact(&mut first).await.unwrap();
+    /// assert_eq!(&first, b"he");
+    ///
+    /// let rest: Vec<_> = frames.map(Result::unwrap).collect().await;
+    /// assert_eq!(rest, vec!["world", "!"]);
+    /// # });
+    /// ```
+    fn frames_ref_async(&mut self, max_frame_len: u64) -> AsyncFrameReader<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: AsyncRead + Unpin> AsyncFrameReaderExt for T {
+    fn frames_ref_async(&mut self, max_frame_len: u64) -> AsyncFrameReader<'_, Self> {
+        AsyncFrameReader::wrap(self, max_frame_len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Netstring;
+    use futures::StreamExt;
+    use tokio::io::{AsyncReadExt, BufReader};
+
+    fn frames(bodies: &[&[u8]]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for body in bodies {
+            out.extend_from_slice(&(body.len() as u32).to_be_bytes());
+            out.extend_from_slice(body);
+        }
+        out
+    }
+
+    /// Hands out one byte at a time, pending before each.
+    struct Trickle {
+        data: Vec<u8>,
+        pos: usize,
+        ready: bool,
+    }
+
+    impl AsyncRead for Trickle {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            self.ready = !self.ready;
+            if !self.ready {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            if let Some(&b) = self.data.get(self.pos) {
+                buf.put_slice(&[b]);
+                self.pos += 1;
+            }
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unread_remainder_is_drained() {
+        let data = frames(&[b"skipped", b"", b"abc"]);
+        let mut reader = BufReader::new(data.as_slice());
+        let mut frames = reader.frames_ref_async(64);
+
+        assert_eq!(
+            frames.next_frame().await.unwrap().unwrap().current_limit(),
+            7
+        );
+        let mut body = Vec::new();
+        frames
+            .next_frame()
+            .await
+            .unwrap()
+            .unwrap()
+            .read_to_end(&mut body)
+            .await
+            .unwrap();
+        assert!(body.is_empty());
+
+        let mut frame = frames.next_frame().await.unwrap().unwrap();
+        assert_eq!(frame.read_u8().await.unwrap(), b'a');
+        assert_eq!(frames.current_frame().unwrap().current_limit(), 2);
+        assert!(frames.next_frame().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_stream_across_pending_reads() {
+        let mut reader = Trickle {
+            data: frames(&[b"abc", b"", b"xy"]),
+            pos: 0,
+            ready: false,
+        };
+        let bodies: Vec<Bytes> = reader
+            .frames_ref_async(64)
+            .map(Result::unwrap)
+            .collect()
+            .await;
+        assert_eq!(bodies, vec!["abc", "", "xy"]);
+    }
+
+    #[tokio::test]
+    async fn test_stream_ends_after_error() {
+        let data = frames(&[b"ok", b"too long"]);
+        let mut reader = data.as_slice();
+        let results: Vec<_> = reader.frames_ref_async(4).collect().await;
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].as_ref().unwrap(), "ok");
+        let err = results[1].as_ref().unwrap_err();
+        assert_eq!(LimitExceeded::from_io(err), Some(&LimitExceeded::new(4)));
+
+        let mut data = frames(&[b"abcdef"]);
+        data.truncate(7);
+        let mut reader = data.as_slice();
+        let mut frames = reader.frames_ref_async(64);
+        let err = frames.next().await.unwrap().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+        assert!(frames.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_strategy_with_trailer() {
+        let mut reader = Trickle {
+            data: b"5:hello,0:,3:abc;".to_vec(),
+            pos: 0,
+            ready: false,
+        };
+        let mut frames = AsyncFrameReader::with_strategy(&mut reader, Netstring::new(16));
+        assert_eq!(frames.next().await.unwrap().unwrap(), "hello");
+        assert_eq!(
+            frames.next_frame().await.unwrap().unwrap().current_limit(),
+            0
+        );
+        assert_eq!(frames.next().await.unwrap().unwrap(), "abc");
+        let err = frames.next().await.unwrap().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+}
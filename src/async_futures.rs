@@ -0,0 +1,243 @@
+//! `futures_io` support for [`RefTake`] and [`RefTakeWrite`].
+
+use std::{
+    cmp, io,
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+use futures_io::{AsyncBufRead, AsyncRead, AsyncWrite};
+
+use crate::{RefTake, RefTakeWrite};
+
+/// Implements `futures_io::AsyncRead` with a byte limit.
+///
+/// The counterpart of the tokio implementation for smol, async-std and other
+/// `futures`-based runtimes: reads are clamped to the remaining limit, and
+/// at limit zero the inner reader is not polled at all.
+impl<T: AsyncRead + Unpin> AsyncRead for RefTake<'_, T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        // Don't poll the inner reader at all at EOF because it may still be pending
+        if self.limit == 0 {
+            return Poll::Ready(Ok(0));
+        }
+
+        let max = cmp::min(buf.len() as u64, self.limit) as usize;
+        let n = ready!(Pin::new(&mut *self.inner).poll_read(cx, &mut buf[..max]))?;
+        assert!(n as u64 <= self.limit, "number of read bytes exceeds limit");
+        self.limit -= n as u64;
+        Poll::Ready(Ok(n))
+    }
+}
+
+/// Implements `futures_io::AsyncBufRead` with a byte limit.
+///
+/// `poll_fill_buf()` returns the inner buffer capped at the remaining
+/// limit, and `consume()` is clamped to the limit as well, so combinators
+/// such as `read_line` stop at the end of the window.
+impl<T: AsyncBufRead + Unpin> AsyncBufRead for RefTake<'_, T> {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        let this = self.get_mut();
+        // Don't poll the inner reader at all at EOF because it may still be pending
+        if this.limit == 0 {
+            return Poll::Ready(Ok(&[]));
+        }
+
+        let buf = ready!(Pin::new(&mut *this.inner).poll_fill_buf(cx))?;
+        let cap = cmp::min(buf.len() as u64, this.limit) as usize;
+        Poll::Ready(Ok(&buf[..cap]))
+    }
+
+    fn consume(mut self: Pin<&mut Self>, amt: usize) {
+        // Don't let callers reset the limit by passing an overlarge value
+        let amt = cmp::min(amt as u64, self.limit) as usize;
+        self.limit -= amt as u64;
+        Pin::new(&mut *self.inner).consume(amt);
+    }
+}
+
+/// Extension trait to provide a `take_ref_async` method on all `futures_io::AsyncRead` types.
+pub trait FuturesRefTakeExt {
+    /// Wraps the async reader in a `RefTake`, allowing limited reading via a mutable reference.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use futures::{executor::block_on, io::AsyncReadExt};
+    /// use reftake::FuturesRefTakeExt;
+    ///
+    /// block_on(async {
+    ///     let mut reader: &[u8] = b"hello world";
+    ///     let mut text = String::new();
+    ///     reader.take_ref_async(5).read_to_string(&mut text).await.unwrap();
+    ///     assert_eq!(text, "hello");
+    ///     assert_eq!(reader, b" world");
+    /// });
+    /// ```
+    fn take_ref_async(&mut self, limit: u64) -> RefTake<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: AsyncRead + Unpin> FuturesRefTakeExt for T {
+    fn take_ref_async(&mut self, limit: u64) -> RefTake<'_, Self> {
+        RefTake::wrap(self, limit)
+    }
+}
+
+/// Implements `futures_io::AsyncWrite` with a byte quota.
+///
+/// Writes that don't fit in the remaining quota are handled according to
+/// the writer's [`OverflowBehavior`](crate::OverflowBehavior), exactly like
+/// the blocking `Write` implementation. Flushes and closes go straight to
+/// the inner writer.
+impl<W: AsyncWrite + Unpin> AsyncWrite for RefTakeWrite<'_, W> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        if let Some(result) = self.check_overflow(buf.len()) {
+            return Poll::Ready(result);
+        }
+
+        let max = cmp::min(buf.len() as u64, self.limit) as usize;
+        let n = ready!(Pin::new(&mut *self.inner).poll_write(cx, &buf[..max]))?;
+        Poll::Ready(Ok(self.account(n, max, buf.len())))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut *self.inner).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut *self.inner).poll_close(cx)
+    }
+}
+
+/// Extension trait to provide a `take_write_ref_async` method on all `futures_io::AsyncWrite` types.
+pub trait FuturesRefTakeWriteExt {
+    /// Wraps the async writer in a `RefTakeWrite` accepting at most `limit` bytes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use futures::{executor::block_on, io::AsyncWriteExt};
+    /// use reftake::FuturesRefTakeWriteExt;
+    ///
+    /// block_on(async {
+    ///     let mut out = Vec::new();
+    ///     let mut limited = out.take_write_ref_async(5);
+    ///     assert_eq!(limited.write(b"hello world").await.unwrap(), 5);
+    ///     assert_eq!(out, b"hello");
+    /// });
+    /// ```
+    fn take_write_ref_async(&mut self, limit: u64) -> RefTakeWrite<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: AsyncWrite + Unpin> FuturesRefTakeWriteExt for T {
+    fn take_write_ref_async(&mut self, limit: u64) -> RefTakeWrite<'_, Self> {
+        RefTakeWrite::wrap(self, limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LimitExceeded, OverflowBehavior};
+    use futures::{
+        executor::block_on,
+        io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt},
+    };
+    use std::io::ErrorKind;
+
+    /// Never ready, to check that an exhausted window doesn't poll it.
+    struct Pending;
+
+    impl AsyncRead for Pending {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            _buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            Poll::Pending
+        }
+    }
+
+    #[test]
+    fn test_reads_are_clamped() {
+        block_on(async {
+            let mut reader: &[u8] = b"abcdefgh";
+            let mut take = FuturesRefTakeExt::take_ref_async(&mut reader, 5);
+
+            let mut buf = [0u8; 3];
+            assert_eq!(take.read(&mut buf).await.unwrap(), 3);
+            assert_eq!(take.read(&mut buf).await.unwrap(), 2);
+            assert_eq!(&buf[..2], b"de");
+            assert_eq!(take.read(&mut buf).await.unwrap(), 0);
+            assert_eq!(reader, b"fgh");
+        });
+    }
+
+    #[test]
+    fn test_exhausted_window_does_not_poll_inner() {
+        block_on(async {
+            let mut reader = Pending;
+            let mut out = Vec::new();
+            let n = reader
+                .take_ref_async(0)
+                .read_to_end(&mut out)
+                .await
+                .unwrap();
+            assert_eq!(n, 0);
+        });
+    }
+
+    #[test]
+    fn test_buffered_reads_stop_at_limit() {
+        block_on(async {
+            let mut reader: &[u8] = b"first\nsecond line\n";
+            let mut take = FuturesRefTakeExt::take_ref_async(&mut reader, 9);
+
+            let mut line = String::new();
+            take.read_line(&mut line).await.unwrap();
+            assert_eq!(line, "first\n");
+            line.clear();
+            take.read_line(&mut line).await.unwrap();
+            assert_eq!(line, "sec");
+            assert_eq!(take.fill_buf().await.unwrap(), b"");
+            take.consume_unpin(10);
+            assert_eq!(reader, b"ond line\n");
+        });
+    }
+
+    #[test]
+    fn test_write_overflow_behaviors() {
+        block_on(async {
+            let mut out = Vec::new();
+            let mut limited = FuturesRefTakeWriteExt::take_write_ref_async(&mut out, 4);
+            assert_eq!(limited.write(b"abcdef").await.unwrap(), 4);
+            let err = limited.write_all(b"g").await.unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::WriteZero);
+            limited.close().await.unwrap();
+            assert_eq!(out, b"abcd");
+
+            let mut out = Vec::new();
+            let mut limited = RefTakeWrite::with_overflow(&mut out, 3, OverflowBehavior::Error);
+            let err = limited.write(b"abcd").await.unwrap_err();
+            assert_eq!(LimitExceeded::from_io(&err), Some(&LimitExceeded::new(3)));
+
+            let mut limited = RefTakeWrite::with_overflow(&mut out, 3, OverflowBehavior::Saturate);
+            limited.write_all(b"abcdef").await.unwrap();
+            limited.flush().await.unwrap();
+            assert_eq!(limited.truncated_bytes(), 3);
+            assert_eq!(out, b"abc");
+        });
+    }
+}
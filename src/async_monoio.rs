@@ -0,0 +1,131 @@
+//! `monoio::io::AsyncReadRent` support for [`RefTake`].
+
+use monoio::{
+    BufResult,
+    buf::{IoBufMut, IoVecBufMut, RawBuf},
+    io::AsyncReadRent,
+};
+
+use crate::RefTake;
+
+/// Implements `monoio::io::AsyncReadRent` with a byte limit.
+///
+/// Completion-based runtimes pass buffer ownership to the reader, so the
+/// poll-based implementations don't apply. Here the rented buffer is trimmed
+/// to the remaining limit before it is handed to the inner reader, and
+/// returned whole afterwards. Vectored reads fill at most the first part of
+/// the vector. At limit zero the inner reader is not called at all.
+impl<R: AsyncReadRent> AsyncReadRent for RefTake<'_, R> {
+    async fn read<T: IoBufMut>(&mut self, mut buf: T) -> BufResult<usize, T> {
+        if self.limit == 0 {
+            return (Ok(0), buf);
+        }
+
+        let (result, buf) = if buf.bytes_total() as u64 <= self.limit {
+            self.inner.read(buf).await
+        } else {
+            let max = self.limit as usize;
+            // SAFETY: the slice starts at 0 and ends within the buffer's capacity
+            let slice = unsafe { buf.slice_mut_unchecked(..max) };
+            let (result, slice) = self.inner.read(slice).await;
+            (result, slice.into_inner())
+        };
+        if let Ok(n) = result {
+            self.limit -= n as u64;
+        }
+        (result, buf)
+    }
+
+    async fn readv<T: IoVecBufMut>(&mut self, mut buf: T) -> BufResult<usize, T> {
+        if self.limit == 0 {
+            return (Ok(0), buf);
+        }
+
+        // SAFETY: `buf` is owned by this future and outlives the read into its first part
+        let Some(mut first) = (unsafe { RawBuf::new_from_iovec_mut(&mut buf) }) else {
+            return (Ok(0), buf);
+        };
+        let max = first
+            .bytes_total()
+            .min(self.limit.min(usize::MAX as u64) as usize);
+        // SAFETY: the slice starts at 0 and ends within the first part
+        let slice = unsafe { first.slice_mut_unchecked(..max) };
+        let (result, _) = self.inner.read(slice).await;
+        if let Ok(n) = result {
+            self.limit -= n as u64;
+            // SAFETY: the inner reader initialized `n` bytes of the first part
+            unsafe { buf.set_init(n) };
+        }
+        (result, buf)
+    }
+}
+
+/// Extension trait to provide a `take_ref_rent` method on all `monoio::io::AsyncReadRent` types.
+pub trait RentRefTakeExt {
+    /// Wraps the reader in a `RefTake`, allowing limited reading via a mutable reference.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use futures::executor::block_on;
+    /// use monoio::io::AsyncReadRent;
+    /// use reftake::RentRefTakeExt;
+    ///
+    /// block_on(async {
+    ///     let mut reader: &[u8] = b"hello world";
+    ///     let (n, buf) = reader.take_ref_rent(5).read(Vec::with_capacity(64)).await;
+    ///     assert_eq!(n.unwrap(), 5);
+    ///     assert_eq!(buf, b"hello");
+    ///     assert_eq!(reader, b" world");
+    /// });
+    /// ```
+    fn take_ref_rent(&mut self, limit: u64) -> RefTake<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: AsyncReadRent> RentRefTakeExt for T {
+    fn take_ref_rent(&mut self, limit: u64) -> RefTake<'_, Self> {
+        RefTake::wrap(self, limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use monoio::buf::VecBuf;
+
+    #[test]
+    fn test_rented_buffer_is_trimmed() {
+        block_on(async {
+            let mut reader: &[u8] = b"abcdefgh";
+            let mut take = reader.take_ref_rent(5);
+
+            let (n, buf) = take.read(Vec::with_capacity(3)).await;
+            assert_eq!((n.unwrap(), buf.as_slice()), (3, &b"abc"[..]));
+            let (n, buf) = take.read(Vec::with_capacity(16)).await;
+            assert_eq!((n.unwrap(), buf.as_slice()), (2, &b"de"[..]));
+            assert_eq!(buf.capacity(), 16);
+            let (n, _) = take.read(Vec::with_capacity(16)).await;
+            assert_eq!(n.unwrap(), 0);
+            assert_eq!(reader, b"fgh");
+        });
+    }
+
+    #[test]
+    fn test_vectored_read_fills_first_part_up_to_limit() {
+        block_on(async {
+            let mut reader: &[u8] = b"0123456789";
+            let mut take = reader.take_ref_rent(4);
+
+            let buf = VecBuf::from(vec![vec![0u8; 6], vec![0u8; 6]]);
+            let (n, buf) = take.readv(buf).await;
+            assert_eq!(n.unwrap(), 4);
+            let parts: Vec<Vec<u8>> = buf.into();
+            assert_eq!(&parts[0][..4], b"0123");
+            assert_eq!(take.current_limit(), 0);
+            assert_eq!(reader, b"456789");
+        });
+    }
+}
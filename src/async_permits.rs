@@ -0,0 +1,193 @@
+//! Byte backpressure from a shared semaphore over a borrowed tokio reader.
+
+use std::{
+    future::Future,
+    io,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll, ready},
+};
+
+use tokio::{
+    io::{AsyncRead, ReadBuf},
+    sync::{AcquireError, OwnedSemaphorePermit, Semaphore},
+};
+
+use crate::async_read::poll_read_limited;
+
+type Acquire = Pin<Box<dyn Future<Output = Result<OwnedSemaphorePermit, AcquireError>> + Send>>;
+
+/// A non-owning async adapter that takes a permit from a shared semaphore
+/// for every `bytes_per_permit` bytes it reads.
+///
+/// Sharing one `tokio::sync::Semaphore` among all connections gives them a
+/// common budget of bytes in flight: when it is used up, reads wait until
+/// another reader gives permits back. Permits are held until the adapter is
+/// dropped or [`PermitReader::release`] is called, so the budget covers the
+/// data until the handler is done with it. Combine it with an async
+/// [`RefTake`](crate::RefTake) for a per-stream limit as well.
+pub struct PermitReader<'a, R> {
+    inner: &'a mut R,
+    semaphore: Arc<Semaphore>,
+    bytes_per_permit: u64,
+    /// Permits taken so far, merged into one.
+    held: Option<OwnedSemaphorePermit>,
+    /// Bytes that may still be read with the permits held.
+    credit: u64,
+    /// Permit acquisition of a read waiting for the budget.
+    acquire: Option<Acquire>,
+}
+
+impl<'a, R> PermitReader<'a, R> {
+    /// Creates a new `PermitReader` taking one permit of `semaphore` per
+    /// `bytes_per_permit` bytes read.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bytes_per_permit` is zero.
+    pub const fn wrap(inner: &'a mut R, semaphore: Arc<Semaphore>, bytes_per_permit: u64) -> Self {
+        assert!(bytes_per_permit > 0, "bytes per permit must be positive");
+        Self {
+            inner,
+            semaphore,
+            bytes_per_permit,
+            held: None,
+            credit: 0,
+            acquire: None,
+        }
+    }
+
+    /// Returns the number of permits held.
+    pub fn permits_held(&self) -> usize {
+        self.held
+            .as_ref()
+            .map_or(0, OwnedSemaphorePermit::num_permits)
+    }
+
+    /// Gives all permits held back to the semaphore, once the bytes read
+    /// so far are no longer buffered anywhere.
+    pub fn release(&mut self) {
+        self.held = None;
+        self.credit = 0;
+    }
+
+    /// Waits for the next permit if the credit is used up.
+    fn poll_credit(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        if self.credit > 0 {
+            return Poll::Ready(Ok(()));
+        }
+        let acquire = self
+            .acquire
+            .get_or_insert_with(|| Box::pin(self.semaphore.clone().acquire_owned()));
+        let result = ready!(acquire.as_mut().poll(cx));
+        self.acquire = None;
+        let permit = result.map_err(|_| io::Error::other("byte budget semaphore closed"))?;
+        match &mut self.held {
+            Some(held) => held.merge(permit),
+            None => self.held = Some(permit),
+        }
+        self.credit = self.bytes_per_permit;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for PermitReader<'_, R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        if buf.remaining() == 0 {
+            return Poll::Ready(Ok(()));
+        }
+        let this = &mut *self;
+        ready!(this.poll_credit(cx))?;
+        poll_read_limited(this.inner, &mut this.credit, cx, buf)
+    }
+}
+
+/// Extension trait to provide a `permits_ref` method on all `tokio::io::AsyncRead` types.
+pub trait PermitReaderExt {
+    /// Wraps the async reader in a `PermitReader` drawing on `semaphore`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use reftake::{AsyncRefTakeExt, PermitReaderExt};
+    /// use tokio::{io::AsyncReadExt, sync::Semaphore};
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// // A budget of 64 KiB in 1 KiB permits, shared by all connections
+    /// let budget = Arc::new(Semaphore::new(64));
+    ///
+    /// let data = vec![0u8; 4096];
+    /// let mut conn = data.as_slice();
+    /// let mut reader = conn.permits_ref(budget.clone(), 1024);
+    /// let mut body = Vec::new();
+    /// reader.take_ref_async(3000).read_to_end(&mut body).await.unwrap();
+    /// assert_eq!(budget.available_permits(), 61);
+    ///
+    /// drop(reader);
+    /// assert_eq!(budget.available_permits(), 64);
+    /// # });
+    /// ```
+    fn permits_ref(
+        &mut self,
+        semaphore: Arc<Semaphore>,
+        bytes_per_permit: u64,
+    ) -> PermitReader<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: AsyncRead + Unpin> PermitReaderExt for T {
+    fn permits_ref(
+        &mut self,
+        semaphore: Arc<Semaphore>,
+        bytes_per_permit: u64,
+    ) -> PermitReader<'_, Self> {
+        PermitReader::wrap(self, semaphore, bytes_per_permit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::task::Waker;
+    use tokio::io::AsyncReadExt;
+
+    #[tokio::test]
+    async fn test_reads_wait_for_released_permits() {
+        let budget = Arc::new(Semaphore::new(2));
+        let mut first_conn: &[u8] = b"aaaaaa";
+        let mut second_conn: &[u8] = b"bbbb";
+        let mut first = first_conn.permits_ref(budget.clone(), 2);
+        let mut second = second_conn.permits_ref(budget.clone(), 2);
+
+        let mut buf = [0u8; 8];
+        assert_eq!(first.read(&mut buf).await.unwrap(), 2);
+        assert_eq!(first.read(&mut buf).await.unwrap(), 2);
+        assert_eq!(first.permits_held(), 2);
+        {
+            let mut read = Box::pin(second.read(&mut buf));
+            let mut cx = Context::from_waker(Waker::noop());
+            assert!(read.as_mut().poll(&mut cx).is_pending());
+        }
+
+        // The waiting read keeps its place in the queue and gets the first permit back
+        first.release();
+        assert_eq!(budget.available_permits(), 1);
+        assert_eq!(second.read(&mut buf).await.unwrap(), 2);
+        assert_eq!(second.permits_held(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_closed_semaphore_is_an_error() {
+        let budget = Arc::new(Semaphore::new(0));
+        budget.close();
+        let mut reader: &[u8] = b"abc";
+        let err = reader.permits_ref(budget, 16).read_u8().await.unwrap_err();
+        assert_eq!(err.to_string(), "byte budget semaphore closed");
+    }
+}
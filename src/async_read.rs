@@ -0,0 +1,181 @@
+//! `tokio::io::AsyncRead` and `AsyncBufRead` support for [`RefTake`].
+
+use std::{
+    cmp, io,
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+use tokio::io::{AsyncBufRead, AsyncRead, ReadBuf};
+
+use crate::RefTake;
+
+/// Implements `tokio::io::AsyncRead` with a byte limit.
+///
+/// Unlike `tokio::io::AsyncReadExt::take`, the inner reader is only
+/// borrowed, so it stays usable once the window has been read. Reads are
+/// clamped to the remaining limit, and at limit zero the inner reader is
+/// not polled at all.
+impl<T: AsyncRead + Unpin> AsyncRead for RefTake<'_, T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        poll_read_limited(this.inner, &mut this.limit, cx, buf)
+    }
+}
+
+/// Implements `tokio::io::AsyncBufRead` with a byte limit.
+///
+/// `poll_fill_buf()` returns the inner buffer capped at the remaining
+/// limit, and `consume()` is clamped to the limit as well.
+impl<T: AsyncBufRead + Unpin> AsyncBufRead for RefTake<'_, T> {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        let this = self.get_mut();
+        // Don't poll the inner reader at all at EOF because it may still be pending
+        if this.limit == 0 {
+            return Poll::Ready(Ok(&[]));
+        }
+
+        let buf = ready!(Pin::new(&mut *this.inner).poll_fill_buf(cx))?;
+        let cap = cmp::min(buf.len() as u64, this.limit) as usize;
+        Poll::Ready(Ok(&buf[..cap]))
+    }
+
+    fn consume(mut self: Pin<&mut Self>, amt: usize) {
+        // Don't let callers reset the limit by passing an overlarge value
+        let amt = cmp::min(amt as u64, self.limit) as usize;
+        self.limit -= amt as u64;
+        Pin::new(&mut *self.inner).consume(amt);
+    }
+}
+
+/// Polls `inner` for at most `*limit` bytes, decrementing `*limit` by the number read.
+pub(crate) fn poll_read_limited<T: AsyncRead + Unpin>(
+    inner: &mut T,
+    limit: &mut u64,
+    cx: &mut Context<'_>,
+    buf: &mut ReadBuf<'_>,
+) -> Poll<io::Result<()>> {
+    // Don't poll the inner reader at all at EOF because it may still be pending
+    if *limit == 0 {
+        return Poll::Ready(Ok(()));
+    }
+
+    let max = cmp::min(buf.remaining() as u64, *limit) as usize;
+    let mut limited = ReadBuf::new(buf.initialize_unfilled_to(max));
+    ready!(Pin::new(inner).poll_read(cx, &mut limited))?;
+    let n = limited.filled().len();
+    buf.advance(n);
+    *limit -= n as u64;
+    trace_event!(trace, read = n, remaining = *limit, "window read");
+    if *limit == 0 {
+        trace_event!(debug, "window limit exhausted");
+    }
+    Poll::Ready(Ok(()))
+}
+
+/// Extension trait to provide a `take_ref_async` method on all `tokio::io::AsyncRead` types.
+pub trait AsyncRefTakeExt {
+    /// Wraps the async reader in a `RefTake`, allowing limited reading via a mutable reference.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use reftake::AsyncRefTakeExt;
+    /// use tokio::io::AsyncReadExt;
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let mut reader: &[u8] = b"hello world";
+    /// let mut text = String::new();
+    /// reader.take_ref_async(5).read_to_string(&mut text).await.unwrap();
+    /// assert_eq!(text, "hello");
+    /// assert_eq!(reader, b" world");
+    /// # });
+    /// ```
+    fn take_ref_async(&mut self, limit: u64) -> RefTake<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: AsyncRead + Unpin> AsyncRefTakeExt for T {
+    fn take_ref_async(&mut self, limit: u64) -> RefTake<'_, Self> {
+        trace_event!(trace, limit, "window created");
+        RefTake::wrap(self, limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+
+    /// Never ready, to check that an exhausted window doesn't poll it.
+    struct Pending;
+
+    impl AsyncRead for Pending {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            _buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            Poll::Pending
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reads_are_clamped() {
+        let mut reader: &[u8] = b"abcdefgh";
+        let mut take = reader.take_ref_async(5);
+
+        let mut buf = [0u8; 3];
+        assert_eq!(take.read(&mut buf).await.unwrap(), 3);
+        assert_eq!(take.read(&mut buf).await.unwrap(), 2);
+        assert_eq!(&buf[..2], b"de");
+        assert_eq!(take.read(&mut buf).await.unwrap(), 0);
+        assert_eq!(take.current_limit(), 0);
+        assert_eq!(reader, b"fgh");
+    }
+
+    #[tokio::test]
+    async fn test_exhausted_window_does_not_poll_inner() {
+        let mut reader = Pending;
+        let mut out = Vec::new();
+        let n = reader
+            .take_ref_async(0)
+            .read_to_end(&mut out)
+            .await
+            .unwrap();
+        assert_eq!(n, 0);
+    }
+
+    #[tokio::test]
+    async fn test_buffered_reads_stop_at_limit() {
+        use tokio::io::AsyncBufReadExt;
+
+        let mut reader: &[u8] = b"first\nsecond line\n";
+        let mut take = reader.take_ref_async(9);
+
+        let mut line = String::new();
+        take.read_line(&mut line).await.unwrap();
+        assert_eq!(line, "first\n");
+        line.clear();
+        take.read_line(&mut line).await.unwrap();
+        assert_eq!(line, "sec");
+        assert_eq!(take.fill_buf().await.unwrap(), b"");
+        assert_eq!(reader, b"ond line\n");
+    }
+
+    #[tokio::test]
+    async fn test_set_limit_continues_reading() {
+        let mut reader: &[u8] = b"0123456789";
+        let mut take = RefTake::wrap(&mut reader, 2);
+        let mut out = Vec::new();
+        take.read_to_end(&mut out).await.unwrap();
+        take.set_limit(3);
+        take.read_to_end(&mut out).await.unwrap();
+        assert_eq!(out, b"01234");
+    }
+}
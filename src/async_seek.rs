@@ -0,0 +1,171 @@
+//! A seekable window over a borrowed tokio reader.
+
+use std::{
+    io::{self, ErrorKind, SeekFrom},
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+use tokio::io::{AsyncRead, AsyncSeek, ReadBuf};
+
+use crate::async_read::poll_read_limited;
+
+/// A non-owning async adapter exposing the next `len` bytes of a seekable
+/// tokio reader as a stream of its own.
+///
+/// Reads behave like those of an async [`RefTake`](crate::RefTake). Seeks
+/// are interpreted relative to the window: position `0` is where the inner
+/// reader was when the window was created, and `SeekFrom::End` counts back
+/// from `len`. Target positions are clamped to `0..=len`, and the inner
+/// reader is moved with a relative seek, so its absolute position never
+/// has to be known.
+pub struct AsyncSeekTake<'a, R> {
+    inner: &'a mut R,
+    len: u64,
+    limit: u64,
+    /// Window position of a seek started but not yet completed.
+    target: Option<u64>,
+}
+
+impl<'a, R> AsyncSeekTake<'a, R> {
+    /// Creates a new `AsyncSeekTake` over the next `len` bytes of the given reader reference.
+    pub const fn wrap(inner: &'a mut R, len: u64) -> Self {
+        Self {
+            inner,
+            len,
+            limit: len,
+            target: None,
+        }
+    }
+
+    /// Returns the length of the window.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns `true` if the window is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the current position within the window.
+    pub fn position(&self) -> u64 {
+        self.len - self.limit
+    }
+
+    /// Returns the number of bytes left to read before the end of the window.
+    pub fn current_limit(&self) -> u64 {
+        self.limit
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for AsyncSeekTake<'_, R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        poll_read_limited(this.inner, &mut this.limit, cx, buf)
+    }
+}
+
+/// Implements `tokio::io::AsyncSeek` within the window.
+///
+/// Completed seeks report the position within the window, not that of the
+/// inner reader.
+impl<R: AsyncSeek + Unpin> AsyncSeek for AsyncSeekTake<'_, R> {
+    fn start_seek(mut self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        let current = self.position();
+        let target = match position {
+            SeekFrom::Start(n) => n.min(self.len),
+            SeekFrom::End(n) => self.len.saturating_add_signed(n).min(self.len),
+            SeekFrom::Current(n) => current.saturating_add_signed(n).min(self.len),
+        };
+        let delta = i64::try_from(target as i128 - current as i128)
+            .map_err(|_| io::Error::new(ErrorKind::InvalidInput, "seek distance out of range"))?;
+        Pin::new(&mut *self.inner).start_seek(SeekFrom::Current(delta))?;
+        self.target = Some(target);
+        Ok(())
+    }
+
+    fn poll_complete(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        ready!(Pin::new(&mut *self.inner).poll_complete(cx))?;
+        if let Some(target) = self.target.take() {
+            self.limit = self.len - target;
+        }
+        Poll::Ready(Ok(self.position()))
+    }
+}
+
+/// Extension trait to provide a `take_ref_seekable` method on all seekable tokio readers.
+pub trait AsyncSeekTakeExt {
+    /// Wraps the reader in an `AsyncSeekTake` over its next `len` bytes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Cursor, SeekFrom};
+    /// use reftake::AsyncSeekTakeExt;
+    /// use tokio::io::{AsyncReadExt, AsyncSeekExt};
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let mut cursor = Cursor::new(b"header|entry data|trailer".to_vec());
+    /// cursor.set_position(7);
+    /// let mut entry = cursor.take_ref_seekable(10);
+    ///
+    /// assert_eq!(entry.seek(SeekFrom::End(-4)).await.unwrap(), 6);
+    /// let mut tail = String::new();
+    /// entry.read_to_string(&mut tail).await.unwrap();
+    /// assert_eq!(tail, "data");
+    /// # });
+    /// ```
+    fn take_ref_seekable(&mut self, len: u64) -> AsyncSeekTake<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: AsyncRead + AsyncSeek + Unpin> AsyncSeekTakeExt for T {
+    fn take_ref_seekable(&mut self, len: u64) -> AsyncSeekTake<'_, Self> {
+        AsyncSeekTake::wrap(self, len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+    #[tokio::test]
+    async fn test_seeks_are_relative_and_clamped() {
+        let mut cursor = Cursor::new(b"0123456789".to_vec());
+        cursor.set_position(2);
+        let mut window = cursor.take_ref_seekable(5);
+
+        assert_eq!(window.seek(SeekFrom::Start(3)).await.unwrap(), 3);
+        assert_eq!(window.read_u8().await.unwrap(), b'5');
+        assert_eq!(window.seek(SeekFrom::Current(-10)).await.unwrap(), 0);
+        assert_eq!(window.read_u8().await.unwrap(), b'2');
+        assert_eq!(window.seek(SeekFrom::Start(100)).await.unwrap(), 5);
+        assert_eq!(window.current_limit(), 0);
+        assert_eq!(window.seek(SeekFrom::End(1)).await.unwrap(), 5);
+        assert_eq!(window.stream_position().await.unwrap(), 5);
+        assert_eq!(cursor.position(), 7);
+    }
+
+    #[tokio::test]
+    async fn test_reads_stop_at_window_end_after_seek() {
+        let mut cursor = Cursor::new(b"abcdefgh".to_vec());
+        let mut window = cursor.take_ref_seekable(6);
+
+        window.seek(SeekFrom::End(-3)).await.unwrap();
+        let mut out = Vec::new();
+        window.read_to_end(&mut out).await.unwrap();
+        assert_eq!(out, b"def");
+        window.seek(SeekFrom::Current(-6)).await.unwrap();
+        out.clear();
+        window.read_to_end(&mut out).await.unwrap();
+        assert_eq!(out, b"abcdef");
+    }
+}
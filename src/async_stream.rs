@@ -0,0 +1,203 @@
+//! A bounded tokio reader over a borrowed `Stream` of `Bytes` chunks.
+
+use std::{
+    cmp, io,
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+use bytes::{Buf, Bytes};
+use futures_core::Stream;
+use tokio::io::{AsyncBufRead, AsyncRead, ReadBuf};
+
+/// A non-owning adapter reading at most `limit` bytes from a stream of
+/// `Result<Bytes, E>` chunks, as a tokio `AsyncRead` + `AsyncBufRead`.
+///
+/// The inverse of [`ByteStream`](crate::ByteStream), for hyper bodies,
+/// channels and other chunk producers that a parser wants to read like a
+/// socket. A chunk is pulled only when the previous one is used up, and
+/// the part of it not read yet is kept across polls, so reads of any size
+/// see the bytes in order. Like [`RefTake`](crate::RefTake), the adapter
+/// reports EOF once `limit` bytes have been read; bytes of the last chunk
+/// past the limit stay in [`StreamTake::buffered`] rather than being lost,
+/// and the stream is not polled again. Stream errors are converted into
+/// `io::Error`s and returned from the read that hit them.
+pub struct StreamTake<'a, S> {
+    inner: &'a mut S,
+    chunk: Bytes,
+    limit: u64,
+}
+
+impl<'a, S> StreamTake<'a, S> {
+    /// Creates a new `StreamTake` over the next `limit` bytes of the given stream reference.
+    pub const fn wrap(inner: &'a mut S, limit: u64) -> Self {
+        Self {
+            inner,
+            chunk: Bytes::new(),
+            limit,
+        }
+    }
+
+    /// Returns the number of bytes left to read before the limit.
+    pub fn current_limit(&self) -> u64 {
+        self.limit
+    }
+
+    /// Returns the bytes of the current chunk that have been pulled from the stream but not read.
+    ///
+    /// Once the limit is reached these are the bytes past it.
+    pub fn buffered(&self) -> &Bytes {
+        &self.chunk
+    }
+
+    /// Consumes the adapter, returning the unread bytes of the current chunk.
+    pub fn into_buffered(self) -> Bytes {
+        self.chunk
+    }
+}
+
+impl<S, E> StreamTake<'_, S>
+where
+    S: Stream<Item = Result<Bytes, E>> + Unpin,
+    E: Into<io::Error>,
+{
+    /// Polls for a non-empty chunk unless the limit or the stream is exhausted.
+    fn poll_chunk(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        while self.limit > 0 && self.chunk.is_empty() {
+            match ready!(Pin::new(&mut *self.inner).poll_next(cx)) {
+                Some(Ok(chunk)) => self.chunk = chunk,
+                Some(Err(e)) => return Poll::Ready(Err(e.into())),
+                None => break,
+            }
+        }
+        let cap = cmp::min(self.chunk.len() as u64, self.limit) as usize;
+        Poll::Ready(Ok(&self.chunk[..cap]))
+    }
+}
+
+impl<S, E> AsyncRead for StreamTake<'_, S>
+where
+    S: Stream<Item = Result<Bytes, E>> + Unpin,
+    E: Into<io::Error>,
+{
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        let available = ready!(this.poll_chunk(cx))?;
+        let n = cmp::min(available.len(), buf.remaining());
+        buf.put_slice(&available[..n]);
+        this.chunk.advance(n);
+        this.limit -= n as u64;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<S, E> AsyncBufRead for StreamTake<'_, S>
+where
+    S: Stream<Item = Result<Bytes, E>> + Unpin,
+    E: Into<io::Error>,
+{
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        self.get_mut().poll_chunk(cx)
+    }
+
+    fn consume(mut self: Pin<&mut Self>, amt: usize) {
+        // Only what `poll_fill_buf` could have returned may be consumed
+        let amt = cmp::min(amt as u64, cmp::min(self.limit, self.chunk.len() as u64)) as usize;
+        self.chunk.advance(amt);
+        self.limit -= amt as u64;
+    }
+}
+
+/// Extension trait to provide a `take_stream_ref` method on all streams of `Bytes` chunks.
+pub trait StreamTakeExt {
+    /// Wraps the stream in a `StreamTake` reading at most `limit` bytes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// use bytes::Bytes;
+    /// use futures::stream;
+    /// use reftake::StreamTakeExt;
+    /// use tokio::io::AsyncReadExt;
+    ///
+    /// let chunks = ["GET / HT", "TP/1.1\r", "\n\r\nbody"].map(|c| Ok::<_, std::io::Error>(Bytes::from(c)));
+    /// let mut body = stream::iter(chunks);
+    ///
+    /// let mut head = body.take_stream_ref(18);
+    /// let mut text = String::new();
+    /// head.read_to_string(&mut text).await.unwrap();
+    /// assert_eq!(text, "GET / HTTP/1.1\r\n\r\n");
+    /// assert_eq!(head.buffered(), "body");
+    /// # });
+    /// ```
+    fn take_stream_ref(&mut self, limit: u64) -> StreamTake<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<S, E> StreamTakeExt for S
+where
+    S: Stream<Item = Result<Bytes, E>> + Unpin,
+{
+    fn take_stream_ref(&mut self, limit: u64) -> StreamTake<'_, Self> {
+        StreamTake::wrap(self, limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{StreamExt, stream};
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt};
+
+    fn chunks(parts: &[&'static str]) -> impl Stream<Item = io::Result<Bytes>> + Unpin {
+        stream::iter(parts.to_vec()).map(|part| Ok(Bytes::from_static(part.as_bytes())))
+    }
+
+    #[tokio::test]
+    async fn test_small_reads_span_chunks() {
+        let mut stream = chunks(&["ab", "", "cdef", "gh"]);
+        let mut take = stream.take_stream_ref(7);
+        let mut buf = [0u8; 3];
+
+        take.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"abc");
+        take.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"def");
+        assert_eq!(take.read(&mut buf).await.unwrap(), 1);
+        assert_eq!(take.read(&mut buf).await.unwrap(), 0);
+        assert_eq!(take.into_buffered(), "h");
+        assert!(stream.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_buffered_lines_and_short_streams() {
+        let mut stream = chunks(&["one\ntw", "o\nthree"]);
+        let mut take = stream.take_stream_ref(100);
+        let mut lines = Vec::new();
+        let mut line = String::new();
+        while take.read_line(&mut line).await.unwrap() > 0 {
+            lines.push(std::mem::take(&mut line));
+        }
+        assert_eq!(lines, ["one\n", "two\n", "three"]);
+        assert_eq!(take.current_limit(), 87);
+    }
+
+    #[tokio::test]
+    async fn test_stream_errors_are_returned() {
+        let mut stream = stream::iter([
+            Ok(Bytes::from_static(b"ok")),
+            Err(io::Error::other("connection reset")),
+        ]);
+        let mut take = stream.take_stream_ref(10);
+        let mut out = Vec::new();
+        let err = take.read_to_end(&mut out).await.unwrap_err();
+        assert_eq!(err.to_string(), "connection reset");
+        assert_eq!(out, b"ok");
+    }
+}
@@ -0,0 +1,195 @@
+//! A token-bucket rate limiter over a borrowed tokio reader.
+
+use std::{
+    cmp,
+    future::Future,
+    io,
+    pin::Pin,
+    task::{Context, Poll, ready},
+    time::Duration,
+};
+
+use tokio::{
+    io::{AsyncRead, ReadBuf},
+    time::{Instant, Sleep},
+};
+
+use crate::async_read::poll_read_limited;
+
+/// The async counterpart of [`RefThrottle`](crate::RefThrottle).
+///
+/// Implements a token bucket holding at most `burst` bytes, refilled at
+/// `bytes_per_sec`. Each read is clamped to the currently available tokens;
+/// when the bucket is empty, the read waits on a tokio timer instead of
+/// blocking the thread. Combined with an async [`RefTake`](crate::RefTake),
+/// it bounds both the size and the bandwidth of an untrusted stream.
+pub struct AsyncThrottle<'a, R> {
+    inner: &'a mut R,
+    rate: u64,
+    burst: u64,
+    tokens: f64,
+    last_refill: Instant,
+    /// Timer of a read waiting for the bucket to refill.
+    sleep: Option<Pin<Box<Sleep>>>,
+}
+
+impl<'a, R> AsyncThrottle<'a, R> {
+    /// Creates a new `AsyncThrottle` reading at most `bytes_per_sec` bytes per second,
+    /// with a burst size of one second worth of bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bytes_per_sec` is zero.
+    pub fn wrap(inner: &'a mut R, bytes_per_sec: u64) -> Self {
+        Self::with_burst(inner, bytes_per_sec, bytes_per_sec)
+    }
+
+    /// Creates a new `AsyncThrottle` with an explicit burst size.
+    ///
+    /// The bucket starts full, so up to `burst` bytes can be read immediately.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bytes_per_sec` or `burst` is zero.
+    pub fn with_burst(inner: &'a mut R, bytes_per_sec: u64, burst: u64) -> Self {
+        assert!(bytes_per_sec > 0, "throttle rate must be positive");
+        assert!(burst > 0, "throttle burst must be positive");
+        Self {
+            inner,
+            rate: bytes_per_sec,
+            burst,
+            tokens: burst as f64,
+            last_refill: Instant::now(),
+            sleep: None,
+        }
+    }
+
+    /// Returns the configured rate in bytes per second.
+    pub fn rate(&self) -> u64 {
+        self.rate
+    }
+
+    /// Changes the rate in bytes per second, keeping the tokens accumulated so far.
+    ///
+    /// A read already waiting for tokens keeps waiting for the old deadline.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bytes_per_sec` is zero.
+    pub fn set_rate(&mut self, bytes_per_sec: u64) {
+        assert!(bytes_per_sec > 0, "throttle rate must be positive");
+        self.refill();
+        self.rate = bytes_per_sec;
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate as f64).min(self.burst as f64);
+        self.last_refill = now;
+    }
+
+    /// Waits until at least one byte may be read, then returns how many may be read.
+    fn poll_acquire(&mut self, cx: &mut Context<'_>, wanted: usize) -> Poll<usize> {
+        self.refill();
+        if self.tokens < 1.0 {
+            let missing = 1.0 - self.tokens;
+            let deadline = self.last_refill + Duration::from_secs_f64(missing / self.rate as f64);
+            let sleep = self
+                .sleep
+                .get_or_insert_with(|| Box::pin(tokio::time::sleep_until(deadline)));
+            ready!(sleep.as_mut().poll(cx));
+            self.sleep = None;
+            self.refill();
+        }
+        Poll::Ready(cmp::min(wanted as u64, cmp::max(self.tokens as u64, 1)) as usize)
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for AsyncThrottle<'_, R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        if buf.remaining() == 0 {
+            return Poll::Ready(Ok(()));
+        }
+        let max = ready!(self.poll_acquire(cx, buf.remaining())) as u64;
+        let mut allowed = max;
+        ready!(poll_read_limited(self.inner, &mut allowed, cx, buf))?;
+        self.tokens -= (max - allowed) as f64;
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Extension trait to provide a `throttle_ref_async` method on all `tokio::io::AsyncRead` types.
+pub trait AsyncThrottleExt {
+    /// Wraps the async reader in an `AsyncThrottle` limited to `bytes_per_sec`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use reftake::{AsyncRefTakeExt, AsyncThrottleExt};
+    /// use tokio::io::AsyncReadExt;
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap().block_on(async {
+    /// let data = vec![0u8; 1024];
+    /// let mut upload = data.as_slice();
+    /// let mut throttle = upload.throttle_ref_async(1_000_000);
+    ///
+    /// let mut buf = Vec::new();
+    /// throttle.take_ref_async(100).read_to_end(&mut buf).await.unwrap();
+    /// assert_eq!(buf.len(), 100);
+    /// # });
+    /// ```
+    fn throttle_ref_async(&mut self, bytes_per_sec: u64) -> AsyncThrottle<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: AsyncRead + Unpin> AsyncThrottleExt for T {
+    fn throttle_ref_async(&mut self, bytes_per_sec: u64) -> AsyncThrottle<'_, Self> {
+        AsyncThrottle::wrap(self, bytes_per_sec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_burst_is_available_immediately() {
+        let data = vec![7u8; 100];
+        let mut reader = data.as_slice();
+        let mut throttle = AsyncThrottle::with_burst(&mut reader, 10, 40);
+
+        let start = Instant::now();
+        let mut buf = [0u8; 100];
+        assert_eq!(throttle.read(&mut buf).await.unwrap(), 40);
+        assert_eq!(start.elapsed(), Duration::ZERO);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_throttle_sleeps_for_tokens() {
+        let data = vec![7u8; 100];
+        let mut reader = data.as_slice();
+        let mut throttle = AsyncThrottle::with_burst(&mut reader, 1000, 10);
+
+        let start = Instant::now();
+        let mut buf = Vec::new();
+        throttle.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(buf.len(), 100);
+        // 90 bytes beyond the initial burst at 1000 B/s
+        assert!(start.elapsed() >= Duration::from_millis(89));
+        assert!(start.elapsed() < Duration::from_millis(200));
+    }
+
+    #[test]
+    #[should_panic(expected = "throttle rate must be positive")]
+    fn test_zero_rate_panics() {
+        let mut reader: &[u8] = b"";
+        let _ = reader.throttle_ref_async(0);
+    }
+}
@@ -0,0 +1,247 @@
+//! Reading browser `ReadableStream`s through tokio's `AsyncRead`.
+
+use std::{
+    cmp,
+    future::Future,
+    io::{self, ErrorKind},
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+use js_sys::{Reflect, Uint8Array};
+use tokio::io::{AsyncBufRead, AsyncRead, ReadBuf};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{ReadableStream, ReadableStreamDefaultReader};
+
+/// An `AsyncRead` + `AsyncBufRead` over the chunks of a JS `ReadableStream`.
+///
+/// Each `read()` promise of the stream's default reader is awaited in turn
+/// and its `Uint8Array` chunk handed out, so a `fetch` body or a
+/// `WebSocketStream` can be consumed with the crate's tokio adapters, such
+/// as a [`RefTake`](crate::RefTake) per record or, with the `stream`
+/// feature, an `AsyncFrameReader` for length-prefixed frames, exactly like
+/// a socket. A chunk is kept until it is fully read,
+/// so no more of the stream is pulled than the reader above asks for.
+///
+/// Rejected promises and chunks that are not `Uint8Array`s are reported
+/// as `io::Error`s carrying the JS value's debug representation.
+///
+/// # Example
+///
+/// ```no_run
+/// use reftake::{ReadableStreamReader, RefTake};
+/// use tokio::io::AsyncReadExt;
+///
+/// async fn read_header(stream: &web_sys::ReadableStream) -> std::io::Result<Vec<u8>> {
+///     let mut reader = ReadableStreamReader::from_stream(stream);
+///     let mut header = Vec::new();
+///     RefTake::wrap(&mut reader, 512).read_to_end(&mut header).await?;
+///     Ok(header)
+/// }
+/// ```
+pub struct ReadableStreamReader {
+    inner: ChunkReader<JsChunks>,
+}
+
+impl ReadableStreamReader {
+    /// Creates a new `ReadableStreamReader` over an already acquired default reader.
+    pub fn new(reader: ReadableStreamDefaultReader) -> Self {
+        Self {
+            inner: ChunkReader::new(JsChunks {
+                reader,
+                pending: None,
+            }),
+        }
+    }
+
+    /// Locks the stream to a new default reader and reads through it.
+    pub fn from_stream(stream: &ReadableStream) -> Self {
+        Self::new(stream.get_reader().unchecked_into())
+    }
+
+    /// Consumes the adapter, returning the default reader, for example to release its lock.
+    ///
+    /// Bytes of the current chunk that were not read yet are lost.
+    pub fn into_inner(self) -> ReadableStreamDefaultReader {
+        self.inner.source.reader
+    }
+}
+
+impl AsyncRead for ReadableStreamReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+impl AsyncBufRead for ReadableStreamReader {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        Pin::new(&mut self.get_mut().inner).poll_fill_buf(cx)
+    }
+
+    fn consume(mut self: Pin<&mut Self>, amt: usize) {
+        Pin::new(&mut self.inner).consume(amt)
+    }
+}
+
+/// A source of the chunks a [`ChunkReader`] hands out, `None` at the end of the stream.
+trait ChunkSource {
+    fn poll_next_chunk(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<Option<Vec<u8>>>>;
+}
+
+/// The `read()` promises of a stream's default reader.
+struct JsChunks {
+    reader: ReadableStreamDefaultReader,
+    pending: Option<JsFuture>,
+}
+
+impl ChunkSource for JsChunks {
+    fn poll_next_chunk(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<Option<Vec<u8>>>> {
+        let pending = self
+            .pending
+            .get_or_insert_with(|| JsFuture::from(self.reader.read()));
+        let result = ready!(Pin::new(pending).poll(cx));
+        self.pending = None;
+        let result = result.map_err(js_error)?;
+
+        if Reflect::get(&result, &"done".into())
+            .map_err(js_error)?
+            .is_truthy()
+        {
+            return Poll::Ready(Ok(None));
+        }
+        let value = Reflect::get(&result, &"value".into()).map_err(js_error)?;
+        let chunk = value.dyn_into::<Uint8Array>().map_err(|value| {
+            io::Error::new(
+                ErrorKind::InvalidData,
+                format!("stream chunk is not a Uint8Array: {value:?}"),
+            )
+        })?;
+        Poll::Ready(Ok(Some(chunk.to_vec())))
+    }
+}
+
+fn js_error(value: JsValue) -> io::Error {
+    io::Error::other(format!("{value:?}"))
+}
+
+/// Serves the chunks of a source, keeping each until it is fully read.
+struct ChunkReader<S> {
+    source: S,
+    chunk: Vec<u8>,
+    pos: usize,
+    done: bool,
+}
+
+impl<S: ChunkSource> ChunkReader<S> {
+    fn new(source: S) -> Self {
+        Self {
+            source,
+            chunk: Vec::new(),
+            pos: 0,
+            done: false,
+        }
+    }
+
+    fn poll_chunk(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        while self.pos == self.chunk.len() && !self.done {
+            match ready!(self.source.poll_next_chunk(cx))? {
+                Some(chunk) => {
+                    self.chunk = chunk;
+                    self.pos = 0;
+                }
+                None => self.done = true,
+            }
+        }
+        Poll::Ready(Ok(&self.chunk[self.pos..]))
+    }
+}
+
+impl<S: ChunkSource + Unpin> AsyncRead for ChunkReader<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        let chunk = ready!(this.poll_chunk(cx))?;
+        let n = cmp::min(chunk.len(), buf.remaining());
+        buf.put_slice(&chunk[..n]);
+        this.pos += n;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<S: ChunkSource + Unpin> AsyncBufRead for ChunkReader<S> {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        self.get_mut().poll_chunk(cx)
+    }
+
+    fn consume(mut self: Pin<&mut Self>, amt: usize) {
+        self.pos = cmp::min(self.pos + amt, self.chunk.len());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RefTake;
+    use futures::executor::block_on;
+    use std::collections::VecDeque;
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt};
+
+    /// Chunks standing in for the `read()` results of a JS stream, counting the chunks pulled.
+    struct Chunks {
+        chunks: VecDeque<&'static [u8]>,
+        pulled: usize,
+    }
+
+    impl ChunkSource for Chunks {
+        fn poll_next_chunk(&mut self, _: &mut Context<'_>) -> Poll<io::Result<Option<Vec<u8>>>> {
+            let chunk = self.chunks.pop_front().map(<[u8]>::to_vec);
+            self.pulled += chunk.is_some() as usize;
+            Poll::Ready(Ok(chunk))
+        }
+    }
+
+    fn reader(chunks: &[&'static [u8]]) -> ChunkReader<Chunks> {
+        ChunkReader::new(Chunks {
+            chunks: chunks.iter().copied().collect(),
+            pulled: 0,
+        })
+    }
+
+    #[test]
+    fn test_limit_pulls_only_the_chunks_needed() {
+        let mut stream = reader(&[b"head", b"er|bo", b"dy", b"|tail"]);
+        let mut header = Vec::new();
+        block_on(RefTake::wrap(&mut stream, 6).read_to_end(&mut header)).unwrap();
+        assert_eq!(header, b"header");
+        assert_eq!(stream.source.pulled, 2);
+
+        // The rest of a chunk is kept for the next window
+        let mut body = Vec::new();
+        block_on(RefTake::wrap(&mut stream, 5).read_to_end(&mut body)).unwrap();
+        assert_eq!(body, b"|body");
+        assert_eq!(stream.source.pulled, 3);
+
+        let mut rest = String::new();
+        block_on(stream.read_to_string(&mut rest)).unwrap();
+        assert_eq!(rest, "|tail");
+    }
+
+    #[test]
+    fn test_bufread_across_chunks() {
+        let mut stream = reader(&[b"one\ntw", b"o\n", b"", b"three"]);
+        let mut lines = Vec::new();
+        let mut line = String::new();
+        while block_on(stream.read_line(&mut line)).unwrap() > 0 {
+            lines.push(std::mem::take(&mut line));
+        }
+        assert_eq!(lines, ["one\n", "two\n", "three"]);
+    }
+}
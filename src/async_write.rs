@@ -0,0 +1,142 @@
+//! `tokio::io::AsyncWrite` support for [`RefTakeWrite`].
+
+use std::{
+    cmp, io,
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+use tokio::io::AsyncWrite;
+
+use crate::RefTakeWrite;
+
+/// Implements `tokio::io::AsyncWrite` with a byte quota.
+///
+/// Writes that don't fit in the remaining quota are handled according to
+/// the writer's [`OverflowBehavior`](crate::OverflowBehavior), exactly like
+/// the blocking `Write` implementation. Flushes and shutdowns go straight to
+/// the inner writer.
+impl<W: AsyncWrite + Unpin> AsyncWrite for RefTakeWrite<'_, W> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        if let Some(result) = self.check_overflow(buf.len()) {
+            return Poll::Ready(result);
+        }
+
+        let max = cmp::min(buf.len() as u64, self.limit) as usize;
+        let n = ready!(Pin::new(&mut *self.inner).poll_write(cx, &buf[..max]))?;
+        Poll::Ready(Ok(self.account(n, max, buf.len())))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut *self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut *self.inner).poll_shutdown(cx)
+    }
+}
+
+/// Extension trait to provide a `take_write_ref_async` method on all `tokio::io::AsyncWrite` types.
+pub trait AsyncRefTakeWriteExt {
+    /// Wraps the async writer in a `RefTakeWrite` accepting at most `limit` bytes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use reftake::AsyncRefTakeWriteExt;
+    /// use tokio::io::AsyncWriteExt;
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let mut out = Vec::new();
+    /// let mut limited = out.take_write_ref_async(5);
+    /// assert_eq!(limited.write(b"hello world").await.unwrap(), 5);
+    /// assert_eq!(out, b"hello");
+    /// # });
+    /// ```
+    fn take_write_ref_async(&mut self, limit: u64) -> RefTakeWrite<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: AsyncWrite + Unpin> AsyncRefTakeWriteExt for T {
+    fn take_write_ref_async(&mut self, limit: u64) -> RefTakeWrite<'_, Self> {
+        RefTakeWrite::wrap(self, limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LimitExceeded, OverflowBehavior};
+    use std::io::ErrorKind;
+    use tokio::io::AsyncWriteExt;
+
+    /// Records flushes and shutdowns on top of a `Vec`.
+    #[derive(Default)]
+    struct Recorder {
+        data: Vec<u8>,
+        flushed: bool,
+        shut_down: bool,
+    }
+
+    impl AsyncWrite for Recorder {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            self.data.extend_from_slice(buf);
+            Poll::Ready(Ok(buf.len()))
+        }
+
+        fn poll_flush(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            self.flushed = true;
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            self.shut_down = true;
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_truncate_then_write_zero() {
+        let mut out = Vec::new();
+        let mut limited = out.take_write_ref_async(4);
+        assert_eq!(limited.write(b"abcdef").await.unwrap(), 4);
+        let err = limited.write_all(b"g").await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::WriteZero);
+        assert_eq!(out, b"abcd");
+    }
+
+    #[tokio::test]
+    async fn test_error_and_saturate_overflow() {
+        let mut out = Vec::new();
+        let mut limited = RefTakeWrite::with_overflow(&mut out, 3, OverflowBehavior::Error);
+        let err = limited.write(b"abcd").await.unwrap_err();
+        assert_eq!(LimitExceeded::from_io(&err), Some(&LimitExceeded::new(3)));
+        limited.write_all(b"abc").await.unwrap();
+
+        let mut out = Vec::new();
+        let mut limited = RefTakeWrite::with_overflow(&mut out, 3, OverflowBehavior::Saturate);
+        limited.write_all(b"abcdef").await.unwrap();
+        assert_eq!(limited.truncated_bytes(), 3);
+        assert_eq!(out, b"abc");
+    }
+
+    #[tokio::test]
+    async fn test_flush_and_shutdown_are_delegated() {
+        let mut recorder = Recorder::default();
+        let mut limited = recorder.take_write_ref_async(2);
+        limited.write_all(b"ok").await.unwrap();
+        limited.flush().await.unwrap();
+        limited.shutdown().await.unwrap();
+        assert!(recorder.flushed && recorder.shut_down);
+        assert_eq!(recorder.data, b"ok");
+    }
+}
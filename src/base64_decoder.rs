@@ -0,0 +1,214 @@
+//! A streaming base64 decoder over a borrowed reader (feature `base64`).
+
+use std::io::{self, BufRead, ErrorKind, Read};
+
+use base64::{
+    Engine,
+    engine::{GeneralPurpose, general_purpose::STANDARD},
+};
+
+/// Size of the raw input chunk read from the inner reader per refill.
+const CHUNK: usize = 1024;
+
+/// A non-owning adapter that base64-decodes a reader on the fly.
+///
+/// Input is decoded in whole 4-character groups; a group split across two
+/// reads of the inner reader is kept until it is complete, so memory use is
+/// constant regardless of the payload size. ASCII whitespace (including line
+/// breaks of armored text) is skipped. Decoding stops after a padded group.
+///
+/// Invalid input fails with `ErrorKind::InvalidData`, as does input ending in
+/// the middle of a group.
+pub struct Base64Decoder<'a, R> {
+    inner: &'a mut R,
+    engine: &'static GeneralPurpose,
+    /// Encoded characters not yet forming a whole group.
+    pending: Vec<u8>,
+    /// Decoded bytes not yet returned to the caller.
+    out: Vec<u8>,
+    out_pos: usize,
+    finished: bool,
+}
+
+impl<'a, R> Base64Decoder<'a, R> {
+    /// Creates a new `Base64Decoder` using the standard alphabet with padding.
+    pub fn wrap(inner: &'a mut R) -> Self {
+        Self::with_engine(inner, &STANDARD)
+    }
+
+    /// Creates a new `Base64Decoder` using the given engine, e.g. `URL_SAFE`.
+    pub fn with_engine(inner: &'a mut R, engine: &'static GeneralPurpose) -> Self {
+        Self {
+            inner,
+            engine,
+            pending: Vec::with_capacity(CHUNK),
+            out: Vec::with_capacity(CHUNK / 4 * 3),
+            out_pos: 0,
+            finished: false,
+        }
+    }
+
+    /// Returns `true` once the end of the encoded data has been reached.
+    pub fn is_finished(&self) -> bool {
+        self.finished && self.out_pos == self.out.len()
+    }
+}
+
+impl<R: Read> Base64Decoder<'_, R> {
+    /// Decodes the next batch of whole groups into `self.out`.
+    fn refill(&mut self) -> io::Result<()> {
+        while self.out_pos == self.out.len() && !self.finished {
+            let mut raw = [0u8; CHUNK];
+            let n = self.inner.read(&mut raw)?;
+            if n == 0 {
+                if !self.pending.is_empty() {
+                    return Err(io::Error::new(
+                        ErrorKind::InvalidData,
+                        "base64 input ended in the middle of a group",
+                    ));
+                }
+                self.finished = true;
+                break;
+            }
+            self.pending
+                .extend(raw[..n].iter().filter(|b| !b.is_ascii_whitespace()));
+
+            let whole = self.pending.len() / 4 * 4;
+            if whole == 0 {
+                continue;
+            }
+            self.out.resize(whole / 4 * 3, 0);
+            let len = self
+                .engine
+                .decode_slice(&self.pending[..whole], &mut self.out)
+                .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+            self.out.truncate(len);
+            self.out_pos = 0;
+            if self.pending[whole - 1] == b'=' {
+                self.finished = true;
+            }
+            self.pending.drain(..whole);
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for Base64Decoder<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        let available = self.fill_buf()?;
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+/// Implements the `BufRead` trait over the internal buffer of decoded bytes.
+impl<R: Read> BufRead for Base64Decoder<'_, R> {
+    fn fill_buf(&mut self) -> Result<&[u8], io::Error> {
+        self.refill()?;
+        Ok(&self.out[self.out_pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.out_pos = (self.out_pos + amt).min(self.out.len());
+    }
+}
+
+/// Extension trait to provide a `base64_decode_ref` method on all `Read` types.
+pub trait Base64DecoderExt {
+    /// Wraps the reader in a `Base64Decoder` using the standard alphabet.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Cursor, Read};
+    /// use reftake::{Base64DecoderExt, RefTakeExt};
+    ///
+    /// let mut cursor = Cursor::new(b"aGVsbG8gd29ybGQ=|trailer");
+    /// let mut frame = cursor.take_ref(16);
+    /// let mut decoded = String::new();
+    /// frame.base64_decode_ref().read_to_string(&mut decoded).unwrap();
+    /// assert_eq!(decoded, "hello world");
+    /// ```
+    fn base64_decode_ref(&mut self) -> Base64Decoder<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: Read> Base64DecoderExt for T {
+    fn base64_decode_ref(&mut self) -> Base64Decoder<'_, Self> {
+        Base64Decoder::wrap(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// A reader returning at most `step` bytes per call.
+    struct Trickle<'a> {
+        data: &'a [u8],
+        step: usize,
+    }
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = self.step.min(buf.len()).min(self.data.len());
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_decode_split_groups() {
+        let encoded = STANDARD.encode(b"The quick brown fox jumps over the lazy dog");
+        for step in 1..8 {
+            let mut reader = Trickle {
+                data: encoded.as_bytes(),
+                step,
+            };
+            let mut decoded = Vec::new();
+            reader
+                .base64_decode_ref()
+                .read_to_end(&mut decoded)
+                .unwrap();
+            assert_eq!(decoded, b"The quick brown fox jumps over the lazy dog");
+        }
+    }
+
+    #[test]
+    fn test_decode_skips_line_breaks() {
+        let mut reader = Cursor::new(b"aGVs\nbG8g\r\nd29y\nbGQ=\n");
+        let mut decoded = String::new();
+        reader
+            .base64_decode_ref()
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, "hello world");
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        let mut reader = Cursor::new(b"aGV*bG8=");
+        let mut decoded = Vec::new();
+        let err = reader
+            .base64_decode_ref()
+            .read_to_end(&mut decoded)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_truncated_group() {
+        let mut reader = Cursor::new(b"aGVsbG");
+        let mut decoded = Vec::new();
+        let err = reader
+            .base64_decode_ref()
+            .read_to_end(&mut decoded)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+}
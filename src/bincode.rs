@@ -0,0 +1,121 @@
+//! Bounded deserialization with `bincode`'s serde support.
+
+use std::io::Read;
+
+use ::bincode::{config::Config, error::DecodeError};
+use serde::de::DeserializeOwned;
+
+use crate::{LimitExceeded, RefGuard};
+
+/// Decodes a `T` from a reader holding a bincode message of at most `limit` bytes.
+///
+/// The whole reader is the message: it is read through a
+/// [`RefGuard`], so no more than one byte past the limit is consumed, and
+/// it must end right after the value. Failures map onto `DecodeError`:
+///
+/// * a reader longer than `limit` bytes gives `DecodeError::LimitExceeded`,
+///   the variant bincode uses for its own configured limit;
+/// * bytes after the value give `DecodeError::Io` with a
+///   [`TrailingData`](crate::TrailingData) payload;
+/// * every other error is bincode's own.
+///
+/// # Example
+///
+/// ```
+/// use std::io::Cursor;
+/// use bincode::error::DecodeError;
+///
+/// let config = bincode::config::standard();
+/// let message = bincode::serde::encode_to_vec(("sensor-7", 21.5f32), config).unwrap();
+///
+/// let value: (String, f32) =
+///     reftake::bincode::from_reader_limited(&mut Cursor::new(&message), 64, config).unwrap();
+/// assert_eq!(value, ("sensor-7".to_string(), 21.5));
+///
+/// let err = reftake::bincode::from_reader_limited::<_, (String, f32), _>(
+///     &mut Cursor::new(&message),
+///     8,
+///     config,
+/// )
+/// .unwrap_err();
+/// assert!(matches!(err, DecodeError::LimitExceeded));
+/// ```
+pub fn from_reader_limited<R: Read, T: DeserializeOwned, C: Config>(
+    reader: &mut R,
+    limit: u64,
+    config: C,
+) -> Result<T, DecodeError> {
+    let mut guard = RefGuard::wrap(reader, limit);
+    let value = ::bincode::serde::decode_from_std_read(&mut guard, config).map_err(limit_error)?;
+    guard.expect_end().map_err(|inner| {
+        limit_error(DecodeError::Io {
+            inner,
+            additional: 0,
+        })
+    })?;
+    Ok(value)
+}
+
+/// Turns an I/O error carrying [`LimitExceeded`] into `DecodeError::LimitExceeded`.
+fn limit_error(err: DecodeError) -> DecodeError {
+    match err {
+        DecodeError::Io { inner, .. } if LimitExceeded::from_io(&inner).is_some() => {
+            DecodeError::LimitExceeded
+        }
+        err => err,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TrailingData;
+    use ::bincode::config::standard;
+    use std::io::{Cursor, ErrorKind};
+
+    fn encode(value: impl serde::Serialize) -> Vec<u8> {
+        ::bincode::serde::encode_to_vec(value, standard()).unwrap()
+    }
+
+    #[test]
+    fn test_message_of_exactly_limit_bytes() {
+        let message = encode(vec![1u32, 2, 3]);
+        let mut reader = Cursor::new(&message);
+        let value: Vec<u32> =
+            from_reader_limited(&mut reader, message.len() as u64, standard()).unwrap();
+        assert_eq!(value, [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_long_message_is_limit_exceeded() {
+        let message = encode("a string longer than the limit");
+        let mut reader = Cursor::new(&message);
+        let err = from_reader_limited::<_, String, _>(&mut reader, 10, standard()).unwrap_err();
+        assert!(matches!(err, DecodeError::LimitExceeded));
+        assert_eq!(reader.position(), 11);
+    }
+
+    #[test]
+    fn test_trailing_bytes_are_reported() {
+        let mut message = encode(7u8);
+        message.push(0);
+        let mut reader = Cursor::new(&message);
+        match from_reader_limited::<_, u8, _>(&mut reader, 16, standard()) {
+            Err(DecodeError::Io { inner, .. }) => {
+                assert_eq!(TrailingData::from_io(&inner), Some(&TrailingData));
+            }
+            other => panic!("unexpected result {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_short_message_is_unexpected_eof() {
+        let message = encode(0xdead_beef_u64);
+        let mut reader = Cursor::new(&message[..3]);
+        let err = from_reader_limited::<_, u64, _>(&mut reader, 16, standard()).unwrap_err();
+        match err {
+            DecodeError::Io { inner, .. } => assert_eq!(inner.kind(), ErrorKind::UnexpectedEof),
+            other => panic!("unexpected error {other:?}"),
+        }
+    }
+}
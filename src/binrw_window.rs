@@ -0,0 +1,82 @@
+//! Parsing `binrw` types out of bounded windows.
+
+use std::io::{Read, Seek, SeekFrom};
+
+use binrw::{BinRead, BinResult, meta::ReadEndian};
+
+use crate::SeekTake;
+
+/// Parses a `T` from the `len` bytes of `reader` starting at absolute offset `offset`.
+///
+/// The reader is moved to `offset` and `T` is read through a [`SeekTake`]
+/// over the section, so seeks made by the parser, such as following
+/// `FilePtr`s or restoring the position after a failed branch, are
+/// relative to the section and clamped to it. Reading past its end fails
+/// with an end-of-file error (see `binrw::Error::is_eof`), whatever follows
+/// in the reader. Afterwards the reader is left where the parser stopped.
+///
+/// `T` must know its own byte order (`#[br(big)]`, `#[br(little)]` or a
+/// magic); for other types, or to pass arguments, call
+/// `read_options` on a [`SeekTake`] directly.
+///
+/// # Example
+///
+/// ```
+/// use std::io::Cursor;
+/// use binrw::BinRead;
+///
+/// #[derive(BinRead, Debug)]
+/// #[br(big, magic = b"SEC")]
+/// struct Section {
+///     count: u8,
+///     #[br(count = count)]
+///     values: Vec<u16>,
+/// }
+///
+/// let file = b"junkSEC\x02\x00\x01\x00\x02SEC\x09\x00\x01";
+/// let section: Section = reftake::parse_window(&mut Cursor::new(file), 4, 8).unwrap();
+/// assert_eq!(section.values, [1, 2]);
+///
+/// // The count of the second section points past its end
+/// let err = reftake::parse_window::<Section, _>(&mut Cursor::new(file), 12, 6).unwrap_err();
+/// assert!(err.is_eof());
+/// ```
+pub fn parse_window<T, R>(reader: &mut R, offset: u64, len: u64) -> BinResult<T>
+where
+    T: BinRead + ReadEndian,
+    for<'a> T::Args<'a>: Default,
+    R: Read + Seek,
+{
+    reader.seek(SeekFrom::Start(offset))?;
+    T::read_args(&mut SeekTake::wrap(reader, len), Default::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use binrw::{BinRead, FilePtr8};
+    use std::io::Cursor;
+
+    #[derive(BinRead, Debug)]
+    #[br(little)]
+    struct Header {
+        name: FilePtr8<u8>,
+        size: u8,
+    }
+
+    #[test]
+    fn test_pointers_resolve_within_the_window() {
+        // The pointer 0x01 is relative to the window, not to the file
+        let file = b"\xff\xff\x01\x07\xaa";
+        let header: Header = parse_window(&mut Cursor::new(file), 2, 3).unwrap();
+        assert_eq!(*header.name, 0x07);
+        assert_eq!(header.size, 0x07);
+    }
+
+    #[test]
+    fn test_pointer_out_of_the_window_fails() {
+        let file = b"\x05\x01\x00\x00\x00\x00\x2a";
+        let err = parse_window::<Header, _>(&mut Cursor::new(file), 0, 2).unwrap_err();
+        assert!(err.is_eof());
+    }
+}
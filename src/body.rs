@@ -0,0 +1,217 @@
+//! A `Content-Length` delimited body reader for HTTP/1.1 style connections.
+
+use std::io::{self, BufRead, ErrorKind, Read};
+
+use crate::{LimitExceeded, skip::discard_by_reading};
+
+/// A non-owning reader of a message body with a known length.
+///
+/// Unlike [`RefTake`](crate::RefTake), a connection that closes before
+/// `content_length` bytes have arrived is an error (`ErrorKind::UnexpectedEof`)
+/// rather than a short body. Whatever the handler leaves unread is drained
+/// when the reader is dropped, so the connection stays positioned at the next
+/// message. Call [`BodyReader::finish`] to drain explicitly and see errors.
+///
+/// Created by [`body_reader`] or [`BodyReader::with_max_len`].
+pub struct BodyReader<'a, R: Read> {
+    inner: &'a mut R,
+    content_length: u64,
+    remaining: u64,
+}
+
+/// Returns a reader for a body of exactly `content_length` bytes.
+///
+/// # Example
+///
+/// ```
+/// use std::io::{BufRead, Cursor, Read};
+///
+/// let mut conn = Cursor::new(b"hello worldGET /next HTTP/1.1\r\n");
+/// {
+///     let mut body = reftake::body_reader(&mut conn, 11);
+///     let mut first = [0u8; 5];
+///     body.read_exact(&mut first).unwrap();
+///     // The handler stops here; the rest is drained on drop
+/// }
+/// let mut line = String::new();
+/// conn.read_line(&mut line).unwrap();
+/// assert_eq!(line, "GET /next HTTP/1.1\r\n");
+/// ```
+pub fn body_reader<R: Read>(reader: &mut R, content_length: u64) -> BodyReader<'_, R> {
+    BodyReader {
+        inner: reader,
+        content_length,
+        remaining: content_length,
+    }
+}
+
+impl<'a, R: Read> BodyReader<'a, R> {
+    /// Returns a reader for a body of `content_length` bytes, or a
+    /// [`LimitExceeded`] error if it is longer than `max_len`.
+    ///
+    /// Nothing is read from `reader` when the body is rejected.
+    pub fn with_max_len(reader: &'a mut R, content_length: u64, max_len: u64) -> io::Result<Self> {
+        if content_length > max_len {
+            return Err(LimitExceeded::new(max_len).into());
+        }
+        Ok(body_reader(reader, content_length))
+    }
+
+    /// Returns the declared length of the body.
+    pub fn content_length(&self) -> u64 {
+        self.content_length
+    }
+
+    /// Returns the number of body bytes not read yet.
+    pub fn remaining(&self) -> u64 {
+        self.remaining
+    }
+
+    /// Returns `true` once the whole body has been read.
+    pub fn is_complete(&self) -> bool {
+        self.remaining == 0
+    }
+
+    /// Drains the unread rest of the body, reporting any error.
+    ///
+    /// A failed drain is not tried again when the reader is dropped.
+    pub fn finish(mut self) -> io::Result<()> {
+        let result = self.drain();
+        // Another drain on a dead connection could block a second time
+        self.remaining = 0;
+        result
+    }
+
+    fn drain(&mut self) -> io::Result<()> {
+        if self.remaining == 0 {
+            return Ok(());
+        }
+        trace_event!(debug, remaining = self.remaining, "draining body");
+        if !discard_by_reading(self.inner, &mut self.remaining)? {
+            return Err(truncated());
+        }
+        Ok(())
+    }
+}
+
+fn truncated() -> io::Error {
+    trace_event!(debug, "body ended before its content length");
+    io::Error::new(
+        ErrorKind::UnexpectedEof,
+        "connection closed before end of body",
+    )
+}
+
+impl<R: Read> Read for BodyReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        if self.remaining == 0 || buf.is_empty() {
+            return Ok(0);
+        }
+        let max = buf
+            .len()
+            .min(self.remaining.min(usize::MAX as u64) as usize);
+        let n = self.inner.read(&mut buf[..max])?;
+        if n == 0 {
+            return Err(truncated());
+        }
+        self.remaining -= n as u64;
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for BodyReader<'_, R> {
+    fn fill_buf(&mut self) -> Result<&[u8], io::Error> {
+        if self.remaining == 0 {
+            return Ok(&[]);
+        }
+        let remaining = self.remaining;
+        let buf = self.inner.fill_buf()?;
+        if buf.is_empty() {
+            return Err(truncated());
+        }
+        let cap = buf.len().min(remaining.min(usize::MAX as u64) as usize);
+        Ok(&buf[..cap])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        let amt = amt.min(self.remaining.min(usize::MAX as u64) as usize);
+        self.remaining -= amt as u64;
+        self.inner.consume(amt);
+    }
+}
+
+/// Drains the unread rest of the body, ignoring errors.
+impl<R: Read> Drop for BodyReader<'_, R> {
+    fn drop(&mut self) {
+        let _ = self.drain();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufReader, Cursor};
+
+    #[test]
+    fn test_truncated_body_is_an_error() {
+        let mut conn = Cursor::new(b"abc");
+        let mut body = body_reader(&mut conn, 5);
+        let err = body.read_to_end(&mut Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_finish_drains_and_reports() {
+        let mut conn = Cursor::new(b"0123456789next");
+        let body = body_reader(&mut conn, 10);
+        assert_eq!(body.content_length(), 10);
+        body.finish().unwrap();
+        assert_eq!(conn.position(), 10);
+
+        let mut conn = Cursor::new(b"0123");
+        let err = body_reader(&mut conn, 10).finish().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_failed_finish_is_not_drained_again() {
+        struct Dead {
+            reads: usize,
+        }
+
+        impl Read for Dead {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                self.reads += 1;
+                Err(io::Error::from(ErrorKind::ConnectionReset))
+            }
+        }
+
+        let mut conn = Dead { reads: 0 };
+        let err = body_reader(&mut conn, 10).finish().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ConnectionReset);
+        assert_eq!(conn.reads, 1);
+    }
+
+    #[test]
+    fn test_max_len() {
+        let mut conn = Cursor::new(b"0123456789");
+        let err = BodyReader::with_max_len(&mut conn, 10, 4).err().unwrap();
+        assert_eq!(LimitExceeded::from_io(&err), Some(&LimitExceeded::new(4)));
+        assert_eq!(conn.position(), 0);
+    }
+
+    #[test]
+    fn test_bufread_body_drained_on_drop() {
+        let mut conn = BufReader::with_capacity(4, Cursor::new(b"line\nmore bodyNEXT"));
+        {
+            let mut body = body_reader(&mut conn, 14);
+            let mut line = String::new();
+            body.read_line(&mut line).unwrap();
+            assert_eq!(line, "line\n");
+            assert!(!body.is_complete());
+        }
+        let mut rest = String::new();
+        conn.read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, "NEXT");
+    }
+}
@@ -0,0 +1,292 @@
+//! Byte order mark detection and removal at the start of a borrowed reader.
+
+use std::io::{self, ErrorKind, Read};
+
+/// Size of the buffer UTF-16 text is read into when transcoding.
+const RAW_CAPACITY: usize = 8 * 1024;
+
+/// A byte order mark found by [`RefStripBom`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Bom {
+    /// `EF BB BF`, UTF-8.
+    Utf8,
+    /// `FF FE`, little-endian UTF-16.
+    Utf16Le,
+    /// `FE FF`, big-endian UTF-16.
+    Utf16Be,
+}
+
+impl Bom {
+    /// Returns the bytes of the mark.
+    pub fn as_bytes(self) -> &'static [u8] {
+        match self {
+            Self::Utf8 => b"\xef\xbb\xbf",
+            Self::Utf16Le => b"\xff\xfe",
+            Self::Utf16Be => b"\xfe\xff",
+        }
+    }
+
+    fn detect(bytes: &[u8]) -> Option<Self> {
+        [Self::Utf8, Self::Utf16Le, Self::Utf16Be]
+            .into_iter()
+            .find(|bom| bytes.starts_with(bom.as_bytes()))
+    }
+}
+
+/// A non-owning adapter removing the byte order mark at the start of a reader.
+///
+/// Text file windows often start with a BOM that string parsers choke on.
+/// The first read looks at up to three bytes to find one, which
+/// [`RefStripBom::bom`] reports, and the mark is never returned. With
+/// [`RefStripBom::transcode_utf16`], text behind a UTF-16 mark is decoded
+/// and returned as UTF-8, so it can go to `read_to_string` like any other;
+/// an unpaired surrogate then fails with `ErrorKind::InvalidData`, and text
+/// that ends inside a character with `ErrorKind::UnexpectedEof`.
+///
+/// Over a [`RefTake`](crate::RefTake), the limit counts the raw bytes,
+/// mark included.
+pub struct RefStripBom<'a, R> {
+    inner: &'a mut R,
+    transcode: bool,
+    /// `None` until the start has been looked at.
+    bom: Option<Option<Bom>>,
+    /// Bytes ready to be returned: those read past a missing or UTF-8 mark, or transcoded text.
+    out: Vec<u8>,
+    pos: usize,
+    /// UTF-16 bytes read but not decoded yet.
+    raw: Vec<u8>,
+}
+
+impl<'a, R> RefStripBom<'a, R> {
+    /// Creates a new `RefStripBom` over the given reader reference.
+    pub fn wrap(inner: &'a mut R) -> Self {
+        Self {
+            inner,
+            transcode: false,
+            bom: None,
+            out: Vec::new(),
+            pos: 0,
+            raw: Vec::new(),
+        }
+    }
+
+    /// Decodes text behind a UTF-16 mark into UTF-8 instead of returning it as it is.
+    pub fn transcode_utf16(mut self) -> Self {
+        self.transcode = true;
+        self
+    }
+}
+
+impl<R: Read> RefStripBom<'_, R> {
+    /// Returns the mark found at the start, reading up to three bytes to look for it if needed.
+    pub fn bom(&mut self) -> io::Result<Option<Bom>> {
+        if let Some(bom) = self.bom {
+            return Ok(bom);
+        }
+        let mut start = [0u8; 3];
+        let mut len = 0;
+        while len < start.len() {
+            match self.inner.read(&mut start[len..]) {
+                Ok(0) => break,
+                Ok(n) => len += n,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        let bom = Bom::detect(&start[..len]);
+        let rest = &start[bom.map_or(0, |bom| bom.as_bytes().len())..len];
+        if self.decodes(bom) {
+            self.raw.extend_from_slice(rest);
+        } else {
+            self.out.extend_from_slice(rest);
+        }
+        self.bom = Some(bom);
+        Ok(bom)
+    }
+
+    fn decodes(&self, bom: Option<Bom>) -> bool {
+        self.transcode && matches!(bom, Some(Bom::Utf16Le | Bom::Utf16Be))
+    }
+
+    /// Reads and decodes the next UTF-16 bytes into `out`, returning `false` at the end.
+    fn decode_more(&mut self, big_endian: bool) -> io::Result<bool> {
+        let kept = self.raw.len();
+        self.raw.resize(kept + RAW_CAPACITY, 0);
+        let read = loop {
+            match self.inner.read(&mut self.raw[kept..]) {
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                result => break result,
+            }
+        };
+        let n = match read {
+            Ok(n) => n,
+            Err(e) => {
+                self.raw.truncate(kept);
+                return Err(e);
+            }
+        };
+        self.raw.truncate(kept + n);
+        if n == 0 {
+            if self.raw.is_empty() {
+                return Ok(false);
+            }
+            return Err(io::Error::new(
+                ErrorKind::UnexpectedEof,
+                "stream ended inside a UTF-16 character",
+            ));
+        }
+
+        let mut units: Vec<u16> = self
+            .raw
+            .chunks_exact(2)
+            .map(|pair| {
+                let pair = [pair[0], pair[1]];
+                if big_endian {
+                    u16::from_be_bytes(pair)
+                } else {
+                    u16::from_le_bytes(pair)
+                }
+            })
+            .collect();
+        // A high surrogate waits for the unit after it
+        let mut decoded = units.len() * 2;
+        if units
+            .last()
+            .is_some_and(|unit| (0xd800..0xdc00).contains(unit))
+        {
+            units.pop();
+            decoded -= 2;
+        }
+        self.out.clear();
+        self.pos = 0;
+        for c in char::decode_utf16(units) {
+            let c = c.map_err(|e| {
+                io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "unpaired surrogate {:#06x} in UTF-16 text",
+                        e.unpaired_surrogate()
+                    ),
+                )
+            })?;
+            self.out
+                .extend_from_slice(c.encode_utf8(&mut [0u8; 4]).as_bytes());
+        }
+        self.raw.drain(..decoded);
+        Ok(true)
+    }
+}
+
+impl<R: Read> Read for RefStripBom<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        let bom = self.bom()?;
+        loop {
+            if self.pos < self.out.len() {
+                let n = (&self.out[self.pos..]).read(buf)?;
+                self.pos += n;
+                return Ok(n);
+            }
+            if !self.decodes(bom) {
+                return self.inner.read(buf);
+            }
+            if buf.is_empty() || !self.decode_more(bom == Some(Bom::Utf16Be))? {
+                return Ok(0);
+            }
+        }
+    }
+}
+
+/// Extension trait to provide a `strip_bom_ref` method on all `Read` types.
+pub trait RefStripBomExt {
+    /// Wraps the reader in a `RefStripBom`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::Read;
+    /// use reftake::{Bom, RefStripBomExt, RefTakeExt};
+    ///
+    /// let mut file = &b"\xff\xfeh\0i\0!\0 trailing"[..];
+    /// let mut take = file.take_ref(8);
+    /// let mut text = take.strip_bom_ref().transcode_utf16();
+    /// let mut out = String::new();
+    /// text.read_to_string(&mut out).unwrap();
+    /// assert_eq!(out, "hi!");
+    /// assert_eq!(text.bom().unwrap(), Some(Bom::Utf16Le));
+    /// ```
+    fn strip_bom_ref(&mut self) -> RefStripBom<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: Read> RefStripBomExt for T {
+    fn strip_bom_ref(&mut self) -> RefStripBom<'_, Self> {
+        RefStripBom::wrap(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns one byte per read.
+    struct Bytewise<'a>(&'a [u8]);
+
+    impl Read for Bytewise<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = buf.len().min(1);
+            self.0.read(&mut buf[..n])
+        }
+    }
+
+    fn read_all(data: &[u8], transcode: bool) -> (Option<Bom>, io::Result<String>) {
+        let mut source = Bytewise(data);
+        let mut reader = source.strip_bom_ref();
+        if transcode {
+            reader = reader.transcode_utf16();
+        }
+        let mut out = String::new();
+        let result = reader.read_to_string(&mut out).map(|_| out);
+        (reader.bom().unwrap(), result)
+    }
+
+    #[test]
+    fn test_marks_are_detected_and_stripped() {
+        assert_eq!(read_all(b"\xef\xbb\xbfabc", false).1.unwrap(), "abc");
+        let (bom, text) = read_all(b"ab", false);
+        assert_eq!((bom, text.unwrap()), (None, "ab".to_string()));
+        let (bom, text) = read_all(b"", true);
+        assert_eq!((bom, text.unwrap()), (None, String::new()));
+        // Without transcoding, UTF-16 text is passed on as it is
+        let (bom, text) = read_all(b"\xfe\xff\xd8\x3d", false);
+        assert_eq!(bom, Some(Bom::Utf16Be));
+        assert_eq!(text.unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_utf16_transcoding() {
+        let text = "a\u{e9}\u{1f600}z";
+        let mut le = b"\xff\xfe".to_vec();
+        let mut be = b"\xfe\xff".to_vec();
+        for unit in text.encode_utf16() {
+            le.extend_from_slice(&unit.to_le_bytes());
+            be.extend_from_slice(&unit.to_be_bytes());
+        }
+        let (bom, decoded) = read_all(&le, true);
+        assert_eq!(
+            (bom, decoded.unwrap()),
+            (Some(Bom::Utf16Le), text.to_string())
+        );
+        let (bom, decoded) = read_all(&be, true);
+        assert_eq!(
+            (bom, decoded.unwrap()),
+            (Some(Bom::Utf16Be), text.to_string())
+        );
+
+        let err = read_all(&le[..le.len() - 1], true).1.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+        let err = read_all(b"\xff\xfe\x00\xdca\x00", true).1.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,328 @@
+//! An adapter that reads from a borrowed `BufRead` up to a multi-byte boundary.
+
+use std::io::{self, BufRead, Read};
+
+/// A non-owning adapter that returns EOF once a byte pattern is reached.
+///
+/// Boundaries that straddle two `fill_buf` chunks of the inner reader are
+/// handled by moving the partial match into a small internal buffer, which
+/// never holds more than `boundary.len() - 1` bytes. Bytes past the boundary
+/// are never pulled from the inner reader, so the parent sees everything after
+/// it. The boundary itself is consumed once found.
+pub struct RefTakeUntilBoundary<'a, R> {
+    inner: &'a mut R,
+    boundary: Vec<u8>,
+    /// Bytes taken from `inner` that form a prefix of `boundary`, unless a
+    /// part of them was already returned by `fill_buf`.
+    carry: Vec<u8>,
+    /// Length of the slice last returned by `fill_buf`.
+    avail: usize,
+    from_carry: bool,
+    found: bool,
+}
+
+enum Step {
+    Eof,
+    EmitCarry(usize),
+    EmitInner(usize),
+    Carry(usize),
+    Found(usize),
+}
+
+impl<'a, R> RefTakeUntilBoundary<'a, R> {
+    /// Creates a new `RefTakeUntilBoundary` that stops at `boundary`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `boundary` is empty.
+    pub fn wrap(inner: &'a mut R, boundary: &[u8]) -> Self {
+        assert!(!boundary.is_empty(), "boundary must not be empty");
+        Self {
+            inner,
+            boundary: boundary.to_vec(),
+            carry: Vec::with_capacity(boundary.len() - 1),
+            avail: 0,
+            from_carry: false,
+            found: false,
+        }
+    }
+
+    /// Returns `true` once the boundary has been reached, as opposed to EOF
+    /// of the inner reader.
+    pub fn found_boundary(&self) -> bool {
+        self.found
+    }
+
+    /// Returns the inner reader. Bytes held in the carry buffer are lost, so
+    /// this is only lossless once the boundary has been found.
+    pub(crate) fn into_inner(self) -> &'a mut R {
+        self.inner
+    }
+}
+
+/// Returns the start of the longest tail of `buf` that is a proper prefix of
+/// `boundary`, or `buf.len()` if there is none.
+fn partial_match_start(buf: &[u8], boundary: &[u8]) -> usize {
+    let first = buf.len().saturating_sub(boundary.len() - 1);
+    (first..buf.len())
+        .find(|&q| boundary.starts_with(&buf[q..]))
+        .unwrap_or(buf.len())
+}
+
+impl<R: BufRead> RefTakeUntilBoundary<'_, R> {
+    fn next_step(&mut self) -> io::Result<Step> {
+        let buf = self.inner.fill_buf()?;
+        let boundary = &self.boundary[..];
+
+        if !self.carry.is_empty() {
+            // A carry partly drained by the caller may no longer start a boundary
+            let keep_from = (0..self.carry.len())
+                .find(|&i| boundary.starts_with(&self.carry[i..]))
+                .unwrap_or(self.carry.len());
+            if keep_from > 0 {
+                return Ok(Step::EmitCarry(keep_from));
+            }
+            let matched = self.carry.len();
+            let need = boundary.len() - matched;
+            let take = need.min(buf.len());
+            return Ok(if buf.is_empty() {
+                Step::EmitCarry(matched)
+            } else if buf[..take] != boundary[matched..matched + take] {
+                // Keep the longest tail of the carry that may still start a boundary
+                let keep_from = (1..matched)
+                    .find(|&i| boundary.starts_with(&self.carry[i..]))
+                    .unwrap_or(matched);
+                Step::EmitCarry(keep_from)
+            } else if take == need {
+                Step::Found(need)
+            } else {
+                Step::Carry(take)
+            });
+        }
+
+        if buf.is_empty() {
+            return Ok(Step::Eof);
+        }
+        Ok(
+            match buf.windows(boundary.len()).position(|w| w == boundary) {
+                Some(0) => Step::Found(boundary.len()),
+                Some(pos) => Step::EmitInner(pos),
+                None => match partial_match_start(buf, boundary) {
+                    0 => Step::Carry(buf.len()),
+                    pos => Step::EmitInner(pos),
+                },
+            },
+        )
+    }
+}
+
+impl<R: BufRead> Read for RefTakeUntilBoundary<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        let available = self.fill_buf()?;
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for RefTakeUntilBoundary<'_, R> {
+    fn fill_buf(&mut self) -> Result<&[u8], io::Error> {
+        self.avail = 0;
+        while !self.found {
+            match self.next_step()? {
+                Step::Eof => break,
+                Step::EmitCarry(n) => {
+                    self.avail = n;
+                    self.from_carry = true;
+                    return Ok(&self.carry[..n]);
+                }
+                Step::EmitInner(n) => {
+                    self.avail = n;
+                    self.from_carry = false;
+                    let buf = self.inner.fill_buf()?;
+                    return Ok(&buf[..n]);
+                }
+                Step::Carry(n) => {
+                    let buf = self.inner.fill_buf()?;
+                    self.carry.extend_from_slice(&buf[..n]);
+                    self.inner.consume(n);
+                }
+                Step::Found(n) => {
+                    self.inner.consume(n);
+                    self.carry.clear();
+                    self.found = true;
+                }
+            }
+        }
+        Ok(&[])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        let amt = amt.min(self.avail);
+        self.avail -= amt;
+        if self.from_carry {
+            self.carry.drain(..amt);
+        } else {
+            self.inner.consume(amt);
+        }
+    }
+}
+
+/// Extension trait to provide a `take_until_boundary_ref` method on all `BufRead` types.
+pub trait RefTakeUntilBoundaryExt {
+    /// Wraps the reader in a `RefTakeUntilBoundary` that stops at `boundary`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Cursor, Read};
+    /// use reftake::RefTakeUntilBoundaryExt;
+    ///
+    /// let mut cursor = Cursor::new(b"part one\r\n--XYZ\r\npart two");
+    /// let mut part = String::new();
+    /// cursor
+    ///     .take_until_boundary_ref(b"\r\n--XYZ")
+    ///     .read_to_string(&mut part)
+    ///     .unwrap();
+    /// assert_eq!(part, "part one");
+    /// ```
+    fn take_until_boundary_ref(&mut self, boundary: &[u8]) -> RefTakeUntilBoundary<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: BufRead> RefTakeUntilBoundaryExt for T {
+    fn take_until_boundary_ref(&mut self, boundary: &[u8]) -> RefTakeUntilBoundary<'_, Self> {
+        RefTakeUntilBoundary::wrap(self, boundary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufReader, Cursor};
+
+    fn split_with_capacity(
+        data: &[u8],
+        boundary: &[u8],
+        capacity: usize,
+    ) -> (Vec<u8>, Vec<u8>, bool) {
+        let mut reader = BufReader::with_capacity(capacity, Cursor::new(data));
+        let mut part = Vec::new();
+        let found = {
+            let mut until = reader.take_until_boundary_ref(boundary);
+            until.read_to_end(&mut part).unwrap();
+            until.found_boundary()
+        };
+        let mut rest = Vec::new();
+        reader.read_to_end(&mut rest).unwrap();
+        (part, rest, found)
+    }
+
+    #[test]
+    fn test_boundary_in_single_chunk() {
+        let (part, rest, found) = split_with_capacity(b"abc--sep--def", b"--sep--", 64);
+        assert_eq!(part, b"abc");
+        assert_eq!(rest, b"def");
+        assert!(found);
+    }
+
+    #[test]
+    fn test_boundary_straddles_every_chunk_size() {
+        let data = b"hello--sep--world";
+        for capacity in 1..data.len() {
+            let (part, rest, found) = split_with_capacity(data, b"--sep--", capacity);
+            assert_eq!(part, b"hello", "capacity {capacity}");
+            assert_eq!(rest, b"world", "capacity {capacity}");
+            assert!(found);
+        }
+    }
+
+    #[test]
+    fn test_false_partial_matches() {
+        for capacity in 1..8 {
+            let (part, rest, found) = split_with_capacity(b"aabaaab!", b"aab!", capacity);
+            assert_eq!(part, b"aaba", "capacity {capacity}");
+            assert_eq!(rest, b"");
+            assert!(found);
+        }
+    }
+
+    /// Reads `data` through a `BufReader` of `capacity` in reads of at most `read_len` bytes.
+    fn split_in_small_reads(
+        data: &[u8],
+        boundary: &[u8],
+        capacity: usize,
+        read_len: usize,
+    ) -> (Vec<u8>, Vec<u8>, bool) {
+        let mut reader = BufReader::with_capacity(capacity, Cursor::new(data));
+        let mut part = Vec::new();
+        let found = {
+            let mut until = reader.take_until_boundary_ref(boundary);
+            let mut buf = vec![0u8; read_len];
+            loop {
+                let n = until.read(&mut buf).unwrap();
+                if n == 0 {
+                    break;
+                }
+                part.extend_from_slice(&buf[..n]);
+            }
+            until.found_boundary()
+        };
+        let mut rest = Vec::new();
+        reader.read_to_end(&mut rest).unwrap();
+        (part, rest, found)
+    }
+
+    #[test]
+    fn test_partly_read_carry_is_not_a_boundary() {
+        let (part, rest, found) = split_in_small_reads(b"abbacba", b"aba", 2, 1);
+        assert_eq!(part, b"abbacba");
+        assert_eq!(rest, b"");
+        assert!(!found);
+    }
+
+    #[test]
+    fn test_small_reads_match_a_naive_split() {
+        // Every string of up to 7 letters of a two-letter alphabet, split
+        // with every buffer capacity and read size
+        let boundaries: [&[u8]; 4] = [b"a", b"ab", b"aba", b"aab"];
+        for len in 0..=7 {
+            for bits in 0..1u32 << len {
+                let data: Vec<u8> = (0..len)
+                    .map(|i| if bits >> i & 1 == 0 { b'a' } else { b'b' })
+                    .collect();
+                for boundary in boundaries {
+                    let expected = match data.windows(boundary.len()).position(|w| w == boundary) {
+                        Some(pos) => (
+                            data[..pos].to_vec(),
+                            data[pos + boundary.len()..].to_vec(),
+                            true,
+                        ),
+                        None => (data.clone(), Vec::new(), false),
+                    };
+                    for capacity in 1..=5 {
+                        for read_len in 1..=3 {
+                            assert_eq!(
+                                split_in_small_reads(&data, boundary, capacity, read_len),
+                                expected,
+                                "{data:?} {boundary:?} {capacity} {read_len}"
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_eof_with_partial_boundary() {
+        for capacity in 1..6 {
+            let (part, rest, found) = split_with_capacity(b"data--se", b"--sep--", capacity);
+            assert_eq!(part, b"data--se");
+            assert_eq!(rest, b"");
+            assert!(!found);
+        }
+    }
+}
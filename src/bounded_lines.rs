@@ -0,0 +1,280 @@
+//! A `lines()` iterator with a cap on the length of a single line.
+
+use std::io::{self, BufRead, ErrorKind};
+
+use crate::{LimitExceeded, Limited};
+
+/// What [`BoundedLines`] does with a line longer than its cap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum LongLine {
+    /// Yield a [`LimitExceeded`] error and stop iterating.
+    #[default]
+    Error,
+    /// Yield the first `max_line_len` bytes of the line and discard the rest of it.
+    Truncate,
+}
+
+/// How the last line returned by [`BoundedLines`] ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum LineEnd {
+    /// A `\n` or `\r\n` terminator.
+    Newline,
+    /// The end of the stream, without a terminator.
+    Eof,
+    /// The byte limit of the reader, which may have cut the line short.
+    Limit,
+}
+
+/// An iterator over the lines of a borrowed `BufRead`, each at most `max_line_len` bytes long.
+///
+/// Lines are returned without their `\n` or `\r\n` terminator, like
+/// `BufRead::lines()`, but no more than `max_line_len` bytes (plus the
+/// terminator) are ever buffered for a single line. Truncated lines are cut
+/// at a character boundary. Lines that are not valid UTF-8 yield an error of
+/// kind `InvalidData`.
+pub struct BoundedLines<'a, R> {
+    inner: &'a mut R,
+    max_line_len: usize,
+    long_line: LongLine,
+    done: bool,
+    /// Whether the last line returned had a terminator.
+    terminated: Option<bool>,
+}
+
+impl<'a, R> BoundedLines<'a, R> {
+    /// Creates a new `BoundedLines` that fails on lines longer than `max_line_len` bytes.
+    pub const fn wrap(inner: &'a mut R, max_line_len: usize) -> Self {
+        Self::with_behavior(inner, max_line_len, LongLine::Error)
+    }
+
+    /// Creates a new `BoundedLines` with the given handling of overlong lines.
+    pub const fn with_behavior(inner: &'a mut R, max_line_len: usize, long_line: LongLine) -> Self {
+        Self {
+            inner,
+            max_line_len,
+            long_line,
+            done: false,
+            terminated: None,
+        }
+    }
+}
+
+impl<R: Limited> BoundedLines<'_, R> {
+    /// Returns how the last line returned ended, or `None` before the first one.
+    ///
+    /// `BufRead::lines()` returns the text before a byte limit as if it
+    /// were a whole line; this tells a final line cut short by the limit,
+    /// [`LineEnd::Limit`], from one the stream ended without a newline,
+    /// [`LineEnd::Eof`], so it can be treated as an error. An unterminated
+    /// line that ends exactly where both the stream and the limit do is
+    /// reported as `Limit`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use reftake::{BoundedLinesExt, LineEnd, RefTakeExt};
+    ///
+    /// let mut cursor = Cursor::new(b"complete\ncut in the mid");
+    /// let mut take = cursor.take_ref(15);
+    /// let mut lines = take.bounded_lines(64);
+    /// assert_eq!(lines.next().unwrap().unwrap(), "complete");
+    /// assert_eq!(lines.line_end(), Some(LineEnd::Newline));
+    /// assert_eq!(lines.next().unwrap().unwrap(), "cut in");
+    /// assert_eq!(lines.line_end(), Some(LineEnd::Limit));
+    /// ```
+    pub fn line_end(&self) -> Option<LineEnd> {
+        Some(match self.terminated? {
+            true => LineEnd::Newline,
+            false if self.inner.remaining() == Some(0) => LineEnd::Limit,
+            false => LineEnd::Eof,
+        })
+    }
+}
+
+impl<R: BufRead> BoundedLines<'_, R> {
+    fn next_line(&mut self) -> io::Result<Option<String>> {
+        // One extra byte leaves room for the `\r` of a `\r\n` terminator
+        let cap = self.max_line_len.saturating_add(1);
+        let mut line = Vec::new();
+        let mut read_any = false;
+        let mut overflow = false;
+        let mut terminated = false;
+
+        loop {
+            let buf = match self.inner.fill_buf() {
+                Ok(buf) => buf,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            if buf.is_empty() {
+                break;
+            }
+            read_any = true;
+
+            let chunk;
+            (chunk, terminated) = match buf.iter().position(|&b| b == b'\n') {
+                Some(i) => (&buf[..i], true),
+                None => (buf, false),
+            };
+            let take = chunk.len().min(cap - line.len());
+            line.extend_from_slice(&chunk[..take]);
+            overflow |= take < chunk.len();
+            let used = if terminated {
+                chunk.len() + 1
+            } else {
+                chunk.len()
+            };
+
+            if overflow && self.long_line == LongLine::Error {
+                self.inner.consume(take);
+                return Err(LimitExceeded::new(self.max_line_len as u64).into());
+            }
+            self.inner.consume(used);
+            if terminated {
+                break;
+            }
+        }
+
+        if !read_any {
+            return Ok(None);
+        }
+        self.terminated = Some(terminated);
+        if line.last() == Some(&b'\r') {
+            line.pop();
+        }
+        if line.len() > self.max_line_len {
+            if self.long_line == LongLine::Error {
+                return Err(LimitExceeded::new(self.max_line_len as u64).into());
+            }
+            line.truncate(self.max_line_len);
+            overflow = true;
+        }
+
+        match String::from_utf8(line) {
+            Ok(line) => Ok(Some(line)),
+            // A multi-byte character may have been cut in half by truncation
+            Err(e) if overflow && e.utf8_error().error_len().is_none() => {
+                let valid = e.utf8_error().valid_up_to();
+                let mut line = e.into_bytes();
+                line.truncate(valid);
+                Ok(Some(
+                    String::from_utf8(line).expect("prefix is valid UTF-8"),
+                ))
+            }
+            Err(e) => Err(io::Error::new(ErrorKind::InvalidData, e)),
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for BoundedLines<'_, R> {
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = self.next_line().transpose();
+        if matches!(result, None | Some(Err(_))) {
+            self.done = true;
+        }
+        result
+    }
+}
+
+/// Extension trait to provide a `bounded_lines` method on all `BufRead` types.
+pub trait BoundedLinesExt {
+    /// Returns an iterator over lines that fails on lines longer than `max_line_len` bytes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use reftake::{BoundedLinesExt, LimitExceeded, RefTakeExt};
+    ///
+    /// let mut cursor = Cursor::new(b"short\nthis line is far too long\n");
+    /// let mut take = cursor.take_ref(1024);
+    /// let mut lines = take.bounded_lines(8);
+    ///
+    /// assert_eq!(lines.next().unwrap().unwrap(), "short");
+    /// let err = lines.next().unwrap().unwrap_err();
+    /// assert!(LimitExceeded::from_io(&err).is_some());
+    /// assert!(lines.next().is_none());
+    /// ```
+    fn bounded_lines(&mut self, max_line_len: usize) -> BoundedLines<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: BufRead> BoundedLinesExt for T {
+    fn bounded_lines(&mut self, max_line_len: usize) -> BoundedLines<'_, Self> {
+        BoundedLines::wrap(self, max_line_len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RefTakeExt;
+    use std::io::{BufReader, Cursor, Read};
+
+    #[test]
+    fn test_lines_within_cap() {
+        let mut reader = BufReader::with_capacity(2, Cursor::new(b"abc\r\ndefg\nhi"));
+        let lines: Vec<String> = reader.bounded_lines(4).map(Result::unwrap).collect();
+        assert_eq!(lines, vec!["abc", "defg", "hi"]);
+    }
+
+    #[test]
+    fn test_long_line_error_stops_early() {
+        let mut reader = Cursor::new(b"abcdefghij\nrest");
+        {
+            let mut lines = reader.bounded_lines(3);
+            let err = lines.next().unwrap().unwrap_err();
+            assert_eq!(LimitExceeded::from_io(&err), Some(&LimitExceeded::new(3)));
+            assert!(lines.next().is_none());
+        }
+        let mut rest = String::new();
+        reader.read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, "efghij\nrest");
+    }
+
+    #[test]
+    fn test_line_end() {
+        let mut reader = BufReader::with_capacity(2, Cursor::new(b"ab\ncd\r\nef"));
+        let mut take = reader.take_ref(100);
+        let mut lines = take.bounded_lines(4);
+        let mut ends = Vec::new();
+        while let Some(line) = lines.next() {
+            ends.push((line.unwrap(), lines.line_end().unwrap()));
+        }
+        assert_eq!(ends[1], ("cd".to_string(), LineEnd::Newline));
+        assert_eq!(ends[2], ("ef".to_string(), LineEnd::Eof));
+
+        let mut cursor = Cursor::new(b"ab\ncd");
+        let mut take = cursor.take_ref(5);
+        let mut lines = take.bounded_lines(4);
+        assert_eq!(lines.line_end(), None);
+        lines.next();
+        lines.next();
+        assert_eq!(lines.line_end(), Some(LineEnd::Limit));
+    }
+
+    #[test]
+    fn test_long_line_truncate() {
+        let mut reader = BufReader::with_capacity(3, Cursor::new("abcdefg\naéé\nok\n".as_bytes()));
+        let lines: Vec<String> = BoundedLines::with_behavior(&mut reader, 4, LongLine::Truncate)
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(lines, vec!["abcd", "aé", "ok"]);
+    }
+
+    #[test]
+    fn test_invalid_utf8() {
+        let mut reader = Cursor::new(b"\xff\n");
+        let err = reader.bounded_lines(4).next().unwrap().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,283 @@
+//! Nested byte budgets shared by the readers of a tenant, connection or request.
+
+use std::{
+    cmp,
+    io::{self, Read},
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use crate::BudgetExhausted;
+
+struct Level {
+    name: Arc<str>,
+    limit: u64,
+    remaining: AtomicU64,
+    parent: Option<Arc<Level>>,
+}
+
+impl Level {
+    /// Iterates over this level and its ancestors, innermost first.
+    fn chain(self: &Arc<Self>) -> impl Iterator<Item = &Arc<Level>> {
+        std::iter::successors(Some(self), |level| level.parent.as_ref())
+    }
+}
+
+/// A handle to a byte budget, which may be nested in a parent budget.
+///
+/// A tracker is created for the outermost quota, say a tenant, and hands
+/// out [`BudgetTracker::child`] budgets for its connections, which hand out
+/// budgets for their requests, and so on. Bytes read through a
+/// [`BudgetReader`] count against its budget and every ancestor at once,
+/// so a request stops at whichever of its own, its connection's or its
+/// tenant's limits runs out first, and the error says which.
+///
+/// Handles are cheap to clone, and clones share the budget. The budgets are
+/// atomic counters, so readers on different threads may draw from them
+/// concurrently.
+#[derive(Clone)]
+pub struct BudgetTracker {
+    level: Arc<Level>,
+}
+
+impl BudgetTracker {
+    /// Creates a new top-level budget of `limit` bytes, reported as `name` in errors.
+    pub fn new(name: impl Into<Arc<str>>, limit: u64) -> Self {
+        Self {
+            level: Arc::new(Level {
+                name: name.into(),
+                limit,
+                remaining: AtomicU64::new(limit),
+                parent: None,
+            }),
+        }
+    }
+
+    /// Creates a budget of `limit` bytes nested in this one.
+    ///
+    /// Reads through the child count against this budget too, so the
+    /// child can never deliver more than what is left here, whatever its
+    /// own limit.
+    pub fn child(&self, name: impl Into<Arc<str>>, limit: u64) -> Self {
+        Self {
+            level: Arc::new(Level {
+                name: name.into(),
+                limit,
+                remaining: AtomicU64::new(limit),
+                parent: Some(Arc::clone(&self.level)),
+            }),
+        }
+    }
+
+    /// Returns the name of this budget.
+    pub fn name(&self) -> &str {
+        &self.level.name
+    }
+
+    /// Returns the limit this budget was created with.
+    pub fn limit(&self) -> u64 {
+        self.level.limit
+    }
+
+    /// Returns the bytes left in this budget, not counting its ancestors.
+    pub fn remaining(&self) -> u64 {
+        self.level.remaining.load(Ordering::Acquire)
+    }
+
+    /// Returns the bytes that may still be read through this budget, the least left at any level.
+    pub fn available(&self) -> u64 {
+        self.level
+            .chain()
+            .map(|level| level.remaining.load(Ordering::Acquire))
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// Takes up to `max` bytes from this budget and all its ancestors.
+    ///
+    /// Returns how much was taken, which is `0` only when some level is used up.
+    fn reserve(&self, max: u64) -> u64 {
+        loop {
+            let amount = cmp::min(max, self.available());
+            if amount == 0 {
+                return 0;
+            }
+            let mut taken = Vec::new();
+            for level in self.level.chain() {
+                let ok = level
+                    .remaining
+                    .fetch_update(Ordering::AcqRel, Ordering::Acquire, |r| {
+                        r.checked_sub(amount)
+                    })
+                    .is_ok();
+                if !ok {
+                    break;
+                }
+                taken.push(level);
+            }
+            if taken.len() == self.level.chain().count() {
+                return amount;
+            }
+            // Another reader got there first; give back what was taken and retry
+            for level in taken {
+                level.remaining.fetch_add(amount, Ordering::AcqRel);
+            }
+        }
+    }
+
+    /// Gives back `amount` bytes to this budget and all its ancestors.
+    fn refund(&self, amount: u64) {
+        if amount > 0 {
+            for level in self.level.chain() {
+                level.remaining.fetch_add(amount, Ordering::AcqRel);
+            }
+        }
+    }
+
+    /// Returns the error for the innermost level that is used up.
+    fn exhausted(&self) -> io::Error {
+        let level = self
+            .level
+            .chain()
+            .find(|level| level.remaining.load(Ordering::Acquire) == 0)
+            .unwrap_or(&self.level);
+        BudgetExhausted::new(Arc::clone(&level.name), level.limit).into()
+    }
+}
+
+/// A non-owning adapter reading through a [`BudgetTracker`].
+///
+/// Like [`RefGuard`](crate::RefGuard), running out is an error rather than
+/// EOF: once the budget, or any budget above it, is used up, the next read
+/// probes the inner reader and fails with a [`BudgetExhausted`] error
+/// naming the level if more data is available. Bytes are taken from the
+/// budgets before each read and what the read did not use is given back.
+pub struct BudgetReader<'a, R> {
+    inner: &'a mut R,
+    budget: BudgetTracker,
+}
+
+impl<'a, R> BudgetReader<'a, R> {
+    /// Creates a new `BudgetReader` drawing from `budget`.
+    pub const fn wrap(inner: &'a mut R, budget: BudgetTracker) -> Self {
+        Self { inner, budget }
+    }
+
+    /// Returns the budget the reader draws from.
+    pub fn budget(&self) -> &BudgetTracker {
+        &self.budget
+    }
+}
+
+impl<R: Read> Read for BudgetReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let reserved = self.budget.reserve(buf.len() as u64);
+        if reserved == 0 {
+            let mut probe = [0u8; 1];
+            return match self.inner.read(&mut probe)? {
+                0 => Ok(0),
+                _ => {
+                    let err = self.budget.exhausted();
+                    trace_event!(warn, "byte budget exhausted");
+                    Err(err)
+                }
+            };
+        }
+        let result = self.inner.read(&mut buf[..reserved as usize]);
+        let used = *result.as_ref().unwrap_or(&0) as u64;
+        self.budget.refund(reserved - used);
+        result
+    }
+}
+
+/// Extension trait to provide a `budget_ref` method on all `Read` types.
+pub trait BudgetReaderExt {
+    /// Wraps the reader in a `BudgetReader` drawing from `budget`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Cursor, Read};
+    /// use reftake::{BudgetExhausted, BudgetReaderExt, BudgetTracker};
+    ///
+    /// let tenant = BudgetTracker::new("tenant", 10);
+    /// let connection = tenant.child("connection", 100);
+    ///
+    /// let mut first = Cursor::new(b"0123456");
+    /// let mut buf = Vec::new();
+    /// first.budget_ref(&connection.child("request 1", 8)).read_to_end(&mut buf).unwrap();
+    ///
+    /// let mut second = Cursor::new(b"0123456");
+    /// let mut request = second.budget_ref(&connection.child("request 2", 8));
+    /// let err = request.read_to_end(&mut buf).unwrap_err();
+    /// assert_eq!(BudgetExhausted::from_io(&err).unwrap().level(), "tenant");
+    /// assert_eq!(tenant.remaining(), 0);
+    /// ```
+    fn budget_ref(&mut self, budget: &BudgetTracker) -> BudgetReader<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: Read> BudgetReaderExt for T {
+    fn budget_ref(&mut self, budget: &BudgetTracker) -> BudgetReader<'_, Self> {
+        BudgetReader::wrap(self, budget.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{io::Cursor, thread};
+
+    #[test]
+    fn test_innermost_exhausted_level_is_reported() {
+        let tenant = BudgetTracker::new("tenant", 100);
+        let request = tenant.child("connection", 50).child("request", 5);
+        let mut reader = Cursor::new(b"0123456789");
+        let mut buf = Vec::new();
+        let err = reader
+            .budget_ref(&request)
+            .read_to_end(&mut buf)
+            .unwrap_err();
+        assert_eq!(buf, b"01234");
+        let exhausted = BudgetExhausted::from_io(&err).unwrap();
+        assert_eq!((exhausted.level(), exhausted.limit()), ("request", 5));
+        assert_eq!(tenant.remaining(), 95);
+        assert_eq!(request.available(), 0);
+
+        let mut exact = Cursor::new(b"abc");
+        let budget = tenant.child("exact", 3);
+        exact.budget_ref(&budget).read_to_end(&mut buf).unwrap();
+        assert_eq!(tenant.remaining(), 92);
+    }
+
+    #[test]
+    fn test_concurrent_readers_never_overdraw() {
+        let tenant = BudgetTracker::new("tenant", 1000);
+        let total: u64 = thread::scope(|s| {
+            let handles: Vec<_> = (0..8)
+                .map(|i| {
+                    let budget = tenant.child(format!("conn {i}"), 400);
+                    s.spawn(move || {
+                        let mut reader = Cursor::new(vec![0u8; 400]);
+                        let mut reader = reader.budget_ref(&budget);
+                        let mut buf = [0u8; 7];
+                        let mut read = 0;
+                        while let Ok(n @ 1..) = reader.read(&mut buf) {
+                            read += n as u64;
+                        }
+                        read
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).sum()
+        });
+        assert_eq!(total, 1000);
+        assert_eq!(tenant.remaining(), 0);
+    }
+}
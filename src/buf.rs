@@ -0,0 +1,147 @@
+//! `bytes::Buf` support for [`RefTake`].
+
+use core::cmp;
+#[cfg(feature = "std")]
+use std::io::{self, BufRead};
+
+use bytes::Buf;
+
+use crate::RefTake;
+
+/// Implements `bytes::Buf` with a byte limit.
+///
+/// `remaining()` and `chunk()` are capped at the remaining limit, so a
+/// parser built on `Buf` sees the window as the whole buffer and the rest
+/// of the inner buffer stays in place for the caller.
+impl<B: Buf> Buf for RefTake<'_, B> {
+    fn remaining(&self) -> usize {
+        cmp::min(self.inner.remaining() as u64, self.limit) as usize
+    }
+
+    fn chunk(&self) -> &[u8] {
+        let chunk = self.inner.chunk();
+        let cap = cmp::min(chunk.len() as u64, self.limit) as usize;
+        &chunk[..cap]
+    }
+
+    fn advance(&mut self, cnt: usize) {
+        assert!(
+            cnt as u64 <= self.limit,
+            "cannot advance past the byte limit"
+        );
+        self.inner.advance(cnt);
+        self.limit -= cnt as u64;
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: BufRead> RefTake<'_, R> {
+    /// Calls `f` with the buffered part of the window as a `bytes::Buf`, consuming what it advanced over.
+    ///
+    /// The view is the slice returned by `fill_buf()`, so nothing is copied;
+    /// it holds at most the remaining limit and, like `fill_buf()`, is only
+    /// empty at the end of the window. Bytes left in the view are left
+    /// unread for the next call.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use bytes::Buf;
+    /// use reftake::RefTakeExt;
+    ///
+    /// let mut cursor = Cursor::new(b"\x00\x2aok, and more");
+    /// let mut take = cursor.take_ref(4);
+    /// let code = take.with_buf(|buf| buf.get_u16()).unwrap();
+    /// assert_eq!(code, 42);
+    /// assert_eq!(take.with_buf(|buf| buf.remaining()).unwrap(), 2);
+    /// ```
+    pub fn with_buf<T>(&mut self, f: impl FnOnce(&mut &[u8]) -> T) -> io::Result<T> {
+        let buf = self.fill_buf()?;
+        let mut view = buf;
+        let out = f(&mut view);
+        let used = buf.len() - view.len();
+        self.consume(used);
+        Ok(out)
+    }
+}
+
+/// Extension trait to provide a `take_ref_buf` method on all `bytes::Buf` types.
+pub trait BufRefTakeExt {
+    /// Wraps the buffer in a `RefTake`, allowing limited access via a mutable reference.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bytes::{Buf, Bytes};
+    /// use reftake::BufRefTakeExt;
+    ///
+    /// let mut buf = Bytes::from_static(b"\x03abcdef");
+    /// let len = buf.get_u8() as usize;
+    /// let field = buf.take_ref_buf(len as u64).copy_to_bytes(len);
+    /// assert_eq!(field, "abc");
+    /// assert_eq!(buf, "def");
+    /// ```
+    fn take_ref_buf(&mut self, limit: u64) -> RefTake<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: Buf> BufRefTakeExt for T {
+    fn take_ref_buf(&mut self, limit: u64) -> RefTake<'_, Self> {
+        RefTake::wrap(self, limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunks_are_capped_across_segments() {
+        let mut buf = (&b"abc"[..]).chain(&b"defgh"[..]);
+        let mut take = buf.take_ref_buf(5);
+
+        assert_eq!(take.remaining(), 5);
+        assert_eq!(take.chunk(), b"abc");
+        take.advance(3);
+        assert_eq!(take.chunk(), b"de");
+        take.advance(2);
+        assert!(!take.has_remaining());
+        assert_eq!(take.chunk(), b"");
+        assert_eq!(buf.remaining(), 3);
+    }
+
+    #[test]
+    fn test_remaining_is_capped_by_inner() {
+        let mut buf: &[u8] = b"xy";
+        let take = buf.take_ref_buf(10);
+        assert_eq!(take.remaining(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot advance past the byte limit")]
+    fn test_advance_past_limit_panics() {
+        let mut buf: &[u8] = b"abcdef";
+        buf.take_ref_buf(2).advance(3);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_with_buf_consumes_only_what_was_advanced() {
+        use crate::RefTakeExt;
+        use std::io::{BufReader, Read};
+
+        let mut reader = BufReader::with_capacity(4, &b"0123456789"[..]);
+        let mut take = reader.take_ref(6);
+        let first = take.with_buf(|buf| buf.copy_to_bytes(3)).unwrap();
+        assert_eq!(first, "012");
+        let left = take.with_buf(|buf| buf.remaining()).unwrap();
+        assert_eq!(left, 1);
+        assert_eq!(take.current_limit(), 3);
+
+        let mut rest = String::new();
+        take.read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, "345");
+    }
+}
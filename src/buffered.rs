@@ -0,0 +1,131 @@
+//! Looking at the bytes a reader has already buffered, without reading more.
+
+use std::{
+    cmp,
+    io::{BufReader, Cursor},
+};
+
+use crate::{LimitedBufReader, RefPrefetch, RefTake, RefTakeAligned, RefTakeBuffered};
+
+/// A reader whose already buffered bytes can be looked at without a read.
+///
+/// `BufRead::fill_buf` reads from the source when the buffer is empty,
+/// which may block; `buffer` never does. It is what lets
+/// [`RefTake::buffer`] sniff a magic number or a message type cheaply,
+/// falling back to a real read only when too little is buffered.
+pub trait Buffered {
+    /// Returns the bytes buffered but not consumed yet, which may be empty.
+    fn buffer(&self) -> &[u8];
+}
+
+impl<T: Buffered + ?Sized> Buffered for &T {
+    fn buffer(&self) -> &[u8] {
+        (**self).buffer()
+    }
+}
+
+impl<T: Buffered + ?Sized> Buffered for &mut T {
+    fn buffer(&self) -> &[u8] {
+        (**self).buffer()
+    }
+}
+
+impl Buffered for &[u8] {
+    fn buffer(&self) -> &[u8] {
+        self
+    }
+}
+
+impl<T: AsRef<[u8]>> Buffered for Cursor<T> {
+    fn buffer(&self) -> &[u8] {
+        let data = self.get_ref().as_ref();
+        let pos = self.position().min(data.len() as u64) as usize;
+        &data[pos..]
+    }
+}
+
+impl<R: ?Sized> Buffered for BufReader<R> {
+    fn buffer(&self) -> &[u8] {
+        BufReader::buffer(self)
+    }
+}
+
+impl<R> Buffered for LimitedBufReader<'_, R> {
+    fn buffer(&self) -> &[u8] {
+        LimitedBufReader::buffer(self)
+    }
+}
+
+impl<R> Buffered for RefTakeBuffered<'_, R> {
+    fn buffer(&self) -> &[u8] {
+        RefTakeBuffered::buffer(self)
+    }
+}
+
+impl<R> Buffered for RefTakeAligned<'_, R> {
+    fn buffer(&self) -> &[u8] {
+        RefTakeAligned::buffer(self)
+    }
+}
+
+impl Buffered for RefPrefetch {
+    fn buffer(&self) -> &[u8] {
+        RefPrefetch::buffer(self)
+    }
+}
+
+impl<R: Buffered> RefTake<'_, R> {
+    /// Returns the bytes of the window the inner reader has already buffered.
+    ///
+    /// Like `BufReader::buffer`, this never reads, so it may return fewer
+    /// bytes than the window holds, or none at all; it never returns more
+    /// than the remaining limit.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{BufRead, BufReader};
+    /// use reftake::RefTakeExt;
+    ///
+    /// let mut reader = BufReader::new(&b"\x1f\x8b\x08 gzip data"[..]);
+    /// let mut take = reader.take_ref(2);
+    /// assert!(take.buffer().is_empty());
+    /// take.fill_buf().unwrap();
+    /// assert_eq!(take.buffer(), b"\x1f\x8b");
+    /// ```
+    pub fn buffer(&self) -> &[u8] {
+        let buf = self.inner.buffer();
+        &buf[..cmp::min(buf.len() as u64, self.current_limit()) as usize]
+    }
+}
+
+impl<R: Buffered> Buffered for RefTake<'_, R> {
+    fn buffer(&self) -> &[u8] {
+        RefTake::buffer(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RefTakeExt;
+    use std::io::{BufRead, Read};
+
+    #[test]
+    fn test_buffer_is_capped_and_never_reads() {
+        let mut cursor = Cursor::new(b"magic and more".to_vec());
+        cursor.set_position(1);
+        let take = cursor.take_ref(4);
+        assert_eq!(take.buffer(), b"agic");
+
+        let mut reader = BufReader::with_capacity(3, &b"abcdef"[..]);
+        let mut take = reader.take_ref(5);
+        assert_eq!(take.buffer(), b"");
+        let mut byte = [0u8; 1];
+        take.read_exact(&mut byte).unwrap();
+        assert_eq!(take.buffer(), b"bc");
+        let mut inner = take.take_ref(1);
+        assert_eq!(inner.buffer(), b"b");
+        assert_eq!(inner.fill_buf().unwrap(), b"b");
+    }
+}
@@ -0,0 +1,225 @@
+//! A `Stream` of `Bytes` chunks over a borrowed reader.
+
+use std::{
+    io::{self, ErrorKind, Read},
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+use bytes::{Bytes, BytesMut};
+use futures_core::Stream;
+use tokio::io::{AsyncRead, ReadBuf};
+
+/// A stream of owned [`Bytes`] chunks read from a borrowed reader, for body
+/// types and channels that take streams rather than readers.
+///
+/// Each chunk holds what a single read returned, at most `chunk_size`
+/// bytes, so data is passed on as soon as it arrives. Wrap the reader in a
+/// [`RefTake`](crate::RefTake) first to bound the stream to a window.
+///
+/// Over a tokio `AsyncRead` it is a [`Stream`]; over a blocking `Read` it
+/// is an `Iterator`, which `futures::stream::iter` turns into a stream
+/// wherever blocking in `poll_next` is acceptable. Either way it ends at
+/// EOF or after the first error.
+pub struct ByteStream<'a, R> {
+    inner: &'a mut R,
+    chunk_size: usize,
+    pub(crate) done: bool,
+}
+
+impl<'a, R> ByteStream<'a, R> {
+    /// Creates a new `ByteStream` over `inner` yielding chunks of at most `chunk_size` bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is zero.
+    pub const fn wrap(inner: &'a mut R, chunk_size: usize) -> Self {
+        assert!(chunk_size > 0, "chunk size must be non-zero");
+        Self {
+            inner,
+            chunk_size,
+            done: false,
+        }
+    }
+
+    /// Returns the maximum chunk size.
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    /// Turns the outcome of a read into the next item, ending the stream at EOF or on error.
+    fn finish(&mut self, chunk: BytesMut, result: io::Result<()>) -> Option<io::Result<Bytes>> {
+        match result {
+            Ok(()) if chunk.is_empty() => {
+                self.done = true;
+                None
+            }
+            Ok(()) => Some(Ok(chunk.freeze())),
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+impl<R: Read> Iterator for ByteStream<'_, R> {
+    type Item = io::Result<Bytes>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let mut chunk = BytesMut::zeroed(self.chunk_size);
+        let result = loop {
+            match self.inner.read(&mut chunk) {
+                Ok(n) => {
+                    chunk.truncate(n);
+                    break Ok(());
+                }
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => break Err(e),
+            }
+        };
+        self.finish(chunk, result)
+    }
+}
+
+impl<R: AsyncRead + Unpin> Stream for ByteStream<'_, R> {
+    type Item = io::Result<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.done {
+            return Poll::Ready(None);
+        }
+        let mut chunk = BytesMut::zeroed(self.chunk_size);
+        let mut buf = ReadBuf::new(&mut chunk);
+        let result = ready!(Pin::new(&mut *self.inner).poll_read(cx, &mut buf));
+        let n = buf.filled().len();
+        chunk.truncate(n);
+        Poll::Ready(self.finish(chunk, result))
+    }
+}
+
+/// Extension trait to provide a `byte_stream_ref` method on all `Read` types.
+pub trait ByteStreamExt {
+    /// Returns an iterator over `Bytes` chunks of at most `chunk_size` bytes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use futures::{StreamExt, executor::block_on, stream};
+    /// use reftake::{ByteStreamExt, RefTakeExt};
+    ///
+    /// let mut cursor = Cursor::new(b"0123456789");
+    /// let mut take = cursor.take_ref(7);
+    /// let chunks: Vec<_> = block_on(stream::iter(take.byte_stream_ref(3)).collect());
+    /// let chunks: Vec<_> = chunks.into_iter().map(Result::unwrap).collect();
+    /// assert_eq!(chunks, vec!["012", "345", "6"]);
+    /// assert_eq!(cursor.position(), 7);
+    /// ```
+    fn byte_stream_ref(&mut self, chunk_size: usize) -> ByteStream<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: Read> ByteStreamExt for T {
+    fn byte_stream_ref(&mut self, chunk_size: usize) -> ByteStream<'_, Self> {
+        ByteStream::wrap(self, chunk_size)
+    }
+}
+
+/// Extension trait to provide a `byte_stream_ref_async` method on all `tokio::io::AsyncRead` types.
+pub trait AsyncByteStreamExt {
+    /// Returns a `Stream` of `Bytes` chunks of at most `chunk_size` bytes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use futures::TryStreamExt;
+    /// use reftake::{AsyncByteStreamExt, AsyncRefTakeExt};
+    /// use tokio::io::BufReader;
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let mut reader = BufReader::new(&b"hello world"[..]);
+    /// let mut body = reader.take_ref_async(5);
+    /// let chunks: Vec<_> = body.byte_stream_ref_async(1024).try_collect().await.unwrap();
+    /// assert_eq!(chunks, vec!["hello"]);
+    /// # });
+    /// ```
+    fn byte_stream_ref_async(&mut self, chunk_size: usize) -> ByteStream<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: AsyncRead + Unpin> AsyncByteStreamExt for T {
+    fn byte_stream_ref_async(&mut self, chunk_size: usize) -> ByteStream<'_, Self> {
+        ByteStream::wrap(self, chunk_size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AsyncRefTakeExt, RefTakeExt};
+    use std::io::Cursor;
+
+    /// Fails with `ErrorKind::Other` after the first read.
+    struct Flaky(bool);
+
+    impl AsyncRead for Flaky {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            if self.0 {
+                return Poll::Ready(Err(io::Error::other("gone")));
+            }
+            self.0 = true;
+            buf.put_slice(b"ok");
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[test]
+    fn test_blocking_chunks_stop_at_window() {
+        let mut cursor = Cursor::new(b"abcdefgh".to_vec());
+        let mut take = cursor.take_ref(5);
+        let mut chunks = take.byte_stream_ref(2);
+        assert_eq!(chunks.chunk_size(), 2);
+        let all: Vec<Bytes> = chunks.by_ref().map(Result::unwrap).collect();
+        assert_eq!(all, vec!["ab", "cd", "e"]);
+        assert!(chunks.next().is_none());
+        assert_eq!(cursor.position(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_async_chunks_stop_at_window() {
+        use futures::StreamExt;
+
+        let mut reader = tokio::io::BufReader::new(&b"0123456789"[..]);
+        let mut take = reader.take_ref_async(8);
+        let all: Vec<Bytes> = take
+            .byte_stream_ref_async(3)
+            .map(Result::unwrap)
+            .collect()
+            .await;
+        assert_eq!(all, vec!["012", "345", "67"]);
+    }
+
+    #[tokio::test]
+    async fn test_stream_ends_after_error() {
+        use futures::StreamExt;
+
+        let mut reader = Flaky(false);
+        let mut chunks = reader.byte_stream_ref_async(8);
+        assert_eq!(chunks.next().await.unwrap().unwrap(), "ok");
+        assert_eq!(
+            chunks.next().await.unwrap().unwrap_err().to_string(),
+            "gone"
+        );
+        assert!(chunks.next().await.is_none());
+    }
+}
@@ -0,0 +1,83 @@
+//! A byte iterator over a [`RefTake`] window that knows its upper bound.
+
+use std::io::{self, ErrorKind, Read};
+
+use crate::RefTake;
+
+/// An iterator over the bytes of a [`RefTake`] window.
+///
+/// Behaves like `Read::bytes`, but its `size_hint` reports the remaining
+/// limit as the upper bound, since the window can never yield more.
+///
+/// Created by [`RefTake::bytes_limited`].
+pub struct LimitedBytes<'s, 'a, R> {
+    take: &'s mut RefTake<'a, R>,
+}
+
+impl<R: Read> Iterator for LimitedBytes<'_, '_, R> {
+    type Item = io::Result<u8>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut byte = 0;
+        loop {
+            return match self.take.read(std::slice::from_mut(&mut byte)) {
+                Ok(0) => None,
+                Ok(_) => Some(Ok(byte)),
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => Some(Err(e)),
+            };
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, usize::try_from(self.take.current_limit()).ok())
+    }
+}
+
+impl<'a, R: Read> RefTake<'a, R> {
+    /// Returns an iterator over the bytes of the window, bounded by the remaining limit.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use reftake::RefTakeExt;
+    ///
+    /// let mut cursor = Cursor::new(b"hello world");
+    /// let mut take = cursor.take_ref(5);
+    /// let bytes = take.bytes_limited();
+    /// assert_eq!(bytes.size_hint(), (0, Some(5)));
+    ///
+    /// let hello: Vec<u8> = bytes.map(Result::unwrap).collect();
+    /// assert_eq!(hello, b"hello");
+    /// ```
+    pub fn bytes_limited(&mut self) -> LimitedBytes<'_, 'a, R> {
+        LimitedBytes { take: self }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::RefTakeExt;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_size_hint_tracks_limit() {
+        let mut reader = Cursor::new(b"abcdef");
+        let mut take = reader.take_ref(4);
+        let mut bytes = take.bytes_limited();
+
+        assert_eq!(bytes.next().unwrap().unwrap(), b'a');
+        assert_eq!(bytes.size_hint(), (0, Some(3)));
+        assert_eq!(bytes.count(), 3);
+        assert_eq!(take.current_limit(), 0);
+    }
+
+    #[test]
+    fn test_inner_eof_before_limit() {
+        let mut reader = Cursor::new(b"ab");
+        let mut take = reader.take_ref(10);
+        let collected: Vec<u8> = take.bytes_limited().map(Result::unwrap).collect();
+        assert_eq!(collected, b"ab");
+    }
+}
@@ -0,0 +1,104 @@
+//! Bounded CBOR deserialization with `ciborium`.
+
+use std::io::{self, Read};
+
+use ciborium::de::Error;
+use serde::de::DeserializeOwned;
+
+use crate::RefGuard;
+
+/// Deserializes a `T` from a reader holding a CBOR item of at most `limit` bytes.
+///
+/// The whole reader is the item: it is read through a [`RefGuard`], so
+/// no more than one byte past the limit is consumed, and it must end right
+/// after the value. Both failures are reported in `ciborium`'s own
+/// `Error::Io` variant, which carries the `io::Error` they are wrapped in:
+///
+/// * a reader longer than `limit` bytes carries a
+///   [`LimitExceeded`](crate::LimitExceeded) payload;
+/// * bytes after the value carry a [`TrailingData`](crate::TrailingData)
+///   payload.
+///
+/// # Example
+///
+/// ```
+/// use std::collections::BTreeMap;
+/// use std::io::Cursor;
+/// use ciborium::de::Error;
+/// use reftake::LimitExceeded;
+///
+/// let mut message = Vec::new();
+/// ciborium::into_writer(&BTreeMap::from([("temp", 21), ("hum", 40)]), &mut message).unwrap();
+///
+/// let map: BTreeMap<String, u32> =
+///     reftake::cbor::from_reader_limited(&mut Cursor::new(&message), 64).unwrap();
+/// assert_eq!(map["hum"], 40);
+///
+/// match reftake::cbor::from_reader_limited::<_, BTreeMap<String, u32>>(&mut Cursor::new(&message), 8) {
+///     Err(Error::Io(e)) => assert!(LimitExceeded::from_io(&e).is_some()),
+///     other => panic!("unexpected result {other:?}"),
+/// }
+/// ```
+pub fn from_reader_limited<R: Read, T: DeserializeOwned>(
+    reader: &mut R,
+    limit: u64,
+) -> Result<T, Error<io::Error>> {
+    let mut guard = RefGuard::wrap(reader, limit);
+    let value = ciborium::from_reader(&mut guard)?;
+    guard.expect_end().map_err(Error::Io)?;
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LimitExceeded, TrailingData};
+    use std::io::{Cursor, ErrorKind};
+
+    fn encode(value: impl serde::Serialize) -> Vec<u8> {
+        let mut out = Vec::new();
+        ciborium::into_writer(&value, &mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn test_item_of_exactly_limit_bytes() {
+        let message = encode(("id", 7u8));
+        let mut reader = Cursor::new(&message);
+        let value: (String, u8) = from_reader_limited(&mut reader, message.len() as u64).unwrap();
+        assert_eq!(value, ("id".to_string(), 7));
+    }
+
+    #[test]
+    fn test_long_item_is_limit_exceeded() {
+        let message = encode(vec![0u8; 64]);
+        let mut reader = Cursor::new(&message);
+        match from_reader_limited::<_, Vec<u8>>(&mut reader, 16) {
+            Err(Error::Io(e)) => {
+                assert_eq!(LimitExceeded::from_io(&e), Some(&LimitExceeded::new(16)));
+            }
+            other => panic!("unexpected result {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_trailing_item_is_reported() {
+        let mut message = encode(1u8);
+        message.extend(encode(2u8));
+        let mut reader = Cursor::new(&message);
+        match from_reader_limited::<_, u8>(&mut reader, 16) {
+            Err(Error::Io(e)) => assert_eq!(TrailingData::from_io(&e), Some(&TrailingData)),
+            other => panic!("unexpected result {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_truncated_item_is_unexpected_eof() {
+        let message = encode("truncated");
+        let mut reader = Cursor::new(&message[..4]);
+        match from_reader_limited::<_, String>(&mut reader, 16) {
+            Err(Error::Io(e)) => assert_eq!(e.kind(), ErrorKind::UnexpectedEof),
+            other => panic!("unexpected result {other:?}"),
+        }
+    }
+}
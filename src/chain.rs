@@ -0,0 +1,145 @@
+//! A non-owning version of `std::io::Chain`.
+
+#[cfg(feature = "std")]
+use std::io::{self, BufRead, Read};
+
+/// A non-owning adapter that reads from two borrowed readers in sequence.
+///
+/// Reads are served from `first` until it reports EOF, then from `second`.
+/// Unlike `std::io::Chain`, neither reader is moved into the adapter, so both
+/// remain usable once the chain is dropped.
+pub struct RefChain<'a, A, B> {
+    // Only read by the trait implementations, which a bare core build has none of
+    #[cfg_attr(not(any(feature = "std", feature = "embedded-io")), allow(dead_code))]
+    pub(crate) first: &'a mut A,
+    #[cfg_attr(not(any(feature = "std", feature = "embedded-io")), allow(dead_code))]
+    pub(crate) second: &'a mut B,
+    pub(crate) done_first: bool,
+}
+
+impl<'a, A, B> RefChain<'a, A, B> {
+    /// Creates a new `RefChain` that reads `first` to EOF, then continues with `second`.
+    pub const fn wrap(first: &'a mut A, second: &'a mut B) -> Self {
+        Self {
+            first,
+            second,
+            done_first: false,
+        }
+    }
+
+    /// Returns `true` once the first reader has reported EOF.
+    pub fn first_done(&self) -> bool {
+        self.done_first
+    }
+}
+
+#[cfg(feature = "std")]
+impl<A: Read, B: Read> Read for RefChain<'_, A, B> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        if !self.done_first {
+            match self.first.read(buf)? {
+                0 if !buf.is_empty() => self.done_first = true,
+                n => return Ok(n),
+            }
+        }
+        self.second.read(buf)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<A: BufRead, B: BufRead> BufRead for RefChain<'_, A, B> {
+    fn fill_buf(&mut self) -> Result<&[u8], io::Error> {
+        if !self.done_first {
+            match self.first.fill_buf()? {
+                [] => self.done_first = true,
+                buf => return Ok(buf),
+            }
+        }
+        self.second.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        if !self.done_first {
+            self.first.consume(amt)
+        } else {
+            self.second.consume(amt)
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+/// Extension trait to provide a `chain_ref` method on all `Read` types.
+pub trait RefChainExt {
+    /// Chains this reader with `next`, without taking ownership of either.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Cursor, Read};
+    /// use reftake::{RefChainExt, RefTakeExt};
+    ///
+    /// let mut header = Cursor::new(b"GET ");
+    /// let mut socket = Cursor::new(b"/index.html HTTP/1.1");
+    ///
+    /// let mut chain = header.chain_ref(&mut socket);
+    /// let mut line = String::new();
+    /// chain.take_ref(15).read_to_string(&mut line).unwrap();
+    /// assert_eq!(line, "GET /index.html");
+    /// ```
+    fn chain_ref<'a, B: Read>(&'a mut self, next: &'a mut B) -> RefChain<'a, Self, B>
+    where
+        Self: Sized;
+}
+
+#[cfg(feature = "std")]
+impl<T: Read> RefChainExt for T {
+    fn chain_ref<'a, B: Read>(&'a mut self, next: &'a mut B) -> RefChain<'a, Self, B> {
+        RefChain::wrap(self, next)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::RefTakeExt;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_chain_reads_both() {
+        let mut a = Cursor::new(b"hello ");
+        let mut b = Cursor::new(b"world");
+        let mut chain = a.chain_ref(&mut b);
+
+        let mut buf = String::new();
+        chain.read_to_string(&mut buf).unwrap();
+        assert_eq!(buf, "hello world");
+        assert!(chain.first_done());
+    }
+
+    #[test]
+    fn test_chain_leaves_readers_usable() {
+        let mut a = Cursor::new(b"ab");
+        let mut b = Cursor::new(b"cdef");
+        {
+            let mut chain = a.chain_ref(&mut b);
+            let mut take = chain.take_ref(3);
+            let mut buf = Vec::new();
+            take.read_to_end(&mut buf).unwrap();
+            assert_eq!(buf, b"abc");
+        }
+
+        let mut rest = String::new();
+        b.read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, "def");
+    }
+
+    #[test]
+    fn test_chain_bufread() {
+        let mut a = Cursor::new(b"line one\nli");
+        let mut b = Cursor::new(b"ne two\n");
+        let mut chain = a.chain_ref(&mut b);
+
+        let lines: Vec<String> = chain.by_ref().lines().map(Result::unwrap).collect();
+        assert_eq!(lines, vec!["line one", "line two"]);
+    }
+}
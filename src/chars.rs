@@ -0,0 +1,155 @@
+//! An incremental UTF-8 decoding iterator over a borrowed `BufRead`.
+
+use std::io::{self, BufRead, ErrorKind};
+
+use crate::InvalidUtf8;
+
+/// Returns the encoded length of a UTF-8 sequence starting with `lead`, or `None` if it can't start one.
+fn utf8_width(lead: u8) -> Option<usize> {
+    match lead {
+        0x00..=0x7f => Some(1),
+        0xc2..=0xdf => Some(2),
+        0xe0..=0xef => Some(3),
+        0xf0..=0xf4 => Some(4),
+        _ => None,
+    }
+}
+
+/// An iterator over the UTF-8 characters of a borrowed `BufRead`.
+///
+/// Characters split across `fill_buf` calls are reassembled, so the window
+/// of a [`RefTake`](crate::RefTake) can be decoded however it is buffered.
+///
+/// An invalid sequence yields an [`InvalidUtf8`] error and decoding resumes
+/// right after it, so a caller may skip bad input. A reader that ends in the
+/// middle of a character yields `ErrorKind::UnexpectedEof`.
+pub struct Chars<'a, R> {
+    inner: &'a mut R,
+}
+
+impl<'a, R> Chars<'a, R> {
+    /// Creates a new `Chars` decoding from the given reader reference.
+    pub const fn wrap(inner: &'a mut R) -> Self {
+        Self { inner }
+    }
+}
+
+impl<R: BufRead> Chars<'_, R> {
+    fn next_char(&mut self) -> io::Result<Option<char>> {
+        let mut bytes = [0u8; 4];
+        let mut len = 0;
+        let mut width = 1;
+
+        while len < width {
+            let buf = match self.inner.fill_buf() {
+                Ok(buf) => buf,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            let Some(&byte) = buf.first() else {
+                if len == 0 {
+                    return Ok(None);
+                }
+                return Err(io::Error::new(
+                    ErrorKind::UnexpectedEof,
+                    "stream ended inside a UTF-8 sequence",
+                ));
+            };
+
+            if len == 0 {
+                self.inner.consume(1);
+                width = utf8_width(byte).ok_or_else(|| InvalidUtf8::new(&[byte]))?;
+            } else if byte & 0xc0 == 0x80 {
+                self.inner.consume(1);
+            } else {
+                // Leave the unexpected byte for the next character
+                return Err(InvalidUtf8::new(&bytes[..len]).into());
+            }
+            bytes[len] = byte;
+            len += 1;
+        }
+
+        // Rejects overlong encodings and surrogates the width check lets through
+        match std::str::from_utf8(&bytes[..len]) {
+            Ok(s) => Ok(s.chars().next()),
+            Err(_) => Err(InvalidUtf8::new(&bytes[..len]).into()),
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for Chars<'_, R> {
+    type Item = io::Result<char>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_char().transpose()
+    }
+}
+
+/// Extension trait to provide a `chars_ref` method on all `BufRead` types.
+pub trait CharsExt {
+    /// Returns an iterator decoding the reader as UTF-8, one character at a time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{BufReader, Cursor};
+    /// use reftake::{CharsExt, RefTakeExt};
+    ///
+    /// let mut reader = BufReader::with_capacity(1, Cursor::new("héllo wörld".as_bytes()));
+    /// let mut take = reader.take_ref(6);
+    /// let word: String = take.chars_ref().map(Result::unwrap).collect();
+    /// assert_eq!(word, "héllo");
+    /// ```
+    fn chars_ref(&mut self) -> Chars<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: BufRead> CharsExt for T {
+    fn chars_ref(&mut self) -> Chars<'_, Self> {
+        Chars::wrap(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RefTakeExt;
+    use std::io::{BufReader, Cursor};
+
+    #[test]
+    fn test_chars_split_across_buffers() {
+        let mut reader = BufReader::with_capacity(1, Cursor::new("aé€😀".as_bytes()));
+        let chars: Vec<char> = reader.chars_ref().map(Result::unwrap).collect();
+        assert_eq!(chars, vec!['a', 'é', '€', '😀']);
+    }
+
+    #[test]
+    fn test_invalid_sequence_then_resume() {
+        let mut reader = Cursor::new(b"a\xc3(b\xffc");
+        let mut chars = reader.chars_ref();
+        assert_eq!(chars.next().unwrap().unwrap(), 'a');
+        let err = chars.next().unwrap().unwrap_err();
+        assert_eq!(InvalidUtf8::from_io(&err).unwrap().bytes(), b"\xc3");
+        assert_eq!(chars.next().unwrap().unwrap(), '(');
+        assert_eq!(chars.next().unwrap().unwrap(), 'b');
+        assert!(InvalidUtf8::from_io(&chars.next().unwrap().unwrap_err()).is_some());
+        assert_eq!(chars.next().unwrap().unwrap(), 'c');
+        assert!(chars.next().is_none());
+    }
+
+    #[test]
+    fn test_overlong_encoding_rejected() {
+        let mut reader = Cursor::new(b"\xe0\x80\xaf");
+        let err = reader.chars_ref().next().unwrap().unwrap_err();
+        assert!(InvalidUtf8::from_io(&err).is_some());
+    }
+
+    #[test]
+    fn test_window_ends_mid_char() {
+        let mut reader = Cursor::new("€".as_bytes());
+        let mut take = reader.take_ref(2);
+        let err = take.chars_ref().next().unwrap().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    }
+}
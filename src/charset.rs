@@ -0,0 +1,430 @@
+//! Decoding text in a legacy charset from a borrowed reader into UTF-8.
+
+use std::io::{self, ErrorKind, Read};
+
+use crate::LimitExceeded;
+
+/// Size of the buffer the encoded text is read into.
+const RAW_CAPACITY: usize = 8 * 1024;
+
+/// The characters of Windows-1252 bytes `0x80` to `0x9f`, the C1 control
+/// character of the same value for the five bytes the charset leaves
+/// undefined, as in the WHATWG encoding standard.
+const WINDOWS_1252_C1: [char; 32] = [
+    '\u{20ac}', '\u{81}', '\u{201a}', '\u{192}', '\u{201e}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{2c6}', '\u{2030}', '\u{160}', '\u{2039}', '\u{152}', '\u{8d}', '\u{17d}', '\u{8f}',
+    '\u{90}', '\u{2018}', '\u{2019}', '\u{201c}', '\u{201d}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{2dc}', '\u{2122}', '\u{161}', '\u{203a}', '\u{153}', '\u{9d}', '\u{17e}', '\u{178}',
+];
+
+/// An incremental decoder of text into UTF-8, as used by [`RefDecodeText`].
+///
+/// The input comes in arbitrary pieces, so a multi-byte sequence may be
+/// split between two calls: the decoder keeps the start of it until the
+/// rest arrives. With the `encoding_rs` feature, an `encoding_rs::Decoder`
+/// is a `TextDecoder` for every charset of the WHATWG encoding standard,
+/// Shift-JIS, EUC-KR or GBK among them.
+pub trait TextDecoder {
+    /// Decodes `src` and appends the text to `dst`.
+    ///
+    /// `last` is set on the final call, with the input ended, where a
+    /// sequence still incomplete is an error.
+    fn decode(&mut self, src: &[u8], dst: &mut Vec<u8>, last: bool) -> io::Result<()>;
+}
+
+/// A charset decoded by the built-in [`CharsetDecoder`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Charset {
+    /// ISO-8859-1, every byte being the character of the same value.
+    Latin1,
+    /// Windows-1252, Latin-1 with printable characters in `0x80` to `0x9f`.
+    Windows1252,
+    /// Little-endian UTF-16, without a byte order mark.
+    Utf16Le,
+    /// Big-endian UTF-16, without a byte order mark.
+    Utf16Be,
+}
+
+impl Charset {
+    /// Returns a new decoder of text in this charset.
+    pub fn decoder(self) -> CharsetDecoder {
+        CharsetDecoder {
+            charset: self,
+            pending: Vec::new(),
+        }
+    }
+}
+
+/// A [`TextDecoder`] of a [`Charset`].
+///
+/// An unpaired UTF-16 surrogate fails with `ErrorKind::InvalidData`, and
+/// text that ends inside a UTF-16 character with `ErrorKind::UnexpectedEof`.
+#[derive(Debug, Clone)]
+pub struct CharsetDecoder {
+    charset: Charset,
+    /// The start of a UTF-16 character split between two calls.
+    pending: Vec<u8>,
+}
+
+impl CharsetDecoder {
+    /// Returns the charset decoded.
+    pub fn charset(&self) -> Charset {
+        self.charset
+    }
+
+    fn decode_utf16(&mut self, src: &[u8], dst: &mut Vec<u8>, last: bool) -> io::Result<()> {
+        self.pending.extend_from_slice(src);
+        let big_endian = self.charset == Charset::Utf16Be;
+        let mut units: Vec<u16> = self
+            .pending
+            .chunks_exact(2)
+            .map(|pair| {
+                let pair = [pair[0], pair[1]];
+                if big_endian {
+                    u16::from_be_bytes(pair)
+                } else {
+                    u16::from_le_bytes(pair)
+                }
+            })
+            .collect();
+        // A high surrogate waits for the unit after it
+        let mut decoded = units.len() * 2;
+        if units
+            .last()
+            .is_some_and(|unit| (0xd800..0xdc00).contains(unit))
+        {
+            units.pop();
+            decoded -= 2;
+        }
+        for c in char::decode_utf16(units) {
+            let c = c.map_err(|e| {
+                io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "unpaired surrogate {:#06x} in UTF-16 text",
+                        e.unpaired_surrogate()
+                    ),
+                )
+            })?;
+            dst.extend_from_slice(c.encode_utf8(&mut [0u8; 4]).as_bytes());
+        }
+        self.pending.drain(..decoded);
+        if last && !self.pending.is_empty() {
+            return Err(io::Error::new(
+                ErrorKind::UnexpectedEof,
+                "stream ended inside a UTF-16 character",
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl TextDecoder for CharsetDecoder {
+    fn decode(&mut self, src: &[u8], dst: &mut Vec<u8>, last: bool) -> io::Result<()> {
+        match self.charset {
+            Charset::Latin1 | Charset::Windows1252 => {
+                for &byte in src {
+                    let c = match (self.charset, byte) {
+                        (Charset::Windows1252, 0x80..0xa0) => {
+                            WINDOWS_1252_C1[usize::from(byte - 0x80)]
+                        }
+                        _ => char::from(byte),
+                    };
+                    dst.extend_from_slice(c.encode_utf8(&mut [0u8; 4]).as_bytes());
+                }
+                Ok(())
+            }
+            Charset::Utf16Le | Charset::Utf16Be => self.decode_utf16(src, dst, last),
+        }
+    }
+}
+
+/// Fails on malformed input with `ErrorKind::InvalidData`, which includes
+/// a sequence cut off by the end of the input.
+#[cfg(feature = "encoding_rs")]
+impl TextDecoder for encoding_rs::Decoder {
+    fn decode(&mut self, mut src: &[u8], dst: &mut Vec<u8>, last: bool) -> io::Result<()> {
+        loop {
+            let start = dst.len();
+            let room = self
+                .max_utf8_buffer_length_without_replacement(src.len())
+                .unwrap_or(RAW_CAPACITY);
+            dst.resize(start + room.max(4), 0);
+            let (result, read, written) =
+                self.decode_to_utf8_without_replacement(src, &mut dst[start..], last);
+            dst.truncate(start + written);
+            src = &src[read..];
+            match result {
+                encoding_rs::DecoderResult::InputEmpty => return Ok(()),
+                encoding_rs::DecoderResult::OutputFull => {}
+                encoding_rs::DecoderResult::Malformed(..) => {
+                    return Err(io::Error::new(
+                        ErrorKind::InvalidData,
+                        format!("malformed {} text", self.encoding().name()),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+/// A non-owning adapter decoding the text of a reader into UTF-8.
+///
+/// Legacy formats embed text in a charset of their own, Latin-1 or
+/// Windows-1252 or UTF-16, often in sections of a known byte length read
+/// through a [`RefTake`](crate::RefTake). The text is decoded as it is
+/// read, with any [`TextDecoder`], so it can go to `read_to_string` like
+/// UTF-8 text. Reading fails with a [`LimitExceeded`] error once more than
+/// `max_output` bytes of UTF-8 would be produced, since decoding can make
+/// text several times longer than its encoded form; the bytes of a failing
+/// read are not returned.
+///
+/// A decoding error is returned again by every later read, rather than the
+/// text decoded before it followed by an EOF that would pass a truncated
+/// stream off as a complete one, until [`RefDecodeText::reset`] installs a
+/// fresh decoder.
+pub struct RefDecodeText<'a, R, D> {
+    inner: &'a mut R,
+    decoder: D,
+    max_output: u64,
+    written: u64,
+    raw: Vec<u8>,
+    /// Decoded text ready to be returned.
+    out: Vec<u8>,
+    pos: usize,
+    done: bool,
+    /// The kind and message of the decoding error every read returns.
+    failure: Option<(ErrorKind, String)>,
+}
+
+impl<'a, R, D> RefDecodeText<'a, R, D> {
+    /// Creates a new `RefDecodeText` producing at most `max_output` bytes of UTF-8.
+    pub fn wrap(inner: &'a mut R, decoder: D, max_output: u64) -> Self {
+        Self {
+            inner,
+            decoder,
+            max_output,
+            written: 0,
+            raw: Vec::new(),
+            out: Vec::new(),
+            pos: 0,
+            done: false,
+            failure: None,
+        }
+    }
+
+    /// Returns the decoder.
+    pub fn decoder(&self) -> &D {
+        &self.decoder
+    }
+
+    /// Returns the number of bytes of UTF-8 decoded so far.
+    pub fn bytes_written(&self) -> u64 {
+        self.written
+    }
+
+    /// Replaces the decoder, clearing a decoding error, to go on reading after it.
+    ///
+    /// The text decoded before the error and not read yet is dropped, as is
+    /// any partial sequence the old decoder held.
+    pub fn reset(&mut self, decoder: D) {
+        self.decoder = decoder;
+        self.failure = None;
+        self.out.clear();
+        self.pos = 0;
+    }
+}
+
+impl<R: Read, D: TextDecoder> RefDecodeText<'_, R, D> {
+    /// Reads and decodes the next piece of text into `out`.
+    fn decode_more(&mut self) -> io::Result<()> {
+        self.raw.resize(RAW_CAPACITY, 0);
+        let n = loop {
+            match self.inner.read(&mut self.raw) {
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                result => break result?,
+            }
+        };
+        self.out.clear();
+        self.pos = 0;
+        self.done = n == 0;
+        if let Err(e) = self
+            .decoder
+            .decode(&self.raw[..n], &mut self.out, self.done)
+        {
+            self.out.clear();
+            self.failure = Some((e.kind(), e.to_string()));
+            return Err(e);
+        }
+        self.written += self.out.len() as u64;
+        if self.written > self.max_output {
+            self.out.clear();
+            return Err(LimitExceeded::new(self.max_output).into());
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read, D: TextDecoder> Read for RefDecodeText<'_, R, D> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        if let Some((kind, message)) = &self.failure {
+            return Err(io::Error::new(*kind, message.clone()));
+        }
+        while self.pos == self.out.len() {
+            if self.done || buf.is_empty() {
+                return Ok(0);
+            }
+            self.decode_more()?;
+        }
+        let n = (&self.out[self.pos..]).read(buf)?;
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// Extension trait to provide a `decode_text_ref` method on all `Read` types.
+pub trait RefDecodeTextExt {
+    /// Wraps the reader in a `RefDecodeText` with the given decoder and output cap.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::Read;
+    /// use reftake::{Charset, RefDecodeTextExt, RefTakeExt};
+    ///
+    /// let mut record = &b"caf\xe9 \x80 5\x00\x00"[..];
+    /// let mut name = record.take_ref(8);
+    /// let mut text = String::new();
+    /// name.decode_text_ref(Charset::Windows1252.decoder(), 64)
+    ///     .read_to_string(&mut text)
+    ///     .unwrap();
+    /// assert_eq!(text, "caf\u{e9} \u{20ac} 5");
+    /// ```
+    fn decode_text_ref<D: TextDecoder>(
+        &mut self,
+        decoder: D,
+        max_output: u64,
+    ) -> RefDecodeText<'_, Self, D>
+    where
+        Self: Sized;
+}
+
+impl<T: Read> RefDecodeTextExt for T {
+    fn decode_text_ref<D: TextDecoder>(
+        &mut self,
+        decoder: D,
+        max_output: u64,
+    ) -> RefDecodeText<'_, Self, D> {
+        RefDecodeText::wrap(self, decoder, max_output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns one byte per read.
+    struct Bytewise<'a>(&'a [u8]);
+
+    impl Read for Bytewise<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = buf.len().min(1);
+            self.0.read(&mut buf[..n])
+        }
+    }
+
+    fn decode(data: &[u8], charset: Charset, max_output: u64) -> io::Result<String> {
+        let mut source = Bytewise(data);
+        let mut text = String::new();
+        source
+            .decode_text_ref(charset.decoder(), max_output)
+            .read_to_string(&mut text)
+            .map(|_| text)
+    }
+
+    #[test]
+    fn test_single_byte_charsets() {
+        let data = b"a\x80\x81\x9f\xe9\xff";
+        assert_eq!(
+            decode(data, Charset::Latin1, 64).unwrap(),
+            "a\u{80}\u{81}\u{9f}\u{e9}\u{ff}"
+        );
+        assert_eq!(
+            decode(data, Charset::Windows1252, 64).unwrap(),
+            "a\u{20ac}\u{81}\u{178}\u{e9}\u{ff}"
+        );
+    }
+
+    #[test]
+    fn test_utf16_split_across_reads() {
+        let text = "a\u{e9}\u{1f600}z";
+        let mut le = Vec::new();
+        let mut be = Vec::new();
+        for unit in text.encode_utf16() {
+            le.extend_from_slice(&unit.to_le_bytes());
+            be.extend_from_slice(&unit.to_be_bytes());
+        }
+        assert_eq!(decode(&le, Charset::Utf16Le, 64).unwrap(), text);
+        assert_eq!(decode(&be, Charset::Utf16Be, 64).unwrap(), text);
+
+        let err = decode(&le[..le.len() - 3], Charset::Utf16Le, 64).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+        let err = decode(b"\x00\xdca\x00", Charset::Utf16Le, 64).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_decoding_error_is_sticky_until_reset() {
+        // An unpaired low surrogate after "ab"
+        let mut source = &b"a\x00b\x00\x00\xdcc\x00"[..];
+        let mut text = source.decode_text_ref(Charset::Utf16Le.decoder(), 64);
+        let mut buf = [0u8; 16];
+        let err = text.read(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        for _ in 0..2 {
+            let again = text.read(&mut buf).unwrap_err();
+            assert_eq!(
+                (again.kind(), again.to_string()),
+                (err.kind(), err.to_string())
+            );
+        }
+
+        text.reset(Charset::Utf16Le.decoder());
+        let mut rest = String::new();
+        text.read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, "");
+    }
+
+    #[cfg(feature = "encoding_rs")]
+    #[test]
+    fn test_shift_jis_split_across_reads() {
+        let decode_sjis = |data: &[u8]| {
+            let mut source = Bytewise(data);
+            let mut text = String::new();
+            source
+                .decode_text_ref(
+                    encoding_rs::SHIFT_JIS.new_decoder_without_bom_handling(),
+                    64,
+                )
+                .read_to_string(&mut text)
+                .map(|_| text)
+        };
+        // Two-byte characters, each split between two inner reads
+        let data = b"\x93\xfa\x96\x7b\x8c\xea abc \xb1";
+        assert_eq!(decode_sjis(data).unwrap(), "日本語 abc ｱ");
+
+        let err = decode_sjis(&data[..3]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "malformed Shift_JIS text");
+    }
+
+    #[test]
+    fn test_output_cap_counts_utf8_bytes() {
+        // Four bytes of Latin-1 are eight bytes of UTF-8
+        let data = b"\xe9\xe9\xe9\xe9";
+        assert_eq!(decode(data, Charset::Latin1, 8).unwrap().len(), 8);
+        let err = decode(data, Charset::Latin1, 7).unwrap_err();
+        assert_eq!(LimitExceeded::from_io(&err), Some(&LimitExceeded::new(7)));
+    }
+}
@@ -0,0 +1,291 @@
+//! Decoding of HTTP/1.1 chunked transfer-encoding from a borrowed `BufRead`.
+
+use std::io::{self, BufRead, ErrorKind, Read};
+
+use crate::{LimitExceeded, UntilStatus, read_until_limited};
+
+/// Longest accepted chunk-size or trailer line, including its terminator.
+const MAX_LINE_LEN: usize = 4 * 1024;
+
+/// Longest accepted trailer section.
+const MAX_TRAILERS_LEN: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// Expecting a chunk-size line.
+    Size,
+    /// Inside a chunk with this many bytes left.
+    Data(u64),
+    /// Expecting the CRLF after a chunk's data.
+    DataEnd,
+    /// Reading trailer lines after the last chunk.
+    Trailers,
+    Done,
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(
+        ErrorKind::InvalidData,
+        format!("invalid chunked encoding: {msg}"),
+    )
+}
+
+/// A non-owning adapter that decodes an HTTP/1.1 chunked body.
+///
+/// Chunk-size lines, chunk extensions and CRLFs are stripped, and the
+/// concatenated chunk data is presented through `Read` and `BufRead`. After
+/// the last chunk, trailer fields are read and kept available through
+/// [`ChunkedDecoder::trailers`]; the inner reader is then left right after the
+/// body, ready for the next message on the connection.
+///
+/// A body whose decoded size would exceed `max_size` fails with a
+/// [`LimitExceeded`] error before the offending chunk is read. Malformed
+/// framing fails with `ErrorKind::InvalidData`, and a stream that ends
+/// before the terminating chunk with `ErrorKind::UnexpectedEof`.
+pub struct ChunkedDecoder<'a, R> {
+    inner: &'a mut R,
+    state: State,
+    max_size: u64,
+    decoded: u64,
+    trailers: Vec<u8>,
+}
+
+impl<'a, R> ChunkedDecoder<'a, R> {
+    /// Creates a new `ChunkedDecoder` accepting at most `max_size` bytes of decoded body.
+    pub const fn wrap(inner: &'a mut R, max_size: u64) -> Self {
+        Self {
+            inner,
+            state: State::Size,
+            max_size,
+            decoded: 0,
+            trailers: Vec::new(),
+        }
+    }
+
+    /// Returns the number of body bytes announced by the chunk headers read so far.
+    pub fn decoded_len(&self) -> u64 {
+        self.decoded
+    }
+
+    /// Returns `true` once the terminating chunk and trailers have been read.
+    pub fn is_finished(&self) -> bool {
+        self.state == State::Done
+    }
+
+    /// Returns the raw trailer section, one `name: value\r\n` line per field.
+    ///
+    /// Empty until the body has been read to the end.
+    pub fn trailers(&self) -> &[u8] {
+        &self.trailers
+    }
+}
+
+impl<R: BufRead> ChunkedDecoder<'_, R> {
+    /// Reads one CRLF- or LF-terminated line, without its terminator.
+    fn read_line(&mut self, line: &mut Vec<u8>) -> io::Result<()> {
+        match read_until_limited(self.inner, b'\n', line, MAX_LINE_LEN)? {
+            UntilStatus::Found(_) => {
+                line.pop();
+                if line.last() == Some(&b'\r') {
+                    line.pop();
+                }
+                Ok(())
+            }
+            UntilStatus::Eof(_) => Err(io::Error::new(
+                ErrorKind::UnexpectedEof,
+                "stream ended inside a chunked body",
+            )),
+            UntilStatus::Capped(_) => Err(invalid("line too long")),
+        }
+    }
+
+    fn read_size(&mut self) -> io::Result<()> {
+        let mut line = Vec::new();
+        self.read_line(&mut line)?;
+        let digits = line
+            .split(|&b| b == b';')
+            .next()
+            .unwrap_or_default()
+            .trim_ascii();
+        if digits.is_empty() || digits.len() > 16 {
+            return Err(invalid("bad chunk size"));
+        }
+        let size = std::str::from_utf8(digits)
+            .ok()
+            .and_then(|s| u64::from_str_radix(s, 16).ok())
+            .ok_or_else(|| invalid("bad chunk size"))?;
+
+        if size > self.max_size - self.decoded {
+            return Err(LimitExceeded::new(self.max_size).into());
+        }
+        self.decoded += size;
+        self.state = if size == 0 {
+            State::Trailers
+        } else {
+            State::Data(size)
+        };
+        Ok(())
+    }
+
+    fn read_data_end(&mut self) -> io::Result<()> {
+        let mut line = Vec::new();
+        self.read_line(&mut line)?;
+        if !line.is_empty() {
+            return Err(invalid("missing CRLF after chunk data"));
+        }
+        self.state = State::Size;
+        Ok(())
+    }
+
+    fn read_trailers(&mut self) -> io::Result<()> {
+        loop {
+            let mut line = Vec::new();
+            self.read_line(&mut line)?;
+            if line.is_empty() {
+                self.state = State::Done;
+                return Ok(());
+            }
+            if self.trailers.len() + line.len() + 2 > MAX_TRAILERS_LEN {
+                return Err(invalid("trailer section too long"));
+            }
+            self.trailers.extend_from_slice(&line);
+            self.trailers.extend_from_slice(b"\r\n");
+        }
+    }
+}
+
+impl<R: BufRead> Read for ChunkedDecoder<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        let available = self.fill_buf()?;
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for ChunkedDecoder<'_, R> {
+    fn fill_buf(&mut self) -> Result<&[u8], io::Error> {
+        loop {
+            match self.state {
+                State::Size => self.read_size()?,
+                State::DataEnd => self.read_data_end()?,
+                State::Trailers => self.read_trailers()?,
+                State::Done => return Ok(&[]),
+                State::Data(remaining) => {
+                    let buf = self.inner.fill_buf()?;
+                    if buf.is_empty() {
+                        return Err(io::Error::new(
+                            ErrorKind::UnexpectedEof,
+                            "stream ended inside a chunk",
+                        ));
+                    }
+                    let cap = buf.len().min(remaining.min(usize::MAX as u64) as usize);
+                    return Ok(&self.inner.fill_buf()?[..cap]);
+                }
+            }
+        }
+    }
+
+    fn consume(&mut self, amt: usize) {
+        if let State::Data(remaining) = self.state {
+            let amt = amt.min(remaining.min(usize::MAX as u64) as usize);
+            self.inner.consume(amt);
+            let remaining = remaining - amt as u64;
+            self.state = if remaining == 0 {
+                State::DataEnd
+            } else {
+                State::Data(remaining)
+            };
+        }
+    }
+}
+
+/// Extension trait to provide a `chunked_ref` method on all `BufRead` types.
+pub trait ChunkedDecoderExt {
+    /// Wraps the reader in a `ChunkedDecoder` accepting at most `max_size` decoded bytes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{BufRead, Cursor, Read};
+    /// use reftake::ChunkedDecoderExt;
+    ///
+    /// let mut conn = Cursor::new(b"5\r\nhello\r\n6;ext=1\r\n world\r\n0\r\n\r\nGET / HTTP/1.1");
+    /// let mut body = String::new();
+    /// conn.chunked_ref(1024).read_to_string(&mut body).unwrap();
+    /// assert_eq!(body, "hello world");
+    ///
+    /// let mut next = String::new();
+    /// conn.read_line(&mut next).unwrap();
+    /// assert_eq!(next, "GET / HTTP/1.1");
+    /// ```
+    fn chunked_ref(&mut self, max_size: u64) -> ChunkedDecoder<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: BufRead> ChunkedDecoderExt for T {
+    fn chunked_ref(&mut self, max_size: u64) -> ChunkedDecoder<'_, Self> {
+        ChunkedDecoder::wrap(self, max_size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufReader, Cursor};
+
+    #[test]
+    fn test_decode_across_small_buffers_with_trailers() {
+        let data = b"4\r\nWiki\r\n5\r\npedia\r\nE\r\n in\r\n\r\nchunks.\r\n0\r\nExpires: never\r\n\r\nrest";
+        let mut reader = BufReader::with_capacity(3, Cursor::new(&data[..]));
+        let mut body = Vec::new();
+        {
+            let mut decoder = reader.chunked_ref(1024);
+            decoder.read_to_end(&mut body).unwrap();
+            assert!(decoder.is_finished());
+            assert_eq!(decoder.decoded_len(), 23);
+            assert_eq!(decoder.trailers(), b"Expires: never\r\n");
+        }
+        assert_eq!(body, b"Wikipedia in\r\n\r\nchunks.");
+
+        let mut rest = String::new();
+        reader.read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, "rest");
+    }
+
+    #[test]
+    fn test_size_cap() {
+        let mut reader = Cursor::new(b"3\r\nabc\r\n10\r\n0123456789abcdef\r\n0\r\n\r\n");
+        let mut decoder = reader.chunked_ref(8);
+        let mut body = Vec::new();
+        let err = decoder.read_to_end(&mut body).unwrap_err();
+        assert_eq!(LimitExceeded::from_io(&err), Some(&LimitExceeded::new(8)));
+        assert_eq!(body, b"abc");
+    }
+
+    #[test]
+    fn test_malformed_and_truncated() {
+        let mut reader = Cursor::new(b"zz\r\n");
+        let err = reader
+            .chunked_ref(64)
+            .read_to_end(&mut Vec::new())
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        let mut reader = Cursor::new(b"3\r\nabcX\r\n");
+        let err = reader
+            .chunked_ref(64)
+            .read_to_end(&mut Vec::new())
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        let mut reader = Cursor::new(b"5\r\nab");
+        let err = reader
+            .chunked_ref(64)
+            .read_to_end(&mut Vec::new())
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,160 @@
+//! A fixed-size chunk iterator over a borrowed reader.
+
+use std::io::{self, ErrorKind, Read};
+
+/// An iterator over fixed-size chunks of a borrowed reader.
+///
+/// Every chunk holds exactly `size` bytes, except possibly the last one,
+/// which holds whatever was left when the reader (or its
+/// [`RefTake`](crate::RefTake) window) ended. After such a short chunk has
+/// been returned, [`Chunks::is_partial`] reports `true` and iteration stops.
+///
+/// [`Chunks::next_into`] fills a caller-provided buffer instead of allocating.
+pub struct Chunks<'a, R> {
+    inner: &'a mut R,
+    size: usize,
+    partial: bool,
+    done: bool,
+}
+
+impl<'a, R> Chunks<'a, R> {
+    /// Creates a new `Chunks` over `inner` yielding chunks of `size` bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero.
+    pub const fn wrap(inner: &'a mut R, size: usize) -> Self {
+        assert!(size > 0, "chunk size must be non-zero");
+        Self {
+            inner,
+            size,
+            partial: false,
+            done: false,
+        }
+    }
+
+    /// Returns the chunk size.
+    pub fn chunk_size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns `true` if the last chunk returned was shorter than the chunk size.
+    pub fn is_partial(&self) -> bool {
+        self.partial
+    }
+}
+
+impl<R: Read> Chunks<'_, R> {
+    /// Reads the next chunk into the first `chunk_size()` bytes of `buf`.
+    ///
+    /// Returns the number of bytes read, which is less than the chunk size
+    /// only for the last, partial chunk, and `0` once the reader is exhausted.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `buf` is shorter than the chunk size.
+    pub fn next_into(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.done {
+            return Ok(0);
+        }
+        let buf = &mut buf[..self.size];
+        let mut filled = 0;
+        while filled < buf.len() {
+            match self.inner.read(&mut buf[filled..]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => {
+                    self.done = true;
+                    return Err(e);
+                }
+            }
+        }
+        if filled < self.size {
+            self.done = true;
+            self.partial = filled > 0;
+        }
+        Ok(filled)
+    }
+}
+
+impl<R: Read> Iterator for Chunks<'_, R> {
+    type Item = io::Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut chunk = vec![0; self.size];
+        match self.next_into(&mut chunk) {
+            Ok(0) => None,
+            Ok(n) => {
+                chunk.truncate(n);
+                Some(Ok(chunk))
+            }
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+/// Extension trait to provide a `chunks_ref` method on all `Read` types.
+pub trait ChunksExt {
+    /// Returns an iterator over chunks of `size` bytes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use reftake::{ChunksExt, RefTakeExt};
+    ///
+    /// let mut cursor = Cursor::new(b"0123456789");
+    /// let mut take = cursor.take_ref(7);
+    /// let mut chunks = take.chunks_ref(3);
+    /// let all: Vec<Vec<u8>> = chunks.by_ref().map(Result::unwrap).collect();
+    /// assert_eq!(all, vec![b"012".to_vec(), b"345".to_vec(), b"6".to_vec()]);
+    /// assert!(chunks.is_partial());
+    /// ```
+    fn chunks_ref(&mut self, size: usize) -> Chunks<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: Read> ChunksExt for T {
+    fn chunks_ref(&mut self, size: usize) -> Chunks<'_, Self> {
+        Chunks::wrap(self, size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RefTakeExt;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_exact_chunks() {
+        let mut reader = Cursor::new(b"abcdef");
+        let mut chunks = reader.chunks_ref(2);
+        let all: Vec<Vec<u8>> = chunks.by_ref().map(Result::unwrap).collect();
+        assert_eq!(all, vec![b"ab".to_vec(), b"cd".to_vec(), b"ef".to_vec()]);
+        assert!(!chunks.is_partial());
+    }
+
+    #[test]
+    fn test_next_into_caller_buffer() {
+        let mut reader = Cursor::new(b"0123456789");
+        let mut take = reader.take_ref(5);
+        let mut chunks = take.chunks_ref(4);
+
+        let mut block = [0u8; 4];
+        assert_eq!(chunks.next_into(&mut block).unwrap(), 4);
+        assert_eq!(&block, b"0123");
+        assert_eq!(chunks.next_into(&mut block).unwrap(), 1);
+        assert!(chunks.is_partial());
+        assert_eq!(chunks.next_into(&mut block).unwrap(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "chunk size must be non-zero")]
+    fn test_zero_size_panics() {
+        let mut reader = Cursor::new(b"abc");
+        reader.chunks_ref(0);
+    }
+}
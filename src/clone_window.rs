@@ -0,0 +1,128 @@
+//! Independent readers over the window of a file, for another thread to re-read.
+
+use std::{
+    fs::File,
+    io::{self, Seek},
+    sync::Arc,
+};
+
+use crate::{RefTake, SeekTake, SharedTakeAt};
+
+/// Clones the file handle, saying what the handle was for when it can't be.
+fn try_clone(file: &File) -> io::Result<Arc<File>> {
+    file.try_clone().map(Arc::new).map_err(|e| {
+        io::Error::new(
+            e.kind(),
+            format!("cannot clone the file of the window: {e}"),
+        )
+    })
+}
+
+impl RefTake<'_, File> {
+    /// Returns an independent reader over the bytes left in the window.
+    ///
+    /// The file handle is cloned, and the new window reads from the current
+    /// position of the file up to the current limit, with positioned reads,
+    /// since a cloned handle shares its cursor with the original: one
+    /// thread can keep parsing through this window while another re-reads
+    /// the same bytes, to hash or verify them. Neither moves the other.
+    ///
+    /// Only available on unix: the positioned reads of Windows move the
+    /// cursor the two handles share.
+    ///
+    /// Fails if the position of the file can't be queried or the handle
+    /// can't be cloned, such as when the process is out of file descriptors.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::{fs::File, io::Read};
+    /// use reftake::RefTakeExt;
+    ///
+    /// let mut file = File::open("archive.bin").unwrap();
+    /// let mut entry = file.take_ref(4096);
+    /// let mut copy = entry.clone_window().unwrap();
+    /// let verifier = std::thread::spawn(move || {
+    ///     let mut bytes = Vec::new();
+    ///     copy.read_to_end(&mut bytes).unwrap();
+    ///     bytes.len()
+    /// });
+    /// let mut parsed = Vec::new();
+    /// entry.read_to_end(&mut parsed).unwrap();
+    /// assert_eq!(verifier.join().unwrap(), parsed.len());
+    /// ```
+    pub fn clone_window(&mut self) -> io::Result<SharedTakeAt<File>> {
+        let position = self.inner.stream_position()?;
+        Ok(SharedTakeAt::new(
+            try_clone(self.inner)?,
+            position,
+            self.limit,
+        ))
+    }
+}
+
+impl SeekTake<'_, File> {
+    /// Returns an independent reader over the whole window, from its first byte.
+    ///
+    /// Like [`RefTake::clone_window`], but as the window knows where it
+    /// starts, the new reader covers all of it, whatever has been read
+    /// here already.
+    ///
+    /// Fails if the position of the file can't be queried or the handle
+    /// can't be cloned.
+    pub fn clone_window(&mut self) -> io::Result<SharedTakeAt<File>> {
+        let state = self.state()?;
+        Ok(SharedTakeAt::new(
+            try_clone(self.inner)?,
+            state.origin,
+            state.len,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{RefTakeExt, SeekTakeExt};
+    use std::{
+        fs::File,
+        io::{Read, Seek, SeekFrom, Write},
+    };
+
+    #[test]
+    fn test_cloned_windows_read_independently() {
+        let path = std::env::temp_dir().join(format!("reftake-clone-{}", std::process::id()));
+        File::create(&path)
+            .unwrap()
+            .write_all(b"header|entry body|trailer")
+            .unwrap();
+        let mut file = File::open(&path).unwrap();
+        file.seek(SeekFrom::Start(7)).unwrap();
+
+        let mut entry = file.take_ref(10);
+        let mut head = [0u8; 6];
+        entry.read_exact(&mut head).unwrap();
+        let mut copy = entry.clone_window().unwrap();
+        let handle = std::thread::spawn(move || {
+            let mut out = String::new();
+            copy.read_to_string(&mut out).unwrap();
+            out
+        });
+        let mut rest = String::new();
+        entry.read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, "body");
+        assert_eq!(handle.join().unwrap(), "body");
+
+        file.seek(SeekFrom::Start(7)).unwrap();
+        let mut entry = file.seek_take_ref(10);
+        entry.read_exact(&mut head).unwrap();
+        let mut whole = String::new();
+        entry
+            .clone_window()
+            .unwrap()
+            .read_to_string(&mut whole)
+            .unwrap();
+        assert_eq!(whole, "entry body");
+        assert_eq!(entry.position(), 6);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
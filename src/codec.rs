@@ -0,0 +1,121 @@
+//! Interoperability with `tokio_util`'s length-delimited codec (feature `tokio-util`).
+
+use tokio_util::codec::{LengthDelimitedCodec, length_delimited::Builder};
+
+use crate::{Endian, FrameConfig, LengthEncoding};
+
+impl FrameConfig {
+    /// Returns a `tokio_util` codec builder that frames data the same way.
+    ///
+    /// This lets async code encode or decode the frames a [`FrameReader`](crate::FrameReader)
+    /// reads, from one shared definition. Returns `None` for
+    /// [`LengthEncoding::Varint`], which the codec cannot express.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use reftake::{FrameConfig, LengthEncoding};
+    ///
+    /// let config = FrameConfig {
+    ///     length: LengthEncoding::U16,
+    ///     max_frame_len: 1024,
+    ///     ..FrameConfig::default()
+    /// };
+    /// let codec = config.to_codec_builder().unwrap().new_codec();
+    /// assert_eq!(codec.max_frame_length(), 1024);
+    /// ```
+    pub fn to_codec_builder(&self) -> Option<Builder> {
+        let header_len = match self.length {
+            LengthEncoding::U8 => 1,
+            LengthEncoding::U16 => 2,
+            LengthEncoding::U32 => 4,
+            LengthEncoding::U64 => 8,
+            LengthEncoding::Varint => return None,
+        };
+
+        let mut builder = LengthDelimitedCodec::builder();
+        builder.length_field_length(header_len);
+        match self.endian {
+            Endian::Big => builder.big_endian(),
+            Endian::Little => builder.little_endian(),
+        };
+        // The codec checks its maximum against the raw length field
+        let mut max_field = self.max_frame_len;
+        if self.length_includes_header {
+            builder.length_adjustment(-(header_len as isize));
+            max_field = max_field.saturating_add(header_len as u64);
+        }
+        builder.max_frame_length(usize::try_from(max_field).unwrap_or(usize::MAX));
+        Some(builder)
+    }
+
+    /// Returns a `tokio_util` codec framing data the same way, or `None` for varint lengths.
+    pub fn to_codec(&self) -> Option<LengthDelimitedCodec> {
+        self.to_codec_builder().map(|builder| builder.new_codec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FrameReader;
+    use bytes::{Bytes, BytesMut};
+    use std::io::{Cursor, Read};
+    use tokio_util::codec::{Decoder, Encoder};
+
+    fn encode(config: &FrameConfig, frames: &[&'static [u8]]) -> Vec<u8> {
+        let mut codec = config.to_codec().unwrap();
+        let mut out = BytesMut::new();
+        for frame in frames {
+            codec.encode(Bytes::from_static(frame), &mut out).unwrap();
+        }
+        out.to_vec()
+    }
+
+    #[test]
+    fn test_codec_output_reads_back() {
+        let config = FrameConfig {
+            length: LengthEncoding::U16,
+            endian: Endian::Little,
+            length_includes_header: true,
+            max_frame_len: 64,
+        };
+        let mut reader = Cursor::new(encode(&config, &[b"hello", b"world!"]));
+        let mut frames = FrameReader::with_config(&mut reader, config);
+
+        let mut body = String::new();
+        frames
+            .next_frame()
+            .unwrap()
+            .unwrap()
+            .read_to_string(&mut body)
+            .unwrap();
+        assert_eq!(body, "hello");
+        body.clear();
+        frames
+            .next_frame()
+            .unwrap()
+            .unwrap()
+            .read_to_string(&mut body)
+            .unwrap();
+        assert_eq!(body, "world!");
+        assert!(frames.next_frame().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_codec_decodes_frame_layout() {
+        let config = FrameConfig::default();
+        let mut codec = config.to_codec().unwrap();
+        let mut src = BytesMut::from(&b"\0\0\0\x03abc"[..]);
+        assert_eq!(codec.decode(&mut src).unwrap().unwrap(), &b"abc"[..]);
+    }
+
+    #[test]
+    fn test_varint_is_not_supported() {
+        let config = FrameConfig {
+            length: LengthEncoding::Varint,
+            ..FrameConfig::default()
+        };
+        assert!(config.to_codec_builder().is_none());
+    }
+}
@@ -0,0 +1,303 @@
+//! Bounded copying from a reader into a writer.
+
+use std::{
+    cmp,
+    error::Error,
+    fmt,
+    io::{self, BufRead, ErrorKind, Read, Write},
+};
+
+use crate::{RefTake, error::payload};
+
+/// Largest scratch buffer [`copy_limited`] allocates.
+const MAX_BUF: usize = 64 * 1024;
+
+/// How a bounded copy ended, with the number of bytes copied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CopyStatus {
+    /// The whole window was copied.
+    Limit(u64),
+    /// The inner reader ended before the window did.
+    Eof(u64),
+}
+
+impl CopyStatus {
+    /// Returns the number of bytes copied.
+    pub fn bytes_copied(&self) -> u64 {
+        match *self {
+            CopyStatus::Limit(n) | CopyStatus::Eof(n) => n,
+        }
+    }
+
+    /// Returns `true` if the copy ended because the limit was reached.
+    pub fn limit_reached(&self) -> bool {
+        matches!(self, CopyStatus::Limit(_))
+    }
+}
+
+/// Error payload reported when the source of an exact copy ends early.
+///
+/// Returned wrapped in an `io::Error` of kind `ErrorKind::UnexpectedEof`
+/// by [`copy_exact`] and [`RefTake::copy_exact_to`]; use
+/// [`ShortCopy::from_io`] to find out how far the copy got.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShortCopy {
+    copied: u64,
+    expected: u64,
+}
+
+impl ShortCopy {
+    /// Returns the number of bytes copied before the source ended.
+    pub fn copied(&self) -> u64 {
+        self.copied
+    }
+
+    /// Returns the number of bytes the copy should have moved.
+    pub fn expected(&self) -> u64 {
+        self.expected
+    }
+
+    /// Returns the `ShortCopy` payload of an I/O error, if it has one.
+    pub fn from_io(err: &io::Error) -> Option<&ShortCopy> {
+        payload(err)
+    }
+}
+
+impl fmt::Display for ShortCopy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "source ended after {} of {} bytes",
+            self.copied, self.expected
+        )
+    }
+}
+
+impl Error for ShortCopy {}
+
+impl From<ShortCopy> for io::Error {
+    fn from(err: ShortCopy) -> Self {
+        io::Error::new(ErrorKind::UnexpectedEof, err)
+    }
+}
+
+/// Copies at most `limit` bytes from `reader` into `writer`.
+///
+/// A replacement for `io::copy(&mut reader.take_ref(limit), writer)`: the
+/// scratch buffer is sized for the copy, up to 64 KiB, instead of a fixed
+/// 8 KiB, so a small body costs a small allocation and a large one fewer
+/// calls, and the result tells a copy cut off by the limit apart from a
+/// reader that ended first. A copy ending exactly at EOF counts as reaching
+/// the limit. Once the limit is reached the reader is not read again, so
+/// copying a body out of a connection never blocks on the next request.
+///
+/// Use [`copy_limited_buf`] for a reader that implements `BufRead`, to
+/// write straight from its buffer instead.
+///
+/// # Example
+///
+/// ```
+/// use reftake::CopyStatus;
+///
+/// let mut reader: &[u8] = b"body of the upload";
+/// let mut out = Vec::new();
+/// let status = reftake::copy_limited(&mut reader, &mut out, 4).unwrap();
+/// assert_eq!(status, CopyStatus::Limit(4));
+/// assert_eq!(out, b"body");
+///
+/// let status = reftake::copy_limited(&mut reader, &mut out, 100).unwrap();
+/// assert_eq!(status, CopyStatus::Eof(14));
+/// ```
+pub fn copy_limited<R, W>(reader: &mut R, writer: &mut W, limit: u64) -> io::Result<CopyStatus>
+where
+    R: Read + ?Sized,
+    W: Write + ?Sized,
+{
+    let mut buf = vec![0u8; cmp::min(limit, MAX_BUF as u64) as usize];
+    let mut copied = 0;
+    while copied < limit {
+        let max = cmp::min(buf.len() as u64, limit - copied) as usize;
+        let n = match reader.read(&mut buf[..max]) {
+            Ok(0) => return Ok(CopyStatus::Eof(copied)),
+            Ok(n) => n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        writer.write_all(&buf[..n])?;
+        copied += n as u64;
+    }
+    Ok(CopyStatus::Limit(copied))
+}
+
+/// Copies at most `limit` bytes from `reader` into `writer`, writing straight from its buffer.
+///
+/// Behaves like [`copy_limited`] without its scratch buffer.
+///
+/// # Example
+///
+/// ```
+/// use std::io::{BufRead, BufReader};
+/// use reftake::CopyStatus;
+///
+/// let mut reader = BufReader::new(&b"body of the upload"[..]);
+/// let mut out = Vec::new();
+/// let status = reftake::copy_limited_buf(&mut reader, &mut out, 4).unwrap();
+/// assert_eq!(status, CopyStatus::Limit(4));
+/// assert_eq!(reader.fill_buf().unwrap(), b" of the upload");
+/// ```
+pub fn copy_limited_buf<R, W>(reader: &mut R, writer: &mut W, limit: u64) -> io::Result<CopyStatus>
+where
+    R: BufRead + ?Sized,
+    W: Write + ?Sized,
+{
+    let mut copied = 0;
+    while copied < limit {
+        let buf = match reader.fill_buf() {
+            Ok([]) => return Ok(CopyStatus::Eof(copied)),
+            Ok(buf) => buf,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        let n = cmp::min(buf.len() as u64, limit - copied) as usize;
+        writer.write_all(&buf[..n])?;
+        reader.consume(n);
+        copied += n as u64;
+    }
+    Ok(CopyStatus::Limit(copied))
+}
+
+/// Copies exactly `n` bytes from `reader` into `writer`.
+///
+/// Fails with a [`ShortCopy`] error of kind `ErrorKind::UnexpectedEof`
+/// holding the number of bytes copied if the reader ends first; those
+/// bytes have been written by then. Buffers like [`copy_limited`].
+///
+/// # Example
+///
+/// ```
+/// use reftake::ShortCopy;
+///
+/// let mut reader: &[u8] = b"truncated";
+/// let mut out = Vec::new();
+/// let err = reftake::copy_exact(&mut reader, &mut out, 16).unwrap_err();
+/// let short = ShortCopy::from_io(&err).unwrap();
+/// assert_eq!((short.copied(), short.expected()), (9, 16));
+/// ```
+pub fn copy_exact<R, W>(reader: &mut R, writer: &mut W, n: u64) -> io::Result<()>
+where
+    R: Read + ?Sized,
+    W: Write + ?Sized,
+{
+    match copy_limited(reader, writer, n)? {
+        CopyStatus::Limit(_) => Ok(()),
+        CopyStatus::Eof(copied) => {
+            trace_event!(debug, copied, expected = n, "exact copy cut short");
+            Err(ShortCopy {
+                copied,
+                expected: n,
+            }
+            .into())
+        }
+    }
+}
+
+impl<R: Read> RefTake<'_, R> {
+    /// Copies the rest of the window into `writer`, failing if the inner reader ends first.
+    ///
+    /// Returns the number of bytes copied, the limit the window had. See
+    /// [`copy_exact`] for the error.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use reftake::RefTakeExt;
+    ///
+    /// let mut cursor = Cursor::new(b"payloadnext");
+    /// let mut out = Vec::new();
+    /// assert_eq!(cursor.take_ref(7).copy_exact_to(&mut out).unwrap(), 7);
+    /// assert_eq!(out, b"payload");
+    /// ```
+    pub fn copy_exact_to<W: Write + ?Sized>(&mut self, writer: &mut W) -> io::Result<u64> {
+        let n = self.current_limit();
+        copy_exact(self, writer, n)?;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RefTakeExt;
+    use std::io::{BufReader, Cursor};
+
+    /// A reader that fails if it is read after running dry, like a connection with nothing more to send.
+    struct Connection<'a> {
+        data: &'a [u8],
+    }
+
+    impl Read for Connection<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            assert!(!self.data.is_empty(), "read would block");
+            self.data.read(buf)
+        }
+    }
+
+    #[test]
+    fn test_limit_is_not_read_past() {
+        let mut conn = Connection {
+            data: b"0123456789",
+        };
+        let mut out = Vec::new();
+        let status = copy_limited(&mut conn, &mut out, 10).unwrap();
+        assert_eq!(status, CopyStatus::Limit(10));
+        assert!(status.limit_reached());
+        assert_eq!(
+            copy_limited(&mut conn, &mut out, 0).unwrap(),
+            CopyStatus::Limit(0)
+        );
+        assert_eq!(out, b"0123456789");
+    }
+
+    #[test]
+    fn test_buffered_copy() {
+        let mut reader = BufReader::with_capacity(3, Cursor::new(b"0123456789"));
+        let mut out = Vec::new();
+        let status = copy_limited_buf(&mut reader, &mut out, 7).unwrap();
+        assert_eq!(status, CopyStatus::Limit(7));
+        assert_eq!(reader.buffer(), b"78");
+
+        let status = copy_limited_buf(&mut reader, &mut out, 10).unwrap();
+        assert_eq!(status, CopyStatus::Eof(3));
+        assert_eq!(status.bytes_copied(), 3);
+        assert_eq!(out, b"0123456789");
+    }
+
+    #[test]
+    fn test_full_writer() {
+        let mut reader: &[u8] = b"abcdef";
+        let mut out = [0u8; 2];
+        let err = copy_limited(&mut reader, &mut &mut out[..], 6).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::WriteZero);
+    }
+
+    #[test]
+    fn test_exact_copy_shortfall() {
+        let mut cursor = Cursor::new(b"abcdef");
+        let mut out = Vec::new();
+        let mut take = cursor.take_ref(10);
+        let err = take.copy_exact_to(&mut out).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+        assert_eq!(err.to_string(), "source ended after 6 of 10 bytes");
+        assert_eq!(
+            ShortCopy::from_io(&err),
+            Some(&ShortCopy {
+                copied: 6,
+                expected: 10
+            })
+        );
+        assert_eq!(out, b"abcdef");
+        assert_eq!(take.current_limit(), 4);
+    }
+}
@@ -0,0 +1,124 @@
+//! A non-limiting adapter that counts the bytes read from a borrowed reader.
+
+#[cfg(feature = "std")]
+use std::io::{self, BufRead, Read};
+
+/// A non-owning adapter that counts how many bytes pass through it.
+///
+/// Unlike [`RefTake`](crate::RefTake), no limit is enforced; reads and
+/// buffered reads are forwarded unchanged to the inner reader.
+pub struct RefCount<'a, R> {
+    // Only read by the trait implementations, which a bare core build has none of
+    #[cfg_attr(not(any(feature = "std", feature = "embedded-io")), allow(dead_code))]
+    pub(crate) inner: &'a mut R,
+    pub(crate) count: u64,
+}
+
+impl<'a, R> RefCount<'a, R> {
+    /// Creates a new `RefCount` over the given reader reference, starting at zero.
+    pub const fn wrap(inner: &'a mut R) -> Self {
+        Self { inner, count: 0 }
+    }
+
+    /// Returns the number of bytes read (or consumed) so far.
+    pub fn bytes_read(&self) -> u64 {
+        self.count
+    }
+
+    /// Resets the byte counter to zero.
+    pub fn reset(&mut self) {
+        self.count = 0;
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read> Read for RefCount<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        let n = self.inner.read(buf)?;
+        self.count += n as u64;
+        Ok(n)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: BufRead> BufRead for RefCount<'_, R> {
+    fn fill_buf(&mut self) -> Result<&[u8], io::Error> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.count += amt as u64;
+        self.inner.consume(amt);
+    }
+}
+
+#[cfg(feature = "std")]
+/// Extension trait to provide a `count_ref` method on all `Read` types.
+pub trait RefCountExt {
+    /// Wraps the reader in a `RefCount` that tracks the number of bytes read.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Cursor, Read};
+    /// use reftake::RefCountExt;
+    ///
+    /// let mut cursor = Cursor::new(b"hello world");
+    /// let mut counter = cursor.count_ref();
+    ///
+    /// let mut buf = [0u8; 5];
+    /// counter.read_exact(&mut buf).unwrap();
+    /// assert_eq!(counter.bytes_read(), 5);
+    /// ```
+    fn count_ref(&mut self) -> RefCount<'_, Self>
+    where
+        Self: Sized;
+}
+
+#[cfg(feature = "std")]
+impl<T: Read> RefCountExt for T {
+    fn count_ref(&mut self) -> RefCount<'_, Self> {
+        RefCount::wrap(self)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::RefTakeExt;
+    use std::io::{BufReader, Cursor};
+
+    #[test]
+    fn test_count_read() {
+        let mut reader = Cursor::new(b"abcdef");
+        let mut counter = reader.count_ref();
+
+        let mut buf = Vec::new();
+        counter.read_to_end(&mut buf).unwrap();
+        assert_eq!(counter.bytes_read(), 6);
+
+        counter.reset();
+        assert_eq!(counter.bytes_read(), 0);
+    }
+
+    #[test]
+    fn test_count_bufread() {
+        let mut reader = BufReader::new(Cursor::new(b"one\ntwo\n"));
+        let mut counter = reader.count_ref();
+
+        let mut line = String::new();
+        counter.read_line(&mut line).unwrap();
+        assert_eq!(counter.bytes_read(), 4);
+    }
+
+    #[test]
+    fn test_count_over_take() {
+        let mut reader = Cursor::new(b"hello world");
+        let mut take = reader.take_ref(3);
+        let mut counter = take.count_ref();
+
+        let mut buf = Vec::new();
+        counter.read_to_end(&mut buf).unwrap();
+        assert_eq!(counter.bytes_read(), 3);
+    }
+}
@@ -0,0 +1,134 @@
+//! A non-limiting adapter that counts the bytes written to a borrowed writer.
+
+use std::io::{self, IoSlice, Write};
+
+/// A non-owning adapter that counts how many bytes are written through it.
+///
+/// Only bytes the inner writer reports as accepted are counted, along with
+/// the number of write calls that reached it. No limit is enforced.
+pub struct RefCountWrite<'a, W> {
+    inner: &'a mut W,
+    count: u64,
+    calls: u64,
+}
+
+impl<'a, W> RefCountWrite<'a, W> {
+    /// Creates a new `RefCountWrite` over the given writer reference, starting at zero.
+    pub const fn wrap(inner: &'a mut W) -> Self {
+        Self {
+            inner,
+            count: 0,
+            calls: 0,
+        }
+    }
+
+    /// Returns the number of bytes successfully written so far.
+    pub fn bytes_written(&self) -> u64 {
+        self.count
+    }
+
+    /// Returns the number of `write` and `write_vectored` calls forwarded to the inner writer.
+    pub fn write_calls(&self) -> u64 {
+        self.calls
+    }
+
+    /// Resets both counters to zero.
+    pub fn reset(&mut self) {
+        self.count = 0;
+        self.calls = 0;
+    }
+}
+
+impl<W: Write> Write for RefCountWrite<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.calls += 1;
+        let n = self.inner.write(buf)?;
+        self.count += n as u64;
+        Ok(n)
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        self.calls += 1;
+        let n = self.inner.write_vectored(bufs)?;
+        self.count += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Extension trait to provide a `count_write_ref` method on all `Write` types.
+pub trait RefCountWriteExt {
+    /// Wraps the writer in a `RefCountWrite` that tracks the number of bytes written.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{self, Write};
+    /// use reftake::RefCountWriteExt;
+    ///
+    /// fn serialize(out: &mut dyn Write) -> io::Result<()> {
+    ///     write!(out, "{}-{}", 12, 345)
+    /// }
+    ///
+    /// let mut out = Vec::new();
+    /// let mut counter = out.count_write_ref();
+    /// serialize(&mut counter).unwrap();
+    /// assert_eq!(counter.bytes_written(), 6);
+    /// ```
+    fn count_write_ref(&mut self) -> RefCountWrite<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: Write> RefCountWriteExt for T {
+    fn count_write_ref(&mut self) -> RefCountWrite<'_, Self> {
+        RefCountWrite::wrap(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RefTakeWriteExt;
+
+    #[test]
+    fn test_count_writes() {
+        let mut out = Vec::new();
+        let mut counter = out.count_write_ref();
+
+        counter.write_all(b"abc").unwrap();
+        counter.write_all(b"defg").unwrap();
+        assert_eq!(counter.bytes_written(), 7);
+        assert_eq!(counter.write_calls(), 2);
+
+        counter.reset();
+        assert_eq!(counter.bytes_written(), 0);
+        assert_eq!(counter.write_calls(), 0);
+    }
+
+    #[test]
+    fn test_count_short_writes() {
+        let mut out = Vec::new();
+        let mut limited = out.take_write_ref(4);
+        let mut counter = limited.count_write_ref();
+
+        assert_eq!(counter.write(b"abcdef").unwrap(), 4);
+        assert_eq!(counter.write(b"gh").unwrap(), 0);
+        assert_eq!(counter.bytes_written(), 4);
+        assert_eq!(counter.write_calls(), 2);
+    }
+
+    #[test]
+    fn test_count_write_vectored() {
+        let mut out = Vec::new();
+        let mut counter = out.count_write_ref();
+
+        let bufs = [IoSlice::new(b"ab"), IoSlice::new(b"cd")];
+        assert_eq!(counter.write_vectored(&bufs).unwrap(), 4);
+        assert_eq!(counter.bytes_written(), 4);
+        assert_eq!(counter.write_calls(), 1);
+    }
+}
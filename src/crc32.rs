@@ -0,0 +1,125 @@
+//! A CRC32 checksumming adapter over a borrowed reader (feature `crc32`).
+
+use std::io::{self, BufRead, Read};
+
+use crc32fast::Hasher;
+
+/// A non-owning adapter that computes the CRC32 (IEEE) of every byte read through it.
+///
+/// Combined with [`RefTake`](crate::RefTake), this yields the checksum of
+/// exactly one bounded region, as required by ZIP and PNG, without copying.
+pub struct Crc32Reader<'a, R> {
+    inner: &'a mut R,
+    hasher: Hasher,
+}
+
+impl<'a, R> Crc32Reader<'a, R> {
+    /// Creates a new `Crc32Reader` over the given reader reference.
+    pub fn wrap(inner: &'a mut R) -> Self {
+        Self {
+            inner,
+            hasher: Hasher::new(),
+        }
+    }
+
+    /// Returns the checksum of the bytes read so far, without resetting it.
+    pub fn checksum(&self) -> u32 {
+        self.hasher.clone().finalize()
+    }
+
+    /// Consumes the adapter and returns the checksum of all bytes read through it.
+    pub fn finalize(self) -> u32 {
+        self.hasher.finalize()
+    }
+}
+
+impl<R: Read> Read for Crc32Reader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        let n = self.inner.read(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+/// Implements the `BufRead` trait, checksumming bytes as they are consumed.
+impl<R: BufRead> BufRead for Crc32Reader<'_, R> {
+    fn fill_buf(&mut self) -> Result<&[u8], io::Error> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        if amt > 0
+            && let Ok(buf) = self.inner.fill_buf()
+        {
+            self.hasher.update(&buf[..amt.min(buf.len())]);
+        }
+        self.inner.consume(amt);
+    }
+}
+
+/// Extension trait to provide a `crc32_ref` method on all `Read` types.
+pub trait Crc32ReaderExt {
+    /// Wraps the reader in a `Crc32Reader` that checksums everything read.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Cursor, Read};
+    /// use reftake::{Crc32ReaderExt, RefTakeExt};
+    ///
+    /// let mut cursor = Cursor::new(b"123456789 trailing");
+    /// let mut take = cursor.take_ref(9);
+    /// let mut crc = take.crc32_ref();
+    /// std::io::copy(&mut crc, &mut std::io::sink()).unwrap();
+    /// assert_eq!(crc.finalize(), 0xCBF4_3926);
+    /// ```
+    fn crc32_ref(&mut self) -> Crc32Reader<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: Read> Crc32ReaderExt for T {
+    fn crc32_ref(&mut self) -> Crc32Reader<'_, Self> {
+        Crc32Reader::wrap(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RefTakeExt;
+    use std::io::{BufReader, Cursor};
+
+    #[test]
+    fn test_crc32_of_read_bytes() {
+        let mut reader = Cursor::new(b"123456789");
+        let mut crc = reader.crc32_ref();
+
+        let mut buf = Vec::new();
+        crc.read_to_end(&mut buf).unwrap();
+        assert_eq!(crc.checksum(), 0xCBF4_3926);
+        assert_eq!(crc.finalize(), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_crc32_bufread() {
+        let mut reader = BufReader::with_capacity(4, Cursor::new(b"123456789\nrest"));
+        let mut crc = reader.crc32_ref();
+
+        let mut line = Vec::new();
+        crc.read_until(b'\n', &mut line).unwrap();
+        assert_eq!(crc.finalize(), crc32fast::hash(b"123456789\n"));
+    }
+
+    #[test]
+    fn test_crc32_of_take_window() {
+        let mut reader = Cursor::new(b"header123456789trailer");
+        reader.set_position(6);
+        let mut take = reader.take_ref(9);
+        let mut crc = take.crc32_ref();
+
+        let mut buf = Vec::new();
+        crc.read_to_end(&mut buf).unwrap();
+        assert_eq!(crc.finalize(), 0xCBF4_3926);
+    }
+}
@@ -0,0 +1,140 @@
+//! An adapter that enforces a wall-clock deadline on reads from a borrowed reader.
+
+use std::{
+    io::{self, BufRead, ErrorKind, Read},
+    time::{Duration, Instant},
+};
+
+/// A non-owning adapter that fails all reads once a deadline has passed.
+///
+/// The deadline is checked before every call into the inner reader; after it
+/// has passed, reads return an error of kind `ErrorKind::TimedOut`. A read that
+/// is already blocked inside the inner reader is not interrupted, so this
+/// should be combined with a socket read timeout when the peer is untrusted.
+pub struct RefDeadline<'a, R> {
+    inner: &'a mut R,
+    deadline: Instant,
+}
+
+impl<'a, R> RefDeadline<'a, R> {
+    /// Creates a new `RefDeadline` that stops reading at `deadline`.
+    pub const fn wrap(inner: &'a mut R, deadline: Instant) -> Self {
+        Self { inner, deadline }
+    }
+
+    /// Creates a new `RefDeadline` that stops reading `timeout` from now.
+    pub fn with_timeout(inner: &'a mut R, timeout: Duration) -> Self {
+        Self::wrap(inner, Instant::now() + timeout)
+    }
+
+    /// Returns the deadline after which reads fail.
+    pub fn deadline(&self) -> Instant {
+        self.deadline
+    }
+
+    /// Moves the deadline to a new point in time.
+    pub fn set_deadline(&mut self, deadline: Instant) {
+        self.deadline = deadline;
+    }
+
+    /// Returns the time left until the deadline, or zero if it has passed.
+    pub fn time_remaining(&self) -> Duration {
+        self.deadline.saturating_duration_since(Instant::now())
+    }
+
+    fn check(&self) -> io::Result<()> {
+        if Instant::now() >= self.deadline {
+            return Err(io::Error::new(
+                ErrorKind::TimedOut,
+                "read deadline exceeded",
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for RefDeadline<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        self.check()?;
+        self.inner.read(buf)
+    }
+}
+
+impl<R: BufRead> BufRead for RefDeadline<'_, R> {
+    fn fill_buf(&mut self) -> Result<&[u8], io::Error> {
+        self.check()?;
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.inner.consume(amt);
+    }
+}
+
+/// Extension trait to provide a `deadline_ref` method on all `Read` types.
+pub trait RefDeadlineExt {
+    /// Wraps the reader in a `RefDeadline` that fails reads after `timeout` has elapsed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Cursor, Read};
+    /// use std::time::Duration;
+    /// use reftake::{RefDeadlineExt, RefTakeExt};
+    ///
+    /// let mut cursor = Cursor::new(b"hello world");
+    /// let mut timed = cursor.deadline_ref(Duration::from_secs(5));
+    ///
+    /// let mut buf = String::new();
+    /// timed.take_ref(5).read_to_string(&mut buf).unwrap();
+    /// assert_eq!(buf, "hello");
+    /// ```
+    fn deadline_ref(&mut self, timeout: Duration) -> RefDeadline<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: Read> RefDeadlineExt for T {
+    fn deadline_ref(&mut self, timeout: Duration) -> RefDeadline<'_, Self> {
+        RefDeadline::with_timeout(self, timeout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufReader, Cursor};
+
+    #[test]
+    fn test_reads_before_deadline() {
+        let mut reader = Cursor::new(b"abc");
+        let mut timed = reader.deadline_ref(Duration::from_secs(60));
+
+        let mut buf = Vec::new();
+        timed.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, b"abc");
+        assert!(timed.time_remaining() > Duration::ZERO);
+    }
+
+    #[test]
+    fn test_reads_fail_after_deadline() {
+        let mut reader = Cursor::new(b"abc");
+        let mut timed = RefDeadline::wrap(&mut reader, Instant::now());
+
+        let mut buf = [0u8; 3];
+        let err = timed.read(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::TimedOut);
+        assert_eq!(timed.time_remaining(), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_fill_buf_fails_after_deadline() {
+        let mut reader = BufReader::new(Cursor::new(b"abc"));
+        let mut timed = reader.deadline_ref(Duration::from_secs(60));
+        assert_eq!(timed.fill_buf().unwrap(), b"abc");
+
+        timed.set_deadline(Instant::now());
+        let err = timed.fill_buf().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::TimedOut);
+    }
+}
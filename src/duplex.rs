@@ -0,0 +1,191 @@
+//! Independent read and write budgets over a borrowed duplex stream.
+
+use std::{
+    cmp,
+    io::{self, BufRead, Read, Write},
+};
+
+/// A non-owning adapter limiting both directions of a `Read + Write` stream.
+///
+/// Reads behave like [`RefTake`](crate::RefTake): at most `read_limit` bytes
+/// are returned, after which reads report EOF without calling the stream.
+/// Writes accept at most `write_limit` bytes; a write larger than the
+/// remaining budget is shortened, and once the budget is exhausted writes
+/// return `Ok(0)`, which `write_all` reports as `ErrorKind::WriteZero`.
+pub struct RefDuplexLimit<'a, S> {
+    inner: &'a mut S,
+    read_limit: u64,
+    write_limit: u64,
+}
+
+impl<'a, S> RefDuplexLimit<'a, S> {
+    /// Creates a new `RefDuplexLimit` with the given read and write budgets.
+    pub const fn wrap(inner: &'a mut S, read_limit: u64, write_limit: u64) -> Self {
+        Self {
+            inner,
+            read_limit,
+            write_limit,
+        }
+    }
+
+    /// Sets a new read limit.
+    pub fn set_read_limit(&mut self, limit: u64) {
+        self.read_limit = limit;
+    }
+
+    /// Returns the number of bytes that may still be read.
+    pub fn current_read_limit(&self) -> u64 {
+        self.read_limit
+    }
+
+    /// Sets a new write limit.
+    pub fn set_write_limit(&mut self, limit: u64) {
+        self.write_limit = limit;
+    }
+
+    /// Returns the number of bytes that may still be written.
+    pub fn current_write_limit(&self) -> u64 {
+        self.write_limit
+    }
+}
+
+impl<S: Read> Read for RefDuplexLimit<'_, S> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        // Don't call into inner reader at all at EOF because it may still block
+        if self.read_limit == 0 {
+            return Ok(0);
+        }
+
+        let max = cmp::min(buf.len() as u64, self.read_limit) as usize;
+        let n = self.inner.read(&mut buf[..max])?;
+        assert!(
+            n as u64 <= self.read_limit,
+            "number of read bytes exceeds limit"
+        );
+        self.read_limit -= n as u64;
+        Ok(n)
+    }
+}
+
+impl<S: BufRead> BufRead for RefDuplexLimit<'_, S> {
+    fn fill_buf(&mut self) -> Result<&[u8], io::Error> {
+        if self.read_limit == 0 {
+            return Ok(&[]);
+        }
+
+        let buf = self.inner.fill_buf()?;
+        let cap = cmp::min(buf.len() as u64, self.read_limit) as usize;
+        Ok(&buf[..cap])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        let amt = cmp::min(amt as u64, self.read_limit) as usize;
+        self.read_limit -= amt as u64;
+        self.inner.consume(amt);
+    }
+}
+
+impl<S: Write> Write for RefDuplexLimit<'_, S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.write_limit == 0 || buf.is_empty() {
+            return Ok(0);
+        }
+
+        let max = cmp::min(buf.len() as u64, self.write_limit) as usize;
+        let n = self.inner.write(&buf[..max])?;
+        assert!(n <= max, "number of written bytes exceeds limit");
+        self.write_limit -= n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Extension trait to provide a `duplex_limit_ref` method on all `Read + Write` types.
+pub trait RefDuplexLimitExt {
+    /// Wraps the stream in a `RefDuplexLimit` with the given read and write budgets.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Cursor, ErrorKind, Read, Write};
+    /// use reftake::RefDuplexLimitExt;
+    ///
+    /// let mut stream = Cursor::new(vec![0u8; 16]);
+    /// let mut limited = stream.duplex_limit_ref(16, 4);
+    /// let err = limited.write_all(b"too long").unwrap_err();
+    /// assert_eq!(err.kind(), ErrorKind::WriteZero);
+    /// ```
+    fn duplex_limit_ref(&mut self, read_limit: u64, write_limit: u64) -> RefDuplexLimit<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: Read + Write> RefDuplexLimitExt for T {
+    fn duplex_limit_ref(&mut self, read_limit: u64, write_limit: u64) -> RefDuplexLimit<'_, Self> {
+        RefDuplexLimit::wrap(self, read_limit, write_limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// A duplex stream with separate inbound and outbound halves.
+    struct Duplex {
+        inbound: Cursor<Vec<u8>>,
+        outbound: Vec<u8>,
+    }
+
+    impl Read for Duplex {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.inbound.read(buf)
+        }
+    }
+
+    impl Write for Duplex {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.outbound.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_independent_budgets() {
+        let mut stream = Duplex {
+            inbound: Cursor::new(b"request body".to_vec()),
+            outbound: Vec::new(),
+        };
+        {
+            let mut limited = stream.duplex_limit_ref(7, 100);
+            let mut request = String::new();
+            limited.read_to_string(&mut request).unwrap();
+            assert_eq!(request, "request");
+
+            limited.write_all(b"response").unwrap();
+            assert_eq!(limited.current_write_limit(), 92);
+            assert_eq!(limited.current_read_limit(), 0);
+        }
+        assert_eq!(stream.outbound, b"response");
+    }
+
+    #[test]
+    fn test_short_write_at_limit() {
+        let mut stream = Duplex {
+            inbound: Cursor::new(Vec::new()),
+            outbound: Vec::new(),
+        };
+        let mut limited = stream.duplex_limit_ref(0, 3);
+
+        assert_eq!(limited.write(b"abcdef").unwrap(), 3);
+        assert_eq!(limited.write(b"def").unwrap(), 0);
+        limited.set_write_limit(1);
+        assert_eq!(limited.write(b"def").unwrap(), 1);
+    }
+}
@@ -0,0 +1,212 @@
+//! A window following a byte budget kept outside of it.
+
+use std::{
+    cmp,
+    io::{self, BufRead, Read},
+};
+
+/// A budget consulted by [`RefTakeDynamic`] before each read.
+///
+/// A closure returning the bytes left is a `LimitSource` that keeps its
+/// count up to date by itself, such as one looking up what a tenant has
+/// left in an accounting service. Implement the trait to also be told of
+/// the bytes read, and charge them to the budget.
+pub trait LimitSource {
+    /// Returns the number of bytes that may be read now.
+    fn available(&mut self) -> u64;
+
+    /// Reports that `n` bytes were read. Does nothing by default.
+    fn consumed(&mut self, n: u64) {
+        let _ = n;
+    }
+}
+
+impl<F: FnMut() -> u64> LimitSource for F {
+    fn available(&mut self) -> u64 {
+        self()
+    }
+}
+
+/// A non-owning adapter reading at most as many bytes as an external budget allows.
+///
+/// The budget is asked for the bytes available before every read, which
+/// never asks the inner reader for more, and is then told how many were
+/// read. The window ends while the budget is empty, and reading resumes
+/// if it is topped up. This is the blocking counterpart of the async
+/// `DynamicTake`, for budgets that are not a fixed total.
+pub struct RefTakeDynamic<'a, R, S> {
+    inner: &'a mut R,
+    source: S,
+    read: u64,
+    /// The bytes returned by the last `fill_buf` and not consumed yet, which bound `consume`.
+    granted: u64,
+}
+
+impl<'a, R, S: LimitSource> RefTakeDynamic<'a, R, S> {
+    /// Creates a new `RefTakeDynamic` drawing from `source`.
+    pub const fn wrap(inner: &'a mut R, source: S) -> Self {
+        Self {
+            inner,
+            source,
+            read: 0,
+            granted: 0,
+        }
+    }
+
+    /// Returns the number of bytes read so far.
+    pub fn bytes_read(&self) -> u64 {
+        self.read
+    }
+
+    /// Returns the number of bytes the budget allows now.
+    pub fn current_limit(&mut self) -> u64 {
+        self.source.available()
+    }
+
+    /// Returns the budget.
+    pub fn source(&self) -> &S {
+        &self.source
+    }
+
+    fn charge(&mut self, n: usize) {
+        if n > 0 {
+            self.read += n as u64;
+            self.source.consumed(n as u64);
+        }
+    }
+}
+
+impl<R: Read, S: LimitSource> Read for RefTakeDynamic<'_, R, S> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        let available = self.source.available();
+        // Don't call into inner reader at all while the budget is empty because it may still block
+        if available == 0 {
+            return Ok(0);
+        }
+        let max = cmp::min(buf.len() as u64, available) as usize;
+        let n = self.inner.read(&mut buf[..max])?;
+        self.charge(n);
+        Ok(n)
+    }
+}
+
+impl<R: BufRead, S: LimitSource> BufRead for RefTakeDynamic<'_, R, S> {
+    fn fill_buf(&mut self) -> Result<&[u8], io::Error> {
+        let available = self.source.available();
+        self.granted = 0;
+        if available == 0 {
+            return Ok(&[]);
+        }
+        let buf = self.inner.fill_buf()?;
+        let cap = cmp::min(buf.len() as u64, available) as usize;
+        self.granted = cap as u64;
+        Ok(&buf[..cap])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        // Don't let callers read past the budget by passing an overlarge value
+        let amt = cmp::min(amt as u64, self.granted) as usize;
+        self.granted -= amt as u64;
+        self.inner.consume(amt);
+        self.charge(amt);
+    }
+}
+
+/// Extension trait to provide a `take_ref_dynamic` method on all `Read` types.
+pub trait RefTakeDynamicExt {
+    /// Wraps the reader in a `RefTakeDynamic` drawing from `source`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::Read;
+    /// use reftake::{LimitSource, RefTakeDynamicExt};
+    ///
+    /// struct Tenant {
+    ///     left: u64,
+    /// }
+    ///
+    /// impl LimitSource for Tenant {
+    ///     fn available(&mut self) -> u64 {
+    ///         self.left
+    ///     }
+    ///
+    ///     fn consumed(&mut self, n: u64) {
+    ///         self.left -= n;
+    ///     }
+    /// }
+    ///
+    /// let mut upload = &b"0123456789"[..];
+    /// let mut limited = upload.take_ref_dynamic(Tenant { left: 4 });
+    /// let mut buf = Vec::new();
+    /// limited.read_to_end(&mut buf).unwrap();
+    /// assert_eq!(buf, b"0123");
+    /// assert_eq!(limited.source().left, 0);
+    /// ```
+    fn take_ref_dynamic<S: LimitSource>(&mut self, source: S) -> RefTakeDynamic<'_, Self, S>
+    where
+        Self: Sized;
+}
+
+impl<T: Read> RefTakeDynamicExt for T {
+    fn take_ref_dynamic<S: LimitSource>(&mut self, source: S) -> RefTakeDynamic<'_, Self, S> {
+        RefTakeDynamic::wrap(self, source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{cell::Cell, io::BufReader};
+
+    #[test]
+    fn test_closure_budget_is_asked_before_every_read() {
+        let left = Cell::new(3);
+        let mut reader: &[u8] = b"abcdefgh";
+        let mut limited = reader.take_ref_dynamic(|| left.get());
+
+        let mut buf = [0u8; 8];
+        assert_eq!(limited.read(&mut buf).unwrap(), 3);
+        // The closure keeps its own count, so nothing was charged
+        assert_eq!(limited.current_limit(), 3);
+        left.set(0);
+        assert_eq!(limited.read(&mut buf).unwrap(), 0);
+        left.set(2);
+        assert_eq!(limited.read(&mut buf).unwrap(), 2);
+        assert_eq!(&buf[..2], b"de");
+        assert_eq!(limited.bytes_read(), 5);
+        assert_eq!(reader, b"fgh");
+    }
+
+    struct Shared<'c>(&'c Cell<u64>);
+
+    impl LimitSource for Shared<'_> {
+        fn available(&mut self) -> u64 {
+            self.0.get()
+        }
+
+        fn consumed(&mut self, n: u64) {
+            self.0.set(self.0.get() - n);
+        }
+    }
+
+    #[test]
+    fn test_bufread_charges_consumed_bytes() {
+        let left = Cell::new(10);
+        let mut reader = BufReader::with_capacity(4, &b"one\ntwo\nthree\n"[..]);
+        let mut limited = reader.take_ref_dynamic(Shared(&left));
+
+        let mut line = String::new();
+        limited.read_line(&mut line).unwrap();
+        assert_eq!(left.get(), 6);
+        // Nothing is buffered, so nothing is charged
+        limited.consume(100);
+        assert_eq!(left.get(), 6);
+        line.clear();
+        limited.read_line(&mut line).unwrap();
+        limited.read_line(&mut line).unwrap();
+        assert_eq!(line, "two\nth");
+        assert_eq!(left.get(), 0);
+        assert_eq!(limited.bytes_read(), 10);
+    }
+}
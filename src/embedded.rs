@@ -0,0 +1,348 @@
+//! `embedded_io` support for [`RefTake`], [`RefSkip`], [`RefChain`] and [`RefCount`].
+
+use core::cmp;
+
+use embedded_io::{BufRead, Error, ErrorKind, ErrorType, Read};
+
+use crate::{RefChain, RefCount, RefSkip, RefTake};
+
+/// Reports the errors of the inner reader unchanged.
+impl<R: ErrorType> ErrorType for RefTake<'_, R> {
+    type Error = R::Error;
+}
+
+/// Implements `embedded_io::Read` with a byte limit.
+///
+/// Reads are clamped to the remaining limit, and at limit zero the inner
+/// reader is not called at all, so a bounded read never blocks on a UART
+/// for bytes beyond the window.
+impl<R: Read> Read for RefTake<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        if self.limit == 0 {
+            return Ok(0);
+        }
+
+        let max = cmp::min(buf.len() as u64, self.limit) as usize;
+        let n = self.inner.read(&mut buf[..max])?;
+        assert!(n as u64 <= self.limit, "number of read bytes exceeds limit");
+        self.limit -= n as u64;
+        Ok(n)
+    }
+}
+
+/// Implements `embedded_io::BufRead` with a byte limit.
+///
+/// `fill_buf()` returns the inner buffer capped at the remaining limit, and
+/// `consume()` is clamped to the limit as well.
+impl<R: BufRead> BufRead for RefTake<'_, R> {
+    fn fill_buf(&mut self) -> Result<&[u8], Self::Error> {
+        if self.limit == 0 {
+            return Ok(&[]);
+        }
+
+        let buf = self.inner.fill_buf()?;
+        let cap = cmp::min(buf.len() as u64, self.limit) as usize;
+        Ok(&buf[..cap])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        let amt = cmp::min(amt as u64, self.limit) as usize;
+        self.limit -= amt as u64;
+        self.inner.consume(amt);
+    }
+}
+
+/// Extension trait to provide a `take_ref_embedded_io` method on all `embedded_io::Read` types.
+pub trait EmbeddedIoRefTakeExt {
+    /// Wraps the reader in a `RefTake`, allowing limited reading via a mutable reference.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_io::Read;
+    /// use reftake::EmbeddedIoRefTakeExt;
+    ///
+    /// let mut uart: &[u8] = b"AT+OK\r\nnext";
+    /// let mut reply = [0u8; 16];
+    /// let n = uart.take_ref_embedded_io(7).read(&mut reply).unwrap();
+    /// assert_eq!(&reply[..n], b"AT+OK\r\n");
+    /// assert_eq!(uart, b"next");
+    /// ```
+    fn take_ref_embedded_io(&mut self, limit: u64) -> RefTake<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: Read> EmbeddedIoRefTakeExt for T {
+    fn take_ref_embedded_io(&mut self, limit: u64) -> RefTake<'_, Self> {
+        RefTake::wrap(self, limit)
+    }
+}
+
+/// Reports the errors of the inner reader unchanged.
+impl<R: ErrorType> ErrorType for RefSkip<'_, R> {
+    type Error = R::Error;
+}
+
+/// Implements `embedded_io::Read`, discarding the pending prefix on first use.
+///
+/// The prefix is drained through a small stack buffer, to stay cheap on
+/// firmware stacks. If the inner reader ends before the prefix has been
+/// skipped, `Ok(0)` is returned and the rest of the skip is retried on the
+/// next call.
+impl<R: Read> Read for RefSkip<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let mut scratch = [0u8; 64];
+        while self.skip > 0 {
+            let max = cmp::min(self.skip, scratch.len() as u64) as usize;
+            match self.inner.read(&mut scratch[..max]) {
+                Ok(0) => return Ok(0),
+                Ok(n) => self.skip -= n as u64,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        self.inner.read(buf)
+    }
+}
+
+/// Implements `embedded_io::BufRead`, skipping the prefix with `fill_buf`/`consume`.
+impl<R: BufRead> BufRead for RefSkip<'_, R> {
+    fn fill_buf(&mut self) -> Result<&[u8], Self::Error> {
+        while self.skip > 0 {
+            let available = match self.inner.fill_buf() {
+                Ok([]) => return Ok(&[]),
+                Ok(buf) => buf.len(),
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            let amt = cmp::min(self.skip, available as u64) as usize;
+            self.inner.consume(amt);
+            self.skip -= amt as u64;
+        }
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.inner.consume(amt);
+    }
+}
+
+/// Extension trait to provide a `skip_ref_embedded_io` method on all `embedded_io::Read` types.
+pub trait EmbeddedIoRefSkipExt {
+    /// Wraps the reader in a `RefSkip` that discards the first `skip` bytes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_io::Read;
+    /// use reftake::EmbeddedIoRefSkipExt;
+    ///
+    /// let mut uart: &[u8] = b"\x02\x02OK";
+    /// let mut reply = [0u8; 8];
+    /// let n = uart.skip_ref_embedded_io(2).read(&mut reply).unwrap();
+    /// assert_eq!(&reply[..n], b"OK");
+    /// ```
+    fn skip_ref_embedded_io(&mut self, skip: u64) -> RefSkip<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: Read> EmbeddedIoRefSkipExt for T {
+    fn skip_ref_embedded_io(&mut self, skip: u64) -> RefSkip<'_, Self> {
+        RefSkip::wrap(self, skip)
+    }
+}
+
+/// Reports the errors of both readers, which must be of the same type.
+impl<A: ErrorType, B: ErrorType<Error = A::Error>> ErrorType for RefChain<'_, A, B> {
+    type Error = A::Error;
+}
+
+/// Implements `embedded_io::Read`, reading `first` to EOF, then `second`.
+impl<A: Read, B: Read<Error = A::Error>> Read for RefChain<'_, A, B> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        if !self.done_first {
+            match self.first.read(buf)? {
+                0 if !buf.is_empty() => self.done_first = true,
+                n => return Ok(n),
+            }
+        }
+        self.second.read(buf)
+    }
+}
+
+/// Implements `embedded_io::BufRead`, filling from `first` until it is empty, then from `second`.
+impl<A: BufRead, B: BufRead<Error = A::Error>> BufRead for RefChain<'_, A, B> {
+    fn fill_buf(&mut self) -> Result<&[u8], Self::Error> {
+        if !self.done_first {
+            match self.first.fill_buf()? {
+                [] => self.done_first = true,
+                buf => return Ok(buf),
+            }
+        }
+        self.second.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        if !self.done_first {
+            self.first.consume(amt)
+        } else {
+            self.second.consume(amt)
+        }
+    }
+}
+
+/// Extension trait to provide a `chain_ref_embedded_io` method on all `embedded_io::Read` types.
+pub trait EmbeddedIoRefChainExt: ErrorType {
+    /// Chains this reader with `next`, without taking ownership of either.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_io::Read;
+    /// use reftake::EmbeddedIoRefChainExt;
+    ///
+    /// let mut peeked: &[u8] = b"AT";
+    /// let mut uart: &[u8] = b"+OK\r\n";
+    /// let mut reply = [0u8; 8];
+    /// let mut chain = peeked.chain_ref_embedded_io(&mut uart);
+    /// chain.read_exact(&mut reply[..5]).unwrap();
+    /// assert_eq!(&reply[..5], b"AT+OK");
+    /// ```
+    fn chain_ref_embedded_io<'a, B: Read<Error = Self::Error>>(
+        &'a mut self,
+        next: &'a mut B,
+    ) -> RefChain<'a, Self, B>
+    where
+        Self: Sized;
+}
+
+impl<T: Read> EmbeddedIoRefChainExt for T {
+    fn chain_ref_embedded_io<'a, B: Read<Error = Self::Error>>(
+        &'a mut self,
+        next: &'a mut B,
+    ) -> RefChain<'a, Self, B> {
+        RefChain::wrap(self, next)
+    }
+}
+
+/// Reports the errors of the inner reader unchanged.
+impl<R: ErrorType> ErrorType for RefCount<'_, R> {
+    type Error = R::Error;
+}
+
+/// Implements `embedded_io::Read`, counting the bytes read.
+impl<R: Read> Read for RefCount<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let n = self.inner.read(buf)?;
+        self.count += n as u64;
+        Ok(n)
+    }
+}
+
+/// Implements `embedded_io::BufRead`, counting the bytes consumed.
+impl<R: BufRead> BufRead for RefCount<'_, R> {
+    fn fill_buf(&mut self) -> Result<&[u8], Self::Error> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.count += amt as u64;
+        self.inner.consume(amt);
+    }
+}
+
+/// Extension trait to provide a `count_ref_embedded_io` method on all `embedded_io::Read` types.
+pub trait EmbeddedIoRefCountExt {
+    /// Wraps the reader in a `RefCount` that tracks the number of bytes read.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_io::Read;
+    /// use reftake::EmbeddedIoRefCountExt;
+    ///
+    /// let mut uart: &[u8] = b"AT+OK\r\n";
+    /// let mut counter = uart.count_ref_embedded_io();
+    /// counter.read_exact(&mut [0u8; 5]).unwrap();
+    /// assert_eq!(counter.bytes_read(), 5);
+    /// ```
+    fn count_ref_embedded_io(&mut self) -> RefCount<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: Read> EmbeddedIoRefCountExt for T {
+    fn count_ref_embedded_io(&mut self) -> RefCount<'_, Self> {
+        RefCount::wrap(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reads_are_clamped() {
+        let mut reader: &[u8] = b"abcdefgh";
+        let mut take = reader.take_ref_embedded_io(5);
+
+        let mut buf = [0u8; 3];
+        assert_eq!(Read::read(&mut take, &mut buf).unwrap(), 3);
+        assert_eq!(Read::read(&mut take, &mut buf).unwrap(), 2);
+        assert_eq!(&buf[..2], b"de");
+        assert_eq!(Read::read(&mut take, &mut buf).unwrap(), 0);
+        assert_eq!(reader, b"fgh");
+    }
+
+    #[test]
+    fn test_fill_buf_and_consume_are_clamped() {
+        let mut reader: &[u8] = b"0123456789";
+        let mut take = reader.take_ref_embedded_io(4);
+
+        assert_eq!(BufRead::fill_buf(&mut take).unwrap(), b"0123");
+        BufRead::consume(&mut take, 10);
+        assert_eq!(take.current_limit(), 0);
+        assert_eq!(BufRead::fill_buf(&mut take).unwrap(), b"");
+        assert_eq!(reader, b"456789");
+    }
+
+    #[test]
+    fn test_skip_then_take() {
+        let mut reader: &[u8] = b"HDR:payload";
+        let mut skip = reader.skip_ref_embedded_io(4);
+        let mut take = skip.take_ref_embedded_io(3);
+
+        let mut buf = [0u8; 8];
+        assert_eq!(Read::read(&mut take, &mut buf).unwrap(), 3);
+        assert_eq!(&buf[..3], b"pay");
+        assert_eq!(skip.remaining_skip(), 0);
+        assert_eq!(BufRead::fill_buf(&mut skip).unwrap(), b"load");
+
+        let mut short: &[u8] = b"ab";
+        let mut skip = short.skip_ref_embedded_io(5);
+        assert_eq!(BufRead::fill_buf(&mut skip).unwrap(), b"");
+        assert_eq!(skip.remaining_skip(), 3);
+    }
+
+    #[test]
+    fn test_chain_and_count() {
+        let mut head: &[u8] = b"one ";
+        let mut tail: &[u8] = b"two";
+        let mut chain = head.chain_ref_embedded_io(&mut tail);
+        let mut counter = chain.count_ref_embedded_io();
+
+        let mut buf = [0u8; 8];
+        let mut len = 0;
+        loop {
+            match Read::read(&mut counter, &mut buf[len..]).unwrap() {
+                0 => break,
+                n => len += n,
+            }
+        }
+        assert_eq!(&buf[..len], b"one two");
+        assert_eq!(counter.bytes_read(), 7);
+        assert!(chain.first_done());
+    }
+}
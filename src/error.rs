@@ -0,0 +1,435 @@
+//! Error types reported by the crate's adapters.
+
+use core::{error::Error, fmt};
+#[cfg(feature = "std")]
+use std::io::{self, ErrorKind};
+
+/// Error payload reported when a stream produces more bytes than allowed.
+///
+/// Adapters return it wrapped in an `io::Error` of kind
+/// `ErrorKind::InvalidData`; use [`LimitExceeded::from_io`] to tell it apart
+/// from other data errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LimitExceeded {
+    limit: u64,
+}
+
+impl LimitExceeded {
+    /// Creates a new `LimitExceeded` for the given byte limit.
+    pub const fn new(limit: u64) -> Self {
+        Self { limit }
+    }
+
+    /// Returns the limit that was exceeded.
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+
+    /// Returns the `LimitExceeded` payload of an I/O error, if it has one.
+    #[cfg(feature = "std")]
+    pub fn from_io(err: &io::Error) -> Option<&LimitExceeded> {
+        payload(err)
+    }
+}
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "byte limit of {} exceeded", self.limit)
+    }
+}
+
+impl Error for LimitExceeded {}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for LimitExceeded {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        defmt::write!(f, "byte limit of {=u64} exceeded", self.limit)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<LimitExceeded> for io::Error {
+    fn from(err: LimitExceeded) -> Self {
+        io::Error::new(ErrorKind::InvalidData, err)
+    }
+}
+
+/// Error payload reported when a stream holds a byte sequence that is not valid UTF-8.
+///
+/// Adapters return it wrapped in an `io::Error` of kind
+/// `ErrorKind::InvalidData`; use [`InvalidUtf8::from_io`] to tell it apart
+/// from other data errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidUtf8 {
+    bytes: [u8; 4],
+    len: usize,
+    offset: Option<u64>,
+}
+
+impl InvalidUtf8 {
+    /// Creates a new `InvalidUtf8` for the given offending bytes (at most 4).
+    pub fn new(bytes: &[u8]) -> Self {
+        let len = bytes.len().min(4);
+        let mut buf = [0; 4];
+        buf[..len].copy_from_slice(&bytes[..len]);
+        Self {
+            bytes: buf,
+            len,
+            offset: None,
+        }
+    }
+
+    /// Records the stream offset of the first offending byte.
+    pub const fn with_offset(mut self, offset: u64) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    /// Returns the bytes that could not be decoded.
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+
+    /// Returns the stream offset of the first offending byte, if the adapter tracked it.
+    pub fn offset(&self) -> Option<u64> {
+        self.offset
+    }
+
+    /// Returns the `InvalidUtf8` payload of an I/O error, if it has one.
+    #[cfg(feature = "std")]
+    pub fn from_io(err: &io::Error) -> Option<&InvalidUtf8> {
+        payload(err)
+    }
+}
+
+impl fmt::Display for InvalidUtf8 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid UTF-8 sequence {:02x?}", self.bytes())?;
+        if let Some(offset) = self.offset {
+            write!(f, " at offset {offset}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for InvalidUtf8 {}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for InvalidUtf8 {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        defmt::write!(f, "invalid UTF-8 sequence {=[u8]:02x}", self.bytes());
+        if let Some(offset) = self.offset {
+            defmt::write!(f, " at offset {=u64}", offset)
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<InvalidUtf8> for io::Error {
+    fn from(err: InvalidUtf8) -> Self {
+        io::Error::new(ErrorKind::InvalidData, err)
+    }
+}
+
+/// Error payload reported when a stream continues after a value that should have ended it.
+///
+/// Adapters return it wrapped in an `io::Error` of kind
+/// `ErrorKind::InvalidData`; use [`TrailingData::from_io`] to tell it apart
+/// from other data errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TrailingData;
+
+impl TrailingData {
+    /// Returns the `TrailingData` payload of an I/O error, if it has one.
+    #[cfg(feature = "std")]
+    pub fn from_io(err: &io::Error) -> Option<&TrailingData> {
+        payload(err)
+    }
+}
+
+impl fmt::Display for TrailingData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("trailing data after the end of the value")
+    }
+}
+
+impl Error for TrailingData {}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for TrailingData {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        defmt::write!(f, "trailing data after the end of the value")
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<TrailingData> for io::Error {
+    fn from(err: TrailingData) -> Self {
+        io::Error::new(ErrorKind::InvalidData, err)
+    }
+}
+
+/// Error payload reported when decompressed output grows too large relative to its input.
+///
+/// Returned by [`RatioGuard`](crate::RatioGuard) wrapped in an `io::Error` of
+/// kind `ErrorKind::InvalidData`; use [`RatioExceeded::from_io`] to tell it
+/// apart from other data errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RatioExceeded {
+    compressed: u64,
+    decompressed: u64,
+    max_ratio: u64,
+}
+
+impl RatioExceeded {
+    /// Creates a new `RatioExceeded` for the given byte counts and ceiling.
+    pub const fn new(compressed: u64, decompressed: u64, max_ratio: u64) -> Self {
+        Self {
+            compressed,
+            decompressed,
+            max_ratio,
+        }
+    }
+
+    /// Returns the number of compressed bytes read when the ceiling was crossed.
+    pub fn compressed(&self) -> u64 {
+        self.compressed
+    }
+
+    /// Returns the number of decompressed bytes produced when the ceiling was crossed.
+    pub fn decompressed(&self) -> u64 {
+        self.decompressed
+    }
+
+    /// Returns the ratio that was exceeded.
+    pub fn max_ratio(&self) -> u64 {
+        self.max_ratio
+    }
+
+    /// Returns the `RatioExceeded` payload of an I/O error, if it has one.
+    #[cfg(feature = "std")]
+    pub fn from_io(err: &io::Error) -> Option<&RatioExceeded> {
+        payload(err)
+    }
+}
+
+impl fmt::Display for RatioExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} bytes decompressed from {} exceed the ratio of {}",
+            self.decompressed, self.compressed, self.max_ratio
+        )
+    }
+}
+
+impl Error for RatioExceeded {}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for RatioExceeded {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        defmt::write!(
+            f,
+            "{=u64} bytes decompressed from {=u64} exceed the ratio of {=u64}",
+            self.decompressed,
+            self.compressed,
+            self.max_ratio
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<RatioExceeded> for io::Error {
+    fn from(err: RatioExceeded) -> Self {
+        io::Error::new(ErrorKind::InvalidData, err)
+    }
+}
+
+/// Error wrapper carrying the bytes read just before an error, and where it happened.
+///
+/// Returned by [`RefHistory`](crate::RefHistory) in place of the errors of
+/// its inner reader, in an `io::Error` of the same kind. The original error
+/// is the [`source`](Error::source), and the `from_io` accessors of the
+/// other payloads look through the wrapper.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct ReadContext {
+    offset: u64,
+    recent: Vec<u8>,
+    source: io::Error,
+}
+
+#[cfg(feature = "std")]
+impl ReadContext {
+    pub(crate) fn new(offset: u64, recent: Vec<u8>, source: io::Error) -> Self {
+        Self {
+            offset,
+            recent,
+            source,
+        }
+    }
+
+    /// Returns the offset at which the error occurred, relative to the start of the adapter.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Returns the bytes read immediately before the error, oldest first.
+    pub fn recent(&self) -> &[u8] {
+        &self.recent
+    }
+
+    /// Returns the error of the inner reader.
+    pub fn inner(&self) -> &io::Error {
+        &self.source
+    }
+
+    /// Returns the recent bytes in `hexdump -C` layout, numbered by their stream offsets.
+    pub fn hex_dump(&self) -> String {
+        let mut out = Vec::new();
+        let start = self.offset - self.recent.len() as u64;
+        crate::hexdump::write_hex_dump(&mut out, start, &self.recent)
+            .expect("writing to a Vec cannot fail");
+        String::from_utf8(out).expect("a hex dump is ASCII")
+    }
+
+    /// Returns the `ReadContext` of an I/O error, if it has one.
+    pub fn from_io(err: &io::Error) -> Option<&ReadContext> {
+        err.get_ref()?.downcast_ref()
+    }
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for ReadContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at offset {}", self.source, self.offset)?;
+        if !self.recent.is_empty() {
+            write!(f, ", after")?;
+            for b in &self.recent {
+                write!(f, " {b:02x}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for ReadContext {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+/// Error payload reported when one level of a [`BudgetTracker`](crate::BudgetTracker) runs out.
+///
+/// Returned wrapped in an `io::Error` of kind `ErrorKind::InvalidData`;
+/// use [`BudgetExhausted::from_io`] to tell it apart from other data errors
+/// and find out which budget was used up.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BudgetExhausted {
+    level: std::sync::Arc<str>,
+    limit: u64,
+}
+
+#[cfg(feature = "std")]
+impl BudgetExhausted {
+    pub(crate) fn new(level: std::sync::Arc<str>, limit: u64) -> Self {
+        Self { level, limit }
+    }
+
+    /// Returns the name of the budget that ran out.
+    pub fn level(&self) -> &str {
+        &self.level
+    }
+
+    /// Returns the limit of the budget that ran out.
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+
+    /// Returns the `BudgetExhausted` payload of an I/O error, if it has one.
+    pub fn from_io(err: &io::Error) -> Option<&BudgetExhausted> {
+        payload(err)
+    }
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for BudgetExhausted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "byte budget `{}` of {} exhausted",
+            self.level, self.limit
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for BudgetExhausted {}
+
+#[cfg(feature = "std")]
+impl From<BudgetExhausted> for io::Error {
+    fn from(err: BudgetExhausted) -> Self {
+        io::Error::new(ErrorKind::InvalidData, err)
+    }
+}
+
+/// Returns the payload of type `T` of an I/O error, looking through a [`ReadContext`].
+#[cfg(feature = "std")]
+pub(crate) fn payload<T: Error + 'static>(err: &io::Error) -> Option<&T> {
+    let inner = err.get_ref()?;
+    match inner.downcast_ref::<ReadContext>() {
+        Some(context) => payload(&context.source),
+        None => inner.downcast_ref(),
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip_through_io_error() {
+        let err: io::Error = LimitExceeded::new(42).into();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(LimitExceeded::from_io(&err), Some(&LimitExceeded::new(42)));
+        assert_eq!(err.to_string(), "byte limit of 42 exceeded");
+    }
+
+    #[test]
+    fn test_other_errors_are_not_limit_exceeded() {
+        let err = io::Error::new(ErrorKind::InvalidData, "bad data");
+        assert_eq!(LimitExceeded::from_io(&err), None);
+        let err = io::Error::from(ErrorKind::UnexpectedEof);
+        assert_eq!(LimitExceeded::from_io(&err), None);
+    }
+
+    #[test]
+    fn test_invalid_utf8_roundtrip() {
+        let err: io::Error = InvalidUtf8::new(&[0xc3, 0x28]).into();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(InvalidUtf8::from_io(&err).unwrap().bytes(), &[0xc3, 0x28]);
+        assert_eq!(LimitExceeded::from_io(&err), None);
+        assert_eq!(err.to_string(), "invalid UTF-8 sequence [c3, 28]");
+    }
+
+    #[test]
+    fn test_ratio_exceeded_roundtrip() {
+        let err: io::Error = RatioExceeded::new(10, 2000, 100).into();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(RatioExceeded::from_io(&err).unwrap().decompressed(), 2000);
+        assert_eq!(
+            err.to_string(),
+            "2000 bytes decompressed from 10 exceed the ratio of 100"
+        );
+    }
+
+    #[test]
+    fn test_trailing_data_roundtrip() {
+        let err: io::Error = TrailingData.into();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(TrailingData::from_io(&err), Some(&TrailingData));
+        assert_eq!(LimitExceeded::from_io(&err), None);
+    }
+}
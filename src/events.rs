@@ -0,0 +1,219 @@
+//! Structured events describing the read calls made through a borrowed reader.
+
+use std::io::{self, BufRead, Read};
+
+/// A call, or a change of state, observed by a [`RefEvents`] adapter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadEvent {
+    /// A `read` call returned; `requested` is the length of the caller's buffer.
+    Read {
+        /// The length of the buffer passed to `read`.
+        requested: usize,
+        /// The number of bytes the call returned.
+        returned: usize,
+    },
+    /// A `fill_buf` call returned a buffer of `len` bytes.
+    FillBuf {
+        /// The length of the returned buffer.
+        len: usize,
+    },
+    /// `consume` was called with `amt`.
+    Consume {
+        /// The amount passed to `consume`.
+        amt: usize,
+    },
+    /// The limit given to [`RefEvents::with_limit`] has been read to the end.
+    LimitReached,
+    /// The inner reader reported EOF before any limit was reached.
+    Drained,
+}
+
+/// An observer receiving the [`ReadEvent`]s of a [`RefEvents`] adapter.
+///
+/// Implemented for closures taking a `ReadEvent`, and so for mutable
+/// references to them.
+pub trait EventSink {
+    /// Receives one event, in the order the calls were made.
+    fn event(&mut self, event: ReadEvent);
+}
+
+impl<F: FnMut(ReadEvent)> EventSink for F {
+    fn event(&mut self, event: ReadEvent) {
+        self(event)
+    }
+}
+
+/// A non-owning adapter reporting every read call of its inner reader to an [`EventSink`].
+///
+/// Where [`RefInspect`](crate::RefInspect) shows the data, this shows the
+/// call pattern: how large the buffers were, how much each call returned,
+/// and how `fill_buf` and `consume` alternate, which is what tells a
+/// reader making tiny reads apart from a slow source. Failed calls are not
+/// reported. Reads are forwarded unchanged.
+///
+/// The inner reader running out is reported as [`ReadEvent::Drained`]. To
+/// have the end of a window reported as [`ReadEvent::LimitReached`]
+/// instead, pass the window's limit to [`RefEvents::with_limit`]. Each of
+/// the two is reported once.
+pub struct RefEvents<'a, R, S> {
+    inner: &'a mut R,
+    sink: S,
+    limit: Option<u64>,
+    ended: bool,
+}
+
+impl<'a, R, S: EventSink> RefEvents<'a, R, S> {
+    /// Creates a new `RefEvents` reporting the calls made on `inner` to `sink`.
+    pub const fn wrap(inner: &'a mut R, sink: S) -> Self {
+        Self {
+            inner,
+            sink,
+            limit: None,
+            ended: false,
+        }
+    }
+
+    /// Reports [`ReadEvent::LimitReached`] once `limit` bytes have been read through the adapter.
+    pub fn with_limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Consumes the adapter, returning the sink.
+    pub fn into_sink(self) -> S {
+        self.sink
+    }
+
+    fn advance(&mut self, n: usize) {
+        if let Some(limit) = &mut self.limit
+            && n > 0
+        {
+            *limit = limit.saturating_sub(n as u64);
+            if *limit == 0 && !self.ended {
+                self.ended = true;
+                self.sink.event(ReadEvent::LimitReached);
+            }
+        }
+    }
+
+    fn eof(&mut self) {
+        if !self.ended {
+            self.ended = true;
+            self.sink.event(ReadEvent::Drained);
+        }
+    }
+}
+
+impl<R: Read, S: EventSink> Read for RefEvents<'_, R, S> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        let n = self.inner.read(buf)?;
+        self.sink.event(ReadEvent::Read {
+            requested: buf.len(),
+            returned: n,
+        });
+        if n == 0 && !buf.is_empty() {
+            self.eof();
+        }
+        self.advance(n);
+        Ok(n)
+    }
+}
+
+impl<R: BufRead, S: EventSink> BufRead for RefEvents<'_, R, S> {
+    fn fill_buf(&mut self) -> Result<&[u8], io::Error> {
+        let len = self.inner.fill_buf()?.len();
+        self.sink.event(ReadEvent::FillBuf { len });
+        if len == 0 {
+            self.eof();
+        }
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.sink.event(ReadEvent::Consume { amt });
+        self.inner.consume(amt);
+        self.advance(amt);
+    }
+}
+
+/// Extension trait to provide an `events_ref` method on all `Read` types.
+pub trait RefEventsExt {
+    /// Wraps the reader in a `RefEvents` that reports every call to `sink`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Cursor, Read};
+    /// use reftake::{ReadEvent, RefEventsExt, RefTakeExt};
+    ///
+    /// let mut cursor = Cursor::new(b"hello world");
+    /// let mut take = cursor.take_ref(5);
+    /// let mut events = Vec::new();
+    /// let mut buf = [0u8; 8];
+    /// let mut reader = take.events_ref(|e| events.push(e)).with_limit(5);
+    /// while reader.read(&mut buf).unwrap() > 0 {}
+    ///
+    /// assert_eq!(
+    ///     events,
+    ///     [
+    ///         ReadEvent::Read { requested: 8, returned: 5 },
+    ///         ReadEvent::LimitReached,
+    ///         ReadEvent::Read { requested: 8, returned: 0 },
+    ///     ]
+    /// );
+    /// ```
+    fn events_ref<S: EventSink>(&mut self, sink: S) -> RefEvents<'_, Self, S>
+    where
+        Self: Sized;
+}
+
+impl<T: Read> RefEventsExt for T {
+    fn events_ref<S: EventSink>(&mut self, sink: S) -> RefEvents<'_, Self, S> {
+        RefEvents::wrap(self, sink)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufReader, Cursor};
+
+    #[test]
+    fn test_drained_source_is_reported_once() {
+        let mut reader = Cursor::new(b"abc");
+        let mut events = Vec::new();
+        {
+            let mut adapter = reader.events_ref(|e| events.push(e)).with_limit(10);
+            let mut buf = [0u8; 2];
+            while adapter.read(&mut buf).unwrap() > 0 {}
+            assert_eq!(adapter.read(&mut buf).unwrap(), 0);
+            assert_eq!(adapter.read(&mut []).unwrap(), 0);
+        }
+
+        let drained = events.iter().filter(|e| **e == ReadEvent::Drained);
+        assert_eq!(drained.count(), 1);
+        assert!(!events.contains(&ReadEvent::LimitReached));
+        assert_eq!(
+            events.last(),
+            Some(&ReadEvent::Read {
+                requested: 0,
+                returned: 0
+            })
+        );
+    }
+
+    #[test]
+    fn test_buffered_call_pattern() {
+        let mut reader = BufReader::with_capacity(4, Cursor::new(b"ab\ncd"));
+        let mut events = Vec::new();
+        let mut line = String::new();
+        {
+            let mut adapter = reader.events_ref(|e| events.push(e));
+            adapter.read_line(&mut line).unwrap();
+        }
+        assert_eq!(
+            events,
+            [ReadEvent::FillBuf { len: 4 }, ReadEvent::Consume { amt: 3 }]
+        );
+    }
+}
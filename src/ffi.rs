@@ -0,0 +1,293 @@
+//! A C API over limited readers, for embedding the crate in C and C++ programs.
+//!
+//! A `ReftakeReader` is an opaque handle owning a source, a read callback
+//! or a borrowed file descriptor, and the number of bytes that may still
+//! be read from it. Reads go through a [`RefTake`](crate::RefTake), so the
+//! source is never asked for more than the limit. No Rust panic crosses
+//! the boundary: every function catches it and reports it as an error.
+//!
+//! ```c
+//! ReftakeReader *body = reftake_reader_from_fd(sock, content_length);
+//! char buf[4096];
+//! intptr_t n;
+//! while ((n = reftake_read(body, buf, sizeof buf)) > 0) {
+//!     consume(buf, n);
+//! }
+//! if (n < 0) {
+//!     log_error(reftake_last_error(body));
+//! }
+//! reftake_reader_free(body);
+//! ```
+
+use std::{
+    ffi::{CString, c_char, c_void},
+    io::{self, ErrorKind, Read},
+    panic::{self, AssertUnwindSafe},
+    ptr, slice,
+};
+
+use crate::RefTakeExt;
+
+/// A read callback: reads up to `len` bytes into `buf`, returning how many,
+/// `0` at the end of the source, or a negated `errno` value on error.
+pub type ReftakeReadFn = unsafe extern "C" fn(ctx: *mut c_void, buf: *mut u8, len: usize) -> isize;
+
+/// An opaque limited reader handle.
+pub struct ReftakeReader {
+    source: Box<dyn Read>,
+    limit: u64,
+    last_error: Option<CString>,
+}
+
+struct Callback {
+    read: ReftakeReadFn,
+    ctx: *mut c_void,
+}
+
+impl Read for Callback {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // SAFETY: the caller of `reftake_reader_from_callback` guarantees the
+        // callback may be called with `ctx` and a writable buffer
+        let n = unsafe { (self.read)(self.ctx, buf.as_mut_ptr(), buf.len()) };
+        match usize::try_from(n) {
+            Ok(n) if n <= buf.len() => Ok(n),
+            Ok(_) => Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "read callback returned more bytes than asked for",
+            )),
+            Err(_) => Err(io::Error::from_raw_os_error(n.unsigned_abs() as i32)),
+        }
+    }
+}
+
+impl ReftakeReader {
+    fn new(source: Box<dyn Read>, limit: u64) -> *mut Self {
+        Box::into_raw(Box::new(Self {
+            source,
+            limit,
+            last_error: None,
+        }))
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut take = self.source.take_ref(self.limit);
+        let result = loop {
+            match take.read(buf) {
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                result => break result,
+            }
+        };
+        self.limit = take.current_limit();
+        result
+    }
+
+    fn fail(&mut self, message: String) -> isize {
+        // A message with a NUL in it is cut at the NUL
+        let message = message.split('\0').next().unwrap_or_default();
+        self.last_error = CString::new(message).ok();
+        -1
+    }
+}
+
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic");
+    format!("panic: {message}")
+}
+
+/// Creates a reader of at most `limit` bytes pulled through `read`.
+///
+/// Returns null if `read` is null. Free the reader with
+/// [`reftake_reader_free`].
+///
+/// # Safety
+///
+/// `read` must be safe to call with `ctx` and any writable buffer, for as
+/// long as the reader exists, and on the thread the reader is used on.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn reftake_reader_from_callback(
+    read: Option<ReftakeReadFn>,
+    ctx: *mut c_void,
+    limit: u64,
+) -> *mut ReftakeReader {
+    match read {
+        Some(read) => ReftakeReader::new(Box::new(Callback { read, ctx }), limit),
+        None => ptr::null_mut(),
+    }
+}
+
+/// Creates a reader of at most `limit` bytes of the file descriptor `fd`.
+///
+/// The descriptor is borrowed: it is read from its current position and
+/// not closed when the reader is freed with [`reftake_reader_free`].
+///
+/// # Safety
+///
+/// `fd` must be an open file descriptor for as long as the reader exists.
+#[cfg(unix)]
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn reftake_reader_from_fd(
+    fd: std::os::fd::RawFd,
+    limit: u64,
+) -> *mut ReftakeReader {
+    use std::{fs::File, mem::ManuallyDrop, os::fd::FromRawFd};
+
+    struct Fd(ManuallyDrop<File>);
+
+    impl Read for Fd {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.0.read(buf)
+        }
+    }
+
+    // SAFETY: the caller guarantees `fd` stays open, and `ManuallyDrop` keeps it from being closed
+    let file = unsafe { File::from_raw_fd(fd) };
+    ReftakeReader::new(Box::new(Fd(ManuallyDrop::new(file))), limit)
+}
+
+/// Reads up to `len` bytes into `buf`, returning how many were read.
+///
+/// Returns `0` once the limit is reached or the source has ended, and
+/// `-1` on error, including a null `reader`; the message is then
+/// available from [`reftake_last_error`]. Interrupted reads are retried.
+///
+/// # Safety
+///
+/// `reader` must be null or a live reader, not used by another thread
+/// during the call, and `buf` must be valid for writes of `len` bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn reftake_read(
+    reader: *mut ReftakeReader,
+    buf: *mut u8,
+    len: usize,
+) -> isize {
+    // SAFETY: the caller guarantees `reader` is null or live and not shared
+    let Some(reader) = (unsafe { reader.as_mut() }) else {
+        return -1;
+    };
+    if len == 0 {
+        return 0;
+    }
+    if buf.is_null() {
+        return reader.fail("null buffer".to_string());
+    }
+    // SAFETY: the caller guarantees `buf` is valid for writes of `len` bytes
+    let buf = unsafe { slice::from_raw_parts_mut(buf, len.min(isize::MAX as usize)) };
+    match panic::catch_unwind(AssertUnwindSafe(|| reader.read(buf))) {
+        Ok(Ok(n)) => n as isize,
+        Ok(Err(e)) => reader.fail(e.to_string()),
+        Err(payload) => reader.fail(panic_message(&*payload)),
+    }
+}
+
+/// Returns the number of bytes that may still be read, or `0` for a null `reader`.
+///
+/// # Safety
+///
+/// `reader` must be null or a live reader.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn reftake_remaining(reader: *const ReftakeReader) -> u64 {
+    // SAFETY: the caller guarantees `reader` is null or live
+    unsafe { reader.as_ref() }.map_or(0, |reader| reader.limit)
+}
+
+/// Returns the message of the last error of `reader`, or null if there was none.
+///
+/// The string belongs to the reader and is valid until its next read or
+/// until it is freed.
+///
+/// # Safety
+///
+/// `reader` must be null or a live reader.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn reftake_last_error(reader: *const ReftakeReader) -> *const c_char {
+    // SAFETY: the caller guarantees `reader` is null or live
+    unsafe { reader.as_ref() }
+        .and_then(|reader| reader.last_error.as_ref())
+        .map_or(ptr::null(), |message| message.as_ptr())
+}
+
+/// Frees a reader created by this API. Does nothing if `reader` is null.
+///
+/// # Safety
+///
+/// `reader` must be null or a live reader, and is not live afterwards.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn reftake_reader_free(reader: *mut ReftakeReader) {
+    if !reader.is_null() {
+        // SAFETY: the caller guarantees `reader` came from `Box::into_raw` here and is live
+        let reader = unsafe { Box::from_raw(reader) };
+        // A panic of the source's destructor must not unwind into C
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| drop(reader)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+
+    /// Serves the bytes of a `&[u8]` behind `ctx`, failing with `EIO` on an empty source.
+    unsafe extern "C" fn read_slice(ctx: *mut c_void, buf: *mut u8, len: usize) -> isize {
+        let source = unsafe { &mut *ctx.cast::<&[u8]>() };
+        if source.is_empty() {
+            return -5;
+        }
+        let n = len.min(source.len());
+        unsafe { ptr::copy_nonoverlapping(source.as_ptr(), buf, n) };
+        *source = &source[n..];
+        n as isize
+    }
+
+    #[test]
+    fn test_callback_reader_stops_at_the_limit() {
+        let mut source: &[u8] = b"0123456789";
+        let mut buf = [0u8; 8];
+        unsafe {
+            let reader =
+                reftake_reader_from_callback(Some(read_slice), (&raw mut source).cast(), 6);
+            assert_eq!(reftake_read(reader, buf.as_mut_ptr(), 4), 4);
+            assert_eq!(reftake_remaining(reader), 2);
+            assert_eq!(reftake_read(reader, buf.as_mut_ptr(), 8), 2);
+            assert_eq!(reftake_read(reader, buf.as_mut_ptr(), 8), 0);
+            assert!(reftake_last_error(reader).is_null());
+            reftake_reader_free(reader);
+        }
+        assert_eq!(&buf[..2], b"45");
+        assert_eq!(source, b"6789");
+    }
+
+    struct Panicking;
+
+    impl Read for Panicking {
+        fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+            panic!("source drained");
+        }
+    }
+
+    #[test]
+    fn test_errors_and_panics_are_reported() {
+        let mut source: &[u8] = b"";
+        let mut buf = [0u8; 8];
+        unsafe {
+            let reader =
+                reftake_reader_from_callback(Some(read_slice), (&raw mut source).cast(), 6);
+            assert_eq!(reftake_read(reader, buf.as_mut_ptr(), 8), -1);
+            let message = CStr::from_ptr(reftake_last_error(reader));
+            let expected = io::Error::from_raw_os_error(5).to_string();
+            assert_eq!(message.to_str().unwrap(), expected);
+            reftake_reader_free(reader);
+
+            let reader = ReftakeReader::new(Box::new(Panicking), 6);
+            assert_eq!(reftake_read(reader, buf.as_mut_ptr(), 8), -1);
+            let message = CStr::from_ptr(reftake_last_error(reader));
+            assert_eq!(message.to_str().unwrap(), "panic: source drained");
+            reftake_reader_free(reader);
+            assert_eq!(reftake_read(ptr::null_mut(), buf.as_mut_ptr(), 8), -1);
+            assert!(reftake_reader_from_callback(None, ptr::null_mut(), 1).is_null());
+        }
+    }
+}
@@ -0,0 +1,145 @@
+//! A bounded `fmt::Write` adapter over a borrowed formatter sink.
+
+use std::fmt;
+
+/// What the budget of a [`RefFmtLimit`] is measured in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Unit {
+    Bytes,
+    Chars,
+}
+
+/// A non-owning adapter that caps the amount of text written to a `fmt::Write` sink.
+///
+/// Output is forwarded until the budget runs out. The write that crosses it
+/// is cut at the last whole character that fits, and `fmt::Error` is
+/// returned; every later write fails the same way without forwarding
+/// anything. Whether this happened can be checked with [`RefFmtLimit::is_truncated`].
+pub struct RefFmtLimit<'a, W> {
+    inner: &'a mut W,
+    remaining: usize,
+    unit: Unit,
+    truncated: bool,
+}
+
+impl<'a, W> RefFmtLimit<'a, W> {
+    /// Creates a new `RefFmtLimit` that forwards at most `max_bytes` bytes of UTF-8.
+    pub const fn wrap(inner: &'a mut W, max_bytes: usize) -> Self {
+        Self {
+            inner,
+            remaining: max_bytes,
+            unit: Unit::Bytes,
+            truncated: false,
+        }
+    }
+
+    /// Creates a new `RefFmtLimit` that forwards at most `max_chars` characters.
+    pub const fn wrap_chars(inner: &'a mut W, max_chars: usize) -> Self {
+        Self {
+            inner,
+            remaining: max_chars,
+            unit: Unit::Chars,
+            truncated: false,
+        }
+    }
+
+    /// Returns the remaining budget, in bytes or characters depending on the constructor.
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+    /// Returns `true` if output has been cut off because the budget was exceeded.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+}
+
+impl<W: fmt::Write> fmt::Write for RefFmtLimit<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if self.truncated {
+            return Err(fmt::Error);
+        }
+
+        let (end, used) = match self.unit {
+            Unit::Bytes if s.len() <= self.remaining => (s.len(), s.len()),
+            Unit::Bytes => {
+                let end = s.floor_char_boundary(self.remaining);
+                (end, end)
+            }
+            Unit::Chars => match s.char_indices().nth(self.remaining) {
+                Some((end, _)) => (end, self.remaining),
+                None => (s.len(), s.chars().count()),
+            },
+        };
+
+        self.inner.write_str(&s[..end])?;
+        self.remaining -= used;
+        if end < s.len() {
+            self.truncated = true;
+            return Err(fmt::Error);
+        }
+        Ok(())
+    }
+}
+
+/// Extension trait to provide a `fmt_limit_ref` method on all `fmt::Write` types.
+pub trait RefFmtLimitExt {
+    /// Wraps the sink in a `RefFmtLimit` accepting at most `max_bytes` bytes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::fmt::Write;
+    /// use reftake::RefFmtLimitExt;
+    ///
+    /// let mut out = String::new();
+    /// let mut limited = out.fmt_limit_ref(8);
+    /// assert!(write!(limited, "name={}", "untrusted input").is_err());
+    /// assert!(limited.is_truncated());
+    /// assert_eq!(out, "name=unt");
+    /// ```
+    fn fmt_limit_ref(&mut self, max_bytes: usize) -> RefFmtLimit<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: fmt::Write> RefFmtLimitExt for T {
+    fn fmt_limit_ref(&mut self, max_bytes: usize) -> RefFmtLimit<'_, Self> {
+        RefFmtLimit::wrap(self, max_bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fmt::Write;
+
+    #[test]
+    fn test_within_budget() {
+        let mut out = String::new();
+        let mut limited = out.fmt_limit_ref(10);
+        write!(limited, "{}-{}", 1, 2).unwrap();
+        assert_eq!(limited.remaining(), 7);
+        assert!(!limited.is_truncated());
+        assert_eq!(out, "1-2");
+    }
+
+    #[test]
+    fn test_byte_budget_cuts_at_char_boundary() {
+        let mut out = String::new();
+        let mut limited = out.fmt_limit_ref(4);
+        assert!(limited.write_str("aéé").is_err());
+        assert!(limited.write_str("b").is_err());
+        assert!(limited.is_truncated());
+        assert_eq!(out, "aé");
+    }
+
+    #[test]
+    fn test_char_budget() {
+        let mut out = String::new();
+        let mut limited = RefFmtLimit::wrap_chars(&mut out, 3);
+        limited.write_str("éé").unwrap();
+        assert!(limited.write_str("éé").is_err());
+        assert_eq!(out, "ééé");
+    }
+}
@@ -0,0 +1,843 @@
+//! Iteration over length-prefixed frames of a borrowed reader.
+
+use std::{
+    error::Error,
+    fmt,
+    io::{self, BufRead, ErrorKind, Read},
+    task::Poll,
+};
+
+use crate::{
+    Endian, LengthPrefix, LimitExceeded, ReadVarint, RefCountExt, RefTake, skip::discard_by_reading,
+};
+
+/// How the length header of a frame is encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum LengthEncoding {
+    /// A single byte.
+    U8,
+    /// A 2-byte integer in the configured byte order.
+    U16,
+    /// A 4-byte integer in the configured byte order.
+    #[default]
+    U32,
+    /// An 8-byte integer in the configured byte order.
+    U64,
+    /// An unsigned LEB128 varint; the byte order setting does not apply.
+    Varint,
+}
+
+/// Framing parameters of a [`FrameReader`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameConfig {
+    /// Encoding of the length header.
+    pub length: LengthEncoding,
+    /// Byte order of fixed-width length headers.
+    pub endian: Endian,
+    /// Whether the encoded length counts the header itself as well as the body.
+    pub length_includes_header: bool,
+    /// Longest accepted frame body, in bytes.
+    pub max_frame_len: u64,
+}
+
+impl Default for FrameConfig {
+    /// A big-endian `u32` length of the body alone, with bodies of up to 8 MiB.
+    fn default() -> Self {
+        Self {
+            length: LengthEncoding::U32,
+            endian: Endian::Big,
+            length_includes_header: false,
+            max_frame_len: 8 * 1024 * 1024,
+        }
+    }
+}
+
+/// Error payload reported when a [`FrameReader`] meets more frames, or more
+/// frame bytes, than its caps allow.
+///
+/// Returned wrapped in an `io::Error` of kind `ErrorKind::InvalidData`;
+/// use [`FrameQuotaExceeded::from_io`] to tell it apart from other data
+/// errors, such as the [`LimitExceeded`] of a single frame that is too long.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameQuotaExceeded {
+    /// The stream holds more than `max` frames.
+    Count {
+        /// The cap set with [`FrameReader::max_frames`].
+        max: u64,
+    },
+    /// The frame bodies add up to more than `max` bytes.
+    TotalLen {
+        /// The cap set with [`FrameReader::max_total_len`].
+        max: u64,
+    },
+}
+
+impl FrameQuotaExceeded {
+    /// Returns the `FrameQuotaExceeded` payload of an I/O error, if it has one.
+    pub fn from_io(err: &io::Error) -> Option<&FrameQuotaExceeded> {
+        crate::error::payload(err)
+    }
+}
+
+impl fmt::Display for FrameQuotaExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrameQuotaExceeded::Count { max } => write!(f, "more than {max} frames"),
+            FrameQuotaExceeded::TotalLen { max } => {
+                write!(f, "frame bodies add up to more than {max} bytes")
+            }
+        }
+    }
+}
+
+impl Error for FrameQuotaExceeded {}
+
+impl From<FrameQuotaExceeded> for io::Error {
+    fn from(err: FrameQuotaExceeded) -> Self {
+        io::Error::new(ErrorKind::InvalidData, err)
+    }
+}
+
+/// Gives an EOF inside a header a more helpful message than `read_exact`'s.
+pub(crate) fn header_eof(e: io::Error) -> io::Error {
+    if e.kind() == ErrorKind::UnexpectedEof {
+        return io::Error::new(
+            ErrorKind::UnexpectedEof,
+            "stream ended inside a frame header",
+        );
+    }
+    e
+}
+
+/// Reads the first byte of a header, returning `None` on a clean EOF.
+pub(crate) fn read_first_byte<R: Read + ?Sized>(reader: &mut R) -> io::Result<Option<u8>> {
+    let mut first = 0u8;
+    loop {
+        match reader.read(std::slice::from_mut(&mut first)) {
+            Ok(0) => return Ok(None),
+            Ok(_) => return Ok(Some(first)),
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Reads the rest of a header integer whose `first` byte has already been
+/// read, returning its value and encoded size.
+pub(crate) fn decode_int<R: Read + ?Sized>(
+    reader: &mut R,
+    first: u8,
+    encoding: LengthEncoding,
+    endian: Endian,
+) -> io::Result<(u64, u64)> {
+    let size = match encoding {
+        LengthEncoding::U8 => 1,
+        LengthEncoding::U16 => 2,
+        LengthEncoding::U32 => 4,
+        LengthEncoding::U64 => 8,
+        LengthEncoding::Varint => {
+            let prefix = [first];
+            let mut chained = prefix.as_slice().chain(reader);
+            let mut header = chained.count_ref();
+            let value = header.read_uvarint().map_err(header_eof)?;
+            return Ok((value, header.bytes_read()));
+        }
+    };
+
+    let mut header = [0u8; 8];
+    header[0] = first;
+    reader
+        .read_exact(&mut header[1..size])
+        .map_err(header_eof)?;
+    let header = &header[..size];
+    let value = match size {
+        1 => u8::decode(header, endian),
+        2 => u16::decode(header, endian),
+        4 => u32::decode(header, endian),
+        _ => u64::decode(header, endian),
+    };
+    Ok((value, size as u64))
+}
+
+/// The body of the current frame of a [`FrameReader`].
+///
+/// Reads stop at the end of the frame, and the [`FrameReader`] uses the
+/// count of unread bytes to skip the remainder before the next header.
+pub struct FrameBody<'a, R> {
+    pub(crate) inner: &'a mut R,
+    pub(crate) remaining: u64,
+}
+
+impl<R> FrameBody<'_, R> {
+    /// Returns the number of bytes of the frame that have not been read yet.
+    pub fn remaining(&self) -> u64 {
+        self.remaining
+    }
+}
+
+impl<R: Read> Read for FrameBody<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        if self.remaining == 0 {
+            return Ok(0);
+        }
+        let max = buf
+            .len()
+            .min(self.remaining.min(usize::MAX as u64) as usize);
+        let n = self.inner.read(&mut buf[..max])?;
+        self.remaining -= n as u64;
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for FrameBody<'_, R> {
+    fn fill_buf(&mut self) -> Result<&[u8], io::Error> {
+        if self.remaining == 0 {
+            return Ok(&[]);
+        }
+        let buf = self.inner.fill_buf()?;
+        let cap = buf
+            .len()
+            .min(self.remaining.min(usize::MAX as u64) as usize);
+        Ok(&buf[..cap])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        let amt = amt.min(self.remaining.min(usize::MAX as u64) as usize);
+        self.remaining -= amt as u64;
+        self.inner.consume(amt);
+    }
+}
+
+/// The wire format of the frames read by a [`FrameReader`].
+///
+/// A strategy parses the header in front of each frame body and may check
+/// a trailer after it; the [`FrameReader`] takes care of bounding the body,
+/// skipping whatever the caller leaves unread of it, and rejecting frames
+/// longer than [`FramingStrategy::max_frame_len`]. [`FrameConfig`] covers
+/// length-prefixed layouts, and [`Netstring`](crate::Netstring) netstrings.
+pub trait FramingStrategy {
+    /// Reads the header of the next frame and returns the length of its body.
+    ///
+    /// Returns `Ok(None)` if the stream ends cleanly before the header.
+    fn decode_header<R: Read + ?Sized>(&mut self, reader: &mut R) -> io::Result<Option<u64>>;
+
+    /// Reads and checks whatever follows a frame body, once the body has
+    /// been fully consumed. Does nothing by default.
+    fn validate_trailer<R: Read + ?Sized>(&mut self, reader: &mut R) -> io::Result<()> {
+        let _ = reader;
+        Ok(())
+    }
+
+    /// Returns the longest accepted frame body, in bytes.
+    fn max_frame_len(&self) -> u64;
+}
+
+impl FramingStrategy for FrameConfig {
+    fn decode_header<R: Read + ?Sized>(&mut self, reader: &mut R) -> io::Result<Option<u64>> {
+        let Some(first) = read_first_byte(reader)? else {
+            return Ok(None);
+        };
+        let (len, header_len) = decode_int(reader, first, self.length, self.endian)?;
+
+        if !self.length_includes_header {
+            return Ok(Some(len));
+        }
+        len.checked_sub(header_len).map(Some).ok_or_else(|| {
+            io::Error::new(
+                ErrorKind::InvalidData,
+                "frame length is shorter than its header",
+            )
+        })
+    }
+
+    fn max_frame_len(&self) -> u64 {
+        self.max_frame_len
+    }
+}
+
+/// A reader of framed messages over a borrowed reader.
+///
+/// The frame layout is given by a [`FramingStrategy`]; by default each
+/// frame starts with a big-endian `u32` length header followed by the body,
+/// and [`FrameConfig`] describes other length-prefixed layouts.
+/// [`FrameReader::next_frame`] reads the header and returns a [`RefTake`]
+/// limited to the body. Whatever the caller leaves unread of a frame is
+/// skipped before the next header is read, so frames stay aligned no
+/// matter how much of each body is consumed.
+///
+/// Non-blocking sources are supported: when the inner reader fails with
+/// `ErrorKind::WouldBlock` part way through a header, trailer or skipped
+/// body, the bytes consumed so far are kept, and calling
+/// [`FrameReader::next_frame`] (or [`FrameReader::poll_next_frame`]) again
+/// resumes where it stopped. A body interrupted the same way can be picked
+/// up again with [`FrameReader::current_frame`].
+///
+/// A peer sending endless tiny frames stays within the frame length limit;
+/// [`FrameReader::max_frames`] and [`FrameReader::max_total_len`] cap the
+/// number of frames and their combined length as well.
+pub struct FrameReader<'a, R, S = FrameConfig> {
+    body: FrameBody<'a, R>,
+    strategy: S,
+    max_frames: Option<u64>,
+    max_total_len: Option<u64>,
+    frame_count: u64,
+    total_len: u64,
+    /// Whether a body has been opened whose trailer wasn't checked yet.
+    open: bool,
+    /// Bytes of a header or trailer consumed before a `WouldBlock`, replayed on the next attempt.
+    partial: Vec<u8>,
+}
+
+/// The progress of a [`FrameReader`], saved with
+/// [`FrameReader::state`] and restored with [`FrameReader::with_state`].
+///
+/// Together with the position of the inner reader, for instance in a
+/// [`WindowState`](crate::WindowState), it lets a reader recreated after a
+/// restart carry on in the middle of a frame, with the same frame count
+/// and total length toward the caps.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FrameState {
+    /// Number of frames returned so far.
+    pub frame_count: u64,
+    /// Combined body length of the frames returned so far.
+    pub total_len: u64,
+    /// Bytes of the body of the current frame not read yet.
+    pub body_remaining: u64,
+    /// Whether a frame has been returned whose trailer wasn't checked yet.
+    pub open: bool,
+    /// Bytes of a header or trailer consumed before an `ErrorKind::WouldBlock`.
+    pub partial: Vec<u8>,
+}
+
+/// Replays the bytes of an earlier, interrupted header or trailer attempt
+/// before reading on from the inner reader, recording everything read.
+struct Replay<'p, R: ?Sized> {
+    inner: &'p mut R,
+    partial: &'p mut Vec<u8>,
+    pos: usize,
+}
+
+impl<R: Read + ?Sized> Read for Replay<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos < self.partial.len() {
+            let n = (&self.partial[self.pos..]).read(buf)?;
+            self.pos += n;
+            return Ok(n);
+        }
+        let n = self.inner.read(buf)?;
+        self.partial.extend_from_slice(&buf[..n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+impl<'a, R> FrameReader<'a, R> {
+    /// Creates a new `FrameReader` with the default layout that rejects frames
+    /// longer than `max_frame_len` bytes.
+    pub fn wrap(inner: &'a mut R, max_frame_len: u64) -> Self {
+        Self::with_config(
+            inner,
+            FrameConfig {
+                max_frame_len,
+                ..FrameConfig::default()
+            },
+        )
+    }
+
+    /// Creates a new `FrameReader` with the given framing parameters.
+    pub const fn with_config(inner: &'a mut R, config: FrameConfig) -> Self {
+        Self::with_strategy(inner, config)
+    }
+
+    /// Returns the framing parameters.
+    pub fn config(&self) -> &FrameConfig {
+        &self.strategy
+    }
+}
+
+impl<'a, R, S: FramingStrategy> FrameReader<'a, R, S> {
+    /// Creates a new `FrameReader` reading frames in the format of `strategy`.
+    pub const fn with_strategy(inner: &'a mut R, strategy: S) -> Self {
+        Self {
+            body: FrameBody {
+                inner,
+                remaining: 0,
+            },
+            strategy,
+            max_frames: None,
+            max_total_len: None,
+            frame_count: 0,
+            total_len: 0,
+            open: false,
+            partial: Vec::new(),
+        }
+    }
+
+    /// Fails with a [`FrameQuotaExceeded`] error on the header of any frame past the first `max`.
+    pub fn max_frames(mut self, max: u64) -> Self {
+        self.max_frames = Some(max);
+        self
+    }
+
+    /// Fails with a [`FrameQuotaExceeded`] error on the header of a frame
+    /// that takes the combined length of the bodies past `max` bytes.
+    pub fn max_total_len(mut self, max: u64) -> Self {
+        self.max_total_len = Some(max);
+        self
+    }
+
+    /// Restores the progress saved by [`FrameReader::state`], for a reader
+    /// recreated over an inner reader at the same position.
+    pub fn with_state(mut self, state: FrameState) -> Self {
+        self.frame_count = state.frame_count;
+        self.total_len = state.total_len;
+        self.body.remaining = state.body_remaining;
+        self.open = state.open;
+        self.partial = state.partial;
+        self
+    }
+
+    /// Returns the progress of the reader, to restore with [`FrameReader::with_state`].
+    pub fn state(&self) -> FrameState {
+        FrameState {
+            frame_count: self.frame_count,
+            total_len: self.total_len,
+            body_remaining: self.body.remaining,
+            open: self.open,
+            partial: self.partial.clone(),
+        }
+    }
+
+    /// Returns the number of frames returned so far.
+    pub fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    /// Returns the combined body length of the frames returned so far.
+    pub fn total_len(&self) -> u64 {
+        self.total_len
+    }
+
+    /// Returns a reference to the inner reader.
+    pub fn get_ref(&self) -> &R {
+        self.body.inner
+    }
+
+    /// Returns a mutable reference to the inner reader, such as to save its position.
+    ///
+    /// Reading from it directly puts the reader out of step with the frames.
+    pub fn get_mut(&mut self) -> &mut R {
+        self.body.inner
+    }
+
+    /// Returns the framing strategy.
+    pub fn strategy(&self) -> &S {
+        &self.strategy
+    }
+
+    /// Returns the maximum accepted frame length.
+    pub fn max_frame_len(&self) -> u64 {
+        self.strategy.max_frame_len()
+    }
+}
+
+impl<'a, R: Read, S: FramingStrategy> FrameReader<'a, R, S> {
+    /// Skips the rest of the current frame, if any, then reads the next frame header.
+    ///
+    /// Returns `Ok(None)` when the stream ends cleanly between frames. A
+    /// stream that ends inside a header or a skipped body fails with
+    /// `ErrorKind::UnexpectedEof`, and a frame longer than the maximum fails
+    /// with a [`LimitExceeded`] error. Going past the frame count or total
+    /// length caps fails with a [`FrameQuotaExceeded`] error. Errors from the
+    /// strategy are passed on.
+    ///
+    /// An `ErrorKind::WouldBlock` error from the inner reader is passed on
+    /// without losing any progress; call again once the reader is ready.
+    pub fn next_frame(&mut self) -> io::Result<Option<RefTake<'_, FrameBody<'a, R>>>> {
+        if self.open {
+            if !discard_by_reading(self.body.inner, &mut self.body.remaining)? {
+                return Err(io::Error::new(
+                    ErrorKind::UnexpectedEof,
+                    "stream ended inside a frame body",
+                ));
+            }
+            self.resumable(|strategy, reader| strategy.validate_trailer(reader))?;
+            self.open = false;
+        }
+
+        let Some(len) = self.resumable(|strategy, reader| strategy.decode_header(reader))? else {
+            return Ok(None);
+        };
+        let max_frame_len = self.strategy.max_frame_len();
+        if len > max_frame_len {
+            return Err(LimitExceeded::new(max_frame_len).into());
+        }
+        if let Some(max) = self.max_frames
+            && self.frame_count >= max
+        {
+            return Err(FrameQuotaExceeded::Count { max }.into());
+        }
+        let total_len = self.total_len.saturating_add(len);
+        if let Some(max) = self.max_total_len
+            && total_len > max
+        {
+            return Err(FrameQuotaExceeded::TotalLen { max }.into());
+        }
+        self.frame_count += 1;
+        self.total_len = total_len;
+
+        self.body.remaining = len;
+        self.open = true;
+        Ok(Some(RefTake::wrap(&mut self.body, len)))
+    }
+
+    /// Like [`FrameReader::next_frame`], but reports `ErrorKind::WouldBlock`
+    /// as `Poll::Pending`, for driving the reader from an event loop.
+    pub fn poll_next_frame(&mut self) -> Poll<io::Result<Option<RefTake<'_, FrameBody<'a, R>>>>> {
+        match self.next_frame() {
+            Err(e) if e.kind() == ErrorKind::WouldBlock => Poll::Pending,
+            result => Poll::Ready(result),
+        }
+    }
+
+    /// Returns the unread rest of the frame last returned by
+    /// [`FrameReader::next_frame`], or `None` if there is none.
+    ///
+    /// Use it to continue reading a body after the inner reader failed with
+    /// `ErrorKind::WouldBlock`; once `next_frame` has been called again, the
+    /// rest of the body is skipped instead.
+    pub fn current_frame(&mut self) -> Option<RefTake<'_, FrameBody<'a, R>>> {
+        let remaining = self.body.remaining;
+        self.open.then(|| RefTake::wrap(&mut self.body, remaining))
+    }
+
+    /// Runs a header or trailer parser of the strategy, replaying the bytes
+    /// consumed by an attempt that was interrupted by `ErrorKind::WouldBlock`.
+    fn resumable<T>(
+        &mut self,
+        parse: impl FnOnce(&mut S, &mut Replay<'_, R>) -> io::Result<T>,
+    ) -> io::Result<T> {
+        let mut reader = Replay {
+            inner: &mut *self.body.inner,
+            partial: &mut self.partial,
+            pos: 0,
+        };
+        let result = parse(&mut self.strategy, &mut reader);
+        if !matches!(&result, Err(e) if e.kind() == ErrorKind::WouldBlock) {
+            self.partial.clear();
+        }
+        result
+    }
+}
+
+/// Extension trait to provide a `frames_ref` method on all `Read` types.
+pub trait FrameReaderExt {
+    /// Wraps the reader in a `FrameReader` accepting frames of at most `max_frame_len` bytes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Cursor, Read};
+    /// use reftake::FrameReaderExt;
+    ///
+    /// let mut cursor = Cursor::new(b"\0\0\0\x05hello\0\0\0\x05world");
+    /// let mut frames = cursor.frames_ref(1024);
+    ///
+    /// let mut first = [0u8; 2];
+    /// frames.next_frame().unwrap().unwrap().read_exact(&mut first).unwrap();
+    /// assert_eq!(&first, b"he");
+    ///
+    /// let mut second = String::new();
+    /// frames.next_frame().unwrap().unwrap().read_to_string(&mut second).unwrap();
+    /// assert_eq!(second, "world");
+    /// assert!(frames.next_frame().unwrap().is_none());
+    /// ```
+    fn frames_ref(&mut self, max_frame_len: u64) -> FrameReader<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: Read> FrameReaderExt for T {
+    fn frames_ref(&mut self, max_frame_len: u64) -> FrameReader<'_, Self> {
+        FrameReader::wrap(self, max_frame_len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufReader, Cursor};
+
+    fn frames(bodies: &[&[u8]]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for body in bodies {
+            out.extend_from_slice(&(body.len() as u32).to_be_bytes());
+            out.extend_from_slice(body);
+        }
+        out
+    }
+
+    #[test]
+    fn test_unread_remainder_is_drained() {
+        let mut reader = Cursor::new(frames(&[b"skipped", b"", b"last"]));
+        let mut frames = reader.frames_ref(64);
+
+        assert_eq!(frames.next_frame().unwrap().unwrap().current_limit(), 7);
+        let mut empty = Vec::new();
+        frames
+            .next_frame()
+            .unwrap()
+            .unwrap()
+            .read_to_end(&mut empty)
+            .unwrap();
+        assert!(empty.is_empty());
+
+        let mut last = Vec::new();
+        frames
+            .next_frame()
+            .unwrap()
+            .unwrap()
+            .read_to_end(&mut last)
+            .unwrap();
+        assert_eq!(last, b"last");
+        assert!(frames.next_frame().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_frame_too_long() {
+        let mut reader = Cursor::new(frames(&[b"0123456789"]));
+        let mut frames = reader.frames_ref(4);
+        let err = frames.next_frame().err().unwrap();
+        assert_eq!(LimitExceeded::from_io(&err), Some(&LimitExceeded::new(4)));
+    }
+
+    #[test]
+    fn test_frame_count_and_total_caps() {
+        let data = frames(&[b"a", b"b", b"c"]);
+        let mut reader = Cursor::new(data.clone());
+        let mut frames = reader.frames_ref(64).max_frames(2);
+        assert!(frames.next_frame().unwrap().is_some());
+        assert!(frames.next_frame().unwrap().is_some());
+        let err = frames.next_frame().err().unwrap();
+        assert_eq!(
+            FrameQuotaExceeded::from_io(&err),
+            Some(&FrameQuotaExceeded::Count { max: 2 })
+        );
+        assert_eq!(frames.frame_count(), 2);
+
+        let mut reader = Cursor::new(data.clone());
+        let mut frames = reader.frames_ref(64).max_frames(3).max_total_len(3);
+        while frames.next_frame().unwrap().is_some() {}
+        assert_eq!((frames.frame_count(), frames.total_len()), (3, 3));
+
+        let mut reader = Cursor::new(data);
+        let mut frames = reader.frames_ref(64).max_total_len(2);
+        frames.next_frame().unwrap();
+        frames.next_frame().unwrap();
+        let err = frames.next_frame().err().unwrap();
+        assert_eq!(err.to_string(), "frame bodies add up to more than 2 bytes");
+    }
+
+    #[test]
+    fn test_truncated_stream() {
+        let mut data = frames(&[b"abcdef"]);
+        data.truncate(7);
+        let mut reader = Cursor::new(data);
+        let mut frames = reader.frames_ref(64);
+        assert!(frames.next_frame().unwrap().is_some());
+        let err = frames.next_frame().err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+
+        let mut reader = Cursor::new(vec![0, 0]);
+        let err = reader.frames_ref(64).next_frame().err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_little_endian_u16_including_header() {
+        let mut reader = Cursor::new(b"\x05\x00abc\x02\x00");
+        let config = FrameConfig {
+            length: LengthEncoding::U16,
+            endian: Endian::Little,
+            length_includes_header: true,
+            ..FrameConfig::default()
+        };
+        let mut frames = FrameReader::with_config(&mut reader, config);
+
+        let mut body = Vec::new();
+        frames
+            .next_frame()
+            .unwrap()
+            .unwrap()
+            .read_to_end(&mut body)
+            .unwrap();
+        assert_eq!(body, b"abc");
+        assert_eq!(frames.next_frame().unwrap().unwrap().current_limit(), 0);
+        assert!(frames.next_frame().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_varint_length() {
+        let mut data = vec![0xac, 0x02];
+        data.extend_from_slice(&[b'x'; 300]);
+        data.extend_from_slice(b"\x01y");
+        let mut reader = Cursor::new(data);
+        let config = FrameConfig {
+            length: LengthEncoding::Varint,
+            ..FrameConfig::default()
+        };
+        let mut frames = FrameReader::with_config(&mut reader, config);
+
+        assert_eq!(frames.next_frame().unwrap().unwrap().current_limit(), 300);
+        let mut body = Vec::new();
+        frames
+            .next_frame()
+            .unwrap()
+            .unwrap()
+            .read_to_end(&mut body)
+            .unwrap();
+        assert_eq!(body, b"y");
+    }
+
+    #[test]
+    fn test_length_shorter_than_header() {
+        let mut reader = Cursor::new(b"\x00\x00\x00\x02");
+        let config = FrameConfig {
+            length_includes_header: true,
+            ..FrameConfig::default()
+        };
+        let err = FrameReader::with_config(&mut reader, config)
+            .next_frame()
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    /// Hands out one byte at a time, failing with `WouldBlock` before each.
+    struct Trickle {
+        data: Cursor<Vec<u8>>,
+        ready: bool,
+    }
+
+    impl Read for Trickle {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.ready = !self.ready;
+            if !self.ready {
+                return Err(ErrorKind::WouldBlock.into());
+            }
+            let len = buf.len().min(1);
+            self.data.read(&mut buf[..len])
+        }
+    }
+
+    #[test]
+    fn test_resumes_after_would_block() {
+        let mut reader = Trickle {
+            data: Cursor::new(frames(&[b"abc", b"skipped", b"xy"])),
+            ready: true,
+        };
+        let mut frames = reader.frames_ref(64);
+
+        let mut pending = 0;
+        let mut body = Vec::new();
+        let mut lens = Vec::new();
+        loop {
+            match frames.poll_next_frame() {
+                Poll::Pending => pending += 1,
+                Poll::Ready(Ok(Some(frame))) => lens.push(frame.current_limit()),
+                Poll::Ready(Ok(None)) => break,
+                Poll::Ready(Err(e)) => panic!("{e}"),
+            }
+            // Read the first body completely, leave the others to be skipped
+            if lens.len() == 1 {
+                while let Some(mut frame) = frames.current_frame() {
+                    match frame.read_to_end(&mut body) {
+                        Ok(_) => break,
+                        Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+                        Err(e) => panic!("{e}"),
+                    }
+                }
+            }
+        }
+        assert_eq!(lens, vec![3, 7, 2]);
+        assert_eq!(body, b"abc");
+        assert!(pending > 10);
+    }
+
+    /// A one-byte length, a body and a `0xff` sentinel.
+    struct Sentinel;
+
+    impl FramingStrategy for Sentinel {
+        fn decode_header<R: Read + ?Sized>(&mut self, reader: &mut R) -> io::Result<Option<u64>> {
+            let mut len = [0u8; 1];
+            match reader.read(&mut len)? {
+                0 => Ok(None),
+                _ => Ok(Some(len[0].into())),
+            }
+        }
+
+        fn validate_trailer<R: Read + ?Sized>(&mut self, reader: &mut R) -> io::Result<()> {
+            let mut sentinel = [0u8; 1];
+            reader.read_exact(&mut sentinel)?;
+            if sentinel != [0xff] {
+                return Err(io::Error::new(ErrorKind::InvalidData, "bad sentinel"));
+            }
+            Ok(())
+        }
+
+        fn max_frame_len(&self) -> u64 {
+            4
+        }
+    }
+
+    #[test]
+    fn test_custom_strategy() {
+        let mut reader = Cursor::new(b"\x02ab\xff\x01c\x00\x05");
+        let mut frames = FrameReader::with_strategy(&mut reader, Sentinel);
+        assert_eq!(frames.max_frame_len(), 4);
+
+        assert_eq!(frames.next_frame().unwrap().unwrap().current_limit(), 2);
+        let mut body = Vec::new();
+        frames
+            .next_frame()
+            .unwrap()
+            .unwrap()
+            .read_to_end(&mut body)
+            .unwrap();
+        assert_eq!(body, b"c");
+        let err = frames.next_frame().err().unwrap();
+        assert_eq!(err.to_string(), "bad sentinel");
+
+        let mut reader = Cursor::new(b"\x05");
+        let err = FrameReader::with_strategy(&mut reader, Sentinel)
+            .next_frame()
+            .err()
+            .unwrap();
+        assert_eq!(LimitExceeded::from_io(&err), Some(&LimitExceeded::new(4)));
+    }
+
+    #[test]
+    fn test_frame_body_bufread() {
+        let mut reader = BufReader::with_capacity(3, Cursor::new(frames(&[b"a\nb\n", b"c\n"])));
+        let mut frames = reader.frames_ref(64);
+
+        let lines: Vec<String> = frames
+            .next_frame()
+            .unwrap()
+            .unwrap()
+            .lines()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(lines, vec!["a", "b"]);
+        let mut line = String::new();
+        frames
+            .next_frame()
+            .unwrap()
+            .unwrap()
+            .read_line(&mut line)
+            .unwrap();
+        assert_eq!(line, "c\n");
+    }
+}
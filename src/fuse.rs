@@ -0,0 +1,140 @@
+//! An adapter that makes EOF of a borrowed reader terminal.
+
+use std::io::{self, BufRead, Read};
+
+/// A non-owning adapter that stops calling the inner reader once it reported EOF.
+///
+/// Some readers return data again after a read returned `Ok(0)`, which breaks
+/// loops built on "`Ok(0)` means done". After the first EOF, `RefFuse` returns
+/// `Ok(0)` (or an empty buffer) forever without touching the inner reader.
+/// Stacked over a [`RefTake`](crate::RefTake), reaching the limit fuses it too.
+pub struct RefFuse<'a, R> {
+    inner: &'a mut R,
+    done: bool,
+}
+
+impl<'a, R> RefFuse<'a, R> {
+    /// Creates a new `RefFuse` over the given reader reference.
+    pub const fn wrap(inner: &'a mut R) -> Self {
+        Self { inner, done: false }
+    }
+
+    /// Returns `true` once EOF has been observed.
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+}
+
+impl<R: Read> Read for RefFuse<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        if self.done || buf.is_empty() {
+            return Ok(0);
+        }
+        let n = self.inner.read(buf)?;
+        self.done = n == 0;
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for RefFuse<'_, R> {
+    fn fill_buf(&mut self) -> Result<&[u8], io::Error> {
+        if self.done {
+            return Ok(&[]);
+        }
+        let buf = self.inner.fill_buf()?;
+        if buf.is_empty() {
+            self.done = true;
+        }
+        Ok(buf)
+    }
+
+    fn consume(&mut self, amt: usize) {
+        if !self.done {
+            self.inner.consume(amt);
+        }
+    }
+}
+
+/// Extension trait to provide a `fuse_ref` method on all `Read` types.
+pub trait RefFuseExt {
+    /// Wraps the reader in a `RefFuse` that makes EOF terminal.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Cursor, Read};
+    /// use reftake::RefFuseExt;
+    ///
+    /// let mut cursor = Cursor::new(b"ab");
+    /// let mut fused = cursor.fuse_ref();
+    /// let mut buf = Vec::new();
+    /// fused.read_to_end(&mut buf).unwrap();
+    /// assert!(fused.is_done());
+    /// ```
+    fn fuse_ref(&mut self) -> RefFuse<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: Read> RefFuseExt for T {
+    fn fuse_ref(&mut self) -> RefFuse<'_, Self> {
+        RefFuse::wrap(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RefTakeExt;
+    use std::io::Cursor;
+
+    /// A reader that reports EOF once, then produces data again.
+    struct Flaky {
+        calls: usize,
+    }
+
+    impl Read for Flaky {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.calls += 1;
+            if self.calls == 2 {
+                return Ok(0);
+            }
+            buf[0] = b'x';
+            Ok(1)
+        }
+    }
+
+    #[test]
+    fn test_eof_is_terminal() {
+        let mut reader = Flaky { calls: 0 };
+        {
+            let mut fused = reader.fuse_ref();
+            let mut buf = [0u8; 4];
+            assert_eq!(fused.read(&mut buf).unwrap(), 1);
+            assert_eq!(fused.read(&mut buf).unwrap(), 0);
+            assert_eq!(fused.read(&mut buf).unwrap(), 0);
+            assert!(fused.is_done());
+        }
+        assert_eq!(reader.calls, 2);
+    }
+
+    #[test]
+    fn test_empty_buffer_does_not_fuse() {
+        let mut reader = Cursor::new(b"abc");
+        let mut fused = reader.fuse_ref();
+        assert_eq!(fused.read(&mut []).unwrap(), 0);
+        assert!(!fused.is_done());
+    }
+
+    #[test]
+    fn test_fuse_over_take_limit() {
+        let mut reader = Cursor::new(b"abcdef");
+        let mut take = reader.take_ref(2);
+        let mut fused = take.fuse_ref();
+
+        assert_eq!(fused.fill_buf().unwrap(), b"ab");
+        fused.consume(2);
+        assert_eq!(fused.fill_buf().unwrap(), b"");
+        assert!(fused.is_done());
+    }
+}
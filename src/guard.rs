@@ -0,0 +1,178 @@
+//! An adapter that fails when a borrowed reader produces more bytes than allowed.
+
+use std::{
+    cmp,
+    io::{self, BufRead, Read},
+};
+
+use crate::LimitExceeded;
+
+/// A non-owning adapter that enforces a hard ceiling on the bytes a reader produces.
+///
+/// Unlike [`RefTake`](crate::RefTake), which silently reports EOF at its
+/// limit, `RefGuard` treats a longer stream as an error: once `max` bytes
+/// have been read, the next read probes the inner reader and fails with
+/// [`LimitExceeded`] if any more data is available. Wrap it around a
+/// decompressing reader (flate2, zstd, ...) to bound the decompressed output.
+pub struct RefGuard<'a, R> {
+    inner: &'a mut R,
+    max: u64,
+    remaining: u64,
+}
+
+impl<'a, R> RefGuard<'a, R> {
+    /// Creates a new `RefGuard` that allows at most `max` bytes to be read.
+    pub const fn wrap(inner: &'a mut R, max: u64) -> Self {
+        Self {
+            inner,
+            max,
+            remaining: max,
+        }
+    }
+
+    /// Recreates a guard that has already let `max - remaining` bytes through.
+    pub(crate) fn resume(inner: &'a mut R, max: u64, remaining: u64) -> Self {
+        Self {
+            inner,
+            max,
+            remaining,
+        }
+    }
+
+    /// Returns the configured ceiling.
+    pub fn max(&self) -> u64 {
+        self.max
+    }
+
+    /// Returns the number of bytes read so far.
+    pub fn bytes_read(&self) -> u64 {
+        self.max - self.remaining
+    }
+}
+
+impl<R: Read> Read for RefGuard<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.remaining == 0 {
+            let mut probe = [0u8; 1];
+            return match self.inner.read(&mut probe)? {
+                0 => Ok(0),
+                _ => {
+                    trace_event!(warn, max = self.max, "guard limit exceeded");
+                    Err(LimitExceeded::new(self.max).into())
+                }
+            };
+        }
+
+        let max = cmp::min(buf.len() as u64, self.remaining) as usize;
+        let n = self.inner.read(&mut buf[..max])?;
+        self.remaining -= n as u64;
+        Ok(n)
+    }
+}
+
+#[cfg(any(feature = "bincode", feature = "cbor"))]
+impl<R: Read> RefGuard<'_, R> {
+    /// Checks that the reader ends here, reporting [`TrailingData`] if it does
+    /// not, or [`LimitExceeded`] if the extra bytes are past the ceiling.
+    pub(crate) fn expect_end(&mut self) -> io::Result<()> {
+        let mut probe = [0u8; 1];
+        loop {
+            return match self.read(&mut probe) {
+                Ok(0) => Ok(()),
+                Ok(_) => Err(crate::TrailingData.into()),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => Err(e),
+            };
+        }
+    }
+}
+
+impl<R: BufRead> BufRead for RefGuard<'_, R> {
+    fn fill_buf(&mut self) -> Result<&[u8], io::Error> {
+        let max = self.max;
+        let remaining = self.remaining;
+        let buf = self.inner.fill_buf()?;
+        if remaining == 0 && !buf.is_empty() {
+            trace_event!(warn, max, "guard limit exceeded");
+            return Err(LimitExceeded::new(max).into());
+        }
+        let cap = cmp::min(buf.len() as u64, remaining) as usize;
+        Ok(&buf[..cap])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        let amt = cmp::min(amt as u64, self.remaining) as usize;
+        self.remaining -= amt as u64;
+        self.inner.consume(amt);
+    }
+}
+
+/// Extension trait to provide a `guard_ref` method on all `Read` types.
+pub trait RefGuardExt {
+    /// Wraps the reader in a `RefGuard` that fails if more than `max` bytes are produced.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Cursor, Read};
+    /// use reftake::{LimitExceeded, RefGuardExt};
+    ///
+    /// let mut decompressed = Cursor::new(vec![0u8; 1024]);
+    /// let mut out = Vec::new();
+    /// let err = decompressed.guard_ref(100).read_to_end(&mut out).unwrap_err();
+    /// assert_eq!(LimitExceeded::from_io(&err).unwrap().limit(), 100);
+    /// ```
+    fn guard_ref(&mut self, max: u64) -> RefGuard<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: Read> RefGuardExt for T {
+    fn guard_ref(&mut self, max: u64) -> RefGuard<'_, Self> {
+        RefGuard::wrap(self, max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufReader, Cursor};
+
+    #[test]
+    fn test_exactly_at_limit_is_ok() {
+        let mut reader = Cursor::new(b"12345");
+        let mut guard = reader.guard_ref(5);
+
+        let mut buf = Vec::new();
+        guard.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, b"12345");
+        assert_eq!(guard.bytes_read(), 5);
+    }
+
+    #[test]
+    fn test_over_limit_fails() {
+        let mut reader = Cursor::new(b"123456");
+        let mut guard = reader.guard_ref(5);
+
+        let mut buf = Vec::new();
+        let err = guard.read_to_end(&mut buf).unwrap_err();
+        assert!(LimitExceeded::from_io(&err).is_some());
+        assert_eq!(buf, b"12345");
+    }
+
+    #[test]
+    fn test_bufread_over_limit_fails() {
+        let mut reader = BufReader::new(Cursor::new(b"line\nline\n"));
+        let mut guard = reader.guard_ref(7);
+
+        let mut line = String::new();
+        guard.read_line(&mut line).unwrap();
+        assert_eq!(line, "line\n");
+        line.clear();
+        let err = guard.read_line(&mut line).unwrap_err();
+        assert_eq!(LimitExceeded::from_io(&err), Some(&LimitExceeded::new(7)));
+    }
+}
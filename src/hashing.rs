@@ -0,0 +1,137 @@
+//! A hashing adapter over a borrowed reader, generic over `digest::Digest` (feature `digest`).
+
+use std::io::{self, BufRead, Read};
+
+use digest::{Digest, Output};
+
+/// A non-owning adapter that feeds every byte read through it into a [`Digest`].
+///
+/// Combined with [`RefTake`](crate::RefTake), this computes content hashes of
+/// individual frames in the same pass that parses them.
+pub struct HashingReader<'a, R, D> {
+    inner: &'a mut R,
+    digest: D,
+}
+
+impl<'a, R, D: Digest> HashingReader<'a, R, D> {
+    /// Creates a new `HashingReader` over the given reader reference with a fresh digest.
+    pub fn wrap(inner: &'a mut R) -> Self {
+        Self::with_digest(inner, D::new())
+    }
+
+    /// Creates a new `HashingReader` that continues updating an existing digest.
+    pub const fn with_digest(inner: &'a mut R, digest: D) -> Self {
+        Self { inner, digest }
+    }
+
+    /// Returns a reference to the digest state.
+    pub fn digest(&self) -> &D {
+        &self.digest
+    }
+
+    /// Consumes the adapter and returns the digest state, e.g. to keep hashing elsewhere.
+    pub fn into_digest(self) -> D {
+        self.digest
+    }
+
+    /// Consumes the adapter and returns the hash of all bytes read through it.
+    pub fn finalize(self) -> Output<D> {
+        self.digest.finalize()
+    }
+}
+
+impl<R: Read, D: Digest> Read for HashingReader<'_, R, D> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        let n = self.inner.read(buf)?;
+        self.digest.update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+/// Implements the `BufRead` trait, hashing bytes as they are consumed.
+impl<R: BufRead, D: Digest> BufRead for HashingReader<'_, R, D> {
+    fn fill_buf(&mut self) -> Result<&[u8], io::Error> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        if amt > 0
+            && let Ok(buf) = self.inner.fill_buf()
+        {
+            self.digest.update(&buf[..amt.min(buf.len())]);
+        }
+        self.inner.consume(amt);
+    }
+}
+
+/// Extension trait to provide a `hash_ref` method on all `Read` types.
+pub trait HashingReaderExt {
+    /// Wraps the reader in a `HashingReader` using a fresh digest of type `D`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Cursor, Read};
+    /// use reftake::{HashingReaderExt, RefTakeExt};
+    /// use sha2::Sha256;
+    ///
+    /// let mut cursor = Cursor::new(b"abcdef");
+    /// let mut take = cursor.take_ref(3);
+    /// let mut hashing = take.hash_ref::<Sha256>();
+    /// std::io::copy(&mut hashing, &mut std::io::sink()).unwrap();
+    ///
+    /// let hash = hashing.finalize();
+    /// assert_eq!(hash[..4], [0xba, 0x78, 0x16, 0xbf]);
+    /// ```
+    fn hash_ref<D: Digest>(&mut self) -> HashingReader<'_, Self, D>
+    where
+        Self: Sized;
+}
+
+impl<T: Read> HashingReaderExt for T {
+    fn hash_ref<D: Digest>(&mut self) -> HashingReader<'_, Self, D> {
+        HashingReader::wrap(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RefTakeExt;
+    use sha2::Sha256;
+    use std::io::{BufReader, Cursor};
+
+    #[test]
+    fn test_hash_of_read_bytes() {
+        let mut reader = Cursor::new(b"hello world");
+        let mut hashing = reader.hash_ref::<Sha256>();
+
+        let mut buf = Vec::new();
+        hashing.read_to_end(&mut buf).unwrap();
+        assert_eq!(hashing.finalize(), Sha256::digest(b"hello world"));
+    }
+
+    #[test]
+    fn test_hash_bufread_consumed_bytes() {
+        let mut reader = BufReader::with_capacity(3, Cursor::new(b"first\nsecond\n"));
+        let mut hashing = reader.hash_ref::<Sha256>();
+
+        let mut line = String::new();
+        hashing.read_line(&mut line).unwrap();
+        assert_eq!(hashing.finalize(), Sha256::digest(b"first\n"));
+    }
+
+    #[test]
+    fn test_hash_per_frame() {
+        let mut reader = Cursor::new(b"frame1frame2");
+        let mut hashes = Vec::new();
+        for _ in 0..2 {
+            let mut take = reader.take_ref(6);
+            let mut hashing = take.hash_ref::<Sha256>();
+            std::io::copy(&mut hashing, &mut std::io::sink()).unwrap();
+            hashes.push(hashing.finalize());
+        }
+        assert_eq!(hashes[0], Sha256::digest(b"frame1"));
+        assert_eq!(hashes[1], Sha256::digest(b"frame2"));
+    }
+}
@@ -0,0 +1,216 @@
+//! A debugging adapter that hex-dumps everything read from a borrowed reader.
+
+use std::io::{self, BufRead, Read, Write};
+
+/// Number of bytes shown per dump line.
+const LINE: usize = 16;
+
+/// A non-owning adapter that writes a hex dump of every chunk read into a sink.
+///
+/// Each chunk is dumped as it is read, in the classic `hexdump -C` layout:
+/// the stream offset, sixteen hex bytes and their printable ASCII form. Since
+/// every chunk starts a new line, the dump also shows where read boundaries
+/// fell, which is often what matters when debugging protocol parsers.
+pub struct RefHexDump<'a, R, W> {
+    inner: &'a mut R,
+    sink: &'a mut W,
+    offset: u64,
+    pending_error: Option<io::Error>,
+}
+
+impl<'a, R, W: Write> RefHexDump<'a, R, W> {
+    /// Creates a new `RefHexDump` that dumps everything read from `inner` into `sink`.
+    pub const fn wrap(inner: &'a mut R, sink: &'a mut W) -> Self {
+        Self {
+            inner,
+            sink,
+            offset: 0,
+            pending_error: None,
+        }
+    }
+
+    /// Returns the offset of the next byte to be read, relative to the start of the adapter.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Dumps `data`, keeping an error of the sink for the next call.
+    fn dump(&mut self, data: &[u8]) {
+        if let Err(e) = write_hex_dump(self.sink, self.offset, data) {
+            self.pending_error.get_or_insert(e);
+        }
+        self.offset += data.len() as u64;
+    }
+}
+
+/// Writes `data` to `w` in `hexdump -C` layout, numbering lines from `offset`.
+pub(crate) fn write_hex_dump<W: Write + ?Sized>(
+    w: &mut W,
+    offset: u64,
+    data: &[u8],
+) -> io::Result<()> {
+    for (i, line) in data.chunks(LINE).enumerate() {
+        write!(w, "{:08x} ", offset + (i * LINE) as u64)?;
+        for j in 0..LINE {
+            if j % 8 == 0 {
+                write!(w, " ")?;
+            }
+            match line.get(j) {
+                Some(b) => write!(w, "{b:02x} ")?,
+                None => write!(w, "   ")?,
+            }
+        }
+        let ascii: String = line
+            .iter()
+            .map(|&b| {
+                if b.is_ascii_graphic() || b == b' ' {
+                    b as char
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+        writeln!(w, " |{ascii}|")?;
+    }
+    Ok(())
+}
+
+/// Implements the `Read` trait, dumping bytes as they are read.
+///
+/// The bytes are already taken from the inner reader when the sink is
+/// written, so an error from the sink is stored and returned by the next
+/// `read()` or `fill_buf()` call, and the bytes are returned first.
+impl<R: Read, W: Write> Read for RefHexDump<'_, R, W> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        if let Some(e) = self.pending_error.take() {
+            return Err(e);
+        }
+        let n = self.inner.read(buf)?;
+        self.dump(&buf[..n]);
+        Ok(n)
+    }
+}
+
+/// Implements the `BufRead` trait, dumping bytes as they are consumed.
+///
+/// Since `consume()` cannot fail, an error from the sink is stored and
+/// returned by the next `read()` or `fill_buf()` call.
+impl<R: BufRead, W: Write> BufRead for RefHexDump<'_, R, W> {
+    fn fill_buf(&mut self) -> Result<&[u8], io::Error> {
+        if let Some(e) = self.pending_error.take() {
+            return Err(e);
+        }
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        if amt == 0 {
+            return;
+        }
+        let result = match self.inner.fill_buf() {
+            Ok(buf) => {
+                let data = &buf[..amt.min(buf.len())];
+                write_hex_dump(self.sink, self.offset, data)
+            }
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            self.pending_error.get_or_insert(e);
+        }
+        self.offset += amt as u64;
+        self.inner.consume(amt);
+    }
+}
+
+/// Extension trait to provide a `hexdump_ref` method on all `Read` types.
+pub trait RefHexDumpExt {
+    /// Wraps the reader in a `RefHexDump` writing a hex dump of all reads into `sink`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Cursor, Read};
+    /// use reftake::{RefHexDumpExt, RefTakeExt};
+    ///
+    /// let mut cursor = Cursor::new(b"Hello, world!");
+    /// let mut take = cursor.take_ref(5);
+    /// let mut dump = Vec::new();
+    /// let mut buf = Vec::new();
+    /// take.hexdump_ref(&mut dump).read_to_end(&mut buf).unwrap();
+    ///
+    /// assert_eq!(
+    ///     String::from_utf8(dump).unwrap(),
+    ///     "00000000  48 65 6c 6c 6f                                    |Hello|\n"
+    /// );
+    /// ```
+    fn hexdump_ref<'a, W: Write>(&'a mut self, sink: &'a mut W) -> RefHexDump<'a, Self, W>
+    where
+        Self: Sized;
+}
+
+impl<T: Read> RefHexDumpExt for T {
+    fn hexdump_ref<'a, W: Write>(&'a mut self, sink: &'a mut W) -> RefHexDump<'a, Self, W> {
+        RefHexDump::wrap(self, sink)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufReader, Cursor};
+
+    #[test]
+    fn test_full_and_partial_lines() {
+        let mut out = Vec::new();
+        write_hex_dump(&mut out, 0x10, b"0123456789abcdef\x00\x01").unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "00000010  30 31 32 33 34 35 36 37  38 39 61 62 63 64 65 66  |0123456789abcdef|\n\
+             00000020  00 01                                             |..|\n"
+        );
+    }
+
+    #[test]
+    fn test_offsets_continue_across_reads() {
+        let mut reader = Cursor::new(b"abcdef");
+        let mut dump = Vec::new();
+        {
+            let mut hex = reader.hexdump_ref(&mut dump);
+            let mut buf = [0u8; 4];
+            assert_eq!(hex.read(&mut buf).unwrap(), 4);
+            assert_eq!(hex.read(&mut buf).unwrap(), 2);
+            assert_eq!(hex.offset(), 6);
+        }
+        let text = String::from_utf8(dump).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert!(lines[0].starts_with("00000000  61 62 63 64"));
+        assert!(lines[1].starts_with("00000004  65 66"));
+    }
+
+    #[test]
+    fn test_sink_error_keeps_the_bytes_read() {
+        let mut reader = Cursor::new(b"abcdef");
+        let mut sink = &mut [0u8; 8][..];
+        let mut hex = reader.hexdump_ref(&mut sink);
+
+        let mut buf = [0u8; 4];
+        assert_eq!(hex.read(&mut buf).unwrap(), 4);
+        assert_eq!(&buf, b"abcd");
+        let err = hex.read(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WriteZero);
+        assert_eq!(hex.read(&mut buf).unwrap(), 2);
+        assert_eq!(hex.offset(), 6);
+    }
+
+    #[test]
+    fn test_bufread_dumps_consumed_bytes() {
+        let mut reader = BufReader::new(Cursor::new(b"ab\ncd\n"));
+        let mut dump = Vec::new();
+        {
+            let mut hex = reader.hexdump_ref(&mut dump);
+            let mut line = String::new();
+            hex.read_line(&mut line).unwrap();
+        }
+        assert!(String::from_utf8(dump).unwrap().ends_with("|ab.|\n"));
+    }
+}
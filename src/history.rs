@@ -0,0 +1,191 @@
+//! Keeping the last bytes read from a borrowed reader, to explain its errors.
+
+use std::{
+    collections::VecDeque,
+    io::{self, BufRead, ErrorKind, Read},
+};
+
+use crate::ReadContext;
+
+/// A non-owning adapter that remembers the last `capacity` bytes read and
+/// attaches them to errors.
+///
+/// A parse error deep in a stream is much easier to understand with the
+/// bytes that led up to it. Every error of the inner reader, including the
+/// [`LimitExceeded`](crate::LimitExceeded) and other payloads of the
+/// crate's adapters, is returned as an `io::Error` of the same kind
+/// carrying a [`ReadContext`]: the error, the offset at which it happened
+/// and a copy of the remembered bytes. `Interrupted` and `WouldBlock`
+/// errors are passed on as they are, since they are retried rather than
+/// reported.
+///
+/// Errors raised by the code reading from the adapter do not go through
+/// it; [`RefHistory::context`] builds the same payload for those.
+pub struct RefHistory<'a, R> {
+    inner: &'a mut R,
+    recent: VecDeque<u8>,
+    capacity: usize,
+    offset: u64,
+}
+
+impl<'a, R> RefHistory<'a, R> {
+    /// Creates a new `RefHistory` remembering the last `capacity` bytes read from `inner`.
+    pub fn wrap(inner: &'a mut R, capacity: usize) -> Self {
+        Self {
+            inner,
+            recent: VecDeque::with_capacity(capacity),
+            capacity,
+            offset: 0,
+        }
+    }
+
+    /// Returns the offset of the next byte to be read, relative to the start of the adapter.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Returns a copy of the remembered bytes, oldest first.
+    pub fn recent(&self) -> Vec<u8> {
+        self.recent.iter().copied().collect()
+    }
+
+    /// Wraps `err` in a [`ReadContext`] holding the current offset and the remembered bytes.
+    ///
+    /// Useful for errors found by a parser in bytes it has already read.
+    pub fn context(&self, err: io::Error) -> io::Error {
+        io::Error::new(
+            err.kind(),
+            ReadContext::new(self.offset, self.recent(), err),
+        )
+    }
+
+    fn wrap_error(&self, err: io::Error) -> io::Error {
+        match err.kind() {
+            ErrorKind::Interrupted | ErrorKind::WouldBlock => err,
+            _ => self.context(err),
+        }
+    }
+}
+
+/// Appends `data` to the ring, dropping the oldest bytes past `capacity`.
+fn remember(recent: &mut VecDeque<u8>, capacity: usize, data: &[u8]) {
+    let keep = &data[data.len().saturating_sub(capacity)..];
+    let excess = (recent.len() + keep.len()).saturating_sub(capacity);
+    recent.drain(..excess);
+    recent.extend(keep);
+}
+
+impl<R: Read> Read for RefHistory<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        match self.inner.read(buf) {
+            Ok(n) => {
+                remember(&mut self.recent, self.capacity, &buf[..n]);
+                self.offset += n as u64;
+                Ok(n)
+            }
+            Err(e) => Err(self.wrap_error(e)),
+        }
+    }
+}
+
+/// Implements the `BufRead` trait, remembering bytes as they are consumed.
+impl<R: BufRead> BufRead for RefHistory<'_, R> {
+    fn fill_buf(&mut self) -> Result<&[u8], io::Error> {
+        if let Err(e) = self.inner.fill_buf() {
+            return Err(self.wrap_error(e));
+        }
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        if let Ok(buf) = self.inner.fill_buf() {
+            let amt = amt.min(buf.len());
+            remember(&mut self.recent, self.capacity, &buf[..amt]);
+            self.offset += amt as u64;
+        }
+        self.inner.consume(amt);
+    }
+}
+
+/// Extension trait to provide a `history_ref` method on all `Read` types.
+pub trait RefHistoryExt {
+    /// Wraps the reader in a `RefHistory` remembering the last `capacity` bytes read.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Cursor, Read};
+    /// use reftake::{LimitExceeded, ReadContext, RefGuardExt, RefHistoryExt};
+    ///
+    /// let mut cursor = Cursor::new(b"header: value\r\nmore than allowed");
+    /// let mut guard = cursor.guard_ref(20);
+    /// let mut reader = guard.history_ref(8);
+    ///
+    /// let mut buf = Vec::new();
+    /// let err = reader.read_to_end(&mut buf).unwrap_err();
+    /// let context = ReadContext::from_io(&err).unwrap();
+    /// assert_eq!(context.offset(), 20);
+    /// assert_eq!(context.recent(), b"e\r\nmore ");
+    /// assert!(LimitExceeded::from_io(&err).is_some());
+    /// ```
+    fn history_ref(&mut self, capacity: usize) -> RefHistory<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: Read> RefHistoryExt for T {
+    fn history_ref(&mut self, capacity: usize) -> RefHistory<'_, Self> {
+        RefHistory::wrap(self, capacity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InvalidUtf8;
+    use std::io::{BufReader, Cursor};
+
+    struct Failing;
+
+    impl Read for Failing {
+        fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+            Err(InvalidUtf8::new(&[0xff]).into())
+        }
+    }
+
+    #[test]
+    fn test_ring_keeps_the_last_bytes() {
+        let mut reader = Cursor::new(b"0123456789").chain(Failing);
+        let mut history = reader.history_ref(4);
+        let mut buf = [0u8; 3];
+        for _ in 0..3 {
+            history.read_exact(&mut buf).unwrap();
+        }
+        assert_eq!(history.recent(), b"5678");
+
+        let mut rest = Vec::new();
+        let err = history.read_to_end(&mut rest).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(InvalidUtf8::from_io(&err).is_some());
+        let context = ReadContext::from_io(&err).unwrap();
+        assert_eq!(context.offset(), 10);
+        assert_eq!(context.recent(), b"6789");
+        assert!(err.to_string().ends_with("at offset 10, after 36 37 38 39"));
+        assert!(context.hex_dump().starts_with("00000006  36 37 38 39"));
+    }
+
+    #[test]
+    fn test_consumed_bytes_and_parser_errors() {
+        let mut reader = BufReader::with_capacity(4, Cursor::new(b"key=value\n"));
+        let mut history = reader.history_ref(16);
+        let mut line = String::new();
+        history.read_line(&mut line).unwrap();
+        assert_eq!(history.offset(), 10);
+
+        let err = history.context(io::Error::new(ErrorKind::InvalidData, "no section"));
+        let context = ReadContext::from_io(&err).unwrap();
+        assert_eq!(context.recent(), b"key=value\n");
+        assert_eq!(context.inner().to_string(), "no section");
+        assert_eq!(history.fill_buf().unwrap(), b"");
+    }
+}
@@ -0,0 +1,128 @@
+//! An adapter that calls a closure with every chunk read from a borrowed reader.
+
+use std::io::{self, BufRead, Read};
+
+/// A non-owning adapter that invokes a callback with every successfully read chunk.
+///
+/// The callback sees exactly the bytes handed to the caller, after any limit
+/// applied by adapters further down the stack. Empty reads are not reported.
+pub struct RefInspect<'a, R, F> {
+    inner: &'a mut R,
+    f: F,
+}
+
+impl<'a, R, F> RefInspect<'a, R, F>
+where
+    F: FnMut(&[u8]),
+{
+    /// Creates a new `RefInspect` that passes every read chunk of `inner` to `f`.
+    pub const fn wrap(inner: &'a mut R, f: F) -> Self {
+        Self { inner, f }
+    }
+}
+
+impl<R: Read, F: FnMut(&[u8])> Read for RefInspect<'_, R, F> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        let n = self.inner.read(buf)?;
+        if n > 0 {
+            (self.f)(&buf[..n]);
+        }
+        Ok(n)
+    }
+}
+
+/// Implements the `BufRead` trait, reporting bytes to the callback as they are consumed.
+impl<R: BufRead, F: FnMut(&[u8])> BufRead for RefInspect<'_, R, F> {
+    fn fill_buf(&mut self) -> Result<&[u8], io::Error> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        if amt > 0
+            && let Ok(buf) = self.inner.fill_buf()
+        {
+            let amt = amt.min(buf.len());
+            if amt > 0 {
+                (self.f)(&buf[..amt]);
+            }
+        }
+        self.inner.consume(amt);
+    }
+}
+
+/// Extension trait to provide an `inspect_ref` method on all `Read` types.
+pub trait RefInspectExt {
+    /// Wraps the reader in a `RefInspect` that calls `f` with every read chunk.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Cursor, Read};
+    /// use reftake::{RefInspectExt, RefTakeExt};
+    ///
+    /// let mut cursor = Cursor::new(b"hello world");
+    /// let mut take = cursor.take_ref(5);
+    ///
+    /// let mut seen = 0;
+    /// let mut buf = Vec::new();
+    /// take.inspect_ref(|chunk| seen += chunk.len())
+    ///     .read_to_end(&mut buf)
+    ///     .unwrap();
+    /// assert_eq!(seen, 5);
+    /// ```
+    fn inspect_ref<F: FnMut(&[u8])>(&mut self, f: F) -> RefInspect<'_, Self, F>
+    where
+        Self: Sized;
+}
+
+impl<T: Read> RefInspectExt for T {
+    fn inspect_ref<F: FnMut(&[u8])>(&mut self, f: F) -> RefInspect<'_, Self, F> {
+        RefInspect::wrap(self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RefTakeExt;
+    use std::io::{BufReader, Cursor};
+
+    #[test]
+    fn test_inspect_sees_read_chunks() {
+        let mut reader = Cursor::new(b"abcdef");
+        let mut chunks = Vec::new();
+        {
+            let mut inspect = reader.inspect_ref(|chunk| chunks.push(chunk.to_vec()));
+            let mut buf = [0u8; 4];
+            assert_eq!(inspect.read(&mut buf).unwrap(), 4);
+            assert_eq!(inspect.read(&mut buf).unwrap(), 2);
+            assert_eq!(inspect.read(&mut buf).unwrap(), 0);
+        }
+        assert_eq!(chunks, vec![b"abcd".to_vec(), b"ef".to_vec()]);
+    }
+
+    #[test]
+    fn test_inspect_bufread_reports_consumed() {
+        let mut reader = BufReader::new(Cursor::new(b"one\ntwo\n"));
+        let mut seen = Vec::new();
+        {
+            let mut inspect = reader.inspect_ref(|chunk| seen.extend_from_slice(chunk));
+            let mut line = String::new();
+            inspect.read_line(&mut line).unwrap();
+        }
+        assert_eq!(seen, b"one\n");
+    }
+
+    #[test]
+    fn test_inspect_over_take_respects_limit() {
+        let mut reader = Cursor::new(b"hello world");
+        let mut take = reader.take_ref(7);
+        let mut total = 0;
+        {
+            let mut inspect = take.inspect_ref(|chunk| total += chunk.len());
+            let mut buf = Vec::new();
+            inspect.read_to_end(&mut buf).unwrap();
+        }
+        assert_eq!(total, 7);
+    }
+}
@@ -0,0 +1,96 @@
+//! Bounded JSON deserialization with `serde_json`.
+
+use std::io::{self, Read};
+
+use serde::de::DeserializeOwned;
+
+use crate::{LimitExceeded, RefTake, TrailingData};
+
+/// Deserializes a `T` from a reader holding a JSON document of at most `limit` bytes.
+///
+/// The whole reader is the document, as with `serde_json::from_reader`,
+/// and it is read through a [`RefTake`] so that at most one byte past the
+/// limit is ever consumed. Failures are reported as distinct `io::Error`s:
+///
+/// * a reader longer than `limit` bytes, whether or not the document was
+///   complete by then, carries a [`LimitExceeded`] payload;
+/// * anything but whitespace after the document carries a [`TrailingData`]
+///   payload;
+/// * a malformed or truncated document carries the `serde_json::Error`,
+///   with the kind `serde_json` gives it (`InvalidData` or `UnexpectedEof`).
+///
+/// # Example
+///
+/// ```
+/// use std::collections::HashMap;
+/// use std::io::Cursor;
+/// use reftake::LimitExceeded;
+///
+/// let mut body = Cursor::new(br#"{"user": "ada", "role": "admin"}"#);
+/// let map: HashMap<String, String> = reftake::json::from_reader_limited(&mut body, 64).unwrap();
+/// assert_eq!(map["user"], "ada");
+///
+/// let mut body = Cursor::new(br#"{"user": "ada", "role": "admin"}"#);
+/// let err = reftake::json::from_reader_limited::<_, HashMap<String, String>>(&mut body, 16)
+///     .unwrap_err();
+/// assert_eq!(LimitExceeded::from_io(&err), Some(&LimitExceeded::new(16)));
+/// assert_eq!(body.position(), 17);
+/// ```
+pub fn from_reader_limited<R: Read, T: DeserializeOwned>(
+    reader: &mut R,
+    limit: u64,
+) -> io::Result<T> {
+    let mut take = RefTake::wrap(reader, limit.saturating_add(1));
+    let mut de = serde_json::Deserializer::from_reader(&mut take);
+    let result = T::deserialize(&mut de).map(|value| (value, de.end()));
+    if take.current_limit() == 0 {
+        return Err(LimitExceeded::new(limit).into());
+    }
+    match result? {
+        (value, Ok(())) => Ok(value),
+        (_, Err(e)) if e.is_syntax() => Err(TrailingData.into()),
+        (_, Err(e)) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+    use std::io::{Cursor, ErrorKind};
+
+    #[test]
+    fn test_document_of_exactly_limit_bytes() {
+        let mut reader = Cursor::new(b"[1, 2, 3]  ");
+        let value: Vec<u32> = from_reader_limited(&mut reader, 11).unwrap();
+        assert_eq!(value, [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_number_cut_by_limit_is_not_accepted() {
+        let mut reader = Cursor::new(b"12345");
+        let err = from_reader_limited::<_, u64>(&mut reader, 3).unwrap_err();
+        assert_eq!(LimitExceeded::from_io(&err), Some(&LimitExceeded::new(3)));
+        assert_eq!(reader.position(), 4);
+    }
+
+    #[test]
+    fn test_trailing_data_is_reported() {
+        let mut reader = Cursor::new(b"{\"a\": 1} {\"b\": 2}");
+        let err = from_reader_limited::<_, Value>(&mut reader, 64).unwrap_err();
+        assert_eq!(TrailingData::from_io(&err), Some(&TrailingData));
+    }
+
+    #[test]
+    fn test_parse_errors_keep_serde_kind() {
+        let mut reader = Cursor::new(b"{\"a\": tru}");
+        let err = from_reader_limited::<_, Value>(&mut reader, 64).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(err.get_ref().unwrap().is::<serde_json::Error>());
+
+        let mut reader = Cursor::new(b"{\"a\": ");
+        let err = from_reader_limited::<_, Value>(&mut reader, 64).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+        assert_eq!(LimitExceeded::from_io(&err), None);
+    }
+}
@@ -4,6 +4,7 @@
 //! # Example
 //!
 //! ```
+//! # #[cfg(feature = "std")] {
 //! use std::io::{Cursor, Read};
 //! use reftake::RefTakeExt;
 //!
@@ -17,11 +18,501 @@
 //! let mut buf2 = String::new();
 //! cursor.read_to_string(&mut buf2).unwrap();
 //! assert_eq!(buf2, " world");
+//! # }
 //! ```
+//!
+//! # `no_std`
+//!
+//! The `std` feature, on by default, provides the `std::io` implementations
+//! and every adapter built on them. Without it the crate is `no_std`: the
+//! `RefTake` type and the error payloads stay available, together with the
+//! `embedded-io`, `embedded-io-async`, `acid_io` and `bytes::Buf`
+//! implementations, and `RefSkip`, `RefChain` and `RefCount` implement the
+//! `embedded-io` traits too. Every other adapter needs `std`. `acid_io`
+//! depends on the `std` feature of `memchr`, so its implementations build
+//! without the `std` feature of this crate, but not for targets without a
+//! standard library. The
+//! `alloc` feature turns on the `Vec` and `String` helpers of those traits,
+//! and the `defmt` feature lets firmware log limits and errors with `defmt`.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+/// Emits a `tracing` event, and a `log` record, under the `reftake` target
+/// if the `tracing` or `log` feature is enabled.
+///
+/// Takes `tracing` syntax: the level, the fields, then the message. The
+/// log record gets the fields appended to the message as `name=value`.
+#[cfg(feature = "std")]
+macro_rules! trace_event {
+    (@log $level:ident, [$($field:ident = $value:expr),*], $name:ident = $val:expr, $($rest:tt)+) => {
+        trace_event!(@log $level, [$($field = $value,)* $name = $val], $($rest)+)
+    };
+    (@log $level:ident, [$($field:ident = $value:expr),*], $name:ident, $($rest:tt)+) => {
+        trace_event!(@log $level, [$($field = $value,)* $name = $name], $($rest)+)
+    };
+    (@log $level:ident, [$($field:ident = $value:expr),*], $message:literal) => {
+        log::$level!(
+            target: "reftake",
+            concat!($message $(, " ", stringify!($field), "={}")*)
+            $(, $value)*
+        )
+    };
+    ($level:ident, $($arg:tt)+) => {
+        #[cfg(feature = "tracing")]
+        tracing::$level!(target: "reftake", $($arg)+);
+        #[cfg(feature = "log")]
+        trace_event!(@log $level, [], $($arg)+);
+    };
+}
+
+#[cfg(feature = "std")]
 use std::{
     cmp,
-    io::{BufRead, Read},
+    io::{BufRead, Read, Write},
+};
+
+mod error;
+mod limited;
+pub mod prelude;
+
+#[cfg(feature = "acid_io")]
+mod acid;
+#[cfg(feature = "std")]
+mod aligned;
+#[cfg(feature = "http-body")]
+mod async_body;
+#[cfg(feature = "tokio")]
+mod async_bridge;
+#[cfg(feature = "tokio")]
+mod async_copy;
+#[cfg(feature = "tokio")]
+mod async_drain;
+#[cfg(feature = "tokio")]
+mod async_dynamic;
+#[cfg(feature = "embedded-io-async")]
+mod async_embedded;
+#[cfg(feature = "tokio")]
+mod async_exact;
+#[cfg(feature = "stream")]
+mod async_frame;
+#[cfg(feature = "futures-io")]
+mod async_futures;
+#[cfg(feature = "monoio")]
+mod async_monoio;
+#[cfg(feature = "tokio")]
+mod async_permits;
+#[cfg(feature = "tokio")]
+mod async_read;
+#[cfg(feature = "tokio")]
+mod async_seek;
+#[cfg(feature = "stream")]
+mod async_stream;
+#[cfg(feature = "tokio")]
+mod async_throttle;
+#[cfg(feature = "wasm")]
+mod async_web;
+#[cfg(feature = "tokio")]
+mod async_write;
+#[cfg(feature = "base64")]
+mod base64_decoder;
+#[cfg(feature = "bincode")]
+pub mod bincode;
+#[cfg(feature = "binrw")]
+mod binrw_window;
+#[cfg(feature = "std")]
+mod body;
+#[cfg(feature = "std")]
+mod bom;
+#[cfg(feature = "std")]
+mod boundary;
+#[cfg(feature = "std")]
+mod bounded_lines;
+#[cfg(feature = "std")]
+mod budget;
+#[cfg(feature = "bytes")]
+mod buf;
+#[cfg(feature = "std")]
+mod buffered;
+#[cfg(feature = "stream")]
+mod byte_stream;
+#[cfg(feature = "std")]
+mod bytes;
+#[cfg(feature = "cbor")]
+pub mod cbor;
+mod chain;
+#[cfg(feature = "std")]
+mod chars;
+#[cfg(feature = "std")]
+mod charset;
+#[cfg(feature = "std")]
+mod chunked;
+#[cfg(feature = "std")]
+mod chunks;
+#[cfg(all(feature = "std", unix))]
+mod clone_window;
+#[cfg(feature = "tokio-util")]
+mod codec;
+#[cfg(feature = "std")]
+mod copy;
+mod count;
+#[cfg(feature = "std")]
+mod count_write;
+#[cfg(feature = "crc32")]
+mod crc32;
+#[cfg(feature = "std")]
+mod deadline;
+#[cfg(feature = "std")]
+mod duplex;
+#[cfg(feature = "std")]
+mod dynamic;
+#[cfg(feature = "embedded-io")]
+mod embedded;
+#[cfg(feature = "std")]
+mod events;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "std")]
+mod fmt_limit;
+#[cfg(feature = "std")]
+mod frame;
+#[cfg(feature = "std")]
+mod fuse;
+#[cfg(feature = "std")]
+mod guard;
+#[cfg(feature = "digest")]
+mod hashing;
+#[cfg(feature = "std")]
+mod hexdump;
+#[cfg(feature = "std")]
+mod history;
+#[cfg(feature = "std")]
+mod inspect;
+#[cfg(feature = "json")]
+pub mod json;
+#[cfg(feature = "std")]
+mod limited_buf;
+#[cfg(feature = "embedded-io")]
+mod line_slice;
+#[cfg(feature = "std")]
+mod lines;
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+mod members;
+#[cfg(feature = "std")]
+mod min_rate;
+#[cfg(feature = "memmap2")]
+mod mmap;
+#[cfg(feature = "std")]
+mod multi_take;
+#[cfg(feature = "std")]
+mod multipart;
+#[cfg(feature = "std")]
+mod netstring;
+#[cfg(feature = "std")]
+mod newlines;
+#[cfg(feature = "nom")]
+mod nom_stream;
+#[cfg(feature = "std")]
+mod os;
+#[cfg(feature = "std")]
+mod padded;
+#[cfg(feature = "rayon")]
+mod par_segments;
+#[cfg(feature = "std")]
+mod peek;
+#[cfg(feature = "std")]
+mod pem;
+#[cfg(feature = "std")]
+mod pipeline;
+#[cfg(any(feature = "bytemuck", feature = "zerocopy"))]
+mod pod;
+#[cfg(feature = "postcard")]
+pub mod postcard;
+#[cfg(feature = "std")]
+mod prefetch;
+#[cfg(feature = "std")]
+mod prefixed;
+#[cfg(feature = "primitives")]
+mod primitives;
+#[cfg(feature = "std")]
+mod process;
+#[cfg(feature = "std")]
+mod progress;
+#[cfg(feature = "std")]
+mod ratio;
+#[cfg(feature = "std")]
+mod read_at;
+#[cfg(feature = "std")]
+mod read_at_rent;
+#[cfg(feature = "std")]
+mod record;
+#[cfg(feature = "std")]
+mod records;
+#[cfg(feature = "std")]
+mod remaining;
+#[cfg(feature = "std")]
+mod seek_take;
+#[cfg(feature = "std")]
+mod segmented;
+#[cfg(feature = "embedded-hal-nb")]
+mod serial;
+mod skip;
+#[cfg(feature = "std")]
+mod slices;
+#[cfg(feature = "std")]
+mod socket_deadline;
+#[cfg(feature = "std")]
+mod split;
+#[cfg(feature = "std")]
+mod stats;
+#[cfg(feature = "std")]
+mod take_buffered;
+#[cfg(feature = "std")]
+mod take_while;
+#[cfg(feature = "std")]
+mod tee;
+#[cfg(feature = "std")]
+mod tee_write;
+#[cfg(feature = "test-util")]
+pub mod testing;
+#[cfg(feature = "std")]
+mod throttle;
+#[cfg(feature = "std")]
+mod tlv;
+#[cfg(feature = "std")]
+mod transaction;
+#[cfg(feature = "std")]
+mod until;
+#[cfg(feature = "std")]
+mod utf8;
+#[cfg(feature = "std")]
+mod varint;
+#[cfg(feature = "std")]
+mod window;
+#[cfg(feature = "std")]
+mod window_state;
+#[cfg(feature = "std")]
+mod write;
+
+pub use error::{InvalidUtf8, LimitExceeded, RatioExceeded, TrailingData};
+pub use limited::{Limited, LimitedMut};
+
+#[cfg(feature = "acid_io")]
+pub use acid::AcidRefTakeExt;
+#[cfg(feature = "std")]
+pub use aligned::{RefTakeAligned, RefTakeAlignedExt};
+#[cfg(feature = "http-body")]
+pub use async_body::{LimitedBody, LimitedBodyExt};
+#[cfg(feature = "tokio")]
+pub use async_bridge::{SyncReadBridge, SyncReadBridgeExt};
+#[cfg(feature = "tokio")]
+pub use async_drain::{MustDrain, MustDrainExt};
+#[cfg(feature = "tokio")]
+pub use async_dynamic::{DynamicTake, DynamicTakeExt};
+#[cfg(feature = "embedded-io-async")]
+pub use async_embedded::EmbeddedRefTakeExt;
+#[cfg(feature = "tokio")]
+pub use async_exact::{ResumableTake, ResumableTakeExt};
+#[cfg(feature = "stream")]
+pub use async_frame::{AsyncFrameReader, AsyncFrameReaderExt};
+#[cfg(feature = "futures-io")]
+pub use async_futures::{FuturesRefTakeExt, FuturesRefTakeWriteExt};
+#[cfg(feature = "monoio")]
+pub use async_monoio::RentRefTakeExt;
+#[cfg(feature = "tokio")]
+pub use async_permits::{PermitReader, PermitReaderExt};
+#[cfg(feature = "tokio")]
+pub use async_read::AsyncRefTakeExt;
+#[cfg(feature = "tokio")]
+pub use async_seek::{AsyncSeekTake, AsyncSeekTakeExt};
+#[cfg(feature = "stream")]
+pub use async_stream::{StreamTake, StreamTakeExt};
+#[cfg(feature = "tokio")]
+pub use async_throttle::{AsyncThrottle, AsyncThrottleExt};
+#[cfg(feature = "wasm")]
+pub use async_web::ReadableStreamReader;
+#[cfg(feature = "tokio")]
+pub use async_write::AsyncRefTakeWriteExt;
+#[cfg(feature = "base64")]
+pub use base64_decoder::{Base64Decoder, Base64DecoderExt};
+#[cfg(feature = "binrw")]
+pub use binrw_window::parse_window;
+#[cfg(feature = "std")]
+pub use body::{BodyReader, body_reader};
+#[cfg(feature = "std")]
+pub use bom::{Bom, RefStripBom, RefStripBomExt};
+#[cfg(feature = "std")]
+pub use boundary::{RefTakeUntilBoundary, RefTakeUntilBoundaryExt};
+#[cfg(feature = "std")]
+pub use bounded_lines::{BoundedLines, BoundedLinesExt, LineEnd, LongLine};
+#[cfg(feature = "std")]
+pub use budget::{BudgetReader, BudgetReaderExt, BudgetTracker};
+#[cfg(feature = "bytes")]
+pub use buf::BufRefTakeExt;
+#[cfg(feature = "std")]
+pub use buffered::Buffered;
+#[cfg(feature = "stream")]
+pub use byte_stream::{AsyncByteStreamExt, ByteStream, ByteStreamExt};
+#[cfg(feature = "std")]
+pub use bytes::LimitedBytes;
+pub use chain::RefChain;
+#[cfg(feature = "std")]
+pub use chain::RefChainExt;
+#[cfg(feature = "std")]
+pub use chars::{Chars, CharsExt};
+#[cfg(feature = "std")]
+pub use charset::{Charset, CharsetDecoder, RefDecodeText, RefDecodeTextExt, TextDecoder};
+#[cfg(feature = "std")]
+pub use chunked::{ChunkedDecoder, ChunkedDecoderExt};
+#[cfg(feature = "std")]
+pub use chunks::{Chunks, ChunksExt};
+#[cfg(feature = "std")]
+pub use copy::{CopyStatus, ShortCopy, copy_exact, copy_limited, copy_limited_buf};
+pub use count::RefCount;
+#[cfg(feature = "std")]
+pub use count::RefCountExt;
+#[cfg(feature = "std")]
+pub use count_write::{RefCountWrite, RefCountWriteExt};
+#[cfg(feature = "crc32")]
+pub use crc32::{Crc32Reader, Crc32ReaderExt};
+#[cfg(feature = "std")]
+pub use deadline::{RefDeadline, RefDeadlineExt};
+#[cfg(feature = "std")]
+pub use duplex::{RefDuplexLimit, RefDuplexLimitExt};
+#[cfg(feature = "std")]
+pub use dynamic::{LimitSource, RefTakeDynamic, RefTakeDynamicExt};
+#[cfg(feature = "embedded-io")]
+pub use embedded::{
+    EmbeddedIoRefChainExt, EmbeddedIoRefCountExt, EmbeddedIoRefSkipExt, EmbeddedIoRefTakeExt,
+};
+#[cfg(feature = "std")]
+pub use error::{BudgetExhausted, ReadContext};
+#[cfg(feature = "std")]
+pub use events::{EventSink, ReadEvent, RefEvents, RefEventsExt};
+#[cfg(feature = "std")]
+pub use fmt_limit::{RefFmtLimit, RefFmtLimitExt};
+#[cfg(feature = "std")]
+pub use frame::{
+    FrameBody, FrameConfig, FrameQuotaExceeded, FrameReader, FrameReaderExt, FrameState,
+    FramingStrategy, LengthEncoding,
+};
+#[cfg(feature = "std")]
+pub use fuse::{RefFuse, RefFuseExt};
+#[cfg(feature = "std")]
+pub use guard::{RefGuard, RefGuardExt};
+#[cfg(feature = "digest")]
+pub use hashing::{HashingReader, HashingReaderExt};
+#[cfg(feature = "std")]
+pub use hexdump::{RefHexDump, RefHexDumpExt};
+#[cfg(feature = "std")]
+pub use history::{RefHistory, RefHistoryExt};
+#[cfg(feature = "std")]
+pub use inspect::{RefInspect, RefInspectExt};
+#[cfg(feature = "std")]
+pub use limited_buf::{LimitedBufReader, LimitedBufReaderExt};
+#[cfg(feature = "embedded-io-async")]
+pub use line_slice::read_until_into_async;
+#[cfg(feature = "embedded-io")]
+pub use line_slice::{LineStatus, read_until_into};
+#[cfg(feature = "std")]
+pub use lines::{LineLimited, LineLimitedExt};
+#[cfg(feature = "gzip")]
+pub use members::GzipMembers;
+#[cfg(feature = "zstd")]
+pub use members::ZstdFrames;
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+pub use members::{Member, MemberDecoder, Members, MembersExt};
+#[cfg(feature = "std")]
+pub use min_rate::{MinRateConfig, RefMinRate, RefMinRateExt};
+#[cfg(feature = "memmap2")]
+pub use mmap::{MmapWindow, MmapWindowExt};
+#[cfg(feature = "std")]
+pub use multi_take::{RefMultiTake, RefMultiTakeExt};
+#[cfg(feature = "std")]
+pub use multipart::{MultipartReader, MultipartReaderExt, Part};
+#[cfg(feature = "std")]
+pub use netstring::{Netstring, NetstringReader, NetstringReaderExt};
+#[cfg(feature = "std")]
+pub use newlines::{NewlineMode, RefNormalizeNewlines, RefNormalizeNewlinesExt};
+#[cfg(feature = "nom")]
+pub use nom_stream::NomError;
+#[cfg(feature = "std")]
+pub use padded::{PaddedTake, PaddedTakeExt};
+#[cfg(feature = "rayon")]
+pub use par_segments::par_segments;
+#[cfg(feature = "std")]
+pub use peek::{RefPeek, RefPeekExt};
+#[cfg(feature = "std")]
+pub use pem::{PemReader, PemReaderExt, PemSection};
+#[cfg(feature = "std")]
+pub use pipeline::Pipeline;
+#[cfg(feature = "std")]
+pub use prefetch::{RefPrefetch, RefPrefetchExt};
+#[cfg(feature = "std")]
+pub use prefixed::{Endian, LengthPrefix};
+#[cfg(feature = "primitives")]
+pub use primitives::ReadPrimitives;
+#[cfg(feature = "std")]
+pub use process::{LimitedOutput, wait_with_output_limited};
+#[cfg(feature = "std")]
+pub use progress::{ProgressInterval, RefProgress};
+#[cfg(feature = "std")]
+pub use ratio::{RatioGuard, RatioGuardExt, RatioLimits};
+#[cfg(feature = "std")]
+pub use read_at::{
+    ReadAt, RefTakeAt, RefTakeAtExt, SharedTakeAt, SharedTakeAtExt, read_segments_into,
+};
+#[cfg(feature = "std")]
+pub use read_at_rent::{ReadAtRent, RentTakeAt, RentTakeAtExt};
+#[cfg(feature = "std")]
+pub use record::{RefRecorder, RefRecorderExt, ReplayReader};
+#[cfg(feature = "std")]
+pub use records::{PartialRecord, Records, RecordsExt};
+#[cfg(feature = "std")]
+pub use remaining::Utf8Policy;
+#[cfg(feature = "std")]
+pub use seek_take::{SeekTake, SeekTakeExt};
+#[cfg(feature = "std")]
+pub use segmented::{
+    InvalidSegment, SegmentRules, SegmentViolation, SegmentedReader, SegmentedReaderExt,
+};
+#[cfg(feature = "embedded-hal-nb")]
+pub use serial::{SerialError, SerialReader, SerialReaderExt};
+pub use skip::RefSkip;
+#[cfg(feature = "std")]
+pub use skip::RefSkipExt;
+#[cfg(feature = "std")]
+pub use slices::Slices;
+#[cfg(feature = "std")]
+pub use socket_deadline::{ReadTimeout, RefTakeTimed, RefTakeTimedExt};
+#[cfg(feature = "std")]
+pub use split::{BoundedSplit, BoundedSplitExt};
+#[cfg(feature = "std")]
+pub use stats::{ReadStats, RefStats, RefStatsExt};
+#[cfg(feature = "std")]
+pub use take_buffered::{RefTakeBuffered, RefTakeBufferedExt};
+#[cfg(feature = "std")]
+pub use take_while::{RefTakeWhile, RefTakeWhileExt};
+#[cfg(feature = "std")]
+pub use tee::{RefTee, RefTeeExt};
+#[cfg(feature = "std")]
+pub use tee_write::{RefTeeWrite, RefTeeWriteExt};
+#[cfg(feature = "std")]
+pub use throttle::{RefThrottle, RefThrottleExt};
+#[cfg(feature = "std")]
+pub use tlv::{TlvBody, TlvConfig, TlvReader, TlvReaderExt};
+#[cfg(feature = "std")]
+pub use until::{
+    RefTakeUntil, RefTakeUntilExt, UntilStatus, read_terminated, read_until_limited, skip_until,
 };
+#[cfg(feature = "std")]
+pub use utf8::{ControlChar, ControlChars, Utf8Validator, Utf8ValidatorExt};
+#[cfg(feature = "std")]
+pub use varint::ReadVarint;
+#[cfg(feature = "std")]
+pub use window::{RefWindow, RefWindowExt};
+#[cfg(feature = "std")]
+pub use window_state::WindowState;
+#[cfg(feature = "std")]
+pub use write::{OverflowBehavior, RefTakeWrite, RefTakeWriteExt};
 
 /// A non-owning adapter that wraps a mutable reference to a reader,
 /// limiting the number of bytes that can be read from it.
@@ -32,6 +523,8 @@ use std::{
 /// Useful in scenarios where ownership cannot be moved, such as within
 /// streaming parsers, frameworks, or when working with borrowed readers.
 pub struct RefTake<'a, R> {
+    // Only read by the trait implementations, which a bare core build has none of
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
     inner: &'a mut R,
     limit: u64,
 }
@@ -47,7 +540,7 @@ impl<'a, R> RefTake<'a, R> {
     /// # Returns
     ///
     /// A `RefTake` wrapper that enforces the given byte limit.
-    pub fn wrap(inner: &'a mut R, limit: u64) -> Self {
+    pub const fn wrap(inner: &'a mut R, limit: u64) -> Self {
         Self { inner, limit }
     }
 
@@ -69,6 +562,15 @@ impl<'a, R> RefTake<'a, R> {
     }
 }
 
+#[cfg(feature = "defmt")]
+/// Formats the remaining limit, the only state a `RefTake` owns.
+impl<R> defmt::Format for RefTake<'_, R> {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        defmt::write!(f, "RefTake {{ limit: {=u64} }}", self.limit)
+    }
+}
+
+#[cfg(feature = "std")]
 /// Implements the `Read` trait with a byte limit.
 ///
 /// This ensures no more than the configured number of bytes are read.
@@ -86,10 +588,15 @@ impl<T: Read> Read for RefTake<'_, T> {
         let n = self.inner.read(&mut buf[..max])?;
         assert!(n as u64 <= self.limit, "number of read bytes exceeds limit");
         self.limit -= n as u64;
+        trace_event!(trace, read = n, remaining = self.limit, "window read");
+        if self.limit == 0 {
+            trace_event!(debug, "window limit exhausted");
+        }
         Ok(n)
     }
 }
 
+#[cfg(feature = "std")]
 /// Implements the `BufRead` trait with a byte limit.
 ///
 /// `fill_buf()` returns a slice of the buffer capped at the remaining limit,
@@ -113,9 +620,39 @@ impl<T: BufRead> BufRead for RefTake<'_, T> {
         let amt = cmp::min(amt as u64, self.limit) as usize;
         self.limit -= amt as u64;
         self.inner.consume(amt);
+        trace_event!(
+            trace,
+            consumed = amt,
+            remaining = self.limit,
+            "window consume"
+        );
+        if amt > 0 && self.limit == 0 {
+            trace_event!(debug, "window limit exhausted");
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+/// Implements the `Write` trait as a transparent passthrough.
+///
+/// The byte limit only applies to reads; writes and flushes go straight to
+/// the inner stream, so duplex streams such as `TcpStream` stay usable while
+/// a bounded view of their input is alive.
+impl<T: Write> Write for RefTake<'_, T> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, std::io::Error> {
+        self.inner.write(buf)
+    }
+
+    fn write_vectored(&mut self, bufs: &[std::io::IoSlice<'_>]) -> Result<usize, std::io::Error> {
+        self.inner.write_vectored(bufs)
+    }
+
+    fn flush(&mut self) -> Result<(), std::io::Error> {
+        self.inner.flush()
     }
 }
 
+#[cfg(feature = "std")]
 /// Extension trait to provide a `take_ref` method on all `Read` types.
 pub trait RefTakeExt {
     /// Wraps the reader in a `RefTake`, allowing limited reading via a mutable reference.
@@ -143,16 +680,18 @@ pub trait RefTakeExt {
         Self: Sized;
 }
 
+#[cfg(feature = "std")]
 impl<T: Read> RefTakeExt for T {
     fn take_ref(&mut self, limit: u64) -> RefTake<'_, Self> {
+        trace_event!(trace, limit, "window created");
         RefTake::wrap(self, limit)
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
-    use std::io::{BufReader, Cursor, Read};
+    use std::io::{BufReader, Cursor, Read, Write};
 
     #[test]
     fn test_read_respects_limit() {
@@ -243,6 +782,21 @@ mod tests {
         assert_eq!(buf3, b"");
     }
 
+    #[test]
+    fn test_write_passthrough() {
+        let mut stream = Cursor::new(vec![0u8; 8]);
+        let mut take = stream.take_ref(2);
+
+        take.write_all(b"abcdef").unwrap();
+        take.flush().unwrap();
+        assert_eq!(take.current_limit(), 2);
+
+        let mut buf = [0u8; 4];
+        assert_eq!(take.read(&mut buf).unwrap(), 2);
+        assert_eq!(&buf[..2], &[0, 0]);
+        assert_eq!(stream.get_ref()[..6], *b"abcdef");
+    }
+
     #[test]
     fn test_bufread_consume_does_not_exceed_limit() {
         let data = b"abcde";
@@ -256,4 +810,121 @@ mod tests {
         let buf = take.fill_buf().unwrap();
         assert_eq!(buf, b"");
     }
+
+    #[test]
+    fn test_wrap_in_const_context() {
+        const fn header<R>(inner: &mut R) -> RefTake<'_, R> {
+            RefTake::wrap(inner, 4)
+        }
+        const EXCEEDED: LimitExceeded = LimitExceeded::new(4);
+
+        let mut reader = Cursor::new(b"abcdef");
+        let mut buf = Vec::new();
+        header(&mut reader).read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, b"abcd");
+        assert_eq!(EXCEEDED.limit(), 4);
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn test_tracing_events() {
+        use std::{
+            fmt,
+            sync::{Arc, Mutex},
+        };
+        use tracing::{
+            Event, Metadata, Subscriber,
+            field::{Field, Visit},
+            span,
+        };
+
+        /// Collects the messages of the events it sees.
+        struct Messages(Arc<Mutex<Vec<String>>>);
+
+        impl Visit for Messages {
+            fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+                if field.name() == "message" {
+                    self.0.lock().unwrap().push(format!("{value:?}"));
+                }
+            }
+        }
+
+        impl Subscriber for Messages {
+            fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+                metadata.target() == "reftake"
+            }
+            fn new_span(&self, _: &span::Attributes<'_>) -> span::Id {
+                span::Id::from_u64(1)
+            }
+            fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+            fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+            fn event(&self, event: &Event<'_>) {
+                event.record(&mut Messages(Arc::clone(&self.0)));
+            }
+            fn enter(&self, _: &span::Id) {}
+            fn exit(&self, _: &span::Id) {}
+        }
+
+        let messages = Arc::new(Mutex::new(Vec::new()));
+        tracing::subscriber::with_default(Messages(Arc::clone(&messages)), || {
+            let mut reader = Cursor::new(b"abcdef");
+            let mut buf = Vec::new();
+            reader.take_ref(4).read_to_end(&mut buf).unwrap();
+        });
+        assert_eq!(
+            *messages.lock().unwrap(),
+            ["window created", "window read", "window limit exhausted"]
+        );
+    }
+
+    #[cfg(feature = "log")]
+    #[test]
+    fn test_log_records() {
+        use std::{
+            sync::Mutex,
+            thread::{self, ThreadId},
+        };
+
+        /// Collects the records logged by one thread, since the logger is global.
+        struct Records(Mutex<Vec<(ThreadId, String)>>);
+
+        impl log::Log for Records {
+            fn enabled(&self, metadata: &log::Metadata<'_>) -> bool {
+                metadata.target() == "reftake"
+            }
+            fn log(&self, record: &log::Record<'_>) {
+                if self.enabled(record.metadata()) {
+                    let entry = (thread::current().id(), record.args().to_string());
+                    self.0.lock().unwrap().push(entry);
+                }
+            }
+            fn flush(&self) {}
+        }
+
+        static RECORDS: Records = Records(Mutex::new(Vec::new()));
+        log::set_logger(&RECORDS).unwrap();
+        log::set_max_level(log::LevelFilter::Trace);
+
+        let mut reader = Cursor::new(b"abcdef");
+        let mut buf = Vec::new();
+        reader.take_ref(4).read_to_end(&mut buf).unwrap();
+
+        let current = thread::current().id();
+        let records: Vec<_> = RECORDS
+            .0
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(thread, _)| *thread == current)
+            .map(|(_, message)| message.clone())
+            .collect();
+        assert_eq!(
+            records,
+            [
+                "window created limit=4",
+                "window read read=4 remaining=0",
+                "window limit exhausted",
+            ]
+        );
+    }
 }
@@ -0,0 +1,185 @@
+//! A common view of the byte limit of the crate's adapters and of `std::io::Take`.
+
+use crate::RefTake;
+
+/// A reader, stream or body that knows how many more bytes it may produce.
+///
+/// Lets generic code accept any bounded source, such as a [`RefTake`], a
+/// [`RefWindow`](crate::RefWindow) or a `std::io::Take`, and size its
+/// buffers or check a frame length up front without knowing which one it
+/// got. `None` means the source is not bounded.
+///
+/// The limit is an upper bound, not a promise: the inner reader may reach
+/// EOF before it is used up.
+pub trait Limited {
+    /// Returns the number of bytes that may still be produced, or `None` if unbounded.
+    fn remaining(&self) -> Option<u64>;
+}
+
+/// A [`Limited`] source whose limit can be changed while in use.
+pub trait LimitedMut: Limited {
+    /// Replaces the number of bytes that may still be produced.
+    fn set_remaining(&mut self, remaining: u64);
+}
+
+impl<T: Limited + ?Sized> Limited for &T {
+    fn remaining(&self) -> Option<u64> {
+        (**self).remaining()
+    }
+}
+
+impl<T: Limited + ?Sized> Limited for &mut T {
+    fn remaining(&self) -> Option<u64> {
+        (**self).remaining()
+    }
+}
+
+impl<T: LimitedMut + ?Sized> LimitedMut for &mut T {
+    fn set_remaining(&mut self, remaining: u64) {
+        (**self).set_remaining(remaining)
+    }
+}
+
+impl<R> Limited for RefTake<'_, R> {
+    fn remaining(&self) -> Option<u64> {
+        Some(self.current_limit())
+    }
+}
+
+impl<R> LimitedMut for RefTake<'_, R> {
+    fn set_remaining(&mut self, remaining: u64) {
+        self.set_limit(remaining)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: ?Sized + Limited> Limited for Box<T> {
+    fn remaining(&self) -> Option<u64> {
+        (**self).remaining()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R> Limited for std::io::Take<R> {
+    fn remaining(&self) -> Option<u64> {
+        Some(self.limit())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R> LimitedMut for std::io::Take<R> {
+    fn set_remaining(&mut self, remaining: u64) {
+        self.set_limit(remaining)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R> Limited for crate::RefWindow<'_, R> {
+    /// Returns the bytes left in the window, not counting any pending skip.
+    fn remaining(&self) -> Option<u64> {
+        Some(self.current_limit())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R> LimitedMut for crate::RefWindow<'_, R> {
+    fn set_remaining(&mut self, remaining: u64) {
+        self.set_limit(remaining)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R> Limited for crate::RefGuard<'_, R> {
+    /// Returns the bytes left before reads start failing.
+    fn remaining(&self) -> Option<u64> {
+        Some(self.max().saturating_sub(self.bytes_read()))
+    }
+}
+
+/// Implements [`Limited`] for adapters whose `current_limit` is the number of bytes left.
+macro_rules! limited_by_current_limit {
+    ($($(#[$cfg:meta])* [$($generics:tt)*] $ty:ty;)+) => {
+        $(
+            $(#[$cfg])*
+            impl<$($generics)*> Limited for $ty {
+                fn remaining(&self) -> Option<u64> {
+                    Some(self.current_limit())
+                }
+            }
+        )+
+    };
+}
+
+limited_by_current_limit! {
+    #[cfg(feature = "std")]
+    [R] crate::RefTakeAligned<'_, R>;
+    #[cfg(feature = "std")]
+    [R] crate::PaddedTake<'_, R>;
+    #[cfg(feature = "std")]
+    [R] crate::RefMultiTake<'_, R>;
+    #[cfg(feature = "std")]
+    [] crate::RefPrefetch;
+    #[cfg(feature = "std")]
+    [R] crate::SeekTake<'_, R>;
+    #[cfg(feature = "std")]
+    [R] crate::RefTakeBuffered<'_, R>;
+    #[cfg(feature = "std")]
+    [F: ?Sized] crate::RefTakeAt<'_, F>;
+    #[cfg(feature = "std")]
+    [F: ?Sized] crate::SharedTakeAt<F>;
+    #[cfg(feature = "std")]
+    [F: ?Sized] crate::RentTakeAt<'_, F>;
+    #[cfg(feature = "std")]
+    [R, F: FnMut(u64, u64)] crate::RefProgress<'_, R, F>;
+    #[cfg(feature = "std")]
+    [S: crate::ReadTimeout] crate::RefTakeTimed<'_, S>;
+    #[cfg(feature = "tokio")]
+    [R] crate::ResumableTake<'_, R>;
+    #[cfg(feature = "tokio")]
+    [R, F: Fn() -> u64] crate::DynamicTake<'_, R, F>;
+    #[cfg(feature = "tokio")]
+    [R] crate::AsyncSeekTake<'_, R>;
+    #[cfg(feature = "tokio")]
+    [R] crate::MustDrain<'_, R>;
+    #[cfg(feature = "tokio")]
+    [R] crate::SyncReadBridge<R>;
+    #[cfg(feature = "stream")]
+    [S] crate::StreamTake<'_, S>;
+    #[cfg(feature = "http-body")]
+    [B] crate::LimitedBody<B>;
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::{RefTakeExt, RefWindowExt};
+    use std::io::{Cursor, Read};
+
+    /// Reads what the source may still produce in one allocation.
+    fn read_rest<R: Read + Limited>(mut reader: R) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(reader.remaining().unwrap_or(0) as usize);
+        reader.read_to_end(&mut buf).unwrap();
+        buf
+    }
+
+    #[test]
+    fn test_generic_over_limited_sources() {
+        let mut cursor = Cursor::new(b"0123456789");
+        assert_eq!(read_rest(cursor.take_ref(3)), b"012");
+        assert_eq!(read_rest(cursor.take_window(1, 2)), b"45");
+        assert_eq!(read_rest((&mut cursor).take(2)), b"67");
+        assert_eq!(cursor.position(), 8);
+    }
+
+    #[test]
+    fn test_set_remaining() {
+        let mut cursor = Cursor::new(b"0123456789");
+        let mut take = cursor.take_ref(2);
+        let mut byte = [0u8; 1];
+        take.read_exact(&mut byte).unwrap();
+        assert_eq!(take.remaining(), Some(1));
+        take.set_remaining(5);
+        assert_eq!(read_rest(&mut take), b"12345");
+        assert_eq!(take.remaining(), Some(0));
+    }
+}
@@ -0,0 +1,399 @@
+//! A buffered reader over a borrowed source that hands out limited windows.
+
+use std::{
+    cmp,
+    io::{self, BufRead, ErrorKind, Read},
+};
+
+use crate::RefTake;
+
+const DEFAULT_CAPACITY: usize = 8 * 1024;
+
+/// A buffering adapter over a borrowed reader that keeps its buffer across windows.
+///
+/// Wrapping a raw `Read` in a fresh `BufReader` for every frame either loses
+/// whatever was read ahead past the frame, or forces unbuffered reads.
+/// `LimitedBufReader` owns a single buffer instead: [`next_window`] returns a
+/// [`RefTake`] view limited to one frame, and any bytes buffered beyond it
+/// are served to the following window.
+///
+/// [`next_window`]: LimitedBufReader::next_window
+pub struct LimitedBufReader<'a, R> {
+    inner: &'a mut R,
+    buf: Box<[u8]>,
+    pos: usize,
+    filled: usize,
+}
+
+impl<'a, R> LimitedBufReader<'a, R> {
+    /// Creates a new `LimitedBufReader` with a default buffer capacity of 8 KiB.
+    pub fn wrap(inner: &'a mut R) -> Self {
+        Self::with_capacity(inner, DEFAULT_CAPACITY)
+    }
+
+    /// Creates a new `LimitedBufReader` with the given buffer capacity.
+    pub fn with_capacity(inner: &'a mut R, capacity: usize) -> Self {
+        Self {
+            inner,
+            buf: vec![0; capacity].into_boxed_slice(),
+            pos: 0,
+            filled: 0,
+        }
+    }
+
+    /// Returns the bytes that have been read ahead from the inner reader but not consumed yet.
+    ///
+    /// These are lost if the `LimitedBufReader` is dropped.
+    pub fn buffer(&self) -> &[u8] {
+        &self.buf[self.pos..self.filled]
+    }
+
+    /// Returns the capacity of the internal buffer.
+    pub fn capacity(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Pushes `data` back in front of the buffered bytes, to be read again first.
+    ///
+    /// Lets a parser that read past what it needed, such as while scanning
+    /// for a boundary, hand the excess to the next window. The bytes need
+    /// not be the ones read last. Fails with `ErrorKind::InvalidInput`,
+    /// leaving the buffer untouched, if they do not fit in the buffer
+    /// alongside the bytes already buffered.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Cursor, Read};
+    /// use reftake::LimitedBufReaderExt;
+    ///
+    /// let mut socket = Cursor::new(b"body--next");
+    /// let mut reader = socket.limited_buf_ref();
+    /// let mut chunk = [0u8; 7];
+    /// reader.read_exact(&mut chunk).unwrap();
+    /// reader.unread(&chunk[4..]).unwrap();
+    ///
+    /// let mut frame = String::new();
+    /// reader.next_window(6).read_to_string(&mut frame).unwrap();
+    /// assert_eq!(frame, "--next");
+    /// ```
+    pub fn unread(&mut self, data: &[u8]) -> io::Result<()> {
+        unread(&mut self.buf, &mut self.pos, &mut self.filled, data)
+    }
+
+    /// Returns a view of the next `len` bytes of the stream.
+    ///
+    /// Whatever the window leaves unread, including bytes buffered past its
+    /// end, remains available to the next window.
+    pub fn next_window(&mut self, len: u64) -> RefTake<'_, Self> {
+        RefTake::wrap(self, len)
+    }
+}
+
+impl<R: Read> LimitedBufReader<'_, R> {
+    /// Reads until at least `n` bytes are buffered, and returns all the buffered bytes.
+    ///
+    /// Unlike `fill_buf`, which may return as little as one byte, this
+    /// guarantees a fixed-size header can be parsed from a single slice.
+    /// The buffered bytes are moved to the front of the buffer if `n` of
+    /// them would not fit after their current position. Fails with
+    /// `ErrorKind::InvalidInput` if `n` exceeds the capacity, and with
+    /// `ErrorKind::UnexpectedEof` if the stream ends first; the bytes read
+    /// until then stay buffered.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{BufRead, Cursor};
+    /// use reftake::LimitedBufReaderExt;
+    ///
+    /// let mut socket = Cursor::new(b"\x00\x05hello");
+    /// let mut reader = socket.limited_buf_ref();
+    /// let header = reader.ensure_buffered(2).unwrap();
+    /// let len = u16::from_be_bytes([header[0], header[1]]);
+    /// reader.consume(2);
+    /// assert_eq!(reader.ensure_buffered(len as usize).unwrap(), b"hello");
+    /// ```
+    pub fn ensure_buffered(&mut self, n: usize) -> io::Result<&[u8]> {
+        let mut unlimited = u64::MAX;
+        ensure_buffered(
+            &mut *self.inner,
+            &mut self.buf,
+            &mut self.pos,
+            &mut self.filled,
+            &mut unlimited,
+            n,
+        )?;
+        Ok(self.buffer())
+    }
+}
+
+/// Reads into `buf` until `buf[*pos..*filled]` holds at least `n` bytes,
+/// moving them to the front first if needed, pulling at most `*limit` bytes.
+pub(crate) fn ensure_buffered<R: Read + ?Sized>(
+    inner: &mut R,
+    buf: &mut [u8],
+    pos: &mut usize,
+    filled: &mut usize,
+    limit: &mut u64,
+    n: usize,
+) -> io::Result<()> {
+    if n > buf.len() {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("{n} bytes requested but the buffer holds {}", buf.len()),
+        ));
+    }
+    while *filled - *pos < n {
+        if buf.len() - *pos < n {
+            buf.copy_within(*pos..*filled, 0);
+            *filled -= *pos;
+            *pos = 0;
+        }
+        let max = cmp::min((buf.len() - *filled) as u64, *limit) as usize;
+        match inner.read(&mut buf[*filled..*filled + max]) {
+            Ok(0) => {
+                return Err(io::Error::new(
+                    ErrorKind::UnexpectedEof,
+                    format!("stream ended with {} of {n} bytes buffered", *filled - *pos),
+                ));
+            }
+            Ok(read) => {
+                assert!(read <= max, "number of read bytes exceeds buffer");
+                *filled += read;
+                *limit -= read as u64;
+            }
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+/// Puts `data` in front of `buf[*pos..*filled]`, moving the buffered bytes if needed.
+pub(crate) fn unread(
+    buf: &mut [u8],
+    pos: &mut usize,
+    filled: &mut usize,
+    data: &[u8],
+) -> io::Result<()> {
+    if data.len() <= *pos {
+        *pos -= data.len();
+    } else {
+        let buffered = *filled - *pos;
+        if buffered + data.len() > buf.len() {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "no room in the buffer to unread",
+            ));
+        }
+        buf.copy_within(*pos..*filled, data.len());
+        *pos = 0;
+        *filled = data.len() + buffered;
+    }
+    buf[*pos..*pos + data.len()].copy_from_slice(data);
+    Ok(())
+}
+
+impl<R> RefTake<'_, LimitedBufReader<'_, R>> {
+    /// Pushes `data` back into the buffer and adds its length back to the limit.
+    ///
+    /// See [`LimitedBufReader::unread`].
+    pub fn unread(&mut self, data: &[u8]) -> io::Result<()> {
+        self.inner.unread(data)?;
+        self.limit += data.len() as u64;
+        Ok(())
+    }
+}
+
+impl<R: Read> RefTake<'_, LimitedBufReader<'_, R>> {
+    /// Reads until at least `n` bytes of the window are buffered, and returns the buffered bytes of the window.
+    ///
+    /// Fails with `ErrorKind::UnexpectedEof` without reading anything if
+    /// the window has fewer than `n` bytes left. See
+    /// [`LimitedBufReader::ensure_buffered`].
+    pub fn ensure_buffered(&mut self, n: usize) -> io::Result<&[u8]> {
+        if n as u64 > self.limit {
+            return Err(io::Error::new(
+                ErrorKind::UnexpectedEof,
+                format!("{n} bytes requested but only {} left in window", self.limit),
+            ));
+        }
+        let buf = self.inner.ensure_buffered(n)?;
+        Ok(&buf[..cmp::min(buf.len() as u64, self.limit) as usize])
+    }
+}
+
+impl<R: Read> Read for LimitedBufReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        // Bypass the internal buffer for large reads when it is empty
+        if self.pos == self.filled && buf.len() >= self.buf.len() {
+            return self.inner.read(buf);
+        }
+        let available = self.fill_buf()?;
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<R: Read> BufRead for LimitedBufReader<'_, R> {
+    fn fill_buf(&mut self) -> Result<&[u8], io::Error> {
+        if self.pos == self.filled {
+            self.filled = self.inner.read(&mut self.buf)?;
+            self.pos = 0;
+        }
+        Ok(&self.buf[self.pos..self.filled])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = (self.pos + amt).min(self.filled);
+    }
+}
+
+/// Extension trait to provide a `limited_buf_ref` method on all `Read` types.
+pub trait LimitedBufReaderExt {
+    /// Wraps the reader in a `LimitedBufReader` with the default capacity.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{BufRead, Cursor, Read};
+    /// use reftake::LimitedBufReaderExt;
+    ///
+    /// let mut socket = Cursor::new(b"first\nsecond\n");
+    /// let mut reader = socket.limited_buf_ref();
+    ///
+    /// let mut frame = String::new();
+    /// reader.next_window(6).read_line(&mut frame).unwrap();
+    /// assert_eq!(frame, "first\n");
+    ///
+    /// let mut frame = String::new();
+    /// reader.next_window(7).read_to_string(&mut frame).unwrap();
+    /// assert_eq!(frame, "second\n");
+    /// ```
+    fn limited_buf_ref(&mut self) -> LimitedBufReader<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: Read> LimitedBufReaderExt for T {
+    fn limited_buf_ref(&mut self) -> LimitedBufReader<'_, Self> {
+        LimitedBufReader::wrap(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_excess_carries_over_to_next_window() {
+        let mut source = Cursor::new(b"abcdefghij");
+        let mut reader = LimitedBufReader::with_capacity(&mut source, 8);
+
+        let mut frame = Vec::new();
+        reader.next_window(3).read_to_end(&mut frame).unwrap();
+        assert_eq!(frame, b"abc");
+        assert_eq!(reader.buffer(), b"defgh");
+
+        frame.clear();
+        reader.next_window(4).read_to_end(&mut frame).unwrap();
+        assert_eq!(frame, b"defg");
+
+        frame.clear();
+        reader.next_window(10).read_to_end(&mut frame).unwrap();
+        assert_eq!(frame, b"hij");
+    }
+
+    #[test]
+    fn test_window_bufread() {
+        let mut source = Cursor::new(b"one\ntwo\nthree\n");
+        let mut reader = source.limited_buf_ref();
+
+        let lines: Vec<String> = reader.next_window(8).lines().map(Result::unwrap).collect();
+        assert_eq!(lines, vec!["one", "two"]);
+
+        let mut rest = String::new();
+        reader.read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, "three\n");
+    }
+
+    #[test]
+    fn test_large_read_bypasses_empty_buffer() {
+        let mut source = Cursor::new(b"abcdefgh");
+        let mut reader = LimitedBufReader::with_capacity(&mut source, 2);
+
+        let mut buf = [0u8; 4];
+        assert_eq!(reader.read(&mut buf).unwrap(), 4);
+        assert!(reader.buffer().is_empty());
+        assert_eq!(reader.capacity(), 2);
+    }
+
+    #[test]
+    fn test_unread() {
+        let mut source = Cursor::new(b"abcdefghij");
+        let mut reader = LimitedBufReader::with_capacity(&mut source, 6);
+        let mut buf = [0u8; 4];
+        reader.read_exact(&mut buf).unwrap();
+        // Fits in the consumed part of the buffer
+        reader.unread(b"XY").unwrap();
+        assert_eq!(reader.buffer(), b"XYef");
+        // Moves the buffered bytes to make room
+        reader.unread(b"12").unwrap();
+        assert_eq!(reader.buffer(), b"12XYef");
+        let err = reader.unread(b"!").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        assert_eq!(reader.buffer(), b"12XYef");
+
+        let mut window = reader.next_window(3);
+        window.read_exact(&mut buf[..3]).unwrap();
+        window.unread(&buf[1..3]).unwrap();
+        assert_eq!(window.current_limit(), 2);
+        let mut rest = Vec::new();
+        window.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest, b"2X");
+        rest.clear();
+        reader.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest, b"Yefghij");
+    }
+
+    /// Returns at most `chunk` bytes per read.
+    struct Trickle<'a> {
+        data: &'a [u8],
+        chunk: usize,
+    }
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = buf.len().min(self.chunk);
+            self.data.read(&mut buf[..n])
+        }
+    }
+
+    #[test]
+    fn test_ensure_buffered_compacts_and_refills() {
+        let mut source = Trickle {
+            data: b"abcdefghij",
+            chunk: 2,
+        };
+        let mut reader = LimitedBufReader::with_capacity(&mut source, 6);
+        assert_eq!(reader.ensure_buffered(5).unwrap(), b"abcdef");
+        reader.consume(4);
+        assert_eq!(reader.ensure_buffered(5).unwrap(), b"efghij");
+
+        let err = reader.ensure_buffered(7).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+        let mut window = reader.next_window(3);
+        assert_eq!(window.ensure_buffered(2).unwrap(), b"efg");
+        let err = window.ensure_buffered(4).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+        reader.consume(5);
+        let err = reader.ensure_buffered(2).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+        assert_eq!(reader.buffer(), b"j");
+    }
+}
@@ -0,0 +1,202 @@
+//! Reading delimited lines into caller-provided buffers, without allocating.
+
+use core::cmp;
+
+/// How a [`read_until_into`] call ended, with the number of bytes written to the buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum LineStatus {
+    /// The delimiter was found; it is the last byte written.
+    Found(usize),
+    /// The buffer filled up before the delimiter.
+    Overflow(usize),
+    /// The reader (or its window) ended before the delimiter.
+    Eof(usize),
+}
+
+impl LineStatus {
+    /// Returns the number of bytes written to the buffer.
+    pub fn len(&self) -> usize {
+        match *self {
+            LineStatus::Found(n) | LineStatus::Overflow(n) | LineStatus::Eof(n) => n,
+        }
+    }
+
+    /// Returns `true` if no bytes were written to the buffer.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` if the delimiter was found.
+    pub fn found(&self) -> bool {
+        matches!(self, LineStatus::Found(_))
+    }
+}
+
+/// Copies bytes from an `embedded_io::BufRead` into `buf` up to and including `delim`.
+///
+/// This is the fixed-buffer counterpart of `read_until_limited` for targets
+/// without an allocator. Bytes are consumed only as they are copied: after
+/// an overflow the reader is left at the first byte that did not fit, so the
+/// rest of the line can be read or skipped with another call. Wrap the
+/// reader in a [`RefTake`](crate::RefTake) to also bound the total read, in
+/// which case the end of the window is reported as [`LineStatus::Eof`].
+///
+/// # Example
+///
+/// ```
+/// use reftake::{EmbeddedIoRefTakeExt, LineStatus, read_until_into};
+///
+/// let mut uart: &[u8] = b"+CSQ: 21,0\r\nOK\r\n";
+/// let mut line = [0u8; 8];
+/// let mut reply = uart.take_ref_embedded_io(16);
+/// assert_eq!(read_until_into(&mut reply, b'\n', &mut line), Ok(LineStatus::Overflow(8)));
+/// assert_eq!(&line, b"+CSQ: 21");
+/// assert_eq!(read_until_into(&mut reply, b'\n', &mut line), Ok(LineStatus::Found(4)));
+/// assert_eq!(&line[..4], b",0\r\n");
+/// ```
+#[cfg(feature = "embedded-io")]
+pub fn read_until_into<R: embedded_io::BufRead + ?Sized>(
+    reader: &mut R,
+    delim: u8,
+    buf: &mut [u8],
+) -> Result<LineStatus, R::Error> {
+    let mut filled = 0;
+    loop {
+        if filled == buf.len() {
+            return Ok(LineStatus::Overflow(filled));
+        }
+        let available = reader.fill_buf()?;
+        if available.is_empty() {
+            return Ok(LineStatus::Eof(filled));
+        }
+        let (n, found) = copy_until(available, delim, &mut buf[filled..]);
+        reader.consume(n);
+        filled += n;
+        if found {
+            return Ok(LineStatus::Found(filled));
+        }
+    }
+}
+
+/// Copies bytes from an `embedded_io_async::BufRead` into `buf` up to and including `delim`.
+///
+/// The async counterpart of [`read_until_into`], with the same consumption
+/// and status rules.
+///
+/// # Example
+///
+/// ```
+/// use futures::executor::block_on;
+/// use reftake::{EmbeddedRefTakeExt, LineStatus, read_until_into_async};
+///
+/// block_on(async {
+///     let mut uart: &[u8] = b"READY";
+///     let mut line = [0u8; 16];
+///     let mut reply = uart.take_ref_embedded(5);
+///     let status = read_until_into_async(&mut reply, b'\n', &mut line).await;
+///     assert_eq!(status, Ok(LineStatus::Eof(5)));
+/// });
+/// ```
+#[cfg(feature = "embedded-io-async")]
+pub async fn read_until_into_async<R: embedded_io_async::BufRead + ?Sized>(
+    reader: &mut R,
+    delim: u8,
+    buf: &mut [u8],
+) -> Result<LineStatus, R::Error> {
+    let mut filled = 0;
+    loop {
+        if filled == buf.len() {
+            return Ok(LineStatus::Overflow(filled));
+        }
+        let available = reader.fill_buf().await?;
+        if available.is_empty() {
+            return Ok(LineStatus::Eof(filled));
+        }
+        let (n, found) = copy_until(available, delim, &mut buf[filled..]);
+        reader.consume(n);
+        filled += n;
+        if found {
+            return Ok(LineStatus::Found(filled));
+        }
+    }
+}
+
+/// Copies from `available` into `out` up to and including `delim`, returning
+/// the number of bytes copied and whether the delimiter was among them.
+fn copy_until(available: &[u8], delim: u8, out: &mut [u8]) -> (usize, bool) {
+    let room = cmp::min(available.len(), out.len());
+    let (n, found) = match available[..room].iter().position(|&b| b == delim) {
+        Some(i) => (i + 1, true),
+        None => (room, false),
+    };
+    out[..n].copy_from_slice(&available[..n]);
+    (n, found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EmbeddedIoRefTakeExt;
+
+    #[test]
+    fn test_found_overflow_and_eof_are_distinct() {
+        let mut reader: &[u8] = b"ok\nlonger line\ntail";
+        let mut take = reader.take_ref_embedded_io(18);
+        let mut buf = [0u8; 6];
+
+        assert_eq!(
+            read_until_into(&mut take, b'\n', &mut buf),
+            Ok(LineStatus::Found(3))
+        );
+        assert_eq!(&buf[..3], b"ok\n");
+        assert_eq!(
+            read_until_into(&mut take, b'\n', &mut buf),
+            Ok(LineStatus::Overflow(6))
+        );
+        assert_eq!(&buf, b"longer");
+        assert_eq!(
+            read_until_into(&mut take, b'\n', &mut buf),
+            Ok(LineStatus::Found(6))
+        );
+        assert_eq!(&buf, b" line\n");
+        assert_eq!(
+            read_until_into(&mut take, b'\n', &mut buf),
+            Ok(LineStatus::Eof(3))
+        );
+        assert_eq!(&buf[..3], b"tai");
+        assert_eq!(take.current_limit(), 0);
+        assert_eq!(reader, b"l");
+    }
+
+    #[test]
+    fn test_delimiter_that_does_not_fit_is_left_unread() {
+        let mut reader: &[u8] = b"abcd\n";
+        let mut buf = [0u8; 4];
+
+        let status = read_until_into(&mut reader, b'\n', &mut buf).unwrap();
+        assert_eq!(status, LineStatus::Overflow(4));
+        assert!(!status.found());
+        assert_eq!(reader, b"\n");
+    }
+
+    #[cfg(feature = "embedded-io-async")]
+    #[test]
+    fn test_async_matches_blocking() {
+        use crate::EmbeddedRefTakeExt;
+        use futures::executor::block_on;
+
+        block_on(async {
+            let mut reader: &[u8] = b"AT\r\nOK\r\n";
+            let mut take = reader.take_ref_embedded(8);
+            let mut buf = [0u8; 8];
+
+            let status = read_until_into_async(&mut take, b'\n', &mut buf).await;
+            assert_eq!(status, Ok(LineStatus::Found(4)));
+            let status = read_until_into_async(&mut take, b'\n', &mut buf).await;
+            assert_eq!(status, Ok(LineStatus::Found(4)));
+            let status = read_until_into_async(&mut take, b'\n', &mut buf).await;
+            assert!(status.unwrap().is_empty());
+        });
+    }
+}
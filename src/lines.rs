@@ -0,0 +1,161 @@
+//! An adapter that limits a borrowed `BufRead` to a number of lines instead of bytes.
+
+use std::io::{self, BufRead, Read};
+
+/// A non-owning adapter that passes through at most `n` newline-terminated lines.
+///
+/// After the `n`-th `\n` has been consumed, reads return EOF and the rest of
+/// the stream is left untouched in the inner reader.
+pub struct LineLimited<'a, R> {
+    inner: &'a mut R,
+    lines: u64,
+}
+
+impl<'a, R> LineLimited<'a, R> {
+    /// Creates a new `LineLimited` that reads at most `lines` lines from the given reader reference.
+    pub const fn wrap(inner: &'a mut R, lines: u64) -> Self {
+        Self { inner, lines }
+    }
+
+    /// Sets a new line limit.
+    pub fn set_limit(&mut self, lines: u64) {
+        self.lines = lines;
+    }
+
+    /// Returns the number of lines that may still be read.
+    pub fn current_limit(&self) -> u64 {
+        self.lines
+    }
+}
+
+/// Returns the length of the prefix of `buf` holding at most `lines` newlines.
+fn line_cap(buf: &[u8], lines: u64) -> usize {
+    let mut seen = 0;
+    for (i, &b) in buf.iter().enumerate() {
+        if b == b'\n' {
+            seen += 1;
+            if seen == lines {
+                return i + 1;
+            }
+        }
+    }
+    buf.len()
+}
+
+impl<R: BufRead> Read for LineLimited<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        let available = self.fill_buf()?;
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for LineLimited<'_, R> {
+    fn fill_buf(&mut self) -> Result<&[u8], io::Error> {
+        // Don't call into inner reader at all at EOF because it may still block
+        if self.lines == 0 {
+            return Ok(&[]);
+        }
+
+        let buf = self.inner.fill_buf()?;
+        let cap = line_cap(buf, self.lines);
+        Ok(&buf[..cap])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        if amt == 0 || self.lines == 0 {
+            return;
+        }
+        // The inner buffer still holds the bytes handed out by `fill_buf`
+        let (amt, newlines) = match self.inner.fill_buf() {
+            Ok(buf) => {
+                let amt = amt.min(line_cap(buf, self.lines));
+                let newlines = buf[..amt].iter().filter(|&&b| b == b'\n').count();
+                (amt, newlines as u64)
+            }
+            Err(_) => (0, 0),
+        };
+        self.lines -= newlines;
+        self.inner.consume(amt);
+    }
+}
+
+/// Extension trait to provide a `take_lines_ref` method on all `BufRead` types.
+pub trait LineLimitedExt {
+    /// Wraps the reader in a `LineLimited` that stops after `lines` lines.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{BufRead, Cursor, Read};
+    /// use reftake::LineLimitedExt;
+    ///
+    /// let mut cursor = Cursor::new(b"one\ntwo\nthree\n");
+    /// let lines: Vec<String> = cursor.take_lines_ref(2).lines().map(Result::unwrap).collect();
+    /// assert_eq!(lines, vec!["one", "two"]);
+    ///
+    /// let mut rest = String::new();
+    /// cursor.read_to_string(&mut rest).unwrap();
+    /// assert_eq!(rest, "three\n");
+    /// ```
+    fn take_lines_ref(&mut self, lines: u64) -> LineLimited<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: BufRead> LineLimitedExt for T {
+    fn take_lines_ref(&mut self, lines: u64) -> LineLimited<'_, Self> {
+        LineLimited::wrap(self, lines)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufReader, Cursor};
+
+    #[test]
+    fn test_read_stops_after_lines() {
+        let mut reader = Cursor::new(b"a\nb\nc\nd\n");
+        let mut limited = reader.take_lines_ref(3);
+
+        let mut buf = String::new();
+        limited.read_to_string(&mut buf).unwrap();
+        assert_eq!(buf, "a\nb\nc\n");
+        assert_eq!(limited.current_limit(), 0);
+    }
+
+    #[test]
+    fn test_lines_across_small_buffers() {
+        let mut reader = BufReader::with_capacity(3, Cursor::new(b"first\nsecond\nthird\n"));
+        {
+            let mut limited = reader.take_lines_ref(2);
+            let lines: Vec<String> = limited.by_ref().lines().map(Result::unwrap).collect();
+            assert_eq!(lines, vec!["first", "second"]);
+        }
+
+        let mut rest = String::new();
+        reader.read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, "third\n");
+    }
+
+    #[test]
+    fn test_zero_lines() {
+        let mut reader = Cursor::new(b"a\n");
+        let mut limited = reader.take_lines_ref(0);
+        assert_eq!(limited.fill_buf().unwrap(), b"");
+    }
+
+    #[test]
+    fn test_unterminated_last_line() {
+        let mut reader = Cursor::new(b"a\nb");
+        let mut limited = reader.take_lines_ref(5);
+
+        let mut buf = String::new();
+        limited.read_to_string(&mut buf).unwrap();
+        assert_eq!(buf, "a\nb");
+        assert_eq!(limited.current_limit(), 4);
+    }
+}
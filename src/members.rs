@@ -0,0 +1,325 @@
+//! Iteration over the members of concatenated gzip streams and zstd frames.
+
+use std::io::{self, BufRead, Read};
+
+use crate::LimitExceeded;
+
+/// A decoder for a single compressed member, created over a borrowed `BufRead`.
+///
+/// The decoder must stop at the end of its member and consume nothing from
+/// the inner reader past it, so that the next member can be opened where it
+/// left off. Implemented for `flate2`'s gzip decoder with the `gzip` feature
+/// and for `zstd`'s single-frame decoder with the `zstd` feature.
+pub trait MemberDecoder<'a, R>: Read + Sized {
+    /// Starts decoding the member at the current position of `inner`.
+    fn open(inner: &'a mut R) -> io::Result<Self>;
+
+    /// Returns the inner reader, positioned after the member once it has been read to the end.
+    fn finish(self) -> &'a mut R;
+}
+
+#[cfg(feature = "gzip")]
+impl<'a, R: BufRead> MemberDecoder<'a, R> for flate2::bufread::GzDecoder<&'a mut R> {
+    fn open(inner: &'a mut R) -> io::Result<Self> {
+        Ok(Self::new(inner))
+    }
+
+    fn finish(self) -> &'a mut R {
+        self.into_inner()
+    }
+}
+
+#[cfg(feature = "zstd")]
+impl<'a, R: BufRead> MemberDecoder<'a, R> for zstd::stream::read::Decoder<'static, &'a mut R> {
+    fn open(inner: &'a mut R) -> io::Result<Self> {
+        Ok(Self::with_buffer(inner)?.single_frame())
+    }
+
+    fn finish(self) -> &'a mut R {
+        self.finish()
+    }
+}
+
+/// An iterator over the gzip members of a borrowed `BufRead`.
+#[cfg(feature = "gzip")]
+pub type GzipMembers<'a, R> = Members<'a, R, flate2::bufread::GzDecoder<&'a mut R>>;
+
+/// An iterator over the zstd frames of a borrowed `BufRead`.
+#[cfg(feature = "zstd")]
+pub type ZstdFrames<'a, R> = Members<'a, R, zstd::stream::read::Decoder<'static, &'a mut R>>;
+
+/// A reader of the members of a stream of concatenated compressed members,
+/// such as a multi-member gzip file or a sequence of zstd frames.
+///
+/// Compressed formats don't record the length of a member up front, so its
+/// end is only found by decoding it; [`Members::next_member`] therefore
+/// returns each member already decompressed. Whatever the caller leaves
+/// unread of a member is decoded and discarded before the next one is
+/// opened. A member that decompresses to more than `max_member_len` bytes
+/// fails with a [`LimitExceeded`] error, which guards against
+/// decompression bombs; the stream can't be realigned after an error, and
+/// the iterator ends.
+pub struct Members<'a, R, D> {
+    /// The inner reader between members; `None` while a member is open or after an error.
+    inner: Option<&'a mut R>,
+    current: Option<D>,
+    read: u64,
+    max_member_len: u64,
+    count: u64,
+}
+
+/// A single decompressed member, borrowed from its [`Members`] iterator.
+pub struct Member<'m, D> {
+    decoder: &'m mut D,
+    read: &'m mut u64,
+    max_len: u64,
+}
+
+impl<'a, R: BufRead, D: MemberDecoder<'a, R>> Members<'a, R, D> {
+    /// Creates a new `Members` iterator accepting members of up to `max_member_len` decompressed bytes.
+    pub const fn wrap(inner: &'a mut R, max_member_len: u64) -> Self {
+        Self {
+            inner: Some(inner),
+            current: None,
+            read: 0,
+            max_member_len,
+            count: 0,
+        }
+    }
+
+    /// Returns the number of members opened so far.
+    pub fn member_count(&self) -> u64 {
+        self.count
+    }
+
+    /// Skips the rest of the current member and returns the next one, or
+    /// `None` at the end of the stream.
+    ///
+    /// A member that is truncated or corrupt fails with the decoder's error.
+    pub fn next_member(&mut self) -> io::Result<Option<Member<'_, D>>> {
+        if let Some(mut decoder) = self.current.take() {
+            io::copy(
+                &mut Member {
+                    decoder: &mut decoder,
+                    read: &mut self.read,
+                    max_len: self.max_member_len,
+                },
+                &mut io::sink(),
+            )?;
+            self.inner = Some(decoder.finish());
+        }
+
+        let Some(inner) = self.inner.take() else {
+            return Ok(None);
+        };
+        if inner.fill_buf()?.is_empty() {
+            self.inner = Some(inner);
+            return Ok(None);
+        }
+
+        self.read = 0;
+        self.count += 1;
+        let decoder = self.current.insert(D::open(inner)?);
+        Ok(Some(Member {
+            decoder,
+            read: &mut self.read,
+            max_len: self.max_member_len,
+        }))
+    }
+}
+
+impl<D> Member<'_, D> {
+    /// Returns the number of decompressed bytes read from the member so far.
+    pub fn bytes_read(&self) -> u64 {
+        *self.read
+    }
+}
+
+impl<D: Read> Read for Member<'_, D> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let allowed = self.max_len - *self.read;
+        if allowed == 0 {
+            let mut probe = [0u8; 1];
+            if self.decoder.read(&mut probe)? == 0 {
+                return Ok(0);
+            }
+            return Err(LimitExceeded::new(self.max_len).into());
+        }
+
+        let max = buf.len().min(allowed.min(usize::MAX as u64) as usize);
+        let n = self.decoder.read(&mut buf[..max])?;
+        *self.read += n as u64;
+        Ok(n)
+    }
+}
+
+/// Extension trait to iterate over the compressed members of any `BufRead` type.
+pub trait MembersExt {
+    /// Wraps the reader in a [`GzipMembers`] iterator accepting members of up
+    /// to `max_member_len` decompressed bytes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Read, Write};
+    /// use flate2::{Compression, write::GzEncoder};
+    /// use reftake::MembersExt;
+    ///
+    /// let mut data = Vec::new();
+    /// for text in ["first", "second"] {
+    ///     let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    ///     encoder.write_all(text.as_bytes()).unwrap();
+    ///     data.extend(encoder.finish().unwrap());
+    /// }
+    ///
+    /// let mut reader = &data[..];
+    /// let mut members = reader.gzip_members_ref(1024);
+    /// let mut texts = Vec::new();
+    /// while let Some(mut member) = members.next_member().unwrap() {
+    ///     let mut text = String::new();
+    ///     member.read_to_string(&mut text).unwrap();
+    ///     texts.push(text);
+    /// }
+    /// assert_eq!(texts, ["first", "second"]);
+    /// ```
+    #[cfg(feature = "gzip")]
+    fn gzip_members_ref(&mut self, max_member_len: u64) -> GzipMembers<'_, Self>
+    where
+        Self: Sized;
+
+    /// Wraps the reader in a [`ZstdFrames`] iterator accepting frames of up
+    /// to `max_member_len` decompressed bytes.
+    #[cfg(feature = "zstd")]
+    fn zstd_frames_ref(&mut self, max_member_len: u64) -> ZstdFrames<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: BufRead> MembersExt for T {
+    #[cfg(feature = "gzip")]
+    fn gzip_members_ref(&mut self, max_member_len: u64) -> GzipMembers<'_, Self> {
+        Members::wrap(self, max_member_len)
+    }
+
+    #[cfg(feature = "zstd")]
+    fn zstd_frames_ref(&mut self, max_member_len: u64) -> ZstdFrames<'_, Self> {
+        Members::wrap(self, max_member_len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "gzip")]
+    fn gzip(parts: &[&[u8]]) -> Vec<u8> {
+        use flate2::{Compression, write::GzEncoder};
+        use std::io::Write;
+
+        let mut data = Vec::new();
+        for part in parts {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(part).unwrap();
+            data.extend(encoder.finish().unwrap());
+        }
+        data
+    }
+
+    #[cfg(feature = "zstd")]
+    fn zstd(parts: &[&[u8]]) -> Vec<u8> {
+        let mut data = Vec::new();
+        for part in parts {
+            data.extend(zstd::encode_all(*part, 0).unwrap());
+        }
+        data
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_gzip_skips_unread_members_and_leaves_trailing_data() {
+        let mut data = gzip(&[b"alpha", b"beta", b"gamma"]);
+        let mut reader = &data[..];
+        {
+            let mut members = reader.gzip_members_ref(64);
+            let mut first = members.next_member().unwrap().unwrap();
+            let mut buf = [0u8; 2];
+            first.read_exact(&mut buf).unwrap();
+            assert_eq!(&buf, b"al");
+            assert_eq!(first.bytes_read(), 2);
+
+            members.next_member().unwrap().unwrap();
+            let mut out = String::new();
+            let mut third = members.next_member().unwrap().unwrap();
+            third.read_to_string(&mut out).unwrap();
+            assert_eq!(out, "gamma");
+            assert!(members.next_member().unwrap().is_none());
+            assert_eq!(members.member_count(), 3);
+        }
+        assert!(reader.is_empty());
+
+        data.truncate(data.len() - 4);
+        let mut reader = &data[..];
+        let mut members = reader.gzip_members_ref(64);
+        members.next_member().unwrap();
+        members.next_member().unwrap();
+        // The truncated member only fails once it is decoded
+        members.next_member().unwrap();
+        assert!(members.next_member().is_err());
+        assert!(members.next_member().unwrap().is_none());
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_gzip_member_over_limit() {
+        let big = vec![0u8; 10_000];
+        let data = gzip(&[b"ok", &big]);
+        let mut reader = &data[..];
+        let mut members = reader.gzip_members_ref(100);
+
+        let mut out = Vec::new();
+        members
+            .next_member()
+            .unwrap()
+            .unwrap()
+            .read_to_end(&mut out)
+            .unwrap();
+        let err = members
+            .next_member()
+            .unwrap()
+            .unwrap()
+            .read_to_end(&mut out)
+            .unwrap_err();
+        assert_eq!(LimitExceeded::from_io(&err), Some(&LimitExceeded::new(100)));
+        assert_eq!(out.len(), 102);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_zstd_frames() {
+        let data = zstd(&[b"one", b"two", b""]);
+        let mut reader = &data[..];
+        let mut frames = reader.zstd_frames_ref(16);
+
+        let mut texts = Vec::new();
+        while let Some(mut frame) = frames.next_member().unwrap() {
+            let mut text = String::new();
+            frame.read_to_string(&mut text).unwrap();
+            texts.push(text);
+        }
+        assert_eq!(texts, ["one", "two", ""]);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_zstd_skipping_oversized_frame_fails() {
+        let big = vec![7u8; 1000];
+        let data = zstd(&[&big, b"next"]);
+        let mut reader = &data[..];
+        let mut frames = reader.zstd_frames_ref(100);
+
+        frames.next_member().unwrap().unwrap();
+        let err = frames.next_member().err().unwrap();
+        assert_eq!(LimitExceeded::from_io(&err), Some(&LimitExceeded::new(100)));
+        assert!(frames.next_member().unwrap().is_none());
+    }
+}
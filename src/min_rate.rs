@@ -0,0 +1,207 @@
+//! An adapter that fails reads from a borrowed reader whose data arrives too slowly.
+
+use std::{
+    collections::VecDeque,
+    io::{self, BufRead, ErrorKind, Read},
+    time::{Duration, Instant},
+};
+
+/// Settings of a [`RefMinRate`] guard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MinRateConfig {
+    /// Fewest bytes per second, averaged over `window`, the reader must deliver.
+    pub min_bytes_per_sec: u64,
+    /// Length of the sliding window the rate is measured over.
+    pub window: Duration,
+    /// Time after the first read during which the rate is not checked, to let slow starts through.
+    pub grace: Duration,
+}
+
+impl Default for MinRateConfig {
+    /// At least 1 KiB/s over the last 10 seconds, checked from 10 seconds after the first read.
+    fn default() -> Self {
+        Self {
+            min_bytes_per_sec: 1024,
+            window: Duration::from_secs(10),
+            grace: Duration::from_secs(10),
+        }
+    }
+}
+
+/// A non-owning adapter that fails once the read rate drops below a minimum.
+///
+/// A [`RefDeadline`](crate::RefDeadline) long enough for a large upload
+/// lets a client trickle in a small one for just as long; this guard
+/// instead measures the bytes delivered over a sliding window and fails
+/// with `ErrorKind::TimedOut` once they average less than
+/// `min_bytes_per_sec`, whatever the size of the body. Combined with a
+/// [`RefTake`](crate::RefTake) it bounds both the size and the duration of
+/// a slowloris request. The rate is checked before and after each call
+/// into the inner reader; like the deadline, a read blocked inside the
+/// inner reader is not interrupted, so use it with a socket read timeout.
+pub struct RefMinRate<'a, R> {
+    inner: &'a mut R,
+    config: MinRateConfig,
+    started: Option<Instant>,
+    samples: VecDeque<(Instant, u64)>,
+    window_bytes: u64,
+}
+
+impl<'a, R> RefMinRate<'a, R> {
+    /// Creates a new `RefMinRate` enforcing `config`; the clock starts at the first read.
+    pub const fn wrap(inner: &'a mut R, config: MinRateConfig) -> Self {
+        Self {
+            inner,
+            config,
+            started: None,
+            samples: VecDeque::new(),
+            window_bytes: 0,
+        }
+    }
+
+    /// Returns the bytes read within the current window.
+    pub fn window_bytes(&self) -> u64 {
+        self.window_bytes
+    }
+
+    fn record(&mut self, now: Instant, n: usize) {
+        if n > 0 {
+            self.samples.push_back((now, n as u64));
+            self.window_bytes += n as u64;
+        }
+    }
+
+    fn check(&mut self, now: Instant) -> io::Result<()> {
+        let started = *self.started.get_or_insert(now);
+        while let Some(&(at, n)) = self.samples.front()
+            && now.duration_since(at) > self.config.window
+        {
+            self.samples.pop_front();
+            self.window_bytes -= n;
+        }
+        let elapsed = now.duration_since(started);
+        if elapsed < self.config.grace {
+            return Ok(());
+        }
+        let span = elapsed.min(self.config.window).as_secs_f64();
+        if span > 0.0 && (self.window_bytes as f64) < self.config.min_bytes_per_sec as f64 * span {
+            trace_event!(
+                warn,
+                bytes = self.window_bytes,
+                "read rate below the minimum"
+            );
+            return Err(io::Error::new(
+                ErrorKind::TimedOut,
+                "read rate below the minimum",
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for RefMinRate<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        self.check(Instant::now())?;
+        let n = self.inner.read(buf)?;
+        let now = Instant::now();
+        self.record(now, n);
+        if n > 0 {
+            self.check(now)?;
+        }
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for RefMinRate<'_, R> {
+    fn fill_buf(&mut self) -> Result<&[u8], io::Error> {
+        self.check(Instant::now())?;
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.record(Instant::now(), amt);
+        self.inner.consume(amt);
+    }
+}
+
+/// Extension trait to provide a `min_rate_ref` method on all `Read` types.
+pub trait RefMinRateExt {
+    /// Wraps the reader in a `RefMinRate` enforcing `config`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Cursor, Read};
+    /// use reftake::{MinRateConfig, RefMinRateExt, RefTakeExt};
+    ///
+    /// let mut conn = Cursor::new(vec![0u8; 4096]);
+    /// let mut body = conn.take_ref(1024);
+    /// let mut guarded = body.min_rate_ref(MinRateConfig::default());
+    ///
+    /// let mut buf = Vec::new();
+    /// guarded.read_to_end(&mut buf).unwrap();
+    /// assert_eq!(buf.len(), 1024);
+    /// ```
+    fn min_rate_ref(&mut self, config: MinRateConfig) -> RefMinRate<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: Read> RefMinRateExt for T {
+    fn min_rate_ref(&mut self, config: MinRateConfig) -> RefMinRate<'_, Self> {
+        RefMinRate::wrap(self, config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn config() -> MinRateConfig {
+        MinRateConfig {
+            min_bytes_per_sec: 100,
+            window: Duration::from_secs(4),
+            grace: Duration::from_secs(2),
+        }
+    }
+
+    #[test]
+    fn test_trickle_fails_after_the_grace() {
+        let mut reader = Cursor::new(b"");
+        let mut guard = reader.min_rate_ref(config());
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        guard.check(at(0)).unwrap();
+        guard.record(at(1), 150);
+        guard.check(at(1)).unwrap();
+        // 150 bytes over 3 seconds is 50 bytes per second
+        let err = guard.check(at(3)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::TimedOut);
+    }
+
+    #[test]
+    fn test_old_samples_leave_the_window() {
+        let mut reader = Cursor::new(b"");
+        let mut guard = reader.min_rate_ref(config());
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        guard.check(at(0)).unwrap();
+        guard.record(at(0), 1000);
+        guard.check(at(4)).unwrap();
+        guard.record(at(5), 390);
+        assert!(guard.check(at(6)).is_err());
+        assert_eq!(guard.window_bytes(), 390);
+    }
+
+    #[test]
+    fn test_fast_reader_passes() {
+        let mut reader = Cursor::new(vec![7u8; 10_000]);
+        let mut guard = reader.min_rate_ref(config());
+        let mut buf = Vec::new();
+        guard.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf.len(), 10_000);
+    }
+}
@@ -0,0 +1,151 @@
+//! Bounded windows over memory-mapped files.
+
+use std::io::{self, BufRead, ErrorKind, Read};
+
+use memmap2::Mmap;
+
+use crate::RefTake;
+
+/// A bounded window over a range of a [`Mmap`], read as a plain slice.
+///
+/// The range is checked against the mapping when the window is created,
+/// so reads never fault on a range the file does not cover; after that the
+/// window is a cursor over the mapped bytes, implementing `Read` and a
+/// copy-free `BufRead`. [`as_take`](Self::as_take) views the rest of it as
+/// a [`RefTake`], so code written against the stream adapters, such as
+/// [`RefTake::read_exact_vec`], runs unchanged on mapped files.
+#[derive(Debug, Clone)]
+pub struct MmapWindow<'a> {
+    data: &'a [u8],
+    len: u64,
+}
+
+impl<'a> MmapWindow<'a> {
+    /// Creates a window over the `len` bytes of `map` starting at `offset`.
+    ///
+    /// Fails with `ErrorKind::InvalidInput` if the range does not fit the mapping.
+    pub fn new(map: &'a Mmap, offset: u64, len: u64) -> io::Result<Self> {
+        let data = offset
+            .checked_add(len)
+            .filter(|&end| end <= map.len() as u64)
+            .map(|end| &map[offset as usize..end as usize])
+            .ok_or_else(|| {
+                io::Error::new(ErrorKind::InvalidInput, "window out of the mapped range")
+            })?;
+        Ok(Self { data, len })
+    }
+
+    /// Returns the length of the window.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns `true` if the window is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of bytes left to read before the end of the window.
+    pub fn current_limit(&self) -> u64 {
+        self.data.len() as u64
+    }
+
+    /// Returns the unread part of the window, borrowed from the mapping.
+    pub fn remaining(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Returns a [`RefTake`] over the unread part of the window.
+    ///
+    /// Bytes read through it are consumed from the window as well.
+    pub fn as_take(&mut self) -> RefTake<'_, &'a [u8]> {
+        let limit = self.current_limit();
+        RefTake::wrap(&mut self.data, limit)
+    }
+}
+
+impl Read for MmapWindow<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        self.data.read(buf)
+    }
+}
+
+impl BufRead for MmapWindow<'_> {
+    fn fill_buf(&mut self) -> Result<&[u8], io::Error> {
+        Ok(self.data)
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.data.consume(amt);
+    }
+}
+
+/// Extension trait to provide a `window` method on memory maps.
+pub trait MmapWindowExt {
+    /// Creates a [`MmapWindow`] over the `len` bytes starting at `offset`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::BufRead;
+    /// use memmap2::MmapMut;
+    /// use reftake::MmapWindowExt;
+    ///
+    /// let mut anon = MmapMut::map_anon(16).unwrap();
+    /// anon[..11].copy_from_slice(b"head\nbody\n!");
+    /// let map = anon.make_read_only().unwrap();
+    ///
+    /// let mut body = map.window(5, 5).unwrap();
+    /// let mut line = String::new();
+    /// body.read_line(&mut line).unwrap();
+    /// assert_eq!(line, "body\n");
+    /// assert!(map.window(12, 8).is_err());
+    /// ```
+    fn window(&self, offset: u64, len: u64) -> io::Result<MmapWindow<'_>>;
+}
+
+impl MmapWindowExt for Mmap {
+    fn window(&self, offset: u64, len: u64) -> io::Result<MmapWindow<'_>> {
+        MmapWindow::new(self, offset, len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use memmap2::MmapMut;
+
+    fn map(data: &[u8]) -> Mmap {
+        let mut anon = MmapMut::map_anon(data.len()).unwrap();
+        anon.copy_from_slice(data);
+        anon.make_read_only().unwrap()
+    }
+
+    #[test]
+    fn test_bounds_are_validated() {
+        let map = map(b"0123456789");
+        assert_eq!(map.window(10, 0).unwrap().len(), 0);
+        assert_eq!(map.window(4, 6).unwrap().remaining(), b"456789");
+        for (offset, len) in [(4, 7), (11, 0), (u64::MAX, 2)] {
+            let err = map.window(offset, len).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        }
+    }
+
+    #[test]
+    fn test_take_view_shares_the_position() {
+        let map = map(b"\x00\x03abcrest");
+        let mut window = map.window(0, 8).unwrap();
+        let mut len = [0u8; 2];
+        window.read_exact(&mut len).unwrap();
+
+        let value = window
+            .as_take()
+            .read_exact_vec(u16::from_be_bytes(len) as usize)
+            .unwrap();
+        assert_eq!(value, b"abc");
+        assert_eq!(window.current_limit(), 3);
+        assert!(window.as_take().read_exact_vec(4).is_err());
+        assert_eq!(window.fill_buf().unwrap(), b"res");
+    }
+}
@@ -0,0 +1,264 @@
+//! Reading a set of disjoint ranges of a borrowed seekable reader in offset order.
+
+use std::{
+    cmp,
+    io::{self, BufRead, ErrorKind, Read, Seek, SeekFrom},
+};
+
+/// A non-owning adapter reading the `(offset, length)` ranges of a seekable
+/// reader one after the other, in the order they appear in the stream.
+///
+/// Sparse extraction, such as reading selected zip members or database
+/// pages, spends most of its time seeking. The ranges are sorted by
+/// offset, so the reader only moves back to re-read overlapping bytes, and
+/// it is not seeked at all when a range starts where the previous one
+/// ended; other moves use `seek_relative`, which a `BufReader` serves from
+/// its buffer when it can.
+///
+/// [`next_range`](RefMultiTake::next_range) moves to the next range and
+/// returns its index in the list the adapter was created with; reads then
+/// return the bytes of that range and end at its end, like a
+/// [`RefTake`](crate::RefTake). [`read_into`](RefMultiTake::read_into)
+/// reads all of them into caller buffers instead.
+pub struct RefMultiTake<'a, R> {
+    inner: &'a mut R,
+    /// `(index, offset, length)` of each range, by offset.
+    ranges: Vec<(usize, u64, u64)>,
+    next: usize,
+    /// Absolute position of the inner reader, if known.
+    position: Option<u64>,
+    limit: u64,
+}
+
+impl<'a, R> RefMultiTake<'a, R> {
+    /// Creates a new `RefMultiTake` over the given `(offset, length)` ranges.
+    pub fn wrap(inner: &'a mut R, ranges: &[(u64, u64)]) -> Self {
+        let mut ranges: Vec<_> = ranges
+            .iter()
+            .enumerate()
+            .map(|(index, &(offset, len))| (index, offset, len))
+            .collect();
+        ranges.sort_by_key(|&(index, offset, _)| (offset, index));
+        Self {
+            inner,
+            ranges,
+            next: 0,
+            position: None,
+            limit: 0,
+        }
+    }
+
+    /// Returns the number of bytes of the current range left to read.
+    pub fn current_limit(&self) -> u64 {
+        self.limit
+    }
+
+    /// Returns the number of ranges not started yet.
+    pub fn ranges_left(&self) -> usize {
+        self.ranges.len() - self.next
+    }
+}
+
+impl<R: Seek> RefMultiTake<'_, R> {
+    /// Moves to the start of the next range by offset, returning its index in the original list.
+    ///
+    /// Whatever is left of the current range is skipped. Returns `None`
+    /// once every range has been started.
+    pub fn next_range(&mut self) -> io::Result<Option<usize>> {
+        let Some(&(index, offset, len)) = self.ranges.get(self.next) else {
+            self.limit = 0;
+            return Ok(None);
+        };
+        let moved = match self.position {
+            Some(position) if position == offset => Ok(()),
+            Some(position) => match i64::try_from(offset as i128 - position as i128) {
+                Ok(delta) => self.inner.seek_relative(delta),
+                Err(_) => self.inner.seek(SeekFrom::Start(offset)).map(|_| ()),
+            },
+            None => self.inner.seek(SeekFrom::Start(offset)).map(|_| ()),
+        };
+        if let Err(e) = moved {
+            self.position = None;
+            return Err(e);
+        }
+        self.position = Some(offset);
+        self.limit = len;
+        self.next += 1;
+        Ok(Some(index))
+    }
+}
+
+impl<R: Read + Seek> RefMultiTake<'_, R> {
+    /// Reads every range not started yet into `bufs`, indexed like the original list.
+    ///
+    /// Each buffer must be as long as its range, or the call fails with
+    /// `ErrorKind::InvalidInput` before reading anything. A range cut short
+    /// by the end of the stream fails with `ErrorKind::UnexpectedEof`.
+    pub fn read_into(&mut self, bufs: &mut [&mut [u8]]) -> io::Result<()> {
+        let mismatch = self.ranges[self.next..]
+            .iter()
+            .any(|&(index, _, len)| bufs.get(index).is_none_or(|buf| buf.len() as u64 != len));
+        if mismatch {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "buffers do not match the ranges",
+            ));
+        }
+        while let Some(index) = self.next_range()? {
+            self.read_exact(bufs[index])?;
+        }
+        Ok(())
+    }
+
+    /// Reads every range not started yet into a new `Vec`, indexed like the original list.
+    ///
+    /// Ranges already started come back empty.
+    pub fn read_all(&mut self) -> io::Result<Vec<Vec<u8>>> {
+        let mut out = vec![Vec::new(); self.ranges.len()];
+        while let Some(index) = self.next_range()? {
+            let mut buf = Vec::with_capacity(cmp::min(self.limit, 64 * 1024) as usize);
+            self.read_to_end(&mut buf)?;
+            out[index] = buf;
+        }
+        Ok(out)
+    }
+}
+
+impl<R: Read> Read for RefMultiTake<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        // Don't call into inner reader at all at EOF because it may still block
+        if self.limit == 0 {
+            return Ok(0);
+        }
+        let max = cmp::min(buf.len() as u64, self.limit) as usize;
+        match self.inner.read(&mut buf[..max]) {
+            Ok(n) => {
+                self.limit -= n as u64;
+                self.position = self.position.map(|p| p + n as u64);
+                Ok(n)
+            }
+            Err(e) => {
+                self.position = None;
+                Err(e)
+            }
+        }
+    }
+}
+
+impl<R: BufRead> BufRead for RefMultiTake<'_, R> {
+    fn fill_buf(&mut self) -> Result<&[u8], io::Error> {
+        if self.limit == 0 {
+            return Ok(&[]);
+        }
+        match self.inner.fill_buf() {
+            Ok(buf) => {
+                let cap = cmp::min(buf.len() as u64, self.limit) as usize;
+                Ok(&buf[..cap])
+            }
+            Err(e) => {
+                self.position = None;
+                Err(e)
+            }
+        }
+    }
+
+    fn consume(&mut self, amt: usize) {
+        let amt = cmp::min(amt as u64, self.limit) as usize;
+        self.limit -= amt as u64;
+        self.position = self.position.map(|p| p + amt as u64);
+        self.inner.consume(amt);
+    }
+}
+
+/// Extension trait to provide a `multi_take_ref` method on all seekable `Read` types.
+pub trait RefMultiTakeExt {
+    /// Wraps the reader in a `RefMultiTake` over the given `(offset, length)` ranges.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Cursor, Read};
+    /// use reftake::RefMultiTakeExt;
+    ///
+    /// let mut archive = Cursor::new(b"[gamma][alpha][beta]");
+    /// let mut members = archive.multi_take_ref(&[(8, 5), (15, 4), (1, 5)]);
+    ///
+    /// let mut order = Vec::new();
+    /// while let Some(index) = members.next_range().unwrap() {
+    ///     let mut name = String::new();
+    ///     members.read_to_string(&mut name).unwrap();
+    ///     order.push((index, name));
+    /// }
+    /// assert_eq!(order[0], (2, "gamma".to_string()));
+    /// assert_eq!(order[2], (1, "beta".to_string()));
+    /// ```
+    fn multi_take_ref(&mut self, ranges: &[(u64, u64)]) -> RefMultiTake<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: Read + Seek> RefMultiTakeExt for T {
+    fn multi_take_ref(&mut self, ranges: &[(u64, u64)]) -> RefMultiTake<'_, Self> {
+        RefMultiTake::wrap(self, ranges)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// Counts the seeks made on a cursor.
+    struct Seeks {
+        cursor: Cursor<Vec<u8>>,
+        seeks: usize,
+    }
+
+    impl Read for Seeks {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.cursor.read(buf)
+        }
+    }
+
+    impl Seek for Seeks {
+        fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+            self.seeks += 1;
+            self.cursor.seek(pos)
+        }
+    }
+
+    #[test]
+    fn test_adjacent_ranges_are_not_seeked() {
+        let mut source = Seeks {
+            cursor: Cursor::new((0..100).collect()),
+            seeks: 0,
+        };
+        let out = source
+            .multi_take_ref(&[(50, 10), (10, 5), (15, 5), (40, 10)])
+            .read_all()
+            .unwrap();
+        assert_eq!(out[1], (10..15).collect::<Vec<u8>>());
+        assert_eq!(out[3], (40..50).collect::<Vec<u8>>());
+        assert_eq!(out[0], (50..60).collect::<Vec<u8>>());
+        assert_eq!(source.seeks, 2);
+    }
+
+    #[test]
+    fn test_read_into_caller_buffers() {
+        let mut cursor = Cursor::new(b"0123456789");
+        let mut a = [0u8; 2];
+        let mut b = [0u8; 3];
+        let mut multi = cursor.multi_take_ref(&[(7, 2), (1, 3)]);
+        multi.read_into(&mut [&mut a, &mut b]).unwrap();
+        assert_eq!((&a, &b), (b"78", b"123"));
+        assert_eq!(multi.ranges_left(), 0);
+
+        let mut multi = cursor.multi_take_ref(&[(7, 5)]);
+        let mut c = [0u8; 4];
+        let err = multi.read_into(&mut [&mut c]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        let mut c = [0u8; 5];
+        let err = multi.read_into(&mut [&mut c]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,348 @@
+//! Reading `multipart/form-data` bodies part by part from a borrowed `BufRead`.
+
+use std::io::{self, BufRead, ErrorKind, Read};
+
+use crate::{LimitExceeded, RefTakeUntilBoundary, UntilStatus, read_until_limited};
+
+/// Longest accepted header section of a single part.
+const MAX_HEADERS_LEN: usize = 16 * 1024;
+
+/// Longest accepted remainder of a delimiter line (transport padding).
+const MAX_DELIMITER_LINE_LEN: usize = 1024;
+
+fn truncated() -> io::Error {
+    io::Error::new(
+        ErrorKind::UnexpectedEof,
+        "stream ended inside a multipart body",
+    )
+}
+
+/// A reader of the parts of a `multipart/form-data` (or any `multipart/*`) body.
+///
+/// [`MultipartReader::next_part`] skips the preamble or the unread rest of
+/// the previous part, then returns a [`Part`] exposing the raw header bytes
+/// and a reader over the part's content, which ends at the next delimiter.
+/// Delimiters split across `fill_buf` chunks are handled by
+/// [`RefTakeUntilBoundary`].
+///
+/// Part content longer than `max_part_len`, or all content taken together
+/// (including the preamble and skipped content) longer than `max_total_len`, fails with a
+/// [`LimitExceeded`] error. Header sections are capped at 16 KiB.
+pub struct MultipartReader<'a, R> {
+    /// Searches for the next delimiter; `None` only while switching between parts.
+    body: Option<RefTakeUntilBoundary<'a, R>>,
+    /// `--` followed by the boundary.
+    dash_boundary: Vec<u8>,
+    headers: Vec<u8>,
+    started: bool,
+    done: bool,
+    part_len: u64,
+    total_len: u64,
+    max_part_len: u64,
+    max_total_len: u64,
+}
+
+/// A single part of a multipart body, borrowed from its [`MultipartReader`].
+///
+/// Reading returns the part's content; whatever is left unread is skipped by
+/// the next call to [`MultipartReader::next_part`].
+pub struct Part<'p, 'a, R> {
+    reader: &'p mut MultipartReader<'a, R>,
+}
+
+impl<'a, R: BufRead> MultipartReader<'a, R> {
+    /// Creates a new `MultipartReader` for the given `boundary` parameter of the content type.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `boundary` is empty.
+    pub fn wrap(inner: &'a mut R, boundary: &[u8], max_part_len: u64, max_total_len: u64) -> Self {
+        assert!(!boundary.is_empty(), "boundary must not be empty");
+        let mut dash_boundary = b"--".to_vec();
+        dash_boundary.extend_from_slice(boundary);
+        Self {
+            // The first delimiter may appear at the very start, without a preceding CRLF
+            body: Some(RefTakeUntilBoundary::wrap(inner, &dash_boundary)),
+            dash_boundary,
+            headers: Vec::new(),
+            started: false,
+            done: false,
+            part_len: 0,
+            total_len: 0,
+            max_part_len,
+            max_total_len,
+        }
+    }
+
+    /// Returns `true` once the closing delimiter has been read.
+    pub fn is_finished(&self) -> bool {
+        self.done
+    }
+
+    fn read_body(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let body = self.body.as_mut().expect("delimiter search in progress");
+        // The preamble only counts towards the total
+        let part_allowed = if self.started {
+            self.max_part_len - self.part_len
+        } else {
+            u64::MAX
+        };
+        let allowed = part_allowed.min(self.max_total_len - self.total_len);
+        if allowed == 0 {
+            let mut probe = [0u8; 1];
+            if body.read(&mut probe)? == 0 {
+                return Ok(0);
+            }
+            let limit = if part_allowed == 0 {
+                self.max_part_len
+            } else {
+                self.max_total_len
+            };
+            return Err(LimitExceeded::new(limit).into());
+        }
+
+        let max = buf.len().min(allowed.min(usize::MAX as u64) as usize);
+        let n = body.read(&mut buf[..max])?;
+        self.part_len += n as u64;
+        self.total_len += n as u64;
+        Ok(n)
+    }
+
+    /// Skips to the next part and returns it, or `None` after the closing delimiter.
+    ///
+    /// A stream that ends before the closing delimiter fails with
+    /// `ErrorKind::UnexpectedEof`, and malformed delimiter lines or header
+    /// sections with `ErrorKind::InvalidData`.
+    pub fn next_part(&mut self) -> io::Result<Option<Part<'_, 'a, R>>> {
+        if self.done {
+            return Ok(None);
+        }
+
+        let mut scratch = [0u8; 8 * 1024];
+        while self.read_body(&mut scratch)? > 0 {}
+        let body = self.body.take().expect("delimiter search in progress");
+        let found = body.found_boundary();
+        let inner = body.into_inner();
+        if !found {
+            self.done = true;
+            return Err(truncated());
+        }
+
+        match self.open_part(inner) {
+            Ok(true) => Ok(Some(Part { reader: self })),
+            Ok(false) => Ok(None),
+            Err(e) => {
+                self.done = true;
+                Err(e)
+            }
+        }
+    }
+
+    /// Reads the rest of a delimiter line and the part headers after it.
+    ///
+    /// Returns `false` for the closing delimiter.
+    fn open_part(&mut self, inner: &'a mut R) -> io::Result<bool> {
+        let mut line = Vec::new();
+        match read_until_limited(inner, b'\n', &mut line, MAX_DELIMITER_LINE_LEN)? {
+            UntilStatus::Found(_) => {}
+            // A closing delimiter may end the stream without a final CRLF
+            UntilStatus::Eof(_) if line.starts_with(b"--") => {}
+            UntilStatus::Eof(_) => return Err(truncated()),
+            UntilStatus::Capped(_) => {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    "malformed multipart delimiter",
+                ));
+            }
+        }
+        if line.starts_with(b"--") {
+            self.done = true;
+            return Ok(false);
+        }
+        if !line.trim_ascii().is_empty() {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "malformed multipart delimiter",
+            ));
+        }
+
+        self.headers.clear();
+        loop {
+            let room = MAX_HEADERS_LEN - self.headers.len();
+            let start = self.headers.len();
+            match read_until_limited(inner, b'\n', &mut self.headers, room)? {
+                UntilStatus::Found(_) => {}
+                UntilStatus::Eof(_) => return Err(truncated()),
+                UntilStatus::Capped(_) => {
+                    return Err(io::Error::new(
+                        ErrorKind::InvalidData,
+                        "multipart headers too long",
+                    ));
+                }
+            }
+            if matches!(&self.headers[start..], b"\r\n" | b"\n") {
+                self.headers.truncate(start);
+                break;
+            }
+        }
+
+        let mut delimiter = b"\r\n".to_vec();
+        delimiter.extend_from_slice(&self.dash_boundary);
+        self.body = Some(RefTakeUntilBoundary::wrap(inner, &delimiter));
+        self.started = true;
+        self.part_len = 0;
+        Ok(true)
+    }
+}
+
+impl<R> Part<'_, '_, R> {
+    /// Returns the raw header section of the part, without the blank line ending it.
+    pub fn headers(&self) -> &[u8] {
+        &self.reader.headers
+    }
+
+    /// Returns the number of content bytes read from this part so far.
+    pub fn bytes_read(&self) -> u64 {
+        self.reader.part_len
+    }
+}
+
+impl<R: BufRead> Read for Part<'_, '_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        self.reader.read_body(buf)
+    }
+}
+
+/// Extension trait to provide a `multipart_ref` method on all `BufRead` types.
+pub trait MultipartReaderExt {
+    /// Wraps the reader in a `MultipartReader` splitting on `boundary`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Cursor, Read};
+    /// use reftake::MultipartReaderExt;
+    ///
+    /// let body = b"--XYZ\r\n\
+    ///     Content-Disposition: form-data; name=\"a\"\r\n\r\n\
+    ///     first\r\n\
+    ///     --XYZ\r\n\
+    ///     Content-Disposition: form-data; name=\"b\"\r\n\r\n\
+    ///     second\r\n\
+    ///     --XYZ--\r\n";
+    /// let mut cursor = Cursor::new(&body[..]);
+    /// let mut multipart = cursor.multipart_ref(b"XYZ", 1024, 4096);
+    ///
+    /// let mut values = Vec::new();
+    /// while let Some(mut part) = multipart.next_part().unwrap() {
+    ///     let mut value = String::new();
+    ///     part.read_to_string(&mut value).unwrap();
+    ///     values.push(value);
+    /// }
+    /// assert_eq!(values, vec!["first", "second"]);
+    /// ```
+    fn multipart_ref(
+        &mut self,
+        boundary: &[u8],
+        max_part_len: u64,
+        max_total_len: u64,
+    ) -> MultipartReader<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: BufRead> MultipartReaderExt for T {
+    fn multipart_ref(
+        &mut self,
+        boundary: &[u8],
+        max_part_len: u64,
+        max_total_len: u64,
+    ) -> MultipartReader<'_, Self> {
+        MultipartReader::wrap(self, boundary, max_part_len, max_total_len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufReader, Cursor};
+
+    const BODY: &[u8] = b"preamble\r\n--sep\r\nName: one\r\n\r\nhello\r\n--sep  \r\nName: two\r\nType: x\r\n\r\nworld--se\r\n--sep--\r\nepilogue";
+
+    #[test]
+    fn test_parts_across_small_buffers() {
+        for capacity in 1..BODY.len() {
+            let mut reader = BufReader::with_capacity(capacity, Cursor::new(BODY));
+            let mut multipart = reader.multipart_ref(b"sep", 64, 64);
+
+            let mut part = multipart.next_part().unwrap().unwrap();
+            assert_eq!(part.headers(), b"Name: one\r\n");
+            let mut content = String::new();
+            part.read_to_string(&mut content).unwrap();
+            assert_eq!(content, "hello", "capacity {capacity}");
+
+            let mut part = multipart.next_part().unwrap().unwrap();
+            assert_eq!(part.headers(), b"Name: two\r\nType: x\r\n");
+            let mut content = String::new();
+            part.read_to_string(&mut content).unwrap();
+            assert_eq!(content, "world--se", "capacity {capacity}");
+
+            assert!(multipart.next_part().unwrap().is_none());
+            assert!(multipart.is_finished());
+        }
+    }
+
+    #[test]
+    fn test_near_delimiters_in_small_reads() {
+        let body = b"--sep\r\n\r\nx\r\n---sep\r\n--s\r\n--sep--\r\n";
+        for capacity in 1..body.len() {
+            let mut reader = BufReader::with_capacity(capacity, Cursor::new(body));
+            let mut multipart = reader.multipart_ref(b"sep", 64, 64);
+            let mut part = multipart.next_part().unwrap().unwrap();
+            let mut content = Vec::new();
+            let mut byte = [0u8];
+            while part.read(&mut byte).unwrap() > 0 {
+                content.push(byte[0]);
+            }
+            assert_eq!(content, b"x\r\n---sep\r\n--s", "capacity {capacity}");
+            assert!(multipart.next_part().unwrap().is_none());
+        }
+    }
+
+    #[test]
+    fn test_unread_parts_are_skipped() {
+        let mut reader = Cursor::new(BODY);
+        let mut multipart = reader.multipart_ref(b"sep", 64, 64);
+        let mut count = 0;
+        while multipart.next_part().unwrap().is_some() {
+            count += 1;
+        }
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn test_part_and_total_caps() {
+        let mut reader = Cursor::new(BODY);
+        let mut multipart = reader.multipart_ref(b"sep", 4, 64);
+        let mut part = multipart.next_part().unwrap().unwrap();
+        let err = part.read_to_end(&mut Vec::new()).unwrap_err();
+        assert_eq!(LimitExceeded::from_io(&err), Some(&LimitExceeded::new(4)));
+
+        // The preamble and the skipped first part count towards the total
+        let mut reader = Cursor::new(BODY);
+        let mut multipart = reader.multipart_ref(b"sep", 64, 20);
+        multipart.next_part().unwrap().unwrap();
+        let mut part = multipart.next_part().unwrap().unwrap();
+        let err = part.read_to_end(&mut Vec::new()).unwrap_err();
+        assert_eq!(LimitExceeded::from_io(&err), Some(&LimitExceeded::new(20)));
+    }
+
+    #[test]
+    fn test_missing_closing_delimiter() {
+        let mut reader = Cursor::new(&b"--sep\r\n\r\nbody without end"[..]);
+        let mut multipart = reader.multipart_ref(b"sep", 64, 64);
+        multipart.next_part().unwrap().unwrap();
+        let err = multipart.next_part().err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    }
+}
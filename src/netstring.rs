@@ -0,0 +1,199 @@
+//! Reading netstrings (`<len>:<payload>,`) from a borrowed reader.
+
+use std::io::{self, ErrorKind, Read};
+
+use crate::{FrameReader, FramingStrategy, LimitExceeded};
+
+/// Reads a single byte, returning `None` at EOF.
+fn read_byte<R: Read + ?Sized>(inner: &mut R) -> io::Result<Option<u8>> {
+    let mut byte = 0u8;
+    loop {
+        match inner.read(std::slice::from_mut(&mut byte)) {
+            Ok(0) => return Ok(None),
+            Ok(_) => return Ok(Some(byte)),
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+fn malformed(msg: &str) -> io::Error {
+    io::Error::new(
+        ErrorKind::InvalidData,
+        format!("malformed netstring: {msg}"),
+    )
+}
+
+fn truncated() -> io::Error {
+    io::Error::new(ErrorKind::UnexpectedEof, "stream ended inside a netstring")
+}
+
+/// The [`FramingStrategy`] of netstrings.
+///
+/// Each netstring is an ASCII decimal length, a colon, the payload and a
+/// trailing comma, e.g. `5:hello,`. The length field may hold no more
+/// digits than `max_len` has, and leading zeros are rejected, so an
+/// oversized or garbage length is detected after a handful of bytes. The
+/// trailing comma is verified once the payload has been read or skipped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Netstring {
+    max_len: u64,
+}
+
+impl Netstring {
+    /// Creates a new `Netstring` strategy that rejects payloads longer than `max_len` bytes.
+    pub const fn new(max_len: u64) -> Self {
+        Self { max_len }
+    }
+}
+
+impl FramingStrategy for Netstring {
+    fn decode_header<R: Read + ?Sized>(&mut self, reader: &mut R) -> io::Result<Option<u64>> {
+        let max_digits = self.max_len.checked_ilog10().unwrap_or(0) + 1;
+
+        let Some(first) = read_byte(reader)? else {
+            return Ok(None);
+        };
+        if !first.is_ascii_digit() {
+            return Err(malformed("length is not a number"));
+        }
+        let mut len = u64::from(first - b'0');
+        let mut digits = 1;
+        loop {
+            match read_byte(reader)?.ok_or_else(truncated)? {
+                b':' => break,
+                d @ b'0'..=b'9' => {
+                    if len == 0 {
+                        return Err(malformed("length has leading zeros"));
+                    }
+                    digits += 1;
+                    if digits > max_digits {
+                        return Err(LimitExceeded::new(self.max_len).into());
+                    }
+                    len = len
+                        .checked_mul(10)
+                        .and_then(|len| len.checked_add(u64::from(d - b'0')))
+                        .ok_or(LimitExceeded::new(self.max_len))?;
+                }
+                _ => return Err(malformed("length is not followed by ':'")),
+            }
+        }
+        Ok(Some(len))
+    }
+
+    fn validate_trailer<R: Read + ?Sized>(&mut self, reader: &mut R) -> io::Result<()> {
+        match read_byte(reader)? {
+            Some(b',') => Ok(()),
+            Some(_) => Err(malformed("missing trailing ','")),
+            None => Err(truncated()),
+        }
+    }
+
+    fn max_frame_len(&self) -> u64 {
+        self.max_len
+    }
+}
+
+/// A reader of consecutive netstrings over a borrowed reader.
+///
+/// [`FrameReader::next_frame`] parses the length and returns a
+/// [`RefTake`](crate::RefTake) limited to the payload. A payload longer
+/// than the maximum fails with a [`LimitExceeded`] error, and malformed
+/// input with `ErrorKind::InvalidData`.
+pub type NetstringReader<'a, R> = FrameReader<'a, R, Netstring>;
+
+/// Extension trait to provide a `netstrings_ref` method on all `Read` types.
+pub trait NetstringReaderExt {
+    /// Wraps the reader in a [`NetstringReader`] accepting payloads of at most `max_len` bytes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Cursor, Read};
+    /// use reftake::NetstringReaderExt;
+    ///
+    /// let mut cursor = Cursor::new(b"5:hello,5:world,");
+    /// let mut netstrings = cursor.netstrings_ref(1024);
+    ///
+    /// let mut payloads = Vec::new();
+    /// while let Some(mut payload) = netstrings.next_frame().unwrap() {
+    ///     let mut text = String::new();
+    ///     payload.read_to_string(&mut text).unwrap();
+    ///     payloads.push(text);
+    /// }
+    /// assert_eq!(payloads, ["hello", "world"]);
+    /// ```
+    fn netstrings_ref(&mut self, max_len: u64) -> NetstringReader<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: Read> NetstringReaderExt for T {
+    fn netstrings_ref(&mut self, max_len: u64) -> NetstringReader<'_, Self> {
+        FrameReader::with_strategy(self, Netstring::new(max_len))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn payloads(data: &[u8], max_len: u64) -> io::Result<Vec<Vec<u8>>> {
+        let mut reader = Cursor::new(data);
+        let mut netstrings = reader.netstrings_ref(max_len);
+        let mut out = Vec::new();
+        while let Some(mut payload) = netstrings.next_frame()? {
+            let mut buf = Vec::new();
+            payload.read_to_end(&mut buf)?;
+            out.push(buf);
+        }
+        Ok(out)
+    }
+
+    #[test]
+    fn test_unread_payload_is_skipped() {
+        let mut reader = Cursor::new(b"3:abc,0:,12:hello world!,");
+        let mut netstrings = reader.netstrings_ref(100);
+        assert_eq!(netstrings.next_frame().unwrap().unwrap().current_limit(), 3);
+        assert_eq!(netstrings.next_frame().unwrap().unwrap().current_limit(), 0);
+
+        let mut text = String::new();
+        netstrings
+            .next_frame()
+            .unwrap()
+            .unwrap()
+            .read_to_string(&mut text)
+            .unwrap();
+        assert_eq!(text, "hello world!");
+        assert!(netstrings.next_frame().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_malformed_input() {
+        for data in [&b"3:abc;"[..], b"03:abc,", b"x:", b"3-abc,", b"3:abc,-"] {
+            let err = payloads(data, 100).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidData, "{data:?}");
+            assert_eq!(LimitExceeded::from_io(&err), None);
+        }
+        for data in [&b"3:ab"[..], b"3:abc", b"12"] {
+            let err = payloads(data, 100).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::UnexpectedEof, "{data:?}");
+        }
+    }
+
+    #[test]
+    fn test_length_caps() {
+        assert_eq!(payloads(b"9:123456789,", 9).unwrap().len(), 1);
+        let err = payloads(b"10:0123456789,", 9).unwrap_err();
+        assert_eq!(LimitExceeded::from_io(&err), Some(&LimitExceeded::new(9)));
+        let err = payloads(b"150:", 100).unwrap_err();
+        assert_eq!(LimitExceeded::from_io(&err), Some(&LimitExceeded::new(100)));
+
+        // Digits are capped before they could overflow
+        let mut reader = Cursor::new(b"99999999999999999999999999:".repeat(1000));
+        let err = reader.netstrings_ref(100).next_frame().err().unwrap();
+        assert_eq!(LimitExceeded::from_io(&err), Some(&LimitExceeded::new(100)));
+        assert_eq!(reader.position(), 4);
+    }
+}
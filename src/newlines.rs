@@ -0,0 +1,198 @@
+//! Newline normalization of a borrowed `BufRead`.
+
+use std::{
+    io::{self, BufRead, Read},
+    mem,
+};
+
+/// Which line endings [`RefNormalizeNewlines`] turns into `\n`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum NewlineMode {
+    /// `\r\n` and a lone `\r`, as in old Mac text.
+    #[default]
+    Any,
+    /// Only `\r\n`; a lone `\r` is passed on as it is.
+    CrLf,
+}
+
+/// A non-owning adapter reading a `BufRead` with its line endings turned into `\n`.
+///
+/// The bytes are counted where they are read, so over a
+/// [`RefTake`](crate::RefTake) the limit applies to the raw input, line
+/// endings included, and a `RefTake` over this adapter limits the
+/// normalized text instead. A `\r\n` split across two `fill_buf` calls of
+/// the inner reader is still read as a single line ending: with
+/// [`NewlineMode::CrLf`], a `\r` at the end of a buffer is held back until
+/// the next byte is known.
+pub struct RefNormalizeNewlines<'a, R> {
+    inner: &'a mut R,
+    mode: NewlineMode,
+    /// A `\r` turned into `\n` was just read, so a `\n` right after it is dropped.
+    after_cr: bool,
+    /// A `\r` at the end of a buffer waits for the next byte ([`NewlineMode::CrLf`]).
+    held_cr: bool,
+}
+
+impl<'a, R> RefNormalizeNewlines<'a, R> {
+    /// Creates a new `RefNormalizeNewlines` normalizing the line endings of `mode`.
+    pub const fn wrap(inner: &'a mut R, mode: NewlineMode) -> Self {
+        Self {
+            inner,
+            mode,
+            after_cr: false,
+            held_cr: false,
+        }
+    }
+
+    /// Returns the normalized line endings.
+    pub fn mode(&self) -> NewlineMode {
+        self.mode
+    }
+}
+
+impl<R: BufRead> Read for RefNormalizeNewlines<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            let chunk = self.inner.fill_buf()?;
+            if chunk.is_empty() {
+                return Ok(if mem::take(&mut self.held_cr) {
+                    buf[0] = b'\r';
+                    1
+                } else {
+                    0
+                });
+            }
+
+            let (mut r, mut w) = (0, 0);
+            if mem::take(&mut self.held_cr) {
+                if chunk[0] == b'\n' {
+                    r = 1;
+                    buf[0] = b'\n';
+                } else {
+                    buf[0] = b'\r';
+                }
+                w = 1;
+            }
+            while r < chunk.len() && w < buf.len() {
+                let byte = chunk[r];
+                r += 1;
+                let after_cr = mem::take(&mut self.after_cr);
+                match (byte, self.mode) {
+                    (b'\r', NewlineMode::Any) => {
+                        self.after_cr = true;
+                        buf[w] = b'\n';
+                    }
+                    (b'\r', NewlineMode::CrLf) => match chunk.get(r) {
+                        Some(b'\n') => {
+                            r += 1;
+                            buf[w] = b'\n';
+                        }
+                        Some(_) => buf[w] = b'\r',
+                        None => {
+                            self.held_cr = true;
+                            break;
+                        }
+                    },
+                    (b'\n', _) if after_cr => continue,
+                    _ => buf[w] = byte,
+                }
+                w += 1;
+            }
+            self.inner.consume(r);
+            // Nothing to return if the buffer only held a `\r` or a dropped `\n`
+            if w > 0 {
+                return Ok(w);
+            }
+        }
+    }
+}
+
+/// Extension trait to provide a `normalize_newlines_ref` method on all `BufRead` types.
+pub trait RefNormalizeNewlinesExt {
+    /// Wraps the reader in a `RefNormalizeNewlines` with the given mode.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{BufReader, Read};
+    /// use reftake::{NewlineMode, RefNormalizeNewlinesExt, RefTakeExt};
+    ///
+    /// let mut reader = BufReader::new(&b"HELO a\r\nQUIT\r\nnext"[..]);
+    /// let mut take = reader.take_ref(14);
+    /// let mut text = String::new();
+    /// take.normalize_newlines_ref(NewlineMode::CrLf)
+    ///     .read_to_string(&mut text)
+    ///     .unwrap();
+    /// assert_eq!(text, "HELO a\nQUIT\n");
+    /// ```
+    fn normalize_newlines_ref(&mut self, mode: NewlineMode) -> RefNormalizeNewlines<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: BufRead> RefNormalizeNewlinesExt for T {
+    fn normalize_newlines_ref(&mut self, mode: NewlineMode) -> RefNormalizeNewlines<'_, Self> {
+        RefNormalizeNewlines::wrap(self, mode)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RefTakeExt;
+    use std::io::BufReader;
+
+    fn normalize(data: &[u8], capacity: usize, mode: NewlineMode, read_len: usize) -> Vec<u8> {
+        let mut reader = BufReader::with_capacity(capacity, data);
+        let mut normalized = reader.normalize_newlines_ref(mode);
+        let mut out = Vec::new();
+        let mut buf = vec![0u8; read_len];
+        loop {
+            let n = normalized.read(&mut buf).unwrap();
+            if n == 0 {
+                return out;
+            }
+            out.extend_from_slice(&buf[..n]);
+        }
+    }
+
+    #[test]
+    fn test_line_endings_split_across_buffers() {
+        let data = b"a\r\nb\rc\n\r\r\nd\r";
+        for capacity in 1..=data.len() {
+            for read_len in 1..4 {
+                assert_eq!(
+                    normalize(data, capacity, NewlineMode::Any, read_len),
+                    b"a\nb\nc\n\n\nd\n",
+                    "{capacity} {read_len}"
+                );
+                assert_eq!(
+                    normalize(data, capacity, NewlineMode::CrLf, read_len),
+                    b"a\nb\rc\n\r\nd\r",
+                    "{capacity} {read_len}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_limit_applies_to_raw_bytes() {
+        let mut reader = BufReader::with_capacity(2, &b"x\r\ny\r\nz"[..]);
+        let mut text = Vec::new();
+        let mut take = reader.take_ref(5);
+        take.normalize_newlines_ref(NewlineMode::Any)
+            .read_to_end(&mut text)
+            .unwrap();
+        assert_eq!(text, b"x\ny\n");
+
+        let mut reader = BufReader::new(&b"x\r\ny\r\nz"[..]);
+        let mut normalized = reader.normalize_newlines_ref(NewlineMode::Any);
+        text.clear();
+        normalized.take_ref(4).read_to_end(&mut text).unwrap();
+        assert_eq!(text, b"x\ny\n");
+    }
+}
@@ -0,0 +1,215 @@
+//! Running `nom` streaming parsers over a [`RefTake`] window.
+
+use std::{
+    error::Error,
+    fmt,
+    io::{self, BufRead, ErrorKind},
+};
+
+use nom::{Err, IResult, Needed};
+
+use crate::RefTake;
+
+/// Error of [`RefTake::parse_nom`].
+#[derive(Debug)]
+pub enum NomError<E> {
+    /// The inner reader failed, or ended (`ErrorKind::UnexpectedEof`)
+    /// before the parser had enough input and before the window did.
+    Io(io::Error),
+    /// The parser rejected the input.
+    Parse(E),
+    /// The parser needed more input than the rest of the window holds.
+    ExceedsWindow(Needed),
+}
+
+impl<E: fmt::Debug> fmt::Display for NomError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NomError::Io(e) => write!(f, "I/O error while parsing: {e}"),
+            NomError::Parse(e) => write!(f, "parse error: {e:?}"),
+            NomError::ExceedsWindow(Needed::Size(n)) => {
+                write!(f, "parser needed {n} more bytes than the window allows")
+            }
+            NomError::ExceedsWindow(Needed::Unknown) => {
+                f.write_str("parser needed more bytes than the window allows")
+            }
+        }
+    }
+}
+
+impl<E: fmt::Debug> Error for NomError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            NomError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// The outcome of one parser call, detached from the input it borrowed.
+enum Step<O, E> {
+    Done(O, usize),
+    More(Needed),
+    Fail(E),
+}
+
+impl<O, E> Step<O, E> {
+    fn from_result(result: IResult<&[u8], O, E>) -> Self {
+        match result {
+            Ok((rest, out)) => Step::Done(out, rest.len()),
+            Err(Err::Incomplete(needed)) => Step::More(needed),
+            Err(Err::Error(e) | Err::Failure(e)) => Step::Fail(e),
+        }
+    }
+}
+
+impl<R: BufRead> RefTake<'_, R> {
+    /// Runs a `nom` streaming parser over the window, refilling on `Incomplete`.
+    ///
+    /// The parser first sees the inner reader's buffer as is, without
+    /// copying. While it asks for more, the input is gathered into a
+    /// growing buffer, refilled from the window and handed over again, so
+    /// the parser always sees one contiguous slice. On success exactly the
+    /// bytes the parser used are consumed, and the rest of the window is
+    /// left for the next call.
+    ///
+    /// A parser that needs more input than the rest of the window holds
+    /// fails with [`NomError::ExceedsWindow`], as soon as its `Needed` size
+    /// shows it, while an inner reader ending inside the window is an
+    /// `UnexpectedEof` [`NomError::Io`]. Parse errors come back as
+    /// [`NomError::Parse`]. The parser's output and error types must not
+    /// borrow the input, so use an owned error type such as `()`, or
+    /// convert `nom::error::Error` with `Err::map` inside the closure.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use nom::{IResult, Parser, bytes::streaming::take, number::streaming::be_u16};
+    /// use reftake::{NomError, RefTakeExt};
+    ///
+    /// fn record(i: &[u8]) -> IResult<&[u8], Vec<u8>, ()> {
+    ///     be_u16.flat_map(take).map(<[u8]>::to_vec).parse(i)
+    /// }
+    ///
+    /// let mut cursor = Cursor::new(b"\x00\x03abc\x00\x09tail");
+    /// let mut window = cursor.take_ref(11);
+    /// assert_eq!(window.parse_nom(record).unwrap(), b"abc");
+    /// assert!(matches!(window.parse_nom(record), Err(NomError::ExceedsWindow(_))));
+    /// ```
+    pub fn parse_nom<O, E, P>(&mut self, mut parser: P) -> Result<O, NomError<E>>
+    where
+        P: FnMut(&[u8]) -> IResult<&[u8], O, E>,
+    {
+        let mut buf = Vec::new();
+        loop {
+            let limit = self.limit;
+            let available = self.fill_buf().map_err(NomError::Io)?;
+            let len = available.len();
+            let step = if buf.is_empty() {
+                Step::from_result(parser(available))
+            } else {
+                buf.extend_from_slice(available);
+                Step::from_result(parser(&buf))
+            };
+
+            match step {
+                Step::Done(out, rest) => {
+                    // Everything before this fill was already consumed
+                    self.consume(len.saturating_sub(rest));
+                    return Ok(out);
+                }
+                Step::Fail(e) => return Err(NomError::Parse(e)),
+                Step::More(needed) => {
+                    let window_left = limit - len as u64;
+                    let exceeds = match needed {
+                        Needed::Size(n) => n.get() as u64 > window_left,
+                        Needed::Unknown => window_left == 0,
+                    };
+                    if exceeds {
+                        return Err(NomError::ExceedsWindow(needed));
+                    }
+                    if len == 0 {
+                        return Err(NomError::Io(ErrorKind::UnexpectedEof.into()));
+                    }
+                    if buf.is_empty() {
+                        buf.extend_from_slice(available);
+                    }
+                    self.consume(len);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RefTakeExt;
+    use nom::{
+        Parser,
+        bytes::streaming::{tag, take_until},
+        error::{Error as NomParseError, ErrorKind as NomErrorKind},
+    };
+    use std::io::{BufReader, Cursor, Read};
+
+    fn line(i: &[u8]) -> IResult<&[u8], Vec<u8>, ()> {
+        (take_until("\n"), tag("\n"))
+            .map(|(line, _): (&[u8], _)| line.to_vec())
+            .parse(i)
+    }
+
+    #[test]
+    fn test_refills_across_small_buffers() {
+        let mut reader = BufReader::with_capacity(3, Cursor::new(b"first line\nsecond\n"));
+        let mut take = reader.take_ref(18);
+
+        assert_eq!(take.parse_nom(line).unwrap(), b"first line");
+        assert_eq!(take.current_limit(), 7);
+        assert_eq!(take.parse_nom(line).unwrap(), b"second");
+    }
+
+    #[test]
+    fn test_unused_input_is_left_in_the_window() {
+        let mut reader = BufReader::with_capacity(4, Cursor::new(b"key=value"));
+        let mut take = reader.take_ref(9);
+
+        let key = take
+            .parse_nom(|i| take_until::<_, _, ()>("=").map(<[u8]>::to_vec).parse(i))
+            .unwrap();
+        assert_eq!(key, b"key");
+        let mut rest = String::new();
+        take.read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, "=value");
+    }
+
+    #[test]
+    fn test_window_end_and_reader_end_are_distinct() {
+        let mut cursor = Cursor::new(b"no newline here");
+        let mut take = cursor.take_ref(6);
+        assert!(matches!(
+            take.parse_nom(line),
+            Err(NomError::ExceedsWindow(_))
+        ));
+
+        let mut cursor = Cursor::new(b"short");
+        let mut take = cursor.take_ref(100);
+        match take.parse_nom(line) {
+            Err(NomError::Io(e)) => assert_eq!(e.kind(), ErrorKind::UnexpectedEof),
+            other => panic!("unexpected result {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_parse_errors_are_reported() {
+        let mut cursor = Cursor::new(b"GET /");
+        let mut take = cursor.take_ref(5);
+        let result = take.parse_nom(|i| {
+            tag::<_, _, NomParseError<_>>("POST")
+                .map(|_| ())
+                .parse(i)
+                .map_err(|e| e.map(|e| e.code))
+        });
+        assert!(matches!(result, Err(NomError::Parse(NomErrorKind::Tag))));
+    }
+}
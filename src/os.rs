@@ -0,0 +1,73 @@
+//! Platform handle passthrough for [`RefTake`], delegating to the inner reader.
+
+#[cfg(unix)]
+mod unix {
+    use std::os::fd::{AsFd, AsRawFd, BorrowedFd, RawFd};
+
+    use crate::RefTake;
+
+    impl<R: AsFd> AsFd for RefTake<'_, R> {
+        fn as_fd(&self) -> BorrowedFd<'_> {
+            self.inner.as_fd()
+        }
+    }
+
+    impl<R: AsRawFd> AsRawFd for RefTake<'_, R> {
+        fn as_raw_fd(&self) -> RawFd {
+            self.inner.as_raw_fd()
+        }
+    }
+}
+
+#[cfg(windows)]
+mod windows {
+    use std::os::windows::io::{
+        AsHandle, AsRawHandle, AsRawSocket, AsSocket, BorrowedHandle, BorrowedSocket, RawHandle,
+        RawSocket,
+    };
+
+    use crate::RefTake;
+
+    impl<R: AsHandle> AsHandle for RefTake<'_, R> {
+        fn as_handle(&self) -> BorrowedHandle<'_> {
+            self.inner.as_handle()
+        }
+    }
+
+    impl<R: AsRawHandle> AsRawHandle for RefTake<'_, R> {
+        fn as_raw_handle(&self) -> RawHandle {
+            self.inner.as_raw_handle()
+        }
+    }
+
+    impl<R: AsSocket> AsSocket for RefTake<'_, R> {
+        fn as_socket(&self) -> BorrowedSocket<'_> {
+            self.inner.as_socket()
+        }
+    }
+
+    impl<R: AsRawSocket> AsRawSocket for RefTake<'_, R> {
+        fn as_raw_socket(&self) -> RawSocket {
+            self.inner.as_raw_socket()
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use crate::RefTakeExt;
+    use std::os::{
+        fd::{AsFd, AsRawFd},
+        unix::net::UnixStream,
+    };
+
+    #[test]
+    fn test_fd_passthrough() {
+        let (mut a, _b) = UnixStream::pair().unwrap();
+        let raw = a.as_raw_fd();
+        let take = a.take_ref(10);
+
+        assert_eq!(take.as_raw_fd(), raw);
+        assert_eq!(take.as_fd().as_raw_fd(), raw);
+    }
+}
@@ -0,0 +1,181 @@
+//! A fixed-length view that pads a short reader with zero bytes.
+
+use std::{
+    cmp,
+    io::{self, BufRead, Read},
+};
+
+/// Zero bytes served by `fill_buf` once the inner reader is exhausted.
+static ZEROS: [u8; 512] = [0; 512];
+
+/// A non-owning adapter that always yields exactly `limit` bytes.
+///
+/// Behaves like [`RefTake`](crate::RefTake) while the inner reader has data.
+/// If the inner reader reaches EOF before the limit, zero bytes are
+/// synthesized until the limit is satisfied, so fixed-size record parsers
+/// never see a short record. Once EOF has been seen, the inner reader is not
+/// called again.
+pub struct PaddedTake<'a, R> {
+    inner: &'a mut R,
+    limit: u64,
+    padded: u64,
+    eof: bool,
+}
+
+impl<'a, R> PaddedTake<'a, R> {
+    /// Creates a new `PaddedTake` that yields exactly `limit` bytes.
+    pub const fn wrap(inner: &'a mut R, limit: u64) -> Self {
+        Self {
+            inner,
+            limit,
+            padded: 0,
+            eof: false,
+        }
+    }
+
+    /// Returns the number of bytes still to be yielded.
+    pub fn current_limit(&self) -> u64 {
+        self.limit
+    }
+
+    /// Returns the number of zero bytes synthesized so far.
+    pub fn padding_added(&self) -> u64 {
+        self.padded
+    }
+
+    /// Returns `true` if the inner reader has reached EOF.
+    pub fn inner_eof(&self) -> bool {
+        self.eof
+    }
+}
+
+impl<R: Read> Read for PaddedTake<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        if self.limit == 0 || buf.is_empty() {
+            return Ok(0);
+        }
+
+        let max = cmp::min(buf.len() as u64, self.limit) as usize;
+        if !self.eof {
+            let n = self.inner.read(&mut buf[..max])?;
+            if n > 0 {
+                assert!(n as u64 <= self.limit, "number of read bytes exceeds limit");
+                self.limit -= n as u64;
+                return Ok(n);
+            }
+            self.eof = true;
+        }
+
+        buf[..max].fill(0);
+        self.limit -= max as u64;
+        self.padded += max as u64;
+        Ok(max)
+    }
+}
+
+impl<R: BufRead> BufRead for PaddedTake<'_, R> {
+    fn fill_buf(&mut self) -> Result<&[u8], io::Error> {
+        if self.limit == 0 {
+            return Ok(&[]);
+        }
+
+        if !self.eof {
+            if self.inner.fill_buf()?.is_empty() {
+                self.eof = true;
+            } else {
+                let buf = self.inner.fill_buf()?;
+                let cap = cmp::min(buf.len() as u64, self.limit) as usize;
+                return Ok(&buf[..cap]);
+            }
+        }
+
+        let cap = cmp::min(ZEROS.len() as u64, self.limit) as usize;
+        Ok(&ZEROS[..cap])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        let amt = cmp::min(amt as u64, self.limit) as usize;
+        self.limit -= amt as u64;
+        if self.eof {
+            self.padded += amt as u64;
+        } else {
+            self.inner.consume(amt);
+        }
+    }
+}
+
+/// Extension trait to provide a `take_padded` method on all `Read` types.
+pub trait PaddedTakeExt {
+    /// Wraps the reader in a `PaddedTake` yielding exactly `limit` bytes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Cursor, Read};
+    /// use reftake::PaddedTakeExt;
+    ///
+    /// let mut cursor = Cursor::new(b"abc");
+    /// let mut record = [0xffu8; 6];
+    /// cursor.take_padded(6).read_exact(&mut record).unwrap();
+    /// assert_eq!(&record, b"abc\0\0\0");
+    /// ```
+    fn take_padded(&mut self, limit: u64) -> PaddedTake<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: Read> PaddedTakeExt for T {
+    fn take_padded(&mut self, limit: u64) -> PaddedTake<'_, Self> {
+        PaddedTake::wrap(self, limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufReader, Cursor};
+
+    #[test]
+    fn test_no_padding_when_data_suffices() {
+        let mut reader = Cursor::new(b"abcdef");
+        let mut padded = reader.take_padded(4);
+
+        let mut buf = Vec::new();
+        padded.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, b"abcd");
+        assert_eq!(padded.padding_added(), 0);
+    }
+
+    #[test]
+    fn test_pads_short_input() {
+        let mut reader = Cursor::new(b"ab");
+        let mut padded = reader.take_padded(5);
+
+        let mut buf = Vec::new();
+        padded.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, b"ab\0\0\0");
+        assert_eq!(padded.padding_added(), 3);
+        assert!(padded.inner_eof());
+    }
+
+    #[test]
+    fn test_bufread_pads_short_input() {
+        let mut reader = BufReader::new(Cursor::new(b"xy"));
+        let mut padded = reader.take_padded(1000);
+
+        let mut buf = Vec::new();
+        loop {
+            let chunk = padded.fill_buf().unwrap();
+            if chunk.is_empty() {
+                break;
+            }
+            let n = chunk.len();
+            buf.extend_from_slice(chunk);
+            padded.consume(n);
+        }
+        assert_eq!(buf.len(), 1000);
+        assert_eq!(&buf[..3], b"xy\0");
+        assert!(buf[2..].iter().all(|&b| b == 0));
+        assert_eq!(padded.padding_added(), 998);
+    }
+}
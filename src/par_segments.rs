@@ -0,0 +1,90 @@
+//! Parallel processing of the segments of a shared source with `rayon`.
+
+use std::sync::Arc;
+
+use rayon::prelude::*;
+
+use crate::{ReadAt, SharedTakeAt};
+
+/// Runs `f` over a [`SharedTakeAt`] window for every `(offset, len)`
+/// segment of `source`, in parallel on the rayon thread pool.
+///
+/// Every segment gets its own window, so `f` can parse it as a plain
+/// `Read` stream without coordinating with the other threads. The results
+/// are returned in the order of `segments`, each segment's error next to
+/// the others' values, so one bad archive member does not hide the rest;
+/// collect into a `Result<Vec<_>, _>` to stop at the first error instead.
+///
+/// # Example
+///
+/// ```
+/// use std::{io::Read, sync::Arc};
+///
+/// let archive: Arc<[u8]> = Arc::from(&b"12|345|x|6789"[..]);
+/// let members = [(0, 2), (3, 3), (7, 1), (9, 4)];
+///
+/// let sums = reftake::par_segments(&archive, &members, |mut member| {
+///     let mut digits = String::new();
+///     member.read_to_string(&mut digits)?;
+///     digits
+///         .bytes()
+///         .map(|b| b.is_ascii_digit().then(|| u32::from(b - b'0')).ok_or("not a digit"))
+///         .sum::<Result<u32, _>>()
+///         .map_err(std::io::Error::other)
+/// });
+///
+/// assert_eq!(sums[1].as_ref().unwrap(), &12);
+/// assert!(sums[2].is_err());
+/// assert_eq!(sums[3].as_ref().unwrap(), &30);
+/// ```
+pub fn par_segments<F, T, E, P>(source: &Arc<F>, segments: &[(u64, u64)], f: P) -> Vec<Result<T, E>>
+where
+    F: ReadAt + Send + Sync + ?Sized,
+    T: Send,
+    E: Send,
+    P: Fn(SharedTakeAt<F>) -> Result<T, E> + Sync,
+{
+    segments
+        .par_iter()
+        .map(|&(offset, len)| f(SharedTakeAt::new(Arc::clone(source), offset, len)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{self, Read};
+
+    #[test]
+    fn test_results_follow_segment_order() {
+        let data: Arc<[u8]> = (0..=255u8).collect::<Vec<_>>().into();
+        let segments: Vec<_> = (0..64).map(|i| (i * 4, 4)).collect();
+
+        let firsts = par_segments(&data, &segments, |mut window| {
+            let mut buf = [0u8; 4];
+            window.read_exact(&mut buf)?;
+            Ok::<_, io::Error>(buf[0])
+        });
+        let expected: Vec<u8> = (0..64).map(|i| i * 4).collect();
+        assert_eq!(
+            firsts.into_iter().collect::<io::Result<Vec<_>>>().unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_segments_past_the_end_fail_alone() {
+        let data: Arc<[u8]> = Arc::from(&b"abcdef"[..]);
+        let results = par_segments(&data, &[(0, 3), (4, 8), (3, 1)], |mut window| {
+            let mut buf = vec![0u8; window.current_limit() as usize];
+            window.read_exact(&mut buf).map(|_| buf)
+        });
+
+        assert_eq!(results[0].as_ref().unwrap(), b"abc");
+        assert_eq!(
+            results[1].as_ref().unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+        assert_eq!(results[2].as_ref().unwrap(), b"d");
+    }
+}
@@ -0,0 +1,233 @@
+//! A lookahead adapter over a borrowed reader.
+
+use std::io::{self, BufRead, ErrorKind, Read};
+
+const DEFAULT_CAPACITY: usize = 8 * 1024;
+
+/// A non-owning adapter that allows looking at upcoming bytes without consuming them.
+///
+/// Bytes requested through [`RefPeek::peek`] are read from the inner reader
+/// into a small internal buffer and replayed by subsequent reads, so callers
+/// see the stream exactly as if no lookahead had happened.
+///
+/// The lookahead is bounded by a capacity set at construction, so a peek
+/// length taken from untrusted input can't make the buffer grow without
+/// limit.
+///
+/// Peeked bytes that have not been read when the adapter is dropped are lost,
+/// since they have already been taken from the inner reader.
+pub struct RefPeek<'a, R> {
+    inner: &'a mut R,
+    buf: Vec<u8>,
+    pos: usize,
+    capacity: usize,
+}
+
+impl<'a, R> RefPeek<'a, R> {
+    /// Creates a new `RefPeek` over the given reader reference, peeking at most 8 KiB ahead.
+    pub const fn wrap(inner: &'a mut R) -> Self {
+        Self::with_capacity(inner, DEFAULT_CAPACITY)
+    }
+
+    /// Creates a new `RefPeek` peeking at most `capacity` bytes ahead.
+    ///
+    /// The lookahead buffer is allocated as it fills, not up front.
+    pub const fn with_capacity(inner: &'a mut R, capacity: usize) -> Self {
+        Self {
+            inner,
+            buf: Vec::new(),
+            pos: 0,
+            capacity,
+        }
+    }
+
+    /// Returns the largest number of bytes that can be peeked at once.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the bytes that have been peeked but not read yet.
+    pub fn buffer(&self) -> &[u8] {
+        &self.buf[self.pos..]
+    }
+}
+
+impl<R: Read> RefPeek<'_, R> {
+    /// Returns the next `n` bytes of the stream without consuming them.
+    ///
+    /// The returned slice is shorter than `n` only if the inner reader reached EOF.
+    /// Fails with `ErrorKind::InvalidInput` if `n` exceeds the capacity.
+    pub fn peek(&mut self, n: usize) -> io::Result<&[u8]> {
+        if n > self.capacity {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("cannot peek {n} bytes, the capacity is {}", self.capacity),
+            ));
+        }
+        if self.buf.len() - self.pos < n && self.pos > 0 {
+            self.buf.drain(..self.pos);
+            self.pos = 0;
+        }
+
+        while self.buf.len() < n {
+            let old = self.buf.len();
+            self.buf.resize(n, 0);
+            match self.inner.read(&mut self.buf[old..]) {
+                Ok(k) => {
+                    self.buf.truncate(old + k);
+                    if k == 0 {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    self.buf.truncate(old);
+                    if e.kind() != ErrorKind::Interrupted {
+                        return Err(e);
+                    }
+                }
+            }
+        }
+
+        let end = (self.pos + n).min(self.buf.len());
+        Ok(&self.buf[self.pos..end])
+    }
+
+    fn advance(&mut self, amt: usize) {
+        self.pos = (self.pos + amt).min(self.buf.len());
+        if self.pos == self.buf.len() {
+            self.buf.clear();
+            self.pos = 0;
+        }
+    }
+}
+
+impl<R: Read> Read for RefPeek<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        let buffered = self.buffer();
+        if buffered.is_empty() {
+            return self.inner.read(buf);
+        }
+        let n = buffered.len().min(buf.len());
+        buf[..n].copy_from_slice(&buffered[..n]);
+        self.advance(n);
+        Ok(n)
+    }
+}
+
+/// Implements the `BufRead` trait, serving the lookahead buffer first and the
+/// inner reader's buffer once it has been drained.
+impl<R: BufRead> BufRead for RefPeek<'_, R> {
+    fn fill_buf(&mut self) -> Result<&[u8], io::Error> {
+        if self.pos < self.buf.len() {
+            return Ok(&self.buf[self.pos..]);
+        }
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        if self.pos < self.buf.len() {
+            self.advance(amt);
+        } else {
+            self.inner.consume(amt);
+        }
+    }
+}
+
+/// Extension trait to provide a `peek_ref` method on all `Read` types.
+pub trait RefPeekExt {
+    /// Wraps the reader in a `RefPeek` that supports lookahead.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Cursor, Read};
+    /// use reftake::RefPeekExt;
+    ///
+    /// let mut cursor = Cursor::new(b"\x89PNG....");
+    /// let mut peek = cursor.peek_ref();
+    /// assert_eq!(peek.peek(4).unwrap(), b"\x89PNG");
+    ///
+    /// let mut all = Vec::new();
+    /// peek.read_to_end(&mut all).unwrap();
+    /// assert_eq!(all, b"\x89PNG....");
+    /// ```
+    fn peek_ref(&mut self) -> RefPeek<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: Read> RefPeekExt for T {
+    fn peek_ref(&mut self) -> RefPeek<'_, Self> {
+        RefPeek::wrap(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RefTakeExt;
+    use std::io::{BufReader, Cursor};
+
+    #[test]
+    fn test_peek_then_read() {
+        let mut reader = Cursor::new(b"abcdef");
+        let mut peek = reader.peek_ref();
+
+        assert_eq!(peek.peek(2).unwrap(), b"ab");
+        assert_eq!(peek.peek(4).unwrap(), b"abcd");
+
+        let mut buf = [0u8; 3];
+        assert_eq!(peek.read(&mut buf).unwrap(), 3);
+        assert_eq!(&buf, b"abc");
+        assert_eq!(peek.buffer(), b"d");
+
+        assert_eq!(peek.peek(2).unwrap(), b"de");
+        let mut rest = String::new();
+        peek.read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, "def");
+    }
+
+    #[test]
+    fn test_peek_past_eof() {
+        let mut reader = Cursor::new(b"ab");
+        let mut peek = reader.peek_ref();
+        assert_eq!(peek.peek(10).unwrap(), b"ab");
+    }
+
+    #[test]
+    fn test_peek_beyond_capacity() {
+        let mut reader = Cursor::new(b"abcdefgh");
+        let mut peek = RefPeek::with_capacity(&mut reader, 4);
+        assert_eq!(peek.peek(4).unwrap(), b"abcd");
+
+        let err = peek.peek(5).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        assert_eq!(err.to_string(), "cannot peek 5 bytes, the capacity is 4");
+        // Nothing more was read, and the lookahead is still replayed
+        assert_eq!(peek.buffer(), b"abcd");
+        let mut all = Vec::new();
+        peek.read_to_end(&mut all).unwrap();
+        assert_eq!(all, b"abcdefgh");
+
+        let mut reader = Cursor::new(b"ab");
+        assert_eq!(reader.peek_ref().capacity(), 8 * 1024);
+    }
+
+    #[test]
+    fn test_peek_under_take_respects_limit() {
+        let mut reader = Cursor::new(b"abcdef");
+        let mut take = reader.take_ref(3);
+        let mut peek = take.peek_ref();
+        assert_eq!(peek.peek(5).unwrap(), b"abc");
+    }
+
+    #[test]
+    fn test_peek_bufread() {
+        let mut reader = BufReader::new(Cursor::new(b"line one\nline two\n"));
+        let mut peek = reader.peek_ref();
+        assert_eq!(peek.peek(4).unwrap(), b"line");
+
+        let lines: Vec<String> = peek.lines().map(Result::unwrap).collect();
+        assert_eq!(lines, vec!["line one", "line two"]);
+    }
+}
@@ -0,0 +1,234 @@
+//! A builder composing the crate's adapters into a single reader.
+
+use std::{
+    io::{self, Read, Write},
+    time::{Duration, Instant},
+};
+
+use crate::{RefDeadline, RefGuard, RefInspect, RefSkip, RefTake, RefTee};
+
+/// A builder that stacks the crate's adapters over one borrowed reader.
+///
+/// Each method adds a stage on top of the previous ones, so stages apply in
+/// the order they are added: `skip(8).limit(1024)` skips 8 bytes of the
+/// source, then reads at most 1024 bytes after them. The result is a single
+/// boxed reader, avoiding deeply nested adapter types.
+///
+/// # Example
+///
+/// ```
+/// use std::io::{Cursor, Read};
+/// use reftake::Pipeline;
+///
+/// let mut source = Cursor::new(b"HEADER..payload....");
+/// let mut log = Vec::new();
+///
+/// let mut reader = Pipeline::new(&mut source).skip(8).limit(7).tee(&mut log).build();
+/// let mut payload = String::new();
+/// reader.read_to_string(&mut payload).unwrap();
+/// drop(reader);
+///
+/// assert_eq!(payload, "payload");
+/// assert_eq!(log, b"payload");
+/// ```
+pub struct Pipeline<'a> {
+    reader: Box<dyn Read + 'a>,
+}
+
+impl<'a> Pipeline<'a> {
+    /// Starts a pipeline reading from the given reader reference.
+    pub fn new<R: Read + 'a>(inner: &'a mut R) -> Self {
+        Self {
+            reader: Box::new(inner),
+        }
+    }
+
+    fn stage<S: Read + 'a>(self, f: impl FnOnce(Box<dyn Read + 'a>) -> S) -> Self {
+        Self {
+            reader: Box::new(f(self.reader)),
+        }
+    }
+
+    /// Discards the first `skip` bytes, like [`RefSkip`].
+    pub fn skip(self, skip: u64) -> Self {
+        self.stage(|prev| SkipStage { prev, skip })
+    }
+
+    /// Reads at most `limit` bytes, like [`RefTake`].
+    pub fn limit(self, limit: u64) -> Self {
+        self.stage(|prev| LimitStage { prev, limit })
+    }
+
+    /// Fails with [`LimitExceeded`](crate::LimitExceeded) if more than `max`
+    /// bytes are available, like [`RefGuard`].
+    pub fn guard(self, max: u64) -> Self {
+        self.stage(|prev| GuardStage {
+            prev,
+            max,
+            remaining: max,
+        })
+    }
+
+    /// Copies every byte read into `writer`, like [`RefTee`].
+    pub fn tee<W: Write + 'a>(self, writer: &'a mut W) -> Self {
+        self.stage(|prev| TeeStage { prev, writer })
+    }
+
+    /// Calls `f` with every chunk read, like [`RefInspect`].
+    pub fn inspect<F: FnMut(&[u8]) + 'a>(self, f: F) -> Self {
+        self.stage(|prev| InspectStage { prev, f })
+    }
+
+    /// Fails reads after `timeout` from now, like [`RefDeadline`].
+    pub fn deadline(self, timeout: Duration) -> Self {
+        let deadline = Instant::now() + timeout;
+        self.stage(|prev| DeadlineStage { prev, deadline })
+    }
+
+    /// Finishes the pipeline, returning the composed reader.
+    pub fn build(self) -> Box<dyn Read + 'a> {
+        self.reader
+    }
+}
+
+impl Read for Pipeline<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        self.reader.read(buf)
+    }
+}
+
+// Each stage owns the previous stage and keeps the state of one adapter,
+// recreating the adapter around the previous stage for every call.
+
+struct SkipStage<'a> {
+    prev: Box<dyn Read + 'a>,
+    skip: u64,
+}
+
+impl Read for SkipStage<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut adapter = RefSkip::wrap(&mut self.prev, self.skip);
+        let result = adapter.read(buf);
+        self.skip = adapter.remaining_skip();
+        result
+    }
+}
+
+struct LimitStage<'a> {
+    prev: Box<dyn Read + 'a>,
+    limit: u64,
+}
+
+impl Read for LimitStage<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut adapter = RefTake::wrap(&mut self.prev, self.limit);
+        let result = adapter.read(buf);
+        self.limit = adapter.current_limit();
+        result
+    }
+}
+
+struct GuardStage<'a> {
+    prev: Box<dyn Read + 'a>,
+    max: u64,
+    remaining: u64,
+}
+
+impl Read for GuardStage<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut adapter = RefGuard::resume(&mut self.prev, self.max, self.remaining);
+        let result = adapter.read(buf);
+        self.remaining = self.max - adapter.bytes_read();
+        result
+    }
+}
+
+struct TeeStage<'a, W> {
+    prev: Box<dyn Read + 'a>,
+    writer: &'a mut W,
+}
+
+impl<W: Write> Read for TeeStage<'_, W> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        RefTee::wrap(&mut self.prev, self.writer).read(buf)
+    }
+}
+
+struct InspectStage<'a, F> {
+    prev: Box<dyn Read + 'a>,
+    f: F,
+}
+
+impl<F: FnMut(&[u8])> Read for InspectStage<'_, F> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        RefInspect::wrap(&mut self.prev, &mut self.f).read(buf)
+    }
+}
+
+struct DeadlineStage<'a> {
+    prev: Box<dyn Read + 'a>,
+    deadline: Instant,
+}
+
+impl Read for DeadlineStage<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        RefDeadline::wrap(&mut self.prev, self.deadline).read(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LimitExceeded;
+    use std::io::{Cursor, ErrorKind};
+
+    #[test]
+    fn test_stages_apply_in_order() {
+        let mut source = Cursor::new(b"0123456789");
+        let mut reader = Pipeline::new(&mut source).limit(6).skip(2).build();
+
+        let mut buf = String::new();
+        reader.read_to_string(&mut buf).unwrap();
+        assert_eq!(buf, "2345");
+    }
+
+    #[test]
+    fn test_inspect_and_tee() {
+        let mut source = Cursor::new(b"abcdefgh");
+        let mut log = Vec::new();
+        let mut seen = 0;
+        {
+            let mut pipeline = Pipeline::new(&mut source)
+                .skip(1)
+                .limit(4)
+                .inspect(|chunk| seen += chunk.len())
+                .tee(&mut log);
+            let mut buf = Vec::new();
+            pipeline.read_to_end(&mut buf).unwrap();
+            assert_eq!(buf, b"bcde");
+        }
+        assert_eq!(seen, 4);
+        assert_eq!(log, b"bcde");
+        assert_eq!(source.position(), 5);
+    }
+
+    #[test]
+    fn test_guard_stage() {
+        let mut source = Cursor::new(vec![1u8; 100]);
+        let mut reader = Pipeline::new(&mut source).guard(10).build();
+
+        let mut buf = Vec::new();
+        let err = reader.read_to_end(&mut buf).unwrap_err();
+        assert_eq!(LimitExceeded::from_io(&err), Some(&LimitExceeded::new(10)));
+    }
+
+    #[test]
+    fn test_deadline_stage() {
+        let mut source = Cursor::new(b"abc");
+        let mut reader = Pipeline::new(&mut source).deadline(Duration::ZERO).build();
+
+        let mut buf = [0u8; 3];
+        let err = reader.read(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::TimedOut);
+    }
+}
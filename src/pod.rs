@@ -0,0 +1,121 @@
+//! Reading fixed-layout structs from a [`RefTake`] window (features `bytemuck` and `zerocopy`).
+
+use std::io::{self, ErrorKind, Read};
+
+use crate::RefTake;
+
+impl<R: Read> RefTake<'_, R> {
+    /// Fails without reading anything if `T` doesn't fit in the remaining window.
+    fn check_fits<T>(&self) -> io::Result<()> {
+        let size = size_of::<T>();
+        if size as u64 > self.current_limit() {
+            return Err(io::Error::new(
+                ErrorKind::UnexpectedEof,
+                format!(
+                    "{size} bytes requested but only {} left in window",
+                    self.current_limit()
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Reads a plain-old-data value of type `T` from the next `size_of::<T>()` bytes.
+    ///
+    /// Fails with `ErrorKind::UnexpectedEof` without reading anything if `T`
+    /// is larger than the remaining window.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use bytemuck::{Pod, Zeroable};
+    /// use reftake::RefTakeExt;
+    ///
+    /// #[derive(Clone, Copy, Pod, Zeroable)]
+    /// #[repr(C)]
+    /// struct Header {
+    ///     magic: [u8; 4],
+    ///     version: [u8; 2],
+    /// }
+    ///
+    /// let mut cursor = Cursor::new(b"PK\x03\x04\x14\x00rest");
+    /// let header: Header = cursor.take_ref(6).read_pod().unwrap();
+    /// assert_eq!(&header.magic, b"PK\x03\x04");
+    /// assert_eq!(header.version, [0x14, 0x00]);
+    /// ```
+    #[cfg(feature = "bytemuck")]
+    pub fn read_pod<T: bytemuck::Pod>(&mut self) -> io::Result<T> {
+        self.check_fits::<T>()?;
+        let mut value = T::zeroed();
+        self.read_exact(bytemuck::bytes_of_mut(&mut value))?;
+        Ok(value)
+    }
+
+    /// Reads a value of type `T` from the next `size_of::<T>()` bytes using `zerocopy`.
+    ///
+    /// Fails with `ErrorKind::UnexpectedEof` without reading anything if `T`
+    /// is larger than the remaining window.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use zerocopy::{FromBytes, little_endian::U16};
+    /// use reftake::RefTakeExt;
+    ///
+    /// #[derive(FromBytes)]
+    /// #[repr(C)]
+    /// struct Header {
+    ///     magic: [u8; 4],
+    ///     version: U16,
+    /// }
+    ///
+    /// let mut cursor = Cursor::new(b"PK\x03\x04\x14\x00rest");
+    /// let header: Header = cursor.take_ref(6).read_zerocopy().unwrap();
+    /// assert_eq!(header.version.get(), 20);
+    /// ```
+    #[cfg(feature = "zerocopy")]
+    pub fn read_zerocopy<T: zerocopy::FromBytes>(&mut self) -> io::Result<T> {
+        self.check_fits::<T>()?;
+        T::read_from_io(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::RefTakeExt;
+    use std::io::{Cursor, ErrorKind};
+
+    #[cfg(feature = "bytemuck")]
+    #[test]
+    fn test_read_pod_too_large_for_window() {
+        let mut reader = Cursor::new([1u8; 16]);
+        let mut take = reader.take_ref(3);
+        let err = take.read_pod::<u32>().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+        assert_eq!(take.current_limit(), 3);
+    }
+
+    #[cfg(feature = "bytemuck")]
+    #[test]
+    fn test_read_pod_sequence() {
+        let mut reader = Cursor::new([1u8, 0, 2, 0, 9]);
+        let mut take = reader.take_ref(4);
+        let pair = take.read_pod::<[u16; 2]>().unwrap();
+        assert_eq!(pair.map(u16::from_le), [1, 2]);
+        assert_eq!(take.current_limit(), 0);
+    }
+
+    #[cfg(feature = "zerocopy")]
+    #[test]
+    fn test_read_zerocopy() {
+        use zerocopy::big_endian::U32;
+
+        let mut reader = Cursor::new([0, 0, 1, 0, 7]);
+        let mut take = reader.take_ref(4);
+        assert_eq!(take.read_zerocopy::<U32>().unwrap().get(), 256);
+        let err = take.read_zerocopy::<u8>().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,115 @@
+//! Bounded deserialization with `postcard`.
+
+use std::io::{self, ErrorKind, Read};
+
+use ::postcard::Error;
+use serde::de::DeserializeOwned;
+
+use crate::{LimitExceeded, RefTake, TrailingData};
+
+/// Deserializes a `T` from a reader holding a postcard message of at most `limit` bytes.
+///
+/// The whole reader is the message: it is read through a [`RefTake`] of
+/// one byte more than the limit, and it must end right after the value.
+/// `scratch` is the buffer `postcard::from_io` decodes strings and byte
+/// arrays through, so it must be as long as the longest of them.
+///
+/// `postcard::Error` has no room for an I/O error, so failures are reported
+/// as `io::Error`s instead:
+///
+/// * a reader longer than `limit` bytes carries a [`LimitExceeded`] payload;
+/// * bytes after the value carry a [`TrailingData`] payload;
+/// * a malformed message carries the `postcard::Error`, with kind
+///   `UnexpectedEof` for a message that ends too early (or does not fit
+///   `scratch`) and `InvalidData` otherwise.
+///
+/// # Example
+///
+/// ```
+/// use std::io::Cursor;
+/// use reftake::LimitExceeded;
+///
+/// let message = postcard::to_stdvec(&("node-3", [1u16, 2, 3])).unwrap();
+/// let mut scratch = [0u8; 32];
+///
+/// let value: (String, [u16; 3]) =
+///     reftake::postcard::from_reader_limited(&mut Cursor::new(&message), 16, &mut scratch).unwrap();
+/// assert_eq!(value.0, "node-3");
+///
+/// let err = reftake::postcard::from_reader_limited::<_, (String, [u16; 3])>(
+///     &mut Cursor::new(&message),
+///     4,
+///     &mut scratch,
+/// )
+/// .unwrap_err();
+/// assert_eq!(LimitExceeded::from_io(&err), Some(&LimitExceeded::new(4)));
+/// ```
+pub fn from_reader_limited<R: Read, T: DeserializeOwned>(
+    reader: &mut R,
+    limit: u64,
+    scratch: &mut [u8],
+) -> io::Result<T> {
+    let mut take = RefTake::wrap(reader, limit.saturating_add(1));
+    let result = ::postcard::from_io((&mut take, scratch)).map(|(value, _)| value);
+    if result.is_ok()
+        && take.current_limit() > 0
+        && take.read(&mut [0u8])? > 0
+        && take.current_limit() > 0
+    {
+        return Err(TrailingData.into());
+    }
+    if take.current_limit() == 0 {
+        return Err(LimitExceeded::new(limit).into());
+    }
+    result.map_err(|e| {
+        let kind = match e {
+            Error::DeserializeUnexpectedEnd => ErrorKind::UnexpectedEof,
+            _ => ErrorKind::InvalidData,
+        };
+        io::Error::new(kind, e)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_message_of_exactly_limit_bytes() {
+        let message = ::postcard::to_stdvec(&(300u16, "ok")).unwrap();
+        let mut reader = Cursor::new(&message);
+        let mut scratch = [0u8; 8];
+        let value: (u16, String) =
+            from_reader_limited(&mut reader, message.len() as u64, &mut scratch).unwrap();
+        assert_eq!(value, (300, "ok".to_string()));
+    }
+
+    #[test]
+    fn test_long_message_is_limit_exceeded() {
+        let message = ::postcard::to_stdvec(&[7u32; 16]).unwrap();
+        let mut reader = Cursor::new(&message);
+        let err = from_reader_limited::<_, [u32; 16]>(&mut reader, 8, &mut []).unwrap_err();
+        assert_eq!(LimitExceeded::from_io(&err), Some(&LimitExceeded::new(8)));
+        assert_eq!(reader.position(), 9);
+    }
+
+    #[test]
+    fn test_trailing_bytes_are_reported() {
+        let mut reader = Cursor::new([1u8, 2]);
+        let err = from_reader_limited::<_, u8>(&mut reader, 8, &mut []).unwrap_err();
+        assert_eq!(TrailingData::from_io(&err), Some(&TrailingData));
+    }
+
+    #[test]
+    fn test_malformed_messages_keep_postcard_error() {
+        let mut reader = Cursor::new([2u8]);
+        let err = from_reader_limited::<_, bool>(&mut reader, 8, &mut []).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(err.get_ref().unwrap().is::<Error>());
+
+        let mut reader = Cursor::new([0x80u8]);
+        let err = from_reader_limited::<_, u32>(&mut reader, 8, &mut []).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,230 @@
+//! Reading ahead of a borrowed reader on a background thread.
+
+use std::{
+    cmp,
+    io::{self, BufRead, ErrorKind, Read},
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+};
+
+/// A limited view over a reader that is read ahead on a background thread.
+///
+/// Created by [`RefPrefetchExt::prefetch_ref`]. While the caller consumes
+/// one chunk, the next one of the window is read into a second buffer, so
+/// the latency of a network or disk source overlaps with the work done on
+/// the data. No more than the limit is read from the inner reader.
+///
+/// An error of the inner reader is returned once the chunks read before it
+/// have been consumed, and every read after it fails too.
+pub struct RefPrefetch {
+    chunks: Receiver<io::Result<Vec<u8>>>,
+    recycle: Sender<Vec<u8>>,
+    current: Vec<u8>,
+    pos: usize,
+    remaining: u64,
+    failed: Option<ErrorKind>,
+}
+
+impl RefPrefetch {
+    /// Returns the number of bytes of the window not consumed yet, including prefetched ones.
+    pub fn current_limit(&self) -> u64 {
+        self.remaining
+    }
+
+    /// Returns the bytes of the current chunk not consumed yet.
+    pub fn buffer(&self) -> &[u8] {
+        &self.current[self.pos..]
+    }
+}
+
+impl Read for RefPrefetch {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        let available = self.fill_buf()?;
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl BufRead for RefPrefetch {
+    fn fill_buf(&mut self) -> Result<&[u8], io::Error> {
+        if self.pos == self.current.len() {
+            if let Some(kind) = self.failed {
+                return Err(io::Error::new(kind, "prefetch stopped by an earlier error"));
+            }
+            match self.chunks.recv() {
+                Ok(Ok(chunk)) => {
+                    let used = std::mem::replace(&mut self.current, chunk);
+                    if used.capacity() > 0 {
+                        // The reading thread is gone if this fails, and the buffer with it
+                        let _ = self.recycle.send(used);
+                    }
+                    self.pos = 0;
+                }
+                Ok(Err(e)) => {
+                    self.failed = Some(e.kind());
+                    return Err(e);
+                }
+                // The reading thread has reached the limit or EOF
+                Err(_) => {}
+            }
+        }
+        Ok(self.buffer())
+    }
+
+    fn consume(&mut self, amt: usize) {
+        let amt = amt.min(self.current.len() - self.pos);
+        self.pos += amt;
+        self.remaining -= amt as u64;
+    }
+}
+
+/// Reads up to `limit` bytes of `inner` in chunks, handing them over and
+/// taking back used buffers, until EOF, an error, or the consumer leaves.
+fn read_ahead<R: Read + ?Sized>(
+    inner: &mut R,
+    mut limit: u64,
+    chunk_size: usize,
+    chunks: Sender<io::Result<Vec<u8>>>,
+    recycle: Receiver<Vec<u8>>,
+) {
+    while limit > 0 {
+        let Ok(mut buf) = recycle.recv() else {
+            return;
+        };
+        buf.resize(cmp::min(chunk_size as u64, limit) as usize, 0);
+        let result = loop {
+            match inner.read(&mut buf) {
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                result => break result,
+            }
+        };
+        let chunk = match result {
+            Ok(0) => return,
+            Ok(n) => {
+                buf.truncate(n);
+                limit -= n as u64;
+                Ok(buf)
+            }
+            Err(e) => Err(e),
+        };
+        let failed = chunk.is_err();
+        if chunks.send(chunk).is_err() || failed {
+            return;
+        }
+    }
+}
+
+/// Extension trait to provide a `prefetch_ref` method on all `Read + Send` types.
+pub trait RefPrefetchExt {
+    /// Runs `f` with a [`RefPrefetch`] reading at most `limit` bytes of the
+    /// reader ahead, in chunks of `chunk_size` bytes, on a scoped thread.
+    ///
+    /// The reader is borrowed by the thread until `f` returns. The thread
+    /// then stops at its next chunk, and this call waits for it, so a read
+    /// of the inner reader that is blocked at that point delays the return.
+    /// Chunks read ahead but not consumed by `f` are lost.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Cursor, Read};
+    /// use reftake::RefPrefetchExt;
+    ///
+    /// let mut archive = Cursor::new(vec![1u8; 100_000]);
+    /// let sum = archive.prefetch_ref(60_000, 8192, |entry| {
+    ///     entry.bytes().map(|b| u64::from(b.unwrap())).sum::<u64>()
+    /// });
+    /// assert_eq!(sum, 60_000);
+    /// ```
+    fn prefetch_ref<T, F>(&mut self, limit: u64, chunk_size: usize, f: F) -> T
+    where
+        Self: Sized,
+        F: FnOnce(&mut RefPrefetch) -> T;
+}
+
+impl<R: Read + Send> RefPrefetchExt for R {
+    fn prefetch_ref<T, F>(&mut self, limit: u64, chunk_size: usize, f: F) -> T
+    where
+        F: FnOnce(&mut RefPrefetch) -> T,
+    {
+        let chunk_size = chunk_size.max(1);
+        let (chunk_tx, chunk_rx) = mpsc::channel();
+        let (recycle_tx, recycle_rx) = mpsc::channel();
+        // One buffer being read into while the other is consumed
+        for _ in 0..2 {
+            let _ = recycle_tx.send(Vec::with_capacity(chunk_size));
+        }
+        thread::scope(|scope| {
+            scope.spawn(move || read_ahead(self, limit, chunk_size, chunk_tx, recycle_rx));
+            let mut prefetch = RefPrefetch {
+                chunks: chunk_rx,
+                recycle: recycle_tx,
+                current: Vec::new(),
+                pos: 0,
+                remaining: limit,
+                failed: None,
+            };
+            f(&mut prefetch)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// Fails once `ok` bytes have been read.
+    struct Failing {
+        ok: usize,
+    }
+
+    impl Read for Failing {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.ok == 0 {
+                return Err(io::Error::new(ErrorKind::ConnectionReset, "reset"));
+            }
+            let n = buf.len().min(self.ok);
+            buf[..n].fill(b'x');
+            self.ok -= n;
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_limit_and_reuse_of_the_reader() {
+        let data: Vec<u8> = (0..=255).cycle().take(10_000).collect();
+        let mut cursor = Cursor::new(data.clone());
+        let out = cursor.prefetch_ref(5000, 300, |reader| {
+            let mut out = Vec::new();
+            reader.read_to_end(&mut out).unwrap();
+            assert_eq!(reader.current_limit(), 0);
+            out
+        });
+        assert_eq!(out, data[..5000]);
+        assert_eq!(cursor.position(), 5000);
+    }
+
+    #[test]
+    fn test_errors_follow_the_data_read_before_them() {
+        let mut reader = Failing { ok: 1000 };
+        let (data, kind) = reader.prefetch_ref(5000, 256, |reader| {
+            let mut out = Vec::new();
+            let err = reader.read_to_end(&mut out).unwrap_err();
+            assert!(reader.fill_buf().is_err());
+            (out, err.kind())
+        });
+        assert_eq!(data.len(), 1000);
+        assert_eq!(kind, ErrorKind::ConnectionReset);
+    }
+
+    #[test]
+    fn test_consumer_leaving_early() {
+        let mut cursor = Cursor::new(vec![0u8; 1 << 20]);
+        let first = cursor.prefetch_ref(u64::MAX, 4096, |reader| reader.fill_buf().unwrap().len());
+        assert_eq!(first, 4096);
+        assert!(cursor.position() <= 2 * 4096);
+    }
+}
@@ -0,0 +1,130 @@
+//! Reading length-prefixed blobs from a [`RefTake`] window.
+
+use std::io::{self, ErrorKind, Read};
+
+use crate::{LimitExceeded, RefTake};
+
+/// Byte order of a length prefix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Endian {
+    /// Most significant byte first (network byte order).
+    #[default]
+    Big,
+    /// Least significant byte first.
+    Little,
+}
+
+/// An unsigned integer type usable as a length prefix.
+pub trait LengthPrefix {
+    /// Width of the prefix in bytes.
+    const SIZE: usize;
+
+    /// Decodes a prefix of exactly `SIZE` bytes.
+    fn decode(bytes: &[u8], endian: Endian) -> u64;
+}
+
+macro_rules! length_prefix {
+    ($($ty:ty),*) => {
+        $(
+            impl LengthPrefix for $ty {
+                const SIZE: usize = size_of::<$ty>();
+
+                fn decode(bytes: &[u8], endian: Endian) -> u64 {
+                    let bytes = bytes.try_into().expect("prefix has the width of the type");
+                    match endian {
+                        Endian::Big => <$ty>::from_be_bytes(bytes) as u64,
+                        Endian::Little => <$ty>::from_le_bytes(bytes) as u64,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+length_prefix!(u8, u16, u32, u64);
+
+impl<R: Read> RefTake<'_, R> {
+    /// Reads a big-endian length prefix of type `P`, then that many bytes of payload.
+    ///
+    /// See [`read_length_prefixed_with`](Self::read_length_prefixed_with) for details.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use reftake::RefTakeExt;
+    ///
+    /// let mut cursor = Cursor::new(b"\x00\x05hello\x00\x03abc");
+    /// let mut take = cursor.take_ref(7);
+    /// assert_eq!(take.read_length_prefixed::<u16>(1024).unwrap(), b"hello");
+    /// ```
+    pub fn read_length_prefixed<P: LengthPrefix>(&mut self, max_len: u64) -> io::Result<Vec<u8>> {
+        self.read_length_prefixed_with::<P>(Endian::Big, max_len)
+    }
+
+    /// Reads a length prefix of type `P` in the given byte order, then that many bytes of payload.
+    ///
+    /// The length is checked before anything is allocated: a length above
+    /// `max_len` fails with a [`LimitExceeded`] error, and one that exceeds
+    /// the rest of the window fails with `ErrorKind::UnexpectedEof`. In both
+    /// cases only the prefix has been consumed.
+    pub fn read_length_prefixed_with<P: LengthPrefix>(
+        &mut self,
+        endian: Endian,
+        max_len: u64,
+    ) -> io::Result<Vec<u8>> {
+        let mut prefix = [0u8; 8];
+        self.read_exact(&mut prefix[..P::SIZE])?;
+        let len = P::decode(&prefix[..P::SIZE], endian);
+        if len > max_len {
+            return Err(LimitExceeded::new(max_len).into());
+        }
+        if len > self.current_limit() {
+            return Err(io::Error::new(
+                ErrorKind::UnexpectedEof,
+                format!(
+                    "length prefix {len} exceeds the {} bytes left in window",
+                    self.current_limit()
+                ),
+            ));
+        }
+        self.read_exact_vec(len as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RefTakeExt;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_little_endian_u32_prefix() {
+        let mut reader = Cursor::new(b"\x03\x00\x00\x00abcdef");
+        let mut take = reader.take_ref(10);
+        let payload = take
+            .read_length_prefixed_with::<u32>(Endian::Little, 16)
+            .unwrap();
+        assert_eq!(payload, b"abc");
+        assert_eq!(take.current_limit(), 3);
+    }
+
+    #[test]
+    fn test_length_above_max() {
+        let mut reader = Cursor::new(b"\xffpayload");
+        let mut take = reader.take_ref(100);
+        let err = take.read_length_prefixed::<u8>(16).unwrap_err();
+        assert_eq!(LimitExceeded::from_io(&err), Some(&LimitExceeded::new(16)));
+        assert_eq!(take.current_limit(), 99);
+    }
+
+    #[test]
+    fn test_length_beyond_window() {
+        let mut reader = Cursor::new(b"\x00\x08abcdefgh");
+        let mut take = reader.take_ref(6);
+        let err = take.read_length_prefixed::<u16>(64).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+        assert_eq!(take.current_limit(), 4);
+    }
+}
@@ -0,0 +1,91 @@
+//! Re-exports of the extension traits and the most used adapters.
+//!
+//! A glob import brings every `*_ref` method into scope at once, along with
+//! the adapter and error types most code names explicitly:
+//!
+//! ```
+//! use std::io::{Cursor, Read};
+//! use reftake::prelude::*;
+//!
+//! let mut cursor = Cursor::new(b"0123456789");
+//! let mut head = cursor.take_ref(4);
+//! let mut tail = head.skip_ref(1);
+//! let mut buf = Vec::new();
+//! tail.read_to_end(&mut buf).unwrap();
+//! assert_eq!(buf, b"123");
+//! ```
+//!
+//! Only the traits and types of the enabled features are included.
+
+pub use crate::{LimitExceeded, Limited, LimitedMut, RefTake};
+
+#[cfg(feature = "std")]
+pub use crate::{FrameReader, RefChain, RefGuard, RefSkip, RefTakeWrite, RefTee, RefWindow};
+
+#[cfg(feature = "std")]
+pub use crate::{
+    BoundedLinesExt, BoundedSplitExt, BudgetReaderExt, Buffered, CharsExt, ChunkedDecoderExt,
+    ChunksExt, FrameReaderExt, LimitedBufReaderExt, LineLimitedExt, MultipartReaderExt,
+    NetstringReaderExt, PaddedTakeExt, PemReaderExt, RatioGuardExt, ReadAt, ReadAtRent, ReadVarint,
+    RecordsExt, RefChainExt, RefCountExt, RefCountWriteExt, RefDeadlineExt, RefDecodeTextExt,
+    RefDuplexLimitExt, RefEventsExt, RefFmtLimitExt, RefFuseExt, RefGuardExt, RefHexDumpExt,
+    RefHistoryExt, RefInspectExt, RefMinRateExt, RefMultiTakeExt, RefNormalizeNewlinesExt,
+    RefPeekExt, RefPrefetchExt, RefRecorderExt, RefSkipExt, RefStatsExt, RefStripBomExt,
+    RefTakeAlignedExt, RefTakeAtExt, RefTakeBufferedExt, RefTakeDynamicExt, RefTakeExt,
+    RefTakeUntilBoundaryExt, RefTakeUntilExt, RefTakeWhileExt, RefTakeWriteExt, RefTeeExt,
+    RefTeeWriteExt, RefThrottleExt, RefWindowExt, RentTakeAtExt, SeekTakeExt, SegmentedReaderExt,
+    SharedTakeAtExt, TlvReaderExt, Utf8ValidatorExt,
+};
+
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+pub use crate::MembersExt;
+
+#[cfg(feature = "acid_io")]
+pub use crate::AcidRefTakeExt;
+
+#[cfg(feature = "base64")]
+pub use crate::Base64DecoderExt;
+
+#[cfg(feature = "bytes")]
+pub use crate::BufRefTakeExt;
+
+#[cfg(feature = "crc32")]
+pub use crate::Crc32ReaderExt;
+
+#[cfg(feature = "digest")]
+pub use crate::HashingReaderExt;
+
+#[cfg(feature = "embedded-hal-nb")]
+pub use crate::SerialReaderExt;
+
+#[cfg(feature = "embedded-io")]
+pub use crate::{
+    EmbeddedIoRefChainExt, EmbeddedIoRefCountExt, EmbeddedIoRefSkipExt, EmbeddedIoRefTakeExt,
+};
+
+#[cfg(feature = "embedded-io-async")]
+pub use crate::EmbeddedRefTakeExt;
+
+#[cfg(feature = "futures-io")]
+pub use crate::{FuturesRefTakeExt, FuturesRefTakeWriteExt};
+
+#[cfg(feature = "http-body")]
+pub use crate::LimitedBodyExt;
+
+#[cfg(feature = "memmap2")]
+pub use crate::MmapWindowExt;
+
+#[cfg(feature = "monoio")]
+pub use crate::RentRefTakeExt;
+
+#[cfg(feature = "primitives")]
+pub use crate::ReadPrimitives;
+
+#[cfg(feature = "stream")]
+pub use crate::{AsyncByteStreamExt, AsyncFrameReaderExt, ByteStreamExt, StreamTakeExt};
+
+#[cfg(feature = "tokio")]
+pub use crate::{
+    AsyncRefTakeExt, AsyncRefTakeWriteExt, AsyncSeekTakeExt, AsyncThrottleExt, DynamicTakeExt,
+    MustDrainExt, PermitReaderExt, ResumableTakeExt, SyncReadBridgeExt,
+};
@@ -0,0 +1,128 @@
+//! Fixed-width integer and float readers (feature `primitives`).
+
+use std::io::{self, Read};
+
+macro_rules! read_primitive {
+    ($($(#[$doc:meta])* $name:ident => $ty:ty, $from:ident;)*) => {
+        $(
+            $(#[$doc])*
+            fn $name(&mut self) -> io::Result<$ty> {
+                let mut bytes = [0u8; size_of::<$ty>()];
+                self.read_exact(&mut bytes)?;
+                Ok(<$ty>::$from(bytes))
+            }
+        )*
+    };
+}
+
+/// Extension trait for reading fixed-width primitives from any `Read`,
+/// including a [`RefTake`](crate::RefTake) window.
+///
+/// Each method reads exactly `size_of::<T>()` bytes. A reader or window that
+/// ends first yields `ErrorKind::UnexpectedEof`; errors from the reader
+/// itself, such as a [`LimitExceeded`](crate::LimitExceeded) reported by a
+/// [`RefGuard`](crate::RefGuard), are returned unchanged.
+///
+/// # Example
+///
+/// ```
+/// use std::io::{Cursor, ErrorKind};
+/// use reftake::{ReadPrimitives, RefTakeExt};
+///
+/// let mut cursor = Cursor::new([0x01, 0x02, 0x03, 0x04, 0x05, 0x06]);
+/// let mut header = cursor.take_ref(5);
+/// assert_eq!(header.read_u16_be().unwrap(), 0x0102);
+/// assert_eq!(header.read_u8().unwrap(), 0x03);
+/// let err = header.read_u32_le().unwrap_err();
+/// assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+/// ```
+pub trait ReadPrimitives: Read {
+    read_primitive! {
+        /// Reads a `u8`.
+        read_u8 => u8, from_le_bytes;
+        /// Reads an `i8`.
+        read_i8 => i8, from_le_bytes;
+        /// Reads a little-endian `u16`.
+        read_u16_le => u16, from_le_bytes;
+        /// Reads a big-endian `u16`.
+        read_u16_be => u16, from_be_bytes;
+        /// Reads a little-endian `i16`.
+        read_i16_le => i16, from_le_bytes;
+        /// Reads a big-endian `i16`.
+        read_i16_be => i16, from_be_bytes;
+        /// Reads a little-endian `u32`.
+        read_u32_le => u32, from_le_bytes;
+        /// Reads a big-endian `u32`.
+        read_u32_be => u32, from_be_bytes;
+        /// Reads a little-endian `i32`.
+        read_i32_le => i32, from_le_bytes;
+        /// Reads a big-endian `i32`.
+        read_i32_be => i32, from_be_bytes;
+        /// Reads a little-endian `u64`.
+        read_u64_le => u64, from_le_bytes;
+        /// Reads a big-endian `u64`.
+        read_u64_be => u64, from_be_bytes;
+        /// Reads a little-endian `i64`.
+        read_i64_le => i64, from_le_bytes;
+        /// Reads a big-endian `i64`.
+        read_i64_be => i64, from_be_bytes;
+        /// Reads a little-endian `u128`.
+        read_u128_le => u128, from_le_bytes;
+        /// Reads a big-endian `u128`.
+        read_u128_be => u128, from_be_bytes;
+        /// Reads a little-endian `i128`.
+        read_i128_le => i128, from_le_bytes;
+        /// Reads a big-endian `i128`.
+        read_i128_be => i128, from_be_bytes;
+        /// Reads a little-endian IEEE 754 `f32`.
+        read_f32_le => f32, from_le_bytes;
+        /// Reads a big-endian IEEE 754 `f32`.
+        read_f32_be => f32, from_be_bytes;
+        /// Reads a little-endian IEEE 754 `f64`.
+        read_f64_le => f64, from_le_bytes;
+        /// Reads a big-endian IEEE 754 `f64`.
+        read_f64_be => f64, from_be_bytes;
+    }
+}
+
+impl<R: Read + ?Sized> ReadPrimitives for R {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LimitExceeded, RefGuardExt, RefTakeExt};
+    use std::io::{Cursor, ErrorKind};
+
+    #[test]
+    fn test_both_endiannesses() {
+        let mut reader = Cursor::new([0x12, 0x34, 0x56, 0x78, 0x12, 0x34, 0x56, 0x78]);
+        assert_eq!(reader.read_u32_le().unwrap(), 0x7856_3412);
+        assert_eq!(reader.read_u32_be().unwrap(), 0x1234_5678);
+    }
+
+    #[test]
+    fn test_signed_and_float() {
+        let mut data = Vec::new();
+        data.extend_from_slice(&(-2i64).to_be_bytes());
+        data.extend_from_slice(&1.5f64.to_le_bytes());
+        let mut reader = Cursor::new(data);
+        assert_eq!(reader.read_i64_be().unwrap(), -2);
+        assert_eq!(reader.read_f64_le().unwrap(), 1.5);
+    }
+
+    #[test]
+    fn test_window_too_small() {
+        let mut reader = Cursor::new([0u8; 8]);
+        let mut take = reader.take_ref(3);
+        let err = take.read_u32_le().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_guard_error_passes_through() {
+        let mut reader = Cursor::new([0u8; 8]);
+        let mut guard = reader.guard_ref(3);
+        let err = guard.read_u64_le().unwrap_err();
+        assert!(LimitExceeded::from_io(&err).is_some());
+    }
+}
@@ -0,0 +1,195 @@
+//! Capturing the output of a child process with a size cap per stream.
+
+use std::{
+    io::{self, Read},
+    panic,
+    process::{Child, ExitStatus},
+    thread,
+};
+
+use crate::RefTake;
+
+/// The output of a child process, as captured by [`wait_with_output_limited`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LimitedOutput {
+    /// The exit status of the process.
+    pub status: ExitStatus,
+    /// Up to the stdout limit of bytes the process wrote to stdout.
+    pub stdout: Vec<u8>,
+    /// Up to the stderr limit of bytes the process wrote to stderr.
+    pub stderr: Vec<u8>,
+    /// `true` if the process wrote more to stdout than its limit.
+    pub stdout_truncated: bool,
+    /// `true` if the process wrote more to stderr than its limit.
+    pub stderr_truncated: bool,
+}
+
+/// Waits for a child process to exit, collecting at most `stdout_limit`
+/// and `stderr_limit` bytes of its output.
+///
+/// The capped counterpart of `Child::wait_with_output`: stdin is closed
+/// first, then stdout and stderr, where they were piped, are read at the
+/// same time, stderr on a scoped thread, each through a [`RefTake`] of its
+/// limit. Once a limit is reached, one more byte is read to tell whether
+/// there was more, and the pipe is closed, so a chatty process can neither
+/// grow the parent's memory nor keep the parent reading forever: its next
+/// write fails with `EPIPE`, or kills it with `SIGPIPE`, and the stream is
+/// flagged as truncated.
+///
+/// If reading a stream fails, the child is killed and waited for before
+/// the error is returned, so it isn't left behind as a zombie.
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(unix)] {
+/// use std::process::{Command, Stdio};
+///
+/// let mut child = Command::new("sh")
+///     .args(["-c", "echo 'all is well'; yes error | head -n 100000 >&2"])
+///     .stdout(Stdio::piped())
+///     .stderr(Stdio::piped())
+///     .spawn()
+///     .unwrap();
+///
+/// let output = reftake::wait_with_output_limited(&mut child, 1024, 12).unwrap();
+/// assert_eq!(output.stdout, b"all is well\n");
+/// assert!(!output.stdout_truncated);
+/// assert_eq!(output.stderr, b"error\nerror\n");
+/// assert!(output.stderr_truncated);
+/// # }
+/// ```
+pub fn wait_with_output_limited(
+    child: &mut Child,
+    stdout_limit: u64,
+    stderr_limit: u64,
+) -> io::Result<LimitedOutput> {
+    // Closes stdin, so a child reading it sees EOF
+    let _ = child.stdin.take();
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+
+    let (stdout, stderr) = thread::scope(|scope| {
+        let stderr = scope.spawn(|| capture(stderr, stderr_limit));
+        let stdout = capture(stdout, stdout_limit);
+        let stderr = stderr.join().unwrap_or_else(|e| panic::resume_unwind(e));
+        (stdout, stderr)
+    });
+    let ((stdout, stdout_truncated), (stderr, stderr_truncated)) = match (stdout, stderr) {
+        (Ok(stdout), Ok(stderr)) => (stdout, stderr),
+        (Err(e), _) | (_, Err(e)) => {
+            // The child may have exited already, and the read error is the one to report
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(e);
+        }
+    };
+
+    Ok(LimitedOutput {
+        status: child.wait()?,
+        stdout,
+        stderr,
+        stdout_truncated,
+        stderr_truncated,
+    })
+}
+
+/// Reads up to `limit` bytes of a pipe and closes it, returning whether there was more.
+fn capture(pipe: Option<impl Read>, limit: u64) -> io::Result<(Vec<u8>, bool)> {
+    let Some(mut pipe) = pipe else {
+        return Ok((Vec::new(), false));
+    };
+    let (data, _) = RefTake::wrap(&mut pipe, limit).read_all_remaining()?;
+    let mut next = [0u8; 1];
+    let more = loop {
+        match pipe.read(&mut next) {
+            Ok(n) => break n > 0,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    };
+    Ok((data, more))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::{
+        io::Write,
+        process::{Command, Stdio},
+    };
+
+    fn spawn(script: &str) -> Child {
+        Command::new("sh")
+            .args(["-c", script])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_both_full_pipes_do_not_deadlock() {
+        let mut child = spawn("head -c 300000 /dev/zero; head -c 300000 /dev/zero >&2; exit 3");
+        let output = wait_with_output_limited(&mut child, 100, 200_000).unwrap();
+
+        assert_eq!(output.status.code(), Some(3));
+        assert_eq!(output.stdout.len(), 100);
+        assert_eq!(output.stderr.len(), 200_000);
+        assert!(output.stdout_truncated && output.stderr_truncated);
+    }
+
+    #[test]
+    fn test_truncation_is_reported_per_stream() {
+        let mut child = spawn("cat; printf err >&2");
+        child.stdin.as_mut().unwrap().write_all(b"ignored").unwrap();
+        let output = wait_with_output_limited(&mut child, 0, 3).unwrap();
+
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"");
+        assert!(output.stdout_truncated);
+        assert_eq!(output.stderr, b"err");
+        assert!(!output.stderr_truncated);
+
+        let mut child = Command::new("true").spawn().unwrap();
+        let output = wait_with_output_limited(&mut child, 0, 0).unwrap();
+        assert!(output.stdout.is_empty() && !output.stdout_truncated);
+    }
+
+    #[test]
+    fn test_endless_output_does_not_hang() {
+        let mut child = spawn("yes; exit 5");
+        let output = wait_with_output_limited(&mut child, 8, 8).unwrap();
+
+        assert_eq!(output.stdout, b"y\ny\ny\ny\n");
+        assert!(output.stdout_truncated);
+        assert!(!output.stderr_truncated);
+        // `yes` is stopped by the closed pipe, and the script goes on
+        assert_eq!(output.status.code(), Some(5));
+    }
+
+    #[test]
+    fn test_capture_stops_one_byte_past_the_limit() {
+        struct Failing;
+
+        impl Read for Failing {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::other("broken pipe reader"))
+            }
+        }
+
+        assert_eq!(
+            capture(Some(Failing), 4).unwrap_err().to_string(),
+            "broken pipe reader"
+        );
+        assert_eq!(
+            capture(Some(&b"abcdef"[..]), 4).unwrap(),
+            (b"abcd".to_vec(), true)
+        );
+        assert_eq!(
+            capture(Some(&b"abcd"[..]), 4).unwrap(),
+            (b"abcd".to_vec(), false)
+        );
+    }
+}
@@ -0,0 +1,185 @@
+//! Progress reports from a [`RefTake`] window, for progress bars.
+
+use std::{
+    cmp,
+    io::{self, BufRead, Read},
+    time::{Duration, Instant},
+};
+
+use crate::RefTake;
+
+/// How often [`RefProgress`] calls its hook.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgressInterval {
+    /// Once at least this many bytes have been read since the last call.
+    Bytes(u64),
+    /// Once at least this much time has passed since the last call.
+    Time(Duration),
+}
+
+/// A [`RefTake`] that calls a hook with the number of bytes read and the original limit as it is read.
+///
+/// Created by [`RefTake::on_progress`]. The hook is called after a read
+/// at the configured [`ProgressInterval`], so a progress bar over a long
+/// copy or download stays current without redrawing on every small read,
+/// and once more when the window is read to its end, by reaching the limit
+/// or EOF, so the last call always reports the final count.
+pub struct RefProgress<'a, R, F> {
+    take: RefTake<'a, R>,
+    f: F,
+    interval: ProgressInterval,
+    original_limit: u64,
+    bytes_read: u64,
+    reported_bytes: u64,
+    reported_at: Instant,
+    finished: bool,
+}
+
+impl<'a, R> RefTake<'a, R> {
+    /// Calls `f(bytes_read, original_limit)` as the window is read, at the given interval.
+    ///
+    /// `original_limit` is the limit of the window when this is called.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{self, Cursor};
+    /// use reftake::{ProgressInterval, RefTakeExt};
+    ///
+    /// let mut download = Cursor::new(vec![0u8; 100_000]);
+    /// let mut reports = Vec::new();
+    /// let mut body = download
+    ///     .take_ref(50_000)
+    ///     .on_progress(ProgressInterval::Bytes(16 * 1024), |read, total| {
+    ///         reports.push(read * 100 / total)
+    ///     });
+    /// io::copy(&mut body, &mut io::sink()).unwrap();
+    /// assert_eq!(reports.last(), Some(&100));
+    /// ```
+    pub fn on_progress<F>(self, interval: ProgressInterval, f: F) -> RefProgress<'a, R, F>
+    where
+        F: FnMut(u64, u64),
+    {
+        RefProgress {
+            original_limit: self.current_limit(),
+            take: self,
+            f,
+            interval,
+            bytes_read: 0,
+            reported_bytes: 0,
+            reported_at: Instant::now(),
+            finished: false,
+        }
+    }
+}
+
+impl<'a, R, F: FnMut(u64, u64)> RefProgress<'a, R, F> {
+    /// Returns the number of bytes read so far.
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read
+    }
+
+    /// Returns the number of bytes left to read before the end of the window.
+    pub fn current_limit(&self) -> u64 {
+        self.take.current_limit()
+    }
+
+    /// Returns the window, without the hook.
+    pub fn into_inner(self) -> RefTake<'a, R> {
+        self.take
+    }
+
+    /// Counts `n` bytes read, `0` meaning the end of the window, and calls the hook if it is due.
+    fn advance(&mut self, n: usize) {
+        if self.finished {
+            return;
+        }
+        self.bytes_read += n as u64;
+        let end = n == 0 || self.take.current_limit() == 0;
+        let due = end
+            || match self.interval {
+                ProgressInterval::Bytes(step) => self.bytes_read - self.reported_bytes >= step,
+                ProgressInterval::Time(period) => self.reported_at.elapsed() >= period,
+            };
+        if due {
+            (self.f)(self.bytes_read, self.original_limit);
+            self.reported_bytes = self.bytes_read;
+            self.reported_at = Instant::now();
+            self.finished = end;
+        }
+    }
+}
+
+impl<R: Read, F: FnMut(u64, u64)> Read for RefProgress<'_, R, F> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        let n = self.take.read(buf)?;
+        if n > 0 || !buf.is_empty() {
+            self.advance(n);
+        }
+        Ok(n)
+    }
+}
+
+impl<R: BufRead, F: FnMut(u64, u64)> BufRead for RefProgress<'_, R, F> {
+    fn fill_buf(&mut self) -> Result<&[u8], io::Error> {
+        if self.take.fill_buf()?.is_empty() {
+            self.advance(0);
+        }
+        self.take.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        let amt = cmp::min(amt as u64, self.take.current_limit()) as usize;
+        self.take.consume(amt);
+        if amt > 0 {
+            self.advance(amt);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RefTakeExt;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_reports_at_byte_interval_and_at_the_end() {
+        let mut reader = Cursor::new(vec![1u8; 100]);
+        let mut reports = Vec::new();
+        let mut progress = reader
+            .take_ref(45)
+            .on_progress(ProgressInterval::Bytes(20), |read, total| {
+                reports.push((read, total))
+            });
+        let mut buf = [0u8; 10];
+        while progress.read(&mut buf).unwrap() > 0 {}
+        assert_eq!(progress.bytes_read(), 45);
+        assert_eq!(progress.read(&mut [0u8; 4]).unwrap(), 0);
+        assert_eq!(reports, [(20, 45), (40, 45), (45, 45)]);
+
+        // A source shorter than the window is reported when it ends
+        let mut reader = Cursor::new(vec![1u8; 30]);
+        let mut reports = Vec::new();
+        let mut progress = reader.take_ref(45).on_progress(
+            ProgressInterval::Time(Duration::from_secs(3600)),
+            |read, _| reports.push(read),
+        );
+        let mut out = Vec::new();
+        progress.read_to_end(&mut out).unwrap();
+        assert_eq!(reports, [30]);
+    }
+
+    #[test]
+    fn test_bufread_consumption_is_reported() {
+        let mut reader = Cursor::new(b"line one\nline two\n".to_vec());
+        let mut reports = Vec::new();
+        let mut progress = reader
+            .take_ref(18)
+            .on_progress(ProgressInterval::Bytes(1), |read, _| reports.push(read));
+        let mut line = String::new();
+        progress.read_line(&mut line).unwrap();
+        progress.read_line(&mut line).unwrap();
+        assert_eq!(reports, [9, 18]);
+    }
+}
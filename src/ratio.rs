@@ -0,0 +1,222 @@
+//! A decompression-bomb guard bounding both the output and its expansion ratio.
+
+use std::io::{self, Read};
+
+use crate::{LimitExceeded, RatioExceeded};
+
+/// Ceilings enforced by a [`RatioGuard`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RatioLimits {
+    /// Most decompressed bytes that may be produced in total.
+    pub max_output: u64,
+    /// Most decompressed bytes allowed per compressed byte.
+    pub max_ratio: u64,
+    /// Output size up to which the ratio is not checked, since headers and
+    /// small inputs say little about the ratio of the whole stream.
+    pub ratio_grace: u64,
+}
+
+impl Default for RatioLimits {
+    /// Up to 1 GiB of output at a ratio of at most 100, checked from the first MiB on.
+    fn default() -> Self {
+        Self {
+            max_output: 1 << 30,
+            max_ratio: 100,
+            ratio_grace: 1 << 20,
+        }
+    }
+}
+
+/// A non-owning adapter over a decompressing reader that aborts on
+/// decompression bombs.
+///
+/// After every read the decompressed bytes produced so far are compared
+/// with the limits: past `max_output` the read fails with a
+/// [`LimitExceeded`] error, and once past `ratio_grace`, more than
+/// `max_ratio` decompressed bytes per compressed byte fails it with a
+/// [`RatioExceeded`] error. The compressed byte count is asked of the
+/// decoder through `compressed_in`, such as `total_in()` for the `flate2`
+/// zlib and deflate decoders, or the [`RefCount`](crate::RefCount) the
+/// decoder reads from. The bytes of a failing read are not returned.
+pub struct RatioGuard<'a, D, F> {
+    inner: &'a mut D,
+    compressed_in: F,
+    limits: RatioLimits,
+    decompressed: u64,
+}
+
+impl<'a, D, F> RatioGuard<'a, D, F>
+where
+    F: FnMut(&D) -> u64,
+{
+    /// Creates a new `RatioGuard` over the given decoder reference.
+    pub const fn wrap(inner: &'a mut D, limits: RatioLimits, compressed_in: F) -> Self {
+        Self {
+            inner,
+            compressed_in,
+            limits,
+            decompressed: 0,
+        }
+    }
+
+    /// Returns the number of decompressed bytes read so far.
+    pub fn bytes_read(&self) -> u64 {
+        self.decompressed
+    }
+
+    /// Returns the limits being enforced.
+    pub fn limits(&self) -> &RatioLimits {
+        &self.limits
+    }
+}
+
+impl<D: Read, F: FnMut(&D) -> u64> Read for RatioGuard<'_, D, F> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        let n = self.inner.read(buf)?;
+        let decompressed = self.decompressed + n as u64;
+        let limits = &self.limits;
+        if decompressed > limits.max_output {
+            trace_event!(
+                warn,
+                max = limits.max_output,
+                "decompressed size limit exceeded"
+            );
+            return Err(LimitExceeded::new(limits.max_output).into());
+        }
+        if decompressed > limits.ratio_grace {
+            let compressed = (self.compressed_in)(self.inner);
+            if decompressed > compressed.saturating_mul(limits.max_ratio) {
+                trace_event!(warn, compressed, decompressed, "expansion ratio exceeded");
+                return Err(RatioExceeded::new(compressed, decompressed, limits.max_ratio).into());
+            }
+        }
+        self.decompressed = decompressed;
+        Ok(n)
+    }
+}
+
+/// Extension trait to provide a `ratio_guard_ref` method on all `Read` types.
+pub trait RatioGuardExt {
+    /// Wraps the decoder in a `RatioGuard` enforcing `limits`, with
+    /// `compressed_in` returning the compressed bytes it has read.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(feature = "gzip")] {
+    /// use std::io::{Read, Write};
+    /// use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+    /// use reftake::{RatioExceeded, RatioGuardExt, RatioLimits, RefCountExt};
+    ///
+    /// let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+    /// encoder.write_all(&vec![0u8; 1 << 20]).unwrap();
+    /// let bomb = encoder.finish().unwrap();
+    ///
+    /// let mut source = &bomb[..];
+    /// let mut decoder = GzDecoder::new(source.count_ref());
+    /// let limits = RatioLimits { ratio_grace: 4096, ..RatioLimits::default() };
+    /// let mut guard = decoder.ratio_guard_ref(limits, |d| d.get_ref().bytes_read());
+    ///
+    /// let err = guard.read_to_end(&mut Vec::new()).unwrap_err();
+    /// assert!(RatioExceeded::from_io(&err).is_some());
+    /// # }
+    /// ```
+    fn ratio_guard_ref<F>(
+        &mut self,
+        limits: RatioLimits,
+        compressed_in: F,
+    ) -> RatioGuard<'_, Self, F>
+    where
+        Self: Sized,
+        F: FnMut(&Self) -> u64;
+}
+
+impl<T: Read> RatioGuardExt for T {
+    fn ratio_guard_ref<F>(
+        &mut self,
+        limits: RatioLimits,
+        compressed_in: F,
+    ) -> RatioGuard<'_, Self, F>
+    where
+        F: FnMut(&Self) -> u64,
+    {
+        RatioGuard::wrap(self, limits, compressed_in)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Expands every input byte into `factor` copies of it.
+    struct Expand<'a> {
+        input: &'a [u8],
+        factor: usize,
+        total_in: u64,
+    }
+
+    impl Read for Expand<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let Some((&byte, rest)) = self.input.split_first() else {
+                return Ok(0);
+            };
+            let n = self.factor.min(buf.len());
+            buf[..n].fill(byte);
+            self.input = rest;
+            self.total_in += 1;
+            Ok(n)
+        }
+    }
+
+    fn limits(max_output: u64) -> RatioLimits {
+        RatioLimits {
+            max_output,
+            max_ratio: 10,
+            ratio_grace: 20,
+        }
+    }
+
+    #[test]
+    fn test_ratio_is_checked_after_the_grace() {
+        let input = [1u8; 10];
+        let mut decoder = Expand {
+            input: &input,
+            factor: 8,
+            total_in: 0,
+        };
+        let mut out = Vec::new();
+        let mut guard = decoder.ratio_guard_ref(limits(1000), |d| d.total_in);
+        guard.read_to_end(&mut out).unwrap();
+        assert_eq!(guard.bytes_read(), 80);
+
+        let mut decoder = Expand {
+            input: &input,
+            factor: 16,
+            total_in: 0,
+        };
+        let mut guard = decoder.ratio_guard_ref(limits(1000), |d| d.total_in);
+        let err = guard.read_to_end(&mut Vec::new()).unwrap_err();
+        assert_eq!(
+            RatioExceeded::from_io(&err),
+            Some(&RatioExceeded::new(2, 32, 10))
+        );
+    }
+
+    #[test]
+    fn test_absolute_output_ceiling() {
+        let input = [1u8; 10];
+        let mut decoder = Expand {
+            input: &input,
+            factor: 4,
+            total_in: 0,
+        };
+        let mut guard = decoder.ratio_guard_ref(limits(30), |d| d.total_in);
+        let mut buf = [0u8; 4];
+        for _ in 0..7 {
+            guard.read_exact(&mut buf).unwrap();
+        }
+        let err = guard.read(&mut buf).unwrap_err();
+        assert_eq!(LimitExceeded::from_io(&err), Some(&LimitExceeded::new(30)));
+        assert_eq!(guard.bytes_read(), 28);
+    }
+}
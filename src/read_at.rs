@@ -0,0 +1,592 @@
+//! Bounded windows over positioned reads.
+
+use std::{
+    cmp,
+    io::{self, ErrorKind, IoSliceMut, Read},
+    mem,
+    sync::Arc,
+};
+
+use crate::WindowState;
+
+/// Segments at most this many bytes apart are read by the same call.
+const MAX_GAP: u64 = 4096;
+
+/// A source that can be read at any offset through a shared reference.
+///
+/// This is the shape of the platform `FileExt::read_at` (unix) and
+/// `FileExt::seek_read` (windows) methods, which [`File`](std::fs::File) delegates to.
+/// Reads do not depend on, or go through, a shared cursor, so any number
+/// of windows can read the same source at once.
+pub trait ReadAt {
+    /// Reads bytes starting at `offset` into `buf`, returning how many were read.
+    ///
+    /// `Ok(0)` means `offset` is at or past the end of the source.
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize>;
+
+    /// Like [`read_at`](ReadAt::read_at), except that it fills the buffers
+    /// of `bufs` one after the other with the bytes starting at `offset`.
+    ///
+    /// The default implementation reads into the first non-empty buffer
+    /// only. `File` reads into all of them with one `preadv` call on Linux
+    /// when the `preadv` feature is enabled.
+    fn read_vectored_at(&self, bufs: &mut [IoSliceMut<'_>], offset: u64) -> io::Result<usize> {
+        let buf = bufs
+            .iter_mut()
+            .find(|buf| !buf.is_empty())
+            .map_or(&mut [][..], |buf| &mut **buf);
+        self.read_at(buf, offset)
+    }
+}
+
+impl<T: ReadAt + ?Sized> ReadAt for &T {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        (**self).read_at(buf, offset)
+    }
+
+    fn read_vectored_at(&self, bufs: &mut [IoSliceMut<'_>], offset: u64) -> io::Result<usize> {
+        (**self).read_vectored_at(bufs, offset)
+    }
+}
+
+impl<T: ReadAt + ?Sized> ReadAt for Arc<T> {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        (**self).read_at(buf, offset)
+    }
+
+    fn read_vectored_at(&self, bufs: &mut [IoSliceMut<'_>], offset: u64) -> io::Result<usize> {
+        (**self).read_vectored_at(bufs, offset)
+    }
+}
+
+impl ReadAt for [u8] {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        let start = cmp::min(offset, self.len() as u64) as usize;
+        let n = cmp::min(buf.len(), self.len() - start);
+        buf[..n].copy_from_slice(&self[start..start + n]);
+        Ok(n)
+    }
+}
+
+#[cfg(unix)]
+impl ReadAt for std::fs::File {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        std::os::unix::fs::FileExt::read_at(self, buf, offset)
+    }
+
+    #[cfg(all(target_os = "linux", feature = "preadv"))]
+    fn read_vectored_at(&self, bufs: &mut [IoSliceMut<'_>], offset: u64) -> io::Result<usize> {
+        use std::os::fd::AsRawFd;
+
+        // UIO_MAXIOV; the buffers past it are left for the next call
+        const IOV_MAX: usize = 1024;
+        let offset = libc::off_t::try_from(offset)
+            .map_err(|_| io::Error::new(ErrorKind::InvalidInput, "offset out of range"))?;
+        let count = cmp::min(bufs.len(), IOV_MAX) as libc::c_int;
+        // SAFETY: `IoSliceMut` is ABI compatible with `iovec` on unix, and
+        // every buffer is valid for writes of its length during the call
+        let n = unsafe {
+            libc::preadv(
+                self.as_raw_fd(),
+                bufs.as_ptr().cast::<libc::iovec>(),
+                count,
+                offset,
+            )
+        };
+        if n < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(n as usize)
+    }
+}
+
+/// Note that on windows the file cursor is moved by each read.
+#[cfg(windows)]
+impl ReadAt for std::fs::File {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        std::os::windows::fs::FileExt::seek_read(self, buf, offset)
+    }
+}
+
+/// A non-owning adapter exposing the `len` bytes of a [`ReadAt`] source
+/// starting at `offset` as a `Read` stream.
+///
+/// Unlike a [`RefTake`](crate::RefTake), it only needs a shared reference
+/// to the source, and the window keeps its own position, so a file can be
+/// read through several windows without seeking it or borrowing it mutably.
+/// A source shorter than the window simply ends early.
+#[derive(Debug)]
+pub struct RefTakeAt<'a, F: ?Sized> {
+    inner: &'a F,
+    offset: u64,
+    limit: u64,
+}
+
+impl<'a, F: ?Sized> RefTakeAt<'a, F> {
+    /// Creates a new `RefTakeAt` over the `len` bytes of the given source starting at `offset`.
+    pub const fn wrap(inner: &'a F, offset: u64, len: u64) -> Self {
+        Self {
+            inner,
+            offset,
+            limit: len,
+        }
+    }
+
+    /// Returns the offset in the source the next read starts at.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Returns the number of bytes left to read before the end of the window.
+    pub fn current_limit(&self) -> u64 {
+        self.limit
+    }
+
+    /// Returns the source reference.
+    pub fn get_ref(&self) -> &'a F {
+        self.inner
+    }
+
+    /// Re-attaches a saved window to `inner`, starting at the first byte not consumed.
+    ///
+    /// Fails with `ErrorKind::InvalidData` if the state consumed past the
+    /// end of the window.
+    pub fn resume(inner: &'a F, state: &WindowState) -> io::Result<Self> {
+        state.check()?;
+        Ok(Self::wrap(inner, state.position(), state.remaining()))
+    }
+
+    /// Returns where the window stands, given the `origin` offset it was created at.
+    pub fn state(&self, origin: u64) -> WindowState {
+        window_state(origin, self.offset, self.limit)
+    }
+}
+
+impl<F: ReadAt + ?Sized> Read for RefTakeAt<'_, F> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        read_window(self.inner, &mut self.offset, &mut self.limit, buf)
+    }
+}
+
+/// An owning counterpart of [`RefTakeAt`], holding its source in an [`Arc`].
+///
+/// Windows over the same `Arc<File>` (or any other shared [`ReadAt`]
+/// source) each track their own position within their range and need no
+/// lock, so they can be handed to different threads to read different
+/// parts of the file, such as archive entries, at the same time. Cloning a
+/// window clones the `Arc` and keeps the current position.
+#[derive(Debug)]
+pub struct SharedTakeAt<F: ?Sized> {
+    inner: Arc<F>,
+    offset: u64,
+    limit: u64,
+}
+
+impl<F: ?Sized> SharedTakeAt<F> {
+    /// Creates a new `SharedTakeAt` over the `len` bytes of the given source starting at `offset`.
+    pub const fn new(inner: Arc<F>, offset: u64, len: u64) -> Self {
+        Self {
+            inner,
+            offset,
+            limit: len,
+        }
+    }
+
+    /// Returns the offset in the source the next read starts at.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Returns the number of bytes left to read before the end of the window.
+    pub fn current_limit(&self) -> u64 {
+        self.limit
+    }
+
+    /// Returns the shared source.
+    pub fn get_ref(&self) -> &Arc<F> {
+        &self.inner
+    }
+
+    /// Consumes the window, returning the shared source.
+    pub fn into_inner(self) -> Arc<F> {
+        self.inner
+    }
+
+    /// Re-attaches a saved window to `inner`, starting at the first byte not consumed.
+    ///
+    /// Fails with `ErrorKind::InvalidData` if the state consumed past the
+    /// end of the window.
+    pub fn resume(inner: Arc<F>, state: &WindowState) -> io::Result<Self> {
+        state.check()?;
+        Ok(Self::new(inner, state.position(), state.remaining()))
+    }
+
+    /// Returns where the window stands, given the `origin` offset it was created at.
+    pub fn state(&self, origin: u64) -> WindowState {
+        window_state(origin, self.offset, self.limit)
+    }
+}
+
+/// Builds the state of a window created at `origin`, now at `offset` with `limit` bytes left.
+fn window_state(origin: u64, offset: u64, limit: u64) -> WindowState {
+    assert!(origin <= offset, "window origin past its position");
+    let consumed = offset - origin;
+    WindowState {
+        origin,
+        len: consumed + limit,
+        consumed,
+        frames: None,
+    }
+}
+
+impl<F: ?Sized> Clone for SharedTakeAt<F> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+            offset: self.offset,
+            limit: self.limit,
+        }
+    }
+}
+
+impl<F: ReadAt + ?Sized> Read for SharedTakeAt<F> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        read_window(&*self.inner, &mut self.offset, &mut self.limit, buf)
+    }
+}
+
+fn read_window<F: ReadAt + ?Sized>(
+    inner: &F,
+    offset: &mut u64,
+    limit: &mut u64,
+    buf: &mut [u8],
+) -> io::Result<usize> {
+    if *limit == 0 {
+        return Ok(0);
+    }
+
+    let max = cmp::min(buf.len() as u64, *limit) as usize;
+    let n = inner.read_at(&mut buf[..max], *offset)?;
+    assert!(n as u64 <= *limit, "number of read bytes exceeds limit");
+    *offset += n as u64;
+    *limit -= n as u64;
+    Ok(n)
+}
+
+/// Fills each buffer of `bufs` with the bytes of `source` starting at the matching offset of `offsets`.
+///
+/// Extracting many small segments, such as the entries of an archive
+/// index, one `read_at` per segment spends most of its time in system
+/// calls. The segments are sorted by offset and every run of them that
+/// are adjacent, or less than a page apart, is read with a single
+/// [`ReadAt::read_vectored_at`] call, the bytes between them going to a
+/// scratch buffer. With the `preadv` feature on Linux that is one
+/// `preadv` per run for a [`File`](std::fs::File). Other sources fall back
+/// to the default `read_vectored_at`, which fills one buffer per call, so
+/// they take a read per segment and another per gap, and still read the up
+/// to 4 KiB of each gap into the scratch buffer.
+///
+/// Fails with `ErrorKind::InvalidInput` if there is not one offset per
+/// buffer, and with `ErrorKind::UnexpectedEof` if the source ends before a
+/// buffer is filled, in which case the buffers hold unspecified data.
+///
+/// # Example
+///
+/// ```
+/// use std::io::IoSliceMut;
+///
+/// let archive: &[u8] = b"name=alpha;name=beta";
+/// let (mut first, mut second) = ([0u8; 5], [0u8; 4]);
+/// reftake::read_segments_into(
+///     archive,
+///     &mut [IoSliceMut::new(&mut second), IoSliceMut::new(&mut first)],
+///     &[16, 5],
+/// )
+/// .unwrap();
+/// assert_eq!((&first, &second), (b"alpha", b"beta"));
+/// ```
+pub fn read_segments_into<F: ReadAt + ?Sized>(
+    source: &F,
+    bufs: &mut [IoSliceMut<'_>],
+    offsets: &[u64],
+) -> io::Result<()> {
+    if bufs.len() != offsets.len() {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            "one offset is needed per buffer",
+        ));
+    }
+    let mut segments: Vec<(u64, &mut [u8])> = offsets
+        .iter()
+        .copied()
+        .zip(bufs.iter_mut().map(|buf| &mut **buf))
+        .collect();
+    segments.sort_by_key(|&(offset, _)| offset);
+
+    let mut segments = segments.into_iter().peekable();
+    let mut scratch = Vec::new();
+    while let Some((start, first)) = segments.next() {
+        // `(gap before, buffer)` of each segment of the run
+        let mut end = start.saturating_add(first.len() as u64);
+        let mut run = vec![(0, first)];
+        while let Some((offset, buf)) =
+            segments.next_if(|&(offset, _)| offset >= end && offset - end <= MAX_GAP)
+        {
+            let gap = (offset - end) as usize;
+            end = offset.saturating_add(buf.len() as u64);
+            run.push((gap, buf));
+        }
+
+        scratch.clear();
+        scratch.resize(run.iter().map(|&(gap, _)| gap).sum(), 0);
+        let mut spare = &mut scratch[..];
+        let mut iovecs = Vec::with_capacity(2 * run.len());
+        for (gap, buf) in run {
+            if gap > 0 {
+                let (skipped, rest) = mem::take(&mut spare).split_at_mut(gap);
+                iovecs.push(IoSliceMut::new(skipped));
+                spare = rest;
+            }
+            iovecs.push(IoSliceMut::new(buf));
+        }
+        read_exact_vectored_at(source, &mut iovecs, start)?;
+    }
+    Ok(())
+}
+
+fn read_exact_vectored_at<F: ReadAt + ?Sized>(
+    source: &F,
+    mut bufs: &mut [IoSliceMut<'_>],
+    mut offset: u64,
+) -> io::Result<()> {
+    IoSliceMut::advance_slices(&mut bufs, 0);
+    while !bufs.is_empty() {
+        match source.read_vectored_at(bufs, offset) {
+            Ok(0) => {
+                return Err(io::Error::new(
+                    ErrorKind::UnexpectedEof,
+                    "source ended before the segment",
+                ));
+            }
+            Ok(n) => {
+                offset += n as u64;
+                IoSliceMut::advance_slices(&mut bufs, n);
+            }
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+/// Extension trait to provide a `take_ref_at` method on all [`ReadAt`] sources.
+pub trait RefTakeAtExt: ReadAt {
+    /// Creates a `RefTakeAt` over the `len` bytes starting at `offset`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::Read;
+    /// use reftake::RefTakeAtExt;
+    ///
+    /// let archive: &[u8] = b"name=alpha;name=beta";
+    /// let mut first = archive.take_ref_at(5, 5);
+    /// let mut second = archive.take_ref_at(16, 4);
+    ///
+    /// let mut out = String::new();
+    /// second.read_to_string(&mut out).unwrap();
+    /// first.read_to_string(&mut out).unwrap();
+    /// assert_eq!(out, "betaalpha");
+    /// ```
+    fn take_ref_at(&self, offset: u64, len: u64) -> RefTakeAt<'_, Self> {
+        RefTakeAt::wrap(self, offset, len)
+    }
+}
+
+impl<T: ReadAt + ?Sized> RefTakeAtExt for T {}
+
+/// Extension trait to provide a `take_shared_at` method on shared [`ReadAt`] sources.
+pub trait SharedTakeAtExt<F: ?Sized> {
+    /// Creates a `SharedTakeAt` over the `len` bytes starting at `offset`,
+    /// holding its own clone of the `Arc`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::{io::Read, sync::Arc, thread};
+    /// use reftake::SharedTakeAtExt;
+    ///
+    /// let archive: Arc<[u8]> = Arc::from(&b"alphabetagamma"[..]);
+    /// let entries = [(0, 5), (5, 4), (9, 5)];
+    ///
+    /// let handles: Vec<_> = entries
+    ///     .iter()
+    ///     .map(|&(offset, len)| {
+    ///         let mut entry = archive.take_shared_at(offset, len);
+    ///         thread::spawn(move || {
+    ///             let mut out = String::new();
+    ///             entry.read_to_string(&mut out).unwrap();
+    ///             out
+    ///         })
+    ///     })
+    ///     .collect();
+    /// let names: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+    /// assert_eq!(names, ["alpha", "beta", "gamma"]);
+    /// ```
+    fn take_shared_at(&self, offset: u64, len: u64) -> SharedTakeAt<F>;
+}
+
+impl<F: ReadAt + ?Sized> SharedTakeAtExt<F> for Arc<F> {
+    fn take_shared_at(&self, offset: u64, len: u64) -> SharedTakeAt<F> {
+        SharedTakeAt::new(Arc::clone(self), offset, len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_stops_at_its_end_and_at_source_end() {
+        let data: &[u8] = b"0123456789";
+        let mut window = data.take_ref_at(2, 4);
+        let mut buf = [0u8; 3];
+        assert_eq!(window.read(&mut buf).unwrap(), 3);
+        assert_eq!(&buf, b"234");
+        assert_eq!(window.offset(), 5);
+        assert_eq!(window.read(&mut buf).unwrap(), 1);
+        assert_eq!(window.read(&mut buf).unwrap(), 0);
+
+        let mut out = Vec::new();
+        data.take_ref_at(8, 10).read_to_end(&mut out).unwrap();
+        assert_eq!(out, b"89");
+        out.clear();
+        data.take_ref_at(20, 10).read_to_end(&mut out).unwrap();
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn test_shared_windows_are_independent() {
+        let data: Arc<[u8]> = Arc::from(&b"0123456789"[..]);
+        let mut window = data.take_shared_at(3, 4);
+        let mut buf = [0u8; 2];
+        window.read_exact(&mut buf).unwrap();
+
+        let mut copy = window.clone();
+        let mut out = Vec::new();
+        copy.read_to_end(&mut out).unwrap();
+        assert_eq!(out, b"56");
+        assert_eq!(window.current_limit(), 2);
+        assert_eq!(Arc::strong_count(&data), 3);
+        drop(copy);
+        assert!(Arc::ptr_eq(&window.into_inner(), &data));
+    }
+
+    /// Fills every buffer of a vectored read, counting the calls, like `preadv`.
+    struct Vectored<'a> {
+        data: &'a [u8],
+        calls: std::cell::Cell<usize>,
+    }
+
+    impl ReadAt for Vectored<'_> {
+        fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+            self.read_vectored_at(&mut [IoSliceMut::new(buf)], offset)
+        }
+
+        fn read_vectored_at(&self, bufs: &mut [IoSliceMut<'_>], offset: u64) -> io::Result<usize> {
+            self.calls.set(self.calls.get() + 1);
+            let mut n = 0;
+            for buf in bufs {
+                let read = self.data.read_at(buf, offset + n as u64)?;
+                n += read;
+                if read < buf.len() {
+                    break;
+                }
+            }
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_nearby_segments_share_a_read() {
+        let data: Vec<u8> = (0..=255).cycle().take(20_000).collect();
+        let source = Vectored {
+            data: &data,
+            calls: std::cell::Cell::new(0),
+        };
+        let (mut a, mut b, mut c, mut d) = ([0u8; 3], [0u8; 4], [0u8; 5], [0u8; 0]);
+        let offsets = [10_000, 13, 100, 19_999];
+        let mut bufs = [
+            IoSliceMut::new(&mut a),
+            IoSliceMut::new(&mut b),
+            IoSliceMut::new(&mut c),
+            IoSliceMut::new(&mut d),
+        ];
+        read_segments_into(&source, &mut bufs, &offsets).unwrap();
+        assert_eq!(source.calls.get(), 2);
+        assert_eq!(a, data[10_000..10_003]);
+        assert_eq!(b, data[13..17]);
+        assert_eq!(c, data[100..105]);
+
+        let mut past = [0u8; 4];
+        let err = read_segments_into(&source, &mut [IoSliceMut::new(&mut past)], &[19_998]);
+        assert_eq!(err.unwrap_err().kind(), ErrorKind::UnexpectedEof);
+        let err = read_segments_into(&source, &mut [IoSliceMut::new(&mut past)], &[]);
+        assert_eq!(err.unwrap_err().kind(), ErrorKind::InvalidInput);
+    }
+
+    #[cfg(any(unix, windows))]
+    #[test]
+    fn test_file_segments() {
+        use std::{fs::File, io::Write};
+
+        let path = std::env::temp_dir().join(format!("reftake-segments-{}", std::process::id()));
+        let data: Vec<u8> = (0..=255).cycle().take(100_000).collect();
+        File::create(&path).unwrap().write_all(&data).unwrap();
+        let file = File::open(&path).unwrap();
+
+        let mut bufs: Vec<Vec<u8>> = (0..2000).map(|i| vec![0u8; i % 7]).collect();
+        let offsets: Vec<u64> = (0..2000u64).map(|i| (i * 7919) % 99_000).collect();
+        let mut slices: Vec<_> = bufs.iter_mut().map(|buf| IoSliceMut::new(buf)).collect();
+        read_segments_into(&file, &mut slices, &offsets).unwrap();
+        for (buf, &offset) in bufs.iter().zip(&offsets) {
+            assert_eq!(buf[..], data[offset as usize..offset as usize + buf.len()]);
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(any(unix, windows))]
+    #[test]
+    fn test_file_windows_share_the_file() {
+        use std::{fs::File, io::Write};
+
+        let path = std::env::temp_dir().join(format!("reftake-read-at-{}", std::process::id()));
+        File::create(&path)
+            .unwrap()
+            .write_all(b"header|first|second")
+            .unwrap();
+        let file = File::open(&path).unwrap();
+
+        let mut first = file.take_ref_at(7, 5);
+        let mut second = file.take_ref_at(13, 6);
+        let mut out = String::new();
+        second.read_to_string(&mut out).unwrap();
+        first.read_to_string(&mut out).unwrap();
+        assert_eq!(out, "secondfirst");
+
+        let file = Arc::new(file);
+        let handle = std::thread::spawn({
+            let mut entry = file.take_shared_at(0, 6);
+            move || {
+                let mut out = Vec::new();
+                entry.read_to_end(&mut out).unwrap();
+                out
+            }
+        });
+        out.clear();
+        file.take_shared_at(13, 6).read_to_string(&mut out).unwrap();
+        assert_eq!(handle.join().unwrap(), b"header");
+        assert_eq!(out, "second");
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,267 @@
+//! Bounded windows over completion-based positioned reads.
+
+use std::{cmp, future::Future, io, sync::Arc};
+
+/// A source read at any offset by lending it an owned buffer, as io_uring requires.
+///
+/// Completion-based I/O, such as `tokio_uring::fs::File::read_at`, takes
+/// the buffer for the duration of the operation and gives it back with the
+/// result, so the borrowed-buffer [`ReadAt`](crate::ReadAt) does not fit.
+/// Like those APIs, a read fills the buffer from its start and sets its
+/// length to the number of bytes read, here at most `len` of them, which
+/// doesn't exceed the capacity of the buffer; `Ok(0)` means `offset` is at
+/// or past the end of the source.
+///
+/// The buffer is always returned, including on error. With the
+/// `tokio-uring` feature, `tokio_uring::fs::File` implements this trait on
+/// Linux; other runtimes' file types are wrapped in a newtype to implement
+/// it.
+pub trait ReadAtRent {
+    /// Reads at most `len` bytes starting at `offset` into `buf`, returning how many were read and the buffer.
+    fn read_at_rent(
+        &self,
+        buf: Vec<u8>,
+        offset: u64,
+        len: usize,
+    ) -> impl Future<Output = (io::Result<usize>, Vec<u8>)>;
+}
+
+impl<T: ReadAtRent + ?Sized> ReadAtRent for &T {
+    fn read_at_rent(
+        &self,
+        buf: Vec<u8>,
+        offset: u64,
+        len: usize,
+    ) -> impl Future<Output = (io::Result<usize>, Vec<u8>)> {
+        (**self).read_at_rent(buf, offset, len)
+    }
+}
+
+impl<T: ReadAtRent + ?Sized> ReadAtRent for Arc<T> {
+    fn read_at_rent(
+        &self,
+        buf: Vec<u8>,
+        offset: u64,
+        len: usize,
+    ) -> impl Future<Output = (io::Result<usize>, Vec<u8>)> {
+        (**self).read_at_rent(buf, offset, len)
+    }
+}
+
+impl ReadAtRent for [u8] {
+    async fn read_at_rent(
+        &self,
+        mut buf: Vec<u8>,
+        offset: u64,
+        len: usize,
+    ) -> (io::Result<usize>, Vec<u8>) {
+        let start = cmp::min(offset, self.len() as u64) as usize;
+        let n = cmp::min(cmp::min(len, buf.capacity()), self.len() - start);
+        buf.clear();
+        buf.extend_from_slice(&self[start..start + n]);
+        (Ok(n), buf)
+    }
+}
+
+#[cfg(all(target_os = "linux", feature = "tokio-uring"))]
+impl ReadAtRent for tokio_uring::fs::File {
+    async fn read_at_rent(
+        &self,
+        mut buf: Vec<u8>,
+        offset: u64,
+        len: usize,
+    ) -> (io::Result<usize>, Vec<u8>) {
+        use tokio_uring::buf::BoundedBuf;
+
+        // The read only ever grows the length of a `Vec`
+        buf.clear();
+        let len = cmp::min(len, buf.capacity());
+        let (result, slice) = self.read_at(buf.slice(..len), offset).await;
+        (result, slice.into_inner())
+    }
+}
+
+/// A non-owning window over the `len` bytes of a [`ReadAtRent`] source starting at `offset`.
+///
+/// The completion-based counterpart of [`RefTakeAt`](crate::RefTakeAt):
+/// it keeps its own position and needs only a shared reference to the
+/// source. A read asks the source for no more than the rest of the
+/// window, however large the buffer, so the source is never read past the
+/// end of the window.
+#[derive(Debug)]
+pub struct RentTakeAt<'a, F: ?Sized> {
+    inner: &'a F,
+    offset: u64,
+    limit: u64,
+}
+
+impl<'a, F: ?Sized> RentTakeAt<'a, F> {
+    /// Creates a new `RentTakeAt` over the `len` bytes of the given source starting at `offset`.
+    pub const fn wrap(inner: &'a F, offset: u64, len: u64) -> Self {
+        Self {
+            inner,
+            offset,
+            limit: len,
+        }
+    }
+
+    /// Returns the offset in the source the next read starts at.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Returns the number of bytes left to read before the end of the window.
+    pub fn current_limit(&self) -> u64 {
+        self.limit
+    }
+
+    /// Returns the source reference.
+    pub fn get_ref(&self) -> &'a F {
+        self.inner
+    }
+}
+
+impl<F: ReadAtRent + ?Sized> RentTakeAt<'_, F> {
+    /// Reads the next bytes of the window into `buf`, returning how many were read and the buffer.
+    ///
+    /// Follows the [`ReadAtRent`] convention: the buffer is filled from its
+    /// start and its length set to the bytes read. At the end of the
+    /// window the source is not called and the buffer comes back empty.
+    pub async fn read(&mut self, mut buf: Vec<u8>) -> (io::Result<usize>, Vec<u8>) {
+        if self.limit == 0 || buf.capacity() == 0 {
+            buf.clear();
+            return (Ok(0), buf);
+        }
+        let len = cmp::min(buf.capacity() as u64, self.limit) as usize;
+        let (result, mut buf) = self.inner.read_at_rent(buf, self.offset, len).await;
+        let result = result.map(|n| {
+            let n = cmp::min(n as u64, self.limit) as usize;
+            buf.truncate(n);
+            self.offset += n as u64;
+            self.limit -= n as u64;
+            n
+        });
+        (result, buf)
+    }
+
+    /// Reads the rest of the window into a new `Vec`, in reads of up to `chunk_size` bytes.
+    ///
+    /// Stops early if the source ends before the window does.
+    pub async fn read_to_end(&mut self, chunk_size: usize) -> io::Result<Vec<u8>> {
+        let mut out = Vec::new();
+        let mut buf = Vec::with_capacity(chunk_size.max(1));
+        while self.limit > 0 {
+            let (result, chunk) = self.read(buf).await;
+            if result? == 0 {
+                break;
+            }
+            out.extend_from_slice(&chunk);
+            buf = chunk;
+        }
+        Ok(out)
+    }
+}
+
+/// Extension trait to provide a `take_ref_at_rent` method on all [`ReadAtRent`] sources.
+pub trait RentTakeAtExt: ReadAtRent {
+    /// Creates a `RentTakeAt` over the `len` bytes starting at `offset`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use futures::executor::block_on;
+    /// use reftake::RentTakeAtExt;
+    ///
+    /// block_on(async {
+    ///     let archive: &[u8] = b"name=alpha;name=beta";
+    ///     let mut entry = archive.take_ref_at_rent(5, 5);
+    ///     let (n, buf) = entry.read(Vec::with_capacity(64)).await;
+    ///     assert_eq!(n.unwrap(), 5);
+    ///     assert_eq!(buf, b"alpha");
+    ///     assert_eq!(entry.current_limit(), 0);
+    /// });
+    /// ```
+    fn take_ref_at_rent(&self, offset: u64, len: u64) -> RentTakeAt<'_, Self> {
+        RentTakeAt::wrap(self, offset, len)
+    }
+}
+
+impl<T: ReadAtRent + ?Sized> RentTakeAtExt for T {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+
+    /// Fails reads that would go past `len`, like a source that must not be read past the window.
+    struct Strict<'a> {
+        data: &'a [u8],
+        len: u64,
+    }
+
+    impl ReadAtRent for Strict<'_> {
+        async fn read_at_rent(
+            &self,
+            buf: Vec<u8>,
+            offset: u64,
+            len: usize,
+        ) -> (io::Result<usize>, Vec<u8>) {
+            assert!(
+                offset + len as u64 <= self.len,
+                "read of {len} bytes at {offset}"
+            );
+            self.data.read_at_rent(buf, offset, len).await
+        }
+    }
+
+    #[test]
+    fn test_reads_are_trimmed_to_the_window() {
+        let source = Strict {
+            data: b"0123456789",
+            len: 7,
+        };
+        let mut window = source.take_ref_at_rent(2, 5);
+        let (n, buf) = block_on(window.read(Vec::with_capacity(3)));
+        assert_eq!((n.unwrap(), &buf[..]), (3, &b"234"[..]));
+        assert_eq!(block_on(window.read_to_end(64)).unwrap(), b"56");
+        assert_eq!(window.offset(), 7);
+
+        let (n, buf) = block_on(window.read(buf));
+        assert_eq!(n.unwrap(), 0);
+        assert!(buf.is_empty());
+
+        // The source goes on past the window, and a large buffer doesn't read into it
+        let mut window = source.take_ref_at_rent(4, 3);
+        let (n, buf) = block_on(window.read(Vec::with_capacity(64)));
+        assert_eq!((n.unwrap(), &buf[..]), (3, &b"456"[..]));
+    }
+
+    #[cfg(all(target_os = "linux", feature = "tokio-uring"))]
+    #[test]
+    fn test_tokio_uring_file_window() {
+        let path = std::env::temp_dir().join(format!("reftake-uring-{}", std::process::id()));
+        std::fs::write(&path, b"header|first|second").unwrap();
+        tokio_uring::start(async {
+            let file = tokio_uring::fs::File::open(&path).await.unwrap();
+            let mut entry = file.take_ref_at_rent(7, 5);
+            // A buffer with stale bytes comes back with only the ones read
+            let (n, buf) = entry.read(b"stale bytes".to_vec()).await;
+            assert_eq!((n.unwrap(), &buf[..]), (5, &b"first"[..]));
+            // The file goes on past the window, and the source is asked for its bytes only
+            let (n, buf) = file.read_at_rent(Vec::with_capacity(64), 7, 5).await;
+            assert_eq!((n.unwrap(), &buf[..]), (5, &b"first"[..]));
+            let mut tail = file.take_ref_at_rent(13, 100);
+            assert_eq!(tail.read_to_end(4).await.unwrap(), b"second");
+            file.close().await.unwrap();
+        });
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_short_source() {
+        let source: &[u8] = b"abc";
+        let mut window = source.take_ref_at_rent(1, 100);
+        assert_eq!(block_on(window.read_to_end(1)).unwrap(), b"bc");
+        assert_eq!(window.current_limit(), 98);
+    }
+}
@@ -0,0 +1,305 @@
+//! Recording the reads of a live source, and replaying them exactly.
+//!
+//! A log starts with the magic bytes `RTRC` and holds one entry per read
+//! call: a `0` tag, the number of bytes as an LEB128 varint and the bytes
+//! themselves, or a `1` tag, the error kind, and the error message as a
+//! length-prefixed UTF-8 string. EOF is recorded as a read of zero bytes,
+//! and offsets follow from the lengths of the entries before.
+
+use std::io::{self, ErrorKind, Read, Write};
+
+use crate::{ReadVarint, RefTake, frame::read_first_byte};
+
+const MAGIC: &[u8; 4] = b"RTRC";
+const TAG_DATA: u8 = 0;
+const TAG_ERROR: u8 = 1;
+
+/// Error kinds that keep their identity through a log; others replay as `Other`.
+const KINDS: [ErrorKind; 16] = [
+    ErrorKind::Other,
+    ErrorKind::NotFound,
+    ErrorKind::PermissionDenied,
+    ErrorKind::ConnectionRefused,
+    ErrorKind::ConnectionReset,
+    ErrorKind::ConnectionAborted,
+    ErrorKind::NotConnected,
+    ErrorKind::BrokenPipe,
+    ErrorKind::WouldBlock,
+    ErrorKind::InvalidInput,
+    ErrorKind::InvalidData,
+    ErrorKind::TimedOut,
+    ErrorKind::Interrupted,
+    ErrorKind::UnexpectedEof,
+    ErrorKind::OutOfMemory,
+    ErrorKind::Unsupported,
+];
+
+fn write_uvarint<W: Write + ?Sized>(log: &mut W, mut value: u64) -> io::Result<()> {
+    let mut buf = [0u8; 10];
+    let mut len = 0;
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            buf[len] = byte;
+            len += 1;
+            break;
+        }
+        buf[len] = byte | 0x80;
+        len += 1;
+    }
+    log.write_all(&buf[..len])
+}
+
+/// A non-owning adapter that logs every read of its inner reader.
+///
+/// Reads are passed through unchanged, and each call is appended to the
+/// log as it returns: the bytes it produced, or the error it failed with.
+/// A [`ReplayReader`] over the log returns the same results in the same
+/// order, including short reads, so a session that tripped a parser in
+/// production can be turned into a test. The magic bytes are written
+/// before the first entry.
+///
+/// A failure to write the log is returned from the read being recorded;
+/// the bytes of that read are lost to the caller.
+pub struct RefRecorder<'a, R, W> {
+    inner: &'a mut R,
+    log: &'a mut W,
+    started: bool,
+}
+
+impl<'a, R, W> RefRecorder<'a, R, W> {
+    /// Creates a new `RefRecorder` reading from `inner` and appending to `log`.
+    pub const fn wrap(inner: &'a mut R, log: &'a mut W) -> Self {
+        Self {
+            inner,
+            log,
+            started: false,
+        }
+    }
+}
+
+impl<R, W: Write> RefRecorder<'_, R, W> {
+    fn record(&mut self, result: &io::Result<&[u8]>) -> io::Result<()> {
+        if !self.started {
+            self.log.write_all(MAGIC)?;
+            self.started = true;
+        }
+        match result {
+            Ok(data) => {
+                self.log.write_all(&[TAG_DATA])?;
+                write_uvarint(self.log, data.len() as u64)?;
+                self.log.write_all(data)
+            }
+            Err(e) => {
+                let kind = KINDS.iter().position(|&k| k == e.kind()).unwrap_or(0);
+                let message = e.to_string();
+                self.log.write_all(&[TAG_ERROR, kind as u8])?;
+                write_uvarint(self.log, message.len() as u64)?;
+                self.log.write_all(message.as_bytes())
+            }
+        }
+    }
+}
+
+impl<R: Read, W: Write> Read for RefRecorder<'_, R, W> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        match self.inner.read(buf) {
+            Ok(n) => {
+                self.record(&Ok(&buf[..n]))?;
+                Ok(n)
+            }
+            Err(e) => {
+                self.record(&Err(io::Error::new(e.kind(), e.to_string())))?;
+                Err(e)
+            }
+        }
+    }
+}
+
+/// Extension trait to provide a `record_ref` method on all `Read` types.
+pub trait RefRecorderExt {
+    /// Wraps the reader in a `RefRecorder` appending to `log`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Cursor, Read};
+    /// use reftake::{RefRecorderExt, ReplayReader};
+    ///
+    /// let mut live = Cursor::new(b"GET / HTTP/1.1\r\n");
+    /// let mut log = Vec::new();
+    /// let mut buf = [0u8; 5];
+    /// let n = live.record_ref(&mut log).read(&mut buf).unwrap();
+    ///
+    /// let mut log = Cursor::new(log);
+    /// let mut replay = ReplayReader::wrap(&mut log);
+    /// let mut again = [0u8; 64];
+    /// assert_eq!(replay.read(&mut again).unwrap(), n);
+    /// assert_eq!(&again[..n], b"GET /");
+    /// ```
+    fn record_ref<'a, W: Write>(&'a mut self, log: &'a mut W) -> RefRecorder<'a, Self, W>
+    where
+        Self: Sized;
+}
+
+impl<T: Read> RefRecorderExt for T {
+    fn record_ref<'a, W: Write>(&'a mut self, log: &'a mut W) -> RefRecorder<'a, Self, W> {
+        RefRecorder::wrap(self, log)
+    }
+}
+
+/// A reader that replays a log written by a [`RefRecorder`].
+///
+/// Each read returns the next recorded result: the recorded bytes, as
+/// many as fit the buffer with the rest returned by the following reads,
+/// or the recorded error, with its kind and message. A log that runs out
+/// fails with `ErrorKind::UnexpectedEof`, so code that reads further than
+/// the recorded session did is noticed rather than fed a made-up EOF.
+/// The log itself is only borrowed and read entry by entry.
+pub struct ReplayReader<'a, L> {
+    log: &'a mut L,
+    started: bool,
+    /// Recorded bytes not returned yet.
+    pending: u64,
+}
+
+impl<'a, L> ReplayReader<'a, L> {
+    /// Creates a new `ReplayReader` over the given log reference.
+    pub const fn wrap(log: &'a mut L) -> Self {
+        Self {
+            log,
+            started: false,
+            pending: 0,
+        }
+    }
+}
+
+impl<L: Read> ReplayReader<'_, L> {
+    /// Reads the next entry header, leaving the bytes of a data entry in the log.
+    fn next_entry(&mut self) -> io::Result<Result<u64, io::Error>> {
+        if !self.started {
+            let mut magic = [0u8; 4];
+            self.log.read_exact(&mut magic)?;
+            if &magic != MAGIC {
+                return Err(io::Error::new(ErrorKind::InvalidData, "not a replay log"));
+            }
+            self.started = true;
+        }
+        match read_first_byte(self.log)? {
+            None => Err(io::Error::new(
+                ErrorKind::UnexpectedEof,
+                "replay log exhausted",
+            )),
+            Some(TAG_DATA) => Ok(Ok(self.log.read_uvarint()?)),
+            Some(TAG_ERROR) => {
+                let mut kind = 0u8;
+                self.log.read_exact(std::slice::from_mut(&mut kind))?;
+                let kind = KINDS
+                    .get(kind as usize)
+                    .copied()
+                    .unwrap_or(ErrorKind::Other);
+                let len = self.log.read_uvarint()?;
+                let mut message = String::new();
+                RefTake::wrap(self.log, len).read_to_string(&mut message)?;
+                if message.len() as u64 != len {
+                    return Err(ErrorKind::UnexpectedEof.into());
+                }
+                Ok(Err(io::Error::new(kind, message)))
+            }
+            Some(tag) => Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("unknown replay log entry {tag}"),
+            )),
+        }
+    }
+}
+
+impl<L: Read> Read for ReplayReader<'_, L> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        if self.pending == 0 {
+            match self.next_entry()? {
+                Ok(0) => return Ok(0),
+                Ok(len) => self.pending = len,
+                Err(recorded) => return Err(recorded),
+            }
+        }
+        let max = self.pending.min(buf.len() as u64) as usize;
+        self.log.read_exact(&mut buf[..max])?;
+        self.pending -= max as u64;
+        Ok(max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// Returns the scripted results one per call.
+    struct Script(Vec<io::Result<&'static [u8]>>);
+
+    impl Read for Script {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.0.remove(0) {
+                Ok(data) => {
+                    buf[..data.len()].copy_from_slice(data);
+                    Ok(data.len())
+                }
+                Err(e) => Err(e),
+            }
+        }
+    }
+
+    #[test]
+    fn test_session_replays_exactly() {
+        let mut live = Script(vec![
+            Ok(b"ab"),
+            Err(io::Error::new(ErrorKind::Interrupted, "signal")),
+            Ok(b"cde"),
+            Err(io::Error::new(ErrorKind::ConnectionReset, "peer reset")),
+            Ok(b""),
+        ]);
+        let mut log = Vec::new();
+        let mut recorder = live.record_ref(&mut log);
+        let mut buf = [0u8; 8];
+        let live_results: Vec<_> = (0..5)
+            .map(|_| {
+                recorder
+                    .read(&mut buf)
+                    .map_err(|e| (e.kind(), e.to_string()))
+            })
+            .collect();
+
+        let mut log = Cursor::new(log);
+        let mut replay = ReplayReader::wrap(&mut log);
+        let replayed: Vec<_> = (0..5)
+            .map(|_| replay.read(&mut buf).map_err(|e| (e.kind(), e.to_string())))
+            .collect();
+        assert_eq!(replayed, live_results);
+        assert_eq!(
+            replay.read(&mut buf).unwrap_err().kind(),
+            ErrorKind::UnexpectedEof
+        );
+    }
+
+    #[test]
+    fn test_small_buffers_split_recorded_reads() {
+        let mut live = Cursor::new(vec![7u8; 300]);
+        let mut log = Vec::new();
+        let mut recorder = live.record_ref(&mut log);
+        let mut big = [0u8; 256];
+        assert_eq!(recorder.read(&mut big).unwrap(), 256);
+        assert_eq!(recorder.read(&mut big).unwrap(), 44);
+
+        let mut log = Cursor::new(log);
+        let mut replay = ReplayReader::wrap(&mut log);
+        let mut buf = [0u8; 100];
+        let lens: Vec<_> = (0..4).map(|_| replay.read(&mut buf).unwrap()).collect();
+        assert_eq!(lens, [100, 100, 56, 44]);
+
+        let mut bad = Cursor::new(b"JUNK");
+        let err = ReplayReader::wrap(&mut bad).read(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,189 @@
+//! A fixed-size record iterator over a borrowed reader.
+
+use std::io::{self, ErrorKind, Read};
+
+use crate::Chunks;
+
+/// What a [`Records`] iterator does with a trailing record shorter than the record length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PartialRecord {
+    /// Fail with `ErrorKind::UnexpectedEof`.
+    #[default]
+    Error,
+    /// Return the short record as the last one.
+    Yield,
+    /// Drop the short record and end the iteration.
+    Skip,
+}
+
+/// An iterator over fixed-size records of a borrowed reader.
+///
+/// Every record holds exactly `record_len` bytes. A stream (or
+/// [`RefTake`](crate::RefTake) window) that ends inside a record leaves a
+/// trailing partial record, handled according to [`PartialRecord`]; in any
+/// case [`Records::is_partial`] reports `true` afterwards.
+///
+/// [`Records::next_into`] fills a caller-provided buffer instead of allocating.
+pub struct Records<'a, R> {
+    chunks: Chunks<'a, R>,
+    partial: PartialRecord,
+}
+
+impl<'a, R> Records<'a, R> {
+    /// Creates a new `Records` over `inner` yielding records of `record_len`
+    /// bytes, failing on a trailing partial record.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `record_len` is zero.
+    pub const fn wrap(inner: &'a mut R, record_len: usize) -> Self {
+        Self::with_partial(inner, record_len, PartialRecord::Error)
+    }
+
+    /// Creates a new `Records` with the given handling of a trailing partial record.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `record_len` is zero.
+    pub const fn with_partial(inner: &'a mut R, record_len: usize, partial: PartialRecord) -> Self {
+        assert!(record_len > 0, "record length must be non-zero");
+        Self {
+            chunks: Chunks::wrap(inner, record_len),
+            partial,
+        }
+    }
+
+    /// Returns the record length.
+    pub fn record_len(&self) -> usize {
+        self.chunks.chunk_size()
+    }
+
+    /// Returns `true` if the stream ended inside a record.
+    pub fn is_partial(&self) -> bool {
+        self.chunks.is_partial()
+    }
+}
+
+impl<R: Read> Records<'_, R> {
+    /// Reads the next record into the first `record_len()` bytes of `buf`.
+    ///
+    /// Returns the number of bytes read, which is the record length except
+    /// for a trailing partial record returned by [`PartialRecord::Yield`],
+    /// and `0` once the reader is exhausted.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `buf` is shorter than the record length.
+    pub fn next_into(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.chunks.next_into(buf)?;
+        if n == 0 || n == self.record_len() {
+            return Ok(n);
+        }
+        match self.partial {
+            PartialRecord::Error => Err(io::Error::new(
+                ErrorKind::UnexpectedEof,
+                format!(
+                    "stream ended after {n} of {} bytes of a record",
+                    self.record_len()
+                ),
+            )),
+            PartialRecord::Yield => Ok(n),
+            PartialRecord::Skip => Ok(0),
+        }
+    }
+}
+
+impl<R: Read> Iterator for Records<'_, R> {
+    type Item = io::Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut record = vec![0; self.record_len()];
+        match self.next_into(&mut record) {
+            Ok(0) => None,
+            Ok(n) => {
+                record.truncate(n);
+                Some(Ok(record))
+            }
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+/// Extension trait to provide a `records_ref` method on all `Read` types.
+pub trait RecordsExt {
+    /// Returns an iterator over records of exactly `record_len` bytes,
+    /// failing if the stream ends inside a record.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use reftake::RecordsExt;
+    ///
+    /// let mut cursor = Cursor::new(b"id01id02id");
+    /// let mut records = cursor.records_ref(4);
+    /// assert_eq!(records.next().unwrap().unwrap(), b"id01");
+    /// assert_eq!(records.next().unwrap().unwrap(), b"id02");
+    /// assert!(records.next().unwrap().is_err());
+    /// assert!(records.next().is_none());
+    /// ```
+    fn records_ref(&mut self, record_len: usize) -> Records<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: Read> RecordsExt for T {
+    fn records_ref(&mut self, record_len: usize) -> Records<'_, Self> {
+        Records::wrap(self, record_len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RefTakeExt;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_exact_records() {
+        let mut reader = Cursor::new(b"aaabbbccc");
+        let mut records = reader.records_ref(3);
+        let all: Vec<Vec<u8>> = records.by_ref().map(Result::unwrap).collect();
+        assert_eq!(all, vec![b"aaa".to_vec(), b"bbb".to_vec(), b"ccc".to_vec()]);
+        assert!(!records.is_partial());
+    }
+
+    #[test]
+    fn test_partial_record_policies() {
+        let mut reader = Cursor::new(b"aaabb");
+        let err = reader.records_ref(3).nth(1).unwrap().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+
+        reader.set_position(0);
+        let mut records = Records::with_partial(&mut reader, 3, PartialRecord::Yield);
+        let all: Vec<Vec<u8>> = records.by_ref().map(Result::unwrap).collect();
+        assert_eq!(all, vec![b"aaa".to_vec(), b"bb".to_vec()]);
+        assert!(records.is_partial());
+
+        reader.set_position(0);
+        let mut records = Records::with_partial(&mut reader, 3, PartialRecord::Skip);
+        let all: Vec<Vec<u8>> = records.by_ref().map(Result::unwrap).collect();
+        assert_eq!(all, vec![b"aaa".to_vec()]);
+        assert!(records.is_partial());
+    }
+
+    #[test]
+    fn test_next_into_within_window() {
+        let mut reader = Cursor::new(b"0123456789");
+        let mut take = reader.take_ref(8);
+        let mut records = take.records_ref(4);
+
+        let mut record = [0u8; 4];
+        assert_eq!(records.next_into(&mut record).unwrap(), 4);
+        assert_eq!(records.next_into(&mut record).unwrap(), 4);
+        assert_eq!(&record, b"4567");
+        assert_eq!(records.next_into(&mut record).unwrap(), 0);
+        assert!(!records.is_partial());
+    }
+}
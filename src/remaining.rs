@@ -0,0 +1,260 @@
+//! Convenience methods that read the rest of a [`RefTake`] window, or as much as fits, in one call.
+
+use std::io::{self, ErrorKind, Read};
+
+use crate::{InvalidUtf8, RefTake};
+
+/// How [`RefTake::read_remaining_to_string`] handles bytes that are not valid UTF-8.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Utf8Policy {
+    /// Fail with an [`InvalidUtf8`] error.
+    #[default]
+    Strict,
+    /// Replace invalid sequences with `U+FFFD REPLACEMENT CHARACTER`.
+    Lossy,
+}
+
+/// Upper bound on up-front allocations sized from a window's limit.
+///
+/// Limits often come from untrusted length fields, so buffers are never
+/// pre-allocated beyond this; they still grow as real data arrives.
+pub(crate) const MAX_PREALLOC: u64 = 64 * 1024;
+
+impl<R: Read> RefTake<'_, R> {
+    /// Reads the rest of the window into a new `Vec`.
+    ///
+    /// The vector is pre-sized from the remaining limit, up to 64 KiB.
+    /// Returns the bytes together with `true` if the inner reader reached EOF
+    /// before the limit did, i.e. the window was cut short.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use reftake::RefTakeExt;
+    ///
+    /// let mut cursor = Cursor::new(b"hello world");
+    /// let (data, short) = cursor.take_ref(5).read_all_remaining().unwrap();
+    /// assert_eq!(data, b"hello");
+    /// assert!(!short);
+    ///
+    /// let (data, short) = cursor.take_ref(100).read_all_remaining().unwrap();
+    /// assert_eq!(data, b" world");
+    /// assert!(short);
+    /// ```
+    pub fn read_all_remaining(&mut self) -> io::Result<(Vec<u8>, bool)> {
+        let mut buf = Vec::with_capacity(self.current_limit().min(MAX_PREALLOC) as usize);
+        self.read_to_end(&mut buf)?;
+        Ok((buf, self.current_limit() > 0))
+    }
+
+    /// Reads exactly `n` bytes from the window into a new `Vec`.
+    ///
+    /// Fails with `ErrorKind::UnexpectedEof` without reading anything if `n`
+    /// exceeds the remaining limit. Otherwise the vector is allocated once and
+    /// filled completely, or `UnexpectedEof` is returned if the inner reader
+    /// ends first, in which case the bytes read so far are lost.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Cursor, ErrorKind};
+    /// use reftake::RefTakeExt;
+    ///
+    /// let mut cursor = Cursor::new(b"abcdef");
+    /// let mut take = cursor.take_ref(4);
+    /// assert_eq!(take.read_exact_vec(3).unwrap(), b"abc");
+    ///
+    /// let err = take.read_exact_vec(2).unwrap_err();
+    /// assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    /// assert_eq!(take.current_limit(), 1);
+    /// ```
+    pub fn read_exact_vec(&mut self, n: usize) -> io::Result<Vec<u8>> {
+        if n as u64 > self.current_limit() {
+            trace_event!(
+                debug,
+                requested = n,
+                remaining = self.current_limit(),
+                "exact read past the window end"
+            );
+            return Err(io::Error::new(
+                ErrorKind::UnexpectedEof,
+                format!(
+                    "{n} bytes requested but only {} left in window",
+                    self.current_limit()
+                ),
+            ));
+        }
+        let mut buf = vec![0; n];
+        self.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+
+    /// Reads until `buf` is full, the window is used up, or the inner reader reaches EOF.
+    ///
+    /// The middle ground between `read`, which may return any short count,
+    /// and `read_exact`, which fails at EOF: returns how many bytes were
+    /// written to the start of `buf`, which is less than its length only
+    /// at the end of the window or the stream. `Interrupted` errors are
+    /// retried; on any other error the bytes read so far are in `buf` but
+    /// their count is lost, as with `read_exact`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Cursor, Read};
+    /// use reftake::RefTakeExt;
+    ///
+    /// let mut stream = Cursor::new(b"ab").chain(Cursor::new(b"cdef"));
+    /// let mut take = stream.take_ref(5);
+    /// let mut buf = [0u8; 4];
+    /// assert_eq!(take.try_fill(&mut buf).unwrap(), 4);
+    /// assert_eq!(&buf, b"abcd");
+    /// assert_eq!(take.try_fill(&mut buf).unwrap(), 1);
+    /// assert_eq!(take.try_fill(&mut buf).unwrap(), 0);
+    /// ```
+    pub fn try_fill(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut filled = 0;
+        while filled < buf.len() {
+            match self.read(&mut buf[filled..]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(filled)
+    }
+
+    /// Reads the rest of the window into a new `String`, decoding it according to `policy`.
+    ///
+    /// The buffer is pre-sized like [`read_all_remaining`](Self::read_all_remaining).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use reftake::{RefTakeExt, Utf8Policy};
+    ///
+    /// let mut cursor = Cursor::new(b"caf\xe9 au lait");
+    /// let text = cursor.take_ref(4).read_remaining_to_string(Utf8Policy::Lossy).unwrap();
+    /// assert_eq!(text, "caf\u{fffd}");
+    /// ```
+    pub fn read_remaining_to_string(&mut self, policy: Utf8Policy) -> io::Result<String> {
+        self.read_remaining_to_string_with(|bytes| match policy {
+            Utf8Policy::Strict => String::from_utf8(bytes).map_err(|e| {
+                let err = e.utf8_error();
+                let start = err.valid_up_to();
+                let end = start + err.error_len().unwrap_or(e.as_bytes().len() - start);
+                InvalidUtf8::new(&e.as_bytes()[start..end]).into()
+            }),
+            Utf8Policy::Lossy => Ok(String::from_utf8(bytes)
+                .unwrap_or_else(|e| String::from_utf8_lossy(e.as_bytes()).into_owned())),
+        })
+    }
+
+    /// Reads the rest of the window and converts it to a `String` with a caller-provided validator.
+    ///
+    /// The validator receives all remaining bytes and may reject them with
+    /// any I/O error, for example after checking for a required charset.
+    pub fn read_remaining_to_string_with<F>(&mut self, validate: F) -> io::Result<String>
+    where
+        F: FnOnce(Vec<u8>) -> io::Result<String>,
+    {
+        let (bytes, _) = self.read_all_remaining()?;
+        validate(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RefTakeExt;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_read_all_remaining_presizes() {
+        let mut reader = Cursor::new(vec![7u8; 100]);
+        let mut take = reader.take_ref(40);
+        let (data, short) = take.read_all_remaining().unwrap();
+        assert_eq!(data.len(), 40);
+        assert_eq!(data.capacity(), 40);
+        assert!(!short);
+    }
+
+    #[test]
+    fn test_huge_limit_is_not_preallocated() {
+        let mut reader = Cursor::new(b"tiny");
+        let mut take = reader.take_ref(u64::MAX);
+        let (data, short) = take.read_all_remaining().unwrap();
+        assert_eq!(data, b"tiny");
+        assert!(data.capacity() <= super::MAX_PREALLOC as usize);
+        assert!(short);
+    }
+
+    #[test]
+    fn test_strict_rejects_invalid_utf8() {
+        let mut reader = Cursor::new(b"ok\xff\xfe");
+        let err = reader
+            .take_ref(4)
+            .read_remaining_to_string(Utf8Policy::Strict)
+            .unwrap_err();
+        assert_eq!(InvalidUtf8::from_io(&err).unwrap().bytes(), b"\xff");
+    }
+
+    #[test]
+    fn test_custom_validator() {
+        let mut reader = Cursor::new(b"ASCII only: \xc3\xa9");
+        let err = reader
+            .take_ref(100)
+            .read_remaining_to_string_with(|bytes| {
+                if !bytes.is_ascii() {
+                    return Err(io::Error::new(ErrorKind::InvalidData, "not ASCII"));
+                }
+                Ok(String::from_utf8(bytes).unwrap())
+            })
+            .unwrap_err();
+        assert_eq!(err.to_string(), "not ASCII");
+    }
+
+    #[test]
+    fn test_read_exact_vec_inner_eof() {
+        let mut reader = Cursor::new(b"ab");
+        let mut take = reader.take_ref(10);
+        let err = take.read_exact_vec(5).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_try_fill_retries_interrupted_reads() {
+        /// Fails every other read with `Interrupted`.
+        struct Flaky<'a> {
+            data: &'a [u8],
+            fail: bool,
+        }
+
+        impl Read for Flaky<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                self.fail = !self.fail;
+                if self.fail {
+                    return Err(ErrorKind::Interrupted.into());
+                }
+                let n = buf.len().min(2).min(self.data.len());
+                buf[..n].copy_from_slice(&self.data[..n]);
+                self.data = &self.data[n..];
+                Ok(n)
+            }
+        }
+
+        let mut reader = Flaky {
+            data: b"abcde",
+            fail: false,
+        };
+        let mut take = reader.take_ref(10);
+        let mut buf = [0u8; 8];
+        assert_eq!(take.try_fill(&mut buf).unwrap(), 5);
+        assert_eq!(&buf[..5], b"abcde");
+        assert_eq!(take.current_limit(), 5);
+    }
+}
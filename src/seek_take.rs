@@ -0,0 +1,304 @@
+//! A seekable window over a borrowed reader.
+
+use std::{
+    cmp,
+    io::{self, BufRead, ErrorKind, Read, Seek, SeekFrom},
+    ops::Range,
+};
+
+use crate::{RefTake, WindowState};
+
+/// A non-owning adapter exposing the next `len` bytes of a seekable reader
+/// as a `Read + Seek` stream of its own.
+///
+/// This is the blocking counterpart of the `AsyncSeekTake` of the `tokio`
+/// feature, for parsers that jump around their input, such as `binrw`.
+/// Reads behave like those of a [`RefTake`](crate::RefTake). Seeks are
+/// interpreted relative to the window: position `0` is where the inner
+/// reader was when the window was created, and `SeekFrom::End` counts back
+/// from `len`. Target positions are clamped to `0..=len`, so a parser
+/// following an offset out of the window sees the end of its input instead
+/// of unrelated bytes, and the inner reader is moved with a relative seek,
+/// so its absolute position never has to be known.
+pub struct SeekTake<'a, R> {
+    pub(crate) inner: &'a mut R,
+    len: u64,
+    limit: u64,
+}
+
+impl<'a, R> SeekTake<'a, R> {
+    /// Creates a new `SeekTake` over the next `len` bytes of the given reader reference.
+    pub const fn wrap(inner: &'a mut R, len: u64) -> Self {
+        Self {
+            inner,
+            len,
+            limit: len,
+        }
+    }
+
+    /// Returns the length of the window.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns `true` if the window is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the current position within the window.
+    pub fn position(&self) -> u64 {
+        self.len - self.limit
+    }
+
+    /// Returns the number of bytes left to read before the end of the window.
+    pub fn current_limit(&self) -> u64 {
+        self.limit
+    }
+}
+
+impl<'a, R: Seek> SeekTake<'a, R> {
+    /// Re-attaches a saved window to `inner`, seeking it to the first byte not consumed.
+    ///
+    /// `inner` is the reopened source the state was saved from; the
+    /// window's origin is an absolute offset in it. Fails with
+    /// `ErrorKind::InvalidData` if the state consumed past the end of the
+    /// window.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Cursor, Read};
+    /// use reftake::{SeekTake, SeekTakeExt};
+    ///
+    /// let mut file = Cursor::new(b"header|a long window of records|trailer");
+    /// file.set_position(7);
+    /// let mut window = file.seek_take_ref(24);
+    /// let mut first = [0u8; 7];
+    /// window.read_exact(&mut first).unwrap();
+    /// let saved = window.state().unwrap();
+    ///
+    /// let mut reopened = Cursor::new(b"header|a long window of records|trailer");
+    /// let mut window = SeekTake::resume(&mut reopened, &saved).unwrap();
+    /// let mut rest = String::new();
+    /// window.read_to_string(&mut rest).unwrap();
+    /// assert_eq!(rest, "window of records");
+    /// ```
+    pub fn resume(inner: &'a mut R, state: &WindowState) -> io::Result<Self> {
+        state.check()?;
+        inner.seek(SeekFrom::Start(state.position()))?;
+        Ok(Self {
+            inner,
+            len: state.len,
+            limit: state.remaining(),
+        })
+    }
+
+    /// Returns where the window stands, to save and [`resume`](SeekTake::resume) later.
+    pub fn state(&mut self) -> io::Result<WindowState> {
+        let consumed = self.position();
+        let origin = self
+            .inner
+            .stream_position()?
+            .checked_sub(consumed)
+            .ok_or_else(|| io::Error::new(ErrorKind::InvalidData, "reader is before the window"))?;
+        Ok(WindowState {
+            origin,
+            len: self.len,
+            consumed,
+            frames: None,
+        })
+    }
+}
+
+impl<R: Read> Read for SeekTake<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        // Don't call into inner reader at all at EOF because it may still block
+        if self.limit == 0 {
+            return Ok(0);
+        }
+
+        let max = cmp::min(buf.len() as u64, self.limit) as usize;
+        let n = self.inner.read(&mut buf[..max])?;
+        assert!(n as u64 <= self.limit, "number of read bytes exceeds limit");
+        self.limit -= n as u64;
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for SeekTake<'_, R> {
+    fn fill_buf(&mut self) -> Result<&[u8], io::Error> {
+        if self.limit == 0 {
+            return Ok(&[]);
+        }
+
+        let buf = self.inner.fill_buf()?;
+        let cap = cmp::min(buf.len() as u64, self.limit) as usize;
+        Ok(&buf[..cap])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        let amt = cmp::min(amt as u64, self.limit) as usize;
+        self.limit -= amt as u64;
+        self.inner.consume(amt);
+    }
+}
+
+/// Implements `Seek` within the window.
+///
+/// Seeks report the position within the window, not that of the inner reader.
+impl<R: Seek> Seek for SeekTake<'_, R> {
+    fn seek(&mut self, position: SeekFrom) -> io::Result<u64> {
+        let current = self.position();
+        let target = match position {
+            SeekFrom::Start(n) => n.min(self.len),
+            SeekFrom::End(n) => self.len.saturating_add_signed(n).min(self.len),
+            SeekFrom::Current(n) => current.saturating_add_signed(n).min(self.len),
+        };
+        let delta = i64::try_from(target as i128 - current as i128)
+            .map_err(|_| io::Error::new(ErrorKind::InvalidInput, "seek distance out of range"))?;
+        if delta != 0 {
+            self.inner.seek_relative(delta)?;
+        }
+        self.limit = self.len - target;
+        Ok(target)
+    }
+
+    fn stream_position(&mut self) -> io::Result<u64> {
+        Ok(self.position())
+    }
+}
+
+/// Extension trait to provide `seek_take_ref` and `take_ref_range` methods on all seekable `Read` types.
+pub trait SeekTakeExt {
+    /// Wraps the reader in a `SeekTake` over its next `len` bytes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Cursor, Read, Seek, SeekFrom};
+    /// use reftake::SeekTakeExt;
+    ///
+    /// let mut cursor = Cursor::new(b"header|entry data|trailer");
+    /// cursor.set_position(7);
+    /// let mut entry = cursor.seek_take_ref(10);
+    ///
+    /// assert_eq!(entry.seek(SeekFrom::End(-4)).unwrap(), 6);
+    /// let mut tail = String::new();
+    /// entry.read_to_string(&mut tail).unwrap();
+    /// assert_eq!(tail, "data");
+    /// assert_eq!(entry.seek(SeekFrom::Start(100)).unwrap(), 10);
+    /// ```
+    fn seek_take_ref(&mut self, len: u64) -> SeekTake<'_, Self>
+    where
+        Self: Sized;
+
+    /// Seeks the reader to `range.start` and wraps it in a `RefTake` ending at `range.end`.
+    ///
+    /// The range is checked against the length of the stream first, and
+    /// one that is reversed or ends past the end of the stream fails with
+    /// `ErrorKind::InvalidInput`, leaving the reader where it was.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Cursor, ErrorKind, Read};
+    /// use reftake::SeekTakeExt;
+    ///
+    /// let mut archive = Cursor::new(b"header|entry data|trailer");
+    /// let mut entry = String::new();
+    /// archive.take_ref_range(7..17).unwrap().read_to_string(&mut entry).unwrap();
+    /// assert_eq!(entry, "entry data");
+    ///
+    /// let err = archive.take_ref_range(20..40).err().unwrap();
+    /// assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    /// assert_eq!(archive.position(), 17);
+    /// ```
+    fn take_ref_range(&mut self, range: Range<u64>) -> io::Result<RefTake<'_, Self>>
+    where
+        Self: Sized;
+}
+
+impl<T: Read + Seek> SeekTakeExt for T {
+    fn seek_take_ref(&mut self, len: u64) -> SeekTake<'_, Self> {
+        SeekTake::wrap(self, len)
+    }
+
+    fn take_ref_range(&mut self, range: Range<u64>) -> io::Result<RefTake<'_, Self>> {
+        let position = self.stream_position()?;
+        let len = self.seek(SeekFrom::End(0))?;
+        if range.start > range.end || range.end > len {
+            self.seek(SeekFrom::Start(position))?;
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("range {range:?} out of the {len} byte stream"),
+            ));
+        }
+        self.seek(SeekFrom::Start(range.start))?;
+        Ok(RefTake::wrap(self, range.end - range.start))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufReader, Cursor};
+
+    #[test]
+    fn test_seeks_are_relative_and_clamped() {
+        let mut cursor = Cursor::new(b"0123456789");
+        cursor.set_position(2);
+        let mut window = cursor.seek_take_ref(5);
+        let mut byte = [0u8; 1];
+
+        assert_eq!(window.seek(SeekFrom::Start(3)).unwrap(), 3);
+        window.read_exact(&mut byte).unwrap();
+        assert_eq!(&byte, b"5");
+        assert_eq!(window.seek(SeekFrom::Current(-10)).unwrap(), 0);
+        window.read_exact(&mut byte).unwrap();
+        assert_eq!(&byte, b"2");
+        assert_eq!(window.seek(SeekFrom::Start(100)).unwrap(), 5);
+        assert_eq!(window.current_limit(), 0);
+        assert_eq!(window.seek(SeekFrom::End(1)).unwrap(), 5);
+        assert_eq!(window.stream_position().unwrap(), 5);
+        assert_eq!(cursor.position(), 7);
+    }
+
+    #[test]
+    fn test_buffered_reads_stop_at_window_end_after_seek() {
+        let mut reader = BufReader::with_capacity(4, Cursor::new(b"abcdefgh"));
+        let mut window = reader.seek_take_ref(6);
+
+        window.seek(SeekFrom::End(-3)).unwrap();
+        assert_eq!(window.fill_buf().unwrap(), b"def");
+        window.consume(2);
+        let mut out = Vec::new();
+        window.read_to_end(&mut out).unwrap();
+        assert_eq!(out, b"f");
+        window.seek(SeekFrom::Current(-6)).unwrap();
+        out.clear();
+        window.read_to_end(&mut out).unwrap();
+        assert_eq!(out, b"abcdef");
+    }
+
+    #[test]
+    fn test_take_ref_range() {
+        let mut cursor = Cursor::new(b"0123456789");
+        let mut out = Vec::new();
+        cursor
+            .take_ref_range(6..10)
+            .unwrap()
+            .read_to_end(&mut out)
+            .unwrap();
+        assert_eq!(out, b"6789");
+
+        cursor.set_position(3);
+        let err = cursor
+            .take_ref_range(Range { start: 5, end: 4 })
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "range 5..4 out of the 10 byte stream");
+        assert_eq!(cursor.position(), 3);
+        assert_eq!(cursor.take_ref_range(10..10).unwrap().current_limit(), 0);
+    }
+}
@@ -0,0 +1,399 @@
+//! Walking a table of `(offset, length)` segments of a borrowed seekable reader.
+
+use std::{
+    error::Error,
+    fmt,
+    io::{self, ErrorKind, Read, Seek, SeekFrom},
+    iter::Copied,
+    slice,
+};
+
+use crate::RefTake;
+
+/// Rules a table of segments from untrusted input must follow.
+///
+/// Checked by [`SegmentRules::validate`] and [`SegmentedReader::validated`]
+/// before any segment is read. Whatever the rules, every segment must lie
+/// within the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SegmentRules {
+    /// Whether segments may share bytes; archives that overlap entries are a known bomb technique.
+    pub allow_overlap: bool,
+    /// Most segments the table may hold.
+    pub max_segments: usize,
+    /// Most bytes all segments may add up to, counting shared bytes once per segment.
+    pub max_total_len: u64,
+}
+
+impl Default for SegmentRules {
+    /// Overlapping segments allowed, no caps on their number or total length.
+    fn default() -> Self {
+        Self {
+            allow_overlap: true,
+            max_segments: usize::MAX,
+            max_total_len: u64::MAX,
+        }
+    }
+}
+
+/// The rule an entry of a segment table broke, as part of an [`InvalidSegment`] error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SegmentViolation {
+    /// The segment ends past the end of the file.
+    OutOfBounds {
+        /// The length of the file.
+        file_len: u64,
+    },
+    /// The segment shares bytes with the entry at index `other`.
+    Overlap {
+        /// The index of the entry it overlaps.
+        other: usize,
+    },
+    /// The table has more than `max` entries; the first one past the cap is reported.
+    TooMany {
+        /// The configured cap.
+        max: usize,
+    },
+    /// The segments up to this one add up to more than `max` bytes.
+    TotalTooLarge {
+        /// The configured cap.
+        max: u64,
+    },
+}
+
+/// Error reported when an entry of a segment table breaks one of the [`SegmentRules`].
+///
+/// Returned by [`SegmentRules::validate`], and wrapped in an `io::Error` of
+/// kind `ErrorKind::InvalidData` by [`SegmentedReader::validated`]; use
+/// [`InvalidSegment::from_io`] to get it back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidSegment {
+    index: usize,
+    segment: (u64, u64),
+    violation: SegmentViolation,
+}
+
+impl InvalidSegment {
+    /// Returns the index of the offending entry in the table.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the `(offset, length)` of the offending entry.
+    pub fn segment(&self) -> (u64, u64) {
+        self.segment
+    }
+
+    /// Returns the rule the entry broke.
+    pub fn violation(&self) -> SegmentViolation {
+        self.violation
+    }
+
+    /// Returns the `InvalidSegment` payload of an I/O error, if it has one.
+    pub fn from_io(err: &io::Error) -> Option<&InvalidSegment> {
+        err.get_ref()?.downcast_ref()
+    }
+}
+
+impl fmt::Display for InvalidSegment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (offset, len) = self.segment;
+        write!(f, "segment {} ({len} bytes at {offset}) ", self.index)?;
+        match self.violation {
+            SegmentViolation::OutOfBounds { file_len } => {
+                write!(f, "ends past the end of the {file_len} byte file")
+            }
+            SegmentViolation::Overlap { other } => write!(f, "overlaps segment {other}"),
+            SegmentViolation::TooMany { max } => write!(f, "is past the cap of {max} segments"),
+            SegmentViolation::TotalTooLarge { max } => {
+                write!(f, "takes the total length past {max} bytes")
+            }
+        }
+    }
+}
+
+impl Error for InvalidSegment {}
+
+impl From<InvalidSegment> for io::Error {
+    fn from(err: InvalidSegment) -> Self {
+        io::Error::new(ErrorKind::InvalidData, err)
+    }
+}
+
+impl SegmentRules {
+    /// Checks every entry of `segments` against the rules, for a file of `file_len` bytes.
+    ///
+    /// Entries are checked in order, and the first violation is returned;
+    /// overlaps are looked for once all entries passed the other checks.
+    pub fn validate(&self, segments: &[(u64, u64)], file_len: u64) -> Result<(), InvalidSegment> {
+        let invalid = |index: usize, violation| InvalidSegment {
+            index,
+            segment: segments[index],
+            violation,
+        };
+        if segments.len() > self.max_segments {
+            let max = self.max_segments;
+            return Err(invalid(max, SegmentViolation::TooMany { max }));
+        }
+        let mut total = 0u64;
+        for (index, &(offset, len)) in segments.iter().enumerate() {
+            if offset.checked_add(len).is_none_or(|end| end > file_len) {
+                return Err(invalid(index, SegmentViolation::OutOfBounds { file_len }));
+            }
+            total = total.saturating_add(len);
+            if total > self.max_total_len {
+                let max = self.max_total_len;
+                return Err(invalid(index, SegmentViolation::TotalTooLarge { max }));
+            }
+        }
+        if !self.allow_overlap {
+            let mut order: Vec<usize> =
+                (0..segments.len()).filter(|&i| segments[i].1 > 0).collect();
+            order.sort_by_key(|&i| segments[i]);
+            for pair in order.windows(2) {
+                let (first, second) = (pair[0], pair[1]);
+                let (offset, len) = segments[first];
+                if offset + len > segments[second].0 {
+                    let (index, other) = (first.max(second), first.min(second));
+                    return Err(invalid(index, SegmentViolation::Overlap { other }));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A reader that visits a sequence of `(offset, length)` segments of a borrowed reader.
+///
+/// [`SegmentedReader::next_segment`] seeks to the start of the next segment
+/// and returns a [`RefTake`] limited to its length. Segments may come in any
+/// order and may overlap, as with the central directory of an archive.
+pub struct SegmentedReader<'a, R, I> {
+    inner: &'a mut R,
+    segments: I,
+    current: Option<(u64, u64)>,
+}
+
+impl<'a, R, I> SegmentedReader<'a, R, I>
+where
+    I: Iterator<Item = (u64, u64)>,
+{
+    /// Creates a new `SegmentedReader` over `inner` visiting the given `(offset, length)` segments.
+    pub fn wrap<S>(inner: &'a mut R, segments: S) -> Self
+    where
+        S: IntoIterator<IntoIter = I>,
+    {
+        Self {
+            inner,
+            segments: segments.into_iter(),
+            current: None,
+        }
+    }
+
+    /// Returns the `(offset, length)` of the segment most recently returned.
+    pub fn current_segment(&self) -> Option<(u64, u64)> {
+        self.current
+    }
+}
+
+impl<'a, 's, R: Seek> SegmentedReader<'a, R, Copied<slice::Iter<'s, (u64, u64)>>> {
+    /// Creates a new `SegmentedReader` over an untrusted segment table, once
+    /// every entry has been checked against `rules`.
+    ///
+    /// The length of the file is found by seeking to its end; the position
+    /// of `inner` is restored afterwards. A table breaking a rule fails with
+    /// an [`InvalidSegment`] error before anything is read.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use reftake::{InvalidSegment, SegmentRules, SegmentViolation, SegmentedReader};
+    ///
+    /// let mut archive = Cursor::new(vec![0u8; 100]);
+    /// let directory = [(0, 40), (30, 20)];
+    /// let rules = SegmentRules { allow_overlap: false, ..SegmentRules::default() };
+    ///
+    /// let err = SegmentedReader::validated(&mut archive, &directory, &rules).err().unwrap();
+    /// let invalid = InvalidSegment::from_io(&err).unwrap();
+    /// assert_eq!(invalid.index(), 1);
+    /// assert_eq!(invalid.violation(), SegmentViolation::Overlap { other: 0 });
+    /// ```
+    pub fn validated(
+        inner: &'a mut R,
+        segments: &'s [(u64, u64)],
+        rules: &SegmentRules,
+    ) -> io::Result<Self> {
+        let position = inner.stream_position()?;
+        let file_len = inner.seek(SeekFrom::End(0))?;
+        inner.seek(SeekFrom::Start(position))?;
+        rules.validate(segments, file_len)?;
+        Ok(Self::wrap(inner, segments.iter().copied()))
+    }
+}
+
+impl<R: Read + Seek, I> SegmentedReader<'_, R, I>
+where
+    I: Iterator<Item = (u64, u64)>,
+{
+    /// Seeks to the next segment and returns a window over it, or `None` when all have been visited.
+    pub fn next_segment(&mut self) -> io::Result<Option<RefTake<'_, R>>> {
+        let Some((offset, len)) = self.segments.next() else {
+            self.current = None;
+            return Ok(None);
+        };
+        self.inner.seek(SeekFrom::Start(offset))?;
+        self.current = Some((offset, len));
+        Ok(Some(RefTake::wrap(self.inner, len)))
+    }
+}
+
+/// Extension trait to provide a `segments_ref` method on all `Read + Seek` types.
+pub trait SegmentedReaderExt {
+    /// Wraps the reader in a `SegmentedReader` visiting the given `(offset, length)` segments.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Cursor, Read};
+    /// use reftake::SegmentedReaderExt;
+    ///
+    /// let mut archive = Cursor::new(b"....first....second");
+    /// let directory = vec![(13, 6), (4, 5)];
+    /// let mut entries = archive.segments_ref(directory);
+    ///
+    /// let mut names = Vec::new();
+    /// while let Some(mut entry) = entries.next_segment().unwrap() {
+    ///     let mut name = String::new();
+    ///     entry.read_to_string(&mut name).unwrap();
+    ///     names.push(name);
+    /// }
+    /// assert_eq!(names, vec!["second", "first"]);
+    /// ```
+    fn segments_ref<S>(&mut self, segments: S) -> SegmentedReader<'_, Self, S::IntoIter>
+    where
+        Self: Sized,
+        S: IntoIterator<Item = (u64, u64)>;
+}
+
+impl<T: Read + Seek> SegmentedReaderExt for T {
+    fn segments_ref<S>(&mut self, segments: S) -> SegmentedReader<'_, Self, S::IntoIter>
+    where
+        S: IntoIterator<Item = (u64, u64)>,
+    {
+        SegmentedReader::wrap(self, segments)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_overlapping_segments() {
+        let mut reader = Cursor::new(b"0123456789");
+        let mut segments = reader.segments_ref([(2, 4), (4, 4)]);
+
+        let mut buf = Vec::new();
+        segments
+            .next_segment()
+            .unwrap()
+            .unwrap()
+            .read_to_end(&mut buf)
+            .unwrap();
+        assert_eq!(buf, b"2345");
+        assert_eq!(segments.current_segment(), Some((2, 4)));
+
+        buf.clear();
+        segments
+            .next_segment()
+            .unwrap()
+            .unwrap()
+            .read_to_end(&mut buf)
+            .unwrap();
+        assert_eq!(buf, b"4567");
+        assert!(segments.next_segment().unwrap().is_none());
+        assert_eq!(segments.current_segment(), None);
+    }
+
+    #[test]
+    fn test_validation_reports_the_broken_rule() {
+        let rules = SegmentRules {
+            allow_overlap: false,
+            max_segments: 3,
+            max_total_len: 50,
+        };
+        let violation = |segments: &[(u64, u64)]| {
+            let err = rules.validate(segments, 100).unwrap_err();
+            (err.index(), err.violation())
+        };
+        assert_eq!(
+            violation(&[(0, 10), (95, 10)]),
+            (1, SegmentViolation::OutOfBounds { file_len: 100 })
+        );
+        assert_eq!(
+            violation(&[(0, 10), (u64::MAX, 2)]),
+            (1, SegmentViolation::OutOfBounds { file_len: 100 })
+        );
+        assert_eq!(
+            violation(&[(0, 1), (1, 1), (2, 1), (3, 1)]),
+            (3, SegmentViolation::TooMany { max: 3 })
+        );
+        assert_eq!(
+            violation(&[(0, 30), (30, 30)]),
+            (1, SegmentViolation::TotalTooLarge { max: 50 })
+        );
+        assert_eq!(
+            violation(&[(50, 10), (20, 5), (55, 1)]),
+            (2, SegmentViolation::Overlap { other: 0 })
+        );
+        assert!(rules.validate(&[(50, 10), (60, 0), (60, 5)], 100).is_ok());
+        assert!(
+            SegmentRules::default()
+                .validate(&[(0, 10), (5, 10)], 15)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_validated_reader_keeps_position() {
+        let mut reader = Cursor::new(b"0123456789");
+        reader.set_position(3);
+        let err = SegmentedReader::validated(&mut reader, &[(8, 5)], &SegmentRules::default())
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(
+            err.to_string(),
+            "segment 0 (5 bytes at 8) ends past the end of the 10 byte file"
+        );
+        assert_eq!(reader.position(), 3);
+
+        let table = [(8, 2)];
+        let mut segments =
+            SegmentedReader::validated(&mut reader, &table, &SegmentRules::default()).unwrap();
+        let mut buf = Vec::new();
+        segments
+            .next_segment()
+            .unwrap()
+            .unwrap()
+            .read_to_end(&mut buf)
+            .unwrap();
+        assert_eq!(buf, b"89");
+    }
+
+    #[test]
+    fn test_segment_past_end_is_short() {
+        let mut reader = Cursor::new(b"abc");
+        let mut segments = reader.segments_ref([(1, 10)]);
+        let mut buf = Vec::new();
+        segments
+            .next_segment()
+            .unwrap()
+            .unwrap()
+            .read_to_end(&mut buf)
+            .unwrap();
+        assert_eq!(buf, b"bc");
+    }
+}
@@ -0,0 +1,216 @@
+//! `embedded_hal_nb` serial receivers as `embedded_io` readers.
+
+use core::fmt;
+
+use embedded_hal_nb::{
+    nb,
+    serial::{self, ErrorKind as SerialErrorKind},
+};
+use embedded_io::{ErrorKind, ErrorType, Read};
+
+/// Error of a [`SerialReader`], wrapping the receiver's own error.
+///
+/// Framing, parity and noise errors are reported as
+/// `ErrorKind::InvalidData`, everything else as `ErrorKind::Other`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SerialError<E>(E);
+
+impl<E> SerialError<E> {
+    /// Returns the receiver's error.
+    pub fn into_inner(self) -> E {
+        self.0
+    }
+}
+
+impl<E: fmt::Debug> fmt::Display for SerialError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "serial receive error: {:?}", self.0)
+    }
+}
+
+impl<E: fmt::Debug> core::error::Error for SerialError<E> {}
+
+impl<E: serial::Error> embedded_io::Error for SerialError<E> {
+    fn kind(&self) -> ErrorKind {
+        match self.0.kind() {
+            SerialErrorKind::FrameFormat | SerialErrorKind::Parity | SerialErrorKind::Noise => {
+                ErrorKind::InvalidData
+            }
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
+/// An `embedded_io::Read` over a borrowed word-at-a-time serial receiver.
+///
+/// Each `read` blocks until the first byte arrives, then takes the bytes
+/// the receiver already holds without waiting for more. Words are pulled
+/// one at a time, so wrapping the reader in a [`RefTake`](crate::RefTake)
+/// never takes a byte beyond the window off the UART: fixed-length frames
+/// can be read back to back, with the same `read_exact` and
+/// [`read_until_into`](crate::read_until_into) calls used on other readers.
+///
+/// A receive error after some bytes of a read is held back and returned by
+/// the next call, so the bytes before it are not lost.
+pub struct SerialReader<'a, S: serial::ErrorType> {
+    inner: &'a mut S,
+    pending_error: Option<S::Error>,
+}
+
+impl<'a, S: serial::ErrorType> SerialReader<'a, S> {
+    /// Creates a new `SerialReader` over the given receiver.
+    pub const fn wrap(inner: &'a mut S) -> Self {
+        Self {
+            inner,
+            pending_error: None,
+        }
+    }
+}
+
+impl<S: serial::ErrorType> ErrorType for SerialReader<'_, S> {
+    type Error = SerialError<S::Error>;
+}
+
+impl<S: serial::Read> Read for SerialReader<'_, S> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if let Some(e) = self.pending_error.take() {
+            return Err(SerialError(e));
+        }
+
+        buf[0] = nb::block!(self.inner.read()).map_err(SerialError)?;
+        let mut n = 1;
+        while n < buf.len() {
+            match self.inner.read() {
+                Ok(byte) => {
+                    buf[n] = byte;
+                    n += 1;
+                }
+                Err(nb::Error::WouldBlock) => break,
+                Err(nb::Error::Other(e)) => {
+                    self.pending_error = Some(e);
+                    break;
+                }
+            }
+        }
+        Ok(n)
+    }
+}
+
+/// Extension trait to provide a `serial_ref` method on all `embedded_hal_nb::serial::Read` types.
+pub trait SerialReaderExt: serial::ErrorType {
+    /// Wraps the receiver in a `SerialReader`, usable as an `embedded_io::Read`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use embedded_hal_nb::{nb, serial};
+    /// use embedded_io::Read;
+    /// use reftake::{EmbeddedIoRefTakeExt, SerialReaderExt};
+    ///
+    /// struct Uart(&'static [u8]);
+    ///
+    /// impl serial::ErrorType for Uart {
+    ///     type Error = serial::ErrorKind;
+    /// }
+    ///
+    /// impl serial::Read for Uart {
+    ///     fn read(&mut self) -> nb::Result<u8, Self::Error> {
+    ///         let (&byte, rest) = self.0.split_first().ok_or(nb::Error::WouldBlock)?;
+    ///         self.0 = rest;
+    ///         Ok(byte)
+    ///     }
+    /// }
+    ///
+    /// let mut uart = Uart(&[0x02, 0x10, 0x20, 0x03, 0x02]);
+    /// let mut frame = [0u8; 4];
+    /// uart.serial_ref().take_ref_embedded_io(4).read_exact(&mut frame).unwrap();
+    /// assert_eq!(frame, [0x02, 0x10, 0x20, 0x03]);
+    /// assert_eq!(uart.0, [0x02]);
+    /// ```
+    fn serial_ref(&mut self) -> SerialReader<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: serial::Read> SerialReaderExt for T {
+    fn serial_ref(&mut self) -> SerialReader<'_, Self> {
+        SerialReader::wrap(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EmbeddedIoRefTakeExt;
+    use embedded_io::Error;
+
+    /// Replays a fixed script of receiver results, then always blocks.
+    struct Script {
+        steps: &'static [nb::Result<u8, SerialErrorKind>],
+        pos: usize,
+    }
+
+    impl serial::ErrorType for Script {
+        type Error = SerialErrorKind;
+    }
+
+    impl serial::Read for Script {
+        fn read(&mut self) -> nb::Result<u8, Self::Error> {
+            let step = self.steps.get(self.pos).copied();
+            self.pos += 1;
+            step.unwrap_or(Err(nb::Error::WouldBlock))
+        }
+    }
+
+    #[test]
+    fn test_read_returns_bytes_already_received() {
+        let mut uart = Script {
+            steps: &[
+                Err(nb::Error::WouldBlock),
+                Ok(b'a'),
+                Ok(b'b'),
+                Err(nb::Error::WouldBlock),
+                Ok(b'c'),
+            ],
+            pos: 0,
+        };
+        let mut reader = uart.serial_ref();
+        let mut buf = [0u8; 8];
+
+        assert_eq!(reader.read(&mut buf).unwrap(), 2);
+        assert_eq!(&buf[..2], b"ab");
+        assert_eq!(reader.read(&mut buf).unwrap(), 1);
+        assert_eq!(buf[0], b'c');
+    }
+
+    #[test]
+    fn test_window_does_not_take_extra_words() {
+        let mut uart = Script {
+            steps: &[Ok(1), Ok(2), Ok(3), Ok(4)],
+            pos: 0,
+        };
+        let mut reader = uart.serial_ref();
+        let mut buf = [0u8; 8];
+
+        assert_eq!(reader.take_ref_embedded_io(3).read(&mut buf).unwrap(), 3);
+        assert_eq!(uart.pos, 3);
+    }
+
+    #[test]
+    fn test_error_after_data_is_reported_next() {
+        let mut uart = Script {
+            steps: &[Ok(b'x'), Err(nb::Error::Other(SerialErrorKind::Parity))],
+            pos: 0,
+        };
+        let mut reader = uart.serial_ref();
+        let mut buf = [0u8; 4];
+
+        assert_eq!(reader.read(&mut buf).unwrap(), 1);
+        let err = reader.read(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(err.into_inner(), SerialErrorKind::Parity);
+    }
+}
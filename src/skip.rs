@@ -0,0 +1,209 @@
+//! An adapter that discards a fixed number of leading bytes from a borrowed reader.
+
+#[cfg(feature = "std")]
+use std::io::{self, BufRead, ErrorKind, Read, Seek, SeekFrom};
+
+/// A non-owning adapter that skips the first `n` bytes of a reader,
+/// then passes everything else through unchanged.
+///
+/// The skip is performed lazily on the first `read` or `fill_buf` call.
+/// `BufRead` readers are skipped with `fill_buf`/`consume`, without copying;
+/// plain `Read` readers are drained through a small stack buffer. When the
+/// inner reader implements `Seek`, [`RefSkip::seek_past`] performs the skip
+/// with a single relative seek instead.
+pub struct RefSkip<'a, R> {
+    // Only read by the trait implementations, which a bare core build has none of
+    #[cfg_attr(not(any(feature = "std", feature = "embedded-io")), allow(dead_code))]
+    pub(crate) inner: &'a mut R,
+    pub(crate) skip: u64,
+}
+
+impl<'a, R> RefSkip<'a, R> {
+    /// Creates a new `RefSkip` that discards the first `skip` bytes of the given reader reference.
+    pub const fn wrap(inner: &'a mut R, skip: u64) -> Self {
+        Self { inner, skip }
+    }
+
+    /// Returns the number of bytes that still have to be discarded.
+    pub fn remaining_skip(&self) -> u64 {
+        self.skip
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Seek> RefSkip<'_, R> {
+    /// Performs the pending skip immediately by seeking forward.
+    ///
+    /// Seeking past the end of the stream is allowed, in which case subsequent
+    /// reads return EOF.
+    pub fn seek_past(&mut self) -> io::Result<()> {
+        discard_by_seeking(self.inner, &mut self.skip)
+    }
+}
+
+#[cfg(feature = "std")]
+/// Discards up to `*skip` bytes by reading them into a scratch buffer,
+/// decrementing `*skip` as it goes.
+///
+/// Returns `false` if the reader hit EOF before the skip was complete.
+pub(crate) fn discard_by_reading<R: Read + ?Sized>(
+    inner: &mut R,
+    skip: &mut u64,
+) -> io::Result<bool> {
+    let mut scratch = [0u8; 8 * 1024];
+    while *skip > 0 {
+        let max = (*skip).min(scratch.len() as u64) as usize;
+        match inner.read(&mut scratch[..max]) {
+            Ok(0) => return Ok(false),
+            Ok(n) => *skip -= n as u64,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(true)
+}
+
+#[cfg(feature = "std")]
+/// Discards up to `*skip` bytes with `fill_buf`/`consume`, decrementing
+/// `*skip` as it goes.
+///
+/// Returns `false` if the reader hit EOF before the skip was complete.
+pub(crate) fn discard_buffered<R: BufRead + ?Sized>(
+    inner: &mut R,
+    skip: &mut u64,
+) -> io::Result<bool> {
+    while *skip > 0 {
+        let available = match inner.fill_buf() {
+            Ok([]) => return Ok(false),
+            Ok(buf) => buf.len(),
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        let amt = (*skip).min(available as u64) as usize;
+        inner.consume(amt);
+        *skip -= amt as u64;
+    }
+    Ok(true)
+}
+
+#[cfg(feature = "std")]
+/// Performs a pending skip on a seekable reader with relative seeks.
+pub(crate) fn discard_by_seeking<R: Seek + ?Sized>(
+    inner: &mut R,
+    skip: &mut u64,
+) -> io::Result<()> {
+    while *skip > 0 {
+        let step = (*skip).min(i64::MAX as u64);
+        inner.seek(SeekFrom::Current(step as i64))?;
+        *skip -= step;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "std")]
+/// Implements the `Read` trait, discarding the pending prefix on first use.
+///
+/// If the inner reader ends before the prefix has been skipped, `Ok(0)` is
+/// returned and the rest of the skip is retried on the next call.
+impl<R: Read> Read for RefSkip<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        if self.skip > 0 && !discard_by_reading(self.inner, &mut self.skip)? {
+            return Ok(0);
+        }
+        self.inner.read(buf)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: BufRead> BufRead for RefSkip<'_, R> {
+    fn fill_buf(&mut self) -> Result<&[u8], io::Error> {
+        if self.skip > 0 && !discard_buffered(self.inner, &mut self.skip)? {
+            return Ok(&[]);
+        }
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.inner.consume(amt);
+    }
+}
+
+#[cfg(feature = "std")]
+/// Extension trait to provide a `skip_ref` method on all `Read` types.
+pub trait RefSkipExt {
+    /// Wraps the reader in a `RefSkip` that discards the first `skip` bytes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Cursor, Read};
+    /// use reftake::RefSkipExt;
+    ///
+    /// let mut cursor = Cursor::new(b"HDR:payload");
+    /// let mut buf = String::new();
+    /// cursor.skip_ref(4).read_to_string(&mut buf).unwrap();
+    /// assert_eq!(buf, "payload");
+    /// ```
+    fn skip_ref(&mut self, skip: u64) -> RefSkip<'_, Self>
+    where
+        Self: Sized;
+}
+
+#[cfg(feature = "std")]
+impl<T: Read> RefSkipExt for T {
+    fn skip_ref(&mut self, skip: u64) -> RefSkip<'_, Self> {
+        RefSkip::wrap(self, skip)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::RefTakeExt;
+    use std::io::{BufReader, Cursor};
+
+    #[test]
+    fn test_read_skips_prefix() {
+        let mut reader = Cursor::new(b"0123456789");
+        let mut skip = reader.skip_ref(3);
+        assert_eq!(skip.remaining_skip(), 3);
+
+        let mut buf = [0u8; 4];
+        let n = skip.read(&mut buf).unwrap();
+        assert_eq!(&buf[..n], b"3456");
+        assert_eq!(skip.remaining_skip(), 0);
+    }
+
+    #[test]
+    fn test_skip_past_eof() {
+        let mut reader = Cursor::new(b"abc");
+        let mut skip = reader.skip_ref(10);
+
+        let mut buf = [0u8; 4];
+        assert_eq!(skip.read(&mut buf).unwrap(), 0);
+        assert_eq!(skip.remaining_skip(), 7);
+    }
+
+    #[test]
+    fn test_bufread_skips_prefix() {
+        let mut reader = BufReader::with_capacity(2, Cursor::new(b"skip:line\nrest"));
+        let mut skip = reader.skip_ref(5);
+
+        let mut line = String::new();
+        skip.read_line(&mut line).unwrap();
+        assert_eq!(line, "line\n");
+    }
+
+    #[test]
+    fn test_seek_past_then_take() {
+        let mut reader = Cursor::new(b"headerbody-trailer");
+        let mut skip = reader.skip_ref(6);
+        skip.seek_past().unwrap();
+        assert_eq!(skip.remaining_skip(), 0);
+
+        let mut body = String::new();
+        skip.take_ref(4).read_to_string(&mut body).unwrap();
+        assert_eq!(body, "body");
+        assert_eq!(reader.position(), 10);
+    }
+}
@@ -0,0 +1,244 @@
+//! Zero-copy iteration over the buffered contents of a [`RefTake`] window.
+
+use std::io::{self, BufRead};
+
+use crate::RefTake;
+
+/// A lending iterator over the `fill_buf` slices of a [`RefTake`] window.
+///
+/// Each call to [`Slices::next_slice`] first consumes the slice returned by
+/// the previous call, then returns the next buffered chunk, clamped to the
+/// remaining limit. The last returned slice is consumed when the iterator
+/// is dropped.
+///
+/// A borrowed slice can't outlive the next call, so the zero-copy forms
+/// are methods: [`Slices::next_chunk`] has the shape of `Iterator::next`,
+/// for a `while let` loop on the hot path. As a convenience `Slices` also
+/// implements `Iterator`, for `for` loops and iterator chains, but each of
+/// its items is an owned copy of the chunk.
+///
+/// Created by [`RefTake::slices`].
+pub struct Slices<'s, 'a, R: BufRead> {
+    take: &'s mut RefTake<'a, R>,
+    pending: usize,
+}
+
+impl<R: BufRead> Slices<'_, '_, R> {
+    /// Consumes the previously returned slice and returns the next one.
+    ///
+    /// Returns `Ok(None)` once the limit is reached or the inner reader is at EOF.
+    pub fn next_slice(&mut self) -> io::Result<Option<&[u8]>> {
+        self.release();
+        let buf = self.take.fill_buf()?;
+        if buf.is_empty() {
+            return Ok(None);
+        }
+        self.pending = buf.len();
+        Ok(Some(buf))
+    }
+
+    /// Like [`next_slice`](Slices::next_slice), with the result shaped like `Iterator::next`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{BufReader, Cursor};
+    /// use reftake::RefTakeExt;
+    ///
+    /// let mut reader = BufReader::with_capacity(4, Cursor::new(b"hello world"));
+    /// let mut take = reader.take_ref(7);
+    ///
+    /// let mut len = 0;
+    /// let mut slices = take.slices();
+    /// while let Some(chunk) = slices.next_chunk() {
+    ///     len += chunk.unwrap().len();
+    /// }
+    /// assert_eq!(len, 7);
+    /// ```
+    pub fn next_chunk(&mut self) -> Option<io::Result<&[u8]>> {
+        self.next_slice().transpose()
+    }
+
+    fn release(&mut self) {
+        if self.pending > 0 {
+            self.take.consume(self.pending);
+            self.pending = 0;
+        }
+    }
+}
+
+/// Yields a copy of each chunk, which costs an allocation per chunk; use
+/// [`Slices::next_chunk`] to avoid it. The error of a failed `fill_buf` is
+/// yielded once and the next call retries it.
+impl<R: BufRead> Iterator for Slices<'_, '_, R> {
+    type Item = io::Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_chunk().map(|chunk| chunk.map(<[u8]>::to_vec))
+    }
+}
+
+impl<R: BufRead> Drop for Slices<'_, '_, R> {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
+impl<'a, R: BufRead> RefTake<'a, R> {
+    /// Returns a lending iterator over the buffered slices of the window.
+    ///
+    /// Iterating it with `for` instead yields an owned copy of each slice;
+    /// [`Slices::next_chunk`] is the zero-copy equivalent.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{BufReader, Cursor};
+    /// use reftake::RefTakeExt;
+    ///
+    /// let mut reader = BufReader::with_capacity(4, Cursor::new(b"hello world"));
+    /// let mut take = reader.take_ref(7);
+    ///
+    /// let mut seen = Vec::new();
+    /// let mut slices = take.slices();
+    /// while let Some(chunk) = slices.next_slice().unwrap() {
+    ///     seen.extend_from_slice(chunk);
+    /// }
+    /// assert_eq!(seen, b"hello w");
+    /// ```
+    pub fn slices(&mut self) -> Slices<'_, 'a, R> {
+        Slices {
+            take: self,
+            pending: 0,
+        }
+    }
+
+    /// Calls `f` with every buffered slice of the window until the limit or EOF
+    /// is reached, consuming each slice after `f` returns successfully.
+    ///
+    /// If `f` returns an error, the slice it was given is left unconsumed.
+    pub fn for_each_slice<F>(&mut self, mut f: F) -> io::Result<()>
+    where
+        F: FnMut(&[u8]) -> io::Result<()>,
+    {
+        loop {
+            let buf = self.fill_buf()?;
+            if buf.is_empty() {
+                return Ok(());
+            }
+            let n = buf.len();
+            f(buf)?;
+            self.consume(n);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::RefTakeExt;
+    use std::io::{self, BufReader, Cursor, Read};
+
+    #[test]
+    fn test_slices_yield_whole_window() {
+        let mut reader = BufReader::with_capacity(3, Cursor::new(b"abcdefgh"));
+        let mut take = reader.take_ref(7);
+
+        let mut chunks = Vec::new();
+        let mut slices = take.slices();
+        while let Some(chunk) = slices.next_slice().unwrap() {
+            chunks.push(chunk.to_vec());
+        }
+        drop(slices);
+
+        assert_eq!(
+            chunks,
+            vec![b"abc".to_vec(), b"def".to_vec(), b"g".to_vec()]
+        );
+        assert_eq!(take.current_limit(), 0);
+    }
+
+    #[test]
+    fn test_slices_in_a_for_loop() {
+        let mut reader = BufReader::with_capacity(3, Cursor::new(b"abcdefgh"));
+        let mut take = reader.take_ref(7);
+
+        let mut chunks = Vec::new();
+        for chunk in take.slices() {
+            chunks.push(chunk.unwrap());
+        }
+        assert_eq!(chunks, [&b"abc"[..], b"def", b"g"]);
+        assert_eq!(take.current_limit(), 0);
+
+        // Mixing both forms: the borrowed slice is consumed by the next call either way
+        let mut reader = BufReader::with_capacity(2, Cursor::new(b"abcdef"));
+        let mut take = reader.take_ref(6);
+        let mut slices = take.slices();
+        assert_eq!(slices.next_slice().unwrap(), Some(&b"ab"[..]));
+        let rest: Vec<u8> = slices.flat_map(Result::unwrap).collect();
+        assert_eq!(rest, b"cdef");
+    }
+
+    #[test]
+    fn test_next_chunk_borrows_and_retries_errors() {
+        struct FailOnce<'d> {
+            data: &'d [u8],
+            failed: bool,
+        }
+
+        impl Read for FailOnce<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                if !self.failed && self.data.len() < 4 {
+                    self.failed = true;
+                    return Err(io::Error::other("flaky"));
+                }
+                self.data.read(buf)
+            }
+        }
+
+        let mut reader = BufReader::with_capacity(
+            3,
+            FailOnce {
+                data: b"abcdef",
+                failed: false,
+            },
+        );
+        let mut take = reader.take_ref(5);
+        let mut slices = take.slices();
+        assert_eq!(slices.next_chunk().unwrap().unwrap(), b"abc");
+        assert_eq!(
+            slices.next_chunk().unwrap().unwrap_err().to_string(),
+            "flaky"
+        );
+        assert_eq!(slices.next_chunk().unwrap().unwrap(), b"de");
+        assert!(slices.next_chunk().is_none());
+    }
+
+    #[test]
+    fn test_slices_drop_consumes_last_slice() {
+        let mut reader = BufReader::with_capacity(4, Cursor::new(b"abcdefgh"));
+        {
+            let mut take = reader.take_ref(6);
+            let mut slices = take.slices();
+            assert_eq!(slices.next_slice().unwrap(), Some(&b"abcd"[..]));
+        }
+
+        let mut rest = String::new();
+        reader.read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, "efgh");
+    }
+
+    #[test]
+    fn test_for_each_slice() {
+        let mut reader = BufReader::with_capacity(2, Cursor::new(b"hello world"));
+        let mut take = reader.take_ref(5);
+
+        let mut seen = Vec::new();
+        take.for_each_slice(|chunk| {
+            seen.extend_from_slice(chunk);
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(seen, b"hello");
+        assert_eq!(take.current_limit(), 0);
+    }
+}
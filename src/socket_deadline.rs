@@ -0,0 +1,223 @@
+//! Windows of a blocking socket read within a deadline, kept by its read timeout.
+
+use std::{
+    cmp,
+    io::{self, ErrorKind, Read},
+    net::TcpStream,
+    time::{Duration, Instant},
+};
+
+/// A socket with a read timeout, which [`RefTakeTimed`] sets to the time left.
+pub trait ReadTimeout {
+    /// Returns the read timeout of the socket, `None` when reads block indefinitely.
+    fn read_timeout(&self) -> io::Result<Option<Duration>>;
+
+    /// Sets the read timeout of the socket.
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()>;
+}
+
+impl ReadTimeout for TcpStream {
+    fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        TcpStream::read_timeout(self)
+    }
+
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        TcpStream::set_read_timeout(self, timeout)
+    }
+}
+
+#[cfg(unix)]
+impl ReadTimeout for std::os::unix::net::UnixStream {
+    fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        std::os::unix::net::UnixStream::read_timeout(self)
+    }
+
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        std::os::unix::net::UnixStream::set_read_timeout(self, timeout)
+    }
+}
+
+/// A non-owning adapter reading at most `limit` bytes of a socket before a deadline.
+///
+/// Unlike [`RefDeadline`](crate::RefDeadline), which only checks the
+/// deadline between reads, this sets the read timeout of the socket to the
+/// time left before every read, so a read blocked on a silent peer fails
+/// at the deadline too, without a watchdog thread. Past the deadline reads
+/// fail with `ErrorKind::TimedOut`. The timeout the socket had before is
+/// still honored when it is shorter, and is put back when the window is
+/// dropped.
+pub struct RefTakeTimed<'a, S: ReadTimeout> {
+    inner: &'a mut S,
+    limit: u64,
+    deadline: Instant,
+    /// The read timeout of the socket before the window.
+    original: Option<Duration>,
+}
+
+impl<'a, S: ReadTimeout> RefTakeTimed<'a, S> {
+    /// Creates a new `RefTakeTimed` of at most `limit` bytes, read before `deadline`.
+    ///
+    /// Fails if the read timeout of the socket can't be queried.
+    pub fn wrap(inner: &'a mut S, limit: u64, deadline: Instant) -> io::Result<Self> {
+        let original = inner.read_timeout()?;
+        Ok(Self {
+            inner,
+            limit,
+            deadline,
+            original,
+        })
+    }
+
+    /// Returns the number of bytes that can be read before the window ends.
+    pub fn current_limit(&self) -> u64 {
+        self.limit
+    }
+
+    /// Returns the deadline after which reads fail.
+    pub fn deadline(&self) -> Instant {
+        self.deadline
+    }
+
+    /// Returns the time left until the deadline, or zero if it has passed.
+    pub fn time_remaining(&self) -> Duration {
+        self.deadline.saturating_duration_since(Instant::now())
+    }
+}
+
+fn deadline_exceeded() -> io::Error {
+    io::Error::new(ErrorKind::TimedOut, "read deadline exceeded")
+}
+
+impl<S: ReadTimeout + Read> Read for RefTakeTimed<'_, S> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        // Don't call into inner reader at all at EOF because it may still block
+        if self.limit == 0 || buf.is_empty() {
+            return Ok(0);
+        }
+        let left = self.time_remaining();
+        if left.is_zero() {
+            return Err(deadline_exceeded());
+        }
+        let timeout = self
+            .original
+            .map_or(left, |original| cmp::min(original, left));
+        self.inner.set_read_timeout(Some(timeout))?;
+
+        let max = cmp::min(buf.len() as u64, self.limit) as usize;
+        match self.inner.read(&mut buf[..max]) {
+            Ok(n) => {
+                self.limit -= n as u64;
+                Ok(n)
+            }
+            // Unix reports a timed out socket read as `WouldBlock`, Windows as `TimedOut`
+            Err(e)
+                if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut)
+                    && Instant::now() >= self.deadline =>
+            {
+                Err(deadline_exceeded())
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl<S: ReadTimeout> Drop for RefTakeTimed<'_, S> {
+    fn drop(&mut self) {
+        // Nothing can be done about a socket that refuses its old timeout back
+        let _ = self.inner.set_read_timeout(self.original);
+    }
+}
+
+/// Extension trait to provide a `take_ref_timed` method on sockets.
+pub trait RefTakeTimedExt: ReadTimeout {
+    /// Wraps the socket in a `RefTakeTimed` of at most `limit` bytes read within `timeout`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::io::Read;
+    /// use std::net::TcpStream;
+    /// use std::time::Duration;
+    /// use reftake::RefTakeTimedExt;
+    ///
+    /// let mut stream = TcpStream::connect("127.0.0.1:7000").unwrap();
+    /// let mut frame = Vec::new();
+    /// stream
+    ///     .take_ref_timed(4096, Duration::from_secs(2))
+    ///     .unwrap()
+    ///     .read_to_end(&mut frame)
+    ///     .unwrap();
+    /// ```
+    fn take_ref_timed(
+        &mut self,
+        limit: u64,
+        timeout: Duration,
+    ) -> io::Result<RefTakeTimed<'_, Self>>
+    where
+        Self: Sized;
+}
+
+impl<T: ReadTimeout> RefTakeTimedExt for T {
+    fn take_ref_timed(
+        &mut self,
+        limit: u64,
+        timeout: Duration,
+    ) -> io::Result<RefTakeTimed<'_, Self>> {
+        RefTakeTimed::wrap(self, limit, Instant::now() + timeout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{io::Write, net::TcpListener};
+
+    fn pair() -> (TcpStream, TcpStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server, _) = listener.accept().unwrap();
+        (client, server)
+    }
+
+    #[test]
+    fn test_limit_and_restored_timeout() {
+        let (mut client, mut server) = pair();
+        server.write_all(b"frame one|frame two").unwrap();
+        client
+            .set_read_timeout(Some(Duration::from_secs(30)))
+            .unwrap();
+
+        let mut frame = String::new();
+        let mut timed = client.take_ref_timed(9, Duration::from_secs(10)).unwrap();
+        timed.read_to_string(&mut frame).unwrap();
+        assert_eq!(timed.current_limit(), 0);
+        drop(timed);
+        assert_eq!(frame, "frame one");
+        assert_eq!(
+            client.read_timeout().unwrap(),
+            Some(Duration::from_secs(30))
+        );
+    }
+
+    #[test]
+    fn test_blocked_read_fails_at_the_deadline() {
+        let (mut client, mut server) = pair();
+        server.write_all(b"abc").unwrap();
+
+        let start = Instant::now();
+        let mut buf = Vec::new();
+        let mut timed = client
+            .take_ref_timed(10, Duration::from_millis(100))
+            .unwrap();
+        let err = timed.read_to_end(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::TimedOut);
+        assert_eq!(buf, b"abc");
+        assert!(start.elapsed() >= Duration::from_millis(100));
+        assert_eq!(
+            timed.read(&mut [0u8; 4]).unwrap_err().kind(),
+            ErrorKind::TimedOut
+        );
+        drop(timed);
+        assert_eq!(client.read_timeout().unwrap(), None);
+    }
+}
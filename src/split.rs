@@ -0,0 +1,139 @@
+//! A delimiter-separated field iterator with a per-field size cap.
+
+use std::io::{self, BufRead};
+
+use crate::{LimitExceeded, UntilStatus, read_until_limited};
+
+/// An iterator over the delimiter-separated fields of a borrowed `BufRead`.
+///
+/// Fields are returned without the delimiter. A field longer than
+/// `max_field_len` bytes yields a [`LimitExceeded`] error and ends the
+/// iteration, so no more than `max_field_len + 1` bytes are ever buffered.
+///
+/// When the reader ends in the middle of a field, as when a
+/// [`RefTake`](crate::RefTake) window cuts a record short, that field is
+/// still returned and [`BoundedSplit::is_partial`] reports `true` afterwards.
+pub struct BoundedSplit<'a, R> {
+    inner: &'a mut R,
+    delim: u8,
+    max_field_len: usize,
+    partial: bool,
+    done: bool,
+}
+
+impl<'a, R> BoundedSplit<'a, R> {
+    /// Creates a new `BoundedSplit` over `inner` splitting on `delim`.
+    pub const fn wrap(inner: &'a mut R, delim: u8, max_field_len: usize) -> Self {
+        Self {
+            inner,
+            delim,
+            max_field_len,
+            partial: false,
+            done: false,
+        }
+    }
+
+    /// Returns `true` if the last field returned was not terminated by the delimiter.
+    pub fn is_partial(&self) -> bool {
+        self.partial
+    }
+}
+
+impl<R: BufRead> Iterator for BoundedSplit<'_, R> {
+    type Item = io::Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let mut field = Vec::new();
+        let cap = self.max_field_len.saturating_add(1);
+        let result = match read_until_limited(self.inner, self.delim, &mut field, cap) {
+            Ok(UntilStatus::Found(_)) => {
+                field.pop();
+                Some(Ok(field))
+            }
+            Ok(UntilStatus::Eof(0)) => None,
+            Ok(UntilStatus::Eof(n)) if n <= self.max_field_len => {
+                self.partial = true;
+                Some(Ok(field))
+            }
+            Ok(_) => Some(Err(LimitExceeded::new(self.max_field_len as u64).into())),
+            Err(e) => Some(Err(e)),
+        };
+        if matches!(result, None | Some(Err(_))) || self.partial {
+            self.done = true;
+        }
+        result
+    }
+}
+
+/// Extension trait to provide a `split_bounded` method on all `BufRead` types.
+pub trait BoundedSplitExt {
+    /// Returns an iterator over `delim`-separated fields of at most `max_field_len` bytes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use reftake::{BoundedSplitExt, RefTakeExt};
+    ///
+    /// let mut cursor = Cursor::new(b"root:x:0:0\nnext");
+    /// let mut record = cursor.take_ref(10);
+    /// let mut fields = record.split_bounded(b':', 16);
+    /// let parsed: Vec<Vec<u8>> = fields.by_ref().map(Result::unwrap).collect();
+    /// assert_eq!(parsed, vec![b"root".to_vec(), b"x".to_vec(), b"0".to_vec(), b"0".to_vec()]);
+    /// assert!(fields.is_partial());
+    /// ```
+    fn split_bounded(&mut self, delim: u8, max_field_len: usize) -> BoundedSplit<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: BufRead> BoundedSplitExt for T {
+    fn split_bounded(&mut self, delim: u8, max_field_len: usize) -> BoundedSplit<'_, Self> {
+        BoundedSplit::wrap(self, delim, max_field_len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RefTakeExt;
+    use std::io::{BufReader, Cursor};
+
+    #[test]
+    fn test_split_terminated_fields() {
+        let mut reader = BufReader::with_capacity(2, Cursor::new(b"a,bc,,def,"));
+        let mut split = reader.split_bounded(b',', 3);
+        let fields: Vec<Vec<u8>> = split.by_ref().map(Result::unwrap).collect();
+        assert_eq!(
+            fields,
+            vec![b"a".to_vec(), b"bc".to_vec(), b"".to_vec(), b"def".to_vec()]
+        );
+        assert!(!split.is_partial());
+    }
+
+    #[test]
+    fn test_field_too_long() {
+        let mut reader = Cursor::new(b"ab,abcdef,x");
+        let mut split = reader.split_bounded(b',', 4);
+        assert_eq!(split.next().unwrap().unwrap(), b"ab");
+        let err = split.next().unwrap().unwrap_err();
+        assert_eq!(LimitExceeded::from_io(&err), Some(&LimitExceeded::new(4)));
+        assert!(split.next().is_none());
+    }
+
+    #[test]
+    fn test_window_ends_mid_field() {
+        let mut reader = Cursor::new(b"ab,cdef,gh");
+        let mut take = reader.take_ref(5);
+        let mut split = take.split_bounded(b',', 8);
+        assert_eq!(split.next().unwrap().unwrap(), b"ab");
+        assert!(!split.is_partial());
+        assert_eq!(split.next().unwrap().unwrap(), b"cd");
+        assert!(split.is_partial());
+        assert!(split.next().is_none());
+    }
+}
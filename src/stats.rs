@@ -0,0 +1,225 @@
+//! Throughput and call-count statistics for a borrowed reader.
+
+use std::{
+    io::{self, BufRead, Read},
+    time::{Duration, Instant},
+};
+
+/// A snapshot of the statistics gathered by a [`RefStats`].
+///
+/// A chunk is what one `read` call returned, or one `consume` call
+/// accepted, when it was not empty.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ReadStats {
+    bytes: u64,
+    calls: u64,
+    chunks: u64,
+    min_chunk: usize,
+    max_chunk: usize,
+    elapsed: Duration,
+}
+
+impl ReadStats {
+    /// Returns the total number of bytes read.
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    /// Returns the number of `read` and `fill_buf` calls, including those that returned nothing.
+    pub fn calls(&self) -> u64 {
+        self.calls
+    }
+
+    /// Returns the number of non-empty chunks.
+    pub fn chunks(&self) -> u64 {
+        self.chunks
+    }
+
+    /// Returns the size of the smallest non-empty chunk, or `0` if there was none.
+    pub fn min_chunk(&self) -> usize {
+        self.min_chunk
+    }
+
+    /// Returns the size of the largest chunk.
+    pub fn max_chunk(&self) -> usize {
+        self.max_chunk
+    }
+
+    /// Returns the mean size of the non-empty chunks, or `0.0` if there was none.
+    pub fn mean_chunk(&self) -> f64 {
+        match self.chunks {
+            0 => 0.0,
+            n => self.bytes as f64 / n as f64,
+        }
+    }
+
+    /// Returns the time since the first call, as of the snapshot.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Returns the mean throughput since the first call, in bytes per second.
+    pub fn bytes_per_sec(&self) -> f64 {
+        match self.elapsed.as_secs_f64() {
+            0.0 => 0.0,
+            secs => self.bytes as f64 / secs,
+        }
+    }
+}
+
+/// A non-owning adapter gathering throughput and call-count statistics.
+///
+/// Like [`RefCount`](crate::RefCount), no limit is enforced and reads are
+/// forwarded unchanged, so it can be put around any reader or adapter,
+/// for example `conn.take_ref(n).stats_ref()`, to find out whether a slow
+/// stream is slow because of tiny reads or because of the peer.
+pub struct RefStats<'a, R> {
+    inner: &'a mut R,
+    stats: ReadStats,
+    first_call: Option<Instant>,
+}
+
+impl<'a, R> RefStats<'a, R> {
+    /// Creates a new `RefStats` over the given reader reference, with empty statistics.
+    pub const fn wrap(inner: &'a mut R) -> Self {
+        Self {
+            inner,
+            stats: ReadStats {
+                bytes: 0,
+                calls: 0,
+                chunks: 0,
+                min_chunk: 0,
+                max_chunk: 0,
+                elapsed: Duration::ZERO,
+            },
+            first_call: None,
+        }
+    }
+
+    /// Returns a snapshot of the statistics gathered so far.
+    pub fn stats(&self) -> ReadStats {
+        ReadStats {
+            elapsed: self.first_call.map_or(Duration::ZERO, |t| t.elapsed()),
+            ..self.stats
+        }
+    }
+
+    /// Clears the statistics, restarting the clock at the next call.
+    pub fn reset(&mut self) {
+        self.stats = ReadStats::default();
+        self.first_call = None;
+    }
+
+    fn record_call(&mut self) {
+        self.first_call.get_or_insert_with(Instant::now);
+        self.stats.calls += 1;
+    }
+
+    fn record_chunk(&mut self, n: usize) {
+        if n == 0 {
+            return;
+        }
+        let stats = &mut self.stats;
+        stats.min_chunk = if stats.chunks == 0 {
+            n
+        } else {
+            stats.min_chunk.min(n)
+        };
+        stats.max_chunk = stats.max_chunk.max(n);
+        stats.chunks += 1;
+        stats.bytes += n as u64;
+    }
+}
+
+impl<R: Read> Read for RefStats<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        self.record_call();
+        let n = self.inner.read(buf)?;
+        self.record_chunk(n);
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for RefStats<'_, R> {
+    fn fill_buf(&mut self) -> Result<&[u8], io::Error> {
+        self.record_call();
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.record_chunk(amt);
+        self.inner.consume(amt);
+    }
+}
+
+/// Extension trait to provide a `stats_ref` method on all `Read` types.
+pub trait RefStatsExt {
+    /// Wraps the reader in a `RefStats` that gathers read statistics.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Cursor, Read};
+    /// use reftake::{RefStatsExt, RefTakeExt};
+    ///
+    /// let mut cursor = Cursor::new(vec![0u8; 100]);
+    /// let mut take = cursor.take_ref(70);
+    /// let mut reader = take.stats_ref();
+    ///
+    /// let mut buf = [0u8; 32];
+    /// while reader.read(&mut buf).unwrap() > 0 {}
+    ///
+    /// let stats = reader.stats();
+    /// assert_eq!(stats.bytes(), 70);
+    /// assert_eq!(stats.calls(), 4);
+    /// assert_eq!((stats.min_chunk(), stats.max_chunk()), (6, 32));
+    /// ```
+    fn stats_ref(&mut self) -> RefStats<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: Read> RefStatsExt for T {
+    fn stats_ref(&mut self) -> RefStats<'_, Self> {
+        RefStats::wrap(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufReader, Cursor};
+
+    #[test]
+    fn test_chunk_sizes_and_mean() {
+        let mut reader = Cursor::new(b"abcdefghij");
+        let mut stats = reader.stats_ref();
+        let mut buf = [0u8; 4];
+        for len in [4, 1, 3] {
+            stats.read_exact(&mut buf[..len]).unwrap();
+        }
+
+        let snapshot = stats.stats();
+        assert_eq!(snapshot.chunks(), 3);
+        assert_eq!(snapshot.mean_chunk(), 8.0 / 3.0);
+        assert_eq!((snapshot.min_chunk(), snapshot.max_chunk()), (1, 4));
+        assert!(snapshot.elapsed() <= stats.stats().elapsed());
+
+        stats.reset();
+        assert_eq!(stats.stats(), ReadStats::default());
+    }
+
+    #[test]
+    fn test_buffered_reads_count_consumed_bytes() {
+        let mut reader = BufReader::with_capacity(4, Cursor::new(b"one\ntwo\n"));
+        let mut stats = reader.stats_ref();
+        let mut line = String::new();
+        stats.read_line(&mut line).unwrap();
+        stats.read_line(&mut line).unwrap();
+
+        let snapshot = stats.stats();
+        assert_eq!(snapshot.bytes(), 8);
+        assert_eq!(snapshot.chunks(), snapshot.calls());
+        assert_eq!(snapshot.max_chunk(), 4);
+    }
+}
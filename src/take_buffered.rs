@@ -0,0 +1,239 @@
+//! A buffered limited view over a borrowed unbuffered reader.
+
+use std::{
+    cmp,
+    io::{self, BufRead, ErrorKind, Read},
+};
+
+use crate::limited_buf::{ensure_buffered, unread};
+
+/// A non-owning adapter that limits a raw `Read` and buffers it to provide `BufRead`.
+///
+/// The buffer is only ever filled from the remaining limit, so no more than
+/// `limit` bytes are pulled from the inner reader, and the stream is left
+/// exactly at the end of the window once it has been read to the end.
+/// Bytes buffered but not read when the adapter is dropped are lost.
+pub struct RefTakeBuffered<'a, R> {
+    inner: &'a mut R,
+    /// Bytes that may still be pulled from the inner reader.
+    limit: u64,
+    buf: Box<[u8]>,
+    pos: usize,
+    filled: usize,
+}
+
+impl<'a, R> RefTakeBuffered<'a, R> {
+    /// Creates a new `RefTakeBuffered` that reads at most `limit` bytes through a buffer of `capacity` bytes.
+    pub fn wrap(inner: &'a mut R, limit: u64, capacity: usize) -> Self {
+        Self {
+            inner,
+            limit,
+            buf: vec![0; capacity.max(1)].into_boxed_slice(),
+            pos: 0,
+            filled: 0,
+        }
+    }
+
+    /// Returns the number of bytes that may still be read, including buffered ones.
+    pub fn current_limit(&self) -> u64 {
+        self.limit + (self.filled - self.pos) as u64
+    }
+
+    /// Returns the bytes that have been pulled from the inner reader but not consumed yet.
+    pub fn buffer(&self) -> &[u8] {
+        &self.buf[self.pos..self.filled]
+    }
+
+    /// Pushes `data` back in front of the buffered bytes, to be read again first.
+    ///
+    /// The bytes count toward [`current_limit`](Self::current_limit) again,
+    /// so a parser that read past what it needed can give the excess back
+    /// to the window. Fails with `ErrorKind::InvalidInput`, leaving the
+    /// buffer untouched, if they do not fit in the buffer alongside the
+    /// bytes already buffered.
+    pub fn unread(&mut self, data: &[u8]) -> io::Result<()> {
+        unread(&mut self.buf, &mut self.pos, &mut self.filled, data)
+    }
+}
+
+impl<R: Read> RefTakeBuffered<'_, R> {
+    /// Reads until at least `n` bytes are buffered, and returns all the buffered bytes.
+    ///
+    /// Lets a fixed-size header be parsed from a single slice, which
+    /// `fill_buf` does not guarantee. Fails with `ErrorKind::UnexpectedEof`
+    /// without reading anything if the window has fewer than `n` bytes
+    /// left, and with `ErrorKind::InvalidInput` if `n` exceeds the
+    /// capacity. A stream that ends first also fails with
+    /// `ErrorKind::UnexpectedEof`, keeping the bytes read until then.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{BufRead, Cursor};
+    /// use reftake::RefTakeBufferedExt;
+    ///
+    /// let mut file = Cursor::new(b"RIFF\x04\x00\x00\x00WAVEdata");
+    /// let mut take = file.take_ref_buffered(12, 64);
+    /// assert_eq!(&take.ensure_buffered(12).unwrap()[8..], b"WAVE");
+    /// assert!(take.ensure_buffered(13).is_err());
+    /// ```
+    pub fn ensure_buffered(&mut self, n: usize) -> io::Result<&[u8]> {
+        if n as u64 > self.current_limit() {
+            return Err(io::Error::new(
+                ErrorKind::UnexpectedEof,
+                format!(
+                    "{n} bytes requested but only {} left in window",
+                    self.current_limit()
+                ),
+            ));
+        }
+        ensure_buffered(
+            &mut *self.inner,
+            &mut self.buf,
+            &mut self.pos,
+            &mut self.filled,
+            &mut self.limit,
+            n,
+        )?;
+        Ok(self.buffer())
+    }
+}
+
+impl<R: Read> Read for RefTakeBuffered<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        // Bypass the internal buffer for large reads when it is empty
+        if self.pos == self.filled && buf.len() >= self.buf.len() {
+            if self.limit == 0 {
+                return Ok(0);
+            }
+            let max = cmp::min(buf.len() as u64, self.limit) as usize;
+            let n = self.inner.read(&mut buf[..max])?;
+            assert!(n <= max, "number of read bytes exceeds limit");
+            self.limit -= n as u64;
+            return Ok(n);
+        }
+        let available = self.fill_buf()?;
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<R: Read> BufRead for RefTakeBuffered<'_, R> {
+    fn fill_buf(&mut self) -> Result<&[u8], io::Error> {
+        // Don't call into inner reader at all at EOF because it may still block
+        if self.pos == self.filled && self.limit > 0 {
+            let max = cmp::min(self.buf.len() as u64, self.limit) as usize;
+            let n = self.inner.read(&mut self.buf[..max])?;
+            assert!(n <= max, "number of read bytes exceeds limit");
+            self.limit -= n as u64;
+            self.pos = 0;
+            self.filled = n;
+        }
+        Ok(&self.buf[self.pos..self.filled])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = (self.pos + amt).min(self.filled);
+    }
+}
+
+/// Extension trait to provide a `take_ref_buffered` method on all `Read` types.
+pub trait RefTakeBufferedExt {
+    /// Wraps the reader in a `RefTakeBuffered` with the given limit and buffer capacity.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{BufRead, Cursor, Read};
+    /// use reftake::RefTakeBufferedExt;
+    ///
+    /// let mut cursor = Cursor::new(b"one\ntwo\nthree\n");
+    /// let lines: Vec<String> = cursor
+    ///     .take_ref_buffered(8, 64)
+    ///     .lines()
+    ///     .map(Result::unwrap)
+    ///     .collect();
+    /// assert_eq!(lines, vec!["one", "two"]);
+    /// assert_eq!(cursor.position(), 8);
+    /// ```
+    fn take_ref_buffered(&mut self, limit: u64, capacity: usize) -> RefTakeBuffered<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: Read> RefTakeBufferedExt for T {
+    fn take_ref_buffered(&mut self, limit: u64, capacity: usize) -> RefTakeBuffered<'_, Self> {
+        RefTakeBuffered::wrap(self, limit, capacity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_never_pulls_past_limit() {
+        let mut reader = Cursor::new(b"abc,def,ghi");
+        {
+            let mut take = reader.take_ref_buffered(6, 1024);
+            let mut field = Vec::new();
+            take.read_until(b',', &mut field).unwrap();
+            assert_eq!(field, b"abc,");
+            assert_eq!(take.buffer(), b"de");
+            assert_eq!(take.current_limit(), 2);
+        }
+        assert_eq!(reader.position(), 6);
+    }
+
+    #[test]
+    fn test_small_capacity() {
+        let mut reader = Cursor::new(b"hello world");
+        let mut take = reader.take_ref_buffered(9, 2);
+
+        let mut buf = String::new();
+        take.read_to_string(&mut buf).unwrap();
+        assert_eq!(buf, "hello wor");
+        assert_eq!(take.current_limit(), 0);
+    }
+
+    #[test]
+    fn test_large_read_bypasses_buffer() {
+        let mut reader = Cursor::new(b"abcdefgh");
+        let mut take = reader.take_ref_buffered(5, 2);
+
+        let mut buf = [0u8; 8];
+        assert_eq!(take.read(&mut buf).unwrap(), 5);
+        assert_eq!(&buf[..5], b"abcde");
+        assert_eq!(take.read(&mut buf).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_unread_credits_the_limit() {
+        let mut reader = Cursor::new(b"key:value;rest");
+        let mut take = reader.take_ref_buffered(9, 4);
+        let mut key = Vec::new();
+        take.read_until(b':', &mut key).unwrap();
+        take.unread(b":").unwrap();
+        assert_eq!(take.current_limit(), 6);
+
+        let mut rest = String::new();
+        take.read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, ":value");
+    }
+
+    #[test]
+    fn test_ensure_buffered_stays_within_limit() {
+        let mut reader = Cursor::new(b"headerbody|next");
+        let mut take = reader.take_ref_buffered(10, 8);
+        assert_eq!(take.ensure_buffered(6).unwrap(), b"headerbo");
+        take.consume(6);
+        assert_eq!(take.ensure_buffered(4).unwrap(), b"body");
+        let err = take.ensure_buffered(5).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+        assert_eq!(take.buffer(), b"body");
+        assert_eq!(reader.position(), 10);
+    }
+}
@@ -0,0 +1,168 @@
+//! An adapter that reads from a borrowed `BufRead` while a byte predicate holds.
+
+use std::io::{self, BufRead, Read};
+
+/// A non-owning adapter that passes bytes through while a predicate holds.
+///
+/// Reading stops at the first byte for which the predicate returns `false`;
+/// that byte is left unconsumed in the inner reader. The predicate is called
+/// exactly once per byte, so stateful predicates are supported.
+pub struct RefTakeWhile<'a, R, P> {
+    inner: &'a mut R,
+    predicate: P,
+    /// Length of the already-checked prefix of the inner buffer.
+    avail: usize,
+    /// Whether the byte right after the checked prefix failed the predicate.
+    stop_pending: bool,
+    done: bool,
+}
+
+impl<'a, R, P> RefTakeWhile<'a, R, P>
+where
+    P: FnMut(u8) -> bool,
+{
+    /// Creates a new `RefTakeWhile` that reads from `inner` while `predicate` holds.
+    pub const fn wrap(inner: &'a mut R, predicate: P) -> Self {
+        Self {
+            inner,
+            predicate,
+            avail: 0,
+            stop_pending: false,
+            done: false,
+        }
+    }
+
+    /// Returns `true` once a byte failing the predicate has been reached,
+    /// as opposed to EOF of the inner reader.
+    pub fn stopped(&self) -> bool {
+        self.done
+    }
+}
+
+impl<R: BufRead, P: FnMut(u8) -> bool> Read for RefTakeWhile<'_, R, P> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        let available = self.fill_buf()?;
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<R: BufRead, P: FnMut(u8) -> bool> BufRead for RefTakeWhile<'_, R, P> {
+    fn fill_buf(&mut self) -> Result<&[u8], io::Error> {
+        if self.done {
+            return Ok(&[]);
+        }
+
+        // The inner buffer is unchanged until `consume`, so bytes already
+        // checked don't need to be passed to the predicate again
+        if self.avail == 0 && self.stop_pending {
+            self.done = true;
+            return Ok(&[]);
+        }
+        if self.avail == 0 {
+            let buf = self.inner.fill_buf()?;
+            match buf.iter().position(|&b| !(self.predicate)(b)) {
+                Some(0) => {
+                    self.done = true;
+                    return Ok(&[]);
+                }
+                Some(pos) => {
+                    self.avail = pos;
+                    self.stop_pending = true;
+                }
+                None => self.avail = buf.len(),
+            }
+        }
+
+        let buf = self.inner.fill_buf()?;
+        Ok(&buf[..self.avail])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        let amt = amt.min(self.avail);
+        self.avail -= amt;
+        self.inner.consume(amt);
+    }
+}
+
+/// Extension trait to provide a `take_while_ref` method on all `BufRead` types.
+pub trait RefTakeWhileExt {
+    /// Wraps the reader in a `RefTakeWhile` that reads while `predicate` holds.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Cursor, Read};
+    /// use reftake::RefTakeWhileExt;
+    ///
+    /// let mut cursor = Cursor::new(b"12345abc");
+    /// let mut digits = String::new();
+    /// cursor
+    ///     .take_while_ref(|b| b.is_ascii_digit())
+    ///     .read_to_string(&mut digits)
+    ///     .unwrap();
+    /// assert_eq!(digits, "12345");
+    /// assert_eq!(cursor.position(), 5);
+    /// ```
+    fn take_while_ref<P: FnMut(u8) -> bool>(&mut self, predicate: P) -> RefTakeWhile<'_, Self, P>
+    where
+        Self: Sized;
+}
+
+impl<T: BufRead> RefTakeWhileExt for T {
+    fn take_while_ref<P: FnMut(u8) -> bool>(&mut self, predicate: P) -> RefTakeWhile<'_, Self, P> {
+        RefTakeWhile::wrap(self, predicate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RefTakeExt;
+    use std::io::{BufReader, Cursor};
+
+    #[test]
+    fn test_take_while_across_buffers() {
+        let mut reader = BufReader::with_capacity(2, Cursor::new(b"   word"));
+        {
+            let mut ws = reader.take_while_ref(|b| b == b' ');
+            let mut buf = Vec::new();
+            ws.read_to_end(&mut buf).unwrap();
+            assert_eq!(buf, b"   ");
+            assert!(ws.stopped());
+        }
+
+        let mut rest = String::new();
+        reader.read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, "word");
+    }
+
+    #[test]
+    fn test_predicate_called_once_per_byte() {
+        let mut reader = Cursor::new(b"aaaab");
+        let mut calls = 0;
+        {
+            let mut take = reader.take_while_ref(|b| {
+                calls += 1;
+                b == b'a'
+            });
+            let mut buf = [0u8; 1];
+            while take.read(&mut buf).unwrap() > 0 {}
+        }
+        assert_eq!(calls, 5);
+    }
+
+    #[test]
+    fn test_take_while_under_take() {
+        let mut reader = Cursor::new(b"0123456789x");
+        let mut take = reader.take_ref(4);
+        let mut digits = take.take_while_ref(|b| b.is_ascii_digit());
+
+        let mut buf = String::new();
+        digits.read_to_string(&mut buf).unwrap();
+        assert_eq!(buf, "0123");
+        assert!(!digits.stopped());
+    }
+}
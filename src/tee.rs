@@ -0,0 +1,167 @@
+//! An adapter that mirrors every byte read from a borrowed reader into a borrowed writer.
+
+use std::io::{self, BufRead, Read, Write};
+
+/// A non-owning adapter that copies every byte read through it into a side writer.
+///
+/// Bytes are written with `write_all` before they are handed to the caller,
+/// so the writer always sees exactly the bytes the caller received. If the
+/// write fails, the error is returned and the bytes already taken from the
+/// inner reader are lost to the caller.
+pub struct RefTee<'a, R, W> {
+    inner: &'a mut R,
+    writer: &'a mut W,
+    pending_error: Option<io::Error>,
+}
+
+impl<'a, R, W> RefTee<'a, R, W> {
+    /// Creates a new `RefTee` that mirrors everything read from `inner` into `writer`.
+    pub const fn wrap(inner: &'a mut R, writer: &'a mut W) -> Self {
+        Self {
+            inner,
+            writer,
+            pending_error: None,
+        }
+    }
+}
+
+impl<R: Read, W: Write> Read for RefTee<'_, R, W> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        if let Some(e) = self.pending_error.take() {
+            return Err(e);
+        }
+        let n = self.inner.read(buf)?;
+        self.writer.write_all(&buf[..n])?;
+        Ok(n)
+    }
+}
+
+/// Implements the `BufRead` trait, mirroring bytes as they are consumed.
+///
+/// Since `consume()` cannot fail, an error from the side writer is stored and
+/// returned by the next `read()` or `fill_buf()` call.
+impl<R: BufRead, W: Write> BufRead for RefTee<'_, R, W> {
+    fn fill_buf(&mut self) -> Result<&[u8], io::Error> {
+        if let Some(e) = self.pending_error.take() {
+            return Err(e);
+        }
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        if amt == 0 {
+            return;
+        }
+        let result = match self.inner.fill_buf() {
+            Ok(buf) => {
+                let amt = amt.min(buf.len());
+                self.writer.write_all(&buf[..amt])
+            }
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            self.pending_error.get_or_insert(e);
+        }
+        self.inner.consume(amt);
+    }
+}
+
+/// Extension trait to provide a `tee_ref` method on all `Read` types.
+pub trait RefTeeExt {
+    /// Wraps the reader in a `RefTee` that mirrors all read bytes into `writer`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Cursor, Read};
+    /// use reftake::{RefTakeExt, RefTeeExt};
+    ///
+    /// let mut cursor = Cursor::new(b"hello world");
+    /// let mut log = Vec::new();
+    ///
+    /// let mut take = cursor.take_ref(5);
+    /// let mut buf = String::new();
+    /// take.tee_ref(&mut log).read_to_string(&mut buf).unwrap();
+    /// assert_eq!(buf, "hello");
+    /// assert_eq!(log, b"hello");
+    /// ```
+    fn tee_ref<'a, W: Write>(&'a mut self, writer: &'a mut W) -> RefTee<'a, Self, W>
+    where
+        Self: Sized;
+}
+
+impl<T: Read> RefTeeExt for T {
+    fn tee_ref<'a, W: Write>(&'a mut self, writer: &'a mut W) -> RefTee<'a, Self, W> {
+        RefTee::wrap(self, writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RefTakeExt;
+    use std::io::{BufReader, Cursor, ErrorKind};
+
+    struct FailingWriter;
+
+    impl Write for FailingWriter {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            Err(io::Error::other("sink closed"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_tee_mirrors_reads() {
+        let mut reader = Cursor::new(b"abcdef");
+        let mut log = Vec::new();
+        let mut tee = reader.tee_ref(&mut log);
+
+        let mut buf = [0u8; 4];
+        let n = tee.read(&mut buf).unwrap();
+        assert_eq!(&buf[..n], b"abcd");
+        drop(tee);
+        assert_eq!(log, b"abcd");
+    }
+
+    #[test]
+    fn test_tee_bufread_mirrors_consumed_bytes_only() {
+        let mut reader = BufReader::new(Cursor::new(b"one\ntwo\n"));
+        let mut log = Vec::new();
+        {
+            let mut tee = reader.tee_ref(&mut log);
+            let mut line = String::new();
+            tee.read_line(&mut line).unwrap();
+            assert_eq!(line, "one\n");
+        }
+        assert_eq!(log, b"one\n");
+    }
+
+    #[test]
+    fn test_tee_under_take() {
+        let mut reader = Cursor::new(b"hello world");
+        let mut log = Vec::new();
+        let mut tee = reader.tee_ref(&mut log);
+
+        let mut buf = Vec::new();
+        tee.take_ref(7).read_to_end(&mut buf).unwrap();
+        drop(tee);
+        assert_eq!(buf, b"hello w");
+        assert_eq!(log, b"hello w");
+    }
+
+    #[test]
+    fn test_tee_writer_error_is_reported() {
+        let mut reader = BufReader::new(Cursor::new(b"abc"));
+        let mut sink = FailingWriter;
+        let mut tee = reader.tee_ref(&mut sink);
+
+        tee.fill_buf().unwrap();
+        tee.consume(1);
+        let err = tee.fill_buf().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Other);
+    }
+}
@@ -0,0 +1,138 @@
+//! An adapter that duplicates every byte written to a borrowed writer into a second writer.
+
+use std::io::{self, Write};
+
+/// A non-owning adapter that copies every byte written through it into a secondary writer.
+///
+/// Each write goes to the primary writer first, and exactly the bytes it
+/// accepted are then passed to the secondary with `write_all`. A short write
+/// by the primary is therefore mirrored as a short write, and the secondary
+/// never sees bytes the primary did not take.
+///
+/// If the secondary fails after the primary has accepted the bytes, the write
+/// still reports them as written, and the error is returned by the next
+/// `write()` or `flush()` call.
+pub struct RefTeeWrite<'a, W, S> {
+    inner: &'a mut W,
+    secondary: &'a mut S,
+    pending_error: Option<io::Error>,
+}
+
+impl<'a, W, S> RefTeeWrite<'a, W, S> {
+    /// Creates a new `RefTeeWrite` that mirrors everything written to `inner` into `secondary`.
+    pub const fn wrap(inner: &'a mut W, secondary: &'a mut S) -> Self {
+        Self {
+            inner,
+            secondary,
+            pending_error: None,
+        }
+    }
+}
+
+impl<W: Write, S: Write> Write for RefTeeWrite<'_, W, S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if let Some(e) = self.pending_error.take() {
+            return Err(e);
+        }
+        let n = self.inner.write(buf)?;
+        if let Err(e) = self.secondary.write_all(&buf[..n]) {
+            self.pending_error = Some(e);
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if let Some(e) = self.pending_error.take() {
+            return Err(e);
+        }
+        self.inner.flush()?;
+        self.secondary.flush()
+    }
+}
+
+/// Extension trait to provide a `tee_write_ref` method on all `Write` types.
+pub trait RefTeeWriteExt {
+    /// Wraps the writer in a `RefTeeWrite` that mirrors all written bytes into `secondary`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::Write;
+    /// use reftake::RefTeeWriteExt;
+    ///
+    /// let mut out = Vec::new();
+    /// let mut audit = Vec::new();
+    /// out.tee_write_ref(&mut audit).write_all(b"hello").unwrap();
+    /// assert_eq!(out, b"hello");
+    /// assert_eq!(audit, b"hello");
+    /// ```
+    fn tee_write_ref<'a, S: Write>(&'a mut self, secondary: &'a mut S) -> RefTeeWrite<'a, Self, S>
+    where
+        Self: Sized;
+}
+
+impl<T: Write> RefTeeWriteExt for T {
+    fn tee_write_ref<'a, S: Write>(&'a mut self, secondary: &'a mut S) -> RefTeeWrite<'a, Self, S> {
+        RefTeeWrite::wrap(self, secondary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RefTakeWriteExt;
+    use std::io::ErrorKind;
+
+    struct FailingWriter;
+
+    impl Write for FailingWriter {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            Err(io::Error::other("sink closed"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_tee_write_mirrors_writes() {
+        let mut out = Vec::new();
+        let mut audit = Vec::new();
+        {
+            let mut tee = out.tee_write_ref(&mut audit);
+            tee.write_all(b"abc").unwrap();
+            tee.write_all(b"def").unwrap();
+            tee.flush().unwrap();
+        }
+        assert_eq!(out, b"abcdef");
+        assert_eq!(audit, b"abcdef");
+    }
+
+    #[test]
+    fn test_short_primary_write_is_mirrored() {
+        let mut out = Vec::new();
+        let mut audit = Vec::new();
+        {
+            let mut limited = out.take_write_ref(4);
+            let mut tee = limited.tee_write_ref(&mut audit);
+            assert_eq!(tee.write(b"abcdef").unwrap(), 4);
+            assert_eq!(tee.write(b"gh").unwrap(), 0);
+        }
+        assert_eq!(out, b"abcd");
+        assert_eq!(audit, b"abcd");
+    }
+
+    #[test]
+    fn test_secondary_error_is_reported_on_next_call() {
+        let mut out = Vec::new();
+        let mut sink = FailingWriter;
+        {
+            let mut tee = out.tee_write_ref(&mut sink);
+            assert_eq!(tee.write(b"abc").unwrap(), 3);
+            let err = tee.write(b"def").unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::Other);
+        }
+        assert_eq!(out, b"abc");
+    }
+}
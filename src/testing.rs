@@ -0,0 +1,388 @@
+//! Readers for testing code built on the adapters of this crate.
+//!
+//! Real streams return fewer bytes than asked for, split messages at any
+//! byte and get interrupted; in-memory test data usually does none of this.
+//! The readers here put those behaviors back, deterministically, so
+//! failures found with them can be reproduced from the seed.
+
+use std::{
+    io::{self, BufRead, ErrorKind, Read},
+    thread,
+    time::Duration,
+};
+
+/// How a [`ChaosReader`] disturbs the reads of its inner reader.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChaosConfig {
+    /// Seed of the pseudo-random sequence; equal seeds give equal read patterns.
+    pub seed: u64,
+    /// Most bytes returned by a single read; `0` means no cap beyond the buffer.
+    pub max_read: usize,
+    /// Percentage of calls failing with `ErrorKind::Interrupted`.
+    pub interrupted_percent: u8,
+    /// Percentage of calls failing with `ErrorKind::WouldBlock`.
+    pub would_block_percent: u8,
+}
+
+impl Default for ChaosConfig {
+    /// Short reads of up to 7 bytes, with 10% of calls interrupted and no `WouldBlock`.
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            max_read: 7,
+            interrupted_percent: 10,
+            would_block_percent: 0,
+        }
+    }
+}
+
+/// A non-owning adapter that makes reads short, and fail, at pseudo-random points.
+///
+/// Every call first rolls for an injected `Interrupted` or `WouldBlock`
+/// error, which leaves the inner reader untouched, and otherwise reads a
+/// pseudo-random number of bytes between one and
+/// [`ChaosConfig::max_read`], so messages come in split at awkward
+/// boundaries. Errors and EOF of the inner reader are passed through.
+/// The sequence is a fixed function of [`ChaosConfig::seed`], so a failing
+/// test can be rerun with exactly the same reads.
+///
+/// `fill_buf` exposes a pseudo-random prefix of the inner buffer in the
+/// same way, chosen again after each `consume`.
+pub struct ChaosReader<'a, R> {
+    inner: &'a mut R,
+    config: ChaosConfig,
+    state: u64,
+    /// Length of the prefix `fill_buf` exposes until the next `consume`.
+    window: Option<usize>,
+}
+
+impl<'a, R> ChaosReader<'a, R> {
+    /// Creates a new `ChaosReader` over the given reader reference with
+    /// the default [`ChaosConfig`] and the given seed.
+    pub const fn wrap(inner: &'a mut R, seed: u64) -> Self {
+        Self::with_config(
+            inner,
+            ChaosConfig {
+                seed,
+                max_read: 7,
+                interrupted_percent: 10,
+                would_block_percent: 0,
+            },
+        )
+    }
+
+    /// Creates a new `ChaosReader` with a custom configuration.
+    pub const fn with_config(inner: &'a mut R, config: ChaosConfig) -> Self {
+        Self {
+            inner,
+            config,
+            // xorshift needs a non-zero state
+            state: config.seed ^ 0x9e37_79b9_7f4a_7c15,
+            window: None,
+        }
+    }
+
+    /// Returns the configuration.
+    pub fn config(&self) -> &ChaosConfig {
+        &self.config
+    }
+
+    fn next(&mut self) -> u64 {
+        // xorshift64*
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// Rolls for an injected error.
+    fn roll_error(&mut self) -> io::Result<()> {
+        let roll = self.next() % 100;
+        let interrupted = u64::from(self.config.interrupted_percent);
+        let would_block = u64::from(self.config.would_block_percent);
+        if roll < interrupted {
+            return Err(io::Error::new(
+                ErrorKind::Interrupted,
+                "injected by ChaosReader",
+            ));
+        }
+        if roll < interrupted + would_block {
+            return Err(io::Error::new(
+                ErrorKind::WouldBlock,
+                "injected by ChaosReader",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Picks a read length for a buffer of `len` bytes.
+    fn roll_len(&mut self, len: usize) -> usize {
+        let max = match self.config.max_read {
+            0 => len,
+            max => len.min(max),
+        };
+        match max {
+            0 => 0,
+            max => 1 + (self.next() % max as u64) as usize,
+        }
+    }
+}
+
+impl<R: Read> Read for ChaosReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        self.roll_error()?;
+        let len = self.roll_len(buf.len());
+        self.inner.read(&mut buf[..len])
+    }
+}
+
+impl<R: BufRead> BufRead for ChaosReader<'_, R> {
+    fn fill_buf(&mut self) -> Result<&[u8], io::Error> {
+        if self.window.is_none() {
+            self.roll_error()?;
+        }
+        let available = self.inner.fill_buf()?.len();
+        let window = match self.window {
+            Some(window) => window.min(available),
+            None => self.roll_len(available),
+        };
+        self.window = Some(window);
+        Ok(&self.inner.fill_buf()?[..window])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.window = None;
+        self.inner.consume(amt);
+    }
+}
+
+/// Extension trait to provide a `chaos_ref` method on all `Read` types.
+pub trait ChaosReaderExt {
+    /// Wraps the reader in a `ChaosReader` with the default configuration and the given seed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Cursor, Read};
+    /// use reftake::testing::ChaosReaderExt;
+    ///
+    /// let mut source = Cursor::new(b"a message split at random");
+    /// let mut chaos = source.chaos_ref(42);
+    ///
+    /// // `read_to_end` retries `Interrupted`, so the data still comes through
+    /// let mut out = Vec::new();
+    /// chaos.read_to_end(&mut out).unwrap();
+    /// assert_eq!(out, b"a message split at random");
+    /// ```
+    fn chaos_ref(&mut self, seed: u64) -> ChaosReader<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: Read> ChaosReaderExt for T {
+    fn chaos_ref(&mut self, seed: u64) -> ChaosReader<'_, Self> {
+        ChaosReader::wrap(self, seed)
+    }
+}
+
+/// Extension trait to provide a `slow_ref` method on all `Read` types.
+pub trait SlowReaderExt {
+    /// Wraps the reader in a `SlowReader` that sleeps the thread.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Cursor, Read};
+    /// use std::time::{Duration, Instant};
+    /// use reftake::testing::{SlowConfig, SlowReaderExt};
+    ///
+    /// let mut source = Cursor::new(vec![0u8; 40]);
+    /// let mut slow = source.slow_ref(SlowConfig::per_second(1000, 10));
+    ///
+    /// let start = Instant::now();
+    /// let mut out = Vec::new();
+    /// slow.read_to_end(&mut out).unwrap();
+    /// assert!(start.elapsed() >= Duration::from_millis(40));
+    /// ```
+    fn slow_ref(&mut self, config: SlowConfig) -> SlowReader<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: Read> SlowReaderExt for T {
+    fn slow_ref(&mut self, config: SlowConfig) -> SlowReader<'_, Self> {
+        SlowReader::wrap(self, config)
+    }
+}
+
+/// How a [`SlowReader`] paces the bytes of its inner reader.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlowConfig {
+    /// Delay before the first read.
+    pub initial_delay: Duration,
+    /// Delay before every read, including the first.
+    pub delay: Duration,
+    /// Most bytes returned by a single read.
+    pub chunk_size: usize,
+}
+
+impl SlowConfig {
+    /// Paces reads of `chunk_size` bytes to deliver `bytes_per_sec` bytes per second.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bytes_per_sec` or `chunk_size` is zero.
+    pub fn per_second(bytes_per_sec: u64, chunk_size: usize) -> Self {
+        assert!(bytes_per_sec > 0, "rate must be non-zero");
+        assert!(chunk_size > 0, "chunk size must be non-zero");
+        Self {
+            initial_delay: Duration::ZERO,
+            delay: Duration::from_secs_f64(chunk_size as f64 / bytes_per_sec as f64),
+            chunk_size,
+        }
+    }
+}
+
+/// A non-owning adapter that delivers the bytes of its inner reader slowly.
+///
+/// Before each read it waits [`SlowConfig::delay`] (plus
+/// [`SlowConfig::initial_delay`] before the first), then returns at most
+/// [`SlowConfig::chunk_size`] bytes. Waiting is done by the sleep hook,
+/// `std::thread::sleep` unless another is given with
+/// [`SlowReader::with_sleep`]: a hook that advances a mock clock instead
+/// tests timeout and throughput logic deterministically and without
+/// actually waiting.
+pub struct SlowReader<'a, R, S = fn(Duration)> {
+    inner: &'a mut R,
+    config: SlowConfig,
+    sleep: S,
+    started: bool,
+    slept: Duration,
+}
+
+impl<'a, R> SlowReader<'a, R> {
+    /// Creates a new `SlowReader` over the given reader reference, sleeping the thread.
+    pub const fn wrap(inner: &'a mut R, config: SlowConfig) -> Self {
+        Self::with_sleep(inner, config, thread::sleep)
+    }
+}
+
+impl<'a, R, S: FnMut(Duration)> SlowReader<'a, R, S> {
+    /// Creates a new `SlowReader` that waits by calling `sleep`.
+    pub const fn with_sleep(inner: &'a mut R, config: SlowConfig, sleep: S) -> Self {
+        Self {
+            inner,
+            config,
+            sleep,
+            started: false,
+            slept: Duration::ZERO,
+        }
+    }
+
+    /// Returns the configuration.
+    pub fn config(&self) -> &SlowConfig {
+        &self.config
+    }
+
+    /// Returns the total delay requested from the sleep hook so far.
+    pub fn total_delay(&self) -> Duration {
+        self.slept
+    }
+
+    fn wait(&mut self) {
+        let mut delay = self.config.delay;
+        if !self.started {
+            self.started = true;
+            delay += self.config.initial_delay;
+        }
+        if !delay.is_zero() {
+            self.slept += delay;
+            (self.sleep)(delay);
+        }
+    }
+}
+
+impl<R: Read, S: FnMut(Duration)> Read for SlowReader<'_, R, S> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        self.wait();
+        let len = buf.len().min(self.config.chunk_size);
+        self.inner.read(&mut buf[..len])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RefTakeExt;
+    use std::io::{BufReader, Cursor};
+
+    #[test]
+    fn test_slow_reads_with_mock_clock() {
+        let clock = std::cell::Cell::new(Duration::ZERO);
+        let mut source = Cursor::new(b"0123456789");
+        let config = SlowConfig {
+            initial_delay: Duration::from_secs(2),
+            ..SlowConfig::per_second(4, 4)
+        };
+        let mut slow = SlowReader::with_sleep(&mut source, config, |d| clock.set(clock.get() + d));
+
+        let mut buf = [0u8; 8];
+        assert_eq!(slow.read(&mut buf).unwrap(), 4);
+        assert_eq!(clock.get(), Duration::from_secs(3));
+        let mut rest = Vec::new();
+        slow.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest, b"456789");
+        // Two more chunks and the read that sees EOF
+        assert_eq!(slow.total_delay(), Duration::from_secs(6));
+        assert_eq!(clock.get(), slow.total_delay());
+    }
+
+    fn pattern(seed: u64) -> Vec<io::Result<usize>> {
+        let mut source = Cursor::new(vec![0u8; 1024]);
+        let mut chaos = ChaosReader::with_config(
+            &mut source,
+            ChaosConfig {
+                seed,
+                would_block_percent: 10,
+                ..ChaosConfig::default()
+            },
+        );
+        let mut buf = [0u8; 16];
+        (0..20).map(|_| chaos.read(&mut buf)).collect()
+    }
+
+    #[test]
+    fn test_same_seed_same_reads() {
+        let summary = |reads: Vec<io::Result<usize>>| -> Vec<_> {
+            reads.into_iter().map(|r| r.map_err(|e| e.kind())).collect()
+        };
+        let first = summary(pattern(7));
+        assert_eq!(first, summary(pattern(7)));
+        assert_ne!(first, summary(pattern(8)));
+        assert!(
+            first
+                .iter()
+                .all(|r| r.as_ref().map_or(true, |&n| (1..=7).contains(&n)))
+        );
+        assert!(first.contains(&Err(ErrorKind::Interrupted)));
+        assert!(first.contains(&Err(ErrorKind::WouldBlock)));
+    }
+
+    #[test]
+    fn test_take_survives_chaos() {
+        let data: Vec<u8> = (0..200u8).collect();
+        for seed in 0..32 {
+            let mut source = BufReader::with_capacity(13, Cursor::new(&data));
+            let mut chaos = source.chaos_ref(seed);
+            let mut first = Vec::new();
+            chaos.take_ref(120).read_to_end(&mut first).unwrap();
+            let mut line = Vec::new();
+            chaos.read_until(199, &mut line).unwrap();
+            assert_eq!(first, data[..120]);
+            assert_eq!(line, data[120..]);
+        }
+    }
+}
@@ -0,0 +1,195 @@
+//! A token-bucket rate limiter over a borrowed reader.
+
+use std::{
+    cmp,
+    io::{self, BufRead, Read},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// A non-owning adapter that limits the read throughput of a reader.
+///
+/// Implements a token bucket holding at most `burst` bytes, refilled at
+/// `bytes_per_sec`. Each read is clamped to the currently available tokens;
+/// when the bucket is empty, the calling thread sleeps until at least one
+/// byte may be read. Reads may therefore return short counts.
+pub struct RefThrottle<'a, R> {
+    inner: &'a mut R,
+    rate: u64,
+    burst: u64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl<'a, R> RefThrottle<'a, R> {
+    /// Creates a new `RefThrottle` reading at most `bytes_per_sec` bytes per second,
+    /// with a burst size of one second worth of bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bytes_per_sec` is zero.
+    pub fn wrap(inner: &'a mut R, bytes_per_sec: u64) -> Self {
+        Self::with_burst(inner, bytes_per_sec, bytes_per_sec)
+    }
+
+    /// Creates a new `RefThrottle` with an explicit burst size.
+    ///
+    /// The bucket starts full, so up to `burst` bytes can be read immediately.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bytes_per_sec` or `burst` is zero.
+    pub fn with_burst(inner: &'a mut R, bytes_per_sec: u64, burst: u64) -> Self {
+        assert!(bytes_per_sec > 0, "throttle rate must be positive");
+        assert!(burst > 0, "throttle burst must be positive");
+        Self {
+            inner,
+            rate: bytes_per_sec,
+            burst,
+            tokens: burst as f64,
+            last_refill: Instant::now(),
+        }
+    }
+
+    /// Returns the configured rate in bytes per second.
+    pub fn rate(&self) -> u64 {
+        self.rate
+    }
+
+    /// Changes the rate in bytes per second, keeping the tokens accumulated so far.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bytes_per_sec` is zero.
+    pub fn set_rate(&mut self, bytes_per_sec: u64) {
+        assert!(bytes_per_sec > 0, "throttle rate must be positive");
+        self.refill();
+        self.rate = bytes_per_sec;
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate as f64).min(self.burst as f64);
+        self.last_refill = now;
+    }
+
+    /// Blocks until at least one byte may be read, then returns how many may be read.
+    fn acquire(&mut self, wanted: usize) -> usize {
+        self.refill();
+        if self.tokens < 1.0 {
+            let missing = 1.0 - self.tokens;
+            thread::sleep(Duration::from_secs_f64(missing / self.rate as f64));
+            self.refill();
+        }
+        cmp::min(wanted as u64, cmp::max(self.tokens as u64, 1)) as usize
+    }
+
+    fn spend(&mut self, n: usize) {
+        self.tokens -= n as f64;
+    }
+}
+
+impl<R: Read> Read for RefThrottle<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let max = self.acquire(buf.len());
+        let n = self.inner.read(&mut buf[..max])?;
+        self.spend(n);
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for RefThrottle<'_, R> {
+    fn fill_buf(&mut self) -> Result<&[u8], io::Error> {
+        let available = self.inner.fill_buf()?.len();
+        if available == 0 {
+            return Ok(&[]);
+        }
+        let max = self.acquire(available);
+        let buf = self.inner.fill_buf()?;
+        Ok(&buf[..cmp::min(max, buf.len())])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.spend(amt);
+        self.inner.consume(amt);
+    }
+}
+
+/// Extension trait to provide a `throttle_ref` method on all `Read` types.
+pub trait RefThrottleExt {
+    /// Wraps the reader in a `RefThrottle` limited to `bytes_per_sec`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Cursor, Read};
+    /// use reftake::{RefTakeExt, RefThrottleExt};
+    ///
+    /// let mut cursor = Cursor::new(vec![0u8; 1024]);
+    /// let mut throttle = cursor.throttle_ref(1_000_000);
+    ///
+    /// let mut buf = Vec::new();
+    /// throttle.take_ref(100).read_to_end(&mut buf).unwrap();
+    /// assert_eq!(buf.len(), 100);
+    /// ```
+    fn throttle_ref(&mut self, bytes_per_sec: u64) -> RefThrottle<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: Read> RefThrottleExt for T {
+    fn throttle_ref(&mut self, bytes_per_sec: u64) -> RefThrottle<'_, Self> {
+        RefThrottle::wrap(self, bytes_per_sec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufReader, Cursor};
+
+    #[test]
+    fn test_burst_is_available_immediately() {
+        let mut reader = Cursor::new(vec![7u8; 100]);
+        let mut throttle = RefThrottle::with_burst(&mut reader, 10, 40);
+
+        let mut buf = [0u8; 100];
+        let n = throttle.read(&mut buf).unwrap();
+        assert_eq!(n, 40);
+    }
+
+    #[test]
+    fn test_throttle_waits_for_tokens() {
+        let mut reader = Cursor::new(vec![7u8; 100]);
+        let mut throttle = RefThrottle::with_burst(&mut reader, 1000, 10);
+
+        let start = Instant::now();
+        let mut buf = Vec::new();
+        throttle.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf.len(), 100);
+        // 90 bytes beyond the initial burst at 1000 B/s
+        assert!(start.elapsed() >= Duration::from_millis(80));
+    }
+
+    #[test]
+    fn test_throttle_bufread_clamps_to_tokens() {
+        let mut reader = BufReader::new(Cursor::new(vec![1u8; 64]));
+        let mut throttle = RefThrottle::with_burst(&mut reader, 1000, 16);
+
+        let buf = throttle.fill_buf().unwrap();
+        assert_eq!(buf.len(), 16);
+        throttle.consume(16);
+        assert_eq!(throttle.rate(), 1000);
+    }
+
+    #[test]
+    #[should_panic(expected = "throttle rate must be positive")]
+    fn test_zero_rate_panics() {
+        let mut reader = Cursor::new(b"");
+        let _ = reader.throttle_ref(0);
+    }
+}
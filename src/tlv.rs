@@ -0,0 +1,304 @@
+//! Iteration over type-length-value elements of a borrowed reader.
+
+use std::io::{self, BufRead, ErrorKind, Read};
+
+use crate::{
+    Endian, FrameBody, LengthEncoding, LimitExceeded, RefTake,
+    frame::{decode_int, header_eof, read_first_byte},
+    skip::discard_by_reading,
+};
+
+/// Layout and caps of the elements read by a [`TlvReader`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TlvConfig {
+    /// Encoding of the tag.
+    pub tag: LengthEncoding,
+    /// Encoding of the length.
+    pub length: LengthEncoding,
+    /// Byte order of fixed-width tags and lengths.
+    pub endian: Endian,
+    /// Longest accepted element value, in bytes.
+    pub max_element_len: u64,
+    /// How many levels of nested readers may be opened below the top level.
+    pub max_depth: usize,
+}
+
+impl Default for TlvConfig {
+    /// A one-byte tag and a big-endian `u16` length, with values of up to
+    /// 64 KiB and up to 8 levels of nesting.
+    fn default() -> Self {
+        Self {
+            tag: LengthEncoding::U8,
+            length: LengthEncoding::U16,
+            endian: Endian::Big,
+            max_element_len: 64 * 1024,
+            max_depth: 8,
+        }
+    }
+}
+
+/// The value of the current element of a [`TlvReader`].
+///
+/// Besides bounding the value, it remembers the layout and nesting depth of
+/// its reader, so that [`TlvReader::nested`] can parse the value as a
+/// sequence of elements itself.
+pub struct TlvBody<'a, R> {
+    body: FrameBody<'a, R>,
+    config: TlvConfig,
+    depth: usize,
+}
+
+impl<R> TlvBody<'_, R> {
+    /// Returns the number of bytes of the value that have not been read yet.
+    pub fn remaining(&self) -> u64 {
+        self.body.remaining()
+    }
+
+    /// Returns the nesting depth of the reader the element belongs to.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+}
+
+impl<R: Read> Read for TlvBody<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.body.read(buf)
+    }
+}
+
+impl<R: BufRead> BufRead for TlvBody<'_, R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.body.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.body.consume(amt);
+    }
+}
+
+/// A reader of type-length-value elements over a borrowed reader.
+///
+/// [`TlvReader::next_element`] reads a tag and a length, laid out as
+/// described by [`TlvConfig`], and returns the tag together with a
+/// [`RefTake`] limited to the value. Whatever the caller leaves unread of a
+/// value is skipped before the next element. A value can itself be read as
+/// a sequence of elements with [`TlvReader::nested`], up to
+/// [`TlvConfig::max_depth`] levels deep.
+pub struct TlvReader<'a, R> {
+    body: TlvBody<'a, R>,
+}
+
+impl<'a, R> TlvReader<'a, R> {
+    /// Creates a new top-level `TlvReader` with the default layout.
+    pub fn wrap(inner: &'a mut R) -> Self {
+        Self::with_config(inner, TlvConfig::default())
+    }
+
+    /// Creates a new top-level `TlvReader` with the given layout and caps.
+    pub const fn with_config(inner: &'a mut R, config: TlvConfig) -> Self {
+        Self {
+            body: TlvBody {
+                body: FrameBody {
+                    inner,
+                    remaining: 0,
+                },
+                config,
+                depth: 0,
+            },
+        }
+    }
+
+    /// Returns the layout and caps of the elements.
+    pub fn config(&self) -> &TlvConfig {
+        &self.body.config
+    }
+
+    /// Returns the nesting depth of this reader, `0` at the top level.
+    pub fn depth(&self) -> usize {
+        self.body.depth
+    }
+}
+
+impl<'b, 't, 'a, R> TlvReader<'b, RefTake<'t, TlvBody<'a, R>>> {
+    /// Creates a `TlvReader` over the value of an element, one level deeper
+    /// than the element's reader and with the same layout.
+    ///
+    /// Fails with `ErrorKind::InvalidData` if that would exceed the maximum depth.
+    pub fn nested(value: &'b mut RefTake<'t, TlvBody<'a, R>>) -> io::Result<Self> {
+        let config = value.inner.config;
+        let depth = value.inner.depth + 1;
+        if depth > config.max_depth {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "TLV elements nested deeper than {} levels",
+                    config.max_depth
+                ),
+            ));
+        }
+        Ok(Self {
+            body: TlvBody {
+                body: FrameBody {
+                    inner: value,
+                    remaining: 0,
+                },
+                config,
+                depth,
+            },
+        })
+    }
+}
+
+impl<'a, R: Read> TlvReader<'a, R> {
+    /// Skips the rest of the current value, if any, then reads the next tag and length.
+    ///
+    /// Returns `Ok(None)` when the stream ends cleanly between elements. A
+    /// stream that ends inside a header or a skipped value fails with
+    /// `ErrorKind::UnexpectedEof`, and a value longer than the maximum with
+    /// a [`LimitExceeded`] error.
+    pub fn next_element(&mut self) -> io::Result<Option<(u64, RefTake<'_, TlvBody<'a, R>>)>> {
+        let body = &mut self.body.body;
+        if !discard_by_reading(body.inner, &mut body.remaining)? {
+            return Err(io::Error::new(
+                ErrorKind::UnexpectedEof,
+                "stream ended inside a TLV value",
+            ));
+        }
+
+        let TlvConfig {
+            tag,
+            length,
+            endian,
+            max_element_len,
+            ..
+        } = self.body.config;
+        let Some(first) = read_first_byte(body.inner)? else {
+            return Ok(None);
+        };
+        let (tag, _) = decode_int(body.inner, first, tag, endian)?;
+        let first = read_first_byte(body.inner)?
+            .ok_or_else(|| header_eof(ErrorKind::UnexpectedEof.into()))?;
+        let (len, _) = decode_int(body.inner, first, length, endian)?;
+        if len > max_element_len {
+            return Err(LimitExceeded::new(max_element_len).into());
+        }
+
+        body.remaining = len;
+        Ok(Some((tag, RefTake::wrap(&mut self.body, len))))
+    }
+}
+
+/// Extension trait to provide a `tlv_ref` method on all `Read` types.
+pub trait TlvReaderExt {
+    /// Wraps the reader in a top-level `TlvReader` with the given layout and caps.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Cursor, Read};
+    /// use reftake::{TlvConfig, TlvReader, TlvReaderExt};
+    ///
+    /// // Tag 1 holds "hi", tag 2 holds a nested element with tag 3
+    /// let mut cursor = Cursor::new(b"\x01\x00\x02hi\x02\x00\x04\x03\x00\x01!");
+    /// let mut tlv = cursor.tlv_ref(TlvConfig::default());
+    ///
+    /// let (tag, mut value) = tlv.next_element().unwrap().unwrap();
+    /// let mut text = String::new();
+    /// value.read_to_string(&mut text).unwrap();
+    /// assert_eq!((tag, text.as_str()), (1, "hi"));
+    ///
+    /// let (tag, mut value) = tlv.next_element().unwrap().unwrap();
+    /// assert_eq!(tag, 2);
+    /// let mut nested = TlvReader::nested(&mut value).unwrap();
+    /// let (tag, value) = nested.next_element().unwrap().unwrap();
+    /// assert_eq!((tag, value.current_limit()), (3, 1));
+    /// ```
+    fn tlv_ref(&mut self, config: TlvConfig) -> TlvReader<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: Read> TlvReaderExt for T {
+    fn tlv_ref(&mut self, config: TlvConfig) -> TlvReader<'_, Self> {
+        TlvReader::with_config(self, config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_elements_and_skipping() {
+        let mut reader = Cursor::new(b"\x01\x00\x03abc\x02\x00\x00\x03\x00\x01z");
+        let mut tlv = TlvReader::wrap(&mut reader);
+
+        let (tag, value) = tlv.next_element().unwrap().unwrap();
+        assert_eq!((tag, value.current_limit()), (1, 3));
+        let (tag, value) = tlv.next_element().unwrap().unwrap();
+        assert_eq!((tag, value.current_limit()), (2, 0));
+        let (tag, mut value) = tlv.next_element().unwrap().unwrap();
+        let mut out = Vec::new();
+        value.read_to_end(&mut out).unwrap();
+        assert_eq!((tag, out.as_slice()), (3, &b"z"[..]));
+        assert!(tlv.next_element().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_varint_tag_and_little_endian_length() {
+        let mut reader = Cursor::new(b"\xac\x02\x02\x00ok");
+        let config = TlvConfig {
+            tag: LengthEncoding::Varint,
+            endian: Endian::Little,
+            ..TlvConfig::default()
+        };
+        let mut tlv = reader.tlv_ref(config);
+        let (tag, value) = tlv.next_element().unwrap().unwrap();
+        assert_eq!((tag, value.current_limit()), (300, 2));
+    }
+
+    #[test]
+    fn test_caps_and_truncation() {
+        let config = TlvConfig {
+            max_element_len: 3,
+            max_depth: 1,
+            ..TlvConfig::default()
+        };
+        let mut reader = Cursor::new(b"\x01\x00\x04abcd");
+        let err = reader.tlv_ref(config).next_element().err().unwrap();
+        assert_eq!(LimitExceeded::from_io(&err), Some(&LimitExceeded::new(3)));
+
+        let mut reader = Cursor::new(b"\x01\x00\x03\x05\x00\x00");
+        let mut tlv = reader.tlv_ref(config);
+        let (_, mut value) = tlv.next_element().unwrap().unwrap();
+        let mut nested = TlvReader::nested(&mut value).unwrap();
+        assert_eq!(nested.depth(), 1);
+        let (tag, mut inner) = nested.next_element().unwrap().unwrap();
+        assert_eq!(tag, 5);
+        let err = TlvReader::nested(&mut inner).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        let mut reader = Cursor::new(b"\x01\x00");
+        let err = reader.tlv_ref(config).next_element().err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+        let mut reader = Cursor::new(b"\x01");
+        let err = reader.tlv_ref(config).next_element().err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_nested_element_cannot_overrun_parent() {
+        let mut reader = Cursor::new(b"\x01\x00\x04\x02\x00\x05xyzzy");
+        let mut tlv = TlvReader::wrap(&mut reader);
+        let (_, mut value) = tlv.next_element().unwrap().unwrap();
+        let mut nested = TlvReader::nested(&mut value).unwrap();
+
+        let (_, mut inner) = nested.next_element().unwrap().unwrap();
+        let mut out = Vec::new();
+        inner.read_to_end(&mut out).unwrap();
+        assert_eq!(out, b"x");
+        let err = nested.next_element().err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    }
+}
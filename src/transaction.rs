@@ -0,0 +1,92 @@
+//! Speculative reads from a [`RefTake`] that are undone when they fail.
+
+use std::io::{self, Seek, SeekFrom};
+
+use crate::RefTake;
+
+impl<R: Seek> RefTake<'_, R> {
+    /// Runs `f` on the window, rewinding the inner reader and restoring the limit if it fails.
+    ///
+    /// When `f` returns `Ok`, whatever it read stays consumed. When it
+    /// returns `Err`, the inner reader is seeked back to where it was and
+    /// the limit reset, so "try format A, else format B" leaves no bytes
+    /// eaten by the failed attempt. Transactions nest.
+    ///
+    /// If the position can't be recorded, `f` is not run and the error is
+    /// returned. If the rewind itself fails, its error is returned instead
+    /// of the one from `f`, since the window is then left part-way.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{self, Cursor, ErrorKind, Read};
+    /// use reftake::RefTakeExt;
+    ///
+    /// fn magic(take: &mut impl Read, expected: &[u8]) -> io::Result<()> {
+    ///     let mut buf = vec![0; expected.len()];
+    ///     take.read_exact(&mut buf)?;
+    ///     if buf != expected {
+    ///         return Err(io::Error::new(ErrorKind::InvalidData, "wrong magic"));
+    ///     }
+    ///     Ok(())
+    /// }
+    ///
+    /// let mut file = Cursor::new(b"GIF89a...");
+    /// let mut take = file.take_ref(9);
+    /// assert!(take.transaction(|t| magic(t, b"\x89PNG")).is_err());
+    /// assert_eq!(take.current_limit(), 9);
+    /// take.transaction(|t| magic(t, b"GIF89a")).unwrap();
+    /// assert_eq!(take.current_limit(), 3);
+    /// ```
+    pub fn transaction<T, E, F>(&mut self, f: F) -> Result<T, E>
+    where
+        E: From<io::Error>,
+        F: FnOnce(&mut Self) -> Result<T, E>,
+    {
+        let position = self.inner.stream_position()?;
+        let limit = self.limit;
+        match f(self) {
+            Ok(value) => Ok(value),
+            Err(e) => {
+                self.inner.seek(SeekFrom::Start(position))?;
+                self.limit = limit;
+                Err(e)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::RefTakeExt;
+    use std::io::{self, BufRead, BufReader, Cursor, ErrorKind, Read};
+
+    #[test]
+    fn test_rollback_and_commit_nest() {
+        let mut reader = BufReader::with_capacity(4, Cursor::new(b"0123456789"));
+        let mut take = reader.take_ref(8);
+        let result: io::Result<()> = take.transaction(|outer| {
+            let mut buf = [0u8; 2];
+            outer.read_exact(&mut buf)?;
+            outer
+                .transaction(|inner| {
+                    inner.read_exact(&mut buf)?;
+                    Ok::<_, io::Error>(())
+                })
+                .unwrap();
+            assert_eq!(outer.current_limit(), 4);
+            let _ = outer.transaction(|inner| {
+                inner.read_exact(&mut buf)?;
+                Err::<(), _>(io::Error::from(ErrorKind::InvalidData))
+            });
+            assert_eq!(outer.current_limit(), 4);
+            assert_eq!(outer.fill_buf()?, b"4567");
+            Err(io::Error::from(ErrorKind::InvalidData))
+        });
+        assert!(result.is_err());
+        assert_eq!(take.current_limit(), 8);
+        let mut rest = String::new();
+        take.read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, "01234567");
+    }
+}
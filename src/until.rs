@@ -0,0 +1,456 @@
+//! An adapter that reads from a borrowed `BufRead` up to a delimiter byte.
+
+use std::io::{self, BufRead, ErrorKind, Read};
+
+use crate::RefTake;
+
+/// A non-owning adapter that returns EOF once a delimiter byte is reached.
+///
+/// The delimiter itself is never returned to the caller. Depending on how the
+/// adapter was created, it is either consumed from the inner reader when it is
+/// reached, or left in place as the next byte the parent reader will see.
+pub struct RefTakeUntil<'a, R> {
+    inner: &'a mut R,
+    delim: u8,
+    consume_delim: bool,
+    found: bool,
+}
+
+impl<'a, R> RefTakeUntil<'a, R> {
+    /// Creates a new `RefTakeUntil` that stops at `delim`.
+    ///
+    /// If `consume_delim` is `true`, the delimiter is removed from the inner
+    /// reader when it is reached.
+    pub const fn wrap(inner: &'a mut R, delim: u8, consume_delim: bool) -> Self {
+        Self {
+            inner,
+            delim,
+            consume_delim,
+            found: false,
+        }
+    }
+
+    /// Returns `true` once the delimiter has been reached, as opposed to EOF
+    /// of the inner reader.
+    pub fn found_delimiter(&self) -> bool {
+        self.found
+    }
+}
+
+impl<R: BufRead> Read for RefTakeUntil<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        let available = self.fill_buf()?;
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for RefTakeUntil<'_, R> {
+    fn fill_buf(&mut self) -> Result<&[u8], io::Error> {
+        if self.found {
+            return Ok(&[]);
+        }
+
+        let pos = self.inner.fill_buf()?.iter().position(|&b| b == self.delim);
+        if pos == Some(0) {
+            self.found = true;
+            if self.consume_delim {
+                self.inner.consume(1);
+            }
+            return Ok(&[]);
+        }
+
+        let buf = self.inner.fill_buf()?;
+        Ok(&buf[..pos.unwrap_or(buf.len())])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        if self.found {
+            return;
+        }
+        // Never let the caller consume the delimiter or anything past it
+        let amt = match self.inner.fill_buf() {
+            Ok(buf) => {
+                let end = buf
+                    .iter()
+                    .position(|&b| b == self.delim)
+                    .unwrap_or(buf.len());
+                amt.min(end)
+            }
+            Err(_) => 0,
+        };
+        self.inner.consume(amt);
+    }
+}
+
+/// Reads bytes up to a `sentinel` byte, reading at most `max_len` bytes before it.
+///
+/// Returns the bytes without the sentinel and leaves `reader` positioned just
+/// past it. Fails with `ErrorKind::UnexpectedEof` if the reader ends before the
+/// sentinel, or with `ErrorKind::InvalidData` if no sentinel occurs within
+/// `max_len` bytes; in both cases the bytes examined so far have been consumed.
+///
+/// # Example
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// let mut cursor = Cursor::new(b"hello\0world\0");
+/// let name = reftake::read_terminated(&mut cursor, b'\0', 16).unwrap();
+/// assert_eq!(name, b"hello");
+/// assert_eq!(cursor.position(), 6);
+/// ```
+pub fn read_terminated<R: BufRead + ?Sized>(
+    reader: &mut R,
+    sentinel: u8,
+    max_len: usize,
+) -> io::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    loop {
+        let buf = match reader.fill_buf() {
+            Ok(buf) => buf,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        if buf.is_empty() {
+            return Err(io::Error::new(
+                ErrorKind::UnexpectedEof,
+                "stream ended before the terminator",
+            ));
+        }
+
+        let allowed = max_len - out.len();
+        let search = &buf[..buf.len().min(allowed + 1)];
+        if let Some(pos) = search.iter().position(|&b| b == sentinel) {
+            out.extend_from_slice(&buf[..pos]);
+            reader.consume(pos + 1);
+            return Ok(out);
+        }
+        if buf.len() > allowed {
+            reader.consume(allowed);
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("terminator not found within {max_len} bytes"),
+            ));
+        }
+
+        let n = buf.len();
+        out.extend_from_slice(buf);
+        reader.consume(n);
+    }
+}
+
+/// How a [`read_until_limited`] or [`skip_until`] call ended, with the number of bytes appended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum UntilStatus {
+    /// The delimiter was found; it is included in the appended bytes.
+    Found(usize),
+    /// The reader (or its window) ended before the delimiter.
+    Eof(usize),
+    /// The accumulation cap was reached before the delimiter.
+    Capped(usize),
+}
+
+impl UntilStatus {
+    /// Returns the number of bytes appended to the output, or skipped.
+    pub fn bytes_read(&self) -> usize {
+        match *self {
+            UntilStatus::Found(n) | UntilStatus::Eof(n) | UntilStatus::Capped(n) => n,
+        }
+    }
+
+    /// Returns `true` if the delimiter was found.
+    pub fn found(&self) -> bool {
+        matches!(self, UntilStatus::Found(_))
+    }
+}
+
+/// Like `BufRead::read_until`, but appends at most `max_accumulate` bytes to `out`.
+///
+/// The delimiter, if found, is appended and consumed as with `read_until`.
+/// When the cap is reached first, the reader is left at the first byte that
+/// did not fit. Unlike `read_until`, the result tells a found delimiter apart
+/// from the end of the reader, which for a [`RefTake`] is the end of its window.
+///
+/// # Example
+///
+/// ```
+/// use std::io::Cursor;
+/// use reftake::{RefTakeExt, UntilStatus};
+///
+/// let mut cursor = Cursor::new(b"key=value;next");
+/// let mut take = cursor.take_ref(7);
+/// let mut out = Vec::new();
+/// let status = reftake::read_until_limited(&mut take, b';', &mut out, 64).unwrap();
+/// assert_eq!(status, UntilStatus::Eof(7));
+/// assert_eq!(out, b"key=val");
+/// ```
+pub fn read_until_limited<R: BufRead + ?Sized>(
+    reader: &mut R,
+    delim: u8,
+    out: &mut Vec<u8>,
+    max_accumulate: usize,
+) -> io::Result<UntilStatus> {
+    let mut read = 0;
+    loop {
+        if read == max_accumulate {
+            return Ok(UntilStatus::Capped(read));
+        }
+        let buf = match reader.fill_buf() {
+            Ok(buf) => buf,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        if buf.is_empty() {
+            return Ok(UntilStatus::Eof(read));
+        }
+
+        let search = &buf[..buf.len().min(max_accumulate - read)];
+        let (n, found) = match search.iter().position(|&b| b == delim) {
+            Some(pos) => (pos + 1, true),
+            None => (search.len(), false),
+        };
+        out.extend_from_slice(&search[..n]);
+        reader.consume(n);
+        read += n;
+        if found {
+            return Ok(UntilStatus::Found(read));
+        }
+    }
+}
+
+/// Discards bytes up to and including the next `delim`, like `BufRead::skip_until`.
+///
+/// Returns [`UntilStatus::Found`] with the number of bytes skipped, including
+/// the delimiter, or [`UntilStatus::Eof`] if the reader (or a [`RefTake`]
+/// window) ended first. Only `fill_buf`/`consume` are used, so nothing is copied.
+///
+/// # Example
+///
+/// ```
+/// use std::io::{BufRead, Cursor};
+/// use reftake::UntilStatus;
+///
+/// let mut cursor = Cursor::new(b"# comment\ndata");
+/// assert_eq!(reftake::skip_until(&mut cursor, b'\n').unwrap(), UntilStatus::Found(10));
+/// assert_eq!(cursor.fill_buf().unwrap(), b"data");
+/// ```
+pub fn skip_until<R: BufRead + ?Sized>(reader: &mut R, delim: u8) -> io::Result<UntilStatus> {
+    let mut skipped = 0;
+    loop {
+        let buf = match reader.fill_buf() {
+            Ok(buf) => buf,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        if buf.is_empty() {
+            return Ok(UntilStatus::Eof(skipped));
+        }
+        let (n, found) = match buf.iter().position(|&b| b == delim) {
+            Some(pos) => (pos + 1, true),
+            None => (buf.len(), false),
+        };
+        reader.consume(n);
+        skipped += n;
+        if found {
+            return Ok(UntilStatus::Found(skipped));
+        }
+    }
+}
+
+impl<R: BufRead> RefTake<'_, R> {
+    /// Reads bytes up to `sentinel` within the window, reading at most `max_len`
+    /// bytes before it. See [`read_terminated`] for details.
+    ///
+    /// Running into the end of the window before the sentinel is reported as
+    /// `ErrorKind::UnexpectedEof`.
+    pub fn read_terminated(&mut self, sentinel: u8, max_len: usize) -> io::Result<Vec<u8>> {
+        read_terminated(self, sentinel, max_len)
+    }
+
+    /// Reads up to and including `delim` into `out`, appending at most
+    /// `max_accumulate` bytes. See [`read_until_limited`] for details.
+    pub fn read_until_limited(
+        &mut self,
+        delim: u8,
+        out: &mut Vec<u8>,
+        max_accumulate: usize,
+    ) -> io::Result<UntilStatus> {
+        read_until_limited(self, delim, out, max_accumulate)
+    }
+
+    /// Discards bytes up to and including `delim` within the window.
+    /// See [`skip_until`] for details.
+    pub fn skip_until(&mut self, delim: u8) -> io::Result<UntilStatus> {
+        skip_until(self, delim)
+    }
+}
+
+/// Extension trait to provide a `take_until_ref` method on all `BufRead` types.
+pub trait RefTakeUntilExt {
+    /// Wraps the reader in a `RefTakeUntil` that stops at, and consumes, `delim`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Cursor, Read};
+    /// use reftake::RefTakeUntilExt;
+    ///
+    /// let mut cursor = Cursor::new(b"name\0value");
+    /// let mut name = String::new();
+    /// cursor.take_until_ref(b'\0').read_to_string(&mut name).unwrap();
+    /// assert_eq!(name, "name");
+    ///
+    /// let mut rest = String::new();
+    /// cursor.read_to_string(&mut rest).unwrap();
+    /// assert_eq!(rest, "value");
+    /// ```
+    fn take_until_ref(&mut self, delim: u8) -> RefTakeUntil<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: BufRead> RefTakeUntilExt for T {
+    fn take_until_ref(&mut self, delim: u8) -> RefTakeUntil<'_, Self> {
+        RefTakeUntil::wrap(self, delim, true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RefTakeExt;
+    use std::io::{BufReader, Cursor};
+
+    #[test]
+    fn test_stops_at_delimiter_across_buffers() {
+        let mut reader = BufReader::with_capacity(2, Cursor::new(b"hello;world"));
+        {
+            let mut until = reader.take_until_ref(b';');
+            let mut buf = String::new();
+            until.read_to_string(&mut buf).unwrap();
+            assert_eq!(buf, "hello");
+            assert!(until.found_delimiter());
+        }
+
+        let mut rest = String::new();
+        reader.read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, "world");
+    }
+
+    #[test]
+    fn test_keep_delimiter() {
+        let mut reader = Cursor::new(b"key=value");
+        {
+            let mut until = RefTakeUntil::wrap(&mut reader, b'=', false);
+            let mut buf = String::new();
+            until.read_to_string(&mut buf).unwrap();
+            assert_eq!(buf, "key");
+        }
+        assert_eq!(reader.position(), 3);
+    }
+
+    #[test]
+    fn test_eof_without_delimiter() {
+        let mut reader = Cursor::new(b"no delimiter");
+        let mut until = reader.take_until_ref(b'\0');
+
+        let mut buf = String::new();
+        until.read_to_string(&mut buf).unwrap();
+        assert_eq!(buf, "no delimiter");
+        assert!(!until.found_delimiter());
+    }
+
+    #[test]
+    fn test_read_terminated() {
+        let mut reader = BufReader::with_capacity(2, Cursor::new(b"abc\0def\0"));
+        assert_eq!(read_terminated(&mut reader, 0, 3).unwrap(), b"abc");
+        assert_eq!(read_terminated(&mut reader, 0, 3).unwrap(), b"def");
+        let err = read_terminated(&mut reader, 0, 3).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_read_terminated_too_long() {
+        let mut reader = Cursor::new(b"abcdef\0");
+        let err = read_terminated(&mut reader, 0, 4).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_read_terminated_within_take() {
+        let mut reader = Cursor::new(b"abc\0def");
+        let mut take = reader.take_ref(3);
+        let err = take.read_terminated(0, 10).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_overlarge_consume_is_clamped() {
+        let mut reader = Cursor::new(b"ab|cd");
+        let mut until = RefTakeUntil::wrap(&mut reader, b'|', false);
+
+        assert_eq!(until.fill_buf().unwrap(), b"ab");
+        until.consume(10);
+        assert_eq!(until.fill_buf().unwrap(), b"");
+        assert_eq!(reader.position(), 2);
+    }
+
+    #[test]
+    fn test_read_until_limited_found() {
+        let mut reader = BufReader::with_capacity(2, Cursor::new(b"abc;def"));
+        let mut out = Vec::new();
+        let status = read_until_limited(&mut reader, b';', &mut out, 10).unwrap();
+        assert_eq!(status, UntilStatus::Found(4));
+        assert!(status.found());
+        assert_eq!(out, b"abc;");
+    }
+
+    #[test]
+    fn test_read_until_limited_capped() {
+        let mut reader = Cursor::new(b"abcdef;");
+        let mut take = reader.take_ref(10);
+        let mut out = Vec::new();
+        let status = take.read_until_limited(b';', &mut out, 4).unwrap();
+        assert_eq!(status, UntilStatus::Capped(4));
+        assert_eq!(status.bytes_read(), 4);
+        assert_eq!(out, b"abcd");
+        assert_eq!(take.current_limit(), 6);
+    }
+
+    #[test]
+    fn test_read_until_limited_window_end() {
+        let mut reader = Cursor::new(b"abc;");
+        let mut take = reader.take_ref(2);
+        let mut out = Vec::new();
+        let status = take.read_until_limited(b';', &mut out, 10).unwrap();
+        assert_eq!(status, UntilStatus::Eof(2));
+        assert!(!status.found());
+    }
+
+    #[test]
+    fn test_skip_until_found() {
+        let mut reader = BufReader::with_capacity(2, Cursor::new(b"skip me;keep"));
+        assert_eq!(
+            skip_until(&mut reader, b';').unwrap(),
+            UntilStatus::Found(8)
+        );
+
+        let mut rest = String::new();
+        reader.read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, "keep");
+    }
+
+    #[test]
+    fn test_skip_until_respects_window() {
+        let mut reader = Cursor::new(b"abcdef;gh");
+        {
+            let mut take = reader.take_ref(4);
+            assert_eq!(take.skip_until(b';').unwrap(), UntilStatus::Eof(4));
+        }
+        assert_eq!(reader.position(), 4);
+    }
+}
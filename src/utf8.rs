@@ -0,0 +1,398 @@
+//! A pass-through adapter that rejects invalid UTF-8 in a borrowed reader.
+
+use std::{
+    error::Error,
+    fmt,
+    io::{self, ErrorKind, Read},
+};
+
+use crate::{InvalidUtf8, error::payload};
+
+/// Which control characters a [`Utf8Validator`] lets through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ControlChars {
+    /// Let every control character through.
+    #[default]
+    Allow,
+    /// Let only tab, line feed and carriage return through.
+    AllowWhitespace,
+    /// Reject every control character.
+    Reject,
+}
+
+impl ControlChars {
+    fn allows(self, ch: char) -> bool {
+        match self {
+            ControlChars::Allow => true,
+            ControlChars::AllowWhitespace => !ch.is_control() || matches!(ch, '\t' | '\n' | '\r'),
+            ControlChars::Reject => !ch.is_control(),
+        }
+    }
+}
+
+/// Error payload reported when a [`Utf8Validator`] meets a control character it does not allow.
+///
+/// Returned wrapped in an `io::Error` of kind `ErrorKind::InvalidData`;
+/// use [`ControlChar::from_io`] to tell it apart from other data errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ControlChar {
+    ch: char,
+    offset: u64,
+}
+
+impl ControlChar {
+    /// Returns the rejected character.
+    pub fn char(&self) -> char {
+        self.ch
+    }
+
+    /// Returns the stream offset of the rejected character.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Returns the `ControlChar` payload of an I/O error, if it has one.
+    pub fn from_io(err: &io::Error) -> Option<&ControlChar> {
+        payload(err)
+    }
+}
+
+impl fmt::Display for ControlChar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "control character U+{:04X} at offset {}",
+            self.ch as u32, self.offset
+        )
+    }
+}
+
+impl Error for ControlChar {}
+
+impl From<ControlChar> for io::Error {
+    fn from(err: ControlChar) -> Self {
+        io::Error::new(ErrorKind::InvalidData, err)
+    }
+}
+
+/// The reason a [`Utf8Validator`] stopped.
+#[derive(Debug, Clone, Copy)]
+enum Failure {
+    Utf8(InvalidUtf8),
+    Control(ControlChar),
+}
+
+impl From<Failure> for io::Error {
+    fn from(failure: Failure) -> Self {
+        match failure {
+            Failure::Utf8(e) => e.into(),
+            Failure::Control(e) => e.into(),
+        }
+    }
+}
+
+/// A non-owning adapter passing through only valid UTF-8, and only the control characters allowed.
+///
+/// Bytes are returned as they are read, but never beyond an invalid
+/// sequence: the bytes before it are returned first, and the next read
+/// fails with an [`InvalidUtf8`] error holding the offending bytes and their
+/// offset, so no garbage ever reaches the caller. A sequence split across
+/// two reads of the inner reader is held back until it is complete; one
+/// cut off by EOF is invalid too. A rejected control character fails the
+/// same way with a [`ControlChar`] error. Once a read has failed, every
+/// later read fails with the same error.
+///
+/// Every chunk returned is valid UTF-8 on its own as long as the buffer
+/// holds at least 4 bytes; smaller buffers get the characters byte by byte.
+pub struct Utf8Validator<'a, R> {
+    inner: &'a mut R,
+    controls: ControlChars,
+    carry: [u8; 4],
+    carry_len: usize,
+    /// Leading bytes of the carry already validated, left over from a small buffer.
+    ready: usize,
+    offset: u64,
+    failure: Option<Failure>,
+}
+
+impl<'a, R> Utf8Validator<'a, R> {
+    /// Creates a new `Utf8Validator` over the given reader reference.
+    pub const fn wrap(inner: &'a mut R, controls: ControlChars) -> Self {
+        Self {
+            inner,
+            controls,
+            carry: [0; 4],
+            carry_len: 0,
+            ready: 0,
+            offset: 0,
+            failure: None,
+        }
+    }
+
+    /// Returns the number of validated bytes returned so far.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Returns how many leading bytes of `data` are valid, and the failure right after them.
+    ///
+    /// Without a failure, the bytes past the valid ones are an incomplete sequence.
+    fn scan(&self, data: &[u8], eof: bool) -> (usize, Option<Failure>) {
+        let (valid, mut failure) = match std::str::from_utf8(data) {
+            Ok(_) => (data.len(), None),
+            Err(e) => {
+                let valid = e.valid_up_to();
+                let bad = match e.error_len() {
+                    Some(len) => Some(&data[valid..valid + len]),
+                    None if eof => Some(&data[valid..]),
+                    None => None,
+                };
+                let offset = self.offset + valid as u64;
+                let failure =
+                    bad.map(|bad| Failure::Utf8(InvalidUtf8::new(bad).with_offset(offset)));
+                (valid, failure)
+            }
+        };
+        let text = std::str::from_utf8(&data[..valid]).expect("prefix is valid UTF-8");
+        if let Some((i, ch)) = text
+            .char_indices()
+            .find(|&(_, ch)| !self.controls.allows(ch))
+        {
+            let offset = self.offset + i as u64;
+            failure = Some(Failure::Control(ControlChar { ch, offset }));
+            return (i, failure);
+        }
+        (valid, failure)
+    }
+}
+
+impl<R: Read> Read for Utf8Validator<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        if let Some(failure) = self.failure {
+            return Err(failure.into());
+        }
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.ready > 0 {
+            let n = self.ready.min(buf.len());
+            buf[..n].copy_from_slice(&self.carry[..n]);
+            self.carry.copy_within(n..self.carry_len, 0);
+            self.carry_len -= n;
+            self.ready -= n;
+            self.offset += n as u64;
+            return Ok(n);
+        }
+        loop {
+            let carried = self.carry_len;
+            if buf.len() >= self.carry.len() {
+                buf[..carried].copy_from_slice(&self.carry[..carried]);
+                let n = self.inner.read(&mut buf[carried..])?;
+                let len = carried + n;
+                let (valid, failure) = self.scan(&buf[..len], n == 0);
+                self.carry_len = 0;
+                if failure.is_none() {
+                    self.carry_len = len - valid;
+                    self.carry[..len - valid].copy_from_slice(&buf[valid..len]);
+                }
+                self.failure = failure;
+                if valid > 0 || n == 0 || failure.is_some() {
+                    self.offset += valid as u64;
+                    return match (valid, failure) {
+                        (0, Some(failure)) => Err(failure.into()),
+                        _ => Ok(valid),
+                    };
+                }
+            } else {
+                // Too small a buffer for a whole character: stage the bytes in the carry
+                let n = match carried {
+                    4 => 0,
+                    _ => self.inner.read(&mut self.carry[carried..])?,
+                };
+                let len = carried + n;
+                self.carry_len = len;
+                let (valid, failure) = self.scan(&self.carry[..len], n == 0 && carried < 4);
+                let ret = valid.min(buf.len());
+                buf[..ret].copy_from_slice(&self.carry[..ret]);
+                self.carry.copy_within(ret..len, 0);
+                self.carry_len = len - ret;
+                self.ready = valid - ret;
+                self.offset += ret as u64;
+                if ret == valid {
+                    self.failure = failure;
+                }
+                if ret > 0 || len == 0 {
+                    return Ok(ret);
+                }
+                if let Some(failure) = self.failure {
+                    return Err(failure.into());
+                }
+            }
+        }
+    }
+}
+
+/// Extension trait to provide a `validate_utf8_ref` method on all `Read` types.
+pub trait Utf8ValidatorExt {
+    /// Wraps the reader in a `Utf8Validator` letting through the given control characters.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Cursor, Read};
+    /// use reftake::{ControlChars, InvalidUtf8, RefTakeExt, Utf8ValidatorExt};
+    ///
+    /// let mut conn = Cursor::new(b"NICK caf\xc3\xa9\r\nJOIN #\xff\r\n".to_vec());
+    /// let mut line = conn.take_ref(64);
+    /// let mut text = line.validate_utf8_ref(ControlChars::AllowWhitespace);
+    ///
+    /// let mut buf = Vec::new();
+    /// let err = text.read_to_end(&mut buf).unwrap_err();
+    /// assert_eq!(buf, "NICK café\r\nJOIN #".as_bytes());
+    /// assert_eq!(InvalidUtf8::from_io(&err).unwrap().offset(), Some(18));
+    /// ```
+    fn validate_utf8_ref(&mut self, controls: ControlChars) -> Utf8Validator<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: Read> Utf8ValidatorExt for T {
+    fn validate_utf8_ref(&mut self, controls: ControlChars) -> Utf8Validator<'_, Self> {
+        Utf8Validator::wrap(self, controls)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// Returns at most `step` bytes per read, cycling `step` through 1 to 5.
+    struct Trickle<'a> {
+        data: &'a [u8],
+        step: usize,
+    }
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.step = self.step % 5 + 1;
+            let n = self.step.min(buf.len()).min(self.data.len());
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_sequences_split_across_reads() {
+        let text = "zß水🦀".repeat(50);
+        for step in 0..5 {
+            for buf_len in [1, 3, 4, 7, 64] {
+                let mut reader = Trickle {
+                    data: text.as_bytes(),
+                    step,
+                };
+                let mut validator = reader.validate_utf8_ref(ControlChars::Reject);
+                let mut out = Vec::new();
+                let mut buf = vec![0u8; buf_len];
+                loop {
+                    match validator.read(&mut buf).unwrap() {
+                        0 => break,
+                        n => out.extend_from_slice(&buf[..n]),
+                    }
+                }
+                assert_eq!(out, text.as_bytes());
+            }
+        }
+    }
+
+    #[test]
+    fn test_tiny_buffers_and_truncated_input() {
+        let mut reader = Cursor::new("é🦀".as_bytes()[..5].to_vec());
+        let mut validator = reader.validate_utf8_ref(ControlChars::Allow);
+        let mut byte = [0u8; 1];
+        let mut out = Vec::new();
+        let err = loop {
+            match validator.read(&mut byte) {
+                Ok(n) => out.extend_from_slice(&byte[..n]),
+                Err(e) => break e,
+            }
+        };
+        assert_eq!(out, "é".as_bytes());
+        let invalid = InvalidUtf8::from_io(&err).unwrap();
+        assert_eq!(
+            (invalid.bytes(), invalid.offset()),
+            (&b"\xf0\x9f\xa6"[..], Some(2))
+        );
+        assert!(validator.read(&mut byte).is_err());
+    }
+
+    #[test]
+    fn test_invalid_byte_starting_an_inner_read() {
+        for (data, step) in [
+            (&b"\x80"[..], 0),
+            (b"\x80\n\xc3\xa9", 4),
+            (b"\xc3\xa9\xff", 1),
+        ] {
+            let mut reader = Trickle { data, step };
+            let mut validator = reader.validate_utf8_ref(ControlChars::Allow);
+            let mut buf = [0u8; 5];
+            let mut out = Vec::new();
+            let err = loop {
+                match validator.read(&mut buf) {
+                    Ok(0) => panic!("{data:?} read to the end"),
+                    Ok(n) => out.extend_from_slice(&buf[..n]),
+                    Err(e) => break e,
+                }
+            };
+            let valid = std::str::from_utf8(data).unwrap_err().valid_up_to();
+            assert_eq!(out, &data[..valid]);
+            assert_eq!(
+                InvalidUtf8::from_io(&err).unwrap().offset(),
+                Some(valid as u64)
+            );
+        }
+    }
+
+    #[test]
+    fn test_output_is_the_valid_prefix() {
+        // Every string of up to 5 bytes of a small alphabet, with every inner read pattern and buffer size
+        let alphabet = [b'a', b'\n', 0x80, 0xa9, 0xc3, 0xff];
+        for len in 0..=5u32 {
+            for index in 0..6usize.pow(len) {
+                let data: Vec<u8> = (0..len)
+                    .map(|i| alphabet[index / 6usize.pow(i) % 6])
+                    .collect();
+                let valid = std::str::from_utf8(&data).map_or_else(|e| e.valid_up_to(), str::len);
+                for step in 0..5 {
+                    for buf_len in [1, 2, 3, 4, 5, 8] {
+                        let mut reader = Trickle { data: &data, step };
+                        let mut validator = reader.validate_utf8_ref(ControlChars::Allow);
+                        let mut buf = vec![0u8; buf_len];
+                        let mut out = Vec::new();
+                        let failed = loop {
+                            match validator.read(&mut buf) {
+                                Ok(0) => break false,
+                                Ok(n) => out.extend_from_slice(&buf[..n]),
+                                Err(_) => break true,
+                            }
+                        };
+                        assert_eq!(out, &data[..valid], "{data:?} {step} {buf_len}");
+                        assert_eq!(failed, valid < data.len(), "{data:?} {step} {buf_len}");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_control_characters() {
+        let mut reader = Cursor::new(b"a\tb\x07c");
+        let mut validator = reader.validate_utf8_ref(ControlChars::AllowWhitespace);
+        let mut buf = [0u8; 16];
+        assert_eq!(validator.read(&mut buf).unwrap(), 3);
+        let err = validator.read(&mut buf).unwrap_err();
+        assert_eq!(err.to_string(), "control character U+0007 at offset 3");
+        assert_eq!(ControlChar::from_io(&err).unwrap().char(), '\u{7}');
+    }
+}
@@ -0,0 +1,104 @@
+//! LEB128 variable-length integer readers.
+
+use std::io::{self, ErrorKind, Read};
+
+/// The longest valid encoding of a `u64`: 64 bits in groups of 7.
+const MAX_VARINT_LEN: usize = 10;
+
+/// Extension trait for reading LEB128 varints from any `Read`, including a
+/// [`RefTake`](crate::RefTake) window.
+///
+/// Bytes are read one at a time, so no more than the varint itself is taken
+/// from the reader. At most 10 bytes are read: a longer encoding, or one
+/// whose value doesn't fit in 64 bits, yields `ErrorKind::InvalidData`
+/// instead of looping on continuation bits. A reader or window that ends in
+/// the middle of a varint yields `ErrorKind::UnexpectedEof`.
+///
+/// # Example
+///
+/// ```
+/// use std::io::Cursor;
+/// use reftake::{ReadVarint, RefTakeExt};
+///
+/// let mut cursor = Cursor::new([0xac, 0x02, 0x03, 0xff]);
+/// let mut take = cursor.take_ref(3);
+/// assert_eq!(take.read_uvarint().unwrap(), 300);
+/// assert_eq!(take.read_ivarint().unwrap(), -2);
+/// ```
+pub trait ReadVarint: Read {
+    /// Reads an unsigned LEB128 varint, as used by protobuf, WASM and git.
+    fn read_uvarint(&mut self) -> io::Result<u64> {
+        let mut value = 0u64;
+        for i in 0..MAX_VARINT_LEN {
+            let mut byte = 0u8;
+            self.read_exact(std::slice::from_mut(&mut byte))?;
+            let shift = 7 * i as u32;
+            // The 10th byte only has room for the top bit of a u64
+            if i == MAX_VARINT_LEN - 1 && byte > 1 {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    "varint overflows u64",
+                ));
+            }
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(io::Error::new(
+            ErrorKind::InvalidData,
+            "varint overflows u64",
+        ))
+    }
+
+    /// Reads a zigzag-encoded signed varint, as used by protobuf `sint64` and Go's `binary.Varint`.
+    fn read_ivarint(&mut self) -> io::Result<i64> {
+        let raw = self.read_uvarint()?;
+        Ok((raw >> 1) as i64 ^ -((raw & 1) as i64))
+    }
+}
+
+impl<R: Read + ?Sized> ReadVarint for R {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RefTakeExt;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_uvarint_values() {
+        let mut reader = Cursor::new([
+            0x00, 0x7f, 0x80, 0x01, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01,
+        ]);
+        assert_eq!(reader.read_uvarint().unwrap(), 0);
+        assert_eq!(reader.read_uvarint().unwrap(), 127);
+        assert_eq!(reader.read_uvarint().unwrap(), 128);
+        assert_eq!(reader.read_uvarint().unwrap(), u64::MAX);
+    }
+
+    #[test]
+    fn test_ivarint_zigzag() {
+        let mut reader = Cursor::new([0x00, 0x01, 0x02, 0x03]);
+        assert_eq!(reader.read_ivarint().unwrap(), 0);
+        assert_eq!(reader.read_ivarint().unwrap(), -1);
+        assert_eq!(reader.read_ivarint().unwrap(), 1);
+        assert_eq!(reader.read_ivarint().unwrap(), -2);
+    }
+
+    #[test]
+    fn test_endless_continuation_is_rejected() {
+        let mut reader = Cursor::new([0x80u8; 64]);
+        let err = reader.read_uvarint().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(reader.position(), MAX_VARINT_LEN as u64);
+    }
+
+    #[test]
+    fn test_window_ends_mid_varint() {
+        let mut reader = Cursor::new([0x80, 0x80, 0x01]);
+        let mut take = reader.take_ref(2);
+        let err = take.read_uvarint().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,171 @@
+//! A combined skip + limit view over a borrowed reader.
+
+use std::{
+    cmp,
+    io::{self, BufRead, Read, Seek},
+};
+
+use crate::skip::{discard_buffered, discard_by_reading, discard_by_seeking};
+
+/// A non-owning adapter exposing the byte range `skip..skip + len` of a reader.
+///
+/// The first `skip` bytes are discarded lazily on the first read, after which
+/// at most `len` bytes are passed through, exactly like
+/// [`RefTake`](crate::RefTake).
+pub struct RefWindow<'a, R> {
+    inner: &'a mut R,
+    skip: u64,
+    limit: u64,
+}
+
+impl<'a, R> RefWindow<'a, R> {
+    /// Creates a new `RefWindow` that skips `skip` bytes of the given reader
+    /// reference, then reads at most `len` bytes.
+    pub const fn wrap(inner: &'a mut R, skip: u64, len: u64) -> Self {
+        Self {
+            inner,
+            skip,
+            limit: len,
+        }
+    }
+
+    /// Sets a new byte limit for the window, not counting any pending skip.
+    pub fn set_limit(&mut self, limit: u64) {
+        self.limit = limit;
+    }
+
+    /// Returns the current limit that is allowed to read.
+    pub fn current_limit(&self) -> u64 {
+        self.limit
+    }
+
+    /// Returns the number of leading bytes that still have to be discarded.
+    pub fn remaining_skip(&self) -> u64 {
+        self.skip
+    }
+}
+
+impl<R: Seek> RefWindow<'_, R> {
+    /// Performs the pending skip immediately by seeking forward.
+    pub fn seek_past(&mut self) -> io::Result<()> {
+        discard_by_seeking(self.inner, &mut self.skip)
+    }
+}
+
+impl<R: Read> Read for RefWindow<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        if self.skip > 0 && !discard_by_reading(self.inner, &mut self.skip)? {
+            return Ok(0);
+        }
+        // Don't call into inner reader at all at EOF because it may still block
+        if self.limit == 0 {
+            return Ok(0);
+        }
+
+        let max = cmp::min(buf.len() as u64, self.limit) as usize;
+        let n = self.inner.read(&mut buf[..max])?;
+        assert!(n as u64 <= self.limit, "number of read bytes exceeds limit");
+        self.limit -= n as u64;
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for RefWindow<'_, R> {
+    fn fill_buf(&mut self) -> Result<&[u8], io::Error> {
+        if self.skip > 0 && !discard_buffered(self.inner, &mut self.skip)? {
+            return Ok(&[]);
+        }
+        if self.limit == 0 {
+            return Ok(&[]);
+        }
+
+        let buf = self.inner.fill_buf()?;
+        let cap = cmp::min(buf.len() as u64, self.limit) as usize;
+        Ok(&buf[..cap])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        let amt = cmp::min(amt as u64, self.limit) as usize;
+        self.limit -= amt as u64;
+        self.inner.consume(amt);
+    }
+}
+
+/// Extension trait to provide a `take_window` method on all `Read` types.
+pub trait RefWindowExt {
+    /// Wraps the reader in a `RefWindow` over the bytes `skip..skip + len`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Cursor, Read};
+    /// use reftake::RefWindowExt;
+    ///
+    /// let mut cursor = Cursor::new(b"....entry....");
+    /// let mut buf = String::new();
+    /// cursor.take_window(4, 5).read_to_string(&mut buf).unwrap();
+    /// assert_eq!(buf, "entry");
+    /// ```
+    fn take_window(&mut self, skip: u64, len: u64) -> RefWindow<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: Read> RefWindowExt for T {
+    fn take_window(&mut self, skip: u64, len: u64) -> RefWindow<'_, Self> {
+        RefWindow::wrap(self, skip, len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufReader, Cursor};
+
+    #[test]
+    fn test_window_read() {
+        let mut reader = Cursor::new(b"0123456789");
+        let mut window = reader.take_window(2, 5);
+
+        let mut buf = [0u8; 10];
+        let n = window.read(&mut buf).unwrap();
+        assert_eq!(&buf[..n], b"23456");
+        assert_eq!(window.current_limit(), 0);
+        assert_eq!(window.read(&mut buf).unwrap(), 0);
+        assert_eq!(reader.position(), 7);
+    }
+
+    #[test]
+    fn test_window_bufread() {
+        let mut reader = BufReader::with_capacity(3, Cursor::new(b"xxxxab\ncd\nef"));
+        let mut window = reader.take_window(4, 6);
+
+        let lines: Vec<String> = window.by_ref().lines().map(Result::unwrap).collect();
+        assert_eq!(lines, vec!["ab", "cd"]);
+        assert_eq!(window.remaining_skip(), 0);
+        assert_eq!(window.current_limit(), 0);
+    }
+
+    #[test]
+    fn test_window_seek_past() {
+        let mut reader = Cursor::new(b"0123456789");
+        let mut window = reader.take_window(8, 5);
+        window.seek_past().unwrap();
+
+        let mut buf = Vec::new();
+        window.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, b"89");
+        assert_eq!(window.current_limit(), 3);
+    }
+
+    #[test]
+    fn test_window_skip_beyond_eof() {
+        let mut reader = Cursor::new(b"abc");
+        let mut window = reader.take_window(5, 2);
+
+        let mut buf = [0u8; 2];
+        assert_eq!(window.read(&mut buf).unwrap(), 0);
+        assert_eq!(window.remaining_skip(), 2);
+        assert_eq!(window.current_limit(), 2);
+    }
+}
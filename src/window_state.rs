@@ -0,0 +1,160 @@
+//! Checkpoints of a window's progress, to resume it after a restart.
+
+use std::io::{self, ErrorKind};
+
+use crate::FrameState;
+
+/// Where a window over a seekable or positioned source stands, detached from the source.
+///
+/// A long job reading a large window can save this, for instance with
+/// `serde` under the `serde` feature, and after a restart re-attach it to
+/// the reopened source with [`SeekTake::resume`](crate::SeekTake::resume),
+/// [`RefTakeAt::resume`](crate::RefTakeAt::resume) or
+/// [`SharedTakeAt::resume`](crate::SharedTakeAt::resume), carrying on from
+/// the first byte not consumed. A [`FrameReader`](crate::FrameReader) over
+/// the window records its own progress in `frames`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WindowState {
+    /// Absolute offset of the start of the window in the source.
+    pub origin: u64,
+    /// Length of the window.
+    pub len: u64,
+    /// Bytes of the window consumed so far.
+    pub consumed: u64,
+    /// Progress of the frame reader reading the window, if any.
+    pub frames: Option<FrameState>,
+}
+
+impl WindowState {
+    /// Creates the state of a window of `len` bytes at `origin` with nothing consumed.
+    pub const fn new(origin: u64, len: u64) -> Self {
+        Self {
+            origin,
+            len,
+            consumed: 0,
+            frames: None,
+        }
+    }
+
+    /// Returns the absolute offset in the source to resume reading at.
+    pub fn position(&self) -> u64 {
+        self.origin.saturating_add(self.consumed)
+    }
+
+    /// Returns the number of bytes of the window left to read.
+    pub fn remaining(&self) -> u64 {
+        self.len.saturating_sub(self.consumed)
+    }
+
+    /// Fails with `ErrorKind::InvalidData` if the state can't be resumed.
+    pub(crate) fn check(&self) -> io::Result<()> {
+        if self.consumed > self.len || self.origin.checked_add(self.len).is_none() {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "invalid window state: {} of {} bytes at {} consumed",
+                    self.consumed, self.len, self.origin
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FrameReaderExt, RefTakeAt, RefTakeAtExt, SeekTake, SeekTakeExt};
+    use std::io::{Cursor, Read};
+
+    #[test]
+    fn test_resume_in_the_middle_of_a_frame() {
+        let mut data = b"junk".to_vec();
+        for body in [&b"first"[..], b"second"] {
+            data.extend_from_slice(&(body.len() as u32).to_be_bytes());
+            data.extend_from_slice(body);
+        }
+        data.extend_from_slice(b"junk");
+
+        let mut file = Cursor::new(data.clone());
+        file.set_position(4);
+        let mut window = file.seek_take_ref(19);
+        let mut frames = window.frames_ref(64);
+        frames
+            .next_frame()
+            .unwrap()
+            .unwrap()
+            .read_to_end(&mut Vec::new())
+            .unwrap();
+        let mut part = [0u8; 3];
+        frames
+            .next_frame()
+            .unwrap()
+            .unwrap()
+            .read_exact(&mut part)
+            .unwrap();
+        let mut saved = frames.get_mut().state().unwrap();
+        saved.frames = Some(frames.state());
+        assert_eq!((saved.origin, saved.consumed), (4, 16));
+
+        let mut reopened = Cursor::new(data);
+        let mut window = SeekTake::resume(&mut reopened, &saved).unwrap();
+        let mut frames = window
+            .frames_ref(64)
+            .with_state(saved.frames.clone().unwrap());
+        let mut rest = String::new();
+        frames
+            .current_frame()
+            .unwrap()
+            .read_to_string(&mut rest)
+            .unwrap();
+        assert_eq!(rest, "ond");
+        assert!(frames.next_frame().unwrap().is_none());
+        assert_eq!(frames.frame_count(), 2);
+    }
+
+    #[test]
+    fn test_positioned_windows() {
+        let data: &[u8] = b"0123456789";
+        let mut window = data.take_ref_at(2, 6);
+        window.read_exact(&mut [0u8; 4]).unwrap();
+        let saved = window.state(2);
+        assert_eq!(
+            saved,
+            WindowState {
+                consumed: 4,
+                ..WindowState::new(2, 6)
+            }
+        );
+
+        let mut rest = String::new();
+        RefTakeAt::resume(data, &saved)
+            .unwrap()
+            .read_to_string(&mut rest)
+            .unwrap();
+        assert_eq!(rest, "67");
+
+        let invalid = WindowState {
+            consumed: 7,
+            ..saved
+        };
+        let err = RefTakeAt::resume(data, &invalid).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    #[cfg(all(feature = "serde", feature = "json"))]
+    #[test]
+    fn test_serde_round_trip() {
+        let state = WindowState {
+            frames: Some(FrameState {
+                frame_count: 3,
+                partial: vec![0, 1],
+                ..FrameState::default()
+            }),
+            ..WindowState::new(37 << 20, 2 << 30)
+        };
+        let json = serde_json::to_string(&state).unwrap();
+        assert_eq!(serde_json::from_str::<WindowState>(&json).unwrap(), state);
+    }
+}
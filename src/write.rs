@@ -0,0 +1,286 @@
+//! A non-owning limited writer, the write-side counterpart of [`RefTake`](crate::RefTake).
+
+use std::{
+    cmp,
+    io::{self, IoSlice, Write},
+};
+
+use crate::LimitExceeded;
+
+/// What a limited writer does with a write that does not fit in the remaining quota.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum OverflowBehavior {
+    /// Shorten the write to the remaining quota; once it is exhausted, writes
+    /// return `Ok(0)` and `write_all` fails with `ErrorKind::WriteZero`.
+    #[default]
+    Truncate,
+    /// Reject the whole write with a [`LimitExceeded`] error, forwarding nothing.
+    Error,
+    /// Forward what fits and silently discard the rest, reporting the whole
+    /// write as successful. Discarded bytes are counted in `truncated_bytes()`.
+    Saturate,
+}
+
+/// A non-owning adapter that wraps a mutable reference to a writer,
+/// limiting the number of bytes that can be written to it.
+///
+/// By default, writes larger than the remaining quota are shortened to fit,
+/// and once the quota is exhausted, writes return `Ok(0)`, which `write_all`
+/// reports as `ErrorKind::WriteZero`. See [`OverflowBehavior`] for the
+/// alternatives. The inner writer remains usable after wrapping.
+pub struct RefTakeWrite<'a, W> {
+    pub(crate) inner: &'a mut W,
+    pub(crate) limit: u64,
+    written: u64,
+    overflow: OverflowBehavior,
+    truncated: u64,
+}
+
+impl<'a, W> RefTakeWrite<'a, W> {
+    /// Creates a new `RefTakeWrite` that accepts at most `limit` bytes for the given writer reference.
+    pub const fn wrap(inner: &'a mut W, limit: u64) -> Self {
+        Self::with_overflow(inner, limit, OverflowBehavior::Truncate)
+    }
+
+    /// Creates a new `RefTakeWrite` with the given overflow behavior.
+    pub const fn with_overflow(inner: &'a mut W, limit: u64, overflow: OverflowBehavior) -> Self {
+        Self {
+            inner,
+            limit,
+            written: 0,
+            overflow,
+            truncated: 0,
+        }
+    }
+
+    /// Sets a new byte limit for the writer.
+    pub fn set_limit(&mut self, limit: u64) {
+        self.limit = limit;
+    }
+
+    /// Returns the current limit that is allowed to write.
+    pub fn current_limit(&self) -> u64 {
+        self.limit
+    }
+
+    /// Returns the configured overflow behavior.
+    pub fn overflow(&self) -> OverflowBehavior {
+        self.overflow
+    }
+
+    /// Changes the overflow behavior for subsequent writes.
+    pub fn set_overflow(&mut self, overflow: OverflowBehavior) {
+        self.overflow = overflow;
+    }
+
+    /// Returns the number of bytes silently discarded by [`OverflowBehavior::Saturate`].
+    pub fn truncated_bytes(&self) -> u64 {
+        self.truncated
+    }
+
+    /// Returns `true` if any bytes have been discarded.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated > 0
+    }
+}
+
+impl<W> RefTakeWrite<'_, W> {
+    /// Applies the overflow behavior to a write of `len` bytes before the
+    /// inner writer is called. Returns the result when it shouldn't be called at all.
+    pub(crate) fn check_overflow(&mut self, len: usize) -> Option<io::Result<usize>> {
+        if len == 0 {
+            return Some(Ok(0));
+        }
+        match self.overflow {
+            OverflowBehavior::Error if len as u64 > self.limit => {
+                Some(Err(LimitExceeded::new(self.written + self.limit).into()))
+            }
+            OverflowBehavior::Saturate if self.limit == 0 => {
+                self.truncated += len as u64;
+                Some(Ok(len))
+            }
+            // Don't call into inner writer at all when the quota is used up
+            _ if self.limit == 0 => Some(Ok(0)),
+            _ => None,
+        }
+    }
+
+    /// Records `n` bytes written by the inner writer out of `max` offered,
+    /// for a write of `len` bytes, and returns the count to report.
+    pub(crate) fn account(&mut self, n: usize, max: usize, len: usize) -> usize {
+        assert!(n <= max, "number of written bytes exceeds limit");
+        self.limit -= n as u64;
+        self.written += n as u64;
+
+        if self.overflow == OverflowBehavior::Saturate && n == max && max < len {
+            // Everything that fit was accepted, the remainder can't ever be written
+            self.truncated += (len - max) as u64;
+            return len;
+        }
+        n
+    }
+}
+
+/// Implements the `Write` trait with a byte limit.
+///
+/// Vectored writes are trimmed to the remaining quota and passed on to the
+/// inner writer's `write_vectored`, so gathered writes stay gathered.
+impl<T: Write> Write for RefTakeWrite<'_, T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if let Some(result) = self.check_overflow(buf.len()) {
+            return result;
+        }
+
+        let max = cmp::min(buf.len() as u64, self.limit) as usize;
+        let n = self.inner.write(&buf[..max])?;
+        Ok(self.account(n, max, buf.len()))
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        let len = bufs
+            .iter()
+            .fold(0usize, |acc, b| acc.saturating_add(b.len()));
+        if let Some(result) = self.check_overflow(len) {
+            return result;
+        }
+
+        let max = cmp::min(len as u64, self.limit) as usize;
+        let n = if max == len {
+            self.inner.write_vectored(bufs)?
+        } else {
+            let mut left = max;
+            let mut trimmed = Vec::with_capacity(bufs.len());
+            for buf in bufs {
+                if left == 0 {
+                    break;
+                }
+                let take = buf.len().min(left);
+                trimmed.push(IoSlice::new(&buf[..take]));
+                left -= take;
+            }
+            self.inner.write_vectored(&trimmed)?
+        };
+        Ok(self.account(n, max, len))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Extension trait to provide a `take_write_ref` method on all `Write` types.
+pub trait RefTakeWriteExt {
+    /// Wraps the writer in a `RefTakeWrite` accepting at most `limit` bytes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::Write;
+    /// use reftake::RefTakeWriteExt;
+    ///
+    /// let mut out = Vec::new();
+    /// let mut limited = out.take_write_ref(5);
+    /// assert_eq!(limited.write(b"hello world").unwrap(), 5);
+    /// assert_eq!(out, b"hello");
+    /// ```
+    fn take_write_ref(&mut self, limit: u64) -> RefTakeWrite<'_, Self>
+    where
+        Self: Sized;
+}
+
+impl<T: Write> RefTakeWriteExt for T {
+    fn take_write_ref(&mut self, limit: u64) -> RefTakeWrite<'_, Self> {
+        RefTakeWrite::wrap(self, limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::ErrorKind;
+
+    #[test]
+    fn test_write_respects_limit() {
+        let mut out = Vec::new();
+        let mut limited = out.take_write_ref(4);
+        assert_eq!(limited.overflow(), OverflowBehavior::Truncate);
+
+        assert_eq!(limited.write(b"ab").unwrap(), 2);
+        assert_eq!(limited.write(b"cdef").unwrap(), 2);
+        assert_eq!(limited.write(b"gh").unwrap(), 0);
+        assert_eq!(limited.current_limit(), 0);
+        assert_eq!(out, b"abcd");
+    }
+
+    #[test]
+    fn test_write_all_over_limit_fails() {
+        let mut out = Vec::new();
+        let mut limited = out.take_write_ref(3);
+
+        let err = limited.write_all(b"abcdef").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::WriteZero);
+        assert_eq!(out, b"abc");
+    }
+
+    #[test]
+    fn test_error_overflow_rejects_whole_write() {
+        let mut out = Vec::new();
+        let mut limited = RefTakeWrite::with_overflow(&mut out, 5, OverflowBehavior::Error);
+
+        limited.write_all(b"abc").unwrap();
+        let err = limited.write(b"def").unwrap_err();
+        assert_eq!(LimitExceeded::from_io(&err), Some(&LimitExceeded::new(5)));
+        limited.write_all(b"de").unwrap();
+        assert_eq!(out, b"abcde");
+    }
+
+    #[test]
+    fn test_saturate_overflow_discards_and_reports() {
+        let mut out = Vec::new();
+        let mut limited = RefTakeWrite::with_overflow(&mut out, 4, OverflowBehavior::Saturate);
+
+        limited.write_all(b"abcdef").unwrap();
+        limited.write_all(b"gh").unwrap();
+        assert_eq!(limited.truncated_bytes(), 4);
+        assert!(limited.is_truncated());
+        assert_eq!(out, b"abcd");
+    }
+
+    #[test]
+    fn test_write_vectored_trims_to_quota() {
+        let mut out = Vec::new();
+        let mut limited = out.take_write_ref(5);
+
+        let bufs = [
+            IoSlice::new(b"abc"),
+            IoSlice::new(b"def"),
+            IoSlice::new(b"gh"),
+        ];
+        assert_eq!(limited.write_vectored(&bufs).unwrap(), 5);
+        assert_eq!(limited.write_vectored(&bufs).unwrap(), 0);
+        assert_eq!(out, b"abcde");
+    }
+
+    #[test]
+    fn test_write_vectored_saturate() {
+        let mut out = Vec::new();
+        let mut limited = RefTakeWrite::with_overflow(&mut out, 4, OverflowBehavior::Saturate);
+
+        let bufs = [IoSlice::new(b"abc"), IoSlice::new(b"def")];
+        assert_eq!(limited.write_vectored(&bufs).unwrap(), 6);
+        assert_eq!(limited.truncated_bytes(), 2);
+        assert_eq!(out, b"abcd");
+    }
+
+    #[test]
+    fn test_set_limit() {
+        let mut out = Vec::new();
+        let mut limited = out.take_write_ref(1);
+        limited.write_all(b"a").unwrap();
+        limited.set_limit(2);
+        limited.write_all(b"bc").unwrap();
+        limited.flush().unwrap();
+        assert_eq!(out, b"abc");
+    }
+}